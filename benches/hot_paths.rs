@@ -0,0 +1,174 @@
+// Benchmarks for the paths latency-sensitive deployments sit on: decoding websocket frames
+// (one benchmark per channel, using the payloads in tests/corpus/ws), copying messages for each
+// subscriber, building order actions, hashing and signing them, and serializing the signed
+// request.
+//
+// Save a baseline on the main branch and compare a change against it:
+//
+//     cargo bench --features bench -- --save-baseline main
+//     cargo bench --features bench -- --baseline main
+//
+// ci.sh fails if any benchmark regresses against $BENCH_BASELINE when it's set.
+#![allow(clippy::result_large_err)]
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use ethers::signers::LocalWallet;
+use hyperliquid_rust_sdk::{
+    decode_ws_frame, l1_action_hash, order_action, sign_l1_action_hash, BookLevel, ClientLimit,
+    ClientOrder, ClientOrderRequest, L2Book, L2BookData, Message, SignedAction, Timestamp,
+};
+use std::{collections::HashMap, fs, path::Path, sync::Arc, time::Duration};
+use uuid::Uuid;
+
+// Batch sizes for the order benchmarks: a single order, and requoting both sides of a
+// 10-level ladder
+const BATCH_SIZES: [usize; 2] = [1, 20];
+
+fn ws_frames() -> Vec<(String, String)> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/corpus/ws");
+    let mut frames: Vec<_> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| {
+            let path = entry.unwrap().path();
+            let name = path.file_stem().unwrap().to_string_lossy().into_owned();
+            (name, fs::read_to_string(path).unwrap())
+        })
+        .collect();
+    frames.sort();
+    frames
+}
+
+fn orders(count: usize) -> Vec<ClientOrderRequest> {
+    (0..count)
+        .map(|level| ClientOrderRequest {
+            asset: "ETH".to_string(),
+            is_buy: level % 2 == 0,
+            reduce_only: false,
+            limit_px: 2000.0 + (level as f64 - count as f64 / 2.0) * 0.5,
+            sz: 0.25,
+            cloid: Some(Uuid::from_u128(level as u128)),
+            order_type: ClientOrder::Limit(ClientLimit {
+                tif: "Alo".to_string(),
+            }),
+        })
+        .collect()
+}
+
+fn coin_to_asset() -> HashMap<String, u32> {
+    HashMap::from([("BTC".to_string(), 0), ("ETH".to_string(), 1)])
+}
+
+fn wallet() -> LocalWallet {
+    "e908f86dbb4d55ac876378565aafeabc187f6690f046459397b17d9b9a19688e"
+        .parse()
+        .unwrap()
+}
+
+fn ws_decode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("ws_decode");
+    for (channel, frame) in ws_frames() {
+        group.throughput(Throughput::Bytes(frame.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(&channel), &frame, |b, frame| {
+            b.iter(|| decode_ws_frame(black_box(frame)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+// A book as deep as the exchange sends, 20 levels a side
+fn deep_book() -> Message {
+    let level = |px: f64| BookLevel {
+        px: px.to_string(),
+        sz: "12.5".to_string(),
+        n: 3,
+    };
+    Message::L2Book(L2Book {
+        data: Arc::new(L2BookData {
+            coin: "ETH".to_string(),
+            time: Timestamp::from_millis(1),
+            levels: vec![
+                (0..20).map(|i| level(2000.0 - i as f64 * 0.1)).collect(),
+                (0..20).map(|i| level(2000.1 + i as f64 * 0.1)).collect(),
+            ],
+        }),
+    })
+}
+
+// Each subscriber gets its own copy of a message. Payloads are shared, so a copy costs the same
+// whatever the channel; `l2Book_deep_payload` is what copying the payload itself would cost.
+fn fan_out(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fan_out");
+    let mut messages: Vec<_> = ws_frames()
+        .into_iter()
+        .filter_map(|(channel, frame)| Some((channel, decode_ws_frame(&frame).unwrap()?)))
+        .collect();
+    messages.push(("l2Book_deep".to_string(), deep_book()));
+    for (channel, message) in &messages {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(channel),
+            message,
+            |b, message| b.iter(|| black_box(message).clone()),
+        );
+    }
+    if let Message::L2Book(book) = deep_book() {
+        group.bench_function("l2Book_deep_payload", |b| {
+            b.iter(|| L2BookData::clone(black_box(&book.data)))
+        });
+    }
+    group.finish();
+}
+
+fn order_actions(c: &mut Criterion) {
+    let coin_to_asset = coin_to_asset();
+    let wallet = wallet();
+    let mut group = c.benchmark_group("order");
+    for size in BATCH_SIZES {
+        let batch = orders(size);
+        let action = order_action(batch.clone(), &coin_to_asset).unwrap();
+        let connection_id = l1_action_hash(&action, 1, None).unwrap();
+        let signed_action = SignedAction {
+            action: serde_json::to_value(&action).unwrap(),
+            signature: sign_l1_action_hash(&wallet, connection_id, true).unwrap(),
+            nonce: 1,
+            vault_address: None,
+            expires_after: None,
+        };
+
+        group.throughput(Throughput::Elements(size as u64));
+        group.bench_with_input(BenchmarkId::new("convert", size), &batch, |b, batch| {
+            b.iter(|| order_action(black_box(batch.clone()), &coin_to_asset).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("hash", size), &action, |b, action| {
+            b.iter(|| l1_action_hash(black_box(action), 1, None).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("serialize", size), &action, |b, action| {
+            b.iter(|| serde_json::to_string(black_box(action)).unwrap())
+        });
+        group.bench_with_input(
+            BenchmarkId::new("request_body", size),
+            &signed_action,
+            |b, signed_action| b.iter(|| black_box(signed_action).body().unwrap()),
+        );
+    }
+    group.finish();
+}
+
+fn signing(c: &mut Criterion) {
+    let wallet = wallet();
+    let action = order_action(orders(1), &coin_to_asset()).unwrap();
+    let connection_id = l1_action_hash(&action, 1, None).unwrap();
+    c.bench_function("sign_l1_action", |b| {
+        b.iter(|| sign_l1_action_hash(&wallet, black_box(connection_id), true).unwrap())
+    });
+}
+
+criterion_group! {
+    name = benches;
+    // A 5% noise threshold, up from criterion's 1%, keeps CI runners' jitter from being
+    // reported as a regression
+    config = Criterion::default()
+        .measurement_time(Duration::from_secs(3))
+        .noise_threshold(0.05);
+    targets = ws_decode, fan_out, order_actions, signing
+}
+criterion_main!(benches);
@@ -0,0 +1,12 @@
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    // The gRPC gateway's service and messages are generated from proto/gateway.proto, with a
+    // vendored protoc so building doesn't need one installed.
+    #[cfg(feature = "grpc")]
+    {
+        println!("cargo:rerun-if-changed=proto/gateway.proto");
+        let protoc = protoc_bin_vendored::protoc_bin_path().expect("vendored protoc");
+        std::env::set_var("PROTOC", protoc);
+        tonic_build::compile_protos("proto/gateway.proto").expect("compiling gateway.proto");
+    }
+}
@@ -1,6 +1,6 @@
 #!/bin/bash
 
-set -e
+set -eo pipefail
 
 # Build
 cargo build
@@ -14,4 +14,13 @@ cargo clippy -- -D warnings
 # Run tests
 cargo test
 
+# Compare benchmarks against a saved baseline, e.g. BENCH_BASELINE=main
+if [ -n "$BENCH_BASELINE" ]; then
+    cargo bench --features bench -- --baseline "$BENCH_BASELINE" | tee target/bench.log
+    if grep -q "Performance has regressed" target/bench.log; then
+        echo "Benchmarks regressed against $BENCH_BASELINE."
+        exit 1
+    fi
+fi
+
 echo "CI checks passed successfully."
\ No newline at end of file
@@ -0,0 +1,59 @@
+#ifndef HYPERLIQUID_H
+#define HYPERLIQUID_H
+
+/* C ABI for hyperliquid_rust_sdk, built with the `ffi` feature. */
+
+#include <stdbool.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct InfoClient InfoClient;
+typedef struct ExchangeClient ExchangeClient;
+
+typedef enum HlNetwork {
+    HL_MAINNET = 0,
+    HL_TESTNET = 1,
+    HL_LOCALHOST = 2,
+} HlNetwork;
+
+/* `tpsl` and `cloid` may be NULL. A NULL `tpsl` places a limit order using `tif`. */
+typedef struct HlOrder {
+    const char *asset;
+    bool is_buy;
+    bool reduce_only;
+    double limit_px;
+    double sz;
+    const char *tif;
+    double trigger_px;
+    bool is_market;
+    const char *tpsl;
+    const char *cloid;
+} HlOrder;
+
+/* `message` is a JSON string only valid for the duration of the call. */
+typedef void (*HlMessageCallback)(const char *message, void *user_data);
+
+const char *hl_last_error(void);
+void hl_string_free(char *s);
+
+InfoClient *hl_info_client_new(HlNetwork network);
+void hl_info_client_free(InfoClient *client);
+char *hl_info(const InfoClient *client, const char *request);
+int64_t hl_subscribe(InfoClient *client, const char *subscription, HlMessageCallback callback,
+                     void *user_data);
+int32_t hl_unsubscribe(InfoClient *client, uint32_t subscription_id);
+
+ExchangeClient *hl_exchange_client_new(const char *private_key, HlNetwork network,
+                                       const char *vault_address);
+void hl_exchange_client_free(ExchangeClient *client);
+char *hl_place_order(const ExchangeClient *client, const HlOrder *order);
+char *hl_cancel_order(const ExchangeClient *client, const char *asset, uint64_t oid);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
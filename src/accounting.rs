@@ -0,0 +1,550 @@
+use crate::{consts::EPSILON, prelude::*, Error, InfoClient, Timestamp, TradeInfo, UserFunding};
+use ethers::types::H160;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, VecDeque},
+    time::Duration,
+};
+
+// How far behind the newest fill a fill can arrive and still be deduplicated by tid. Older fills
+// are ignored, so the set of seen tids stays bounded.
+const DEDUP_WINDOW: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+// Which open lot a closing trade is matched against. Average cost keeps a single lot per
+// position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CostBasisMethod {
+    Fifo,
+    AverageCost,
+    Lifo,
+    // Highest-priced lot first, which realizes the smallest gain when closing longs
+    Hifo,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Lot {
+    // Signed: positive for long lots, negative for short lots
+    pub sz: f64,
+    pub px: f64,
+    pub time: Timestamp,
+}
+
+// A lot (or part of one) closed by a trade, with the gain it realized, for tax reporting.
+// Spot coins ("@107", "PURR/USDC") are flagged with `is_spot`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RealizedGain {
+    pub coin: String,
+    pub is_spot: bool,
+    pub is_long: bool,
+    // Unsigned size closed
+    pub sz: f64,
+    pub open_time: Timestamp,
+    pub open_px: f64,
+    pub close_time: Timestamp,
+    pub close_px: f64,
+    // The tid of the closing fill
+    pub close_tid: u64,
+    // Before fees
+    pub gain: f64,
+}
+
+impl RealizedGain {
+    pub fn holding_period(&self) -> Duration {
+        self.close_time.duration_since(self.open_time)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CoinPnl {
+    pub position: f64,
+    pub realized_pnl: f64,
+    pub fees: f64,
+    // Positive when funding was received, negative when paid
+    pub funding: f64,
+    pub mark_px: Option<f64>,
+    pub lots: VecDeque<Lot>,
+}
+
+impl CoinPnl {
+    pub fn avg_entry_px(&self) -> Option<f64> {
+        if self.position.abs() < EPSILON {
+            return None;
+        }
+        let cost: f64 = self.lots.iter().map(|lot| lot.sz * lot.px).sum();
+        Some(cost / self.position)
+    }
+
+    pub fn unrealized_pnl(&self) -> f64 {
+        match self.mark_px {
+            Some(mark_px) => self
+                .lots
+                .iter()
+                .map(|lot| (mark_px - lot.px) * lot.sz)
+                .sum(),
+            None => 0.0,
+        }
+    }
+
+    pub fn net_pnl(&self) -> f64 {
+        self.realized_pnl + self.unrealized_pnl() - self.fees + self.funding
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct AccountPnl {
+    pub realized_pnl: f64,
+    pub unrealized_pnl: f64,
+    pub fees: f64,
+    pub funding: f64,
+    pub net_pnl: f64,
+}
+
+// A position the engine tracks differently from the exchange.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PositionMismatch {
+    pub coin: String,
+    pub tracked: f64,
+    pub exchange: f64,
+}
+
+// Serializable, so long-running bots can persist it with `save_snapshot` and, after a restart,
+// restore it with `load_snapshot` and catch up with `reconcile`.
+#[derive(Serialize, Deserialize)]
+pub struct PnlEngine {
+    pub method: CostBasisMethod,
+    coins: HashMap<String, CoinPnl>,
+    // The time of each fill seen within `DEDUP_WINDOW` of the newest, by tid
+    seen_tids: HashMap<u64, Timestamp>,
+    #[serde(default)]
+    last_fill_time: Timestamp,
+    #[serde(default)]
+    realized_gains: Vec<RealizedGain>,
+    // Size of `seen_tids` that triggers the next pruning
+    #[serde(skip)]
+    prune_at: usize,
+}
+
+impl PnlEngine {
+    pub fn new(method: CostBasisMethod) -> PnlEngine {
+        PnlEngine {
+            method,
+            coins: HashMap::new(),
+            seen_tids: HashMap::new(),
+            last_fill_time: Timestamp::UNIX_EPOCH,
+            realized_gains: Vec::new(),
+            prune_at: 0,
+        }
+    }
+
+    pub fn coin(&self, coin: &str) -> Option<&CoinPnl> {
+        self.coins.get(coin)
+    }
+
+    pub fn coins(&self) -> &HashMap<String, CoinPnl> {
+        &self.coins
+    }
+
+    // Every lot closed so far, in the order closed.
+    pub fn realized_gains(&self) -> &[RealizedGain] {
+        &self.realized_gains
+    }
+
+    // Removes and returns the lots closed so far, e.g. once they've been exported.
+    pub fn take_realized_gains(&mut self) -> Vec<RealizedGain> {
+        std::mem::take(&mut self.realized_gains)
+    }
+
+    // Fills are deduplicated by tid so the engine can be fed overlapping REST and WS data. Fills
+    // more than a week older than the newest one seen are ignored, so backfill oldest first.
+    // Fees charged in another token, like spot buys' fees in the bought token, are converted to
+    // USD at the fill price.
+    pub fn on_fill(&mut self, fill: &TradeInfo) -> Result<()> {
+        self.apply_fill(fill).map(|_| ())
+    }
+
+    // Whether the fill was new.
+    fn apply_fill(&mut self, fill: &TradeInfo) -> Result<bool> {
+        if fill.time < self.dedup_horizon() || self.seen_tids.contains_key(&fill.tid) {
+            return Ok(false);
+        }
+        let px: f64 = fill.px.parse().map_err(|_| Error::FloatStringParse)?;
+        let sz: f64 = fill.sz.parse().map_err(|_| Error::FloatStringParse)?;
+        let fee: f64 = fill.fee.parse().map_err(|_| Error::FloatStringParse)?;
+        let fee = match fill.fee_token.as_deref() {
+            None | Some("USDC") => fee,
+            Some(_) => fee * px,
+        };
+        let signed_sz = if fill.side == "B" { sz } else { -sz };
+        self.seen_tids.insert(fill.tid, fill.time);
+        self.last_fill_time = self.last_fill_time.max(fill.time);
+        if self.seen_tids.len() > self.prune_at {
+            let horizon = self.dedup_horizon();
+            self.seen_tids.retain(|_, time| *time >= horizon);
+            self.prune_at = (2 * self.seen_tids.len()).max(1024);
+        }
+
+        let method = self.method;
+        let coin = self.coins.entry(fill.coin.clone()).or_default();
+        coin.fees += fee;
+        for closed in Self::apply_trade(coin, method, signed_sz, px, fill.time) {
+            self.realized_gains.push(RealizedGain {
+                coin: fill.coin.clone(),
+                is_spot: !is_perp(&fill.coin),
+                is_long: closed.sz > 0.0,
+                sz: closed.sz.abs(),
+                open_time: closed.time,
+                open_px: closed.px,
+                close_time: fill.time,
+                close_px: px,
+                close_tid: fill.tid,
+                gain: (px - closed.px) * closed.sz,
+            });
+        }
+        Ok(true)
+    }
+
+    fn dedup_horizon(&self) -> Timestamp {
+        self.last_fill_time.saturating_sub(DEDUP_WINDOW)
+    }
+
+    pub fn on_funding(&mut self, funding: &UserFunding) -> Result<()> {
+        let usdc: f64 = funding.usdc.parse().map_err(|_| Error::FloatStringParse)?;
+        self.coins.entry(funding.coin.clone()).or_default().funding += usdc;
+        Ok(())
+    }
+
+    pub fn on_mark_price(&mut self, coin: &str, mark_px: f64) {
+        if let Some(coin) = self.coins.get_mut(coin) {
+            coin.mark_px = Some(mark_px);
+        }
+    }
+
+    pub fn account(&self) -> AccountPnl {
+        self.coins
+            .values()
+            .fold(AccountPnl::default(), |mut account, coin| {
+                account.realized_pnl += coin.realized_pnl;
+                account.unrealized_pnl += coin.unrealized_pnl();
+                account.fees += coin.fees;
+                account.funding += coin.funding;
+                account.net_pnl += coin.net_pnl();
+                account
+            })
+    }
+
+    // Applies the fills `user` made since the last fill the engine saw, e.g. while a restored
+    // snapshot was offline, then returns the perp positions that still disagree with the
+    // exchange. Funding paid meanwhile isn't backfilled.
+    pub async fn reconcile(
+        &mut self,
+        info_client: &InfoClient,
+        user: H160,
+    ) -> Result<Vec<PositionMismatch>> {
+        // Fills sharing the last fill's millisecond may not all have been seen
+        let mut start_time = self.last_fill_time.saturating_sub(Duration::from_millis(1));
+        loop {
+            let fills = info_client
+                .user_fills_by_time(user, start_time, None)
+                .await?;
+            let mut new_fills = 0;
+            for fill in fills {
+                start_time = start_time.max(fill.time);
+                if self.apply_fill(&TradeInfo::from(fill))? {
+                    new_fills += 1;
+                }
+            }
+            // Responses are capped, so keep paging until one brings nothing new
+            if new_fills == 0 {
+                break;
+            }
+        }
+
+        let user_state = info_client.user_state(user).await?;
+        let mut exchange: HashMap<String, f64> = HashMap::new();
+        for asset_position in user_state.asset_positions {
+            let szi = asset_position
+                .position
+                .szi
+                .parse()
+                .map_err(|_| Error::FloatStringParse)?;
+            exchange.insert(asset_position.position.coin, szi);
+        }
+        let mismatches = position_mismatches(&self.coins, &exchange);
+        for mismatch in &mismatches {
+            warn!(
+                "Tracked {} position {} differs from exchange position {}",
+                mismatch.coin, mismatch.tracked, mismatch.exchange
+            );
+        }
+        Ok(mismatches)
+    }
+
+    // Returns the parts of lots the trade closed.
+    fn apply_trade(
+        coin: &mut CoinPnl,
+        method: CostBasisMethod,
+        signed_sz: f64,
+        px: f64,
+        time: Timestamp,
+    ) -> Vec<Lot> {
+        let mut closed_lots = Vec::new();
+        let mut remaining = signed_sz;
+        // Close against existing lots of the opposite sign first
+        while remaining.abs() > EPSILON {
+            let Some(index) = closing_lot(&coin.lots, method) else {
+                break;
+            };
+            let lot = &mut coin.lots[index];
+            if lot.sz.signum() == remaining.signum() {
+                break;
+            }
+            let closed = remaining.abs().min(lot.sz.abs()) * lot.sz.signum();
+            coin.realized_pnl += (px - lot.px) * closed;
+            closed_lots.push(Lot {
+                sz: closed,
+                px: lot.px,
+                time: lot.time,
+            });
+            lot.sz -= closed;
+            remaining += closed;
+            if lot.sz.abs() < EPSILON {
+                coin.lots.remove(index);
+            }
+        }
+
+        if remaining.abs() > EPSILON {
+            match (method, coin.lots.back_mut()) {
+                (CostBasisMethod::AverageCost, Some(lot)) => {
+                    let sz = lot.sz + remaining;
+                    lot.px = (lot.px * lot.sz + px * remaining) / sz;
+                    lot.sz = sz;
+                }
+                _ => coin.lots.push_back(Lot {
+                    sz: remaining,
+                    px,
+                    time,
+                }),
+            }
+        }
+        coin.position += signed_sz;
+        if coin.position.abs() < EPSILON {
+            coin.position = 0.0;
+        }
+        closed_lots
+    }
+}
+
+// Open lots all have the position's sign, since trades close lots before opening new ones.
+fn closing_lot(lots: &VecDeque<Lot>, method: CostBasisMethod) -> Option<usize> {
+    if lots.is_empty() {
+        return None;
+    }
+    match method {
+        CostBasisMethod::Fifo | CostBasisMethod::AverageCost => Some(0),
+        CostBasisMethod::Lifo => Some(lots.len() - 1),
+        CostBasisMethod::Hifo => lots
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.px.total_cmp(&b.px))
+            .map(|(index, _)| index),
+    }
+}
+
+fn is_perp(coin: &str) -> bool {
+    !coin.starts_with('@') && !coin.contains('/')
+}
+
+// Spot balances aren't positions, so spot coins ("@107", "PURR/USDC") are skipped.
+fn position_mismatches(
+    coins: &HashMap<String, CoinPnl>,
+    exchange: &HashMap<String, f64>,
+) -> Vec<PositionMismatch> {
+    let mut names: Vec<&String> = coins
+        .keys()
+        .filter(|coin| is_perp(coin))
+        .chain(exchange.keys())
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+        .into_iter()
+        .filter_map(|coin| {
+            let tracked = coins.get(coin).map_or(0.0, |pnl| pnl.position);
+            let exchange = exchange.get(coin).copied().unwrap_or_default();
+            ((tracked - exchange).abs() > EPSILON).then(|| PositionMismatch {
+                coin: coin.clone(),
+                tracked,
+                exchange,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fill(tid: u64, side: &str, px: &str, sz: &str) -> TradeInfo {
+        TradeInfo {
+            side: side.to_string(),
+            px: px.to_string(),
+            time: Timestamp::from_millis(tid),
+            dir: String::new(),
+            crossed: true,
+            fee: "0.5".to_string(),
+            ..TradeInfo::test_fill(1, tid, sz)
+        }
+    }
+
+    fn run(method: CostBasisMethod) -> Result<PnlEngine> {
+        let mut engine = PnlEngine::new(method);
+        engine.on_fill(&fill(1, "B", "100", "1"))?;
+        engine.on_fill(&fill(2, "B", "200", "1"))?;
+        engine.on_fill(&fill(3, "A", "300", "1"))?;
+        // Duplicate delivery is ignored
+        engine.on_fill(&fill(3, "A", "300", "1"))?;
+        engine.on_mark_price("ETH", 250.0);
+        engine.on_funding(&UserFunding {
+            time: Timestamp::from_millis(4),
+            coin: "ETH".to_string(),
+            usdc: "-1.5".to_string(),
+            szi: "1".to_string(),
+            funding_rate: "0.0001".to_string(),
+            extra: Default::default(),
+        })?;
+        Ok(engine)
+    }
+
+    #[test]
+    fn test_fifo() -> Result<()> {
+        let engine = run(CostBasisMethod::Fifo)?;
+        let eth = engine.coin("ETH").unwrap();
+        assert!((eth.realized_pnl - 200.0).abs() < EPSILON);
+        assert!((eth.unrealized_pnl() - 50.0).abs() < EPSILON);
+        assert!((eth.avg_entry_px().unwrap() - 200.0).abs() < EPSILON);
+
+        let account = engine.account();
+        assert!((account.fees - 1.5).abs() < EPSILON);
+        assert!((account.funding + 1.5).abs() < EPSILON);
+        assert!((account.net_pnl - (200.0 + 50.0 - 1.5 - 1.5)).abs() < EPSILON);
+        Ok(())
+    }
+
+    #[test]
+    fn test_dedup_window() -> Result<()> {
+        let mut engine = PnlEngine::new(CostBasisMethod::Fifo);
+        let day = 24 * 60 * 60 * 1_000;
+        for tid in 0..2_000 {
+            engine.on_fill(&TradeInfo {
+                time: Timestamp::from_millis(tid * day / 100),
+                ..fill(tid, "B", "100", "1")
+            })?;
+        }
+        // Only the last week's tids are kept
+        assert!(engine.seen_tids.len() <= 2 * 7 * 100);
+        let position = engine.coin("ETH").unwrap().position;
+        // A fill from before the window is ignored rather than counted again
+        engine.on_fill(&fill(0, "B", "100", "1"))?;
+        assert_eq!(engine.coin("ETH").unwrap().position, position);
+        Ok(())
+    }
+
+    #[test]
+    fn test_fee_token() -> Result<()> {
+        let mut engine = PnlEngine::new(CostBasisMethod::Fifo);
+        // A spot buy's fee is charged in the bought token
+        engine.on_fill(&TradeInfo {
+            coin: "@107".to_string(),
+            fee: "0.01".to_string(),
+            fee_token: Some("HYPE".to_string()),
+            ..fill(1, "B", "20", "10")
+        })?;
+        engine.on_fill(&TradeInfo {
+            fee_token: Some("USDC".to_string()),
+            ..fill(2, "B", "100", "1")
+        })?;
+        assert!((engine.coin("@107").unwrap().fees - 0.2).abs() < EPSILON);
+        assert!((engine.coin("ETH").unwrap().fees - 0.5).abs() < EPSILON);
+        Ok(())
+    }
+
+    #[test]
+    fn test_average_cost() -> Result<()> {
+        let engine = run(CostBasisMethod::AverageCost)?;
+        let eth = engine.coin("ETH").unwrap();
+        assert!((eth.realized_pnl - 150.0).abs() < EPSILON);
+        assert!((eth.unrealized_pnl() - 100.0).abs() < EPSILON);
+        assert!((eth.position - 1.0).abs() < EPSILON);
+        Ok(())
+    }
+
+    #[test]
+    fn test_flip_position() -> Result<()> {
+        let mut engine = PnlEngine::new(CostBasisMethod::Fifo);
+        engine.on_fill(&fill(1, "B", "100", "1"))?;
+        engine.on_fill(&fill(2, "A", "90", "3"))?;
+        let eth = engine.coin("ETH").unwrap();
+        assert!((eth.realized_pnl + 10.0).abs() < EPSILON);
+        assert!((eth.position + 2.0).abs() < EPSILON);
+        assert!((eth.avg_entry_px().unwrap() - 90.0).abs() < EPSILON);
+        Ok(())
+    }
+
+    #[test]
+    fn test_lot_methods() -> Result<()> {
+        let closed = |method| -> Result<Vec<(f64, f64)>> {
+            let mut engine = PnlEngine::new(method);
+            engine.on_fill(&fill(1, "B", "100", "1"))?;
+            engine.on_fill(&fill(2, "B", "300", "1"))?;
+            engine.on_fill(&fill(3, "B", "200", "1"))?;
+            engine.on_fill(&fill(4, "A", "250", "1.5"))?;
+            Ok(engine
+                .take_realized_gains()
+                .iter()
+                .map(|gain| (gain.open_px, gain.sz))
+                .collect())
+        };
+        assert_eq!(closed(CostBasisMethod::Fifo)?, [(100.0, 1.0), (300.0, 0.5)]);
+        assert_eq!(closed(CostBasisMethod::Lifo)?, [(200.0, 1.0), (300.0, 0.5)]);
+        assert_eq!(closed(CostBasisMethod::Hifo)?, [(300.0, 1.0), (200.0, 0.5)]);
+
+        let mut engine = PnlEngine::new(CostBasisMethod::Hifo);
+        engine.on_fill(&fill(1, "A", "100", "1"))?;
+        engine.on_fill(&fill(5, "B", "90", "1"))?;
+        let gain = &engine.realized_gains()[0];
+        assert!(!gain.is_long && !gain.is_spot);
+        assert!((gain.gain - 10.0).abs() < EPSILON);
+        assert_eq!(gain.holding_period(), Duration::from_millis(4));
+        assert_eq!(gain.close_tid, 5);
+        Ok(())
+    }
+
+    #[test]
+    fn test_snapshot_restore() -> Result<()> {
+        let engine = run(CostBasisMethod::Fifo)?;
+        let path = std::env::temp_dir().join(format!("hl-pnl-{}.json", uuid::Uuid::new_v4()));
+        crate::save_snapshot(&path, &engine)?;
+        let mut restored: PnlEngine = crate::load_snapshot(&path)?.unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(crate::load_snapshot::<PnlEngine>(&path)?.is_none());
+
+        assert_eq!(restored.account(), engine.account());
+        assert_eq!(restored.last_fill_time, Timestamp::from_millis(3));
+        // Fills seen before the snapshot are still deduplicated
+        restored.on_fill(&fill(3, "A", "300", "1"))?;
+        assert!((restored.coin("ETH").unwrap().position - 1.0).abs() < EPSILON);
+
+        let exchange = HashMap::from([("ETH".to_string(), 1.0), ("BTC".to_string(), -0.5)]);
+        let mismatches = position_mismatches(&restored.coins, &exchange);
+        assert_eq!(
+            mismatches,
+            [PositionMismatch {
+                coin: "BTC".to_string(),
+                tracked: 0.0,
+                exchange: -0.5
+            }]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,68 @@
+use crate::{prelude::*, Error, ExchangeClient, ExchangeResponseStatus};
+use ethers::signers::{LocalWallet, Signer};
+use log::{error, info};
+use std::{sync::Arc, time::Duration};
+use tokio::{sync::RwLock, task::JoinHandle, time};
+
+// Rotates the agent key a long-running bot signs with, without restarting it. Actions go
+// through a shared `RwLock<ExchangeClient>` read lock; a rotation takes the write lock, so it
+// waits for in-flight actions, approves a new agent with the master wallet (which replaces, and
+// so deregisters, the previous agent of the same name), swaps the client's wallet and
+// releases the lock. No action is ever signed by a revoked key: actions started during a
+// rotation wait one approval round trip instead.
+pub struct AgentRotator {
+    client: Arc<RwLock<ExchangeClient>>,
+    master: LocalWallet,
+    agent_name: Option<String>,
+}
+
+impl AgentRotator {
+    pub fn new(
+        client: Arc<RwLock<ExchangeClient>>,
+        master: LocalWallet,
+        agent_name: Option<String>,
+    ) -> AgentRotator {
+        AgentRotator {
+            client,
+            master,
+            agent_name,
+        }
+    }
+
+    // Returns the new agent's private key, e.g. to persist it for the next restart. On failure
+    // the client keeps signing with the current agent.
+    pub async fn rotate(&self) -> Result<String> {
+        let mut client = self.client.write().await;
+        let (key, response) = client
+            .approve_named_agent(self.agent_name.as_deref(), Some(&self.master))
+            .await?;
+        if let ExchangeResponseStatus::Err(err) = response {
+            return Err(Error::AgentApproval(err.message));
+        }
+        let agent: LocalWallet = key
+            .parse()
+            .map_err(|e| Error::PrivateKeyParse(format!("{e}")))?;
+        info!(
+            "Rotated agent {:?} from {:?} to {:?}",
+            self.agent_name,
+            client.wallet.address(),
+            agent.address()
+        );
+        client.wallet = agent;
+        Ok(key)
+    }
+
+    // Rotates every `interval` until the returned task is aborted. Failed rotations are logged
+    // and retried at the next interval.
+    pub fn spawn(self, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = time::interval_at(time::Instant::now() + interval, interval);
+            loop {
+                ticker.tick().await;
+                if let Err(err) = self.rotate().await {
+                    error!("Agent rotation failed: {err}");
+                }
+            }
+        })
+    }
+}
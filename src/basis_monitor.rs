@@ -0,0 +1,205 @@
+use crate::{prelude::*, Error, InfoClient, MidCache, SpotMeta};
+use log::warn;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+
+// A perp and the spot market of the same token, by their names in AllMids. Spot tokens are
+// sometimes named differently from the perp, e.g. UBTC for BTC.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BasisPair {
+    pub perp: String,
+    // "PURR/USDC" for canonical pairs, "@<index>" for the others
+    pub spot: String,
+}
+
+impl BasisPair {
+    // Pairs `perp` with the USDC spot market of `spot_token`.
+    pub fn resolve(spot_meta: &SpotMeta, perp: &str, spot_token: &str) -> Result<BasisPair> {
+        let token = spot_meta.token(spot_token).ok_or(Error::AssetNotFound)?;
+        let usdc = spot_meta.token("USDC").ok_or(Error::AssetNotFound)?;
+        let market = spot_meta
+            .universe
+            .iter()
+            .find(|market| market.tokens == [token.index, usdc.index])
+            .ok_or(Error::AssetNotFound)?;
+        Ok(BasisPair {
+            perp: perp.to_string(),
+            spot: market.name.clone(),
+        })
+    }
+
+    // As `resolve`, with spot meta from `info_client` and its cache, if enabled.
+    pub async fn fetch(
+        info_client: &InfoClient,
+        perp: &str,
+        spot_token: &str,
+    ) -> Result<BasisPair> {
+        BasisPair::resolve(&info_client.spot_meta().await?, perp, spot_token)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BasisUpdate {
+    pub pair: BasisPair,
+    pub perp_mid: f64,
+    pub spot_mid: f64,
+    // (perp mid - spot mid) / spot mid
+    pub basis_bps: f64,
+    // Thresholds crossed since the previous update
+    pub crossings: Vec<BasisCrossing>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BasisCrossing {
+    pub threshold_bps: f64,
+    // Whether the basis rose above the threshold, rather than fell below it
+    pub upward: bool,
+}
+
+// Detects the basis crossing any of `thresholds_bps`.
+struct ThresholdTracker {
+    thresholds_bps: Vec<f64>,
+    last: Option<f64>,
+}
+
+impl ThresholdTracker {
+    fn observe(&mut self, basis_bps: f64) -> Vec<BasisCrossing> {
+        let Some(last) = self.last.replace(basis_bps) else {
+            return Vec::new();
+        };
+        self.thresholds_bps
+            .iter()
+            .filter_map(|&threshold_bps| {
+                if last < threshold_bps && basis_bps >= threshold_bps {
+                    Some(BasisCrossing {
+                        threshold_bps,
+                        upward: true,
+                    })
+                } else if last >= threshold_bps && basis_bps < threshold_bps {
+                    Some(BasisCrossing {
+                        threshold_bps,
+                        upward: false,
+                    })
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+// Streams the basis of `pair` whenever either mid in `mids` changes, flagging crossings of
+// `thresholds_bps`, which are also logged as warnings. Keep `mids` updated, e.g. with
+// `MidCache::subscribe`; AllMids carries both perp and spot mids.
+pub fn monitor_basis(
+    mids: &MidCache,
+    pair: BasisPair,
+    thresholds_bps: Vec<f64>,
+) -> UnboundedReceiver<BasisUpdate> {
+    let mut perp_mids = mids.watch(&pair.perp, 0);
+    let mut spot_mids = mids.watch(&pair.spot, 0);
+    let (sender, updates) = unbounded_channel();
+    tokio::spawn(async move {
+        let mut tracker = ThresholdTracker {
+            thresholds_bps,
+            last: None,
+        };
+        let (mut perp_mid, mut spot_mid) = (None, None);
+        loop {
+            tokio::select! {
+                change = perp_mids.recv() => match change {
+                    Some(change) => perp_mid = Some(change.mid),
+                    None => return,
+                },
+                change = spot_mids.recv() => match change {
+                    Some(change) => spot_mid = Some(change.mid),
+                    None => return,
+                },
+            }
+            let (Some(perp_mid), Some(spot_mid)) = (perp_mid, spot_mid) else {
+                continue;
+            };
+            let basis_bps = (perp_mid - spot_mid) / spot_mid * 10_000.0;
+            let crossings = tracker.observe(basis_bps);
+            for crossing in &crossings {
+                warn!(
+                    "{} basis {} {} bps: {basis_bps:.1} bps",
+                    pair.perp,
+                    if crossing.upward {
+                        "rose above"
+                    } else {
+                        "fell below"
+                    },
+                    crossing.threshold_bps
+                );
+            }
+            let update = BasisUpdate {
+                pair: pair.clone(),
+                perp_mid,
+                spot_mid,
+                basis_bps,
+                crossings,
+            };
+            if sender.send(update).is_err() {
+                return;
+            }
+        }
+    });
+    updates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_resolve_pair() -> Result<()> {
+        let spot_meta: SpotMeta = serde_json::from_str(
+            r#"{"universe":[{"tokens":[1,0],"name":"PURR/USDC","index":0,"isCanonical":true},
+            {"tokens":[150,0],"name":"@107","index":107,"isCanonical":false}],
+            "tokens":[{"name":"USDC","szDecimals":8,"weiDecimals":8,"index":0,
+            "tokenId":"0x6d1e7cde53ba9467b783cb7c530ce054","isCanonical":true},
+            {"name":"HYPE","szDecimals":2,"weiDecimals":8,"index":150,
+            "tokenId":"0x0d01dc56dcaaca66ad901c959b4011ec","isCanonical":false}]}"#,
+        )
+        .unwrap();
+        let pair = BasisPair::resolve(&spot_meta, "HYPE", "HYPE")?;
+        assert_eq!(pair.spot, "@107");
+        assert!(BasisPair::resolve(&spot_meta, "BTC", "UBTC").is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_basis_crossings() {
+        let mids = MidCache::default();
+        let pair = BasisPair {
+            perp: "HYPE".to_string(),
+            spot: "@107".to_string(),
+        };
+        let mut updates = monitor_basis(&mids, pair, vec![10.0, 50.0]);
+        let update = |perp: &str, spot: &str| {
+            mids.update(&HashMap::from([
+                ("HYPE".to_string(), perp.to_string()),
+                ("@107".to_string(), spot.to_string()),
+            ]))
+        };
+
+        update("20.01", "20.0");
+        // Updates start once both mids are known
+        updates.recv().await.unwrap();
+        update("20.04", "20.0");
+        let rising = updates.recv().await.unwrap();
+        assert!((rising.basis_bps - 20.0).abs() < 1e-6);
+        assert_eq!(
+            rising.crossings,
+            [BasisCrossing {
+                threshold_bps: 10.0,
+                upward: true
+            }]
+        );
+        update("20.04", "20.04");
+        let falling = updates.recv().await.unwrap();
+        assert_eq!(falling.basis_bps, 0.0);
+        assert!(!falling.crossings[0].upward);
+    }
+}
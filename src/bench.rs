@@ -0,0 +1,46 @@
+use crate::{
+    exchange::action_hash, prelude::*, signature::sign_l1_action, ws::WsManager, Actions,
+    BulkOrder, ClientOrderRequest, Message,
+};
+use ethers::{
+    signers::LocalWallet,
+    types::{Signature, H160, H256},
+};
+use std::collections::HashMap;
+
+// The SDK's hot paths, exposed for the criterion benchmarks in benches/ (which only see public
+// items) under the `bench` feature. These are the same calls the clients make, not copies, so
+// a regression in them shows up in `cargo bench --features bench`.
+
+// Parses a websocket text frame as the client does on receipt.
+pub fn decode_ws_frame(text: &str) -> Result<Option<Message>> {
+    WsManager::classify(text)
+}
+
+// Converts orders to the wire action, as `ExchangeClient::bulk_order` does before signing.
+pub fn order_action(
+    orders: Vec<ClientOrderRequest>,
+    coin_to_asset: &HashMap<String, u32>,
+) -> Result<Actions> {
+    let orders = orders
+        .into_iter()
+        .map(|order| order.convert(coin_to_asset))
+        .collect::<Result<_>>()?;
+    Ok(Actions::Order(BulkOrder {
+        orders,
+        grouping: "na".to_string(),
+    }))
+}
+
+// The msgpack hash an L1 action is signed over.
+pub fn l1_action_hash(action: &Actions, nonce: u64, vault_address: Option<H160>) -> Result<H256> {
+    action_hash(action, nonce, vault_address, None)
+}
+
+pub fn sign_l1_action_hash(
+    wallet: &LocalWallet,
+    connection_id: H256,
+    is_mainnet: bool,
+) -> Result<Signature> {
+    sign_l1_action(wallet, connection_id, is_mainnet)
+}
@@ -0,0 +1,16 @@
+use ethers::types::H160;
+use hyperliquid_rust_sdk::{BaseUrl, EvmClient};
+use log::info;
+use std::str::FromStr;
+
+#[tokio::main]
+async fn main() {
+    env_logger::init();
+    let evm_client = EvmClient::new(Some(BaseUrl::Testnet)).await.unwrap();
+
+    let user = H160::from_str("0xc64cc00b46101bd40aa1c3121195e85c0b0918d8").unwrap();
+    info!(
+        "HYPE balance for {user}: {}",
+        evm_client.hype_balance(user).await.unwrap()
+    );
+}
@@ -0,0 +1,29 @@
+use ethers::types::H160;
+use hyperliquid_rust_sdk::{BaseUrl, ExplorerClient};
+use log::info;
+use std::str::FromStr;
+
+#[tokio::main]
+async fn main() {
+    env_logger::init();
+    let explorer_client = ExplorerClient::new(None, Some(BaseUrl::Testnet))
+        .await
+        .unwrap();
+
+    let user = H160::from_str("0xc64cc00b46101bd40aa1c3121195e85c0b0918d8").unwrap();
+    let user_details = explorer_client.user_details(user).await.unwrap();
+    info!("User details for {user}: {user_details:?}");
+
+    if let Some(tx) = user_details.txs.first() {
+        info!(
+            "Tx details for {}: {:?}",
+            tx.hash,
+            explorer_client.tx_details(tx.hash.clone()).await.unwrap()
+        );
+        info!(
+            "Block details for {}: {:?}",
+            tx.block,
+            explorer_client.block_details(tx.block).await.unwrap()
+        );
+    }
+}
@@ -0,0 +1,215 @@
+use clap::{Parser, Subcommand, ValueEnum};
+use ethers::{signers::LocalWallet, types::H160};
+use hyperliquid_rust_sdk::{
+    BaseUrl, ClientCancelRequest, ClientLimit, ClientOrder, ClientOrderRequest, Error,
+    ExchangeClient, InfoClient, KeySource, Message, Subscription,
+};
+use std::path::PathBuf;
+use tokio::sync::mpsc::unbounded_channel;
+
+// Command line access to common SDK operations. Build with `cargo run --features cli --bin hl`.
+#[derive(Parser)]
+#[command(name = "hl", about = "Hyperliquid command line client")]
+struct Cli {
+    /// Use testnet instead of mainnet
+    #[arg(long, global = true)]
+    testnet: bool,
+    /// Signing key for exchange commands
+    #[arg(long, env = "HL_PRIVATE_KEY", global = true, hide_env_values = true)]
+    private_key: Option<String>,
+    /// Web3 JSON keystore holding the signing key, decrypted with HL_KEYSTORE_PASSWORD
+    #[arg(long, global = true, conflicts_with = "private_key")]
+    keystore: Option<PathBuf>,
+    /// Command printing the signing key, e.g. a password manager CLI (run with sh -c)
+    #[arg(long, global = true, conflicts_with_all = ["private_key", "keystore"])]
+    key_command: Option<String>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Account value, withdrawable balance and open positions
+    Balances { user: H160 },
+    /// Open orders
+    Orders { user: H160 },
+    /// Stream the top of a coin's book until interrupted
+    Book {
+        coin: String,
+        #[arg(long, default_value_t = 5)]
+        depth: usize,
+    },
+    /// Place a limit order
+    Order {
+        coin: String,
+        side: Side,
+        sz: f64,
+        px: f64,
+        #[arg(long, default_value = "Gtc")]
+        tif: String,
+        #[arg(long)]
+        reduce_only: bool,
+    },
+    /// Cancel an order by oid
+    Cancel { coin: String, oid: u64 },
+    /// Approve a newly generated agent key and print it
+    ApproveAgent,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Side {
+    Buy,
+    Sell,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    env_logger::init();
+    let cli = Cli::parse();
+    let base_url = if cli.testnet {
+        BaseUrl::Testnet
+    } else {
+        BaseUrl::Mainnet
+    };
+    let key = cli.key();
+
+    match cli.command {
+        Command::Balances { user } => {
+            let info_client = InfoClient::new(None, Some(base_url)).await?;
+            let user_state = info_client.user_state(user).await?;
+            println!(
+                "account value {}  withdrawable {}",
+                user_state.margin_summary.account_value, user_state.withdrawable
+            );
+            for asset_position in user_state.asset_positions {
+                let position = asset_position.position;
+                println!(
+                    "{:<10} {:>14} @ {:<12} upnl {:<12} liq {}",
+                    position.coin,
+                    position.szi,
+                    position.entry_px.unwrap_or_default(),
+                    position.unrealized_pnl,
+                    position.liquidation_px.unwrap_or_default()
+                );
+            }
+        }
+        Command::Orders { user } => {
+            let info_client = InfoClient::new(None, Some(base_url)).await?;
+            for order in info_client.open_orders(user).await? {
+                println!(
+                    "{:<10} {} {:>14} @ {:<12} oid {}",
+                    order.coin, order.side, order.sz, order.limit_px, order.oid
+                );
+            }
+        }
+        Command::Book { coin, depth } => {
+            let mut info_client = InfoClient::new(None, Some(base_url)).await?;
+            let (sender, mut receiver) = unbounded_channel();
+            info_client
+                .subscribe(Subscription::L2Book { coin }, sender)
+                .await?;
+            // Runs until the connection closes; resyncs and errors are reported and the book
+            // keeps printing.
+            while let Some(message) = receiver.recv().await {
+                match message {
+                    Message::L2Book(l2_book) => {
+                        let [bids, asks] = l2_book.data.levels.as_slice() else {
+                            continue;
+                        };
+                        println!("--- {} {}", l2_book.data.coin, l2_book.data.time);
+                        for ask in asks.iter().take(depth).rev() {
+                            println!("{:>14} {:>14}", ask.px, ask.sz);
+                        }
+                        println!("{:-^29}", "");
+                        for bid in bids.iter().take(depth) {
+                            println!("{:>14} {:>14}", bid.px, bid.sz);
+                        }
+                    }
+                    Message::Resync(resync) => {
+                        eprintln!("Resyncing since {}: {:?}", resync.since, resync.reason)
+                    }
+                    Message::Error(error) => eprintln!("Error: {}", error.data),
+                    Message::SubscriptionRejected(rejection) => {
+                        eprintln!("Subscription rejected: {}", rejection.reason);
+                        break;
+                    }
+                    Message::Closed(closed) => {
+                        eprintln!("Connection closed ({}): {}", closed.code, closed.reason);
+                        break;
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Command::Order {
+            coin,
+            side,
+            sz,
+            px,
+            tif,
+            reduce_only,
+        } => {
+            let exchange_client = exchange_client(key, base_url).await?;
+            let order = ClientOrderRequest {
+                asset: coin,
+                is_buy: matches!(side, Side::Buy),
+                reduce_only,
+                limit_px: px,
+                sz,
+                cloid: None,
+                order_type: ClientOrder::Limit(ClientLimit { tif }),
+            };
+            println!("{:?}", exchange_client.order(order, None).await?);
+        }
+        Command::Cancel { coin, oid } => {
+            let exchange_client = exchange_client(key, base_url).await?;
+            let cancel = ClientCancelRequest { asset: coin, oid };
+            println!("{:?}", exchange_client.cancel(cancel, None).await?);
+        }
+        Command::ApproveAgent => {
+            let exchange_client = exchange_client(key, base_url).await?;
+            let (private_key, response) = exchange_client.approve_agent(None).await?;
+            println!("{response:?}");
+            println!("agent private key: {private_key}");
+        }
+    }
+    Ok(())
+}
+
+enum Key {
+    PrivateKey(Option<String>),
+    Source(KeySource),
+}
+
+impl Cli {
+    fn key(&self) -> Key {
+        if let Some(path) = &self.keystore {
+            return Key::Source(KeySource::Keystore {
+                path: path.clone(),
+                password_env: "HL_KEYSTORE_PASSWORD".to_string(),
+            });
+        }
+        if let Some(command) = &self.key_command {
+            return Key::Source(KeySource::Command {
+                program: "sh".to_string(),
+                args: vec!["-c".to_string(), command.clone()],
+            });
+        }
+        Key::PrivateKey(self.private_key.clone())
+    }
+}
+
+async fn exchange_client(key: Key, base_url: BaseUrl) -> Result<ExchangeClient, Error> {
+    let wallet: LocalWallet = match key {
+        Key::PrivateKey(Some(private_key)) => private_key
+            .parse()
+            .map_err(|e| Error::PrivateKeyParse(format!("{e}")))?,
+        Key::PrivateKey(None) => {
+            return Err(Error::PrivateKeyParse(
+                "set --private-key, HL_PRIVATE_KEY, --keystore or --key-command".to_string(),
+            ))
+        }
+        Key::Source(source) => source.load()?,
+    };
+    ExchangeClient::new(None, wallet, Some(base_url), None, None).await
+}
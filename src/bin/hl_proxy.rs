@@ -0,0 +1,45 @@
+use hyperliquid_rust_sdk::{ApiKey, BaseUrl, Error, ExchangeClient, KeySource, RestProxy};
+use std::{path::PathBuf, sync::Arc};
+
+// Serves the REST order management proxy. Build with `cargo run --features proxy --bin
+// hl_proxy`. Configured by environment:
+//
+//   HL_PROXY_KEYS     JSON file with the API keys, e.g.
+//                     [{"key": "...", "name": "dashboard", "requests_per_minute": 60,
+//                       "read_only": true}]
+//   HL_PROXY_ADDR     listen address, 127.0.0.1:8080 by default
+//   HL_NETWORK        "testnet" for testnet, mainnet otherwise
+//   HL_KEYSTORE       web3 keystore holding the signing key, decrypted with
+//                     HL_KEYSTORE_PASSWORD; otherwise the key is read from HL_PRIVATE_KEY
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    env_logger::init();
+    let keys_path = std::env::var("HL_PROXY_KEYS")
+        .map_err(|_| Error::Config("HL_PROXY_KEYS is not set".to_string()))?;
+    let keys = std::fs::read_to_string(&keys_path)
+        .map_err(|e| Error::Config(format!("reading {keys_path}: {e}")))?;
+    let keys: Vec<ApiKey> = serde_json::from_str(&keys)
+        .map_err(|e| Error::Config(format!("parsing {keys_path}: {e}")))?;
+    let addr = std::env::var("HL_PROXY_ADDR")
+        .unwrap_or_else(|_| "127.0.0.1:8080".to_string())
+        .parse()
+        .map_err(|e| Error::Config(format!("invalid HL_PROXY_ADDR: {e}")))?;
+    let base_url = match std::env::var("HL_NETWORK").as_deref() {
+        Ok("testnet") => BaseUrl::Testnet,
+        _ => BaseUrl::Mainnet,
+    };
+    let key_source = match std::env::var("HL_KEYSTORE") {
+        Ok(path) => KeySource::Keystore {
+            path: PathBuf::from(path),
+            password_env: "HL_KEYSTORE_PASSWORD".to_string(),
+        },
+        Err(_) => KeySource::default(),
+    };
+
+    let exchange_client =
+        ExchangeClient::new(None, key_source.load()?, Some(base_url), None, None).await?;
+    log::info!("Serving {} API keys on {addr}", keys.len());
+    RestProxy::new(Arc::new(exchange_client), keys)
+        .serve(addr)
+        .await
+}
@@ -1,5 +1,5 @@
 use ethers::types::H160;
-use hyperliquid_rust_sdk::{BaseUrl, InfoClient};
+use hyperliquid_rust_sdk::{BaseUrl, CandleInterval, InfoClient, Timestamp};
 use log::info;
 use std::str::FromStr;
 
@@ -8,6 +8,7 @@ async fn main() {
     env_logger::init();
     let info_client = InfoClient::new(None, Some(BaseUrl::Testnet)).await.unwrap();
     open_orders_example(&info_client).await;
+    frontend_open_orders_example(&info_client).await;
     user_state_example(&info_client).await;
     user_states_example(&info_client).await;
     recent_trades(&info_client).await;
@@ -17,6 +18,7 @@ async fn main() {
     funding_history_example(&info_client).await;
     l2_snapshot_example(&info_client).await;
     candles_snapshot_example(&info_client).await;
+    max_builder_fee_example(&info_client).await;
 }
 
 async fn open_orders_example(info_client: &InfoClient) {
@@ -31,6 +33,18 @@ async fn open_orders_example(info_client: &InfoClient) {
     );
 }
 
+async fn frontend_open_orders_example(info_client: &InfoClient) {
+    let user: String = "0xc64cc00b46101bd40aa1c3121195e85c0b0918d8"
+        .parse()
+        .unwrap();
+    let user = H160::from_str(&user).unwrap();
+
+    info!(
+        "Frontend open order data for {user}: {:?}",
+        info_client.frontend_open_orders(user).await.unwrap()
+    );
+}
+
 async fn user_state_example(info_client: &InfoClient) {
     let user: String = "0xc64cc00b46101bd40aa1c3121195e85c0b0918d8"
         .parse()
@@ -87,8 +101,8 @@ async fn user_fills_example(info_client: &InfoClient) {
 async fn funding_history_example(info_client: &InfoClient) {
     let coin = "ETH";
 
-    let start_timestamp = 1690540602225;
-    let end_timestamp = 1690569402225;
+    let start_timestamp = Timestamp::from_millis(1690540602225);
+    let end_timestamp = Timestamp::from_millis(1690569402225);
     info!(
         "Funding data history for {coin} between timestamps {start_timestamp} and {end_timestamp}: {:?}",
         info_client.funding_history(coin.to_string(), start_timestamp, Some(end_timestamp)).await.unwrap()
@@ -106,15 +120,25 @@ async fn l2_snapshot_example(info_client: &InfoClient) {
 
 async fn candles_snapshot_example(info_client: &InfoClient) {
     let coin = "ETH";
-    let start_timestamp = 1690540602225;
-    let end_timestamp = 1690569402225;
-    let interval = "1h";
+    let start_timestamp = Timestamp::from_millis(1690540602225);
+    let end_timestamp = Timestamp::from_millis(1690569402225);
+    let interval = CandleInterval::OneHour;
 
     info!(
         "Candles snapshot data for {coin} between timestamps {start_timestamp} and {end_timestamp} with interval {interval}: {:?}",
         info_client
-            .candles_snapshot(coin.to_string(), interval.to_string(), start_timestamp, end_timestamp)
+            .candles_snapshot(coin.to_string(), interval, start_timestamp, end_timestamp)
             .await
             .unwrap()
     );
 }
+
+async fn max_builder_fee_example(info_client: &InfoClient) {
+    let user = H160::from_str("0xc64cc00b46101bd40aa1c3121195e85c0b0918d8").unwrap();
+    let builder = H160::from_str("0x0d1d9635d0640821d15e323ac8adadfa9c111414").unwrap();
+
+    info!(
+        "Max builder fee approved by {user} for {builder}: {}",
+        info_client.max_builder_fee(user, builder).await.unwrap()
+    );
+}
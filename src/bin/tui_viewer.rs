@@ -0,0 +1,83 @@
+use ethers::types::H160;
+use hyperliquid_rust_sdk::{BaseUrl, BookPane, FillsPane, InfoClient, PositionsPane, Subscription};
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode},
+    layout::{Constraint, Layout},
+};
+use tokio::{
+    sync::mpsc::unbounded_channel,
+    time::{interval, Duration},
+};
+
+#[tokio::main]
+async fn main() {
+    env_logger::init();
+    let user: H160 = "0xc64cc00b46101bd40aa1c3121195e85c0b0918d8"
+        .parse()
+        .unwrap();
+
+    let mut info_client = InfoClient::new(None, Some(BaseUrl::Testnet)).await.unwrap();
+    let (sender, mut receiver) = unbounded_channel();
+    info_client
+        .subscribe(
+            Subscription::L2Book {
+                coin: "ETH".to_string(),
+            },
+            sender.clone(),
+        )
+        .await
+        .unwrap();
+    info_client
+        .subscribe(Subscription::all_mids(), sender.clone())
+        .await
+        .unwrap();
+    info_client
+        .subscribe(Subscription::UserFills { user }, sender)
+        .await
+        .unwrap();
+
+    let mut book = BookPane::new("ETH", 10);
+    let mut positions = PositionsPane::default();
+    let mut fills = FillsPane::new(20);
+
+    let mut terminal = ratatui::init();
+    let mut poll_positions = interval(Duration::from_secs(5));
+    let mut poll_keys = interval(Duration::from_millis(50));
+    loop {
+        tokio::select! {
+            Some(message) = receiver.recv() => {
+                book.on_message(&message);
+                positions.on_message(&message);
+                fills.on_message(&message);
+            }
+            _ = poll_positions.tick() => {
+                if let Ok(user_state) = info_client.user_state(user).await {
+                    positions.on_user_state(&user_state);
+                }
+            }
+            _ = poll_keys.tick() => {
+                if event::poll(Duration::ZERO).unwrap() {
+                    if let Event::Key(key) = event::read().unwrap() {
+                        if key.code == KeyCode::Char('q') {
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        terminal
+            .draw(|frame| {
+                let [left, right] =
+                    Layout::horizontal([Constraint::Percentage(30), Constraint::Fill(1)])
+                        .areas(frame.area());
+                let [top, bottom] =
+                    Layout::vertical([Constraint::Percentage(40), Constraint::Fill(1)])
+                        .areas(right);
+                frame.render_widget(&book, left);
+                frame.render_widget(&positions, top);
+                frame.render_widget(&fills, bottom);
+            })
+            .unwrap();
+    }
+    ratatui::restore();
+}
@@ -15,7 +15,7 @@ async fn main() {
 
     let (sender, mut receiver) = unbounded_channel();
     let subscription_id = info_client
-        .subscribe(Subscription::AllMids, sender)
+        .subscribe(Subscription::all_mids(), sender)
         .await
         .unwrap();
 
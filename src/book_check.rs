@@ -0,0 +1,335 @@
+use crate::{
+    BookLevel, InfoClient, L2Book, L2BookData, L2SnapshotResponse, Message, Resync, ResyncReason,
+    Subscription,
+};
+use log::{error, warn};
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::{
+    sync::mpsc::{unbounded_channel, UnboundedReceiver},
+    time,
+};
+
+#[derive(Debug, Clone)]
+pub struct BookCheckConfig {
+    pub interval: Duration,
+    // Levels compared on each side
+    pub depth: usize,
+    // Largest relative difference between two sizes at the same price that still matches
+    pub size_tolerance: f64,
+    // Checks in a row a coin has to fail before it is resynced, since a book can move between
+    // its last update and the snapshot
+    pub confirmations: u32,
+}
+
+impl Default for BookCheckConfig {
+    fn default() -> BookCheckConfig {
+        BookCheckConfig {
+            interval: Duration::from_secs(30),
+            depth: 5,
+            size_tolerance: 0.01,
+            confirmations: 2,
+        }
+    }
+}
+
+// The first level where a local book differs from the exchange's. A missing level is None.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BookDivergence {
+    pub coin: String,
+    // 0 for bids, 1 for asks, as in `L2BookData::levels`
+    pub side: usize,
+    pub level: usize,
+    pub local: Option<BookLevel>,
+    pub remote: Option<BookLevel>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BookCheckStats {
+    pub checks: u64,
+    pub divergences: u64,
+    pub resyncs: u64,
+}
+
+#[derive(Default)]
+struct BookCheckCounters {
+    checks: AtomicU64,
+    divergences: AtomicU64,
+    resyncs: AtomicU64,
+}
+
+// Counts of a `validate_books` task's checks, for exporting as metrics.
+#[derive(Clone, Default)]
+pub struct BookCheckMetrics(Arc<BookCheckCounters>);
+
+impl BookCheckMetrics {
+    pub fn stats(&self) -> BookCheckStats {
+        BookCheckStats {
+            checks: self.0.checks.load(Ordering::Relaxed),
+            divergences: self.0.divergences.load(Ordering::Relaxed),
+            resyncs: self.0.resyncs.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl From<L2SnapshotResponse> for L2BookData {
+    fn from(snapshot: L2SnapshotResponse) -> L2BookData {
+        L2BookData {
+            coin: snapshot.coin,
+            time: snapshot.time,
+            levels: snapshot
+                .levels
+                .into_iter()
+                .map(|side| {
+                    side.into_iter()
+                        .map(|level| BookLevel {
+                            px: level.px,
+                            sz: level.sz,
+                            n: level.n,
+                        })
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+}
+
+// Compares the top `depth` levels of each side of two books of the same coin. Prices have to
+// match exactly and sizes within `size_tolerance` of the larger one; order counts aren't
+// compared.
+pub fn compare_books(
+    local: &L2BookData,
+    remote: &L2BookData,
+    depth: usize,
+    size_tolerance: f64,
+) -> Option<BookDivergence> {
+    let empty = Vec::new();
+    for side in 0..2 {
+        let local_side = local.levels.get(side).unwrap_or(&empty);
+        let remote_side = remote.levels.get(side).unwrap_or(&empty);
+        for level in 0..depth {
+            let (local_level, remote_level) = (local_side.get(level), remote_side.get(level));
+            let matches = match (local_level, remote_level) {
+                (None, None) => break,
+                (Some(a), Some(b)) => levels_match(a, b, size_tolerance),
+                _ => false,
+            };
+            if !matches {
+                return Some(BookDivergence {
+                    coin: remote.coin.clone(),
+                    side,
+                    level,
+                    local: local_level.cloned(),
+                    remote: remote_level.cloned(),
+                });
+            }
+        }
+    }
+    None
+}
+
+fn levels_match(a: &BookLevel, b: &BookLevel, size_tolerance: f64) -> bool {
+    let parse = |level: &BookLevel| -> Option<(f64, f64)> {
+        Some((level.px.parse().ok()?, level.sz.parse().ok()?))
+    };
+    let (Some((a_px, a_sz)), Some((b_px, b_sz))) = (parse(a), parse(b)) else {
+        return false;
+    };
+    a_px == b_px && (a_sz - b_sz).abs() <= size_tolerance * a_sz.max(b_sz)
+}
+
+// Tracks the latest L2Book of each coin and checks it against REST snapshots, resyncing coins
+// that fail `confirmations` checks in a row.
+pub struct BookValidator {
+    config: BookCheckConfig,
+    books: BTreeMap<String, Arc<L2BookData>>,
+    failures: BTreeMap<String, u32>,
+    metrics: BookCheckMetrics,
+}
+
+impl BookValidator {
+    pub fn new(config: BookCheckConfig) -> BookValidator {
+        BookValidator {
+            config,
+            books: BTreeMap::new(),
+            failures: BTreeMap::new(),
+            metrics: BookCheckMetrics::default(),
+        }
+    }
+
+    pub fn metrics(&self) -> BookCheckMetrics {
+        self.metrics.clone()
+    }
+
+    pub fn on_message(&mut self, message: &Message) {
+        if let Message::L2Book(l2_book) = message {
+            self.books
+                .insert(l2_book.data.coin.clone(), l2_book.data.clone());
+        }
+    }
+
+    // The coins with a book to check.
+    pub fn coins(&self) -> Vec<String> {
+        self.books.keys().cloned().collect()
+    }
+
+    // Checks the local book of the snapshot's coin. Once it has diverged `confirmations` times
+    // in a row, returns a Resync followed by the snapshot, which replaces the local book.
+    pub fn on_snapshot(&mut self, snapshot: L2BookData) -> Vec<Message> {
+        let Some(local) = self.books.get(&snapshot.coin) else {
+            return Vec::new();
+        };
+        let counters = &self.metrics.0;
+        counters.checks.fetch_add(1, Ordering::Relaxed);
+        let Some(divergence) = compare_books(
+            local,
+            &snapshot,
+            self.config.depth,
+            self.config.size_tolerance,
+        ) else {
+            self.failures.remove(&snapshot.coin);
+            return Vec::new();
+        };
+        counters.divergences.fetch_add(1, Ordering::Relaxed);
+        let failures = self.failures.entry(snapshot.coin.clone()).or_default();
+        *failures += 1;
+        if *failures < self.config.confirmations {
+            return Vec::new();
+        }
+
+        warn!(
+            "Local {} book diverged from the exchange's, resyncing: {divergence:?}",
+            snapshot.coin
+        );
+        counters.resyncs.fetch_add(1, Ordering::Relaxed);
+        self.failures.remove(&snapshot.coin);
+        let since = local.time;
+        let snapshot = Arc::new(snapshot);
+        self.books.insert(snapshot.coin.clone(), snapshot.clone());
+        vec![
+            Message::Resync(Resync {
+                subscription: Subscription::l2_book(snapshot.coin.as_str()),
+                since,
+                reason: ResyncReason::BookDiverged,
+            }),
+            Message::L2Book(L2Book { data: snapshot }),
+        ]
+    }
+}
+
+// Forwards `receiver`'s messages, checking the books among them against REST snapshots every
+// `config.interval`. A diverged book is resynced in the stream: a Resync with reason
+// BookDiverged, then the snapshot as an L2Book, so consumers rebuild the book from it.
+pub fn validate_books(
+    info_client: InfoClient,
+    mut receiver: UnboundedReceiver<Message>,
+    config: BookCheckConfig,
+) -> (UnboundedReceiver<Message>, BookCheckMetrics) {
+    let (sender, messages) = unbounded_channel();
+    let mut validator = BookValidator::new(config);
+    let metrics = validator.metrics();
+    tokio::spawn(async move {
+        let mut checks = time::interval(validator.config.interval);
+        checks.tick().await;
+        loop {
+            tokio::select! {
+                message = receiver.recv() => {
+                    let Some(message) = message else {
+                        return;
+                    };
+                    validator.on_message(&message);
+                    if sender.send(message).is_err() {
+                        return;
+                    }
+                }
+                _ = checks.tick() => {
+                    for coin in validator.coins() {
+                        let snapshot = match info_client.l2_snapshot(coin.clone()).await {
+                            Ok(snapshot) => snapshot,
+                            Err(err) => {
+                                error!("Error fetching the {coin} book to check: {err}");
+                                continue;
+                            }
+                        };
+                        for message in validator.on_snapshot(snapshot.into()) {
+                            if sender.send(message).is_err() {
+                                return;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    });
+    (messages, metrics)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diverged_books_are_resynced() {
+        let local = L2BookData::test_book(
+            1,
+            vec![
+                BookLevel::test_level("1999", "1"),
+                BookLevel::test_level("1998", "2"),
+            ],
+            vec![
+                BookLevel::test_level("2000", "1"),
+                BookLevel::test_level("2001", "5"),
+            ],
+        );
+        // Small size differences and levels beyond the depth are ignored
+        let mut remote = local.clone();
+        remote.levels[0][0].sz = "1.005".to_string();
+        remote.levels[1][1].sz = "9".to_string();
+        assert_eq!(compare_books(&local, &remote, 1, 0.01), None);
+
+        let divergence = compare_books(&local, &remote, 2, 0.01).unwrap();
+        assert_eq!((divergence.side, divergence.level), (1, 1));
+        remote.levels[1].pop();
+        let divergence = compare_books(&local, &remote, 2, 0.01).unwrap();
+        assert_eq!(divergence.remote, None);
+
+        let mut validator = BookValidator::new(BookCheckConfig::default());
+        let stale = || {
+            L2BookData::test_book(
+                1,
+                vec![BookLevel::test_level("1990", "1")],
+                vec![BookLevel::test_level("1991", "1")],
+            )
+        };
+        assert!(validator.on_snapshot(stale()).is_empty());
+        validator.on_message(&Message::L2Book(L2Book {
+            data: Arc::new(local.clone()),
+        }));
+        // A book that diverges once isn't resynced, so it has to diverge twice in a row
+        assert!(validator.on_snapshot(stale()).is_empty());
+        assert!(validator.on_snapshot(local.clone()).is_empty());
+        assert!(validator.on_snapshot(stale()).is_empty());
+        let messages = validator.on_snapshot(stale());
+        let [Message::Resync(resync), Message::L2Book(l2_book)] = &messages[..] else {
+            panic!("expected a resync and a book, got {messages:?}");
+        };
+        assert_eq!(resync.reason, ResyncReason::BookDiverged);
+        assert_eq!(resync.subscription, Subscription::l2_book("ETH"));
+        assert_eq!(*l2_book.data, stale());
+        assert!(validator.on_snapshot(stale()).is_empty());
+        assert_eq!(
+            validator.metrics().stats(),
+            BookCheckStats {
+                checks: 5,
+                divergences: 3,
+                resyncs: 1,
+            }
+        );
+    }
+}
@@ -0,0 +1,175 @@
+use crate::{prelude::*, BookLevel, Error, L2BookData, Timestamp};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// One changed level. A size of "0" removes the level.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct LevelChange {
+    // 0 for bids, 1 for asks, matching the index into `L2BookData::levels`
+    pub side: u8,
+    pub px: String,
+    pub sz: String,
+    pub n: u64,
+}
+
+// The changes turning the book at `prev_time` into the book at `time`. Re-broadcasting these
+// instead of full snapshots cuts bandwidth, as most updates only touch a few levels.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct L2BookDelta {
+    pub coin: String,
+    pub prev_time: Timestamp,
+    pub time: Timestamp,
+    pub changes: Vec<LevelChange>,
+}
+
+impl L2BookDelta {
+    pub fn diff(prev: &L2BookData, next: &L2BookData) -> Result<L2BookDelta> {
+        if prev.coin != next.coin {
+            return Err(Error::GenericParse(format!(
+                "cannot diff {} book against {} book",
+                prev.coin, next.coin
+            )));
+        }
+        let mut changes = Vec::new();
+        for side in 0..2 {
+            let prev_levels = levels(prev, side);
+            let next_levels = levels(next, side);
+            let by_px: HashMap<&str, &BookLevel> = prev_levels
+                .iter()
+                .map(|level| (level.px.as_str(), level))
+                .collect();
+            for level in next_levels {
+                if by_px.get(level.px.as_str()) != Some(&level) {
+                    changes.push(LevelChange {
+                        side: side as u8,
+                        px: level.px.clone(),
+                        sz: level.sz.clone(),
+                        n: level.n,
+                    });
+                }
+            }
+            for level in prev_levels {
+                if !next_levels.iter().any(|next| next.px == level.px) {
+                    changes.push(LevelChange {
+                        side: side as u8,
+                        px: level.px.clone(),
+                        sz: "0".to_string(),
+                        n: 0,
+                    });
+                }
+            }
+        }
+        Ok(L2BookDelta {
+            coin: next.coin.clone(),
+            prev_time: prev.time,
+            time: next.time,
+            changes,
+        })
+    }
+
+    // Applies the delta to the book it was computed from. Fails without modifying the book if
+    // the book isn't at `prev_time`, i.e. a delta was missed and a new snapshot is needed.
+    pub fn apply(&self, book: &mut L2BookData) -> Result<()> {
+        if book.coin != self.coin || book.time != self.prev_time {
+            return Err(Error::GenericParse(format!(
+                "delta from {} {} applied to {} book at {}",
+                self.coin, self.prev_time, book.coin, book.time
+            )));
+        }
+        let mut sides = Vec::with_capacity(2);
+        for side in 0..2 {
+            let mut levels = levels(book, side).to_vec();
+            for change in self
+                .changes
+                .iter()
+                .filter(|change| change.side == side as u8)
+            {
+                levels.retain(|level| level.px != change.px);
+                if change
+                    .sz
+                    .parse::<f64>()
+                    .map_err(|_| Error::FloatStringParse)?
+                    > 0.0
+                {
+                    levels.push(BookLevel {
+                        px: change.px.clone(),
+                        sz: change.sz.clone(),
+                        n: change.n,
+                    });
+                }
+            }
+            let mut keyed = levels
+                .into_iter()
+                .map(|level| {
+                    Ok((
+                        level
+                            .px
+                            .parse::<f64>()
+                            .map_err(|_| Error::FloatStringParse)?,
+                        level,
+                    ))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            // Bids best (highest) first, asks best (lowest) first
+            keyed.sort_by(|(a, _), (b, _)| {
+                if side == 0 {
+                    b.total_cmp(a)
+                } else {
+                    a.total_cmp(b)
+                }
+            });
+            sides.push(keyed.into_iter().map(|(_, level)| level).collect());
+        }
+        book.levels = sides;
+        book.time = self.time;
+        Ok(())
+    }
+}
+
+fn levels(book: &L2BookData, side: usize) -> &[BookLevel] {
+    book.levels.get(side).map(Vec::as_slice).unwrap_or(&[])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_and_apply_roundtrip() -> Result<()> {
+        let prev = L2BookData::test_book(
+            1,
+            vec![
+                BookLevel::test_level("100", "1"),
+                BookLevel::test_level("99", "2"),
+                BookLevel::test_level("98", "3"),
+            ],
+            vec![
+                BookLevel::test_level("101", "1"),
+                BookLevel::test_level("102", "2"),
+            ],
+        );
+        let next = L2BookData::test_book(
+            2,
+            vec![
+                BookLevel::test_level("100.5", "4"),
+                BookLevel::test_level("100", "1"),
+                BookLevel::test_level("98", "5"),
+            ],
+            vec![
+                BookLevel::test_level("101", "1"),
+                BookLevel::test_level("102", "2"),
+            ],
+        );
+        let delta = L2BookDelta::diff(&prev, &next)?;
+        // New 100.5 bid, resized 98 bid and removed 99 bid; asks unchanged
+        assert_eq!(delta.changes.len(), 3);
+
+        let mut book = prev.clone();
+        delta.apply(&mut book)?;
+        assert_eq!(book, next);
+
+        // Applying it again is a sequence error
+        assert!(delta.apply(&mut book).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,282 @@
+use crate::{prelude::*, Error, Message};
+use log::warn;
+#[cfg(feature = "kafka")]
+use std::time::Duration;
+use std::{collections::HashMap, future::Future};
+use tokio::{sync::mpsc::UnboundedReceiver, task::JoinHandle};
+
+// How bridged messages are serialized. Both keep the message's `channel` tag, so consumers
+// can deserialize payloads back into `Message`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Json,
+    MsgPack,
+}
+
+// An event bus the bridge publishes to. `key` is the message's coin or user, if it has one,
+// for buses that partition by key.
+pub trait Publisher {
+    fn publish(
+        &self,
+        topic: &str,
+        key: Option<&str>,
+        payload: Vec<u8>,
+    ) -> impl Future<Output = Result<()>> + Send;
+}
+
+// Republishes SDK messages onto Kafka topics or NATS subjects. Each message's topic comes from
+// its channel's template, or the default template, where `{channel}` is replaced with the
+// channel name (e.g. "l2Book") and `{key}` with the message's coin or user, or "all" without
+// one. Key characters a bus may reject are replaced with '_'.
+//
+//     let bridge = Bridge::new(NatsPublisher::connect("nats://localhost:4222").await?)
+//         .default_topic(Some("hl.{channel}.{key}"))
+//         .encoding(Encoding::MsgPack);
+//     bridge.spawn(receiver);
+pub struct Bridge<P> {
+    publisher: P,
+    encoding: Encoding,
+    topics: HashMap<String, String>,
+    default_topic: Option<String>,
+}
+
+impl<P: Publisher> Bridge<P> {
+    // Publishes every message as JSON to "hyperliquid.{channel}".
+    pub fn new(publisher: P) -> Bridge<P> {
+        Bridge {
+            publisher,
+            encoding: Encoding::Json,
+            topics: HashMap::new(),
+            default_topic: Some("hyperliquid.{channel}".to_string()),
+        }
+    }
+
+    pub fn encoding(mut self, encoding: Encoding) -> Bridge<P> {
+        self.encoding = encoding;
+        self
+    }
+
+    pub fn topic(mut self, channel: &str, template: &str) -> Bridge<P> {
+        self.topics
+            .insert(channel.to_string(), template.to_string());
+        self
+    }
+
+    // The template of channels without their own. With `None`, only channels with a template
+    // are published.
+    pub fn default_topic(mut self, template: Option<&str>) -> Bridge<P> {
+        self.default_topic = template.map(str::to_string);
+        self
+    }
+
+    // None for messages that aren't published: subscription acks, pongs and channels without a
+    // template.
+    pub fn topic_for(&self, message: &Message) -> Option<String> {
+        let channel = message.channel()?;
+        let template = self.topics.get(channel).or(self.default_topic.as_ref())?;
+        let key = key(message).map(|key| sanitize(&key));
+        Some(
+            template
+                .replace("{channel}", channel)
+                .replace("{key}", key.as_deref().unwrap_or("all")),
+        )
+    }
+
+    pub fn encode(&self, message: &Message) -> Result<Vec<u8>> {
+        match self.encoding {
+            Encoding::Json => {
+                serde_json::to_vec(message).map_err(|e| Error::JsonParse(e.to_string()))
+            }
+            Encoding::MsgPack => {
+                rmp_serde::to_vec_named(message).map_err(|e| Error::RmpParse(e.to_string()))
+            }
+        }
+    }
+
+    pub async fn publish(&self, message: &Message) -> Result<()> {
+        let Some(topic) = self.topic_for(message) else {
+            return Ok(());
+        };
+        let payload = self.encode(message)?;
+        self.publisher
+            .publish(&topic, key(message).as_deref(), payload)
+            .await
+    }
+}
+
+impl<P: Publisher + Send + Sync + 'static> Bridge<P> {
+    // Publishes every message `receiver` yields, in order, until it closes. Failed publishes
+    // are logged and the message dropped.
+    pub fn spawn(self, mut receiver: UnboundedReceiver<Message>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            while let Some(message) = receiver.recv().await {
+                if let Err(err) = self.publish(&message).await {
+                    warn!("Bridging message failed: {err}");
+                }
+            }
+        })
+    }
+}
+
+fn key(message: &Message) -> Option<String> {
+    match message {
+        Message::Trades(trades) => trades.data.first().map(|trade| trade.coin.clone()),
+        Message::L2Book(l2_book) => Some(l2_book.data.coin.clone()),
+        Message::Candle(candle) => Some(candle.data.coin.clone()),
+        Message::ActiveAssetCtx(ctx) => Some(ctx.data.coin.clone()),
+        Message::UserFills(fills) => Some(format!("{:?}", fills.data.user)),
+        Message::UserFundings(fundings) => Some(format!("{:?}", fundings.data.user)),
+        Message::UserNonFundingLedgerUpdates(updates) => Some(format!("{:?}", updates.data.user)),
+        _ => None,
+    }
+}
+
+// Kafka topics allow only [a-zA-Z0-9._-], and '.', '*' and '>' are special in NATS subjects,
+// so spot coins like "@107" or "PURR/USDC" are rewritten.
+fn sanitize(key: &str) -> String {
+    key.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[cfg(feature = "nats")]
+pub struct NatsPublisher {
+    client: async_nats::Client,
+}
+
+#[cfg(feature = "nats")]
+impl NatsPublisher {
+    pub async fn connect(url: &str) -> Result<NatsPublisher> {
+        let client = async_nats::connect(url)
+            .await
+            .map_err(|e| Error::Bridge(format!("connecting to {url}: {e}")))?;
+        Ok(NatsPublisher { client })
+    }
+
+    pub fn from_client(client: async_nats::Client) -> NatsPublisher {
+        NatsPublisher { client }
+    }
+}
+
+#[cfg(feature = "nats")]
+impl Publisher for NatsPublisher {
+    async fn publish(&self, topic: &str, _key: Option<&str>, payload: Vec<u8>) -> Result<()> {
+        self.client
+            .publish(topic.to_string(), payload.into())
+            .await
+            .map_err(|e| Error::Bridge(e.to_string()))
+    }
+}
+
+#[cfg(feature = "kafka")]
+pub struct KafkaPublisher {
+    producer: rdkafka::producer::FutureProducer,
+    timeout: Duration,
+}
+
+#[cfg(feature = "kafka")]
+impl KafkaPublisher {
+    // `brokers` is a comma separated list of host:port pairs.
+    pub fn new(brokers: &str) -> Result<KafkaPublisher> {
+        let mut config = rdkafka::ClientConfig::new();
+        config.set("bootstrap.servers", brokers);
+        KafkaPublisher::from_config(&config)
+    }
+
+    pub fn from_config(config: &rdkafka::ClientConfig) -> Result<KafkaPublisher> {
+        let producer = config
+            .create()
+            .map_err(|e| Error::Bridge(format!("creating Kafka producer: {e}")))?;
+        Ok(KafkaPublisher {
+            producer,
+            timeout: Duration::from_secs(5),
+        })
+    }
+
+    // How long a publish waits for room in the producer's queue.
+    pub fn timeout(mut self, timeout: Duration) -> KafkaPublisher {
+        self.timeout = timeout;
+        self
+    }
+}
+
+#[cfg(feature = "kafka")]
+impl Publisher for KafkaPublisher {
+    async fn publish(&self, topic: &str, key: Option<&str>, payload: Vec<u8>) -> Result<()> {
+        let mut record = rdkafka::producer::FutureRecord::<str, [u8]>::to(topic).payload(&payload);
+        if let Some(key) = key {
+            record = record.key(key);
+        }
+        self.producer
+            .send(record, self.timeout)
+            .await
+            .map(|_| ())
+            .map_err(|(e, _)| Error::Bridge(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    // Topic, key and payload
+    type Published = (String, Option<String>, Vec<u8>);
+
+    #[derive(Default)]
+    struct Recorder {
+        published: Mutex<Vec<Published>>,
+    }
+
+    impl Publisher for &Recorder {
+        async fn publish(&self, topic: &str, key: Option<&str>, payload: Vec<u8>) -> Result<()> {
+            self.published.lock().unwrap().push((
+                topic.to_string(),
+                key.map(str::to_string),
+                payload,
+            ));
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bridge_topics() -> Result<()> {
+        let book: Message = serde_json::from_value(serde_json::json!({
+            "channel": "l2Book",
+            "data": {"coin": "@107", "time": 1_000, "levels": [[], []]}
+        }))
+        .unwrap();
+        let mids: Message = serde_json::from_value(serde_json::json!({
+            "channel": "allMids",
+            "data": {"mids": {"ETH": "2000.0"}}
+        }))
+        .unwrap();
+
+        let recorder = Recorder::default();
+        let bridge = Bridge::new(&recorder)
+            .topic("l2Book", "hl.books.{key}")
+            .encoding(Encoding::MsgPack);
+        bridge.publish(&book).await?;
+        bridge.publish(&mids).await?;
+        bridge.publish(&Message::Pong).await?;
+        {
+            let published = recorder.published.lock().unwrap();
+            assert_eq!(published.len(), 2);
+            assert_eq!(published[0].0, "hl.books._107");
+            assert_eq!(published[0].1.as_deref(), Some("@107"));
+            let decoded: Message = rmp_serde::from_slice(&published[0].2).unwrap();
+            assert!(matches!(decoded, Message::L2Book(_)));
+            assert_eq!(published[1].0, "hyperliquid.allMids");
+        }
+
+        let bridge = Bridge::new(&recorder).default_topic(None);
+        assert!(bridge.topic_for(&mids).is_none());
+        Ok(())
+    }
+}
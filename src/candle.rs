@@ -0,0 +1,265 @@
+use crate::{prelude::*, Error, Timestamp};
+use chrono::{Datelike, TimeZone, Utc};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::{fmt, str::FromStr, time::Duration};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum CandleInterval {
+    OneMinute,
+    ThreeMinutes,
+    FiveMinutes,
+    FifteenMinutes,
+    ThirtyMinutes,
+    OneHour,
+    TwoHours,
+    FourHours,
+    EightHours,
+    TwelveHours,
+    OneDay,
+    ThreeDays,
+    OneWeek,
+    OneMonth,
+}
+
+impl CandleInterval {
+    pub const ALL: [CandleInterval; 14] = [
+        CandleInterval::OneMinute,
+        CandleInterval::ThreeMinutes,
+        CandleInterval::FiveMinutes,
+        CandleInterval::FifteenMinutes,
+        CandleInterval::ThirtyMinutes,
+        CandleInterval::OneHour,
+        CandleInterval::TwoHours,
+        CandleInterval::FourHours,
+        CandleInterval::EightHours,
+        CandleInterval::TwelveHours,
+        CandleInterval::OneDay,
+        CandleInterval::ThreeDays,
+        CandleInterval::OneWeek,
+        CandleInterval::OneMonth,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CandleInterval::OneMinute => "1m",
+            CandleInterval::ThreeMinutes => "3m",
+            CandleInterval::FiveMinutes => "5m",
+            CandleInterval::FifteenMinutes => "15m",
+            CandleInterval::ThirtyMinutes => "30m",
+            CandleInterval::OneHour => "1h",
+            CandleInterval::TwoHours => "2h",
+            CandleInterval::FourHours => "4h",
+            CandleInterval::EightHours => "8h",
+            CandleInterval::TwelveHours => "12h",
+            CandleInterval::OneDay => "1d",
+            CandleInterval::ThreeDays => "3d",
+            CandleInterval::OneWeek => "1w",
+            CandleInterval::OneMonth => "1M",
+        }
+    }
+
+    // Months are calendar months, so their duration is nominal (30 days); use `candle_start`
+    // and `next_boundary` for exact month edges.
+    pub fn duration(&self) -> Duration {
+        const MINUTE: u64 = 60;
+        const HOUR: u64 = 60 * MINUTE;
+        const DAY: u64 = 24 * HOUR;
+        Duration::from_secs(match self {
+            CandleInterval::OneMinute => MINUTE,
+            CandleInterval::ThreeMinutes => 3 * MINUTE,
+            CandleInterval::FiveMinutes => 5 * MINUTE,
+            CandleInterval::FifteenMinutes => 15 * MINUTE,
+            CandleInterval::ThirtyMinutes => 30 * MINUTE,
+            CandleInterval::OneHour => HOUR,
+            CandleInterval::TwoHours => 2 * HOUR,
+            CandleInterval::FourHours => 4 * HOUR,
+            CandleInterval::EightHours => 8 * HOUR,
+            CandleInterval::TwelveHours => 12 * HOUR,
+            CandleInterval::OneDay => DAY,
+            CandleInterval::ThreeDays => 3 * DAY,
+            CandleInterval::OneWeek => 7 * DAY,
+            CandleInterval::OneMonth => 30 * DAY,
+        })
+    }
+
+    // The open time of the candle containing `time`. Fixed intervals are aligned to the Unix
+    // epoch, months to the first of the month (UTC).
+    pub fn candle_start(&self, time: Timestamp) -> Timestamp {
+        if *self == CandleInterval::OneMonth {
+            let datetime = time.to_datetime();
+            return Utc
+                .with_ymd_and_hms(datetime.year(), datetime.month(), 1, 0, 0, 0)
+                .single()
+                .map(Timestamp::from)
+                .unwrap_or(time);
+        }
+        let interval_ms = self.duration().as_millis() as u64;
+        Timestamp::from_millis(time.as_millis() / interval_ms * interval_ms)
+    }
+
+    // The open time of the candle after the one containing `time`.
+    pub fn next_boundary(&self, time: Timestamp) -> Timestamp {
+        let start = self.candle_start(time);
+        if *self == CandleInterval::OneMonth {
+            let datetime = start.to_datetime();
+            let (year, month) = match datetime.month() {
+                12 => (datetime.year() + 1, 1),
+                month => (datetime.year(), month + 1),
+            };
+            return Utc
+                .with_ymd_and_hms(year, month, 1, 0, 0, 0)
+                .single()
+                .map(Timestamp::from)
+                .unwrap_or(start + self.duration());
+        }
+        start + self.duration()
+    }
+}
+
+impl fmt::Display for CandleInterval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+// Parses the exchange's names ("15m", "1h", "1M"), and normalizes other spellings of the same
+// intervals to them: "60m" or "1H" is "1h", "5min" is "5m", "7d" is "1w", "1mo" is "1M". Minutes
+// and months are told apart by case, as on the exchange. Intervals the exchange doesn't offer,
+// like "2m", are an `Error::InvalidCandleInterval`.
+impl FromStr for CandleInterval {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<CandleInterval> {
+        if let Some(interval) = CandleInterval::ALL
+            .into_iter()
+            .find(|interval| interval.as_str() == s)
+        {
+            return Ok(interval);
+        }
+        CandleInterval::normalize(s).ok_or_else(|| Error::InvalidCandleInterval(s.to_string()))
+    }
+}
+
+impl CandleInterval {
+    fn normalize(s: &str) -> Option<CandleInterval> {
+        let s = s.trim();
+        let digits = s.find(|c: char| !c.is_ascii_digit())?;
+        let (count, unit) = s.split_at(digits);
+        let count: u64 = count.parse().ok()?;
+        let minutes = match unit {
+            "m" | "min" | "mins" => count,
+            "h" | "H" | "hr" | "hour" | "hours" => count.checked_mul(60)?,
+            "d" | "D" | "day" | "days" => count.checked_mul(60 * 24)?,
+            "w" | "W" | "week" | "weeks" => count.checked_mul(60 * 24 * 7)?,
+            "M" | "mo" | "mon" | "month" | "months" => {
+                return (count == 1).then_some(CandleInterval::OneMonth)
+            }
+            _ => return None,
+        };
+        let secs = minutes.checked_mul(60)?;
+        CandleInterval::ALL.into_iter().find(|interval| {
+            *interval != CandleInterval::OneMonth && interval.duration().as_secs() == secs
+        })
+    }
+}
+
+impl Serialize for CandleInterval {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+// Accepts what `from_str` does, so configs and JSON subscriptions can use any spelling.
+impl<'de> Deserialize<'de> for CandleInterval {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        struct IntervalVisitor;
+
+        impl de::Visitor<'_> for IntervalVisitor {
+            type Value = CandleInterval;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a candle interval like \"15m\"")
+            }
+
+            fn visit_str<E: de::Error>(self, s: &str) -> std::result::Result<CandleInterval, E> {
+                s.parse().map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_str(IntervalVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_candle_interval() -> Result<()> {
+        for interval in CandleInterval::ALL {
+            assert_eq!(interval.as_str().parse::<CandleInterval>()?, interval);
+            assert_eq!(
+                serde_json::to_string(&interval).unwrap(),
+                format!("\"{interval}\"")
+            );
+        }
+        assert!(matches!(
+            "2m".parse::<CandleInterval>(),
+            Err(Error::InvalidCandleInterval(interval)) if interval == "2m"
+        ));
+        for (alias, interval) in [
+            ("60m", CandleInterval::OneHour),
+            ("1H", CandleInterval::OneHour),
+            ("5min", CandleInterval::FiveMinutes),
+            ("240m", CandleInterval::FourHours),
+            ("24h", CandleInterval::OneDay),
+            ("7d", CandleInterval::OneWeek),
+            ("1mo", CandleInterval::OneMonth),
+        ] {
+            assert_eq!(alias.parse::<CandleInterval>()?, interval);
+        }
+        assert!("2M".parse::<CandleInterval>().is_err());
+        assert!("0m".parse::<CandleInterval>().is_err());
+        for huge in [
+            "20000000000000000w",
+            "20000000000000000m",
+            "18446744073709551615h",
+        ] {
+            assert!(matches!(
+                huge.parse::<CandleInterval>(),
+                Err(Error::InvalidCandleInterval(interval)) if interval == huge
+            ));
+        }
+        assert_eq!(
+            serde_json::from_str::<CandleInterval>("\"60m\"").unwrap(),
+            CandleInterval::OneHour
+        );
+        let err = serde_json::from_str::<crate::Subscription>(
+            r#"{"type":"candle","coin":"ETH","interval":"2m"}"#,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("Unsupported candle interval"));
+
+        // 2024-02-15 10:07:30 UTC
+        let time = Timestamp::from_millis(1_707_991_650_000);
+        let fifteen = CandleInterval::FifteenMinutes;
+        assert_eq!(
+            fifteen.candle_start(time).to_string(),
+            "2024-02-15T10:00:00.000Z"
+        );
+        assert_eq!(
+            fifteen.next_boundary(time).to_string(),
+            "2024-02-15T10:15:00.000Z"
+        );
+        let month = CandleInterval::OneMonth;
+        assert_eq!(
+            month.candle_start(time).to_string(),
+            "2024-02-01T00:00:00.000Z"
+        );
+        assert_eq!(
+            month.next_boundary(time).to_string(),
+            "2024-03-01T00:00:00.000Z"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,64 @@
+// Migration shims for code written against earlier releases, kept for one minor release after
+// the change they smooth over. Import what you need from here while migrating, then follow the
+// deprecation notes.
+//
+// Since 0.5, `Message`, `Subscription` and `Error` are `#[non_exhaustive]`: the exchange adds
+// channels often, and adding the variant no longer breaks downstream matches. Matches need a
+// `_` arm, and `Message::channel` names whatever it caught:
+//
+//     match message {
+//         Message::Trades(trades) => on_trades(trades),
+//         Message::L2Book(l2_book) => on_book(l2_book),
+//         other => debug!("ignoring {:?}", other.channel()),
+//     }
+//
+// Prefer the constructors like `Subscription::l2_book("ETH")` to struct variants, which break
+// if the exchange adds a field to the subscription.
+use crate::{
+    prelude::*, CandleInterval, CandlesSnapshotResponse, FundingHistoryResponse, InfoClient,
+    Timestamp,
+};
+
+// `InfoClient::funding_history` as it was before it took `Timestamp`s.
+#[deprecated(
+    since = "0.5.0",
+    note = "use InfoClient::funding_history with Timestamps"
+)]
+pub async fn funding_history(
+    info_client: &InfoClient,
+    coin: String,
+    start_time: u64,
+    end_time: Option<u64>,
+) -> Result<Vec<FundingHistoryResponse>> {
+    info_client
+        .funding_history(
+            coin,
+            Timestamp::from_millis(start_time),
+            end_time.map(Timestamp::from_millis),
+        )
+        .await
+}
+
+// `InfoClient::candles_snapshot` as it was before it took a `CandleInterval` and `Timestamp`s.
+// Fails on an interval the exchange doesn't offer instead of sending it.
+#[deprecated(
+    since = "0.5.0",
+    note = "use InfoClient::candles_snapshot with a CandleInterval and Timestamps"
+)]
+pub async fn candles_snapshot(
+    info_client: &InfoClient,
+    coin: String,
+    interval: String,
+    start_time: u64,
+    end_time: u64,
+) -> Result<Vec<CandlesSnapshotResponse>> {
+    let interval: CandleInterval = interval.parse()?;
+    info_client
+        .candles_snapshot(
+            coin,
+            interval,
+            Timestamp::from_millis(start_time),
+            Timestamp::from_millis(end_time),
+        )
+        .await
+}
@@ -0,0 +1,197 @@
+use crate::{prelude::*, BaseUrl, Error, KeySource, Subscription};
+use serde::Deserialize;
+use std::{collections::HashSet, path::Path};
+
+// Configuration shared by bots built on the SDK, loaded from a TOML file and overridden by
+// environment variables:
+//
+//     network = "testnet"
+//     coins = ["ETH", "BTC"]
+//
+//     [wallet]
+//     source = "keystore"
+//     path = "/etc/bot/key.json"
+//     password_env = "HL_KEYSTORE_PASSWORD"
+//
+//     [risk]
+//     max_position_usd = 50000.0
+//     max_open_orders = 20
+//
+//     [[subscriptions]]
+//     type = "l2Book"
+//     coin = "ETH"
+//
+// Environment overrides: HL_NETWORK, HL_COINS (comma separated), HL_MAX_POSITION_USD,
+// HL_MAX_ORDER_USD, HL_MAX_OPEN_ORDERS and HL_MAX_LEVERAGE.
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct BotConfig {
+    #[serde(default = "default_network")]
+    pub network: BaseUrl,
+    #[serde(default)]
+    pub wallet: KeySource,
+    #[serde(default)]
+    pub risk: RiskLimits,
+    #[serde(default)]
+    pub coins: Vec<String>,
+    #[serde(default)]
+    pub subscriptions: Vec<Subscription>,
+}
+
+// Limits a bot enforces on itself. Unset limits aren't enforced.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct RiskLimits {
+    pub max_position_usd: Option<f64>,
+    pub max_order_usd: Option<f64>,
+    pub max_open_orders: Option<usize>,
+    pub max_leverage: Option<u32>,
+}
+
+fn default_network() -> BaseUrl {
+    BaseUrl::Mainnet
+}
+
+impl BotConfig {
+    // Reads `path`, applies environment overrides and validates the result.
+    pub fn load(path: impl AsRef<Path>) -> Result<BotConfig> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| Error::Config(format!("reading {}: {e}", path.display())))?;
+        let mut config = BotConfig::from_toml(&contents)?;
+        config.apply_env(|name| std::env::var(name).ok())?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    // Configuration from the environment alone, for deployments without a file.
+    pub fn from_env() -> Result<BotConfig> {
+        let mut config = BotConfig::from_toml("")?;
+        config.apply_env(|name| std::env::var(name).ok())?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn from_toml(contents: &str) -> Result<BotConfig> {
+        toml::from_str(contents).map_err(|e| Error::Config(e.to_string()))
+    }
+
+    pub fn apply_env(&mut self, var: impl Fn(&str) -> Option<String>) -> Result<()> {
+        if let Some(network) = var("HL_NETWORK") {
+            self.network = match network.to_lowercase().as_str() {
+                "mainnet" => BaseUrl::Mainnet,
+                "testnet" => BaseUrl::Testnet,
+                "localhost" => BaseUrl::Localhost,
+                _ => return Err(Error::Config(format!("unknown network {network}"))),
+            };
+        }
+        if let Some(coins) = var("HL_COINS") {
+            self.coins = coins
+                .split(',')
+                .map(str::trim)
+                .filter(|coin| !coin.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+        if let Some(value) = var("HL_MAX_POSITION_USD") {
+            self.risk.max_position_usd = Some(parse_env("HL_MAX_POSITION_USD", &value)?);
+        }
+        if let Some(value) = var("HL_MAX_ORDER_USD") {
+            self.risk.max_order_usd = Some(parse_env("HL_MAX_ORDER_USD", &value)?);
+        }
+        if let Some(value) = var("HL_MAX_OPEN_ORDERS") {
+            self.risk.max_open_orders = Some(parse_env("HL_MAX_OPEN_ORDERS", &value)?);
+        }
+        if let Some(value) = var("HL_MAX_LEVERAGE") {
+            self.risk.max_leverage = Some(parse_env("HL_MAX_LEVERAGE", &value)?);
+        }
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        let mut seen = HashSet::new();
+        for coin in &self.coins {
+            if coin.is_empty() || !seen.insert(coin) {
+                return Err(Error::Config(format!("empty or duplicate coin {coin:?}")));
+            }
+        }
+        for (name, limit) in [
+            ("max_position_usd", self.risk.max_position_usd),
+            ("max_order_usd", self.risk.max_order_usd),
+        ] {
+            if limit.is_some_and(|limit| !limit.is_finite() || limit <= 0.0) {
+                return Err(Error::Config(format!("{name} must be positive")));
+            }
+        }
+        if let (Some(max_order), Some(max_position)) =
+            (self.risk.max_order_usd, self.risk.max_position_usd)
+        {
+            if max_order > max_position {
+                return Err(Error::Config(
+                    "max_order_usd exceeds max_position_usd".to_string(),
+                ));
+            }
+        }
+        if self.risk.max_leverage == Some(0) {
+            return Err(Error::Config("max_leverage must be positive".to_string()));
+        }
+        self.wallet.validate()
+    }
+}
+
+fn parse_env<T: std::str::FromStr>(name: &str, value: &str) -> Result<T> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| Error::Config(format!("invalid {name}: {value}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bot_config() -> Result<()> {
+        let mut config = BotConfig::from_toml(
+            r#"
+            network = "testnet"
+            coins = ["ETH", "BTC"]
+
+            [risk]
+            max_position_usd = 50000.0
+            max_open_orders = 20
+
+            [[subscriptions]]
+            type = "l2Book"
+            coin = "ETH"
+
+            [[subscriptions]]
+            type = "candle"
+            coin = "BTC"
+            interval = "15m"
+            "#,
+        )?;
+        config.validate()?;
+        assert!(matches!(config.network, BaseUrl::Testnet));
+        assert_eq!(config.wallet, KeySource::default());
+        assert_eq!(config.risk.max_open_orders, Some(20));
+        assert_eq!(config.subscriptions.len(), 2);
+
+        config.apply_env(|name| match name {
+            "HL_COINS" => Some("SOL, ETH".to_string()),
+            "HL_MAX_ORDER_USD" => Some("1000".to_string()),
+            _ => None,
+        })?;
+        assert_eq!(config.coins, ["SOL", "ETH"]);
+        assert_eq!(config.risk.max_order_usd, Some(1000.0));
+        config.validate()?;
+
+        config.risk.max_order_usd = Some(100_000.0);
+        assert!(config.validate().is_err());
+        assert!(config
+            .apply_env(|name| (name == "HL_NETWORK").then(|| "devnet".to_string()))
+            .is_err());
+        assert!(BotConfig::from_toml("coins = [\"ETH\"]\nleverage = 3").is_err());
+        Ok(())
+    }
+}
@@ -1,5 +1,9 @@
 pub static MAINNET_API_URL: &str = "https://api.hyperliquid.xyz";
 pub static TESTNET_API_URL: &str = "https://api.hyperliquid-testnet.xyz";
 pub static LOCAL_API_URL: &str = "http://localhost:3001";
+pub static MAINNET_RPC_URL: &str = "https://rpc.hyperliquid.xyz";
+pub static TESTNET_RPC_URL: &str = "https://rpc.hyperliquid-testnet.xyz";
+// Wire subscriptions the exchange allows per IP across all websocket connections
+pub const MAX_WS_SUBSCRIPTIONS: usize = 1000;
 pub const EPSILON: f64 = 1e-9;
 pub(crate) const INF_BPS: u16 = 10_001;
@@ -0,0 +1,322 @@
+use crate::{
+    helpers::round_sz_down, load_snapshot, prelude::*, save_snapshot, ClientLimit, ClientOrder,
+    ClientOrderRequest, Error, ExchangeClient, ExchangeDataStatus, ExchangeResponseStatus,
+    InfoClient, Timestamp,
+};
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::{path::PathBuf, time::Duration};
+use tokio::time;
+
+// How much each run buys or sells.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DcaSize {
+    // In the coin's units
+    Base(f64),
+    // In the quote currency, e.g. USDC, converted at the mid
+    Quote(f64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DcaOrderKind {
+    // An IOC order up to `slippage` (a fraction, e.g. 0.01) through the mid
+    Market { slippage: f64 },
+    // A GTC order `offset` (a fraction) better than the mid, resting until it fills or is
+    // canceled
+    Limit { offset: f64 },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DcaSchedule {
+    // Spot pairs by name, e.g. "PURR/USDC", or index, e.g. "@107"; perps work too
+    pub coin: String,
+    pub is_buy: bool,
+    pub size: DcaSize,
+    pub interval: Duration,
+    pub kind: DcaOrderKind,
+    // Runs are skipped while the mid is outside these bounds
+    pub min_px: Option<f64>,
+    pub max_px: Option<f64>,
+    // Decimals order sizes and prices are rounded to
+    pub sz_decimals: u32,
+    pub px_decimals: u32,
+    // Runs after which the schedule is done, counting skipped ones
+    pub max_runs: Option<u64>,
+}
+
+// Why a run placed no order.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DcaSkip {
+    PriceAboveMax(f64),
+    PriceBelowMin(f64),
+    // The order would round to nothing
+    SizeTooSmall(f64),
+}
+
+// What a schedule has done, persisted between runs.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DcaState {
+    pub next_run: Option<Timestamp>,
+    pub runs: u64,
+    pub skipped: u64,
+    pub failed: u64,
+    // Filled size and its notional, from immediate fills; resting limit orders' later fills
+    // aren't tracked
+    pub filled_sz: f64,
+    pub filled_notional: f64,
+    pub resting_oids: Vec<u64>,
+}
+
+impl DcaState {
+    pub fn average_px(&self) -> Option<f64> {
+        (self.filled_sz > 0.0).then(|| self.filled_notional / self.filled_sz)
+    }
+}
+
+// Places a schedule's orders every `interval`, saving its state after each run so a restart
+// picks up where it left off. Runs missed while stopped aren't made up: an overdue schedule runs
+// once, then keeps to its original cadence.
+pub struct DcaScheduler {
+    pub schedule: DcaSchedule,
+    pub state: DcaState,
+    state_path: Option<PathBuf>,
+}
+
+impl DcaScheduler {
+    pub fn new(schedule: DcaSchedule) -> DcaScheduler {
+        DcaScheduler {
+            schedule,
+            state: DcaState::default(),
+            state_path: None,
+        }
+    }
+
+    // Persists the state at `path`, resuming from it if it exists.
+    pub fn with_state_path(mut self, path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        if let Some(state) = load_snapshot(&path)? {
+            self.state = state;
+        }
+        self.state_path = Some(path);
+        Ok(self)
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.schedule
+            .max_runs
+            .is_some_and(|max_runs| self.state.runs >= max_runs)
+    }
+
+    // When the next run is due; immediately on a fresh schedule.
+    pub fn next_run(&self, now: Timestamp) -> Timestamp {
+        self.state.next_run.unwrap_or(now)
+    }
+
+    // The order a run at `mid` places.
+    pub fn order(&self, mid: f64) -> std::result::Result<ClientOrderRequest, DcaSkip> {
+        let schedule = &self.schedule;
+        if let Some(max_px) = schedule.max_px.filter(|&max_px| mid > max_px) {
+            return Err(DcaSkip::PriceAboveMax(max_px));
+        }
+        if let Some(min_px) = schedule.min_px.filter(|&min_px| mid < min_px) {
+            return Err(DcaSkip::PriceBelowMin(min_px));
+        }
+        let sz = match schedule.size {
+            DcaSize::Base(sz) => sz,
+            DcaSize::Quote(notional) => notional / mid,
+        };
+        let sz = round_sz_down(sz, schedule.sz_decimals);
+        if sz <= 0.0 {
+            return Err(DcaSkip::SizeTooSmall(sz));
+        }
+        let direction = if schedule.is_buy { 1.0 } else { -1.0 };
+        let (limit_px, tif) = match schedule.kind {
+            DcaOrderKind::Market { slippage } => (mid * (1.0 + direction * slippage), "Ioc"),
+            DcaOrderKind::Limit { offset } => (mid * (1.0 - direction * offset), "Gtc"),
+        };
+        let pow10 = 10f64.powi(schedule.px_decimals as i32);
+        Ok(ClientOrderRequest {
+            asset: schedule.coin.clone(),
+            is_buy: schedule.is_buy,
+            reduce_only: false,
+            limit_px: (limit_px * pow10).round() / pow10,
+            sz,
+            cloid: None,
+            order_type: ClientOrder::Limit(ClientLimit {
+                tif: tif.to_string(),
+            }),
+        })
+    }
+
+    // Records a run made at `now` and schedules the next, saving the state.
+    pub fn record(
+        &mut self,
+        now: Timestamp,
+        outcome: std::result::Result<&ExchangeDataStatus, &DcaSkip>,
+    ) -> Result<()> {
+        let state = &mut self.state;
+        state.runs += 1;
+        match outcome {
+            Ok(ExchangeDataStatus::Filled(filled)) => {
+                if let (Ok(sz), Ok(px)) =
+                    (filled.total_sz.parse::<f64>(), filled.avg_px.parse::<f64>())
+                {
+                    state.filled_sz += sz;
+                    state.filled_notional += sz * px;
+                }
+            }
+            Ok(ExchangeDataStatus::Resting(resting)) => state.resting_oids.push(resting.oid),
+            Ok(ExchangeDataStatus::Error(_)) => state.failed += 1,
+            Ok(_) => {}
+            Err(_) => state.skipped += 1,
+        }
+        let mut next_run = state.next_run.unwrap_or(now) + self.schedule.interval;
+        while next_run <= now {
+            next_run += self.schedule.interval;
+        }
+        state.next_run = Some(next_run);
+        match &self.state_path {
+            Some(path) => save_snapshot(path, &self.state),
+            None => Ok(()),
+        }
+    }
+
+    // Runs the schedule until it's done. Errors reaching the exchange count as failed runs;
+    // only failing to save the state stops it.
+    pub async fn run(
+        &mut self,
+        info_client: &InfoClient,
+        exchange_client: &ExchangeClient,
+    ) -> Result<()> {
+        while !self.is_done() {
+            let now = Timestamp::now();
+            let next_run = self.next_run(now);
+            if next_run > now {
+                time::sleep(next_run - now).await;
+            }
+            let now = Timestamp::now();
+            let status = match self.place(info_client, exchange_client).await {
+                Ok(Ok(status)) => status,
+                Ok(Err(skip)) => {
+                    info!("Skipped a {} DCA run: {skip:?}", self.schedule.coin);
+                    self.record(now, Err(&skip))?;
+                    continue;
+                }
+                Err(err) => {
+                    error!("Error placing a {} DCA order: {err}", self.schedule.coin);
+                    ExchangeDataStatus::Error(err.to_string())
+                }
+            };
+            if let ExchangeDataStatus::Error(err) = &status {
+                warn!("{} DCA order failed: {err}", self.schedule.coin);
+            }
+            self.record(now, Ok(&status))?;
+        }
+        Ok(())
+    }
+
+    async fn place(
+        &self,
+        info_client: &InfoClient,
+        exchange_client: &ExchangeClient,
+    ) -> Result<std::result::Result<ExchangeDataStatus, DcaSkip>> {
+        let mids = info_client.all_mids().await?;
+        let mid = mids
+            .get(&self.schedule.coin)
+            .and_then(|mid| mid.parse::<f64>().ok())
+            .ok_or(Error::AssetNotFound)?;
+        let order = match self.order(mid) {
+            Ok(order) => order,
+            Err(skip) => return Ok(Err(skip)),
+        };
+        let status = match exchange_client.order(order, None).await? {
+            ExchangeResponseStatus::Ok(response) => response
+                .data
+                .and_then(|data| data.statuses.into_iter().next())
+                .unwrap_or(ExchangeDataStatus::Success),
+            ExchangeResponseStatus::Err(err) => ExchangeDataStatus::Error(format!("{err:?}")),
+        };
+        Ok(Ok(status))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FilledOrder, RestingOrder};
+
+    fn schedule() -> DcaSchedule {
+        DcaSchedule {
+            coin: "PURR/USDC".to_string(),
+            is_buy: true,
+            size: DcaSize::Quote(100.0),
+            interval: Duration::from_secs(3600),
+            kind: DcaOrderKind::Market { slippage: 0.01 },
+            min_px: None,
+            max_px: Some(0.5),
+            sz_decimals: 0,
+            px_decimals: 4,
+            max_runs: Some(3),
+        }
+    }
+
+    #[test]
+    fn test_schedule_runs_and_resumes() {
+        let path = std::env::temp_dir().join(format!("dca-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut scheduler = DcaScheduler::new(schedule())
+            .with_state_path(&path)
+            .unwrap();
+        let start = Timestamp::from_millis(1_000_000);
+        assert_eq!(scheduler.next_run(start), start);
+
+        // $100 at 0.3 is 333 PURR, bought up to 1% over the mid
+        let order = scheduler.order(0.3).unwrap();
+        assert_eq!((order.sz, order.limit_px), (333.0, 0.303));
+        scheduler
+            .record(
+                start,
+                Ok(&ExchangeDataStatus::Filled(FilledOrder {
+                    total_sz: "333".to_string(),
+                    avg_px: "0.3".to_string(),
+                    oid: 1,
+                })),
+            )
+            .unwrap();
+        assert_eq!(
+            scheduler.order(0.6).err(),
+            Some(DcaSkip::PriceAboveMax(0.5))
+        );
+        let hour = Duration::from_secs(3600);
+        scheduler
+            .record(start + hour, Err(&DcaSkip::PriceAboveMax(0.5)))
+            .unwrap();
+
+        // A restart resumes the state; runs missed while down aren't made up
+        let mut scheduler = DcaScheduler::new(DcaSchedule {
+            kind: DcaOrderKind::Limit { offset: 0.02 },
+            ..schedule()
+        })
+        .with_state_path(&path)
+        .unwrap();
+        assert_eq!(scheduler.state.runs, 2);
+        assert!((scheduler.state.average_px().unwrap() - 0.3).abs() < 1e-12);
+        assert_eq!(scheduler.next_run(start), start + hour * 2);
+        assert_eq!(scheduler.order(0.25).unwrap().limit_px, 0.245);
+        scheduler
+            .record(
+                start + hour * 5 + Duration::from_secs(60),
+                Ok(&ExchangeDataStatus::Resting(RestingOrder { oid: 2 })),
+            )
+            .unwrap();
+        assert_eq!(scheduler.state.next_run, Some(start + hour * 6));
+        assert_eq!(scheduler.state.resting_oids, vec![2]);
+        assert!(scheduler.is_done());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
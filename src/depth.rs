@@ -0,0 +1,150 @@
+use crate::{prelude::*, Error, L2BookData, L2SnapshotResponse};
+
+// What a marketable order can expect to fill against a book, ignoring other takers and
+// hidden liquidity.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DepthFill {
+    pub sz: f64,
+    pub avg_px: f64,
+    // The price of the deepest level reached, usable as the limit price of an IOC order
+    pub worst_px: f64,
+    // Distance of `avg_px` from the best price
+    pub impact_bps: f64,
+}
+
+// The size fillable without reaching levels more than `max_impact_bps` from the best price.
+// `levels` are the (px, sz) levels the order takes, best first: asks for buys, bids for sells.
+// None if that side of the book is empty.
+pub fn size_within_impact(
+    levels: &[(f64, f64)],
+    is_buy: bool,
+    max_impact_bps: f64,
+) -> Option<DepthFill> {
+    let &(best_px, _) = levels.first()?;
+    let offset = best_px * max_impact_bps / 10_000.0;
+    let limit_px = if is_buy {
+        best_px + offset
+    } else {
+        best_px - offset
+    };
+    let within = levels.iter().take_while(|(px, _)| {
+        if is_buy {
+            *px <= limit_px
+        } else {
+            *px >= limit_px
+        }
+    });
+    walk(within.copied(), best_px, f64::INFINITY)
+}
+
+// The expected fill of `sz` against `levels`, given as for `size_within_impact`. None if the
+// book isn't deep enough to fill all of it.
+pub fn fill_for_size(levels: &[(f64, f64)], sz: f64) -> Option<DepthFill> {
+    let &(best_px, _) = levels.first()?;
+    let fill = walk(levels.iter().copied(), best_px, sz)?;
+    (fill.sz >= sz).then_some(fill)
+}
+
+fn walk(levels: impl Iterator<Item = (f64, f64)>, best_px: f64, max_sz: f64) -> Option<DepthFill> {
+    let (mut sz, mut notional, mut worst_px) = (0.0, 0.0, best_px);
+    for (px, level_sz) in levels {
+        if sz >= max_sz {
+            break;
+        }
+        let take = level_sz.min(max_sz - sz);
+        sz += take;
+        notional += take * px;
+        worst_px = px;
+    }
+    if sz <= 0.0 {
+        return None;
+    }
+    let avg_px = notional / sz;
+    Some(DepthFill {
+        sz,
+        avg_px,
+        worst_px,
+        impact_bps: (avg_px - best_px).abs() / best_px * 10_000.0,
+    })
+}
+
+fn parse_levels<'a>(levels: impl Iterator<Item = (&'a str, &'a str)>) -> Result<Vec<(f64, f64)>> {
+    levels
+        .map(|(px, sz)| {
+            Ok((
+                px.parse().map_err(|_| Error::FloatStringParse)?,
+                sz.parse().map_err(|_| Error::FloatStringParse)?,
+            ))
+        })
+        .collect()
+}
+
+impl L2SnapshotResponse {
+    // The levels an order on the given side takes, as input to `size_within_impact` and
+    // `fill_for_size`.
+    pub fn taker_levels(&self, is_buy: bool) -> Result<Vec<(f64, f64)>> {
+        let side = self
+            .levels
+            .get(is_buy as usize)
+            .map_or(&[][..], Vec::as_slice);
+        parse_levels(
+            side.iter()
+                .map(|level| (level.px.as_str(), level.sz.as_str())),
+        )
+    }
+}
+
+impl L2BookData {
+    pub fn taker_levels(&self, is_buy: bool) -> Result<Vec<(f64, f64)>> {
+        let side = self
+            .levels
+            .get(is_buy as usize)
+            .map_or(&[][..], Vec::as_slice);
+        parse_levels(
+            side.iter()
+                .map(|level| (level.px.as_str(), level.sz.as_str())),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BookLevel, Timestamp};
+
+    #[test]
+    fn test_depth_fills() -> Result<()> {
+        let book = L2BookData {
+            coin: "ETH".to_string(),
+            time: Timestamp::from_millis(0),
+            levels: vec![
+                vec![
+                    BookLevel::test_level("1999.0", "1.0"),
+                    BookLevel::test_level("1998.0", "2.0"),
+                ],
+                vec![
+                    BookLevel::test_level("2000.0", "1.0"),
+                    BookLevel::test_level("2001.0", "2.0"),
+                    BookLevel::test_level("2010.0", "5.0"),
+                ],
+            ],
+        };
+        let asks = book.taker_levels(true)?;
+
+        // 10 bps from 2000 reaches 2002, so the 2010 level is out
+        let fill = size_within_impact(&asks, true, 10.0).unwrap();
+        assert_eq!(fill.sz, 3.0);
+        assert_eq!(fill.worst_px, 2001.0);
+        assert!((fill.avg_px - 6002.0 / 3.0).abs() < 1e-9);
+
+        let fill = fill_for_size(&asks, 2.0).unwrap();
+        assert_eq!(fill.avg_px, 2000.5);
+        assert!((fill.impact_bps - 2.5).abs() < 1e-9);
+        assert!(fill_for_size(&asks, 9.0).is_none());
+
+        let bids = book.taker_levels(false)?;
+        assert_eq!(size_within_impact(&bids, false, 1.0).unwrap().sz, 1.0);
+        assert!(size_within_impact(&[], true, 10.0).is_none());
+        Ok(())
+    }
+}
@@ -0,0 +1,276 @@
+use crate::{
+    prelude::*, InfoClient, LedgerUpdateData, Liquidation, Message, OrderUpdate, Subscription,
+    Timestamp, TradeInfo, UserData, UserFunding,
+};
+use ethers::types::H160;
+use serde::Serialize;
+use std::{collections::BTreeMap, time::Duration};
+use tokio::{
+    sync::mpsc::{unbounded_channel, UnboundedReceiver},
+    time,
+};
+
+// How often the merged stream releases events whose reorder window has passed.
+const RELEASE_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase", tag = "type", content = "data")]
+pub enum DropCopyKind {
+    Order(OrderUpdate),
+    Fill(TradeInfo),
+    Funding(UserFunding),
+    // Deposits, withdrawals, transfers and other non-funding ledger updates
+    Transfer(LedgerUpdateData),
+    Liquidation(Liquidation),
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DropCopyEvent {
+    // Increases by one with every event, across all accounts
+    pub seq: u64,
+    pub account: H160,
+    // The exchange's time for the event; liquidations, which carry none, get their arrival time
+    pub time: Timestamp,
+    pub event: DropCopyKind,
+}
+
+// Merges every account's order updates, fills, fundings, ledger updates and liquidations into
+// one stream in time order, numbered with sequence numbers, for surveillance and reconciliation
+// consumers.
+//
+// Each account's channels arrive as separate subscriptions, so events are held for
+// `reorder_window` and then released in (time, arrival) order. An event arriving later than
+// that is released right away, after events with later times; it keeps the next sequence
+// number, so `seq` is always gapless and increasing. Snapshots sent on subscribing are history,
+// not activity, so they're skipped.
+//
+//     let (mut events, subscription_ids) = DropCopy::new(Duration::from_secs(1))
+//         .subscribe(&mut info_client, &[trader, hedger])
+//         .await?;
+//     while let Some(event) = events.recv().await {
+//         log_line(serde_json::to_string(&event)?);
+//     }
+pub struct DropCopy {
+    reorder_window: Duration,
+    next_seq: u64,
+    arrivals: u64,
+    pending: BTreeMap<(Timestamp, u64), (H160, DropCopyKind)>,
+}
+
+impl DropCopy {
+    pub fn new(reorder_window: Duration) -> DropCopy {
+        DropCopy {
+            reorder_window,
+            next_seq: 1,
+            arrivals: 0,
+            pending: BTreeMap::new(),
+        }
+    }
+
+    // Continues numbering from `next_seq`, e.g. after a restart.
+    pub fn starting_seq(mut self, next_seq: u64) -> DropCopy {
+        self.next_seq = next_seq;
+        self
+    }
+
+    // Buffers the events in a message from one of `account`'s subscriptions.
+    pub fn push(&mut self, account: H160, message: &Message, received: Timestamp) {
+        let events: Vec<(Timestamp, DropCopyKind)> = match message {
+            Message::OrderUpdates(updates) => updates
+                .data
+                .iter()
+                .map(|update| (update.status_timestamp, DropCopyKind::Order(update.clone())))
+                .collect(),
+            Message::UserFills(fills) if !fills.data.is_snapshot.unwrap_or(false) => fills
+                .data
+                .fills
+                .iter()
+                .map(|fill| (fill.time, DropCopyKind::Fill(fill.clone())))
+                .collect(),
+            Message::UserFundings(fundings) if !fundings.data.is_snapshot.unwrap_or(false) => {
+                fundings
+                    .data
+                    .fundings
+                    .iter()
+                    .map(|funding| (funding.time, DropCopyKind::Funding(funding.clone())))
+                    .collect()
+            }
+            Message::UserNonFundingLedgerUpdates(updates)
+                if !updates.data.is_snapshot.unwrap_or(false) =>
+            {
+                updates
+                    .data
+                    .non_funding_ledger_updates
+                    .iter()
+                    .map(|update| (update.time, DropCopyKind::Transfer(update.clone())))
+                    .collect()
+            }
+            // Fills and fundings also arrive on userEvents; only liquidations are taken from it
+            Message::User(user) => match &*user.data {
+                UserData::Liquidation(liquidation) => {
+                    vec![(received, DropCopyKind::Liquidation(liquidation.clone()))]
+                }
+                _ => Vec::new(),
+            },
+            _ => Vec::new(),
+        };
+        for (time, event) in events {
+            self.pending.insert((time, self.arrivals), (account, event));
+            self.arrivals += 1;
+        }
+    }
+
+    // Events whose reorder window has passed by `now`, numbered.
+    pub fn release(&mut self, now: Timestamp) -> Vec<DropCopyEvent> {
+        let cutoff = now.saturating_sub(self.reorder_window);
+        let later = self.pending.split_off(&(cutoff, u64::MAX));
+        let ready = std::mem::replace(&mut self.pending, later);
+        self.number(ready)
+    }
+
+    // Every buffered event, numbered.
+    pub fn flush(&mut self) -> Vec<DropCopyEvent> {
+        let ready = std::mem::take(&mut self.pending);
+        self.number(ready)
+    }
+
+    fn number(
+        &mut self,
+        ready: BTreeMap<(Timestamp, u64), (H160, DropCopyKind)>,
+    ) -> Vec<DropCopyEvent> {
+        ready
+            .into_iter()
+            .map(|((time, _), (account, event))| {
+                let seq = self.next_seq;
+                self.next_seq += 1;
+                DropCopyEvent {
+                    seq,
+                    account,
+                    time,
+                    event,
+                }
+            })
+            .collect()
+    }
+
+    // Subscribes to every activity channel of each account and streams the merged events
+    // until the subscriptions are dropped, when buffered events are flushed. Returns the
+    // subscription ids for unsubscribing.
+    pub async fn subscribe(
+        mut self,
+        info_client: &mut InfoClient,
+        accounts: &[H160],
+    ) -> Result<(UnboundedReceiver<DropCopyEvent>, Vec<u32>)> {
+        let (merged_sender, mut merged) = unbounded_channel();
+        let mut subscription_ids = Vec::new();
+        for &user in accounts {
+            let (sender, mut receiver) = unbounded_channel();
+            for subscription in [
+                Subscription::OrderUpdates { user },
+                Subscription::UserFills { user },
+                Subscription::UserFundings { user },
+                Subscription::UserNonFundingLedgerUpdates { user },
+                Subscription::UserEvents { user },
+            ] {
+                subscription_ids.push(info_client.subscribe(subscription, sender.clone()).await?);
+            }
+            let merged_sender = merged_sender.clone();
+            tokio::spawn(async move {
+                while let Some(message) = receiver.recv().await {
+                    if merged_sender.send((user, message)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(merged_sender);
+
+        let (sender, receiver) = unbounded_channel();
+        tokio::spawn(async move {
+            let mut ticker = time::interval(RELEASE_INTERVAL);
+            loop {
+                tokio::select! {
+                    received = merged.recv() => match received {
+                        Some((account, message)) => {
+                            self.push(account, &message, Timestamp::now());
+                        }
+                        None => {
+                            for event in self.flush() {
+                                let _ = sender.send(event);
+                            }
+                            return;
+                        }
+                    },
+                    _ = ticker.tick() => {
+                        for event in self.release(Timestamp::now()) {
+                            if sender.send(event).is_err() {
+                                return;
+                            }
+                        }
+                    }
+                }
+            }
+        });
+        Ok((receiver, subscription_ids))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_drop_copy_ordering() {
+        let trader = H160::from_low_u64_be(1);
+        let hedger = H160::from_low_u64_be(2);
+        let fill = |time: u64, tid: u64, is_snapshot: bool| -> Message {
+            serde_json::from_value(json!({
+                "channel": "userFills",
+                "data": {"isSnapshot": is_snapshot, "user": trader, "fills": [{
+                    "coin": "ETH", "side": "B", "px": "2000", "sz": "1", "time": time,
+                    "hash": "0x00", "startPosition": "0", "dir": "Open Long", "closedPnl": "0",
+                    "oid": 7, "crossed": true, "fee": "0", "tid": tid
+                }]}
+            }))
+            .unwrap()
+        };
+        let order: Message = serde_json::from_value(json!({
+            "channel": "orderUpdates",
+            "data": [{
+                "order": {"coin": "BTC", "side": "A", "limitPx": "60000", "sz": "1", "oid": 9,
+                          "timestamp": 1_000, "origSz": "1"},
+                "status": "open",
+                "statusTimestamp": 1_500
+            }]
+        }))
+        .unwrap();
+
+        let mut drop_copy = DropCopy::new(Duration::from_secs(1));
+        drop_copy.push(trader, &fill(500, 1, true), Timestamp::from_millis(2_000));
+        drop_copy.push(
+            trader,
+            &fill(2_000, 2, false),
+            Timestamp::from_millis(2_000),
+        );
+        drop_copy.push(hedger, &order, Timestamp::from_millis(2_000));
+        drop_copy.push(
+            trader,
+            &fill(1_200, 3, false),
+            Timestamp::from_millis(2_000),
+        );
+
+        let events = drop_copy.release(Timestamp::from_millis(2_600));
+        let released: Vec<_> = events
+            .iter()
+            .map(|event| (event.seq, event.account, event.time.as_millis()))
+            .collect();
+        assert_eq!(released, vec![(1, trader, 1_200), (2, hedger, 1_500)]);
+
+        let events = drop_copy.flush();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].seq, 3);
+        assert!(matches!(&events[0].event, DropCopyKind::Fill(fill) if fill.tid == 2));
+    }
+}
@@ -1,7 +1,9 @@
+use crate::{OrderState, WsErrorKind};
 use reqwest::header::HeaderMap;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum Error {
     // TODO: turn some embedded types into errors instead of strings
     #[error("Client error: status code: {status_code}, error code: {error_code:?}, error message: {error_message}, headers: {headers:?}, error data: {error_data:?}")]
@@ -9,7 +11,8 @@ pub enum Error {
         status_code: u16,
         error_code: Option<u16>,
         error_message: String,
-        headers: HeaderMap,
+        // Boxed to keep every `Result` small
+        headers: Box<HeaderMap>,
         error_data: Option<String>,
     },
     #[error("Server error: status code: {status_code}, error message: {error_message}")]
@@ -35,6 +38,16 @@ pub enum Error {
     Websocket(String),
     #[error("Subscription not found")]
     SubscriptionNotFound,
+    #[error("Subscription rejected with {active}/{limit} active subscriptions: {reason}")]
+    SubscriptionRejected {
+        reason: String,
+        active: usize,
+        limit: usize,
+    },
+    #[error("Websocket closed by the exchange with code {code}: {reason}")]
+    WsClosed { code: u16, reason: String },
+    #[error("Websocket error from the exchange ({kind:?}): {message}")]
+    WsServer { kind: WsErrorKind, message: String },
     #[error("WS manager not instantiated")]
     WsManagerNotFound,
     #[error("WS send error: {0:?}")]
@@ -51,8 +64,6 @@ pub enum Error {
     RandGen(String),
     #[error("Private key parse error: {0:?}")]
     PrivateKeyParse(String),
-    #[error("Cannot subscribe to multiple user events")]
-    UserEvents,
     #[error("Rmp parse error: {0:?}")]
     RmpParse(String),
     #[error("Invalid input number")]
@@ -61,4 +72,63 @@ pub enum Error {
     NoCloid,
     #[error("ECDSA signature failed: {0:?}")]
     SignatureFailure(String),
+    #[error("EVM RPC error: {0:?}")]
+    EvmRpc(String),
+    #[error("Token {0} is not linked to HyperEVM")]
+    TokenNotLinkedToEvm(String),
+    #[error("Invalid amount: {0:?}")]
+    InvalidAmount(String),
+    #[error("Export error: {0:?}")]
+    Export(String),
+    #[error("History archive error: {0:?}")]
+    History(String),
+    #[error("Agent approval rejected: {0:?}")]
+    AgentApproval(String),
+    #[error("Config error: {0:?}")]
+    Config(String),
+    #[error("Signal handler error: {0:?}")]
+    Signal(String),
+    #[error("Multi-sig error: {0:?}")]
+    MultiSig(String),
+    #[error("Order {0} still unresolved after canceling")]
+    OrderUnresolved(String),
+    #[error("Snapshot error: {0:?}")]
+    Snapshot(String),
+    #[error("SQLite error: {0:?}")]
+    Sqlite(String),
+    #[error("Postgres error: {0:?}")]
+    Postgres(String),
+    #[error("Bridge error: {0:?}")]
+    Bridge(String),
+    #[error("Redis error: {0:?}")]
+    Redis(String),
+    #[error("gRPC error: {0:?}")]
+    Grpc(String),
+    #[error("Proxy error: {0:?}")]
+    Proxy(String),
+    #[error("Webhook error: {0:?}")]
+    Webhook(String),
+    #[error("FIX error: {0:?}")]
+    Fix(String),
+    #[error("Testnet error: {0:?}")]
+    Testnet(String),
+    #[error("Runtime error: {0:?}")]
+    Runtime(String),
+    #[error("Lease error: {0:?}")]
+    Lease(String),
+    #[error("Unsupported candle interval {0:?}, expected one of 1m, 3m, 5m, 15m, 30m, 1h, 2h, 4h, 8h, 12h, 1d, 3d, 1w, 1M")]
+    InvalidCandleInterval(String),
+    #[error("Illegal order transition from {from:?} to {to:?}")]
+    IllegalOrderTransition { from: OrderState, to: OrderState },
+    #[error("Order {oid} overfilled: filled {filled_sz} of {orig_sz}")]
+    OrderOverfill {
+        oid: u64,
+        filled_sz: f64,
+        orig_sz: f64,
+    },
+    #[error("Schema drift decoding {type_name}: unknown fields {}", fields.join(", "))]
+    SchemaDrift {
+        type_name: &'static str,
+        fields: Vec<String>,
+    },
 }
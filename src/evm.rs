@@ -0,0 +1,208 @@
+use crate::{prelude::*, BaseUrl, Error, TokenInfo};
+use ethers::{
+    abi::{decode, encode, ParamType, Token},
+    middleware::SignerMiddleware,
+    providers::{Http, Middleware, Provider},
+    signers::{LocalWallet, Signer},
+    types::{transaction::eip2718::TypedTransaction, Bytes, TransactionRequest, H160, H256, U256},
+    utils::keccak256,
+};
+
+pub const MAINNET_EVM_CHAIN_ID: u64 = 999;
+pub const TESTNET_EVM_CHAIN_ID: u64 = 998;
+
+// Sending HYPE to this address on the EVM credits it to the sender's spot balance.
+pub const HYPE_SYSTEM_ADDRESS: H160 = H160([0x22; 20]);
+
+// Every spot token linked to the EVM has a system address of the form 0x20 followed by the
+// token index encoded big-endian, e.g. 0x2000000000000000000000000000000000000001 for index 1.
+pub fn token_system_address(token_index: u64) -> H160 {
+    let mut address = [0u8; 20];
+    address[0] = 0x20;
+    address[12..].copy_from_slice(&token_index.to_be_bytes());
+    H160(address)
+}
+
+pub(crate) const HYPE_TOKEN_NAME: &str = "HYPE";
+const HYPE_EVM_DECIMALS: u32 = 18;
+
+// Converts a decimal string into integer units, rejecting amounts with more precision than
+// the token supports instead of silently truncating them.
+pub fn parse_token_amount(amount: &str, decimals: u32) -> Result<U256> {
+    let invalid = || Error::InvalidAmount(amount.to_string());
+    let (whole, fraction) = amount.split_once('.').unwrap_or((amount, ""));
+    if whole.is_empty() && fraction.is_empty()
+        || !whole
+            .chars()
+            .chain(fraction.chars())
+            .all(|c| c.is_ascii_digit())
+    {
+        return Err(invalid());
+    }
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.len() > decimals as usize {
+        return Err(Error::InvalidAmount(format!(
+            "{amount} has more than {decimals} decimals"
+        )));
+    }
+    let digits = format!("{whole}{fraction:0<width$}", width = decimals as usize);
+    U256::from_dec_str(&digits).map_err(|_| invalid())
+}
+
+pub fn evm_decimals(token: &TokenInfo) -> Result<u32> {
+    if token.name == HYPE_TOKEN_NAME {
+        return Ok(HYPE_EVM_DECIMALS);
+    }
+    let evm_contract = token
+        .evm_contract
+        .as_ref()
+        .ok_or_else(|| Error::TokenNotLinkedToEvm(token.name.clone()))?;
+    u32::try_from(token.wei_decimals as i32 + evm_contract.evm_extra_wei_decimals as i32)
+        .map_err(|e| Error::InvalidAmount(e.to_string()))
+}
+
+pub fn spot_system_address(token: &TokenInfo) -> H160 {
+    if token.name == HYPE_TOKEN_NAME {
+        HYPE_SYSTEM_ADDRESS
+    } else {
+        token_system_address(token.index as u64)
+    }
+}
+
+pub struct EvmClient {
+    pub provider: Provider<Http>,
+    pub chain_id: u64,
+}
+
+impl EvmClient {
+    pub async fn new(base_url: Option<BaseUrl>) -> Result<EvmClient> {
+        let base_url = base_url.unwrap_or(BaseUrl::Mainnet);
+        let chain_id = match base_url {
+            BaseUrl::Mainnet => MAINNET_EVM_CHAIN_ID,
+            BaseUrl::Testnet | BaseUrl::Localhost => TESTNET_EVM_CHAIN_ID,
+        };
+        let provider = Provider::<Http>::try_from(format!("{}/evm", base_url.get_rpc_url()))
+            .map_err(|e| Error::EvmRpc(e.to_string()))?;
+
+        Ok(EvmClient { provider, chain_id })
+    }
+
+    pub async fn hype_balance(&self, address: H160) -> Result<U256> {
+        self.provider
+            .get_balance(address, None)
+            .await
+            .map_err(|e| Error::EvmRpc(e.to_string()))
+    }
+
+    pub async fn erc20_balance(&self, token: H160, owner: H160) -> Result<U256> {
+        let output = self
+            .call(token, "balanceOf(address)", &[Token::Address(owner)])
+            .await?;
+        Self::decode_uint(&output)
+    }
+
+    pub async fn erc20_decimals(&self, token: H160) -> Result<u8> {
+        let output = self.call(token, "decimals()", &[]).await?;
+        let decimals = Self::decode_uint(&output)?;
+        if decimals > U256::from(u8::MAX) {
+            return Err(Error::EvmRpc(format!("invalid decimals {decimals}")));
+        }
+        Ok(decimals.as_u32() as u8)
+    }
+
+    // Moves `amount` of `token` from the wallet's EVM balance to its spot balance by sending it
+    // to the token's system address. Returns the EVM transaction hash.
+    pub async fn transfer_to_spot(
+        &self,
+        wallet: &LocalWallet,
+        token: &TokenInfo,
+        amount: &str,
+    ) -> Result<H256> {
+        let value = parse_token_amount(amount, evm_decimals(token)?)?;
+        let system_address = spot_system_address(token);
+
+        let tx = if token.name == HYPE_TOKEN_NAME {
+            TransactionRequest::new().to(system_address).value(value)
+        } else {
+            let evm_contract = token
+                .evm_contract
+                .as_ref()
+                .ok_or_else(|| Error::TokenNotLinkedToEvm(token.name.clone()))?;
+            let mut data = keccak256("transfer(address,uint256)")[..4].to_vec();
+            data.extend(encode(&[
+                Token::Address(system_address),
+                Token::Uint(value),
+            ]));
+            TransactionRequest::new()
+                .to(evm_contract.address)
+                .data(data)
+        };
+
+        let client = SignerMiddleware::new(
+            self.provider.clone(),
+            wallet.clone().with_chain_id(self.chain_id),
+        );
+        let pending_tx = client
+            .send_transaction(tx, None)
+            .await
+            .map_err(|e| Error::EvmRpc(e.to_string()))?;
+        Ok(pending_tx.tx_hash())
+    }
+
+    async fn call(&self, to: H160, signature: &str, args: &[Token]) -> Result<Bytes> {
+        let mut data = keccak256(signature)[..4].to_vec();
+        data.extend(encode(args));
+        let tx: TypedTransaction = TransactionRequest::new().to(to).data(data).into();
+        self.provider
+            .call(&tx, None)
+            .await
+            .map_err(|e| Error::EvmRpc(e.to_string()))
+    }
+
+    fn decode_uint(output: &[u8]) -> Result<U256> {
+        decode(&[ParamType::Uint(256)], output)
+            .map_err(|e| Error::EvmRpc(e.to_string()))?
+            .pop()
+            .and_then(Token::into_uint)
+            .ok_or_else(|| Error::EvmRpc("unexpected return data".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_token_system_address() {
+        assert_eq!(
+            token_system_address(0),
+            H160::from_str("0x2000000000000000000000000000000000000000").unwrap()
+        );
+        assert_eq!(
+            token_system_address(200),
+            H160::from_str("0x20000000000000000000000000000000000000c8").unwrap()
+        );
+        assert_eq!(
+            HYPE_SYSTEM_ADDRESS,
+            H160::from_str("0x2222222222222222222222222222222222222222").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_token_amount() -> Result<()> {
+        assert_eq!(parse_token_amount("1", 8)?, U256::from(100_000_000u64));
+        assert_eq!(parse_token_amount("0.5", 2)?, U256::from(50u64));
+        assert_eq!(parse_token_amount("12.340", 2)?, U256::from(1234u64));
+        assert_eq!(parse_token_amount(".25", 2)?, U256::from(25u64));
+        assert_eq!(
+            parse_token_amount("1.5", 18)?,
+            U256::from(1_500_000_000_000_000_000u128)
+        );
+        assert!(parse_token_amount("0.001", 2).is_err());
+        assert!(parse_token_amount("-1", 2).is_err());
+        assert!(parse_token_amount("1e5", 2).is_err());
+        assert!(parse_token_amount(".", 2).is_err());
+        Ok(())
+    }
+}
@@ -1,4 +1,7 @@
-use crate::exchange::{cancel::CancelRequest, order::OrderRequest};
+use crate::{
+    exchange::{cancel::CancelRequest, order::OrderRequest},
+    Actions, Timestamp,
+};
 pub(crate) use ethers::{
     abi::{encode, ParamType, Tokenizable},
     types::{
@@ -6,7 +9,7 @@ pub(crate) use ethers::{
             eip712,
             eip712::{encode_eip712_type, EIP712Domain, Eip712, Eip712Error},
         },
-        H160, U256,
+        Signature, H160, H256, U256,
     },
     utils::keccak256,
 };
@@ -113,6 +116,117 @@ pub struct BulkCancelCloid {
     pub cancels: Vec<CancelRequestCloid>,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenSpec {
+    pub name: String,
+    pub sz_decimals: u32,
+    pub wei_decimals: u32,
+}
+
+// The steps of deploying a spot token, in the order they are performed. Token and spot
+// indices are the ones assigned by `RegisterToken2` and `RegisterSpot` respectively.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub enum SpotDeploy {
+    #[serde(rename_all = "camelCase")]
+    RegisterToken2 {
+        spec: TokenSpec,
+        // Maximum gas, in HYPE wei, the deployer is willing to pay in the deploy auction
+        max_gas: u64,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        full_name: Option<String>,
+    },
+    #[serde(rename_all = "camelCase")]
+    UserGenesis {
+        token: u32,
+        user_and_wei: Vec<(H160, String)>,
+        existing_token_and_wei: Vec<(u32, String)>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Genesis {
+        token: u32,
+        max_supply: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        no_hyperliquidity: Option<bool>,
+    },
+    RegisterSpot {
+        // Base and quote token indices
+        tokens: [u32; 2],
+    },
+    #[serde(rename_all = "camelCase")]
+    RegisterHyperliquidity {
+        spot: u32,
+        start_px: String,
+        order_sz: String,
+        n_orders: u32,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        n_seeded_levels: Option<u32>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PerpAssetRequest {
+    pub coin: String,
+    pub sz_decimals: u32,
+    pub oracle_px: String,
+    pub margin_table_id: u32,
+    pub only_isolated: bool,
+}
+
+// Only given when registering the first asset of a new dex.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PerpDexSchema {
+    pub full_name: String,
+    pub collateral_token: u32,
+    pub oracle_updater: Option<H160>,
+}
+
+// Actions for builder-deployed (HIP-3) perp dexs. Prices are sorted by coin, as the
+// exchange expects.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub enum PerpDeploy {
+    #[serde(rename_all = "camelCase")]
+    RegisterAsset {
+        max_gas: Option<u64>,
+        asset_request: PerpAssetRequest,
+        dex: String,
+        schema: Option<PerpDexSchema>,
+    },
+    #[serde(rename_all = "camelCase")]
+    SetOracle {
+        dex: String,
+        oracle_pxs: Vec<(String, String)>,
+        mark_pxs: Vec<Vec<(String, String)>>,
+        external_perp_pxs: Vec<(String, String)>,
+    },
+}
+
+// Big blocks trade a higher gas limit for a slower block rate on HyperEVM; they are needed
+// to deploy large contracts.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct EvmUserModify {
+    pub using_big_blocks: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ReserveRequestWeight {
+    pub weight: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleCancel {
+    // Omitted to remove a previously scheduled cancel
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time: Option<Timestamp>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct ApproveAgent {
@@ -208,3 +322,172 @@ impl Eip712 for Withdraw3 {
         Ok(keccak256(encode(&items)))
     }
 }
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SpotSend {
+    pub signature_chain_id: U256,
+    pub hyperliquid_chain: String,
+    pub destination: String,
+    pub token: String,
+    pub amount: String,
+    pub time: u64,
+}
+
+impl Eip712 for SpotSend {
+    type Error = Eip712Error;
+
+    fn domain(&self) -> Result<EIP712Domain, Self::Error> {
+        Ok(eip_712_domain(self.signature_chain_id))
+    }
+
+    fn type_hash() -> Result<[u8; 32], Self::Error> {
+        Ok(eip712::make_type_hash(
+            format!("{HYPERLIQUID_EIP_PREFIX}SpotSend"),
+            &[
+                ("hyperliquidChain".to_string(), ParamType::String),
+                ("destination".to_string(), ParamType::String),
+                ("token".to_string(), ParamType::String),
+                ("amount".to_string(), ParamType::String),
+                ("time".to_string(), ParamType::Uint(64)),
+            ],
+        ))
+    }
+
+    fn struct_hash(&self) -> Result<[u8; 32], Self::Error> {
+        let Self {
+            signature_chain_id: _,
+            hyperliquid_chain,
+            destination,
+            token,
+            amount,
+            time,
+        } = self;
+        let items = vec![
+            ethers::abi::Token::Uint(Self::type_hash()?.into()),
+            encode_eip712_type(hyperliquid_chain.clone().into_token()),
+            encode_eip712_type(destination.clone().into_token()),
+            encode_eip712_type(token.clone().into_token()),
+            encode_eip712_type(amount.clone().into_token()),
+            encode_eip712_type(time.into_token()),
+        ];
+        Ok(keccak256(encode(&items)))
+    }
+}
+
+// The accounts allowed to sign for a multi-sig user, and how many of them must sign each
+// action.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct MultiSigSigners {
+    pub authorized_users: Vec<H160>,
+    pub threshold: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ConvertToMultiSigUser {
+    pub signature_chain_id: U256,
+    pub hyperliquid_chain: String,
+    // `MultiSigSigners` as JSON, with the authorized users sorted
+    pub signers: String,
+    pub nonce: u64,
+}
+
+impl Eip712 for ConvertToMultiSigUser {
+    type Error = Eip712Error;
+
+    fn domain(&self) -> Result<EIP712Domain, Self::Error> {
+        Ok(eip_712_domain(self.signature_chain_id))
+    }
+
+    fn type_hash() -> Result<[u8; 32], Self::Error> {
+        Ok(eip712::make_type_hash(
+            format!("{HYPERLIQUID_EIP_PREFIX}ConvertToMultiSigUser"),
+            &[
+                ("hyperliquidChain".to_string(), ParamType::String),
+                ("signers".to_string(), ParamType::String),
+                ("nonce".to_string(), ParamType::Uint(64)),
+            ],
+        ))
+    }
+
+    fn struct_hash(&self) -> Result<[u8; 32], Self::Error> {
+        let Self {
+            signature_chain_id: _,
+            hyperliquid_chain,
+            signers,
+            nonce,
+        } = self;
+        let items = vec![
+            ethers::abi::Token::Uint(Self::type_hash()?.into()),
+            encode_eip712_type(hyperliquid_chain.clone().into_token()),
+            encode_eip712_type(signers.clone().into_token()),
+            encode_eip712_type(nonce.into_token()),
+        ];
+        Ok(keccak256(encode(&items)))
+    }
+}
+
+// An action of a multi-sig user, carrying the signatures of its authorized users and
+// submitted by one of them, the outer signer.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MultiSig {
+    pub signature_chain_id: U256,
+    pub signatures: Vec<Signature>,
+    pub payload: MultiSigPayload,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MultiSigPayload {
+    pub multi_sig_user: H160,
+    pub outer_signer: H160,
+    pub action: Box<Actions>,
+}
+
+// What the outer signer signs: the hash of the `MultiSig` action, signatures included.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SendMultiSig {
+    pub signature_chain_id: U256,
+    pub hyperliquid_chain: String,
+    pub multi_sig_action_hash: H256,
+    pub nonce: u64,
+}
+
+impl Eip712 for SendMultiSig {
+    type Error = Eip712Error;
+
+    fn domain(&self) -> Result<EIP712Domain, Self::Error> {
+        Ok(eip_712_domain(self.signature_chain_id))
+    }
+
+    fn type_hash() -> Result<[u8; 32], Self::Error> {
+        Ok(eip712::make_type_hash(
+            format!("{HYPERLIQUID_EIP_PREFIX}SendMultiSig"),
+            &[
+                ("hyperliquidChain".to_string(), ParamType::String),
+                ("multiSigActionHash".to_string(), ParamType::FixedBytes(32)),
+                ("nonce".to_string(), ParamType::Uint(64)),
+            ],
+        ))
+    }
+
+    fn struct_hash(&self) -> Result<[u8; 32], Self::Error> {
+        let Self {
+            signature_chain_id: _,
+            hyperliquid_chain,
+            multi_sig_action_hash,
+            nonce,
+        } = self;
+        let items = vec![
+            ethers::abi::Token::Uint(Self::type_hash()?.into()),
+            encode_eip712_type(hyperliquid_chain.clone().into_token()),
+            encode_eip712_type(multi_sig_action_hash.into_token()),
+            encode_eip712_type(nonce.into_token()),
+        ];
+        Ok(keccak256(encode(&items)))
+    }
+}
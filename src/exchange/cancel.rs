@@ -1,6 +1,8 @@
+use crate::{prelude::*, ExchangeDataStatus, ExchangeResponseStatus};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+#[derive(Debug, Clone)]
 pub struct ClientCancelRequest {
     pub asset: String,
     pub oid: u64,
@@ -24,3 +26,116 @@ pub struct CancelRequestCloid {
     pub asset: u32,
     pub cloid: String,
 }
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CoinFilter {
+    Only(Vec<String>),
+    Except(Vec<String>),
+}
+
+impl CoinFilter {
+    pub fn matches(&self, coin: &str) -> bool {
+        match self {
+            CoinFilter::Only(coins) => coins.iter().any(|only| only == coin),
+            CoinFilter::Except(coins) => !coins.iter().any(|except| except == coin),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CancelAllSummary {
+    pub canceled: Vec<u64>,
+    // Orders that filled or were canceled before the cancel arrived
+    pub already_gone: Vec<u64>,
+    // Orders still failing once retries ran out, with the last error
+    pub failed: Vec<(u64, String)>,
+}
+
+impl CancelAllSummary {
+    // Records the outcome of one batch and returns the cancels worth retrying. Failures of the
+    // whole request are retried; per-order errors other than "already gone" are kept in
+    // `failed` until a retry succeeds.
+    pub(crate) fn record(
+        &mut self,
+        cancels: Vec<ClientCancelRequest>,
+        result: Result<ExchangeResponseStatus>,
+    ) -> Vec<ClientCancelRequest> {
+        let statuses = match result {
+            Ok(ExchangeResponseStatus::Ok(response)) => {
+                response.data.map(|data| data.statuses).unwrap_or_default()
+            }
+            Ok(ExchangeResponseStatus::Err(err)) => return self.fail(cancels, &err.message),
+            Err(err) => return self.fail(cancels, &err.to_string()),
+        };
+        if statuses.len() != cancels.len() {
+            return self.fail(cancels, "unexpected number of statuses");
+        }
+        let mut retry = Vec::new();
+        for (cancel, status) in cancels.into_iter().zip(statuses) {
+            self.failed.retain(|(oid, _)| *oid != cancel.oid);
+            match status {
+                ExchangeDataStatus::Error(err) if err.contains("never placed") => {
+                    self.already_gone.push(cancel.oid)
+                }
+                ExchangeDataStatus::Error(err) => {
+                    self.failed.push((cancel.oid, err));
+                    retry.push(cancel);
+                }
+                _ => self.canceled.push(cancel.oid),
+            }
+        }
+        retry
+    }
+
+    fn fail(&mut self, cancels: Vec<ClientCancelRequest>, err: &str) -> Vec<ClientCancelRequest> {
+        for cancel in &cancels {
+            self.failed.retain(|(oid, _)| *oid != cancel.oid);
+            self.failed.push((cancel.oid, err.to_string()));
+        }
+        cancels
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Error, ExchangeDataStatuses, ExchangeResponse};
+
+    fn cancel(oid: u64) -> ClientCancelRequest {
+        ClientCancelRequest {
+            asset: "ETH".to_string(),
+            oid,
+        }
+    }
+
+    #[test]
+    fn test_cancel_all_summary() {
+        assert!(CoinFilter::Only(vec!["ETH".to_string()]).matches("ETH"));
+        assert!(!CoinFilter::Except(vec!["ETH".to_string()]).matches("ETH"));
+
+        let mut summary = CancelAllSummary::default();
+        let retry = summary.record(
+            vec![cancel(1), cancel(2)],
+            Err(Error::GenericRequest("timeout".to_string())),
+        );
+        assert_eq!(retry.len(), 2);
+        assert_eq!(summary.failed.len(), 2);
+
+        let response = ExchangeResponseStatus::Ok(ExchangeResponse {
+            response_type: "cancel".to_string(),
+            data: Some(ExchangeDataStatuses {
+                statuses: vec![
+                    ExchangeDataStatus::Success,
+                    ExchangeDataStatus::Error(
+                        "Order was never placed, already canceled, or filled.".to_string(),
+                    ),
+                ],
+            }),
+            meta: Default::default(),
+        });
+        assert!(summary.record(retry, Ok(response)).is_empty());
+        assert_eq!(summary.canceled, vec![1]);
+        assert_eq!(summary.already_gone, vec![2]);
+        assert!(summary.failed.is_empty());
+    }
+}
@@ -1,14 +1,18 @@
 use crate::meta::SpotMeta;
 use crate::signature::sign_typed_data;
-use crate::Withdraw3;
 use crate::{
     consts::MAINNET_API_URL,
+    evm::{evm_decimals, parse_token_amount, spot_system_address},
     exchange::{
         actions::{
-            ApproveAgent, BulkCancel, BulkOrder, UpdateIsolatedMargin, UpdateLeverage, UsdSend,
+            ApproveAgent, BulkCancel, BulkOrder, ConvertToMultiSigUser, EvmUserModify, MultiSig,
+            MultiSigSigners, PerpAssetRequest, PerpDeploy, PerpDexSchema, ReserveRequestWeight,
+            ScheduleCancel, SendMultiSig, SpotDeploy, TokenSpec, UpdateIsolatedMargin,
+            UpdateLeverage, UsdSend,
         },
         cancel::{CancelRequest, CancelRequestCloid},
-        ClientCancelRequest, ClientOrderRequest,
+        CancelAllSummary, ClientCancelRequest, ClientOrderRequest, CoinFilter, RepricePolicy,
+        SubmitPolicy,
     },
     helpers::{generate_random_key, next_nonce, uuid_to_hex_string},
     info::info_client::InfoClient,
@@ -16,35 +20,66 @@ use crate::{
     prelude::*,
     req::HttpClient,
     signature::sign_l1_action,
-    BaseUrl, BulkCancelCloid, Error, ExchangeResponseStatus,
+    ws::Connector,
+    BaseUrl, BulkCancelCloid, Error, ExchangeDataStatus, ExchangeResponseStatus, LatencyRecorder,
+    MultiSigCoordinator, NoncePartition, OrderId, OrderStatusResponse, ResponseMeta, SubmitOutcome,
+    Timestamp, UserRoleResponse,
 };
+#[cfg(feature = "validator")]
+use crate::{CSignerAction, CValidatorAction, ValidatorProfile, ValidatorProfileChange};
+use crate::{SpotSend, Withdraw3};
+use chrono::{DateTime, Utc};
 use ethers::{
     abi::AbiEncode,
     signers::{LocalWallet, Signer},
     types::{Signature, H160, H256},
 };
 use log::debug;
-use reqwest::Client;
+use reqwest::{header::HeaderMap, Client};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use super::cancel::ClientCancelRequestCloid;
 
+pub const MAX_CANCEL_BATCH: usize = 1000;
+const CANCEL_ALL_RETRIES: u32 = 3;
+
 pub struct ExchangeClient {
     pub http_client: HttpClient,
     pub wallet: LocalWallet,
     pub meta: Meta,
     pub vault_address: Option<H160>,
     pub coin_to_asset: HashMap<String, u32>,
+    pub latency: Arc<LatencyRecorder>,
+    // When set, orders that only close part of this account's positions are sent reduce-only,
+    // at the cost of one user state query per order batch.
+    pub reduce_only_closes: Option<H160>,
+    // The nonces this client signs with; see `Failover::nonce_partition`
+    pub nonce_partition: NoncePartition,
 }
 
-#[derive(Serialize, Deserialize)]
+// A signed action, ready to submit through any transport: serialized, it's both the body of
+// a request to /exchange and the payload of a websocket post request.
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
-struct ExchangePayload {
-    action: serde_json::Value,
-    signature: Signature,
-    nonce: u64,
-    vault_address: Option<H160>,
+pub struct SignedAction {
+    pub action: serde_json::Value,
+    pub signature: Signature,
+    pub nonce: u64,
+    pub vault_address: Option<H160>,
+    // The exchange rejects the action if it arrives after this time, in milliseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_after: Option<u64>,
+}
+
+impl SignedAction {
+    pub fn body(&self) -> Result<String> {
+        serde_json::to_string(self).map_err(|e| Error::JsonParse(e.to_string()))
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -59,20 +94,64 @@ pub enum Actions {
     CancelByCloid(BulkCancelCloid),
     ApproveAgent(ApproveAgent),
     Withdraw3(Withdraw3),
+    SpotSend(SpotSend),
+    SpotDeploy(SpotDeploy),
+    PerpDeploy(PerpDeploy),
+    EvmUserModify(EvmUserModify),
+    ReserveRequestWeight(ReserveRequestWeight),
+    Noop,
+    ScheduleCancel(ScheduleCancel),
+    ConvertToMultiSigUser(ConvertToMultiSigUser),
+    MultiSig(MultiSig),
+    #[cfg(feature = "validator")]
+    #[serde(rename = "CSignerAction")]
+    CSignerAction(CSignerAction),
+    #[cfg(feature = "validator")]
+    #[serde(rename = "CValidatorAction")]
+    CValidatorAction(CValidatorAction),
 }
 
 impl Actions {
     fn hash(&self, timestamp: u64, vault_address: Option<H160>) -> Result<H256> {
-        let mut bytes =
-            rmp_serde::to_vec_named(self).map_err(|e| Error::RmpParse(e.to_string()))?;
-        bytes.extend(timestamp.to_be_bytes());
-        if let Some(vault_address) = vault_address {
-            bytes.push(1);
-            bytes.extend(vault_address.to_fixed_bytes());
-        } else {
-            bytes.push(0);
-        }
-        Ok(H256(ethers::utils::keccak256(bytes)))
+        action_hash(self, timestamp, vault_address, None)
+    }
+}
+
+// The connection id L1 actions are signed over. Multi-sig signers hash an envelope around the
+// action rather than the action itself.
+pub(crate) fn action_hash<T: Serialize>(
+    action: &T,
+    timestamp: u64,
+    vault_address: Option<H160>,
+    expires_after: Option<u64>,
+) -> Result<H256> {
+    let mut bytes = rmp_serde::to_vec_named(action).map_err(|e| Error::RmpParse(e.to_string()))?;
+    bytes.extend(timestamp.to_be_bytes());
+    if let Some(vault_address) = vault_address {
+        bytes.push(1);
+        bytes.extend(vault_address.to_fixed_bytes());
+    } else {
+        bytes.push(0);
+    }
+    if let Some(expires_after) = expires_after {
+        bytes.push(0);
+        bytes.extend(expires_after.to_be_bytes());
+    }
+    Ok(H256(ethers::utils::keccak256(bytes)))
+}
+
+fn response_meta(nonce: u64, sent_at: Timestamp, headers: &HeaderMap) -> ResponseMeta {
+    let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+    ResponseMeta {
+        nonce,
+        sent_at,
+        received_at: Timestamp::now(),
+        server_time: header("date")
+            .and_then(|date| DateTime::parse_from_rfc2822(date).ok())
+            .map(|date| Timestamp::from(date.with_timezone(&Utc))),
+        request_id: header("x-amz-cf-id")
+            .or_else(|| header("x-request-id"))
+            .map(str::to_string),
     }
 }
 
@@ -87,7 +166,7 @@ impl ExchangeClient {
         let client = client.unwrap_or_default();
         let base_url = base_url.unwrap_or(BaseUrl::Mainnet);
 
-        let info = InfoClient::new(None, Some(base_url)).await?;
+        let info = InfoClient::new(Some(client.clone()), Some(base_url)).await?;
         let meta = if let Some(meta) = meta {
             meta
         } else {
@@ -115,6 +194,9 @@ impl ExchangeClient {
                 base_url: base_url.get_url(),
             },
             coin_to_asset,
+            latency: Arc::new(LatencyRecorder::default()),
+            reduce_only_closes: None,
+            nonce_partition: NoncePartition::default(),
         })
     }
 
@@ -124,24 +206,85 @@ impl ExchangeClient {
         signature: Signature,
         nonce: u64,
     ) -> Result<ExchangeResponseStatus> {
-        let exchange_payload = ExchangePayload {
+        self.post_expiring(action, signature, nonce, None).await
+    }
+
+    async fn post_expiring(
+        &self,
+        action: serde_json::Value,
+        signature: Signature,
+        nonce: u64,
+        expires_after: Option<u64>,
+    ) -> Result<ExchangeResponseStatus> {
+        self.submit_signed_action(&SignedAction {
             action,
             signature,
             nonce,
             vault_address: self.vault_address,
-        };
-        let res = serde_json::to_string(&exchange_payload)
-            .map_err(|e| Error::JsonParse(e.to_string()))?;
+            expires_after,
+        })
+        .await
+    }
+
+    // Signs `action` without sending it, e.g. to submit it through a co-located proxy or a
+    // websocket post. User-signed actions keep the nonce in their time or nonce field; L1
+    // actions get a fresh one. Multi-sig actions are signed by `send_multi_sig`.
+    pub fn build_signed_action(
+        &self,
+        action: Actions,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<SignedAction> {
+        let wallet = wallet.unwrap_or(&self.wallet);
+        let is_mainnet = self.http_client.base_url == BaseUrl::Mainnet.get_url();
+        let (signature, nonce) = self.latency.time_sign(|| match &action {
+            Actions::UsdSend(usd_send) => Ok((sign_typed_data(usd_send, wallet)?, usd_send.time)),
+            Actions::ApproveAgent(approve_agent) => {
+                Ok((sign_typed_data(approve_agent, wallet)?, approve_agent.nonce))
+            }
+            Actions::Withdraw3(withdraw) => Ok((sign_typed_data(withdraw, wallet)?, withdraw.time)),
+            Actions::SpotSend(spot_send) => {
+                Ok((sign_typed_data(spot_send, wallet)?, spot_send.time))
+            }
+            Actions::ConvertToMultiSigUser(convert) => {
+                Ok((sign_typed_data(convert, wallet)?, convert.nonce))
+            }
+            Actions::MultiSig(_) => Err(Error::MultiSig(
+                "multi-sig actions are signed by send_multi_sig".to_string(),
+            )),
+            _ => {
+                let nonce = next_nonce(self.nonce_partition);
+                let connection_id = action.hash(nonce, self.vault_address)?;
+                Ok((sign_l1_action(wallet, connection_id, is_mainnet)?, nonce))
+            }
+        })?;
+        Ok(SignedAction {
+            action: serde_json::to_value(&action).map_err(|e| Error::JsonParse(e.to_string()))?,
+            signature,
+            nonce,
+            vault_address: self.vault_address,
+            expires_after: None,
+        })
+    }
+
+    pub async fn submit_signed_action(
+        &self,
+        signed_action: &SignedAction,
+    ) -> Result<ExchangeResponseStatus> {
+        let res = signed_action.body()?;
         debug!("Sending request {res:?}");
 
-        serde_json::from_str(
-            &self
-                .http_client
-                .post("/exchange", res)
-                .await
-                .map_err(|e| Error::JsonParse(e.to_string()))?,
-        )
-        .map_err(|e| Error::JsonParse(e.to_string()))
+        let start = Instant::now();
+        let sent_at = Timestamp::now();
+        let (response, headers) = self
+            .http_client
+            .post_with_headers("/exchange", res)
+            .await
+            .map_err(|e| Error::JsonParse(e.to_string()))?;
+        self.latency.record_send_to_ack(start.elapsed());
+        let meta = response_meta(signed_action.nonce, sent_at, &headers);
+        let status: ExchangeResponseStatus =
+            serde_json::from_str(&response).map_err(|e| Error::JsonParse(e.to_string()))?;
+        Ok(status.with_meta(meta))
     }
 
     pub async fn usdc_transfer(
@@ -157,7 +300,7 @@ impl ExchangeClient {
             "Testnet".to_string()
         };
 
-        let timestamp = next_nonce();
+        let timestamp = next_nonce(self.nonce_partition);
         let usd_send = UsdSend {
             signature_chain_id: 421614.into(),
             hyperliquid_chain,
@@ -165,7 +308,9 @@ impl ExchangeClient {
             amount: amount.to_string(),
             time: timestamp,
         };
-        let signature = sign_typed_data(&usd_send, wallet)?;
+        let signature = self
+            .latency
+            .time_sign(|| sign_typed_data(&usd_send, wallet))?;
         let action = serde_json::to_value(Actions::UsdSend(usd_send))
             .map_err(|e| Error::JsonParse(e.to_string()))?;
 
@@ -184,9 +329,22 @@ impl ExchangeClient {
         &self,
         orders: Vec<ClientOrderRequest>,
         wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        self.bulk_order_expiring(orders, None, wallet).await
+    }
+
+    pub(crate) async fn bulk_order_expiring(
+        &self,
+        orders: Vec<ClientOrderRequest>,
+        expires_after: Option<Timestamp>,
+        wallet: Option<&LocalWallet>,
     ) -> Result<ExchangeResponseStatus> {
         let wallet = wallet.unwrap_or(&self.wallet);
-        let timestamp = next_nonce();
+        let orders = match self.reduce_only_closes {
+            Some(user) => self.mark_reduce_only_closes(user, orders).await?,
+            None => orders,
+        };
+        let timestamp = next_nonce(self.nonce_partition);
 
         let mut transformed_orders = Vec::new();
 
@@ -198,12 +356,138 @@ impl ExchangeClient {
             orders: transformed_orders,
             grouping: "na".to_string(),
         });
-        let connection_id = action.hash(timestamp, self.vault_address)?;
+        let expires_after = expires_after.map(Timestamp::as_millis);
+        let connection_id = action_hash(&action, timestamp, self.vault_address, expires_after)?;
         let action = serde_json::to_value(&action).map_err(|e| Error::JsonParse(e.to_string()))?;
 
         let is_mainnet = self.http_client.base_url == BaseUrl::Mainnet.get_url();
-        let signature = sign_l1_action(wallet, connection_id, is_mainnet)?;
-        self.post(action, signature, timestamp).await
+        let signature = self
+            .latency
+            .time_sign(|| sign_l1_action(wallet, connection_id, is_mainnet))?;
+        self.post_expiring(action, signature, timestamp, expires_after)
+            .await
+    }
+
+    // Places an ALO order, repricing and resending it per `policy` while it's rejected for
+    // crossing the book. Returns the last response.
+    pub async fn post_only_with_reprice(
+        &self,
+        mut order: ClientOrderRequest,
+        policy: RepricePolicy,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        let mut retries = 0;
+        loop {
+            let response = self.order(order.clone(), wallet).await?;
+            let rejection = match &response {
+                ExchangeResponseStatus::Ok(response) => response
+                    .data
+                    .as_ref()
+                    .and_then(|data| data.statuses.first())
+                    .and_then(ExchangeDataStatus::post_only_rejection),
+                ExchangeResponseStatus::Err(_) => None,
+            };
+            let Some(rejection) = rejection else {
+                return Ok(response);
+            };
+            if retries == policy.max_retries {
+                return Ok(response);
+            }
+            retries += 1;
+            order.limit_px = policy.reprice(&order, &rejection);
+            debug!("Post only order crossed, retrying at {}", order.limit_px);
+        }
+    }
+
+    // Places an order at most once even when submissions time out: before resending, the order
+    // is looked up by its cloid (required) and returned if the exchange already has it. A resend
+    // racing a late original is rejected by the exchange as a duplicate cloid.
+    pub async fn order_idempotent(
+        &self,
+        order: ClientOrderRequest,
+        policy: SubmitPolicy,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<SubmitOutcome> {
+        let cloid = order.cloid.ok_or(Error::NoCloid)?;
+        let mut last_err = Error::GenericRequest("no submission attempted".to_string());
+        for attempt in 0..policy.max_attempts {
+            if attempt > 0 {
+                let user = self.account_address(wallet).await?;
+                match self
+                    .info_client()
+                    .order_status(user, OrderId::Cloid(cloid))
+                    .await
+                {
+                    Ok(OrderStatusResponse::Order { order }) => {
+                        return Ok(SubmitOutcome::Found(order))
+                    }
+                    Ok(OrderStatusResponse::UnknownOid) => {}
+                    Err(err) => debug!("Order status lookup for {cloid} failed: {err}"),
+                }
+            }
+            match tokio::time::timeout(policy.timeout, self.order(order.clone(), wallet)).await {
+                Ok(Ok(response)) => return Ok(SubmitOutcome::Response(response)),
+                Ok(Err(err @ (Error::GenericRequest(_) | Error::ServerRequest { .. }))) => {
+                    last_err = err
+                }
+                Ok(Err(err)) => return Err(err),
+                Err(_) => {
+                    last_err = Error::GenericRequest(format!(
+                        "order submission timed out after {:?}",
+                        policy.timeout
+                    ))
+                }
+            }
+        }
+        Err(last_err)
+    }
+
+    // The account orders act on: the vault address if set, otherwise the wallet's address,
+    // resolved to its master account when the wallet is an agent.
+    pub(crate) async fn account_address(&self, wallet: Option<&LocalWallet>) -> Result<H160> {
+        if let Some(vault_address) = self.vault_address {
+            return Ok(vault_address);
+        }
+        let address = wallet.unwrap_or(&self.wallet).address();
+        match self.info_client().user_role(address).await? {
+            UserRoleResponse::Agent { user } => Ok(user),
+            _ => Ok(address),
+        }
+    }
+
+    async fn mark_reduce_only_closes(
+        &self,
+        user: H160,
+        mut orders: Vec<ClientOrderRequest>,
+    ) -> Result<Vec<ClientOrderRequest>> {
+        let user_state = self.info_client().user_state(user).await?;
+        let positions: HashMap<String, f64> = user_state
+            .asset_positions
+            .into_iter()
+            .filter_map(|asset_position| {
+                let position = asset_position.position;
+                Some((position.coin, position.szi.parse().ok()?))
+            })
+            .collect();
+        for order in &mut orders {
+            if let Some(&szi) = positions.get(&order.asset) {
+                order.reduce_only |= order.is_close(szi);
+            }
+        }
+        Ok(orders)
+    }
+
+    pub(crate) fn info_client(&self) -> InfoClient {
+        InfoClient {
+            http_client: HttpClient {
+                client: self.http_client.client.clone(),
+                base_url: self.http_client.base_url.clone(),
+            },
+            ws_manager: None,
+            cache: None,
+            ws_connector: Connector::default(),
+            ws_runtime: None,
+        }
     }
 
     pub async fn cancel(
@@ -220,7 +504,7 @@ impl ExchangeClient {
         wallet: Option<&LocalWallet>,
     ) -> Result<ExchangeResponseStatus> {
         let wallet = wallet.unwrap_or(&self.wallet);
-        let timestamp = next_nonce();
+        let timestamp = next_nonce(self.nonce_partition);
 
         let mut transformed_cancels = Vec::new();
         for cancel in cancels.into_iter() {
@@ -241,7 +525,9 @@ impl ExchangeClient {
 
         let action = serde_json::to_value(&action).map_err(|e| Error::JsonParse(e.to_string()))?;
         let is_mainnet = self.http_client.base_url == BaseUrl::Mainnet.get_url();
-        let signature = sign_l1_action(wallet, connection_id, is_mainnet)?;
+        let signature = self
+            .latency
+            .time_sign(|| sign_l1_action(wallet, connection_id, is_mainnet))?;
 
         self.post(action, signature, timestamp).await
     }
@@ -254,13 +540,58 @@ impl ExchangeClient {
         self.bulk_cancel_by_cloid(vec![cancel], wallet).await
     }
 
+    // Cancels every open order of the account, or those whose coin passes `filter`, in batches
+    // of up to MAX_CANCEL_BATCH. Batches that fail are retried up to CANCEL_ALL_RETRIES times.
+    pub async fn cancel_all_orders(
+        &self,
+        filter: Option<CoinFilter>,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<CancelAllSummary> {
+        let info = self.info_client();
+        let user = self.account_address(wallet).await?;
+        let mut pending: Vec<ClientCancelRequest> = info
+            .open_orders(user)
+            .await?
+            .into_iter()
+            .filter(|order| {
+                filter
+                    .as_ref()
+                    .is_none_or(|filter| filter.matches(&order.coin))
+            })
+            .map(|order| ClientCancelRequest {
+                asset: order.coin,
+                oid: order.oid,
+            })
+            .collect();
+
+        let mut summary = CancelAllSummary::default();
+        for attempt in 0..=CANCEL_ALL_RETRIES {
+            if pending.is_empty() {
+                break;
+            }
+            if attempt > 0 {
+                tokio::time::sleep(Duration::from_millis(200 * attempt as u64)).await;
+            }
+            let mut retry = Vec::new();
+            while !pending.is_empty() {
+                let batch: Vec<ClientCancelRequest> = pending
+                    .drain(..pending.len().min(MAX_CANCEL_BATCH))
+                    .collect();
+                let result = self.bulk_cancel(batch.clone(), wallet).await;
+                retry.extend(summary.record(batch, result));
+            }
+            pending = retry;
+        }
+        Ok(summary)
+    }
+
     pub async fn bulk_cancel_by_cloid(
         &self,
         cancels: Vec<ClientCancelRequestCloid>,
         wallet: Option<&LocalWallet>,
     ) -> Result<ExchangeResponseStatus> {
         let wallet = wallet.unwrap_or(&self.wallet);
-        let timestamp = next_nonce();
+        let timestamp = next_nonce(self.nonce_partition);
 
         let mut transformed_cancels: Vec<CancelRequestCloid> = Vec::new();
         for cancel in cancels.into_iter() {
@@ -281,7 +612,9 @@ impl ExchangeClient {
         let connection_id = action.hash(timestamp, self.vault_address)?;
         let action = serde_json::to_value(&action).map_err(|e| Error::JsonParse(e.to_string()))?;
         let is_mainnet = self.http_client.base_url == BaseUrl::Mainnet.get_url();
-        let signature = sign_l1_action(wallet, connection_id, is_mainnet)?;
+        let signature = self
+            .latency
+            .time_sign(|| sign_l1_action(wallet, connection_id, is_mainnet))?;
 
         self.post(action, signature, timestamp).await
     }
@@ -295,7 +628,7 @@ impl ExchangeClient {
     ) -> Result<ExchangeResponseStatus> {
         let wallet = wallet.unwrap_or(&self.wallet);
 
-        let timestamp = next_nonce();
+        let timestamp = next_nonce(self.nonce_partition);
 
         let &asset_index = self.coin_to_asset.get(coin).ok_or(Error::AssetNotFound)?;
         let action = Actions::UpdateLeverage(UpdateLeverage {
@@ -306,7 +639,9 @@ impl ExchangeClient {
         let connection_id = action.hash(timestamp, self.vault_address)?;
         let action = serde_json::to_value(&action).map_err(|e| Error::JsonParse(e.to_string()))?;
         let is_mainnet = self.http_client.base_url == BaseUrl::Mainnet.get_url();
-        let signature = sign_l1_action(wallet, connection_id, is_mainnet)?;
+        let signature = self
+            .latency
+            .time_sign(|| sign_l1_action(wallet, connection_id, is_mainnet))?;
 
         self.post(action, signature, timestamp).await
     }
@@ -320,7 +655,7 @@ impl ExchangeClient {
         let wallet = wallet.unwrap_or(&self.wallet);
 
         let amount = (amount * 1_000_000.0).round() as i64;
-        let timestamp = next_nonce();
+        let timestamp = next_nonce(self.nonce_partition);
 
         let &asset_index = self.coin_to_asset.get(coin).ok_or(Error::AssetNotFound)?;
         let action = Actions::UpdateIsolatedMargin(UpdateIsolatedMargin {
@@ -331,7 +666,9 @@ impl ExchangeClient {
         let connection_id = action.hash(timestamp, self.vault_address)?;
         let action = serde_json::to_value(&action).map_err(|e| Error::JsonParse(e.to_string()))?;
         let is_mainnet = self.http_client.base_url == BaseUrl::Mainnet.get_url();
-        let signature = sign_l1_action(wallet, connection_id, is_mainnet)?;
+        let signature = self
+            .latency
+            .time_sign(|| sign_l1_action(wallet, connection_id, is_mainnet))?;
 
         self.post(action, signature, timestamp).await
     }
@@ -339,6 +676,16 @@ impl ExchangeClient {
     pub async fn approve_agent(
         &self,
         wallet: Option<&LocalWallet>,
+    ) -> Result<(String, ExchangeResponseStatus)> {
+        self.approve_named_agent(None, wallet).await
+    }
+
+    // Approving an agent replaces the account's existing agent of the same name (or the unnamed
+    // agent), which can no longer sign.
+    pub async fn approve_named_agent(
+        &self,
+        agent_name: Option<&str>,
+        wallet: Option<&LocalWallet>,
     ) -> Result<(String, ExchangeResponseStatus)> {
         let wallet = wallet.unwrap_or(&self.wallet);
         let key = H256::from(generate_random_key()?).encode_hex()[2..].to_string();
@@ -354,20 +701,120 @@ impl ExchangeClient {
             "Testnet".to_string()
         };
 
-        let nonce = next_nonce();
+        let nonce = next_nonce(self.nonce_partition);
         let approve_agent = ApproveAgent {
             signature_chain_id: 421614.into(),
             hyperliquid_chain,
             agent_address: address,
-            agent_name: None,
+            agent_name: agent_name.map(str::to_string),
             nonce,
         };
-        let signature = sign_typed_data(&approve_agent, wallet)?;
+        let signature = self
+            .latency
+            .time_sign(|| sign_typed_data(&approve_agent, wallet))?;
         let action = serde_json::to_value(Actions::ApproveAgent(approve_agent))
             .map_err(|e| Error::JsonParse(e.to_string()))?;
         Ok((key, self.post(action, signature, nonce).await?))
     }
 
+    // Turns the wallet's account into a multi-sig user controlled by `signers`. Afterwards,
+    // its actions need `threshold` signatures, collected with `multi_sig`.
+    pub async fn convert_to_multi_sig_user(
+        &self,
+        mut signers: MultiSigSigners,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        let wallet = wallet.unwrap_or(&self.wallet);
+        signers.authorized_users.sort();
+        signers.authorized_users.dedup();
+        if signers.threshold == 0 || signers.threshold > signers.authorized_users.len() {
+            return Err(Error::MultiSig(format!(
+                "threshold {} with {} authorized users",
+                signers.threshold,
+                signers.authorized_users.len()
+            )));
+        }
+
+        let hyperliquid_chain = if self.http_client.base_url.eq(MAINNET_API_URL) {
+            "Mainnet".to_string()
+        } else {
+            "Testnet".to_string()
+        };
+
+        let nonce = next_nonce(self.nonce_partition);
+        let convert = ConvertToMultiSigUser {
+            signature_chain_id: 421614.into(),
+            hyperliquid_chain,
+            signers: serde_json::to_string(&signers)
+                .map_err(|e| Error::JsonParse(e.to_string()))?,
+            nonce,
+        };
+        let signature = self
+            .latency
+            .time_sign(|| sign_typed_data(&convert, wallet))?;
+        let action = serde_json::to_value(Actions::ConvertToMultiSigUser(convert))
+            .map_err(|e| Error::JsonParse(e.to_string()))?;
+        self.post(action, signature, nonce).await
+    }
+
+    // Starts collecting signatures on an L1 action of `multi_sig_user`. The wallet, which must
+    // be one of the authorized users, submits the action with `send_multi_sig`.
+    pub fn multi_sig(
+        &self,
+        multi_sig_user: H160,
+        signers: MultiSigSigners,
+        action: Actions,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<MultiSigCoordinator> {
+        let wallet = wallet.unwrap_or(&self.wallet);
+        let is_mainnet = self.http_client.base_url == BaseUrl::Mainnet.get_url();
+        MultiSigCoordinator::new(
+            multi_sig_user,
+            wallet.address(),
+            signers,
+            action,
+            next_nonce(self.nonce_partition),
+            self.vault_address,
+            is_mainnet,
+        )
+    }
+
+    pub async fn send_multi_sig(
+        &self,
+        coordinator: MultiSigCoordinator,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        let wallet = wallet.unwrap_or(&self.wallet);
+        if wallet.address() != coordinator.outer_signer() {
+            return Err(Error::MultiSig(format!(
+                "{:?} is not the outer signer {:?}",
+                wallet.address(),
+                coordinator.outer_signer()
+            )));
+        }
+        let (multi_sig, nonce) = coordinator.into_action()?;
+
+        let hyperliquid_chain = if self.http_client.base_url.eq(MAINNET_API_URL) {
+            "Mainnet".to_string()
+        } else {
+            "Testnet".to_string()
+        };
+
+        // The outer signature covers the action without its type tag
+        let send_multi_sig = SendMultiSig {
+            signature_chain_id: 421614.into(),
+            hyperliquid_chain,
+            multi_sig_action_hash: action_hash(&multi_sig, nonce, self.vault_address, None)?,
+            nonce,
+        };
+        let signature = self
+            .latency
+            .time_sign(|| sign_typed_data(&send_multi_sig, wallet))?;
+        let action = serde_json::to_value(Actions::MultiSig(multi_sig))
+            .map_err(|e| Error::JsonParse(e.to_string()))?;
+        self.post(action, signature, nonce).await
+    }
+
     pub async fn withdraw_from_bridge(
         &self,
         amount: &str,
@@ -381,7 +828,7 @@ impl ExchangeClient {
             "Testnet".to_string()
         };
 
-        let timestamp = next_nonce();
+        let timestamp = next_nonce(self.nonce_partition);
         let withdraw = Withdraw3 {
             signature_chain_id: 421614.into(),
             hyperliquid_chain,
@@ -389,12 +836,321 @@ impl ExchangeClient {
             amount: amount.to_string(),
             time: timestamp,
         };
-        let signature = sign_typed_data(&withdraw, wallet)?;
+        let signature = self
+            .latency
+            .time_sign(|| sign_typed_data(&withdraw, wallet))?;
         let action = serde_json::to_value(Actions::Withdraw3(withdraw))
             .map_err(|e| Error::JsonParse(e.to_string()))?;
 
         self.post(action, signature, timestamp).await
     }
+
+    pub async fn spot_transfer(
+        &self,
+        amount: &str,
+        destination: &str,
+        token: &str,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        let wallet = wallet.unwrap_or(&self.wallet);
+        let hyperliquid_chain = if self.http_client.base_url.eq(MAINNET_API_URL) {
+            "Mainnet".to_string()
+        } else {
+            "Testnet".to_string()
+        };
+
+        let timestamp = next_nonce(self.nonce_partition);
+        let spot_send = SpotSend {
+            signature_chain_id: 421614.into(),
+            hyperliquid_chain,
+            destination: destination.to_string(),
+            token: token.to_string(),
+            amount: amount.to_string(),
+            time: timestamp,
+        };
+        let signature = self
+            .latency
+            .time_sign(|| sign_typed_data(&spot_send, wallet))?;
+        let action = serde_json::to_value(Actions::SpotSend(spot_send))
+            .map_err(|e| Error::JsonParse(e.to_string()))?;
+
+        self.post(action, signature, timestamp).await
+    }
+
+    pub async fn spot_transfer_to_evm(
+        &self,
+        amount: &str,
+        token: &str,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        let spot_meta = self.info_client().spot_meta().await?;
+        let token = spot_meta.token(token).ok_or(Error::AssetNotFound)?;
+        evm_decimals(token)?;
+        parse_token_amount(amount, token.wei_decimals.into())?;
+
+        let destination = format!("{:?}", spot_system_address(token));
+        self.spot_transfer(amount, &destination, &token.wire_name(), wallet)
+            .await
+    }
+
+    async fn post_l1_action(
+        &self,
+        action: Actions,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        self.post_l1_action_with_nonce(action, next_nonce(self.nonce_partition), wallet)
+            .await
+    }
+
+    async fn post_l1_action_with_nonce(
+        &self,
+        action: Actions,
+        timestamp: u64,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        let wallet = wallet.unwrap_or(&self.wallet);
+
+        let connection_id = action.hash(timestamp, self.vault_address)?;
+        let action = serde_json::to_value(&action).map_err(|e| Error::JsonParse(e.to_string()))?;
+        let is_mainnet = self.http_client.base_url == BaseUrl::Mainnet.get_url();
+        let signature = self
+            .latency
+            .time_sign(|| sign_l1_action(wallet, connection_id, is_mainnet))?;
+
+        self.post(action, signature, timestamp).await
+    }
+
+    pub async fn spot_deploy_register_token(
+        &self,
+        spec: TokenSpec,
+        max_gas: u64,
+        full_name: Option<String>,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        let action = Actions::SpotDeploy(SpotDeploy::RegisterToken2 {
+            spec,
+            max_gas,
+            full_name,
+        });
+        self.post_l1_action(action, wallet).await
+    }
+
+    // Balances are in wei. `existing_token_and_wei` airdrops to holders of existing tokens.
+    pub async fn spot_deploy_user_genesis(
+        &self,
+        token: u32,
+        user_and_wei: Vec<(H160, String)>,
+        existing_token_and_wei: Vec<(u32, String)>,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        let action = Actions::SpotDeploy(SpotDeploy::UserGenesis {
+            token,
+            user_and_wei,
+            existing_token_and_wei,
+        });
+        self.post_l1_action(action, wallet).await
+    }
+
+    pub async fn spot_deploy_genesis(
+        &self,
+        token: u32,
+        max_supply: &str,
+        no_hyperliquidity: bool,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        let action = Actions::SpotDeploy(SpotDeploy::Genesis {
+            token,
+            max_supply: max_supply.to_string(),
+            no_hyperliquidity: no_hyperliquidity.then_some(true),
+        });
+        self.post_l1_action(action, wallet).await
+    }
+
+    pub async fn spot_deploy_register_spot(
+        &self,
+        base_token: u32,
+        quote_token: u32,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        let action = Actions::SpotDeploy(SpotDeploy::RegisterSpot {
+            tokens: [base_token, quote_token],
+        });
+        self.post_l1_action(action, wallet).await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn spot_deploy_register_hyperliquidity(
+        &self,
+        spot: u32,
+        start_px: &str,
+        order_sz: &str,
+        n_orders: u32,
+        n_seeded_levels: Option<u32>,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        let action = Actions::SpotDeploy(SpotDeploy::RegisterHyperliquidity {
+            spot,
+            start_px: start_px.to_string(),
+            order_sz: order_sz.to_string(),
+            n_orders,
+            n_seeded_levels,
+        });
+        self.post_l1_action(action, wallet).await
+    }
+
+    pub async fn perp_deploy_register_asset(
+        &self,
+        dex: &str,
+        max_gas: Option<u64>,
+        asset_request: PerpAssetRequest,
+        schema: Option<PerpDexSchema>,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        let action = Actions::PerpDeploy(PerpDeploy::RegisterAsset {
+            max_gas,
+            asset_request,
+            dex: dex.to_string(),
+            schema,
+        });
+        self.post_l1_action(action, wallet).await
+    }
+
+    // Must be sent by the dex's oracle updater. `mark_pxs` may hold several sources, the
+    // exchange takes the median of them together with the local mark price.
+    pub async fn perp_deploy_set_oracle(
+        &self,
+        dex: &str,
+        oracle_pxs: HashMap<String, String>,
+        mark_pxs: Vec<HashMap<String, String>>,
+        external_perp_pxs: HashMap<String, String>,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        let sorted = |pxs: HashMap<String, String>| {
+            let mut pxs: Vec<(String, String)> = pxs.into_iter().collect();
+            pxs.sort();
+            pxs
+        };
+        let action = Actions::PerpDeploy(PerpDeploy::SetOracle {
+            dex: dex.to_string(),
+            oracle_pxs: sorted(oracle_pxs),
+            mark_pxs: mark_pxs.into_iter().map(sorted).collect(),
+            external_perp_pxs: sorted(external_perp_pxs),
+        });
+        self.post_l1_action(action, wallet).await
+    }
+
+    // Switches HyperEVM transactions sent by the wallet's address between small and big blocks.
+    pub async fn use_big_blocks(
+        &self,
+        enable: bool,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        let action = Actions::EvmUserModify(EvmUserModify {
+            using_big_blocks: enable,
+        });
+        self.post_l1_action(action, wallet).await
+    }
+
+    // Buys `weight` additional requests of address-based rate limit capacity, paid from the
+    // perp balance. Check the current budget with `InfoClient::user_rate_limit`.
+    pub async fn reserve_request_weight(
+        &self,
+        weight: u64,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        let action = Actions::ReserveRequestWeight(ReserveRequestWeight { weight });
+        self.post_l1_action(action, wallet).await
+    }
+
+    // Cancels all open orders at `time` (ms, at least 5 seconds ahead) unless rescheduled or
+    // cleared with `None` first. Works as a dead man's switch.
+    pub async fn schedule_cancel(
+        &self,
+        time: Option<Timestamp>,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        let action = Actions::ScheduleCancel(ScheduleCancel { time });
+        self.post_l1_action(action, wallet).await
+    }
+
+    // A noop does nothing except consume its nonce.
+    pub async fn noop(
+        &self,
+        nonce: u64,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        self.post_l1_action_with_nonce(Actions::Noop, nonce, wallet)
+            .await
+    }
+
+    // Burns each nonce with a noop so actions that were signed with them but never submitted,
+    // e.g. by a bot instance that has since failed over, can no longer be executed. A nonce
+    // that was already used is reported as an error status for that entry.
+    pub async fn invalidate_nonces(
+        &self,
+        nonces: &[u64],
+        wallet: Option<&LocalWallet>,
+    ) -> Result<Vec<ExchangeResponseStatus>> {
+        let mut statuses = Vec::with_capacity(nonces.len());
+        for &nonce in nonces {
+            statuses.push(self.noop(nonce, wallet).await?);
+        }
+        Ok(statuses)
+    }
+
+    // Must be signed by the validator's signer key.
+    #[cfg(feature = "validator")]
+    pub async fn c_signer_unjail_self(
+        &self,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        let action = Actions::CSignerAction(CSignerAction::UnjailSelf(()));
+        self.post_l1_action(action, wallet).await
+    }
+
+    #[cfg(feature = "validator")]
+    pub async fn c_signer_jail_self(
+        &self,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        let action = Actions::CSignerAction(CSignerAction::JailSelf(()));
+        self.post_l1_action(action, wallet).await
+    }
+
+    #[cfg(feature = "validator")]
+    pub async fn c_validator_register(
+        &self,
+        profile: ValidatorProfile,
+        unjailed: bool,
+        initial_wei: u64,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        let action = Actions::CValidatorAction(CValidatorAction::Register {
+            profile,
+            unjailed,
+            initial_wei,
+        });
+        self.post_l1_action(action, wallet).await
+    }
+
+    #[cfg(feature = "validator")]
+    pub async fn c_validator_change_profile(
+        &self,
+        change: ValidatorProfileChange,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        let action = Actions::CValidatorAction(CValidatorAction::ChangeProfile(change));
+        self.post_l1_action(action, wallet).await
+    }
+
+    #[cfg(feature = "validator")]
+    pub async fn c_validator_unregister(
+        &self,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus> {
+        let action = Actions::CValidatorAction(CValidatorAction::Unregister(()));
+        self.post_l1_action(action, wallet).await
+    }
 }
 
 #[cfg(test)]
@@ -535,4 +1291,304 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_spot_deploy_action_serialization() -> Result<()> {
+        let action = Actions::SpotDeploy(SpotDeploy::RegisterToken2 {
+            spec: TokenSpec {
+                name: "TEST".to_string(),
+                sz_decimals: 2,
+                wei_decimals: 8,
+            },
+            max_gas: 1_000_000,
+            full_name: None,
+        });
+        assert_eq!(
+            serde_json::to_value(&action).unwrap(),
+            serde_json::json!({
+                "type": "spotDeploy",
+                "registerToken2": {
+                    "spec": {"name": "TEST", "szDecimals": 2, "weiDecimals": 8},
+                    "maxGas": 1_000_000
+                }
+            })
+        );
+
+        let action = Actions::SpotDeploy(SpotDeploy::UserGenesis {
+            token: 5,
+            user_and_wei: vec![(H160::from_low_u64_be(1), "100".to_string())],
+            existing_token_and_wei: vec![(0, "50".to_string())],
+        });
+        assert_eq!(
+            serde_json::to_value(&action).unwrap(),
+            serde_json::json!({
+                "type": "spotDeploy",
+                "userGenesis": {
+                    "token": 5,
+                    "userAndWei": [["0x0000000000000000000000000000000000000001", "100"]],
+                    "existingTokenAndWei": [[0, "50"]]
+                }
+            })
+        );
+        // The msgpack encoding used for signing must accept the nested enum as well
+        action.hash(1583838, None)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_perp_deploy_action_serialization() -> Result<()> {
+        let action = Actions::PerpDeploy(PerpDeploy::RegisterAsset {
+            max_gas: None,
+            asset_request: PerpAssetRequest {
+                coin: "test:ABC".to_string(),
+                sz_decimals: 2,
+                oracle_px: "10.0".to_string(),
+                margin_table_id: 10,
+                only_isolated: false,
+            },
+            dex: "test".to_string(),
+            schema: None,
+        });
+        assert_eq!(
+            serde_json::to_value(&action).unwrap(),
+            serde_json::json!({
+                "type": "perpDeploy",
+                "registerAsset": {
+                    "maxGas": null,
+                    "assetRequest": {
+                        "coin": "test:ABC",
+                        "szDecimals": 2,
+                        "oraclePx": "10.0",
+                        "marginTableId": 10,
+                        "onlyIsolated": false
+                    },
+                    "dex": "test",
+                    "schema": null
+                }
+            })
+        );
+        action.hash(1583838, None)?;
+        Ok(())
+    }
+
+    #[cfg(feature = "validator")]
+    #[test]
+    fn test_validator_action_serialization() -> Result<()> {
+        let action = Actions::CSignerAction(CSignerAction::UnjailSelf(()));
+        assert_eq!(
+            serde_json::to_value(&action).unwrap(),
+            serde_json::json!({"type": "CSignerAction", "unjailSelf": null})
+        );
+
+        let action =
+            Actions::CValidatorAction(CValidatorAction::ChangeProfile(ValidatorProfileChange {
+                commission_bps: Some(500),
+                ..Default::default()
+            }));
+        assert_eq!(
+            serde_json::to_value(&action).unwrap(),
+            serde_json::json!({
+                "type": "CValidatorAction",
+                "changeProfile": {
+                    "node_ip": null,
+                    "name": null,
+                    "description": null,
+                    "unjailed": false,
+                    "disable_delegations": null,
+                    "commission_bps": 500,
+                    "signer": null
+                }
+            })
+        );
+        action.hash(1583838, None)?;
+
+        let action = Actions::CValidatorAction(CValidatorAction::Register {
+            profile: ValidatorProfile {
+                node_ip: crate::NodeIp::Ip("1.2.3.4".to_string()),
+                name: "Validator".to_string(),
+                description: "A validator".to_string(),
+                delegations_disabled: true,
+                commission_bps: 500,
+                signer: H160::from_low_u64_be(1),
+            },
+            unjailed: false,
+            initial_wei: 1_000_000_000_000_000_000,
+        });
+        assert_eq!(
+            serde_json::to_value(&action).unwrap(),
+            serde_json::json!({
+                "type": "CValidatorAction",
+                "register": {
+                    "profile": {
+                        "node_ip": {"Ip": "1.2.3.4"},
+                        "name": "Validator",
+                        "description": "A validator",
+                        "delegations_disabled": true,
+                        "commission_bps": 500,
+                        "signer": "0x0000000000000000000000000000000000000001"
+                    },
+                    "unjailed": false,
+                    "initial_wei": 1_000_000_000_000_000_000u64
+                }
+            })
+        );
+        // The hash of the Python SDK's msgpack encoding of the same action
+        assert_eq!(
+            format!("{:x}", action.hash(1583838, None)?),
+            "8c7bb47edb1dc00b7f7442ae450fd6316d4b1baf504d3fe63881bdf8133fc788"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_noop_action_serialization() -> Result<()> {
+        assert_eq!(
+            serde_json::to_value(Actions::Noop).unwrap(),
+            serde_json::json!({"type": "noop"})
+        );
+        Actions::Noop.hash(1583838, None)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_build_signed_action() -> Result<()> {
+        use crate::{signature::agent::l1, sim::serve_rest};
+        use ethers::types::transaction::eip712::Eip712;
+        use std::sync::Mutex;
+
+        // Records the bodies `post` sends
+        let bodies = Arc::new(Mutex::new(Vec::new()));
+        let base_url = serve_rest({
+            let bodies = bodies.clone();
+            move |body: serde_json::Value| {
+                bodies.lock().unwrap().push(body);
+                async {
+                    (
+                        200,
+                        serde_json::json!({"status": "ok", "response": {"type": "default"}}),
+                    )
+                }
+            }
+        })
+        .await?
+        .base_url;
+        let wallet = get_wallet()?;
+        let vault_address = H160::from_low_u64_be(7);
+        let client = ExchangeClient {
+            http_client: HttpClient {
+                client: Client::default(),
+                base_url,
+            },
+            wallet: wallet.clone(),
+            meta: Meta {
+                universe: Vec::new(),
+            },
+            vault_address: Some(vault_address),
+            coin_to_asset: HashMap::new(),
+            latency: Arc::new(LatencyRecorder::default()),
+            reduce_only_closes: None,
+            nonce_partition: NoncePartition::default(),
+        };
+        let sent_by_post = |signed_action: &SignedAction| {
+            let client = &client;
+            let bodies = &bodies;
+            let signed_action = signed_action.clone();
+            async move {
+                client
+                    .post(
+                        signed_action.action.clone(),
+                        signed_action.signature,
+                        signed_action.nonce,
+                    )
+                    .await?;
+                let sent = bodies.lock().unwrap().pop().unwrap();
+                let built: serde_json::Value =
+                    serde_json::from_str(&signed_action.body()?).unwrap();
+                assert_eq!(built, sent);
+                Ok::<_, Error>(())
+            }
+        };
+
+        // An L1 action gets a fresh nonce, is signed over the vault address, and on testnet
+        let action = Actions::UpdateLeverage(UpdateLeverage {
+            asset: 0,
+            is_cross: true,
+            leverage: 5,
+        });
+        let before = Timestamp::now().as_millis();
+        let signed_action = client.build_signed_action(action.clone(), None)?;
+        assert!(signed_action.nonce >= before);
+        assert_eq!(signed_action.vault_address, Some(vault_address));
+        let agent = l1::Agent {
+            source: "b".to_string(),
+            connection_id: action.hash(signed_action.nonce, Some(vault_address))?,
+        };
+        let hash = H256::from(agent.encode_eip712().unwrap());
+        assert_eq!(
+            signed_action.signature.recover(hash).unwrap(),
+            wallet.address()
+        );
+        sent_by_post(&signed_action).await?;
+
+        // A user-signed action keeps the nonce in its time field
+        let usd_send = UsdSend {
+            signature_chain_id: 421614.into(),
+            hyperliquid_chain: "Testnet".to_string(),
+            destination: "0x0D1d9635D0640821d15e323ac8AdADfA9c111414".to_string(),
+            amount: "1".to_string(),
+            time: 1_700_000_000_000,
+        };
+        let signed_action = client.build_signed_action(Actions::UsdSend(usd_send.clone()), None)?;
+        assert_eq!(signed_action.nonce, 1_700_000_000_000);
+        assert_eq!(signed_action.action["type"], "usdSend");
+        let hash = H256::from(usd_send.encode_eip712().unwrap());
+        assert_eq!(
+            signed_action.signature.recover(hash).unwrap(),
+            wallet.address()
+        );
+        sent_by_post(&signed_action).await?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_signed_action_body() -> Result<()> {
+        let wallet = get_wallet()?;
+        let connection_id = Actions::Noop.hash(1583838, None)?;
+        let signed_action = SignedAction {
+            action: serde_json::to_value(Actions::Noop).unwrap(),
+            signature: sign_l1_action(&wallet, connection_id, true)?,
+            nonce: 1583838,
+            vault_address: None,
+            expires_after: None,
+        };
+        let body: serde_json::Value = serde_json::from_str(&signed_action.body()?).unwrap();
+        assert_eq!(body["action"], serde_json::json!({"type": "noop"}));
+        assert_eq!(body["nonce"], 1583838);
+        assert!(body["vaultAddress"].is_null());
+        assert!(body["signature"]["r"].is_string());
+        Ok(())
+    }
+
+    #[test]
+    fn test_response_meta() {
+        let mut headers = HeaderMap::new();
+        headers.insert("date", "Tue, 14 Nov 2023 22:13:21 GMT".parse().unwrap());
+        headers.insert("x-amz-cf-id", "abc123==".parse().unwrap());
+        let sent_at = Timestamp::from_millis(1_700_000_000_500);
+        let meta = response_meta(1_700_000_000_400, sent_at, &headers);
+        assert_eq!(
+            meta.server_time,
+            Some(Timestamp::from_millis(1_700_000_001_000))
+        );
+        assert_eq!(meta.request_id.as_deref(), Some("abc123=="));
+        assert_eq!(meta.one_way_latency(), Some(Duration::from_millis(500)));
+
+        let status: ExchangeResponseStatus =
+            serde_json::from_str(r#"{"status":"err","response":"Insufficient margin"}"#).unwrap();
+        let status = status.with_meta(meta.clone());
+        assert_eq!(status.meta(), &meta);
+        assert!(
+            matches!(status, ExchangeResponseStatus::Err(err) if err.message == "Insufficient margin")
+        );
+    }
 }
@@ -1,11 +1,13 @@
-use serde::Deserialize;
+use crate::{OrderStatusData, Timestamp};
+use serde::{Deserialize, Serialize};
+use std::{fmt, time::Duration};
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct RestingOrder {
     pub oid: u64,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct FilledOrder {
     pub total_sz: String,
@@ -13,7 +15,7 @@ pub struct FilledOrder {
     pub oid: u64,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub enum ExchangeDataStatus {
     Success,
@@ -24,22 +26,138 @@ pub enum ExchangeDataStatus {
     Filled(FilledOrder),
 }
 
-#[derive(Deserialize, Debug, Clone)]
+impl ExchangeDataStatus {
+    // ALO orders that would cross are rejected with e.g. "Post only order would have
+    // immediately matched, bbo was 2465.4@2465.5. asset=1".
+    pub fn post_only_rejection(&self) -> Option<PostOnlyRejection> {
+        let ExchangeDataStatus::Error(error) = self else {
+            return None;
+        };
+        if !error.contains("would have immediately matched") {
+            return None;
+        }
+        let bbo = error
+            .split("bbo was ")
+            .nth(1)
+            .and_then(|rest| rest.split_whitespace().next())
+            .map(|bbo| bbo.trim_end_matches(['.', ',']));
+        let (bid, ask) = match bbo.and_then(|bbo| bbo.split_once('@')) {
+            Some((bid, ask)) => (bid.parse().ok(), ask.parse().ok()),
+            None => (None, None),
+        };
+        Some(PostOnlyRejection { bid, ask })
+    }
+}
+
+// The best bid and ask at the time a post-only order was rejected, when reported.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PostOnlyRejection {
+    pub bid: Option<f64>,
+    pub ask: Option<f64>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct ExchangeDataStatuses {
     pub statuses: Vec<ExchangeDataStatus>,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct ExchangeResponse {
     #[serde(rename = "type")]
     pub response_type: String,
     pub data: Option<ExchangeDataStatuses>,
+    #[serde(skip)]
+    pub meta: ResponseMeta,
+}
+
+// An action the exchange rejected as a whole.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(from = "String", into = "String")]
+pub struct ExchangeError {
+    pub message: String,
+    pub meta: ResponseMeta,
+}
+
+impl From<String> for ExchangeError {
+    fn from(message: String) -> Self {
+        ExchangeError {
+            message,
+            meta: ResponseMeta::default(),
+        }
+    }
+}
+
+impl From<ExchangeError> for String {
+    fn from(error: ExchangeError) -> Self {
+        error.message
+    }
+}
+
+impl fmt::Display for ExchangeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 #[serde(tag = "status", content = "response")]
 pub enum ExchangeResponseStatus {
     Ok(ExchangeResponse),
-    Err(String),
+    Err(ExchangeError),
+}
+
+impl ExchangeResponseStatus {
+    pub fn meta(&self) -> &ResponseMeta {
+        match self {
+            ExchangeResponseStatus::Ok(response) => &response.meta,
+            ExchangeResponseStatus::Err(error) => &error.meta,
+        }
+    }
+
+    pub(crate) fn with_meta(mut self, meta: ResponseMeta) -> Self {
+        match &mut self {
+            ExchangeResponseStatus::Ok(response) => response.meta = meta,
+            ExchangeResponseStatus::Err(error) => error.meta = meta,
+        }
+        self
+    }
+}
+
+// When and how the exchange handled a request, for latency measurements and for matching
+// requests with exchange-side logs. The response body has neither a timestamp nor a request
+// id, so the server's are taken from the HTTP headers. Unset for responses submitted through
+// other transports.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResponseMeta {
+    // The request's nonce, which is also the time it was signed
+    pub nonce: u64,
+    pub sent_at: Timestamp,
+    pub received_at: Timestamp,
+    // From the Date header, so only accurate to the second
+    pub server_time: Option<Timestamp>,
+    // The id the exchange's edge assigned the request, from the x-amz-cf-id or x-request-id
+    // header
+    pub request_id: Option<String>,
+}
+
+impl ResponseMeta {
+    pub fn round_trip(&self) -> Duration {
+        self.received_at.duration_since(self.sent_at)
+    }
+
+    // Time from sending to the server's clock, with the one second resolution of the Date
+    // header and any clock skew between the two hosts.
+    pub fn one_way_latency(&self) -> Option<Duration> {
+        self.server_time
+            .map(|server_time| server_time.duration_since(self.sent_at))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum SubmitOutcome {
+    // The exchange answered the submission
+    Response(ExchangeResponseStatus),
+    // The answer was lost, but the order was found by its cloid
+    Found(Box<OrderStatusData>),
 }
@@ -2,10 +2,26 @@ mod actions;
 mod cancel;
 mod exchange_client;
 mod exchange_responses;
+mod multi_sig;
 mod order;
+mod order_builder;
+#[cfg(feature = "validator")]
+mod validator;
 
 pub use actions::*;
-pub use cancel::{ClientCancelRequest, ClientCancelRequestCloid};
+#[cfg(any(test, feature = "proptest-support"))]
+pub(crate) use cancel::CancelRequest;
+pub use cancel::{CancelAllSummary, ClientCancelRequest, ClientCancelRequestCloid, CoinFilter};
 pub use exchange_client::*;
 pub use exchange_responses::*;
-pub use order::{ClientLimit, ClientOrder, ClientOrderRequest, ClientTrigger, Order};
+pub use multi_sig::MultiSigCoordinator;
+pub use order::{
+    ClientLimit, ClientOrder, ClientOrderRequest, ClientTrigger, Order, RepricePolicy, SubmitPolicy,
+};
+#[cfg(any(test, feature = "proptest-support"))]
+pub(crate) use order::{Limit, OrderRequest, Trigger};
+pub use order_builder::{
+    NeedsPrice, NeedsSide, NeedsSize, NeedsTif, NeedsTriggerExecution, OrderBuilder, Ready,
+};
+#[cfg(feature = "validator")]
+pub use validator::*;
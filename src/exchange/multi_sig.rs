@@ -0,0 +1,199 @@
+use crate::{
+    exchange::exchange_client::action_hash, prelude::*, signature::agent::l1, Actions, Error,
+    MultiSig, MultiSigPayload, MultiSigSigners,
+};
+use ethers::{
+    signers::Signer,
+    types::{transaction::eip712::Eip712, Signature, H160, H256},
+};
+use std::collections::BTreeMap;
+
+// Collects the signatures of a multi-sig user's authorized users on one L1 action. Signers can
+// be any `Signer` implementation, e.g. local keys, hardware wallets or a KMS, or sign elsewhere
+// and hand over their signature; either way it's checked against the authorized users. Once
+// the threshold is met, the outer signer submits the action:
+//
+//     let mut coordinator = exchange_client.multi_sig(multi_sig_user, signers, action, None)?;
+//     coordinator.sign(&ledger).await?;
+//     coordinator.add_signature(signature_from_cosigner)?;
+//     exchange_client.send_multi_sig(coordinator, None).await?;
+#[derive(Debug)]
+pub struct MultiSigCoordinator {
+    multi_sig_user: H160,
+    outer_signer: H160,
+    action: Actions,
+    nonce: u64,
+    vault_address: Option<H160>,
+    is_mainnet: bool,
+    signers: MultiSigSigners,
+    signatures: BTreeMap<H160, Signature>,
+}
+
+impl MultiSigCoordinator {
+    pub(crate) fn new(
+        multi_sig_user: H160,
+        outer_signer: H160,
+        signers: MultiSigSigners,
+        action: Actions,
+        nonce: u64,
+        vault_address: Option<H160>,
+        is_mainnet: bool,
+    ) -> Result<MultiSigCoordinator> {
+        // User-signed actions are signed over their typed fields, not the L1 connection id
+        if matches!(
+            action,
+            Actions::UsdSend(_)
+                | Actions::ApproveAgent(_)
+                | Actions::Withdraw3(_)
+                | Actions::SpotSend(_)
+                | Actions::ConvertToMultiSigUser(_)
+                | Actions::MultiSig(_)
+        ) {
+            return Err(Error::MultiSig(
+                "only L1 actions can be multi-signed".to_string(),
+            ));
+        }
+        if !signers.authorized_users.contains(&outer_signer) {
+            return Err(Error::MultiSig(format!(
+                "outer signer {outer_signer:?} is not an authorized user"
+            )));
+        }
+        Ok(MultiSigCoordinator {
+            multi_sig_user,
+            outer_signer,
+            action,
+            nonce,
+            vault_address,
+            is_mainnet,
+            signers,
+            signatures: BTreeMap::new(),
+        })
+    }
+
+    pub fn outer_signer(&self) -> H160 {
+        self.outer_signer
+    }
+
+    pub fn nonce(&self) -> u64 {
+        self.nonce
+    }
+
+    // What each authorized user signs, as an L1 action: the action wrapped with the multi-sig
+    // user and the outer signer.
+    pub fn connection_id(&self) -> Result<H256> {
+        action_hash(
+            &(self.multi_sig_user, self.outer_signer, &self.action),
+            self.nonce,
+            self.vault_address,
+            None,
+        )
+    }
+
+    // Returns the signer's address.
+    pub async fn sign<S: Signer>(&mut self, signer: &S) -> Result<H160> {
+        let signature = signer
+            .sign_typed_data(&self.agent()?)
+            .await
+            .map_err(|e| Error::SignatureFailure(e.to_string()))?;
+        self.add_signature(signature)
+    }
+
+    // Adds a signature made elsewhere over `connection_id`, returning the signer's address.
+    // A second signature from the same user replaces the first.
+    pub fn add_signature(&mut self, signature: Signature) -> Result<H160> {
+        let hash = self
+            .agent()?
+            .encode_eip712()
+            .map_err(|e| Error::Eip712(e.to_string()))?;
+        let signer = signature
+            .recover(H256::from(hash))
+            .map_err(|e| Error::SignatureFailure(e.to_string()))?;
+        if !self.signers.authorized_users.contains(&signer) {
+            return Err(Error::MultiSig(format!(
+                "{signer:?} is not an authorized user"
+            )));
+        }
+        self.signatures.insert(signer, signature);
+        Ok(signer)
+    }
+
+    // How many more signatures the threshold needs.
+    pub fn missing(&self) -> usize {
+        self.signers.threshold.saturating_sub(self.signatures.len())
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.missing() == 0
+    }
+
+    pub(crate) fn into_action(self) -> Result<(MultiSig, u64)> {
+        if !self.is_complete() {
+            return Err(Error::MultiSig(format!(
+                "{} of {} signatures missing",
+                self.missing(),
+                self.signers.threshold
+            )));
+        }
+        let multi_sig = MultiSig {
+            signature_chain_id: 421614.into(),
+            signatures: self.signatures.into_values().collect(),
+            payload: MultiSigPayload {
+                multi_sig_user: self.multi_sig_user,
+                outer_signer: self.outer_signer,
+                action: Box::new(self.action),
+            },
+        };
+        Ok((multi_sig, self.nonce))
+    }
+
+    fn agent(&self) -> Result<l1::Agent> {
+        Ok(l1::Agent {
+            source: if self.is_mainnet { "a" } else { "b" }.to_string(),
+            connection_id: self.connection_id()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{signature::sign_l1_action, ScheduleCancel};
+    use ethers::signers::LocalWallet;
+
+    fn wallet(key: &str) -> LocalWallet {
+        key.parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_collect_signatures() -> Result<()> {
+        let first = wallet("e908f86dbb4d55ac876378565aafeabc187f6690f046459397b17d9b9a19688e");
+        let second = wallet("0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef");
+        let outsider = wallet("1111111111111111111111111111111111111111111111111111111111111111");
+        let signers = MultiSigSigners {
+            authorized_users: vec![first.address(), second.address()],
+            threshold: 2,
+        };
+        let action = Actions::ScheduleCancel(ScheduleCancel { time: None });
+        let mut coordinator = MultiSigCoordinator::new(
+            H160::from_low_u64_be(1),
+            first.address(),
+            signers,
+            action,
+            1_700_000_000_000,
+            None,
+            false,
+        )?;
+
+        assert_eq!(coordinator.sign(&first).await?, first.address());
+        assert!(coordinator.sign(&outsider).await.is_err());
+        assert_eq!(coordinator.missing(), 1);
+
+        let signature = sign_l1_action(&second, coordinator.connection_id()?, false)?;
+        assert_eq!(coordinator.add_signature(signature)?, second.address());
+        assert!(coordinator.is_complete());
+        let (multi_sig, nonce) = coordinator.into_action()?;
+        assert_eq!(multi_sig.signatures.len(), 2);
+        assert_eq!(nonce, 1_700_000_000_000);
+        Ok(())
+    }
+}
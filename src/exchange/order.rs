@@ -1,10 +1,12 @@
 use crate::{
+    consts::EPSILON,
     errors::Error,
+    exchange::exchange_responses::PostOnlyRejection,
     helpers::{float_to_string_for_hashing, uuid_to_hex_string},
     prelude::*,
 };
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::{collections::HashMap, time::Duration};
 use uuid::Uuid;
 
 #[derive(Deserialize, Serialize, Clone, Debug)]
@@ -46,20 +48,25 @@ pub struct OrderRequest {
     pub cloid: Option<String>,
 }
 
+#[derive(Clone, Debug)]
 pub struct ClientLimit {
     pub tif: String,
 }
 
+#[derive(Clone, Debug)]
 pub struct ClientTrigger {
     pub is_market: bool,
     pub trigger_px: f64,
     pub tpsl: String,
 }
 
+#[derive(Clone, Debug)]
 pub enum ClientOrder {
     Limit(ClientLimit),
     Trigger(ClientTrigger),
 }
+
+#[derive(Clone, Debug)]
 pub struct ClientOrderRequest {
     pub asset: String,
     pub is_buy: bool,
@@ -71,6 +78,42 @@ pub struct ClientOrderRequest {
 }
 
 impl ClientOrderRequest {
+    // An ALO limit order, rejected instead of taking liquidity.
+    pub fn post_only(asset: &str, is_buy: bool, sz: f64, limit_px: f64) -> ClientOrderRequest {
+        ClientOrderRequest::limit(asset, is_buy, sz, limit_px, "Alo", false)
+    }
+
+    // A GTC limit order that can only shrink the position.
+    pub fn reduce_only(asset: &str, is_buy: bool, sz: f64, limit_px: f64) -> ClientOrderRequest {
+        ClientOrderRequest::limit(asset, is_buy, sz, limit_px, "Gtc", true)
+    }
+
+    fn limit(
+        asset: &str,
+        is_buy: bool,
+        sz: f64,
+        limit_px: f64,
+        tif: &str,
+        reduce_only: bool,
+    ) -> ClientOrderRequest {
+        ClientOrderRequest {
+            asset: asset.to_string(),
+            is_buy,
+            reduce_only,
+            limit_px,
+            sz,
+            cloid: None,
+            order_type: ClientOrder::Limit(ClientLimit {
+                tif: tif.to_string(),
+            }),
+        }
+    }
+
+    // Whether the order only closes (part of) a position of signed size `szi`.
+    pub(crate) fn is_close(&self, szi: f64) -> bool {
+        szi != 0.0 && self.is_buy == (szi < 0.0) && self.sz <= szi.abs() + EPSILON
+    }
+
     pub(crate) fn convert(self, coin_to_asset: &HashMap<String, u32>) -> Result<OrderRequest> {
         let order_type = match self.order_type {
             ClientOrder::Limit(limit) => Order::Limit(Limit { tif: limit.tif }),
@@ -95,3 +138,76 @@ impl ClientOrderRequest {
         })
     }
 }
+
+// How `ExchangeClient::post_only_with_reprice` retries an ALO order rejected for crossing the
+// book. Each retry joins the touch reported in the rejection, or backs off by `tick` when the
+// rejection didn't include it.
+#[derive(Debug, Clone, Copy)]
+pub struct RepricePolicy {
+    pub max_retries: u32,
+    pub tick: f64,
+}
+
+impl RepricePolicy {
+    pub(crate) fn reprice(&self, order: &ClientOrderRequest, rejection: &PostOnlyRejection) -> f64 {
+        if order.is_buy {
+            rejection.bid.unwrap_or(order.limit_px - self.tick)
+        } else {
+            rejection.ask.unwrap_or(order.limit_px + self.tick)
+        }
+    }
+}
+
+// How `ExchangeClient::order_idempotent` retries an order whose submission timed out or
+// failed in transit. Every attempt waits at most `timeout` for the exchange to answer.
+#[derive(Debug, Clone, Copy)]
+pub struct SubmitPolicy {
+    pub timeout: Duration,
+    pub max_attempts: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ExchangeDataStatus;
+
+    #[test]
+    fn test_is_close() {
+        let sell = ClientOrderRequest::reduce_only("ETH", false, 1.0, 2000.0);
+        assert!(sell.reduce_only);
+        assert!(sell.is_close(1.5));
+        assert!(!sell.is_close(0.5));
+        assert!(!sell.is_close(-1.5));
+        assert!(!sell.is_close(0.0));
+        let buy = ClientOrderRequest::post_only("ETH", true, 1.0, 2000.0);
+        assert!(buy.is_close(-1.0));
+        assert!(!buy.is_close(1.0));
+    }
+
+    #[test]
+    fn test_reprice() {
+        let policy = RepricePolicy {
+            max_retries: 2,
+            tick: 0.1,
+        };
+        let status = ExchangeDataStatus::Error(
+            "Post only order would have immediately matched, bbo was 2465.4@2465.5. asset=1"
+                .to_string(),
+        );
+        let rejection = status.post_only_rejection().unwrap();
+        assert_eq!(rejection.bid, Some(2465.4));
+        assert_eq!(rejection.ask, Some(2465.5));
+        let buy = ClientOrderRequest::post_only("ETH", true, 1.0, 2466.0);
+        assert_eq!(policy.reprice(&buy, &rejection), 2465.4);
+
+        let rejection =
+            ExchangeDataStatus::Error("Post only order would have immediately matched".to_string())
+                .post_only_rejection()
+                .unwrap();
+        let sell = ClientOrderRequest::post_only("ETH", false, 1.0, 2465.0);
+        assert!((policy.reprice(&sell, &rejection) - 2465.1).abs() < 1e-9);
+        assert!(ExchangeDataStatus::Error("Insufficient margin".to_string())
+            .post_only_rejection()
+            .is_none());
+    }
+}
@@ -0,0 +1,278 @@
+use crate::{ClientLimit, ClientOrder, ClientOrderRequest, ClientTrigger};
+use uuid::Uuid;
+
+// Builds a `ClientOrderRequest` one required field at a time:
+//
+//     OrderBuilder::new("ETH").buy().size(0.1).limit(1800.0).alo().build()
+//     OrderBuilder::new("ETH").sell().size(0.1).stop_loss(1700.0).market(1650.0).build()
+//
+// Each step returns a different type, so an order missing its side, size or price doesn't
+// compile, and neither do combinations the exchange would reject: a time in force can only
+// be picked for plain limit orders (no ALO on a trigger), and a trigger can't be created
+// without its trigger price.
+#[derive(Debug, Clone)]
+pub struct OrderBuilder<S> {
+    state: S,
+}
+
+#[derive(Debug, Clone)]
+pub struct NeedsSide {
+    coin: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct NeedsSize {
+    coin: String,
+    is_buy: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct NeedsPrice {
+    coin: String,
+    is_buy: bool,
+    sz: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct NeedsTif {
+    coin: String,
+    is_buy: bool,
+    sz: f64,
+    limit_px: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct NeedsTriggerExecution {
+    coin: String,
+    is_buy: bool,
+    sz: f64,
+    trigger_px: f64,
+    tpsl: &'static str,
+}
+
+pub struct Ready {
+    request: ClientOrderRequest,
+}
+
+impl OrderBuilder<NeedsSide> {
+    pub fn new(coin: &str) -> OrderBuilder<NeedsSide> {
+        OrderBuilder {
+            state: NeedsSide {
+                coin: coin.to_string(),
+            },
+        }
+    }
+
+    pub fn buy(self) -> OrderBuilder<NeedsSize> {
+        self.side(true)
+    }
+
+    pub fn sell(self) -> OrderBuilder<NeedsSize> {
+        self.side(false)
+    }
+
+    pub fn side(self, is_buy: bool) -> OrderBuilder<NeedsSize> {
+        OrderBuilder {
+            state: NeedsSize {
+                coin: self.state.coin,
+                is_buy,
+            },
+        }
+    }
+}
+
+impl OrderBuilder<NeedsSize> {
+    pub fn size(self, sz: f64) -> OrderBuilder<NeedsPrice> {
+        OrderBuilder {
+            state: NeedsPrice {
+                coin: self.state.coin,
+                is_buy: self.state.is_buy,
+                sz,
+            },
+        }
+    }
+}
+
+impl OrderBuilder<NeedsPrice> {
+    pub fn limit(self, limit_px: f64) -> OrderBuilder<NeedsTif> {
+        let NeedsPrice { coin, is_buy, sz } = self.state;
+        OrderBuilder {
+            state: NeedsTif {
+                coin,
+                is_buy,
+                sz,
+                limit_px,
+            },
+        }
+    }
+
+    pub fn take_profit(self, trigger_px: f64) -> OrderBuilder<NeedsTriggerExecution> {
+        self.trigger(trigger_px, "tp")
+    }
+
+    pub fn stop_loss(self, trigger_px: f64) -> OrderBuilder<NeedsTriggerExecution> {
+        self.trigger(trigger_px, "sl")
+    }
+
+    fn trigger(self, trigger_px: f64, tpsl: &'static str) -> OrderBuilder<NeedsTriggerExecution> {
+        let NeedsPrice { coin, is_buy, sz } = self.state;
+        OrderBuilder {
+            state: NeedsTriggerExecution {
+                coin,
+                is_buy,
+                sz,
+                trigger_px,
+                tpsl,
+            },
+        }
+    }
+}
+
+impl OrderBuilder<NeedsTif> {
+    pub fn gtc(self) -> OrderBuilder<Ready> {
+        self.tif("Gtc")
+    }
+
+    pub fn ioc(self) -> OrderBuilder<Ready> {
+        self.tif("Ioc")
+    }
+
+    // Post only: rejected instead of crossing the book.
+    pub fn alo(self) -> OrderBuilder<Ready> {
+        self.tif("Alo")
+    }
+
+    fn tif(self, tif: &str) -> OrderBuilder<Ready> {
+        let NeedsTif {
+            coin,
+            is_buy,
+            sz,
+            limit_px,
+        } = self.state;
+        OrderBuilder::ready(
+            coin,
+            is_buy,
+            sz,
+            limit_px,
+            ClientOrder::Limit(ClientLimit {
+                tif: tif.to_string(),
+            }),
+        )
+    }
+}
+
+impl OrderBuilder<NeedsTriggerExecution> {
+    // Executes as a market order once triggered, with `limit_px` bounding the slippage.
+    pub fn market(self, limit_px: f64) -> OrderBuilder<Ready> {
+        self.execution(limit_px, true)
+    }
+
+    // Rests as a limit order at `limit_px` once triggered.
+    pub fn limit(self, limit_px: f64) -> OrderBuilder<Ready> {
+        self.execution(limit_px, false)
+    }
+
+    fn execution(self, limit_px: f64, is_market: bool) -> OrderBuilder<Ready> {
+        let NeedsTriggerExecution {
+            coin,
+            is_buy,
+            sz,
+            trigger_px,
+            tpsl,
+        } = self.state;
+        OrderBuilder::ready(
+            coin,
+            is_buy,
+            sz,
+            limit_px,
+            ClientOrder::Trigger(ClientTrigger {
+                is_market,
+                trigger_px,
+                tpsl: tpsl.to_string(),
+            }),
+        )
+    }
+}
+
+impl OrderBuilder<Ready> {
+    fn ready(
+        coin: String,
+        is_buy: bool,
+        sz: f64,
+        limit_px: f64,
+        order_type: ClientOrder,
+    ) -> OrderBuilder<Ready> {
+        OrderBuilder {
+            state: Ready {
+                request: ClientOrderRequest {
+                    asset: coin,
+                    is_buy,
+                    reduce_only: false,
+                    limit_px,
+                    sz,
+                    cloid: None,
+                    order_type,
+                },
+            },
+        }
+    }
+
+    pub fn reduce_only(mut self) -> OrderBuilder<Ready> {
+        self.state.request.reduce_only = true;
+        self
+    }
+
+    pub fn cloid(mut self, cloid: Uuid) -> OrderBuilder<Ready> {
+        self.state.request.cloid = Some(cloid);
+        self
+    }
+
+    pub fn build(self) -> ClientOrderRequest {
+        self.state.request
+    }
+}
+
+impl From<OrderBuilder<Ready>> for ClientOrderRequest {
+    fn from(builder: OrderBuilder<Ready>) -> ClientOrderRequest {
+        builder.build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_order_builder() {
+        let order = OrderBuilder::new("ETH")
+            .buy()
+            .size(0.1)
+            .limit(1800.0)
+            .alo()
+            .build();
+        assert_eq!(order.asset, "ETH");
+        assert!(order.is_buy && !order.reduce_only);
+        assert!(
+            matches!(order.order_type, ClientOrder::Limit(ClientLimit { ref tif }) if tif == "Alo")
+        );
+
+        let cloid = Uuid::new_v4();
+        let order = OrderBuilder::new("ETH")
+            .sell()
+            .size(0.1)
+            .stop_loss(1700.0)
+            .market(1650.0)
+            .reduce_only()
+            .cloid(cloid)
+            .build();
+        assert!(!order.is_buy && order.reduce_only);
+        assert_eq!(order.limit_px, 1650.0);
+        assert_eq!(order.cloid, Some(cloid));
+        let ClientOrder::Trigger(trigger) = order.order_type else {
+            panic!("expected a trigger order");
+        };
+        assert!(trigger.is_market);
+        assert_eq!(trigger.trigger_px, 1700.0);
+        assert_eq!(trigger.tpsl, "sl");
+    }
+}
@@ -0,0 +1,52 @@
+use ethers::types::H160;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum NodeIp {
+    Ip(String),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ValidatorProfile {
+    pub node_ip: NodeIp,
+    pub name: String,
+    pub description: String,
+    pub delegations_disabled: bool,
+    pub commission_bps: u64,
+    pub signer: H160,
+}
+
+// Every field left as `None` keeps its current value.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ValidatorProfileChange {
+    pub node_ip: Option<NodeIp>,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub unjailed: bool,
+    pub disable_delegations: Option<bool>,
+    pub commission_bps: Option<u64>,
+    pub signer: Option<H160>,
+}
+
+// Actions sent by a validator's signer key. The unit payloads are sent as null, hence `()`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub enum CSignerAction {
+    UnjailSelf(()),
+    JailSelf(()),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub enum CValidatorAction {
+    // Only the variant names are camelCase; the fields go out in snake_case, as the exchange
+    // hashes them
+    Register {
+        profile: ValidatorProfile,
+        unjailed: bool,
+        // Initial self-delegation, in HYPE wei
+        initial_wei: u64,
+    },
+    ChangeProfile(ValidatorProfileChange),
+    Unregister(()),
+}
@@ -0,0 +1,194 @@
+use crate::{
+    prelude::*, ClientCancelRequest, ClientOrderRequest, Error, ExchangeClient, ExchangeDataStatus,
+    ExchangeResponseStatus, Timestamp,
+};
+use log::{error, warn};
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+use tokio::{
+    sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+    task::JoinHandle,
+    time,
+};
+
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+const IDLE_WAIT: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expiry {
+    pub coin: String,
+    pub oid: u64,
+    pub expires_at: Timestamp,
+}
+
+// Pending expiries ordered by deadline.
+#[derive(Debug, Default)]
+pub struct ExpiryQueue {
+    pending: BTreeMap<(Timestamp, u64), String>,
+}
+
+impl ExpiryQueue {
+    pub fn push(&mut self, expiry: Expiry) {
+        self.pending
+            .insert((expiry.expires_at, expiry.oid), expiry.coin);
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    pub fn next_deadline(&self) -> Option<Timestamp> {
+        self.pending
+            .keys()
+            .next()
+            .map(|(expires_at, _)| *expires_at)
+    }
+
+    pub fn pop_due(&mut self, now: Timestamp) -> Vec<Expiry> {
+        let not_due = self.pending.split_off(&(now + Duration::from_millis(1), 0));
+        std::mem::replace(&mut self.pending, not_due)
+            .into_iter()
+            .map(|((expires_at, oid), coin)| Expiry {
+                coin,
+                oid,
+                expires_at,
+            })
+            .collect()
+    }
+}
+
+// Emulates good-til-time orders, which the exchange doesn't support, by canceling orders
+// client-side once their deadline passes. Cancels that fail (e.g. while the connection is
+// down) are retried every second until the exchange answers; an order that already filled or
+// was canceled simply reports an error status and is dropped. Dropping the scheduler stops
+// intake but pending expiries are still canceled before the task exits.
+pub struct ExpiryScheduler {
+    pub exchange_client: Arc<ExchangeClient>,
+    sender: UnboundedSender<Expiry>,
+    handle: JoinHandle<()>,
+}
+
+impl ExpiryScheduler {
+    pub fn spawn(exchange_client: Arc<ExchangeClient>) -> ExpiryScheduler {
+        let (sender, receiver) = unbounded_channel();
+        let handle = tokio::spawn(run(exchange_client.clone(), receiver));
+        ExpiryScheduler {
+            exchange_client,
+            sender,
+            handle,
+        }
+    }
+
+    // Places `order` and schedules its cancel at `expires_at` if it rests.
+    pub async fn order(
+        &self,
+        order: ClientOrderRequest,
+        expires_at: Timestamp,
+    ) -> Result<ExchangeResponseStatus> {
+        let coin = order.asset.clone();
+        let response = self.exchange_client.order(order, None).await?;
+        if let ExchangeResponseStatus::Ok(exchange_response) = &response {
+            let statuses = exchange_response
+                .data
+                .iter()
+                .flat_map(|data| &data.statuses);
+            for status in statuses {
+                if let ExchangeDataStatus::Resting(resting) = status {
+                    self.schedule(&coin, resting.oid, expires_at);
+                }
+            }
+        }
+        Ok(response)
+    }
+
+    // Schedules the cancel of an order placed elsewhere.
+    pub fn schedule(&self, coin: &str, oid: u64, expires_at: Timestamp) {
+        let expiry = Expiry {
+            coin: coin.to_string(),
+            oid,
+            expires_at,
+        };
+        if self.sender.send(expiry).is_err() {
+            error!("Expiry task stopped, order {oid} won't be canceled");
+        }
+    }
+
+    pub fn abort(&self) {
+        self.handle.abort();
+    }
+}
+
+async fn run(exchange_client: Arc<ExchangeClient>, mut receiver: UnboundedReceiver<Expiry>) {
+    let mut queue = ExpiryQueue::default();
+    let mut open = true;
+    loop {
+        let wait = match queue.next_deadline() {
+            Some(deadline) => deadline - Timestamp::now(),
+            None if open => IDLE_WAIT,
+            None => return,
+        };
+        tokio::select! {
+            expiry = receiver.recv(), if open => match expiry {
+                Some(expiry) => queue.push(expiry),
+                None => open = false,
+            },
+            _ = time::sleep(wait) => {
+                let due = queue.pop_due(Timestamp::now());
+                if due.is_empty() {
+                    continue;
+                }
+                let cancels = due
+                    .iter()
+                    .map(|expiry| ClientCancelRequest {
+                        asset: expiry.coin.clone(),
+                        oid: expiry.oid,
+                    })
+                    .collect();
+                match exchange_client.bulk_cancel(cancels, None).await {
+                    Ok(ExchangeResponseStatus::Ok(_)) => {}
+                    Err(Error::AssetNotFound) => {
+                        error!("Dropping expiries with an unknown coin: {due:?}");
+                    }
+                    result => {
+                        warn!("Canceling expired orders failed, retrying: {result:?}");
+                        let retry_at = Timestamp::now() + RETRY_DELAY;
+                        for expiry in due {
+                            queue.push(Expiry {
+                                expires_at: retry_at,
+                                ..expiry
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expiry_queue() {
+        let expiry = |oid, expires_at| Expiry {
+            coin: "ETH".to_string(),
+            oid,
+            expires_at: Timestamp::from_millis(expires_at),
+        };
+        let mut queue = ExpiryQueue::default();
+        queue.push(expiry(1, 2_000));
+        queue.push(expiry(2, 1_000));
+        queue.push(expiry(3, 1_000));
+        assert_eq!(queue.next_deadline(), Some(Timestamp::from_millis(1_000)));
+
+        assert!(queue.pop_due(Timestamp::from_millis(999)).is_empty());
+        let due = queue.pop_due(Timestamp::from_millis(1_000));
+        assert_eq!(due, vec![expiry(2, 1_000), expiry(3, 1_000)]);
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue.next_deadline(), Some(Timestamp::from_millis(2_000)));
+    }
+}
@@ -0,0 +1,58 @@
+use crate::{
+    explorer::{BlockDetailsResponse, TxDetailsResponse, UserDetailsResponse},
+    prelude::*,
+    req::HttpClient,
+    BaseUrl, Error,
+};
+
+use ethers::types::H160;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(tag = "type")]
+#[serde(rename_all = "camelCase")]
+pub enum ExplorerRequest {
+    BlockDetails { height: u64 },
+    TxDetails { hash: String },
+    UserDetails { user: H160 },
+}
+
+pub struct ExplorerClient {
+    pub http_client: HttpClient,
+}
+
+impl ExplorerClient {
+    pub async fn new(client: Option<Client>, base_url: Option<BaseUrl>) -> Result<ExplorerClient> {
+        let client = client.unwrap_or_default();
+        let base_url = base_url.unwrap_or(BaseUrl::Mainnet).get_rpc_url();
+
+        Ok(ExplorerClient {
+            http_client: HttpClient { client, base_url },
+        })
+    }
+
+    pub async fn block_details(&self, height: u64) -> Result<BlockDetailsResponse> {
+        let input = ExplorerRequest::BlockDetails { height };
+        let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
+
+        let return_data = self.http_client.post("/explorer", data).await?;
+        serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))
+    }
+
+    pub async fn tx_details(&self, hash: String) -> Result<TxDetailsResponse> {
+        let input = ExplorerRequest::TxDetails { hash };
+        let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
+
+        let return_data = self.http_client.post("/explorer", data).await?;
+        serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))
+    }
+
+    pub async fn user_details(&self, address: H160) -> Result<UserDetailsResponse> {
+        let input = ExplorerRequest::UserDetails { user: address };
+        let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
+
+        let return_data = self.http_client.post("/explorer", data).await?;
+        serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))
+    }
+}
@@ -0,0 +1,4 @@
+pub(super) mod explorer_client;
+mod response_structs;
+
+pub use response_structs::*;
@@ -0,0 +1,41 @@
+use crate::Timestamp;
+use ethers::types::H160;
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ExplorerTx {
+    pub time: Timestamp,
+    pub user: H160,
+    pub action: serde_json::Value,
+    pub block: u64,
+    pub hash: String,
+    pub error: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockDetails {
+    pub height: u64,
+    pub block_time: Timestamp,
+    pub hash: String,
+    pub proposer: H160,
+    pub num_txs: u64,
+    pub txs: Vec<ExplorerTx>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockDetailsResponse {
+    pub block_details: BlockDetails,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct TxDetailsResponse {
+    pub tx: ExplorerTx,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct UserDetailsResponse {
+    pub txs: Vec<ExplorerTx>,
+}
@@ -0,0 +1,301 @@
+use crate::{
+    prelude::*, Error, LedgerUpdate, LedgerUpdateData, RealizedGain, TradeInfo, UserFunding,
+};
+use std::io::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    Utf8,
+    UInt64,
+    Boolean,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExportValue {
+    Utf8(Option<String>),
+    UInt64(u64),
+    Boolean(bool),
+}
+
+// A record with a fixed, ordered column schema. Columns are only ever appended to keep
+// downstream pipelines stable.
+pub trait ExportRecord {
+    const COLUMNS: &'static [(&'static str, ColumnType)];
+
+    fn values(&self) -> Vec<ExportValue>;
+}
+
+impl ExportRecord for TradeInfo {
+    const COLUMNS: &'static [(&'static str, ColumnType)] = &[
+        ("time", ColumnType::UInt64),
+        ("coin", ColumnType::Utf8),
+        ("side", ColumnType::Utf8),
+        ("px", ColumnType::Utf8),
+        ("sz", ColumnType::Utf8),
+        ("dir", ColumnType::Utf8),
+        ("start_position", ColumnType::Utf8),
+        ("closed_pnl", ColumnType::Utf8),
+        ("fee", ColumnType::Utf8),
+        ("crossed", ColumnType::Boolean),
+        ("oid", ColumnType::UInt64),
+        ("tid", ColumnType::UInt64),
+        ("cloid", ColumnType::Utf8),
+        ("hash", ColumnType::Utf8),
+    ];
+
+    fn values(&self) -> Vec<ExportValue> {
+        vec![
+            ExportValue::UInt64(self.time.as_millis()),
+            ExportValue::Utf8(Some(self.coin.clone())),
+            ExportValue::Utf8(Some(self.side.clone())),
+            ExportValue::Utf8(Some(self.px.clone())),
+            ExportValue::Utf8(Some(self.sz.clone())),
+            ExportValue::Utf8(Some(self.dir.clone())),
+            ExportValue::Utf8(Some(self.start_position.clone())),
+            ExportValue::Utf8(Some(self.closed_pnl.clone())),
+            ExportValue::Utf8(Some(self.fee.clone())),
+            ExportValue::Boolean(self.crossed),
+            ExportValue::UInt64(self.oid),
+            ExportValue::UInt64(self.tid),
+            ExportValue::Utf8(self.cloid.clone()),
+            ExportValue::Utf8(Some(self.hash.clone())),
+        ]
+    }
+}
+
+impl ExportRecord for UserFunding {
+    const COLUMNS: &'static [(&'static str, ColumnType)] = &[
+        ("time", ColumnType::UInt64),
+        ("coin", ColumnType::Utf8),
+        ("usdc", ColumnType::Utf8),
+        ("szi", ColumnType::Utf8),
+        ("funding_rate", ColumnType::Utf8),
+    ];
+
+    fn values(&self) -> Vec<ExportValue> {
+        vec![
+            ExportValue::UInt64(self.time.as_millis()),
+            ExportValue::Utf8(Some(self.coin.clone())),
+            ExportValue::Utf8(Some(self.usdc.clone())),
+            ExportValue::Utf8(Some(self.szi.clone())),
+            ExportValue::Utf8(Some(self.funding_rate.clone())),
+        ]
+    }
+}
+
+impl LedgerUpdate {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            LedgerUpdate::Deposit(_) => "deposit",
+            LedgerUpdate::Withdraw(_) => "withdraw",
+            LedgerUpdate::InternalTransfer(_) => "internalTransfer",
+            LedgerUpdate::SubAccountTransfer(_) => "subAccountTransfer",
+            LedgerUpdate::LedgerLiquidation(_) => "ledgerLiquidation",
+            LedgerUpdate::VaultDeposit(_) => "vaultDeposit",
+            LedgerUpdate::VaultCreate(_) => "vaultCreate",
+            LedgerUpdate::VaultDistribution(_) => "vaultDistribution",
+            LedgerUpdate::VaultWithdraw(_) => "vaultWithdraw",
+            LedgerUpdate::VaultLeaderCommission(_) => "vaultLeaderCommission",
+            LedgerUpdate::AccountClassTransfer(_) => "accountClassTransfer",
+            LedgerUpdate::SpotTransfer(_) => "spotTransfer",
+            LedgerUpdate::SpotGenesis(_) => "spotGenesis",
+        }
+    }
+
+    // USDC amount moved by the update, when it has one.
+    pub fn usdc(&self) -> Option<&str> {
+        match self {
+            LedgerUpdate::Deposit(delta) => Some(&delta.usdc),
+            LedgerUpdate::Withdraw(delta) => Some(&delta.usdc),
+            LedgerUpdate::InternalTransfer(delta) => Some(&delta.usdc),
+            LedgerUpdate::SubAccountTransfer(delta) => Some(&delta.usdc),
+            LedgerUpdate::VaultDeposit(delta)
+            | LedgerUpdate::VaultCreate(delta)
+            | LedgerUpdate::VaultDistribution(delta) => Some(&delta.usdc),
+            LedgerUpdate::VaultWithdraw(delta) => Some(&delta.net_withdrawn_usd),
+            LedgerUpdate::VaultLeaderCommission(delta) => Some(&delta.usdc),
+            LedgerUpdate::AccountClassTransfer(delta) => Some(&delta.usdc),
+            LedgerUpdate::SpotTransfer(delta) => Some(&delta.usdc_value),
+            LedgerUpdate::LedgerLiquidation(_) | LedgerUpdate::SpotGenesis(_) => None,
+        }
+    }
+
+    pub fn fee(&self) -> Option<&str> {
+        match self {
+            LedgerUpdate::Withdraw(delta) => Some(&delta.fee),
+            LedgerUpdate::InternalTransfer(delta) => Some(&delta.fee),
+            LedgerUpdate::SpotTransfer(delta) => Some(&delta.fee),
+            _ => None,
+        }
+    }
+}
+
+impl ExportRecord for LedgerUpdateData {
+    const COLUMNS: &'static [(&'static str, ColumnType)] = &[
+        ("time", ColumnType::UInt64),
+        ("hash", ColumnType::Utf8),
+        ("type", ColumnType::Utf8),
+        ("usdc", ColumnType::Utf8),
+        ("fee", ColumnType::Utf8),
+        ("delta", ColumnType::Utf8),
+    ];
+
+    fn values(&self) -> Vec<ExportValue> {
+        vec![
+            ExportValue::UInt64(self.time.as_millis()),
+            ExportValue::Utf8(Some(self.hash.clone())),
+            ExportValue::Utf8(Some(self.delta.type_name().to_string())),
+            ExportValue::Utf8(self.delta.usdc().map(str::to_string)),
+            ExportValue::Utf8(self.delta.fee().map(str::to_string)),
+            ExportValue::Utf8(serde_json::to_string(&self.delta).ok()),
+        ]
+    }
+}
+
+// Realized lots for tax reporting, one row per lot part closed.
+impl ExportRecord for RealizedGain {
+    const COLUMNS: &'static [(&'static str, ColumnType)] = &[
+        ("open_time", ColumnType::UInt64),
+        ("close_time", ColumnType::UInt64),
+        ("coin", ColumnType::Utf8),
+        ("is_spot", ColumnType::Boolean),
+        ("side", ColumnType::Utf8),
+        ("sz", ColumnType::Utf8),
+        ("open_px", ColumnType::Utf8),
+        ("close_px", ColumnType::Utf8),
+        ("gain", ColumnType::Utf8),
+        ("close_tid", ColumnType::UInt64),
+    ];
+
+    fn values(&self) -> Vec<ExportValue> {
+        let side = if self.is_long { "long" } else { "short" };
+        vec![
+            ExportValue::UInt64(self.open_time.as_millis()),
+            ExportValue::UInt64(self.close_time.as_millis()),
+            ExportValue::Utf8(Some(self.coin.clone())),
+            ExportValue::Boolean(self.is_spot),
+            ExportValue::Utf8(Some(side.to_string())),
+            ExportValue::Utf8(Some(self.sz.to_string())),
+            ExportValue::Utf8(Some(self.open_px.to_string())),
+            ExportValue::Utf8(Some(self.close_px.to_string())),
+            ExportValue::Utf8(Some(self.gain.to_string())),
+            ExportValue::UInt64(self.close_tid),
+        ]
+    }
+}
+
+fn csv_field(value: &ExportValue) -> String {
+    let raw = match value {
+        ExportValue::Utf8(value) => value.clone().unwrap_or_default(),
+        ExportValue::UInt64(value) => value.to_string(),
+        ExportValue::Boolean(value) => value.to_string(),
+    };
+    if raw.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", raw.replace('"', "\"\""))
+    } else {
+        raw
+    }
+}
+
+pub fn write_csv<W: Write, R: ExportRecord>(mut writer: W, records: &[R]) -> Result<()> {
+    let header: Vec<&str> = R::COLUMNS.iter().map(|(name, _)| *name).collect();
+    writeln!(writer, "{}", header.join(",")).map_err(|e| Error::Export(e.to_string()))?;
+    for record in records {
+        let row: Vec<String> = record.values().iter().map(csv_field).collect();
+        writeln!(writer, "{}", row.join(",")).map_err(|e| Error::Export(e.to_string()))?;
+    }
+    writer.flush().map_err(|e| Error::Export(e.to_string()))
+}
+
+#[cfg(feature = "parquet")]
+pub fn write_parquet<W: Write + Send, R: ExportRecord>(writer: W, records: &[R]) -> Result<()> {
+    use arrow_array::{ArrayRef, BooleanArray, RecordBatch, StringArray, UInt64Array};
+    use arrow_schema::{DataType, Field, Schema};
+    use parquet::arrow::ArrowWriter;
+    use std::sync::Arc;
+
+    let schema = Arc::new(Schema::new(
+        R::COLUMNS
+            .iter()
+            .map(|(name, column_type)| match column_type {
+                ColumnType::Utf8 => Field::new(*name, DataType::Utf8, true),
+                ColumnType::UInt64 => Field::new(*name, DataType::UInt64, false),
+                ColumnType::Boolean => Field::new(*name, DataType::Boolean, false),
+            })
+            .collect::<Vec<_>>(),
+    ));
+
+    let rows: Vec<Vec<ExportValue>> = records.iter().map(ExportRecord::values).collect();
+    let columns = R::COLUMNS
+        .iter()
+        .enumerate()
+        .map(|(i, (_, column_type))| -> ArrayRef {
+            let values = rows.iter().map(|row| &row[i]);
+            match column_type {
+                ColumnType::Utf8 => Arc::new(StringArray::from_iter(values.map(|v| match v {
+                    ExportValue::Utf8(v) => v.clone(),
+                    _ => None,
+                }))),
+                ColumnType::UInt64 => {
+                    Arc::new(UInt64Array::from_iter_values(values.map(|v| match v {
+                        ExportValue::UInt64(v) => *v,
+                        _ => 0,
+                    })))
+                }
+                ColumnType::Boolean => Arc::new(BooleanArray::from_iter(values.map(|v| match v {
+                    ExportValue::Boolean(v) => Some(*v),
+                    _ => None,
+                }))),
+            }
+        })
+        .collect();
+
+    let batch = RecordBatch::try_new(Arc::clone(&schema), columns)
+        .map_err(|e| Error::Export(e.to_string()))?;
+    let mut writer =
+        ArrowWriter::try_new(writer, schema, None).map_err(|e| Error::Export(e.to_string()))?;
+    writer
+        .write(&batch)
+        .map_err(|e| Error::Export(e.to_string()))?;
+    writer.close().map_err(|e| Error::Export(e.to_string()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Deposit, Timestamp, Withdraw};
+
+    #[test]
+    fn test_ledger_updates_csv() -> Result<()> {
+        let updates = vec![
+            LedgerUpdateData {
+                time: Timestamp::from_millis(1),
+                hash: "0xabc".to_string(),
+                delta: LedgerUpdate::Deposit(Deposit {
+                    usdc: "100.0".to_string(),
+                }),
+            },
+            LedgerUpdateData {
+                time: Timestamp::from_millis(2),
+                hash: "0xdef".to_string(),
+                delta: LedgerUpdate::Withdraw(Withdraw {
+                    usdc: "50.0".to_string(),
+                    nonce: 3,
+                    fee: "1.0".to_string(),
+                }),
+            },
+        ];
+        let mut out = Vec::new();
+        write_csv(&mut out, &updates)?;
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines[0], "time,hash,type,usdc,fee,delta");
+        assert_eq!(
+            lines[1],
+            r#"1,0xabc,deposit,100.0,,"{""type"":""deposit"",""usdc"":""100.0""}""#
+        );
+        assert!(lines[2].starts_with("2,0xdef,withdraw,50.0,1.0,"));
+        Ok(())
+    }
+}
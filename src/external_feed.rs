@@ -0,0 +1,205 @@
+use crate::{prelude::*, Error, MidCache, Timestamp};
+use futures_util::{SinkExt, StreamExt};
+use log::{debug, warn};
+use serde::Deserialize;
+use std::{collections::HashMap, future::Future};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+use tokio_tungstenite::{connect_async, tungstenite::protocol};
+
+// A price from another venue, keyed by that venue's symbol.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExternalPrice {
+    pub venue: &'static str,
+    pub symbol: String,
+    pub px: f64,
+    pub time: Timestamp,
+}
+
+// A source of reference prices from another venue, to compare with Hyperliquid's.
+pub trait ExternalPriceFeed {
+    // Streams prices of `symbols`, named as the venue names them, until the receiver is
+    // dropped or the feed disconnects.
+    fn subscribe(
+        &self,
+        symbols: &[&str],
+    ) -> impl Future<Output = Result<UnboundedReceiver<ExternalPrice>>> + Send;
+}
+
+// Mids of Binance's best bid and offer, from its bookTicker stream.
+pub struct BinanceFeed {
+    url: String,
+}
+
+impl BinanceFeed {
+    pub fn spot() -> BinanceFeed {
+        BinanceFeed {
+            url: "wss://stream.binance.com:9443/stream".to_string(),
+        }
+    }
+
+    pub fn usd_futures() -> BinanceFeed {
+        BinanceFeed {
+            url: "wss://fstream.binance.com/stream".to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct BinanceEnvelope {
+    data: BinanceBookTicker,
+}
+
+#[derive(Deserialize)]
+struct BinanceBookTicker {
+    #[serde(rename = "s")]
+    symbol: String,
+    #[serde(rename = "b")]
+    bid: String,
+    #[serde(rename = "a")]
+    ask: String,
+    // Only sent by the futures stream
+    #[serde(rename = "E")]
+    event_time: Option<u64>,
+}
+
+fn parse_book_ticker(text: &str) -> Option<ExternalPrice> {
+    let ticker = serde_json::from_str::<BinanceEnvelope>(text).ok()?.data;
+    let bid: f64 = ticker.bid.parse().ok()?;
+    let ask: f64 = ticker.ask.parse().ok()?;
+    Some(ExternalPrice {
+        venue: "binance",
+        symbol: ticker.symbol,
+        px: (bid + ask) / 2.0,
+        time: ticker
+            .event_time
+            .map_or_else(Timestamp::now, Timestamp::from_millis),
+    })
+}
+
+impl ExternalPriceFeed for BinanceFeed {
+    async fn subscribe(&self, symbols: &[&str]) -> Result<UnboundedReceiver<ExternalPrice>> {
+        let streams: Vec<String> = symbols
+            .iter()
+            .map(|symbol| format!("{}@bookTicker", symbol.to_lowercase()))
+            .collect();
+        let url = format!("{}?streams={}", self.url, streams.join("/"));
+        let (ws_stream, _) = connect_async(url)
+            .await
+            .map_err(|e| Error::Websocket(e.to_string()))?;
+        let (mut writer, mut reader) = ws_stream.split();
+
+        let (sender, receiver) = unbounded_channel();
+        tokio::spawn(async move {
+            while let Some(frame) = reader.next().await {
+                match frame {
+                    Ok(protocol::Message::Text(text)) => match parse_book_ticker(&text) {
+                        Some(price) => {
+                            if sender.send(price).is_err() {
+                                return;
+                            }
+                        }
+                        None => debug!("Unexpected Binance message {text}"),
+                    },
+                    // Binance disconnects clients that don't answer its pings
+                    Ok(protocol::Message::Ping(payload)) => {
+                        if let Err(err) = writer.send(protocol::Message::Pong(payload)).await {
+                            warn!("Binance pong failed: {err}");
+                            return;
+                        }
+                    }
+                    Ok(protocol::Message::Close(_)) => return,
+                    Ok(_) => {}
+                    Err(err) => {
+                        warn!("Binance feed failed: {err}");
+                        return;
+                    }
+                }
+            }
+        });
+        Ok(receiver)
+    }
+}
+
+// An external price joined with the Hyperliquid mid of the matching coin.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Basis {
+    pub coin: String,
+    pub mid: f64,
+    pub external: ExternalPrice,
+    // (Hyperliquid mid - external price) / external price
+    pub basis_bps: f64,
+}
+
+// Joins each external price with the latest mid in `mids` of the coin `coins` maps its symbol
+// to, e.g. "ETHUSDT" to "ETH". Prices of unmapped symbols or coins without a mid are dropped.
+pub fn join_mids(
+    mut external: UnboundedReceiver<ExternalPrice>,
+    mids: MidCache,
+    coins: HashMap<String, String>,
+) -> UnboundedReceiver<Basis> {
+    let (sender, receiver) = unbounded_channel();
+    tokio::spawn(async move {
+        while let Some(price) = external.recv().await {
+            let Some(coin) = coins.get(&price.symbol) else {
+                continue;
+            };
+            let Some(mid) = mids.get(coin) else {
+                continue;
+            };
+            let basis = Basis {
+                coin: coin.clone(),
+                mid,
+                basis_bps: (mid - price.px) / price.px * 10_000.0,
+                external: price,
+            };
+            if sender.send(basis).is_err() {
+                return;
+            }
+        }
+    });
+    receiver
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_book_ticker() {
+        let price = parse_book_ticker(
+            r#"{"stream":"ethusdt@bookTicker","data":{"e":"bookTicker","u":400900217,
+            "E":1568014460893,"T":1568014460891,"s":"ETHUSDT","b":"2000.10","B":"31.21",
+            "a":"2000.30","A":"40.66"}}"#,
+        )
+        .unwrap();
+        assert_eq!(price.symbol, "ETHUSDT");
+        assert!((price.px - 2000.2).abs() < 1e-9);
+        assert_eq!(price.time, Timestamp::from_millis(1568014460893));
+        assert!(parse_book_ticker(r#"{"result":null,"id":1}"#).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_join_mids() {
+        let mids = MidCache::default();
+        mids.update(&[("ETH".to_string(), "2001.0".to_string())].into());
+        let (sender, external) = unbounded_channel();
+        let coins = [("ETHUSDT".to_string(), "ETH".to_string())].into();
+        let mut basis = join_mids(external, mids, coins);
+
+        for symbol in ["BTCUSDT", "ETHUSDT"] {
+            sender
+                .send(ExternalPrice {
+                    venue: "binance",
+                    symbol: symbol.to_string(),
+                    px: 2000.0,
+                    time: Timestamp::from_millis(0),
+                })
+                .unwrap();
+        }
+        drop(sender);
+        let eth = basis.recv().await.unwrap();
+        assert_eq!(eth.coin, "ETH");
+        assert!((eth.basis_bps - 5.0).abs() < 1e-9);
+        assert!(basis.recv().await.is_none());
+    }
+}
@@ -0,0 +1,194 @@
+use crate::{prelude::*, Error, ExchangeClient, NoncePartition, Timestamp};
+use log::{info, warn};
+use std::{
+    future::Future,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+// Shared lease deciding which of several bot instances is the leader. Implement it on top of
+// whatever coordination store the deployment has (Redis, etcd, a database row, ...).
+pub trait LeaseProvider {
+    // Acquires the lease for `instance_id`, or renews it if already held, for `ttl`. Returns
+    // whether `instance_id` holds the lease afterwards.
+    fn try_acquire(
+        &self,
+        instance_id: &str,
+        ttl: Duration,
+    ) -> impl Future<Output = Result<bool>> + Send;
+
+    fn release(&self, instance_id: &str) -> impl Future<Output = Result<()>> + Send;
+}
+
+// A lease shared between instances in the same process, mostly useful for tests.
+#[derive(Clone, Default)]
+pub struct InMemoryLease {
+    holder: Arc<Mutex<Option<(String, Instant)>>>,
+}
+
+impl LeaseProvider for InMemoryLease {
+    async fn try_acquire(&self, instance_id: &str, ttl: Duration) -> Result<bool> {
+        let mut holder = self
+            .holder
+            .lock()
+            .map_err(|e| Error::Lease(e.to_string()))?;
+        let now = Instant::now();
+        match &*holder {
+            Some((id, expiry)) if id != instance_id && *expiry > now => Ok(false),
+            _ => {
+                *holder = Some((instance_id.to_string(), now + ttl));
+                Ok(true)
+            }
+        }
+    }
+
+    async fn release(&self, instance_id: &str) -> Result<()> {
+        let mut holder = self
+            .holder
+            .lock()
+            .map_err(|e| Error::Lease(e.to_string()))?;
+        if matches!(&*holder, Some((id, _)) if id == instance_id) {
+            *holder = None;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Leader,
+    Standby,
+}
+
+#[derive(Debug, Clone)]
+pub struct FailoverConfig {
+    pub instance_id: String,
+    // This instance's position among `instance_count` instances, used to partition nonces
+    pub instance_index: u64,
+    pub instance_count: u64,
+    pub lease_ttl: Duration,
+    // A dead man's switch: every tick the leader schedules a cancel of all the account's
+    // orders this far ahead, pushing back the one before. If the leader dies or loses the
+    // lease without stepping down, its orders are swept once it stops renewing; a new leader's
+    // first tick replaces the pending cancel before it quotes. Must exceed the tick interval,
+    // and costs one action per tick.
+    pub leader_cancel_after: Option<Duration>,
+}
+
+// Runs one bot instance of a leader/standby pair (or larger group). Call `tick` more often
+// than `lease_ttl` and only submit orders while `is_leader` is true. Give each instance's
+// ExchangeClient its `nonce_partition`, so actions signed by a former leader can't collide
+// with the new one's.
+pub struct Failover<L: LeaseProvider> {
+    pub config: FailoverConfig,
+    lease: L,
+    role: Option<Role>,
+}
+
+impl<L: LeaseProvider> Failover<L> {
+    pub fn new(lease: L, config: FailoverConfig) -> Failover<L> {
+        Failover {
+            config,
+            lease,
+            role: None,
+        }
+    }
+
+    // The nonces this instance signs with, disjoint from every other instance's:
+    //
+    //     exchange_client.nonce_partition = failover.nonce_partition();
+    pub fn nonce_partition(&self) -> NoncePartition {
+        NoncePartition::new(self.config.instance_index, self.config.instance_count)
+    }
+
+    pub fn is_leader(&self) -> bool {
+        self.role == Some(Role::Leader)
+    }
+
+    // Renews or acquires the lease. Returns the new role when it changed.
+    pub async fn tick(&mut self, exchange_client: &ExchangeClient) -> Result<Option<Role>> {
+        let role = match self
+            .lease
+            .try_acquire(&self.config.instance_id, self.config.lease_ttl)
+            .await
+        {
+            Ok(true) => Role::Leader,
+            Ok(false) => Role::Standby,
+            // Without knowing who holds the lease, stop acting as the leader
+            Err(err) => {
+                warn!("Could not reach lease provider: {err}");
+                Role::Standby
+            }
+        };
+        // Only the lease holder arms the switch, so it never sweeps another leader's orders
+        if role == Role::Leader {
+            if let Some(cancel_after) = self.config.leader_cancel_after {
+                let time = Timestamp::now() + cancel_after;
+                exchange_client.schedule_cancel(Some(time), None).await?;
+            }
+        }
+        if self.role == Some(role) {
+            return Ok(None);
+        }
+        self.role = Some(role);
+        info!("{} is now {role:?}", self.config.instance_id);
+        Ok(Some(role))
+    }
+
+    // Steps down voluntarily, e.g. on shutdown, so a standby can take over without waiting
+    // for the lease to expire.
+    pub async fn release(&mut self) -> Result<()> {
+        self.role = Some(Role::Standby);
+        self.lease.release(&self.config.instance_id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_in_memory_lease() -> Result<()> {
+        let lease = InMemoryLease::default();
+        let ttl = Duration::from_millis(50);
+        assert!(lease.try_acquire("a", ttl).await?);
+        assert!(!lease.try_acquire("b", ttl).await?);
+        // Renewal by the holder
+        assert!(lease.try_acquire("a", ttl).await?);
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert!(lease.try_acquire("b", ttl).await?);
+        assert!(!lease.try_acquire("a", ttl).await?);
+
+        lease.release("b").await?;
+        assert!(lease.try_acquire("a", ttl).await?);
+
+        // A lease poisoned by a panicking holder fails rather than guessing who holds it
+        let holder = Arc::clone(&lease.holder);
+        let _ = std::thread::spawn(move || {
+            let _guard = holder.lock();
+            panic!("holder panicked");
+        })
+        .join();
+        assert!(matches!(
+            lease.try_acquire("a", ttl).await,
+            Err(Error::Lease(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_nonce_partitions_are_per_instance() {
+        let config = |instance_index| FailoverConfig {
+            instance_id: format!("bot-{instance_index}"),
+            instance_index,
+            instance_count: 2,
+            lease_ttl: Duration::from_secs(10),
+            leader_cancel_after: None,
+        };
+        let first = Failover::new(InMemoryLease::default(), config(0));
+        let second = Failover::new(InMemoryLease::default(), config(1));
+        assert_eq!(first.nonce_partition(), NoncePartition::new(0, 2));
+        assert_eq!(second.nonce_partition(), NoncePartition::new(1, 2));
+    }
+}
@@ -0,0 +1,524 @@
+// C ABI for embedding the SDK from other languages. Build a shared library with
+// `cargo rustc --release --features ffi --crate-type cdylib` and see `include/hyperliquid.h`.
+use crate::{
+    prelude::*, BaseUrl, ClientCancelRequest, ClientLimit, ClientOrder, ClientOrderRequest,
+    ClientTrigger, Error, ExchangeClient, InfoClient, InfoRequest, Subscription,
+};
+use ethers::{signers::LocalWallet, types::H160};
+use lazy_static::lazy_static;
+use log::error;
+use std::{
+    cell::RefCell,
+    ffi::{c_char, c_void, CStr, CString},
+    fmt::Display,
+    ptr,
+    str::FromStr,
+};
+use tokio::{runtime::Runtime, sync::mpsc::unbounded_channel};
+use uuid::Uuid;
+
+lazy_static! {
+    static ref RUNTIME: Runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .expect("failed to build FFI runtime");
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+pub type HlMessageCallback = extern "C" fn(message: *const c_char, user_data: *mut c_void);
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub enum HlNetwork {
+    Mainnet = 0,
+    Testnet = 1,
+    Localhost = 2,
+}
+
+impl From<HlNetwork> for BaseUrl {
+    fn from(network: HlNetwork) -> Self {
+        match network {
+            HlNetwork::Mainnet => BaseUrl::Mainnet,
+            HlNetwork::Testnet => BaseUrl::Testnet,
+            HlNetwork::Localhost => BaseUrl::Localhost,
+        }
+    }
+}
+
+// `tpsl` and `cloid` may be null. A null `tpsl` places a limit order using `tif`,
+// otherwise a trigger order using `trigger_px` and `is_market`.
+#[repr(C)]
+pub struct HlOrder {
+    pub asset: *const c_char,
+    pub is_buy: bool,
+    pub reduce_only: bool,
+    pub limit_px: f64,
+    pub sz: f64,
+    pub tif: *const c_char,
+    pub trigger_px: f64,
+    pub is_market: bool,
+    pub tpsl: *const c_char,
+    pub cloid: *const c_char,
+}
+
+struct CallbackContext {
+    callback: HlMessageCallback,
+    user_data: *mut c_void,
+}
+
+// The caller guarantees user_data can be used from the SDK's runtime threads.
+unsafe impl Send for CallbackContext {}
+
+fn set_last_error(err: impl Display) {
+    let message = CString::new(err.to_string().replace('\0', ""))
+        .unwrap_or_else(|_| CString::from(c"unknown error"));
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+unsafe fn str_from_ptr<'a>(ptr: *const c_char) -> Result<&'a str> {
+    if ptr.is_null() {
+        return Err(Error::GenericParse("unexpected null string".to_string()));
+    }
+    CStr::from_ptr(ptr)
+        .to_str()
+        .map_err(|e| Error::GenericParse(e.to_string()))
+}
+
+unsafe fn optional_str_from_ptr<'a>(ptr: *const c_char) -> Result<Option<&'a str>> {
+    if ptr.is_null() {
+        Ok(None)
+    } else {
+        str_from_ptr(ptr).map(Some)
+    }
+}
+
+fn into_c_string(result: Result<String>) -> *mut c_char {
+    match result.and_then(|s| CString::new(s).map_err(|e| Error::GenericParse(e.to_string()))) {
+        Ok(s) => s.into_raw(),
+        Err(err) => {
+            set_last_error(err);
+            ptr::null_mut()
+        }
+    }
+}
+
+unsafe fn order_from_ffi(order: &HlOrder) -> Result<ClientOrderRequest> {
+    let order_type = match optional_str_from_ptr(order.tpsl)? {
+        Some(tpsl) => ClientOrder::Trigger(ClientTrigger {
+            is_market: order.is_market,
+            trigger_px: order.trigger_px,
+            tpsl: tpsl.to_string(),
+        }),
+        None => ClientOrder::Limit(ClientLimit {
+            tif: str_from_ptr(order.tif)?.to_string(),
+        }),
+    };
+    let cloid = optional_str_from_ptr(order.cloid)?
+        .map(Uuid::from_str)
+        .transpose()
+        .map_err(|e| Error::GenericParse(e.to_string()))?;
+
+    Ok(ClientOrderRequest {
+        asset: str_from_ptr(order.asset)?.to_string(),
+        is_buy: order.is_buy,
+        reduce_only: order.reduce_only,
+        limit_px: order.limit_px,
+        sz: order.sz,
+        cloid,
+        order_type,
+    })
+}
+
+/// Returns the last error raised on the calling thread, or null if there was none.
+/// The string is owned by the SDK and valid until the next failing call on this thread.
+#[no_mangle]
+pub extern "C" fn hl_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map(|message| message.as_ptr())
+            .unwrap_or(ptr::null())
+    })
+}
+
+/// # Safety
+///
+/// `s` must be null or a string returned by this library that has not already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn hl_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn hl_info_client_new(network: HlNetwork) -> *mut InfoClient {
+    match RUNTIME.block_on(InfoClient::new(None, Some(network.into()))) {
+        Ok(info_client) => Box::into_raw(Box::new(info_client)),
+        Err(err) => {
+            set_last_error(err);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// # Safety
+///
+/// `client` must be null or a pointer returned by `hl_info_client_new` that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn hl_info_client_free(client: *mut InfoClient) {
+    if !client.is_null() {
+        let client = Box::from_raw(client);
+        let _guard = RUNTIME.enter();
+        drop(client);
+    }
+}
+
+/// Posts an info request given as JSON (e.g. `{"type":"allMids"}`) and returns the raw JSON
+/// response, or null on error. The result must be released with `hl_string_free`.
+///
+/// # Safety
+///
+/// `client` must be a live pointer from `hl_info_client_new` and `request` a valid C string.
+#[no_mangle]
+pub unsafe extern "C" fn hl_info(client: *const InfoClient, request: *const c_char) -> *mut c_char {
+    let Some(client) = client.as_ref() else {
+        set_last_error(Error::GenericParse("null info client".to_string()));
+        return ptr::null_mut();
+    };
+    let result = str_from_ptr(request).and_then(|request| {
+        let request = serde_json::from_str::<InfoRequest>(request)
+            .map_err(|e| Error::JsonParse(e.to_string()))?;
+        let data = serde_json::to_string(&request).map_err(|e| Error::JsonParse(e.to_string()))?;
+        RUNTIME.block_on(client.http_client.post("/info", data))
+    });
+    into_c_string(result)
+}
+
+/// Subscribes to a websocket channel given as JSON (e.g. `{"type":"trades","coin":"ETH"}`).
+/// Every message is delivered as a JSON string to `callback` on an SDK-owned thread; the string
+/// is only valid for the duration of the call. Returns the subscription id, or -1 on error.
+///
+/// # Safety
+///
+/// `client` must be a live pointer from `hl_info_client_new`, `subscription` a valid C string,
+/// and `user_data` safe to use from other threads until the subscription is removed.
+#[no_mangle]
+pub unsafe extern "C" fn hl_subscribe(
+    client: *mut InfoClient,
+    subscription: *const c_char,
+    callback: HlMessageCallback,
+    user_data: *mut c_void,
+) -> i64 {
+    let Some(client) = client.as_mut() else {
+        set_last_error(Error::GenericParse("null info client".to_string()));
+        return -1;
+    };
+    let subscription = match str_from_ptr(subscription).and_then(|subscription| {
+        serde_json::from_str::<Subscription>(subscription)
+            .map_err(|e| Error::JsonParse(e.to_string()))
+    }) {
+        Ok(subscription) => subscription,
+        Err(err) => {
+            set_last_error(err);
+            return -1;
+        }
+    };
+
+    let (sender, mut receiver) = unbounded_channel();
+    let subscription_id = match RUNTIME.block_on(client.subscribe(subscription, sender)) {
+        Ok(subscription_id) => subscription_id,
+        Err(err) => {
+            set_last_error(err);
+            return -1;
+        }
+    };
+
+    let context = CallbackContext {
+        callback,
+        user_data,
+    };
+    RUNTIME.spawn(async move {
+        let context = context;
+        while let Some(message) = receiver.recv().await {
+            match serde_json::to_string(&message)
+                .map_err(|e| Error::JsonParse(e.to_string()))
+                .and_then(|s| CString::new(s).map_err(|e| Error::GenericParse(e.to_string())))
+            {
+                Ok(message) => (context.callback)(message.as_ptr(), context.user_data),
+                Err(err) => error!("Error serializing message for FFI callback: {err}"),
+            }
+        }
+    });
+
+    subscription_id as i64
+}
+
+/// Returns 0 on success and -1 on error.
+///
+/// # Safety
+///
+/// `client` must be a live pointer from `hl_info_client_new`.
+#[no_mangle]
+pub unsafe extern "C" fn hl_unsubscribe(client: *mut InfoClient, subscription_id: u32) -> i32 {
+    let Some(client) = client.as_mut() else {
+        set_last_error(Error::GenericParse("null info client".to_string()));
+        return -1;
+    };
+    match RUNTIME.block_on(client.unsubscribe(subscription_id)) {
+        Ok(()) => 0,
+        Err(err) => {
+            set_last_error(err);
+            -1
+        }
+    }
+}
+
+/// # Safety
+///
+/// `private_key` must be a valid C string. `vault_address` may be null.
+#[no_mangle]
+pub unsafe extern "C" fn hl_exchange_client_new(
+    private_key: *const c_char,
+    network: HlNetwork,
+    vault_address: *const c_char,
+) -> *mut ExchangeClient {
+    let result = (|| {
+        let wallet = str_from_ptr(private_key)?
+            .parse::<LocalWallet>()
+            .map_err(|e| Error::PrivateKeyParse(e.to_string()))?;
+        let vault_address = optional_str_from_ptr(vault_address)?
+            .map(H160::from_str)
+            .transpose()
+            .map_err(|e| Error::GenericParse(e.to_string()))?;
+        RUNTIME.block_on(ExchangeClient::new(
+            None,
+            wallet,
+            Some(network.into()),
+            None,
+            vault_address,
+        ))
+    })();
+    match result {
+        Ok(exchange_client) => Box::into_raw(Box::new(exchange_client)),
+        Err(err) => {
+            set_last_error(err);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// # Safety
+///
+/// `client` must be null or a pointer returned by `hl_exchange_client_new` that has not been
+/// freed.
+#[no_mangle]
+pub unsafe extern "C" fn hl_exchange_client_free(client: *mut ExchangeClient) {
+    if !client.is_null() {
+        let client = Box::from_raw(client);
+        let _guard = RUNTIME.enter();
+        drop(client);
+    }
+}
+
+/// Places an order and returns the exchange response as JSON, or null on error.
+/// The result must be released with `hl_string_free`.
+///
+/// # Safety
+///
+/// `client` must be a live pointer from `hl_exchange_client_new` and `order` must point to a
+/// valid `HlOrder` whose strings are valid C strings (or null where allowed).
+#[no_mangle]
+pub unsafe extern "C" fn hl_place_order(
+    client: *const ExchangeClient,
+    order: *const HlOrder,
+) -> *mut c_char {
+    let (Some(client), Some(order)) = (client.as_ref(), order.as_ref()) else {
+        set_last_error(Error::GenericParse(
+            "null exchange client or order".to_string(),
+        ));
+        return ptr::null_mut();
+    };
+    let result = order_from_ffi(order).and_then(|order| {
+        let response = RUNTIME.block_on(client.order(order, None))?;
+        serde_json::to_string(&response).map_err(|e| Error::JsonParse(e.to_string()))
+    });
+    into_c_string(result)
+}
+
+/// Cancels an order by oid and returns the exchange response as JSON, or null on error.
+/// The result must be released with `hl_string_free`.
+///
+/// # Safety
+///
+/// `client` must be a live pointer from `hl_exchange_client_new` and `asset` a valid C string.
+#[no_mangle]
+pub unsafe extern "C" fn hl_cancel_order(
+    client: *const ExchangeClient,
+    asset: *const c_char,
+    oid: u64,
+) -> *mut c_char {
+    let Some(client) = client.as_ref() else {
+        set_last_error(Error::GenericParse("null exchange client".to_string()));
+        return ptr::null_mut();
+    };
+    let result = str_from_ptr(asset).and_then(|asset| {
+        let cancel = ClientCancelRequest {
+            asset: asset.to_string(),
+            oid,
+        };
+        let response = RUNTIME.block_on(client.cancel(cancel, None))?;
+        serde_json::to_string(&response).map_err(|e| Error::JsonParse(e.to_string()))
+    });
+    into_c_string(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        req::HttpClient, sim::serve_rest, LatencyRecorder, Meta, NoncePartition, SimExchange,
+    };
+    use serde_json::{json, Value};
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+    };
+
+    fn last_error() -> String {
+        unsafe { CStr::from_ptr(hl_last_error()) }
+            .to_str()
+            .unwrap()
+            .to_string()
+    }
+
+    // Takes ownership of a string returned by the library
+    unsafe fn take_string(s: *mut c_char) -> String {
+        assert!(!s.is_null(), "{}", last_error());
+        let string = CStr::from_ptr(s).to_str().unwrap().to_string();
+        hl_string_free(s);
+        string
+    }
+
+    // Serves the REST API on the FFI runtime, recording requests and answering with `response`
+    fn serve(response: Value) -> (String, Arc<Mutex<Vec<Value>>>) {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let server = RUNTIME
+            .block_on(serve_rest({
+                let requests = requests.clone();
+                move |request| {
+                    requests.lock().unwrap().push(request);
+                    let response = response.clone();
+                    async move { (200, response) }
+                }
+            }))
+            .unwrap();
+        (server.base_url, requests)
+    }
+
+    fn exchange_client(base_url: String) -> ExchangeClient {
+        ExchangeClient {
+            http_client: HttpClient {
+                client: Default::default(),
+                base_url,
+            },
+            wallet: LocalWallet::new(&mut rand::thread_rng()),
+            meta: Meta {
+                universe: Vec::new(),
+            },
+            vault_address: None,
+            coin_to_asset: HashMap::from([("ETH".to_string(), 1)]),
+            latency: Arc::new(LatencyRecorder::default()),
+            reduce_only_closes: None,
+            nonce_partition: NoncePartition::default(),
+        }
+    }
+
+    #[test]
+    fn test_ffi_create_and_free() {
+        let info_client = hl_info_client_new(HlNetwork::Localhost);
+        assert!(!info_client.is_null(), "{}", last_error());
+        unsafe { hl_info_client_free(info_client) };
+
+        // Freed off the runtime, like a C caller would
+        let exchange_client = Box::into_raw(Box::new(exchange_client(String::new())));
+        unsafe { hl_exchange_client_free(exchange_client) };
+    }
+
+    #[test]
+    fn test_ffi_null_pointers() {
+        unsafe {
+            hl_string_free(ptr::null_mut());
+            hl_info_client_free(ptr::null_mut());
+            hl_exchange_client_free(ptr::null_mut());
+
+            assert!(hl_info(ptr::null(), c"{}".as_ptr()).is_null());
+            assert_eq!(last_error(), r#"Generic parse error: "null info client""#);
+            assert_eq!(hl_unsubscribe(ptr::null_mut(), 0), -1);
+            assert!(hl_place_order(ptr::null(), ptr::null()).is_null());
+            assert!(hl_cancel_order(ptr::null(), c"ETH".as_ptr(), 1).is_null());
+            assert!(last_error().contains("null exchange client"));
+
+            let info_client = hl_info_client_new(HlNetwork::Localhost);
+            assert!(hl_info(info_client, ptr::null()).is_null());
+            assert!(last_error().contains("unexpected null string"));
+            hl_info_client_free(info_client);
+
+            assert!(
+                hl_exchange_client_new(ptr::null(), HlNetwork::Localhost, ptr::null()).is_null()
+            );
+            assert!(last_error().contains("unexpected null string"));
+        }
+    }
+
+    #[test]
+    fn test_ffi_json_round_trip() {
+        let (base_url, requests) = serve(json!({"ETH": "3000.5"}));
+        let mut info_client = SimExchange::new().info_client();
+        info_client.http_client.base_url = base_url;
+        let info_client = Box::into_raw(Box::new(info_client));
+        unsafe {
+            let response = take_string(hl_info(info_client, cr#"{"type":"allMids"}"#.as_ptr()));
+            assert_eq!(
+                serde_json::from_str::<Value>(&response).unwrap(),
+                json!({"ETH": "3000.5"})
+            );
+            assert!(hl_info(info_client, c"not json".as_ptr()).is_null());
+            assert!(last_error().starts_with("Json parse error"));
+            hl_info_client_free(info_client);
+        }
+        assert_eq!(*requests.lock().unwrap(), vec![json!({"type": "allMids"})]);
+
+        let response = json!({"status": "ok", "response": {"type": "order",
+            "data": {"statuses": [{"resting": {"oid": 7}}]}}});
+        let (base_url, requests) = serve(response.clone());
+        let exchange_client = Box::into_raw(Box::new(exchange_client(base_url)));
+        let order = HlOrder {
+            asset: c"ETH".as_ptr(),
+            is_buy: true,
+            reduce_only: false,
+            limit_px: 3000.5,
+            sz: 0.1,
+            tif: c"Gtc".as_ptr(),
+            trigger_px: 0.0,
+            is_market: false,
+            tpsl: ptr::null(),
+            cloid: ptr::null(),
+        };
+        unsafe {
+            let placed = take_string(hl_place_order(exchange_client, &order));
+            assert_eq!(serde_json::from_str::<Value>(&placed).unwrap(), response);
+            hl_exchange_client_free(exchange_client);
+        }
+        let requests = requests.lock().unwrap();
+        let sent = &requests[0]["action"]["orders"][0];
+        assert_eq!(
+            (&sent["a"], &sent["b"], &sent["p"], &sent["s"]),
+            (&json!(1), &json!(true), &json!("3000.5"), &json!("0.1"))
+        );
+        assert_eq!(sent["t"], json!({"limit": {"tif": "Gtc"}}));
+    }
+}
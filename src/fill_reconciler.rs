@@ -0,0 +1,180 @@
+use crate::{prelude::*, InfoClient, Message, Timestamp, TradeInfo, UserData};
+use ethers::types::H160;
+use log::{error, warn};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+    time::Duration,
+};
+use tokio::{
+    sync::mpsc::{UnboundedReceiver, UnboundedSender},
+    time,
+};
+
+// Merges the websocket fill stream with periodic userFillsByTime queries so fills missed across
+// reconnects are recovered. Fills are deduplicated by (tid, oid) and held back for
+// `settle_delay` before release, which gives a REST poll time to backfill anything the
+// websocket dropped, so the released stream is ordered by (time, tid). A fill recovered later
+// than that is still delivered (at-least-once wins over ordering) with a warning logged.
+pub struct FillReconciler {
+    pub user: H160,
+    pub settle_delay: Duration,
+    pub lookback: Duration,
+    seen: HashMap<(u64, u64), Timestamp>,
+    pending: BTreeMap<(Timestamp, u64), TradeInfo>,
+    released_until: Timestamp,
+}
+
+impl FillReconciler {
+    pub fn new(user: H160, start_time: Timestamp, settle_delay: Duration) -> FillReconciler {
+        FillReconciler {
+            user,
+            settle_delay,
+            lookback: Duration::from_secs(60),
+            seen: HashMap::new(),
+            pending: BTreeMap::new(),
+            released_until: start_time,
+        }
+    }
+
+    // Returns how many of the fills were new.
+    pub fn ingest(&mut self, fills: impl IntoIterator<Item = TradeInfo>) -> usize {
+        let mut added = 0;
+        let oldest_tracked = self.released_until.saturating_sub(self.lookback);
+        for fill in fills {
+            if fill.time < oldest_tracked {
+                continue;
+            }
+            if self.seen.insert((fill.tid, fill.oid), fill.time).is_none() {
+                if fill.time < self.released_until {
+                    warn!(
+                        "Recovered fill {} after later fills were released",
+                        fill.tid
+                    );
+                }
+                self.pending.insert((fill.time, fill.tid), fill);
+                added += 1;
+            }
+        }
+        added
+    }
+
+    // Releases, in order, every pending fill older than `now - settle_delay`.
+    pub fn drain_ready(&mut self, now: Timestamp) -> Vec<TradeInfo> {
+        let watermark = now.saturating_sub(self.settle_delay);
+        if watermark <= self.released_until {
+            return Vec::new();
+        }
+        let still_pending = self.pending.split_off(&(watermark, 0));
+        let ready = std::mem::replace(&mut self.pending, still_pending);
+        self.released_until = watermark;
+
+        let prune_before = watermark.saturating_sub(self.lookback);
+        self.seen.retain(|_, time| *time >= prune_before);
+
+        ready.into_values().collect()
+    }
+
+    pub async fn poll(&mut self, info_client: &InfoClient) -> Result<usize> {
+        let start_time = self.released_until.saturating_sub(self.lookback);
+        let fills = info_client
+            .user_fills_by_time(self.user, start_time, None)
+            .await?;
+        Ok(self.ingest(fills.into_iter().map(TradeInfo::from)))
+    }
+
+    // Consumes UserFills (or userEvents fills) messages from `receiver` and forwards the
+    // reconciled stream to `sender` until the receiver closes.
+    pub async fn run(
+        mut self,
+        info_client: &InfoClient,
+        mut receiver: UnboundedReceiver<Message>,
+        sender: UnboundedSender<TradeInfo>,
+        poll_interval: Duration,
+    ) {
+        let mut poll_interval = time::interval(poll_interval);
+        let mut release_interval = time::interval(Duration::from_millis(100));
+        loop {
+            tokio::select! {
+                message = receiver.recv() => match message {
+                    Some(Message::UserFills(user_fills)) if user_fills.data.user == self.user => {
+                        self.ingest(Arc::unwrap_or_clone(user_fills.data).fills);
+                    }
+                    Some(Message::User(user)) => {
+                        if let UserData::Fills(fills) = Arc::unwrap_or_clone(user.data) {
+                            self.ingest(fills);
+                        }
+                    }
+                    Some(_) => {}
+                    None => return,
+                },
+                _ = poll_interval.tick() => {
+                    if let Err(err) = self.poll(info_client).await {
+                        error!("Error polling fills for {:?}: {err}", self.user);
+                    }
+                }
+                _ = release_interval.tick() => {}
+            }
+            for fill in self.drain_ready(Timestamp::now()) {
+                if sender.send(fill).is_err() {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fill(tid: u64, oid: u64, time: u64) -> TradeInfo {
+        TradeInfo {
+            time: Timestamp::from_millis(time),
+            ..TradeInfo::test_fill(oid, tid, "1")
+        }
+    }
+
+    #[test]
+    fn test_dedup_and_ordering() {
+        let mut reconciler = FillReconciler::new(
+            H160::zero(),
+            Timestamp::from_millis(1_000),
+            Duration::from_millis(500),
+        );
+
+        assert_eq!(
+            reconciler.ingest(vec![fill(3, 1, 1_300), fill(1, 1, 1_100)]),
+            2
+        );
+        // A REST snapshot containing a fill the websocket missed and duplicates of the rest
+        assert_eq!(
+            reconciler.ingest(vec![
+                fill(1, 1, 1_100),
+                fill(2, 1, 1_200),
+                fill(3, 1, 1_300)
+            ]),
+            1
+        );
+
+        assert!(reconciler
+            .drain_ready(Timestamp::from_millis(1_500))
+            .is_empty());
+        let released: Vec<u64> = reconciler
+            .drain_ready(Timestamp::from_millis(1_801))
+            .into_iter()
+            .map(|fill| fill.tid)
+            .collect();
+        assert_eq!(released, vec![1, 2, 3]);
+
+        // Already released fills are not emitted again, late recoveries still are
+        assert_eq!(reconciler.ingest(vec![fill(2, 1, 1_200)]), 0);
+        assert_eq!(reconciler.ingest(vec![fill(4, 2, 1_250)]), 1);
+        let released: Vec<u64> = reconciler
+            .drain_ready(Timestamp::from_millis(2_000))
+            .into_iter()
+            .map(|fill| fill.tid)
+            .collect();
+        assert_eq!(released, vec![4]);
+    }
+}
@@ -0,0 +1,473 @@
+use crate::{
+    helpers::uuid_to_hex_string, prelude::*, ClientLimit, ClientOrder, ClientOrderRequest, Error,
+    Message, OrderState, OrderUpdate, Timestamp, TradeInfo,
+};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+const BEGIN_STRING: &str = "FIX.4.4";
+const SOH: char = '\x01';
+
+// Tags used by the translated messages
+pub mod fix_tag {
+    pub const AVG_PX: u32 = 6;
+    pub const BEGIN_STRING: u32 = 8;
+    pub const BODY_LENGTH: u32 = 9;
+    pub const CHECK_SUM: u32 = 10;
+    pub const CL_ORD_ID: u32 = 11;
+    pub const CUM_QTY: u32 = 14;
+    pub const EXEC_ID: u32 = 17;
+    pub const EXEC_INST: u32 = 18;
+    pub const LAST_PX: u32 = 31;
+    pub const LAST_QTY: u32 = 32;
+    pub const MSG_SEQ_NUM: u32 = 34;
+    pub const MSG_TYPE: u32 = 35;
+    pub const ORDER_ID: u32 = 37;
+    pub const ORDER_QTY: u32 = 38;
+    pub const ORD_STATUS: u32 = 39;
+    pub const ORD_TYPE: u32 = 40;
+    pub const ORIG_CL_ORD_ID: u32 = 41;
+    pub const PRICE: u32 = 44;
+    pub const SENDER_COMP_ID: u32 = 49;
+    pub const SENDING_TIME: u32 = 52;
+    pub const SIDE: u32 = 54;
+    pub const SYMBOL: u32 = 55;
+    pub const TARGET_COMP_ID: u32 = 56;
+    pub const TEXT: u32 = 58;
+    pub const TIME_IN_FORCE: u32 = 59;
+    pub const TRANSACT_TIME: u32 = 60;
+    pub const EXEC_TYPE: u32 = 150;
+    pub const LEAVES_QTY: u32 = 151;
+    pub const CXL_REJ_RESPONSE_TO: u32 = 434;
+}
+
+pub mod fix_msg_type {
+    pub const EXECUTION_REPORT: &str = "8";
+    pub const ORDER_CANCEL_REJECT: &str = "9";
+    pub const NEW_ORDER_SINGLE: &str = "D";
+}
+
+// A FIX 4.4 message: its MsgType and body fields in order. The standard header's session
+// fields (SenderCompID, TargetCompID, MsgSeqNum, SendingTime) are added by `encode`, since
+// sessions, sequencing and resends belong to the FIX engine the translation feeds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FixMessage {
+    pub msg_type: String,
+    pub fields: Vec<(u32, String)>,
+}
+
+impl FixMessage {
+    pub fn new(msg_type: &str) -> FixMessage {
+        FixMessage {
+            msg_type: msg_type.to_string(),
+            fields: Vec::new(),
+        }
+    }
+
+    pub fn with(mut self, tag: u32, value: impl ToString) -> FixMessage {
+        self.fields.push((tag, value.to_string()));
+        self
+    }
+
+    // The first value of `tag`.
+    pub fn get(&self, tag: u32) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(field_tag, _)| *field_tag == tag)
+            .map(|(_, value)| value.as_str())
+    }
+
+    // The message on the wire, with BodyLength and CheckSum filled in.
+    pub fn encode(
+        &self,
+        sender_comp_id: &str,
+        target_comp_id: &str,
+        seq_num: u64,
+        sending_time: Timestamp,
+    ) -> String {
+        let mut body = format!("{}={}{SOH}", fix_tag::MSG_TYPE, self.msg_type);
+        let header = [
+            (fix_tag::SENDER_COMP_ID, sender_comp_id.to_string()),
+            (fix_tag::TARGET_COMP_ID, target_comp_id.to_string()),
+            (fix_tag::MSG_SEQ_NUM, seq_num.to_string()),
+            (fix_tag::SENDING_TIME, utc_timestamp(sending_time)),
+        ];
+        for (tag, value) in header.iter().chain(&self.fields) {
+            body.push_str(&format!("{tag}={value}{SOH}"));
+        }
+        let mut message = format!(
+            "{}={BEGIN_STRING}{SOH}{}={}{SOH}{body}",
+            fix_tag::BEGIN_STRING,
+            fix_tag::BODY_LENGTH,
+            body.len()
+        );
+        message.push_str(&format!(
+            "{}={:03}{SOH}",
+            fix_tag::CHECK_SUM,
+            checksum(&message)
+        ));
+        message
+    }
+
+    // Parses a message off the wire, checking its BeginString, BodyLength and CheckSum.
+    // Header fields other than those three stay in `fields`.
+    pub fn parse(message: &str) -> Result<FixMessage> {
+        let invalid = |reason: &str| Error::Fix(format!("{reason} in {message:?}"));
+        let checksum_start = message
+            .trim_end_matches(SOH)
+            .rfind(SOH)
+            .map(|index| index + 1)
+            .ok_or_else(|| invalid("missing CheckSum"))?;
+
+        let mut fields = Vec::new();
+        for field in message.split(SOH).filter(|field| !field.is_empty()) {
+            let (tag, value) = field
+                .split_once('=')
+                .ok_or_else(|| invalid("malformed field"))?;
+            let tag = tag.parse::<u32>().map_err(|_| invalid("malformed tag"))?;
+            fields.push((tag, value.to_string()));
+        }
+        let mut fields = fields.into_iter();
+        match (fields.next(), fields.next()) {
+            (Some((fix_tag::BEGIN_STRING, begin)), Some((fix_tag::BODY_LENGTH, length)))
+                if begin == BEGIN_STRING =>
+            {
+                let body_start = message
+                    .match_indices(SOH)
+                    .nth(1)
+                    .map(|(index, _)| index + 1)
+                    .unwrap_or_default();
+                if length.parse::<usize>().ok() != Some(checksum_start - body_start) {
+                    return Err(invalid("wrong BodyLength"));
+                }
+            }
+            _ => return Err(invalid("missing FIX.4.4 header")),
+        }
+
+        let mut fields: Vec<_> = fields.collect();
+        match fields.pop() {
+            Some((fix_tag::CHECK_SUM, sum))
+                if sum.parse::<u32>().ok() == Some(checksum(&message[..checksum_start])) => {}
+            _ => return Err(invalid("wrong CheckSum")),
+        }
+        let msg_type = match fields.first() {
+            Some((fix_tag::MSG_TYPE, msg_type)) => msg_type.clone(),
+            _ => return Err(invalid("missing MsgType")),
+        };
+        Ok(FixMessage {
+            msg_type,
+            fields: fields.split_off(1),
+        })
+    }
+}
+
+fn checksum(message: &str) -> u32 {
+    message.bytes().map(u32::from).sum::<u32>() % 256
+}
+
+fn utc_timestamp(time: Timestamp) -> String {
+    time.to_datetime().format("%Y%m%d-%H:%M:%S%.3f").to_string()
+}
+
+struct FixOrder {
+    order_id: u64,
+    cl_ord_id: String,
+    symbol: String,
+    is_buy: bool,
+    // Unknown for orders first seen through a fill
+    order_qty: Option<f64>,
+    price: String,
+    cum_qty: f64,
+    notional: f64,
+    ord_status: char,
+}
+
+// Translates between the SDK's order flow and FIX 4.4, so an OMS speaking FIX can trade
+// through the SDK:
+//
+// - inbound NewOrderSingle (D) becomes a ClientOrderRequest, remembering the ClOrdID;
+// - orderUpdates become ExecutionReports (8) for new, canceled and rejected orders, and
+//   userFills become ExecutionReports for trades, with CumQty, LeavesQty and AvgPx;
+// - failed cancels become OrderCancelReject (9).
+//
+//     let mut translator = FixTranslator::new();
+//     let order = translator.new_order_single(&FixMessage::parse(&inbound)?)?;
+//     exchange_client.order(order, None).await?;
+//     for report in translator.on_message(&message) {
+//         outbound.send(report.encode("HL", "OMS", next_seq(), Timestamp::now()));
+//     }
+//
+// Orders are identified by the exchange oid as OrderID, and by the OMS's ClOrdID when the
+// order came through `new_order_single` (its cloid otherwise). The "filled" order update isn't
+// reported, since the fills completing the order already were.
+#[derive(Default)]
+pub struct FixTranslator {
+    orders: HashMap<u64, FixOrder>,
+    // ClOrdIDs of orders sent through `new_order_single`, by their cloid's hex string
+    cl_ord_ids: HashMap<String, (String, f64)>,
+}
+
+impl FixTranslator {
+    pub fn new() -> FixTranslator {
+        FixTranslator::default()
+    }
+
+    // Limit orders only. TimeInForce 0 (Day) and 1 (GTC) rest, 3 (IOC) doesn't; ExecInst 6
+    // (participate don't initiate) makes the order post-only and E (do not increase) makes it
+    // reduce-only. A ClOrdID that is a UUID is used as the order's cloid; otherwise a new cloid
+    // is generated.
+    pub fn new_order_single(&mut self, message: &FixMessage) -> Result<ClientOrderRequest> {
+        if message.msg_type != fix_msg_type::NEW_ORDER_SINGLE {
+            return Err(Error::Fix(format!(
+                "expected NewOrderSingle, got MsgType {}",
+                message.msg_type
+            )));
+        }
+        let field = |tag: u32| {
+            message
+                .get(tag)
+                .ok_or_else(|| Error::Fix(format!("NewOrderSingle missing tag {tag}")))
+        };
+        let number = |tag: u32| {
+            field(tag)?
+                .parse::<f64>()
+                .map_err(|_| Error::Fix(format!("NewOrderSingle tag {tag} isn't a number")))
+        };
+        let cl_ord_id = field(fix_tag::CL_ORD_ID)?;
+        let is_buy = match field(fix_tag::SIDE)? {
+            "1" => true,
+            "2" => false,
+            side => return Err(Error::Fix(format!("unsupported Side {side}"))),
+        };
+        if field(fix_tag::ORD_TYPE)? != "2" {
+            return Err(Error::Fix(
+                "only limit orders (OrdType 2) are supported".to_string(),
+            ));
+        }
+        let exec_inst = message.get(fix_tag::EXEC_INST).unwrap_or_default();
+        let tif = match message.get(fix_tag::TIME_IN_FORCE).unwrap_or("0") {
+            _ if exec_inst.contains('6') => "Alo",
+            "0" | "1" => "Gtc",
+            "3" => "Ioc",
+            tif => return Err(Error::Fix(format!("unsupported TimeInForce {tif}"))),
+        };
+        let sz = number(fix_tag::ORDER_QTY)?;
+
+        let cloid = Uuid::parse_str(cl_ord_id).unwrap_or_else(|_| Uuid::new_v4());
+        self.cl_ord_ids
+            .insert(uuid_to_hex_string(cloid), (cl_ord_id.to_string(), sz));
+        Ok(ClientOrderRequest {
+            asset: field(fix_tag::SYMBOL)?.to_string(),
+            is_buy,
+            reduce_only: exec_inst.contains('E'),
+            limit_px: number(fix_tag::PRICE)?,
+            sz,
+            cloid: Some(cloid),
+            order_type: ClientOrder::Limit(ClientLimit {
+                tif: tif.to_string(),
+            }),
+        })
+    }
+
+    // The ExecutionReports for an orderUpdates or userFills message.
+    pub fn on_message(&mut self, message: &Message) -> Vec<FixMessage> {
+        match message {
+            Message::OrderUpdates(updates) => updates
+                .data
+                .iter()
+                .filter_map(|update| self.on_order_update(update))
+                .collect(),
+            Message::UserFills(fills) if !fills.data.is_snapshot.unwrap_or(false) => fills
+                .data
+                .fills
+                .iter()
+                .map(|fill| self.on_fill(fill))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn on_order_update(&mut self, update: &OrderUpdate) -> Option<FixMessage> {
+        let basic = &update.order;
+        let order = self.order(basic.oid, basic.cloid.as_deref(), &basic.coin, &basic.side);
+        order.order_qty = basic.orig_sz.parse().ok().or(order.order_qty);
+        order.price.clone_from(&basic.limit_px);
+
+        let (exec_type, ord_status) = match OrderState::from_order_status(&update.status)? {
+            OrderState::Resting if order.cum_qty > 0.0 => ('0', '1'),
+            OrderState::Resting => ('0', '0'),
+            OrderState::Canceled => ('4', '4'),
+            OrderState::Rejected => ('8', '8'),
+            OrderState::Created | OrderState::PartiallyFilled | OrderState::Filled => return None,
+        };
+        order.ord_status = ord_status;
+        let mut report = execution_report(
+            order,
+            format!(
+                "{}-{}-{}",
+                basic.oid,
+                update.status,
+                update.status_timestamp.as_millis()
+            ),
+            exec_type,
+            update.status_timestamp,
+        );
+        if matches!(exec_type, '4' | '8') {
+            report = report.with(fix_tag::TEXT, &update.status);
+        }
+        Some(report)
+    }
+
+    pub fn on_fill(&mut self, fill: &TradeInfo) -> FixMessage {
+        let order = self.order(fill.oid, fill.cloid.as_deref(), &fill.coin, &fill.side);
+        let last_qty = fill.sz.parse::<f64>().unwrap_or_default();
+        let last_px = fill.px.parse::<f64>().unwrap_or_default();
+        order.cum_qty += last_qty;
+        order.notional += last_qty * last_px;
+        order.ord_status = match order.order_qty {
+            Some(order_qty) if order.cum_qty < order_qty - f64::EPSILON => '1',
+            Some(_) => '2',
+            None => '1',
+        };
+        execution_report(order, fill.tid.to_string(), 'F', fill.time)
+            .with(fix_tag::LAST_QTY, &fill.sz)
+            .with(fix_tag::LAST_PX, &fill.px)
+    }
+
+    // An OrderCancelReject for a cancel of `orig_cl_ord_id` the exchange refused. Known orders
+    // report their last status; unknown ones are reported as rejected.
+    pub fn cancel_reject(&self, cl_ord_id: &str, orig_cl_ord_id: &str, reason: &str) -> FixMessage {
+        let order = self
+            .orders
+            .values()
+            .find(|order| order.cl_ord_id == orig_cl_ord_id);
+        let order_id = order.map_or("NONE".to_string(), |order| order.order_id.to_string());
+        FixMessage::new(fix_msg_type::ORDER_CANCEL_REJECT)
+            .with(fix_tag::ORDER_ID, order_id)
+            .with(fix_tag::CL_ORD_ID, cl_ord_id)
+            .with(fix_tag::ORIG_CL_ORD_ID, orig_cl_ord_id)
+            .with(
+                fix_tag::ORD_STATUS,
+                order.map_or('8', |order| order.ord_status),
+            )
+            .with(fix_tag::CXL_REJ_RESPONSE_TO, 1)
+            .with(fix_tag::TEXT, reason)
+    }
+
+    fn order(&mut self, oid: u64, cloid: Option<&str>, coin: &str, side: &str) -> &mut FixOrder {
+        self.orders.entry(oid).or_insert_with(|| {
+            let known = cloid.and_then(|cloid| self.cl_ord_ids.remove(cloid));
+            FixOrder {
+                order_id: oid,
+                cl_ord_id: known
+                    .as_ref()
+                    .map(|(cl_ord_id, _)| cl_ord_id.clone())
+                    .or(cloid.map(str::to_string))
+                    .unwrap_or_else(|| oid.to_string()),
+                symbol: coin.to_string(),
+                is_buy: side == "B",
+                order_qty: known.map(|(_, order_qty)| order_qty),
+                price: String::new(),
+                cum_qty: 0.0,
+                notional: 0.0,
+                ord_status: '0',
+            }
+        })
+    }
+}
+
+fn execution_report(
+    order: &FixOrder,
+    exec_id: String,
+    exec_type: char,
+    time: Timestamp,
+) -> FixMessage {
+    let avg_px = if order.cum_qty > 0.0 {
+        order.notional / order.cum_qty
+    } else {
+        0.0
+    };
+    let leaves_qty = match (order.ord_status, order.order_qty) {
+        ('0' | '1', Some(order_qty)) => (order_qty - order.cum_qty).max(0.0),
+        _ => 0.0,
+    };
+    let mut report = FixMessage::new(fix_msg_type::EXECUTION_REPORT)
+        .with(fix_tag::ORDER_ID, order.order_id)
+        .with(fix_tag::CL_ORD_ID, &order.cl_ord_id)
+        .with(fix_tag::EXEC_ID, exec_id)
+        .with(fix_tag::EXEC_TYPE, exec_type)
+        .with(fix_tag::ORD_STATUS, order.ord_status)
+        .with(fix_tag::SYMBOL, &order.symbol)
+        .with(fix_tag::SIDE, if order.is_buy { '1' } else { '2' });
+    if let Some(order_qty) = order.order_qty {
+        report = report.with(fix_tag::ORDER_QTY, order_qty);
+    }
+    if !order.price.is_empty() {
+        report = report
+            .with(fix_tag::ORD_TYPE, '2')
+            .with(fix_tag::PRICE, &order.price);
+    }
+    report
+        .with(fix_tag::LEAVES_QTY, leaves_qty)
+        .with(fix_tag::CUM_QTY, order.cum_qty)
+        .with(fix_tag::AVG_PX, avg_px)
+        .with(fix_tag::TRANSACT_TIME, utc_timestamp(time))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_fix_round_trip() -> Result<()> {
+        let mut translator = FixTranslator::new();
+        let inbound = FixMessage::new(fix_msg_type::NEW_ORDER_SINGLE)
+            .with(fix_tag::CL_ORD_ID, "oms-1")
+            .with(fix_tag::SYMBOL, "ETH")
+            .with(fix_tag::SIDE, 1)
+            .with(fix_tag::ORDER_QTY, 2)
+            .with(fix_tag::ORD_TYPE, 2)
+            .with(fix_tag::PRICE, 2000)
+            .with(fix_tag::EXEC_INST, 6)
+            .encode("OMS", "HL", 1, Timestamp::from_millis(0));
+        assert!(inbound.contains("52=19700101-00:00:00.000\x01"));
+        let parsed = FixMessage::parse(&inbound)?;
+        assert_eq!(parsed.get(fix_tag::SENDER_COMP_ID), Some("OMS"));
+        assert!(FixMessage::parse(&inbound.replace("55=ETH", "55=BTC")).is_err());
+
+        let order = translator.new_order_single(&parsed)?;
+        assert!(matches!(&order.order_type, ClientOrder::Limit(limit) if limit.tif == "Alo"));
+        let cloid = uuid_to_hex_string(order.cloid.unwrap());
+
+        let update: OrderUpdate = serde_json::from_value(json!({
+            "order": {"coin": "ETH", "side": "B", "limitPx": "2000", "sz": "2", "oid": 7,
+                      "timestamp": 1_000, "origSz": "2", "cloid": cloid},
+            "status": "open",
+            "statusTimestamp": 1_000
+        }))
+        .unwrap();
+        let report = translator.on_order_update(&update).unwrap();
+        assert_eq!(report.get(fix_tag::CL_ORD_ID), Some("oms-1"));
+        assert_eq!(report.get(fix_tag::ORD_STATUS), Some("0"));
+        assert_eq!(report.get(fix_tag::LEAVES_QTY), Some("2"));
+
+        let fill: TradeInfo = serde_json::from_value(json!({
+            "coin": "ETH", "side": "B", "px": "1999.5", "sz": "0.5", "time": 2_000,
+            "hash": "0x00", "startPosition": "0", "dir": "Open Long", "closedPnl": "0",
+            "oid": 7, "crossed": false, "fee": "0", "tid": 42, "cloid": cloid
+        }))
+        .unwrap();
+        let report = translator.on_fill(&fill);
+        assert_eq!(report.get(fix_tag::EXEC_TYPE), Some("F"));
+        assert_eq!(report.get(fix_tag::ORD_STATUS), Some("1"));
+        assert_eq!(report.get(fix_tag::CUM_QTY), Some("0.5"));
+        assert_eq!(report.get(fix_tag::LEAVES_QTY), Some("1.5"));
+        assert_eq!(report.get(fix_tag::AVG_PX), Some("1999.5"));
+
+        let reject = translator.cancel_reject("oms-2", "oms-1", "Order was never placed");
+        assert_eq!(reject.get(fix_tag::ORDER_ID), Some("7"));
+        assert_eq!(reject.get(fix_tag::ORD_STATUS), Some("1"));
+        Ok(())
+    }
+}
@@ -0,0 +1,195 @@
+use crate::{Message, Timestamp};
+use std::{collections::BTreeMap, time::Duration};
+use tokio::{
+    sync::mpsc::{unbounded_channel, UnboundedReceiver},
+    time,
+};
+
+// Order flow and book features of one coin over one interval.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlowFeatures {
+    pub coin: String,
+    // End of the interval
+    pub time: Timestamp,
+    pub trade_count: usize,
+    // Volumes by aggressor side, in base units
+    pub buy_volume: f64,
+    pub sell_volume: f64,
+    // Buy minus sell volume, and the same in quote units
+    pub signed_flow: f64,
+    pub signed_notional: f64,
+    // Buy over sell volume, None without sells
+    pub buy_sell_ratio: Option<f64>,
+    // (bid size - ask size) / (bid size + ask size) over the top levels of the latest book,
+    // from -1 (only asks) to 1 (only bids). None before the first book.
+    pub book_imbalance: Option<f64>,
+}
+
+#[derive(Default)]
+struct CoinFlow {
+    trade_count: usize,
+    buy_volume: f64,
+    sell_volume: f64,
+    signed_notional: f64,
+    book_imbalance: Option<f64>,
+}
+
+// Accumulates features per coin from Trades and L2Book messages. Book imbalance is taken
+// over the top `book_depth` levels of each side.
+pub struct FlowAnalytics {
+    book_depth: usize,
+    coins: BTreeMap<String, CoinFlow>,
+}
+
+impl FlowAnalytics {
+    pub fn new(book_depth: usize) -> FlowAnalytics {
+        FlowAnalytics {
+            book_depth: book_depth.max(1),
+            coins: BTreeMap::new(),
+        }
+    }
+
+    pub fn on_message(&mut self, message: &Message) {
+        match message {
+            Message::Trades(trades) => {
+                for trade in trades.data.iter() {
+                    let (Ok(px), Ok(sz)) = (trade.px.parse::<f64>(), trade.sz.parse::<f64>())
+                    else {
+                        continue;
+                    };
+                    let flow = self.coins.entry(trade.coin.clone()).or_default();
+                    flow.trade_count += 1;
+                    // The side is the aggressor's: B for buys, A for sells
+                    if trade.side == "B" {
+                        flow.buy_volume += sz;
+                        flow.signed_notional += px * sz;
+                    } else {
+                        flow.sell_volume += sz;
+                        flow.signed_notional -= px * sz;
+                    }
+                }
+            }
+            Message::L2Book(l2_book) => {
+                let size = |side: usize| -> f64 {
+                    l2_book.data.levels.get(side).map_or(0.0, |levels| {
+                        levels
+                            .iter()
+                            .take(self.book_depth)
+                            .filter_map(|level| level.sz.parse::<f64>().ok())
+                            .sum()
+                    })
+                };
+                let (bids, asks) = (size(0), size(1));
+                let flow = self.coins.entry(l2_book.data.coin.clone()).or_default();
+                flow.book_imbalance = (bids + asks > 0.0).then(|| (bids - asks) / (bids + asks));
+            }
+            _ => {}
+        }
+    }
+
+    // The features of every coin seen so far, for the interval ending at `time`. Trade flow
+    // restarts from zero; the book imbalance carries over until the next book.
+    pub fn take(&mut self, time: Timestamp) -> Vec<FlowFeatures> {
+        self.coins
+            .iter_mut()
+            .map(|(coin, flow)| {
+                let features = FlowFeatures {
+                    coin: coin.clone(),
+                    time,
+                    trade_count: flow.trade_count,
+                    buy_volume: flow.buy_volume,
+                    sell_volume: flow.sell_volume,
+                    signed_flow: flow.buy_volume - flow.sell_volume,
+                    signed_notional: flow.signed_notional,
+                    buy_sell_ratio: (flow.sell_volume > 0.0)
+                        .then(|| flow.buy_volume / flow.sell_volume),
+                    book_imbalance: flow.book_imbalance,
+                };
+                *flow = CoinFlow {
+                    book_imbalance: flow.book_imbalance,
+                    ..CoinFlow::default()
+                };
+                features
+            })
+            .collect()
+    }
+}
+
+// Emits the features of every coin on `receiver` each `cadence`, until the subscriptions
+// feeding it end. Subscribe the same sender to the Trades and L2Book channels of the coins of
+// interest.
+pub fn flow_features(
+    mut receiver: UnboundedReceiver<Message>,
+    book_depth: usize,
+    cadence: Duration,
+) -> UnboundedReceiver<FlowFeatures> {
+    let (sender, features) = unbounded_channel();
+    tokio::spawn(async move {
+        let mut analytics = FlowAnalytics::new(book_depth);
+        let mut ticker = time::interval_at(time::Instant::now() + cadence, cadence);
+        loop {
+            tokio::select! {
+                message = receiver.recv() => match message {
+                    Some(message) => analytics.on_message(&message),
+                    None => return,
+                },
+                _ = ticker.tick() => {
+                    for coin_features in analytics.take(Timestamp::now()) {
+                        if sender.send(coin_features).is_err() {
+                            return;
+                        }
+                    }
+                }
+            }
+        }
+    });
+    features
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BookLevel, L2Book, L2BookData, Trade, Trades};
+    use std::sync::Arc;
+
+    fn level(sz: &str) -> BookLevel {
+        BookLevel::test_level("2000.0", sz)
+    }
+
+    #[test]
+    fn test_flow_features() {
+        let mut analytics = FlowAnalytics::new(2);
+        analytics.on_message(&Message::Trades(Trades {
+            data: Arc::new(vec![
+                Trade::test_trade("B", "2000.0", "3.0", 0),
+                Trade::test_trade("A", "1999.0", "1.0", 0),
+                Trade::test_trade("B", "2001.0", "1.0", 0),
+            ]),
+        }));
+        analytics.on_message(&Message::L2Book(L2Book {
+            data: Arc::new(L2BookData {
+                coin: "ETH".to_string(),
+                time: Timestamp::from_millis(0),
+                // The third bid is beyond the depth
+                levels: vec![
+                    vec![level("3.0"), level("3.0"), level("100.0")],
+                    vec![level("1.0"), level("1.0")],
+                ],
+            }),
+        }));
+
+        let features = analytics.take(Timestamp::from_millis(1_000));
+        assert_eq!(features.len(), 1);
+        let eth = &features[0];
+        assert_eq!(eth.trade_count, 3);
+        assert_eq!(eth.signed_flow, 3.0);
+        assert_eq!(eth.signed_notional, 6000.0 - 1999.0 + 2001.0);
+        assert_eq!(eth.buy_sell_ratio, Some(4.0));
+        assert_eq!(eth.book_imbalance, Some(0.5));
+
+        let features = analytics.take(Timestamp::from_millis(2_000));
+        assert_eq!(features[0].trade_count, 0);
+        assert_eq!(features[0].buy_sell_ratio, None);
+        assert_eq!(features[0].book_imbalance, Some(0.5));
+    }
+}
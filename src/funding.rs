@@ -0,0 +1,144 @@
+use crate::{prelude::*, Error, InfoClient, PerpAssetCtx, Timestamp, UserStateResponse};
+use ethers::types::H160;
+use log::error;
+use std::{collections::HashMap, time::Duration};
+use tokio::{sync::mpsc::UnboundedSender, task::JoinHandle, time};
+
+const FUNDING_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+// Funding is paid on every hour.
+pub fn next_funding_time(now: Timestamp) -> Timestamp {
+    let interval_ms = FUNDING_INTERVAL.as_millis() as u64;
+    Timestamp::from_millis((now.as_millis() / interval_ms + 1) * interval_ms)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FundingForecast {
+    pub coin: String,
+    // Signed position size, positive for longs
+    pub szi: f64,
+    // Hourly rate; positive means longs pay shorts
+    pub funding_rate: f64,
+    pub oracle_px: f64,
+    pub funding_time: Timestamp,
+    // Positive when the position receives funding, negative when it pays
+    pub expected_payment: f64,
+}
+
+impl FundingForecast {
+    // The rate is the one currently predicted; the rate actually applied at `funding_time` is
+    // the average premium over the hour, so the forecast converges as the hour ends.
+    pub fn new(
+        coin: &str,
+        szi: f64,
+        funding_rate: f64,
+        oracle_px: f64,
+        now: Timestamp,
+    ) -> FundingForecast {
+        FundingForecast {
+            coin: coin.to_string(),
+            szi,
+            funding_rate,
+            oracle_px,
+            funding_time: next_funding_time(now),
+            expected_payment: -szi * oracle_px * funding_rate,
+        }
+    }
+
+    // Forecasts for every open position in `user_state`, using the matching asset contexts.
+    pub fn for_positions(
+        user_state: &UserStateResponse,
+        asset_ctxs: &HashMap<String, PerpAssetCtx>,
+        now: Timestamp,
+    ) -> Result<Vec<FundingForecast>> {
+        let parse = |value: &str| value.parse::<f64>().map_err(|_| Error::FloatStringParse);
+        let mut forecasts = Vec::new();
+        for asset_position in &user_state.asset_positions {
+            let position = &asset_position.position;
+            let Some(ctx) = asset_ctxs.get(&position.coin) else {
+                continue;
+            };
+            let szi = parse(&position.szi)?;
+            if szi == 0.0 {
+                continue;
+            }
+            forecasts.push(FundingForecast::new(
+                &position.coin,
+                szi,
+                parse(&ctx.funding)?,
+                parse(&ctx.oracle_px)?,
+                now,
+            ));
+        }
+        Ok(forecasts)
+    }
+}
+
+// Sends forecasts for all of `user`'s open positions `lead` before each funding time, until
+// the receiver is dropped.
+pub fn spawn_funding_alerts(
+    info_client: InfoClient,
+    user: H160,
+    lead: Duration,
+    sender: UnboundedSender<FundingForecast>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            let now = Timestamp::now();
+            let mut alert_time = next_funding_time(now).saturating_sub(lead);
+            if alert_time <= now {
+                alert_time += FUNDING_INTERVAL;
+            }
+            time::sleep(alert_time - now).await;
+
+            let forecasts = async {
+                let user_state = info_client.user_state(user).await?;
+                let (meta, asset_ctxs) = info_client.meta_and_asset_ctxs().await?;
+                let asset_ctxs: HashMap<String, PerpAssetCtx> = meta
+                    .universe
+                    .into_iter()
+                    .map(|asset| asset.name)
+                    .zip(asset_ctxs)
+                    .collect();
+                FundingForecast::for_positions(&user_state, &asset_ctxs, Timestamp::now())
+            };
+            match forecasts.await {
+                Ok(forecasts) => {
+                    for forecast in forecasts {
+                        if sender.send(forecast).is_err() {
+                            return;
+                        }
+                    }
+                }
+                Err(err) => error!("Error forecasting funding for {user:?}: {err}"),
+            }
+            if sender.is_closed() {
+                return;
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_forecast() {
+        // 2024-06-10 06:59:00 UTC
+        let now = Timestamp::from_millis(1_718_002_740_000);
+        let funding_time = next_funding_time(now);
+        assert_eq!(funding_time, Timestamp::from_millis(1_718_002_800_000));
+        assert_eq!(funding_time - now, Duration::from_secs(60));
+        assert_eq!(
+            next_funding_time(funding_time),
+            funding_time + Duration::from_secs(60 * 60)
+        );
+
+        // A long pays positive funding, a short receives it
+        let long = FundingForecast::new("ETH", 2.0, 0.0001, 3000.0, now);
+        assert!((long.expected_payment + 0.6).abs() < 1e-9);
+        let short = FundingForecast::new("ETH", -2.0, 0.0001, 3000.0, now);
+        assert!((short.expected_payment - 0.6).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,660 @@
+use crate::{
+    consts::EPSILON, helpers::uuid_to_hex_string, prelude::*, ClientCancelRequest,
+    ClientOrderRequest, Error, ExchangeClient, ExchangeDataStatus, ExchangeResponseStatus, Message,
+    OrderLifecycle, OrderState, OrderUpdate, TradeInfo,
+};
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::mpsc::UnboundedReceiver;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GridSpacing {
+    // Levels an equal price apart
+    Arithmetic,
+    // Levels an equal ratio apart, for ranges wide enough that percent moves matter
+    Geometric,
+}
+
+// What the grid does while the mid is outside its range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OutOfRange {
+    // Leave the orders resting; the grid resumes trading when the mid comes back
+    Hold,
+    // Cancel every order, and lay the grid again once the mid is back in range
+    Cancel,
+    // Move the range, keeping its width and level count, to center it on the mid
+    Recenter,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GridConfig {
+    pub coin: String,
+    pub lower_px: f64,
+    pub upper_px: f64,
+    // Price levels, including both ends of the range
+    pub levels: usize,
+    pub spacing: GridSpacing,
+    pub size_per_level: f64,
+    // Decimals prices are rounded to
+    pub decimals: u32,
+    // Largest position, long or short, that the grid's resting orders could add up to if they
+    // all filled. Orders that would exceed it aren't placed.
+    pub max_position: Option<f64>,
+    pub out_of_range: OutOfRange,
+}
+
+impl GridConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.levels < 2 {
+            return Err(Error::Config("a grid needs at least 2 levels".to_string()));
+        }
+        if !(self.lower_px > 0.0 && self.lower_px < self.upper_px) {
+            return Err(Error::Config(format!(
+                "grid range {}..{} is empty",
+                self.lower_px, self.upper_px
+            )));
+        }
+        if self.size_per_level <= 0.0 {
+            return Err(Error::Config(
+                "grid size per level must be positive".to_string(),
+            ));
+        }
+        let prices = self.prices();
+        if prices.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err(Error::Config(format!(
+                "grid levels collide when rounded to {} decimals",
+                self.decimals
+            )));
+        }
+        Ok(())
+    }
+
+    // The levels' prices, lowest first.
+    pub fn prices(&self) -> Vec<f64> {
+        let steps = (self.levels - 1) as f64;
+        let pow10 = 10f64.powi(self.decimals as i32);
+        (0..self.levels)
+            .map(|level| {
+                let fraction = level as f64 / steps;
+                let px = match self.spacing {
+                    GridSpacing::Arithmetic => {
+                        self.lower_px + (self.upper_px - self.lower_px) * fraction
+                    }
+                    GridSpacing::Geometric => {
+                        self.lower_px * (self.upper_px / self.lower_px).powf(fraction)
+                    }
+                };
+                (px * pow10).round() / pow10
+            })
+            .collect()
+    }
+
+    fn recentered(&self, mid: f64) -> GridConfig {
+        let (lower_px, upper_px) = match self.spacing {
+            GridSpacing::Arithmetic => {
+                let half_width = (self.upper_px - self.lower_px) / 2.0;
+                (mid - half_width, mid + half_width)
+            }
+            GridSpacing::Geometric => {
+                let half_ratio = (self.upper_px / self.lower_px).sqrt();
+                (mid / half_ratio, mid * half_ratio)
+            }
+        };
+        GridConfig {
+            lower_px,
+            upper_px,
+            ..self.clone()
+        }
+    }
+}
+
+// Orders to send, cancels first.
+#[derive(Debug, Clone, Default)]
+pub struct GridActions {
+    pub cancels: Vec<ClientCancelRequest>,
+    pub orders: Vec<ClientOrderRequest>,
+}
+
+impl GridActions {
+    pub fn is_empty(&self) -> bool {
+        self.cancels.is_empty() && self.orders.is_empty()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GridOrder {
+    pub level: usize,
+    pub px: f64,
+    pub is_buy: bool,
+    pub cloid: Uuid,
+    pub order: OrderLifecycle,
+    // Fills of orders being canceled, e.g. when the grid moves, don't place counter-orders
+    pub canceling: bool,
+}
+
+// A grid of post-only orders between two prices. It starts with buys on the levels below the
+// mid and sells on those above, leaving the level nearest the mid empty. Whenever an order
+// fills, the opposite order goes on the next level over, into the gap, so each round trip
+// between neighbouring levels earns their spacing.
+//
+// A counter-order that would cross the book is rejected and leaves its level empty until the
+// grid is laid again. Orders are tracked by cloid until acknowledged, then by oid.
+//
+// `Grid` only decides what to send; `GridStrategy` sends it through an `ExchangeClient`.
+pub struct Grid {
+    config: GridConfig,
+    prices: Vec<f64>,
+    orders: Vec<GridOrder>,
+    position: f64,
+    // Whether orders are out, or held back while the mid is out of range
+    active: bool,
+}
+
+impl Grid {
+    pub fn new(config: GridConfig) -> Result<Grid> {
+        config.validate()?;
+        Ok(Grid {
+            prices: config.prices(),
+            config,
+            orders: Vec::new(),
+            position: 0.0,
+            active: false,
+        })
+    }
+
+    pub fn config(&self) -> &GridConfig {
+        &self.config
+    }
+
+    // The position the grid's fills have built, positive long.
+    pub fn position(&self) -> f64 {
+        self.position
+    }
+
+    // Orders sent and not yet known to be done.
+    pub fn orders(&self) -> &[GridOrder] {
+        &self.orders
+    }
+
+    pub fn on_mid(&mut self, mid: f64) -> GridActions {
+        let in_range = (self.config.lower_px..=self.config.upper_px).contains(&mid);
+        if in_range {
+            return if self.active {
+                GridActions::default()
+            } else {
+                self.lay(mid)
+            };
+        }
+        match self.config.out_of_range {
+            OutOfRange::Hold if !self.active => self.lay(mid),
+            OutOfRange::Hold => GridActions::default(),
+            OutOfRange::Cancel => {
+                self.active = false;
+                self.cancel_all()
+            }
+            OutOfRange::Recenter => {
+                let config = self.config.recentered(mid);
+                if let Err(err) = config.validate() {
+                    warn!("Not recentering the {} grid on {mid}: {err}", config.coin);
+                    return GridActions::default();
+                }
+                info!(
+                    "Recentering the {} grid on {mid}: {}..{}",
+                    config.coin, config.lower_px, config.upper_px
+                );
+                let mut actions = self.cancel_all();
+                self.prices = config.prices();
+                self.config = config;
+                actions.orders = self.lay(mid).orders;
+                actions
+            }
+        }
+    }
+
+    // Cancels every live order, e.g. before shutting down.
+    pub fn cancel_all(&mut self) -> GridActions {
+        let mut actions = GridActions::default();
+        for order in &mut self.orders {
+            if order.canceling {
+                continue;
+            }
+            order.canceling = true;
+            if let Some(oid) = order.order.oid {
+                actions.cancels.push(ClientCancelRequest {
+                    asset: self.config.coin.clone(),
+                    oid,
+                });
+            }
+        }
+        actions
+    }
+
+    // Applies the exchange's status for the order sent with `cloid`.
+    pub fn on_ack(&mut self, cloid: Uuid, status: &ExchangeDataStatus) -> GridActions {
+        let Some(index) = self.orders.iter().position(|order| order.cloid == cloid) else {
+            return GridActions::default();
+        };
+        let order = &mut self.orders[index];
+        if let Err(err) = order.order.on_ack(status) {
+            warn!("Grid order {cloid} ack {status:?} not applied: {err}");
+        }
+        let mut actions = GridActions::default();
+        // Cancels sent before the oid was known couldn't name it
+        if order.canceling && !order.order.state.is_terminal() {
+            if let Some(oid) = order.order.oid {
+                actions.cancels.push(ClientCancelRequest {
+                    asset: self.config.coin.clone(),
+                    oid,
+                });
+            }
+        }
+        if let ExchangeDataStatus::Error(error) = status {
+            warn!(
+                "Grid {} order at {} rejected: {error}",
+                if order.is_buy { "buy" } else { "sell" },
+                order.px
+            );
+        }
+        self.retire(index);
+        actions
+    }
+
+    // Forgets orders whose request failed without statuses.
+    pub fn on_rejected(&mut self, cloids: &[Uuid]) {
+        self.orders.retain(|order| !cloids.contains(&order.cloid));
+    }
+
+    pub fn on_order_update(&mut self, update: &OrderUpdate) {
+        let Some(index) = self.find(update.order.oid, update.order.cloid.as_deref()) else {
+            return;
+        };
+        if let Err(err) = self.orders[index].order.on_order_update(update) {
+            warn!("Grid order update {update:?} not applied: {err}");
+        }
+        self.retire(index);
+    }
+
+    pub fn on_fill(&mut self, fill: &TradeInfo) -> GridActions {
+        if fill.coin != self.config.coin {
+            return GridActions::default();
+        }
+        let Some(index) = self.find(fill.oid, fill.cloid.as_deref()) else {
+            return GridActions::default();
+        };
+        let order = &mut self.orders[index];
+        let before = order.order.filled_sz;
+        if let Err(err) = order.order.on_fill(fill) {
+            warn!("Grid fill {} not applied: {err}", fill.tid);
+            return GridActions::default();
+        }
+        let filled = order.order.filled_sz - before;
+        self.position += if order.is_buy { filled } else { -filled };
+
+        let (level, is_buy, canceling) = (order.level, order.is_buy, order.canceling);
+        let done = order.order.state == OrderState::Filled;
+        self.retire(index);
+        if !done || canceling || !self.active {
+            return GridActions::default();
+        }
+        // A filled buy is sold a level up, and a filled sell bought back a level down
+        let counter = if is_buy {
+            level + 1
+        } else {
+            match level.checked_sub(1) {
+                Some(level) => level,
+                None => return GridActions::default(),
+            }
+        };
+        let mut actions = GridActions::default();
+        if counter < self.prices.len() {
+            actions.orders.extend(self.place(counter, !is_buy));
+        }
+        actions
+    }
+
+    // Orders for every free level but the one nearest `mid`.
+    fn lay(&mut self, mid: f64) -> GridActions {
+        self.active = true;
+        let gap = (0..self.prices.len())
+            .min_by(|&a, &b| {
+                (self.prices[a] - mid)
+                    .abs()
+                    .total_cmp(&(self.prices[b] - mid).abs())
+            })
+            .unwrap_or_default();
+        let mut actions = GridActions::default();
+        // Nearest the mid first, so a position cap cuts the outermost levels
+        let mut levels: Vec<usize> = (0..self.prices.len()).filter(|&l| l != gap).collect();
+        levels.sort_by_key(|&level| level.abs_diff(gap));
+        for level in levels {
+            actions
+                .orders
+                .extend(self.place(level, self.prices[level] < mid));
+        }
+        actions
+    }
+
+    fn place(&mut self, level: usize, is_buy: bool) -> Option<ClientOrderRequest> {
+        let taken = self
+            .orders
+            .iter()
+            .any(|order| order.level == level && !order.canceling);
+        if taken {
+            return None;
+        }
+        let sz = self.config.size_per_level;
+        if let Some(max_position) = self.config.max_position {
+            // The position if every live order on this side filled
+            let exposure: f64 = self
+                .orders
+                .iter()
+                .filter(|order| order.is_buy == is_buy && !order.canceling)
+                .map(|order| order.order.remaining_sz())
+                .sum();
+            let worst = if is_buy {
+                self.position + exposure + sz
+            } else {
+                -self.position + exposure + sz
+            };
+            if worst > max_position + EPSILON {
+                return None;
+            }
+        }
+        let px = self.prices[level];
+        let cloid = Uuid::new_v4();
+        self.orders.push(GridOrder {
+            level,
+            px,
+            is_buy,
+            cloid,
+            order: OrderLifecycle::new(sz, Some(uuid_to_hex_string(cloid))),
+            canceling: false,
+        });
+        Some(ClientOrderRequest {
+            cloid: Some(cloid),
+            ..ClientOrderRequest::post_only(&self.config.coin, is_buy, sz, px)
+        })
+    }
+
+    fn find(&self, oid: u64, cloid: Option<&str>) -> Option<usize> {
+        self.orders.iter().position(|order| {
+            order.order.oid == Some(oid)
+                || (cloid.is_some() && order.order.cloid.as_deref() == cloid)
+        })
+    }
+
+    fn retire(&mut self, index: usize) {
+        if self.orders[index].order.state.is_terminal() {
+            self.orders.remove(index);
+        }
+    }
+}
+
+// Runs a `Grid` against the exchange, sending its orders and cancels in bulk.
+pub struct GridStrategy {
+    pub grid: Grid,
+    exchange_client: ExchangeClient,
+}
+
+impl GridStrategy {
+    pub fn new(grid: Grid, exchange_client: ExchangeClient) -> GridStrategy {
+        GridStrategy {
+            grid,
+            exchange_client,
+        }
+    }
+
+    // Trades until `receiver` closes, then cancels the grid's orders. Subscribe it to AllMids
+    // (or the perp DEX's) and the exchange client's user's OrderUpdates and UserFills.
+    pub async fn run(&mut self, mut receiver: UnboundedReceiver<Message>) {
+        while let Some(message) = receiver.recv().await {
+            let actions = match message {
+                Message::AllMids(all_mids) => {
+                    let mid = all_mids.data.mids.get(&self.grid.config.coin);
+                    match mid.map(|mid| mid.parse::<f64>()) {
+                        Some(Ok(mid)) => self.grid.on_mid(mid),
+                        _ => continue,
+                    }
+                }
+                Message::OrderUpdates(updates) => {
+                    for update in updates.data.iter() {
+                        self.grid.on_order_update(update);
+                    }
+                    continue;
+                }
+                // The snapshot sent on subscribing repeats fills from before the grid started
+                Message::UserFills(user_fills) if user_fills.data.is_snapshot != Some(true) => {
+                    let mut actions = GridActions::default();
+                    for fill in Arc::unwrap_or_clone(user_fills.data).fills {
+                        let more = self.grid.on_fill(&fill);
+                        actions.cancels.extend(more.cancels);
+                        actions.orders.extend(more.orders);
+                    }
+                    actions
+                }
+                _ => continue,
+            };
+            self.execute(actions).await;
+        }
+        let actions = self.grid.cancel_all();
+        self.execute(actions).await;
+    }
+
+    async fn execute(&mut self, actions: GridActions) {
+        self.cancel(actions.cancels).await;
+        if actions.orders.is_empty() {
+            return;
+        }
+        let cloids: Vec<Uuid> = actions.orders.iter().filter_map(|o| o.cloid).collect();
+        let statuses = match self.exchange_client.bulk_order(actions.orders, None).await {
+            Ok(ExchangeResponseStatus::Ok(response)) => {
+                response.data.map(|data| data.statuses).unwrap_or_default()
+            }
+            Ok(ExchangeResponseStatus::Err(err)) => {
+                error!("Grid orders failed: {err:?}");
+                Vec::new()
+            }
+            Err(err) => {
+                error!("Grid orders failed: {err}");
+                Vec::new()
+            }
+        };
+        let mut cancels = Vec::new();
+        for (cloid, status) in cloids.iter().zip(&statuses) {
+            cancels.extend(self.grid.on_ack(*cloid, status).cancels);
+        }
+        self.grid
+            .on_rejected(&cloids[statuses.len().min(cloids.len())..]);
+        self.cancel(cancels).await;
+    }
+
+    // Orders a cancel misses are left to their orderUpdates.
+    async fn cancel(&self, cancels: Vec<ClientCancelRequest>) {
+        if cancels.is_empty() {
+            return;
+        }
+        match self.exchange_client.bulk_cancel(cancels, None).await {
+            Ok(ExchangeResponseStatus::Ok(_)) => {}
+            Ok(ExchangeResponseStatus::Err(err)) => error!("Grid cancels failed: {err:?}"),
+            Err(err) => error!("Grid cancels failed: {err}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RestingOrder, Timestamp};
+
+    fn config(out_of_range: OutOfRange) -> GridConfig {
+        GridConfig {
+            coin: "ETH".to_string(),
+            lower_px: 1900.0,
+            upper_px: 2100.0,
+            levels: 5,
+            spacing: GridSpacing::Arithmetic,
+            size_per_level: 1.0,
+            decimals: 1,
+            max_position: None,
+            out_of_range,
+        }
+    }
+
+    fn fill(order: &GridOrder, sz: &str, tid: u64) -> TradeInfo {
+        TradeInfo {
+            side: if order.is_buy { "B" } else { "A" }.to_string(),
+            px: order.px.to_string(),
+            dir: String::new(),
+            cloid: order.order.cloid.clone(),
+            ..TradeInfo::test_fill(order.order.oid.unwrap(), tid, sz)
+        }
+    }
+
+    // Acks every unacknowledged order as resting, with fresh oids.
+    fn rest_all(grid: &mut Grid) {
+        let next = grid
+            .orders()
+            .iter()
+            .filter_map(|order| order.order.oid)
+            .max()
+            .unwrap_or(99)
+            + 1;
+        let cloids: Vec<Uuid> = grid
+            .orders()
+            .iter()
+            .filter(|order| order.order.oid.is_none())
+            .map(|order| order.cloid)
+            .collect();
+        for (oid, cloid) in (next..).zip(cloids) {
+            grid.on_ack(cloid, &ExchangeDataStatus::Resting(RestingOrder { oid }));
+        }
+    }
+
+    fn sides(actions: &GridActions) -> Vec<(bool, f64)> {
+        let mut sides: Vec<_> = actions
+            .orders
+            .iter()
+            .map(|order| (order.is_buy, order.limit_px))
+            .collect();
+        sides.sort_by(|a, b| a.1.total_cmp(&b.1));
+        sides
+    }
+
+    #[test]
+    fn test_grid_trades_round_trips() {
+        assert!(GridConfig {
+            levels: 1,
+            ..config(OutOfRange::Hold)
+        }
+        .validate()
+        .is_err());
+        let geometric = GridConfig {
+            lower_px: 1000.0,
+            upper_px: 4000.0,
+            levels: 3,
+            spacing: GridSpacing::Geometric,
+            ..config(OutOfRange::Hold)
+        };
+        assert_eq!(geometric.prices(), vec![1000.0, 2000.0, 4000.0]);
+
+        let mut grid = Grid::new(config(OutOfRange::Hold)).unwrap();
+        // The level nearest the mid, 2000, is left empty
+        let actions = grid.on_mid(2010.0);
+        assert_eq!(
+            sides(&actions),
+            vec![
+                (true, 1900.0),
+                (true, 1950.0),
+                (false, 2050.0),
+                (false, 2100.0)
+            ]
+        );
+        assert!(grid.on_mid(2020.0).is_empty());
+        rest_all(&mut grid);
+
+        // A partial fill waits; the full fill sells a level up, into the gap
+        let buy = grid
+            .orders()
+            .iter()
+            .find(|o| o.px == 1950.0)
+            .unwrap()
+            .clone();
+        assert!(grid.on_fill(&fill(&buy, "0.4", 1)).is_empty());
+        let actions = grid.on_fill(&fill(&buy, "0.6", 2));
+        assert_eq!(sides(&actions), vec![(false, 2000.0)]);
+        assert_eq!(grid.position(), 1.0);
+        assert_eq!(grid.orders().len(), 4);
+        rest_all(&mut grid);
+
+        // The sell fills and the level below is bought again
+        let sell = grid
+            .orders()
+            .iter()
+            .find(|o| o.px == 2000.0)
+            .unwrap()
+            .clone();
+        let actions = grid.on_fill(&fill(&sell, "1", 3));
+        assert_eq!(sides(&actions), vec![(true, 1950.0)]);
+        assert_eq!(grid.position(), 0.0);
+    }
+
+    #[test]
+    fn test_grid_range_rules() {
+        // Out of range the grid is canceled, then laid again when the mid returns
+        let mut grid = Grid::new(config(OutOfRange::Cancel)).unwrap();
+        grid.on_mid(2000.0);
+        rest_all(&mut grid);
+        let actions = grid.on_mid(2200.0);
+        assert_eq!(actions.cancels.len(), 4);
+        assert!(actions.orders.is_empty());
+        for order in grid.orders().to_vec() {
+            grid.on_order_update(&OrderUpdate {
+                order: crate::BasicOrder {
+                    coin: "ETH".to_string(),
+                    side: String::new(),
+                    limit_px: order.px.to_string(),
+                    sz: "1".to_string(),
+                    oid: order.order.oid.unwrap(),
+                    timestamp: Timestamp::default(),
+                    orig_sz: "1".to_string(),
+                    cloid: None,
+                    extra: Default::default(),
+                },
+                status: "canceled".to_string(),
+                status_timestamp: Timestamp::default(),
+            });
+        }
+        assert!(grid.orders().is_empty());
+        assert_eq!(grid.on_mid(2000.0).orders.len(), 4);
+
+        // Recentering moves the range and lays it again around the mid
+        let mut grid = Grid::new(config(OutOfRange::Recenter)).unwrap();
+        grid.on_mid(2000.0);
+        rest_all(&mut grid);
+        let actions = grid.on_mid(2300.0);
+        assert_eq!(actions.cancels.len(), 4);
+        assert_eq!(
+            sides(&actions),
+            vec![
+                (true, 2200.0),
+                (true, 2250.0),
+                (false, 2350.0),
+                (false, 2400.0)
+            ]
+        );
+
+        // The position cap keeps the outer levels out
+        let mut grid = Grid::new(GridConfig {
+            max_position: Some(1.0),
+            ..config(OutOfRange::Hold)
+        })
+        .unwrap();
+        let actions = grid.on_mid(2000.0);
+        assert_eq!(sides(&actions), vec![(true, 1950.0), (false, 2050.0)]);
+    }
+}
@@ -0,0 +1,282 @@
+use crate::{
+    ClientCancelRequest, ClientCancelRequestCloid, ClientLimit, ClientOrder, ClientOrderRequest,
+    Error, ExchangeClient, ExchangeDataStatus, ExchangeResponseStatus, InfoClient, Subscription,
+};
+use futures_util::Stream;
+use gateway_proto::{
+    cancel_request, gateway_server::GatewayServer, place_order_response, CancelRequest,
+    CancelResponse, FilledOrder, PlaceOrderRequest, PlaceOrderResponse, QueryRequest,
+    QueryResponse, StreamMessage, SubscribeRequest, TimeInForce,
+};
+use log::warn;
+use std::{net::SocketAddr, pin::Pin, sync::Arc};
+use tokio::sync::{
+    mpsc::{channel as bounded_channel, unbounded_channel},
+    Mutex,
+};
+use tonic::{Request, Response, Status};
+use uuid::Uuid;
+
+// Generated from proto/gateway.proto.
+#[allow(clippy::all, unreachable_pub)]
+pub mod gateway_proto {
+    tonic::include_proto!("hyperliquid.gateway.v1");
+}
+
+// Messages a Subscribe call may have in flight before the gateway stops reading its
+// subscription, at which point the websocket consumer's queue grows instead.
+const STREAM_CAPACITY: usize = 1024;
+
+// A gRPC server exposing the SDK, so services in any language can share one process's
+// exchange connection and signing key, e.g. as a sidecar. Orders and cancels are signed with
+// `exchange_client`'s wallet; subscriptions share one websocket connection.
+//
+//     let exchange_client = Arc::new(ExchangeClient::new(None, wallet, None, None, None).await?);
+//     GrpcGateway::new(exchange_client).serve("127.0.0.1:50051".parse()?).await?;
+//
+// The gateway doesn't authenticate callers, so it must only listen where untrusted clients
+// can't reach it.
+pub struct GrpcGateway {
+    exchange_client: Arc<ExchangeClient>,
+    info_client: Arc<Mutex<InfoClient>>,
+    // Queries don't need the websocket, so they don't wait on subscription changes
+    query_client: InfoClient,
+}
+
+impl GrpcGateway {
+    pub fn new(exchange_client: Arc<ExchangeClient>) -> GrpcGateway {
+        GrpcGateway {
+            info_client: Arc::new(Mutex::new(exchange_client.info_client())),
+            query_client: exchange_client.info_client(),
+            exchange_client,
+        }
+    }
+
+    // The service, to serve alongside others on a `tonic::transport::Server`.
+    pub fn into_service(self) -> GatewayServer<GrpcGateway> {
+        GatewayServer::new(self)
+    }
+
+    pub async fn serve(self, addr: SocketAddr) -> Result<(), Error> {
+        tonic::transport::Server::builder()
+            .add_service(self.into_service())
+            .serve(addr)
+            .await
+            .map_err(|e| Error::Grpc(e.to_string()))
+    }
+}
+
+#[tonic::async_trait]
+impl gateway_proto::gateway_server::Gateway for GrpcGateway {
+    type SubscribeStream = Pin<Box<dyn Stream<Item = Result<StreamMessage, Status>> + Send>>;
+
+    async fn subscribe(
+        &self,
+        request: Request<SubscribeRequest>,
+    ) -> Result<Response<Self::SubscribeStream>, Status> {
+        let subscription: Subscription =
+            serde_json::from_str(&request.into_inner().subscription_json)
+                .map_err(|e| Status::invalid_argument(format!("invalid subscription: {e}")))?;
+        let (sender, mut receiver) = unbounded_channel();
+        let subscription_id = self
+            .info_client
+            .lock()
+            .await
+            .subscribe(subscription, sender)
+            .await
+            .map_err(|e| Status::unavailable(e.to_string()))?;
+
+        // Forwards messages until the caller goes away, then unsubscribes
+        let (stream_sender, stream_receiver) = bounded_channel(STREAM_CAPACITY);
+        let info_client = self.info_client.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    message = receiver.recv() => {
+                        let Some(message) = message else { break };
+                        let Some(channel) = message.channel() else { continue };
+                        let streamed = serde_json::to_string(&message)
+                            .map(|json| StreamMessage {
+                                channel: channel.to_string(),
+                                json,
+                            })
+                            .map_err(|e| Status::internal(e.to_string()));
+                        if stream_sender.send(streamed).await.is_err() {
+                            break;
+                        }
+                    }
+                    _ = stream_sender.closed() => break,
+                }
+            }
+            if let Err(err) = info_client.lock().await.unsubscribe(subscription_id).await {
+                warn!("Unsubscribing gRPC stream {subscription_id} failed: {err}");
+            }
+        });
+        let stream = futures_util::stream::unfold(stream_receiver, |mut receiver| async move {
+            receiver.recv().await.map(|item| (item, receiver))
+        });
+        Ok(Response::new(Box::pin(stream)))
+    }
+
+    async fn place_order(
+        &self,
+        request: Request<PlaceOrderRequest>,
+    ) -> Result<Response<PlaceOrderResponse>, Status> {
+        let order = order_request(request.into_inner())?;
+        let response = self
+            .exchange_client
+            .order(order, None)
+            .await
+            .map_err(|e| Status::unavailable(e.to_string()))?;
+        Ok(Response::new(order_response(response)))
+    }
+
+    async fn cancel(
+        &self,
+        request: Request<CancelRequest>,
+    ) -> Result<Response<CancelResponse>, Status> {
+        let CancelRequest { coin, id } = request.into_inner();
+        let response = match id {
+            Some(cancel_request::Id::Oid(oid)) => {
+                self.exchange_client
+                    .cancel(ClientCancelRequest { asset: coin, oid }, None)
+                    .await
+            }
+            Some(cancel_request::Id::Cloid(cloid)) => {
+                let cloid = parse_cloid(&cloid)?;
+                self.exchange_client
+                    .cancel_by_cloid(ClientCancelRequestCloid { asset: coin, cloid }, None)
+                    .await
+            }
+            None => return Err(Status::invalid_argument("missing oid or cloid")),
+        }
+        .map_err(|e| Status::unavailable(e.to_string()))?;
+        let error = match first_status(response) {
+            Ok(ExchangeDataStatus::Error(message)) | Err(message) => message,
+            Ok(_) => String::new(),
+        };
+        Ok(Response::new(CancelResponse { error }))
+    }
+
+    async fn query(
+        &self,
+        request: Request<QueryRequest>,
+    ) -> Result<Response<QueryResponse>, Status> {
+        let body = request.into_inner().request_json;
+        serde_json::from_str::<serde_json::Value>(&body)
+            .map_err(|e| Status::invalid_argument(format!("invalid request: {e}")))?;
+        let json = self
+            .query_client
+            .http_client
+            .post("/info", body)
+            .await
+            .map_err(|e| Status::unavailable(e.to_string()))?;
+        Ok(Response::new(QueryResponse { json }))
+    }
+}
+
+// Returns tonic's `Status` as is, since that's what the service methods propagate with `?`
+#[allow(clippy::result_large_err)]
+fn order_request(request: PlaceOrderRequest) -> Result<ClientOrderRequest, Status> {
+    let tif = match TimeInForce::try_from(request.tif) {
+        Ok(TimeInForce::Gtc) => "Gtc",
+        Ok(TimeInForce::Alo) => "Alo",
+        Ok(TimeInForce::Ioc) => "Ioc",
+        Err(_) => return Err(Status::invalid_argument("unknown time in force")),
+    };
+    Ok(ClientOrderRequest {
+        asset: request.coin,
+        is_buy: request.is_buy,
+        reduce_only: request.reduce_only,
+        limit_px: request.limit_px,
+        sz: request.sz,
+        cloid: request.cloid.as_deref().map(parse_cloid).transpose()?,
+        order_type: ClientOrder::Limit(ClientLimit {
+            tif: tif.to_string(),
+        }),
+    })
+}
+
+fn order_response(response: ExchangeResponseStatus) -> PlaceOrderResponse {
+    let status = match first_status(response) {
+        Ok(ExchangeDataStatus::Resting(resting)) => {
+            place_order_response::Status::RestingOid(resting.oid)
+        }
+        Ok(ExchangeDataStatus::Filled(filled)) => {
+            place_order_response::Status::Filled(FilledOrder {
+                oid: filled.oid,
+                total_sz: filled.total_sz,
+                avg_px: filled.avg_px,
+            })
+        }
+        Ok(ExchangeDataStatus::Error(message)) | Err(message) => {
+            place_order_response::Status::Error(message)
+        }
+        Ok(status) => place_order_response::Status::Error(format!("unexpected status {status:?}")),
+    };
+    PlaceOrderResponse {
+        status: Some(status),
+    }
+}
+
+// The status of a single order or cancel, or the error the whole action was rejected with.
+fn first_status(response: ExchangeResponseStatus) -> Result<ExchangeDataStatus, String> {
+    match response {
+        ExchangeResponseStatus::Ok(response) => response
+            .data
+            .and_then(|data| data.statuses.into_iter().next())
+            .ok_or_else(|| "missing status".to_string()),
+        ExchangeResponseStatus::Err(err) => Err(err.message),
+    }
+}
+
+#[allow(clippy::result_large_err)]
+fn parse_cloid(cloid: &str) -> Result<Uuid, Status> {
+    Uuid::parse_str(cloid).map_err(|e| Status::invalid_argument(format!("invalid cloid: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grpc_orders() {
+        let request = PlaceOrderRequest {
+            coin: "ETH".to_string(),
+            is_buy: true,
+            limit_px: 2000.0,
+            sz: 0.1,
+            reduce_only: false,
+            tif: TimeInForce::Alo as i32,
+            cloid: Some("6b2f1b1e-0000-4000-8000-000000000001".to_string()),
+        };
+        let order = order_request(request.clone()).unwrap();
+        assert!(matches!(&order.order_type, ClientOrder::Limit(limit) if limit.tif == "Alo"));
+        assert!(order.cloid.is_some());
+        let invalid = PlaceOrderRequest {
+            cloid: Some("not-a-uuid".to_string()),
+            ..request
+        };
+        assert_eq!(
+            order_request(invalid).unwrap_err().code(),
+            tonic::Code::InvalidArgument
+        );
+
+        let response: ExchangeResponseStatus = serde_json::from_value(serde_json::json!({
+            "status": "ok",
+            "response": {
+                "type": "order",
+                "data": {"statuses": [{"filled": {"totalSz": "0.1", "avgPx": "2000.0", "oid": 7}}]}
+            }
+        }))
+        .unwrap();
+        assert_eq!(
+            order_response(response).status,
+            Some(place_order_response::Status::Filled(FilledOrder {
+                oid: 7,
+                total_sz: "0.1".to_string(),
+                avg_px: "2000.0".to_string(),
+            }))
+        );
+    }
+}
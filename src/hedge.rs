@@ -0,0 +1,269 @@
+use crate::{
+    consts::EPSILON,
+    helpers::{round_perp_px, round_sz_down},
+    prelude::*,
+    ClientLimit, ClientOrder, ClientOrderRequest, Error, ExchangeClient, ExchangeDataStatus,
+    ExchangeResponseStatus, SafeOutcome, SafeSubmitter,
+};
+use ethers::types::H160;
+use log::{info, warn};
+use std::{sync::Arc, time::Duration};
+use tokio::sync::mpsc::UnboundedReceiver;
+use uuid::Uuid;
+
+#[derive(Debug, Clone)]
+pub struct HedgeConfig {
+    // The perp hedging the exposure
+    pub coin: String,
+    // Perp units held against each unit of exposure, normally 1
+    pub hedge_ratio: f64,
+    // How far, in perp units, the position may drift from the hedge before it is traded back
+    pub band: f64,
+    // How far through the mid (a fraction) each IOC order may fill
+    pub slippage: f64,
+}
+
+// An order moving the hedge towards its target.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HedgeOrder {
+    pub is_buy: bool,
+    pub sz: f64,
+    pub reduce_only: bool,
+}
+
+// The orders that bring `position` to the hedge of `exposure` once it has left the band. A trade
+// that would flip the position is split: a reduce-only order closes it first, so a stale
+// position can never make the close open a position the other way, and a second order opens
+// the rest.
+pub fn hedge_orders(
+    position: f64,
+    exposure: f64,
+    config: &HedgeConfig,
+    sz_decimals: u32,
+) -> Vec<HedgeOrder> {
+    let target = -exposure * config.hedge_ratio;
+    let delta = target - position;
+    if delta.abs() <= config.band {
+        return Vec::new();
+    }
+    let is_buy = delta > 0.0;
+    let reduces = position.abs() > EPSILON && (position > 0.0) != is_buy;
+    let (reduce, open) = if reduces {
+        // A full close matches the position exactly
+        let reduce = if delta.abs() >= position.abs() {
+            position.abs()
+        } else {
+            round_sz_down(delta.abs(), sz_decimals)
+        };
+        (reduce, delta.abs() - position.abs().min(delta.abs()))
+    } else {
+        (0.0, delta.abs())
+    };
+    [(reduce, true), (round_sz_down(open, sz_decimals), false)]
+        .into_iter()
+        .filter(|&(sz, _)| sz > EPSILON)
+        .map(|(sz, reduce_only)| HedgeOrder {
+            is_buy,
+            sz,
+            reduce_only,
+        })
+        .collect()
+}
+
+// Keeps a perp position offsetting an exposure held elsewhere, e.g. an options book's delta or
+// spot holdings, trading only when it drifts out of the band. The position is read back from
+// the account after every hedge rather than inferred from fills, so partial fills, lost acks and
+// trades made by hand are all picked up.
+pub struct DeltaHedger {
+    pub config: HedgeConfig,
+    client: Arc<ExchangeClient>,
+    submitter: SafeSubmitter,
+}
+
+impl DeltaHedger {
+    pub fn new(client: Arc<ExchangeClient>, config: HedgeConfig) -> DeltaHedger {
+        DeltaHedger {
+            config,
+            submitter: SafeSubmitter::new(client.clone(), Duration::from_secs(5)),
+            client,
+        }
+    }
+
+    pub fn with_submitter(mut self, submitter: SafeSubmitter) -> Self {
+        self.submitter = submitter;
+        self
+    }
+
+    // Hedges each exposure received, in the coin's units, until `exposures` closes. Exposures
+    // arriving while a hedge is in flight are coalesced into the latest. Failed orders and
+    // lookups are logged and retried on the next exposure; only the startup lookups are fatal.
+    pub async fn run(&self, mut exposures: UnboundedReceiver<f64>) -> Result<()> {
+        let user = self.client.account_address(None).await?;
+        let info_client = self.client.info_client();
+        let sz_decimals = info_client
+            .meta()
+            .await?
+            .universe
+            .into_iter()
+            .find(|asset| asset.name == self.config.coin)
+            .ok_or(Error::AssetNotFound)?
+            .sz_decimals;
+        // `None` after a failed refresh, until one succeeds, so a stale position isn't hedged
+        let mut position = Some(self.position(user).await?);
+
+        while let Some(mut exposure) = exposures.recv().await {
+            while let Ok(latest) = exposures.try_recv() {
+                exposure = latest;
+            }
+            let current = match position {
+                Some(position) => position,
+                None => match self.position(user).await {
+                    Ok(current) => *position.insert(current),
+                    Err(err) => {
+                        warn!("{} hedge position lookup failed: {err}", self.config.coin);
+                        continue;
+                    }
+                },
+            };
+            let orders = hedge_orders(current, exposure, &self.config, sz_decimals);
+            if orders.is_empty() {
+                continue;
+            }
+            let mid = match info_client.all_mids().await {
+                Ok(mids) => mids
+                    .get(&self.config.coin)
+                    .and_then(|mid| mid.parse::<f64>().ok()),
+                Err(err) => {
+                    warn!("{} hedge mid lookup failed: {err}", self.config.coin);
+                    continue;
+                }
+            };
+            let Some(mid) = mid else {
+                warn!("No mid for {}, skipping hedge", self.config.coin);
+                continue;
+            };
+            info!(
+                "Hedging {} exposure of {exposure} from a {current} position: {orders:?}",
+                self.config.coin
+            );
+            for order in orders {
+                if !self.send(order, mid, sz_decimals).await && order.reduce_only {
+                    // Don't open the other way without having closed
+                    break;
+                }
+            }
+            position = match self.position(user).await {
+                Ok(position) => Some(position),
+                Err(err) => {
+                    warn!("{} hedge position lookup failed: {err}", self.config.coin);
+                    None
+                }
+            };
+        }
+        Ok(())
+    }
+
+    // Whether the order was accepted.
+    async fn send(&self, order: HedgeOrder, mid: f64, sz_decimals: u32) -> bool {
+        let slipped = mid
+            * if order.is_buy {
+                1.0 + self.config.slippage
+            } else {
+                1.0 - self.config.slippage
+            };
+        let request = ClientOrderRequest {
+            asset: self.config.coin.clone(),
+            is_buy: order.is_buy,
+            reduce_only: order.reduce_only,
+            limit_px: round_perp_px(slipped, sz_decimals),
+            sz: order.sz,
+            cloid: Some(Uuid::new_v4()),
+            order_type: ClientOrder::Limit(ClientLimit {
+                tif: "Ioc".to_string(),
+            }),
+        };
+        let error = match self.submitter.submit(request, None).await {
+            Ok(SafeOutcome::Acked(ExchangeResponseStatus::Ok(response))) => {
+                match response
+                    .data
+                    .and_then(|data| data.statuses.into_iter().next())
+                {
+                    Some(ExchangeDataStatus::Error(error)) => error,
+                    _ => return true,
+                }
+            }
+            Ok(SafeOutcome::Acked(ExchangeResponseStatus::Err(err))) => format!("{err:?}"),
+            Ok(SafeOutcome::NotPlaced) => "not placed".to_string(),
+            Ok(SafeOutcome::Settled(_)) => return true,
+            Err(err) => err.to_string(),
+        };
+        warn!("{} hedge order {order:?} failed: {error}", self.config.coin);
+        false
+    }
+
+    async fn position(&self, user: H160) -> Result<f64> {
+        let user_state = self.client.info_client().user_state(user).await?;
+        Ok(user_state
+            .asset_positions
+            .iter()
+            .find(|asset_position| asset_position.position.coin == self.config.coin)
+            .and_then(|asset_position| asset_position.position.szi.parse().ok())
+            .unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hedge_orders() {
+        let config = HedgeConfig {
+            coin: "ETH".to_string(),
+            hedge_ratio: 1.0,
+            band: 0.5,
+            slippage: 0.01,
+        };
+        // Within the band, nothing trades
+        assert!(hedge_orders(-9.7, 10.0, &config, 2).is_empty());
+        // Out of it, the hedge is traded all the way back to its target
+        assert_eq!(
+            hedge_orders(-9.0, 10.0, &config, 2),
+            vec![HedgeOrder {
+                is_buy: false,
+                sz: 1.0,
+                reduce_only: false
+            }]
+        );
+        // A shrinking exposure is unwound reduce-only
+        assert_eq!(
+            hedge_orders(-10.0, 4.0049, &config, 2),
+            vec![HedgeOrder {
+                is_buy: true,
+                sz: 5.99,
+                reduce_only: true
+            }]
+        );
+        // A flip closes the short before opening the long
+        assert_eq!(
+            hedge_orders(-2.0, -3.125, &config, 2),
+            vec![
+                HedgeOrder {
+                    is_buy: true,
+                    sz: 2.0,
+                    reduce_only: true
+                },
+                HedgeOrder {
+                    is_buy: true,
+                    sz: 3.12,
+                    reduce_only: false
+                }
+            ]
+        );
+        let half = HedgeConfig {
+            hedge_ratio: 0.5,
+            ..config
+        };
+        assert_eq!(hedge_orders(0.0, 10.0, &half, 2)[0].sz, 5.0);
+    }
+}
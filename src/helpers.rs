@@ -3,16 +3,45 @@ use chrono::prelude::Utc;
 use lazy_static::lazy_static;
 use log::info;
 use rand::{thread_rng, Rng};
+use serde::Deserialize;
 use std::sync::atomic::{AtomicU64, Ordering};
 use uuid::Uuid;
 
-fn now_timestamp_ms() -> u64 {
+pub(crate) fn now_timestamp_ms() -> u64 {
     let now = Utc::now();
     now.timestamp_millis() as u64
 }
 
-pub(crate) fn next_nonce() -> u64 {
-    let nonce = CUR_NONCE.fetch_add(1, Ordering::Relaxed);
+// Restricts a client to nonces congruent to `index` modulo `count`, so several processes
+// signing for the same address never produce the same nonce.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoncePartition {
+    index: u64,
+    count: u64,
+}
+
+impl NoncePartition {
+    pub fn new(index: u64, count: u64) -> NoncePartition {
+        let count = count.max(1);
+        NoncePartition {
+            index: index % count,
+            count,
+        }
+    }
+}
+
+impl Default for NoncePartition {
+    fn default() -> NoncePartition {
+        NoncePartition::new(0, 1)
+    }
+}
+
+pub(crate) fn next_nonce(partition: NoncePartition) -> u64 {
+    let NoncePartition { index, count } = partition;
+    // Reserves [raw, raw + count), which holds exactly one nonce of the partition, so clients
+    // with different partitions in one process can't collide either
+    let raw = CUR_NONCE.fetch_add(count, Ordering::Relaxed);
+    let nonce = raw + (index + count - raw % count) % count;
     let now_ms = now_timestamp_ms();
     if nonce > now_ms + 1000 {
         info!("nonce progressed too far ahead {nonce} {now_ms}");
@@ -68,6 +97,21 @@ pub fn truncate_float(float: f64, decimals: u32, round_up: bool) -> f64 {
     float as f64 / pow10
 }
 
+// Rounds a size down to `decimals`, nudged so values that are already round don't drop a unit to
+// float error.
+pub(crate) fn round_sz_down(sz: f64, decimals: u32) -> f64 {
+    let pow10 = 10f64.powi(decimals as i32);
+    (sz * pow10 + 1e-9).floor() / pow10
+}
+
+// Perp prices take at most 5 significant figures and 6 decimals less the size's.
+pub(crate) fn round_perp_px(px: f64, sz_decimals: u32) -> f64 {
+    let magnitude = px.abs().log10().floor() as i32;
+    let decimals = (4 - magnitude).min(6 - sz_decimals as i32).max(0);
+    let pow10 = 10f64.powi(decimals);
+    (px * pow10).round() / pow10
+}
+
 pub fn bps_diff(x: f64, y: f64) -> u16 {
     if x.abs() < EPSILON {
         INF_BPS
@@ -76,7 +120,8 @@ pub fn bps_diff(x: f64, y: f64) -> u16 {
     }
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum BaseUrl {
     Localhost,
     Testnet,
@@ -91,6 +136,14 @@ impl BaseUrl {
             BaseUrl::Testnet => TESTNET_API_URL.to_string(),
         }
     }
+
+    pub(crate) fn get_rpc_url(&self) -> String {
+        match self {
+            BaseUrl::Localhost => LOCAL_API_URL.to_string(),
+            BaseUrl::Mainnet => MAINNET_RPC_URL.to_string(),
+            BaseUrl::Testnet => TESTNET_RPC_URL.to_string(),
+        }
+    }
 }
 
 lazy_static! {
@@ -101,6 +154,24 @@ lazy_static! {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_nonce_partition() {
+        // Interleaved with a client that isn't partitioned
+        let odd = NoncePartition::new(1, 2);
+        let mut odd_nonces = Vec::new();
+        let mut nonces = Vec::new();
+        for _ in 0..4 {
+            odd_nonces.push(next_nonce(odd));
+            nonces.push(next_nonce(NoncePartition::default()));
+        }
+        assert!(odd_nonces.iter().all(|nonce| nonce % 2 == 1));
+        assert!(odd_nonces.windows(2).all(|pair| pair[0] < pair[1]));
+        nonces.extend(odd_nonces);
+        nonces.sort();
+        nonces.dedup();
+        assert_eq!(nonces.len(), 8);
+    }
+
     #[test]
     fn float_to_string_for_hashing_test() {
         assert_eq!(float_to_string_for_hashing(0.), "0".to_string());
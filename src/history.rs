@@ -0,0 +1,289 @@
+use crate::{prelude::*, Error, ExtraFields, L2BookData, Timestamp, Trade};
+use chrono::{DateTime, Duration, DurationRound, NaiveDateTime, Utc};
+use log::warn;
+use lz4_flex::frame::FrameDecoder;
+use reqwest::{Client, StatusCode};
+use serde::Deserialize;
+use std::{io::Read, path::PathBuf};
+
+pub const MARKET_DATA_ARCHIVE_URL: &str = "https://hyperliquid-archive.s3.amazonaws.com";
+pub const NODE_DATA_ARCHIVE_URL: &str = "https://hl-mainnet-node-data.s3.amazonaws.com";
+
+// Where archive objects are read from. Both buckets are requester-pays, so `Http` needs either
+// a signing proxy / mirror in front of them or presigned access; objects synced locally with
+// `aws s3 sync --request-payer requester` can be read with `Local` using the same layout.
+#[derive(Debug, Clone)]
+pub enum ArchiveSource {
+    Http {
+        market_data_url: String,
+        node_data_url: String,
+    },
+    Local {
+        market_data_dir: PathBuf,
+        node_data_dir: PathBuf,
+    },
+}
+
+impl Default for ArchiveSource {
+    fn default() -> Self {
+        ArchiveSource::Http {
+            market_data_url: MARKET_DATA_ARCHIVE_URL.to_string(),
+            node_data_url: NODE_DATA_ARCHIVE_URL.to_string(),
+        }
+    }
+}
+
+// `{"time": .., "ver_num": 1, "raw": {"channel": "l2Book", "data": L2BookData}}`
+#[derive(Deserialize)]
+struct L2BookLine {
+    raw: L2BookRaw,
+}
+
+#[derive(Deserialize)]
+struct L2BookRaw {
+    data: L2BookData,
+}
+
+#[derive(Deserialize)]
+struct NodeTradeLine {
+    coin: String,
+    side: String,
+    time: String,
+    px: String,
+    sz: String,
+    hash: String,
+    #[serde(default)]
+    tid: u64,
+}
+
+pub struct HistoryClient {
+    pub http_client: Client,
+    pub source: ArchiveSource,
+}
+
+impl HistoryClient {
+    pub fn new(client: Option<Client>, source: ArchiveSource) -> HistoryClient {
+        HistoryClient {
+            http_client: client.unwrap_or_default(),
+            source,
+        }
+    }
+
+    // Hourly l2Book snapshots for `coins` between `start` (inclusive) and `end` (exclusive),
+    // ordered by coin then time. Hours missing from the archive are skipped.
+    pub async fn l2_books(
+        &self,
+        coins: &[&str],
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<L2BookData>> {
+        let range = Timestamp::from(start)..Timestamp::from(end);
+        let mut books = Vec::new();
+        for coin in coins {
+            for hour in archive_hours(start, end) {
+                let key = format!(
+                    "market_data/{}/{}/l2Book/{coin}.lz4",
+                    hour.format("%Y%m%d"),
+                    hour.format("%-H")
+                );
+                let Some(data) = self.fetch(false, &key).await? else {
+                    continue;
+                };
+                books.extend(
+                    parse_l2_books(&decompress(&data)?)?
+                        .into_iter()
+                        .filter(|book| range.contains(&book.time)),
+                );
+            }
+        }
+        Ok(books)
+    }
+
+    // Trades between `start` (inclusive) and `end` (exclusive), ordered by time. An empty
+    // `coins` returns every coin.
+    pub async fn trades(
+        &self,
+        coins: &[&str],
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<Trade>> {
+        let range = Timestamp::from(start)..Timestamp::from(end);
+        let mut trades = Vec::new();
+        for hour in archive_hours(start, end) {
+            let key = format!(
+                "node_trades/hourly/{}/{}.lz4",
+                hour.format("%Y%m%d"),
+                hour.format("%-H")
+            );
+            let Some(data) = self.fetch(true, &key).await? else {
+                continue;
+            };
+            trades.extend(
+                parse_trades(&decompress(&data)?, coins)?
+                    .into_iter()
+                    .filter(|trade| range.contains(&trade.time)),
+            );
+        }
+        Ok(trades)
+    }
+
+    async fn fetch(&self, node_data: bool, key: &str) -> Result<Option<Vec<u8>>> {
+        match &self.source {
+            ArchiveSource::Http {
+                market_data_url,
+                node_data_url,
+            } => {
+                let base_url = if node_data {
+                    node_data_url
+                } else {
+                    market_data_url
+                };
+                let response = self
+                    .http_client
+                    .get(format!("{base_url}/{key}"))
+                    .send()
+                    .await
+                    .map_err(|e| Error::GenericRequest(e.to_string()))?;
+                if response.status() == StatusCode::NOT_FOUND {
+                    warn!("Archive object {key} not found");
+                    return Ok(None);
+                }
+                if !response.status().is_success() {
+                    return Err(Error::History(format!(
+                        "fetching {key}: status code {}",
+                        response.status()
+                    )));
+                }
+                let bytes = response
+                    .bytes()
+                    .await
+                    .map_err(|e| Error::GenericRequest(e.to_string()))?;
+                Ok(Some(bytes.to_vec()))
+            }
+            ArchiveSource::Local {
+                market_data_dir,
+                node_data_dir,
+            } => {
+                let dir = if node_data {
+                    node_data_dir
+                } else {
+                    market_data_dir
+                };
+                match std::fs::read(dir.join(key)) {
+                    Ok(data) => Ok(Some(data)),
+                    Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                        warn!("Archive file {key} not found");
+                        Ok(None)
+                    }
+                    Err(err) => Err(Error::History(format!("reading {key}: {err}"))),
+                }
+            }
+        }
+    }
+}
+
+// The archive hours overlapping [start, end).
+pub fn archive_hours(start: DateTime<Utc>, end: DateTime<Utc>) -> Vec<DateTime<Utc>> {
+    let Ok(mut hour) = start.duration_trunc(Duration::hours(1)) else {
+        return Vec::new();
+    };
+    let mut hours = Vec::new();
+    while hour < end {
+        hours.push(hour);
+        hour += Duration::hours(1);
+    }
+    hours
+}
+
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>> {
+    let mut decompressed = Vec::new();
+    FrameDecoder::new(data)
+        .read_to_end(&mut decompressed)
+        .map_err(|e| Error::History(format!("lz4: {e}")))?;
+    Ok(decompressed)
+}
+
+// Parses decompressed l2Book archive lines, which wrap the same payload as the l2Book channel.
+pub fn parse_l2_books(data: &[u8]) -> Result<Vec<L2BookData>> {
+    lines(data)
+        .map(|line| {
+            serde_json::from_str::<L2BookLine>(line)
+                .map(|line| line.raw.data)
+                .map_err(|e| Error::JsonParse(e.to_string()))
+        })
+        .collect()
+}
+
+// Parses decompressed node trade lines into the trades channel's type. Node trades carry a
+// nanosecond timestamp string, which is truncated to milliseconds.
+pub fn parse_trades(data: &[u8], coins: &[&str]) -> Result<Vec<Trade>> {
+    let mut trades = Vec::new();
+    for line in lines(data) {
+        let trade: NodeTradeLine =
+            serde_json::from_str(line).map_err(|e| Error::JsonParse(e.to_string()))?;
+        if !coins.is_empty() && !coins.contains(&trade.coin.as_str()) {
+            continue;
+        }
+        let time = NaiveDateTime::parse_from_str(&trade.time, "%Y-%m-%dT%H:%M:%S%.f")
+            .map_err(|e| Error::GenericParse(format!("trade time {}: {e}", trade.time)))?;
+        trades.push(Trade {
+            coin: trade.coin,
+            side: trade.side,
+            px: trade.px,
+            sz: trade.sz,
+            time: Timestamp::from(time.and_utc()),
+            hash: trade.hash,
+            tid: trade.tid,
+            extra: ExtraFields::new(),
+        });
+    }
+    Ok(trades)
+}
+
+fn lines(data: &[u8]) -> impl Iterator<Item = &str> {
+    data.split(|byte| *byte == b'\n')
+        .filter_map(|line| std::str::from_utf8(line).ok())
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use lz4_flex::frame::FrameEncoder;
+    use std::io::Write;
+
+    #[test]
+    fn test_archive_hours() {
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 22, 30, 0).unwrap();
+        let end = Utc.with_ymd_and_hms(2024, 1, 2, 1, 0, 0).unwrap();
+        let hours: Vec<String> = archive_hours(start, end)
+            .iter()
+            .map(|hour| hour.format("%Y%m%d/%-H").to_string())
+            .collect();
+        assert_eq!(hours, vec!["20240101/22", "20240101/23", "20240102/0"]);
+    }
+
+    #[test]
+    fn test_parse_archives() -> Result<()> {
+        let l2_book = r#"{"time":"2023-09-16T09:00:00.087585781","ver_num":1,"raw":{"channel":"l2Book","data":{"coin":"SOL","time":1694854800062,"levels":[[{"px":"19.828","sz":"1285.09","n":5}],[{"px":"19.83","sz":"10.0","n":1}]]}}}"#;
+        let mut encoder = FrameEncoder::new(Vec::new());
+        encoder
+            .write_all(format!("{l2_book}\n{l2_book}\n").as_bytes())
+            .unwrap();
+        let books = parse_l2_books(&decompress(&encoder.finish().unwrap())?)?;
+        assert_eq!(books.len(), 2);
+        assert_eq!(books[0].coin, "SOL");
+        assert_eq!(books[0].time.as_millis(), 1694854800062);
+        assert_eq!(books[0].levels[1][0].px, "19.83");
+
+        let trades = r#"{"coin":"SOL","side":"A","time":"2025-03-01T09:00:00.123456789","px":"140.1","sz":"2.5","hash":"0xabc","trade_dir_override":"Na","side_info":[]}
+{"coin":"BTC","side":"B","time":"2025-03-01T09:00:01.000000000","px":"84000","sz":"0.1","hash":"0xdef","trade_dir_override":"Na","side_info":[]}"#;
+        let sol = parse_trades(trades.as_bytes(), &["SOL"])?;
+        assert_eq!(sol.len(), 1);
+        assert_eq!(sol[0].time.as_millis(), 1740819600123);
+        assert_eq!(parse_trades(trades.as_bytes(), &[])?.len(), 2);
+        Ok(())
+    }
+}
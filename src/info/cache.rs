@@ -0,0 +1,82 @@
+use crate::{
+    info::UserFeesResponse,
+    meta::{Meta, SpotMeta},
+};
+use ethers::types::H160;
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+pub(crate) struct TtlCache<K, V> {
+    ttl: Duration,
+    entries: Mutex<HashMap<K, (Instant, V)>>,
+}
+
+impl<K: Eq + Hash, V: Clone> TtlCache<K, V> {
+    pub(crate) fn new(ttl: Duration) -> TtlCache<K, V> {
+        TtlCache {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub(crate) fn get(&self, key: &K) -> Option<V> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(key)
+            .filter(|(fetched_at, _)| fetched_at.elapsed() < self.ttl)
+            .map(|(_, value)| value.clone())
+    }
+
+    pub(crate) fn insert(&self, key: K, value: V) {
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(key, (Instant::now(), value));
+    }
+
+    pub(crate) fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+}
+
+// Responses of slowly-changing endpoints kept for `ttl` after they are fetched.
+pub(crate) struct InfoCache {
+    pub(crate) meta: TtlCache<(), Meta>,
+    pub(crate) spot_meta: TtlCache<(), SpotMeta>,
+    pub(crate) user_fees: TtlCache<H160, UserFeesResponse>,
+}
+
+impl InfoCache {
+    pub(crate) fn new(ttl: Duration) -> InfoCache {
+        InfoCache {
+            meta: TtlCache::new(ttl),
+            spot_meta: TtlCache::new(ttl),
+            user_fees: TtlCache::new(ttl),
+        }
+    }
+
+    pub(crate) fn clear(&self) {
+        self.meta.clear();
+        self.spot_meta.clear();
+        self.user_fees.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entries_expire() {
+        let cache = TtlCache::new(Duration::from_millis(20));
+        cache.insert("meta", 1);
+        assert_eq!(cache.get(&"meta"), Some(1));
+        assert_eq!(cache.get(&"spotMeta"), None);
+        std::thread::sleep(Duration::from_millis(30));
+        assert_eq!(cache.get(&"meta"), None);
+    }
+}
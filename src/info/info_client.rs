@@ -1,28 +1,40 @@
 use crate::{
     info::{
-        CandlesSnapshotResponse, FundingHistoryResponse, L2SnapshotResponse, OpenOrdersResponse,
-        RecentTradesResponse, UserFillsResponse, UserStateResponse,
+        cache::InfoCache, CandlesSnapshotResponse, ExtraAgentResponse, FrontendOpenOrdersResponse,
+        FundingHistoryResponse, L2SnapshotResponse, OpenOrdersResponse, OrderId,
+        OrderStatusResponse, PerpDeployAuctionStatus, RecentTradesResponse, ReferralResponse,
+        UserFeesResponse, UserFillsResponse, UserFundingResponse, UserRateLimitResponse,
+        UserRoleResponse, UserStateResponse,
     },
-    meta::{Meta, SpotMeta},
+    meta::{Meta, PerpAssetCtx, SpotMeta},
     prelude::*,
     req::HttpClient,
-    ws::{Subscription, WsManager},
-    BaseUrl, Error, Message,
+    unknown_fields,
+    ws::{Connector, Consumer, SlowConsumerPolicy, Subscription, WsManager, WsTraffic},
+    BaseUrl, CandleInterval, Error, LedgerUpdateData, LiquidationEvent, Message, MultiSigSigners,
+    OrderUpdates, Timestamp, TradeInfo, UserFills, UserFillsData,
 };
 
 use ethers::types::H160;
+use futures_util::future::join_all;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use tokio::sync::mpsc::UnboundedSender;
+use std::{collections::HashMap, future::Future, sync::Arc, time::Duration};
+use tokio::{
+    runtime::Handle,
+    sync::{
+        mpsc::{unbounded_channel, Sender, UnboundedSender},
+        Semaphore,
+    },
+};
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct CandleSnapshotRequest {
     coin: String,
-    interval: String,
-    start_time: u64,
-    end_time: u64,
+    interval: CandleInterval,
+    start_time: Timestamp,
+    end_time: Timestamp,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -40,17 +52,43 @@ pub enum InfoRequest {
     OpenOrders {
         user: H160,
     },
+    FrontendOpenOrders {
+        user: H160,
+    },
+    OrderStatus {
+        user: H160,
+        oid: OrderId,
+    },
     Meta,
+    MetaAndAssetCtxs,
     SpotMeta,
     AllMids,
     UserFills {
         user: H160,
     },
     #[serde(rename_all = "camelCase")]
+    UserFillsByTime {
+        user: H160,
+        start_time: Timestamp,
+        end_time: Option<Timestamp>,
+    },
+    #[serde(rename_all = "camelCase")]
+    UserFunding {
+        user: H160,
+        start_time: Timestamp,
+        end_time: Option<Timestamp>,
+    },
+    #[serde(rename_all = "camelCase")]
+    UserNonFundingLedgerUpdates {
+        user: H160,
+        start_time: Timestamp,
+        end_time: Option<Timestamp>,
+    },
+    #[serde(rename_all = "camelCase")]
     FundingHistory {
         coin: String,
-        start_time: u64,
-        end_time: Option<u64>,
+        start_time: Timestamp,
+        end_time: Option<Timestamp>,
     },
     L2Book {
         coin: String,
@@ -58,6 +96,29 @@ pub enum InfoRequest {
     RecentTrades {
         coin: String,
     },
+    UserRole {
+        user: H160,
+    },
+    UserToMultiSigSigners {
+        user: H160,
+    },
+    Referral {
+        user: H160,
+    },
+    ExtraAgents {
+        user: H160,
+    },
+    MaxBuilderFee {
+        user: H160,
+        builder: H160,
+    },
+    PerpDeployAuctionStatus,
+    UserRateLimit {
+        user: H160,
+    },
+    UserFees {
+        user: H160,
+    },
     #[serde(rename_all = "camelCase")]
     CandleSnapshot {
         req: CandleSnapshotRequest,
@@ -67,9 +128,14 @@ pub enum InfoRequest {
 pub struct InfoClient {
     pub http_client: HttpClient,
     pub(crate) ws_manager: Option<WsManager>,
+    pub(crate) cache: Option<InfoCache>,
+    pub(crate) ws_connector: Connector,
+    pub(crate) ws_runtime: Option<Handle>,
 }
 
 impl InfoClient {
+    const BATCH_CONCURRENCY: usize = 8;
+
     pub async fn new(client: Option<Client>, base_url: Option<BaseUrl>) -> Result<InfoClient> {
         let client = client.unwrap_or_default();
         let base_url = base_url.unwrap_or(BaseUrl::Mainnet).get_url();
@@ -77,34 +143,213 @@ impl InfoClient {
         Ok(InfoClient {
             http_client: HttpClient { client, base_url },
             ws_manager: None,
+            cache: None,
+            ws_connector: Connector::default(),
+            ws_runtime: None,
         })
     }
 
+    // Caches meta, spotMeta and userFees responses for `ttl`, so callers can query them every
+    // loop iteration without spending rate limit.
+    pub fn with_cache(mut self, ttl: Duration) -> InfoClient {
+        self.cache = Some(InfoCache::new(ttl));
+        self
+    }
+
+    // Opens and reads the websocket connections on `runtime` instead of the caller's runtime,
+    // e.g. a `PinnedRuntime`'s, so decoding and fan-out stay off the strategy's threads.
+    pub fn with_ws_runtime(mut self, runtime: Handle) -> InfoClient {
+        self.ws_runtime = Some(runtime);
+        self
+    }
+
+    // Offers permessage-deflate when opening websocket connections, which cuts bandwidth for
+    // full-book subscribers on constrained links at the cost of CPU to inflate each message.
+    // Connections stay uncompressed if the exchange declines; compare the wire and payload bytes
+    // in `ws_traffic` to see what it saves.
+    pub fn with_ws_compression(mut self) -> InfoClient {
+        self.ws_connector = self.ws_connector.compressed();
+        self
+    }
+
+    // Forces the next cached queries to hit the API, e.g. after a new asset is listed.
+    pub fn clear_cache(&self) {
+        if let Some(cache) = &self.cache {
+            cache.clear();
+        }
+    }
+
+    // Closes the websocket connections, if any. Subscriptions are dropped; subscribing again
+    // opens new connections.
+    pub async fn close_ws(&mut self) -> Result<()> {
+        match self.ws_manager.take() {
+            Some(ws_manager) => ws_manager.close().await,
+            None => Ok(()),
+        }
+    }
+
+    // Frames, wire bytes and decompressed payload bytes received over the websocket, or None
+    // before the first subscription.
+    pub fn ws_traffic(&self) -> Option<WsTraffic> {
+        self.ws_manager.as_ref().map(WsManager::traffic)
+    }
+
     pub async fn subscribe(
         &mut self,
         subscription: Subscription,
         sender_channel: UnboundedSender<Message>,
+    ) -> Result<u32> {
+        self.add_consumer(subscription, sender_channel.into()).await
+    }
+
+    // Like `subscribe`, but with a bounded queue that can't grow without limit behind a stalled
+    // receiver: once it fills up, the consumer gets a `Message::SlowConsumer` and is handled
+    // according to `policy`.
+    pub async fn subscribe_bounded(
+        &mut self,
+        subscription: Subscription,
+        sender_channel: Sender<Message>,
+        policy: SlowConsumerPolicy,
+    ) -> Result<u32> {
+        self.add_consumer(subscription, Consumer::bounded(sender_channel, policy))
+            .await
+    }
+
+    // Messages waiting in each bounded subscription's queue, by subscription id.
+    pub async fn ws_queue_depths(&self) -> HashMap<u32, usize> {
+        match &self.ws_manager {
+            Some(ws_manager) => ws_manager.queue_depths().await,
+            None => HashMap::new(),
+        }
+    }
+
+    async fn add_consumer(
+        &mut self,
+        subscription: Subscription,
+        consumer: Consumer,
     ) -> Result<u32> {
         if self.ws_manager.is_none() {
-            let ws_manager =
-                WsManager::new(format!("ws{}/ws", &self.http_client.base_url[4..])).await?;
+            let ws_manager = WsManager::new(
+                format!("ws{}/ws", &self.http_client.base_url[4..]),
+                self.ws_connector.clone(),
+                self.ws_runtime.clone(),
+            )
+            .await?;
             self.ws_manager = Some(ws_manager);
         }
 
-        let identifier =
-            serde_json::to_string(&subscription).map_err(|e| Error::JsonParse(e.to_string()))?;
-
         self.ws_manager
             .as_mut()
             .ok_or(Error::WsManagerNotFound)?
-            .add_subscription(identifier, sender_channel)
+            .add_subscription(subscription, consumer)
             .await
     }
 
+    // Like `subscribe`, but for OrderUpdates and UserFills first delivers a snapshot built from
+    // the frontend open orders or recent fills REST query, so consumers start from a consistent state.
+    // The subscription is made before the query, so updates racing the snapshot may be delivered
+    // twice but never missed. The server's own userFills snapshot is dropped in favour of ours.
+    // Other subscriptions behave exactly like `subscribe`.
+    pub async fn subscribe_with_snapshot(
+        &mut self,
+        subscription: Subscription,
+        sender_channel: UnboundedSender<Message>,
+    ) -> Result<u32> {
+        let (sender, mut receiver) = unbounded_channel();
+        let snapshot_user = match subscription {
+            Subscription::OrderUpdates { user } | Subscription::UserFills { user } => user,
+            subscription => return self.subscribe(subscription, sender_channel).await,
+        };
+        let is_order_updates = matches!(subscription, Subscription::OrderUpdates { .. });
+        let subscription_id = self.subscribe(subscription, sender).await?;
+
+        let snapshot = if is_order_updates {
+            self.frontend_open_orders(snapshot_user)
+                .await
+                .map(|orders| {
+                    Message::OrderUpdates(OrderUpdates {
+                        data: Arc::new(orders.into_iter().map(Into::into).collect()),
+                    })
+                })
+        } else {
+            self.user_fills(snapshot_user).await.map(|fills| {
+                Message::UserFills(UserFills {
+                    data: Arc::new(UserFillsData {
+                        is_snapshot: Some(true),
+                        user: snapshot_user,
+                        fills: fills.into_iter().map(TradeInfo::from).collect(),
+                    }),
+                })
+            })
+        };
+        let snapshot = match snapshot {
+            Ok(snapshot) => snapshot,
+            Err(err) => {
+                self.unsubscribe(subscription_id).await?;
+                return Err(err);
+            }
+        };
+        sender_channel
+            .send(snapshot)
+            .map_err(|e| Error::WsSend(e.to_string()))?;
+
+        self.spawn_forwarder(async move {
+            while let Some(message) = receiver.recv().await {
+                if let Message::UserFills(user_fills) = &message {
+                    if user_fills.data.is_snapshot == Some(true) {
+                        continue;
+                    }
+                }
+                if sender_channel.send(message).is_err() {
+                    return;
+                }
+            }
+        });
+        Ok(subscription_id)
+    }
+
+    // Subscribes to userEvents for `user` and forwards only its liquidations, as typed events.
+    // The returned id is unsubscribed like any other subscription.
+    pub async fn subscribe_liquidations(
+        &mut self,
+        user: H160,
+        sender_channel: UnboundedSender<LiquidationEvent>,
+    ) -> Result<u32> {
+        let (sender, mut receiver) = unbounded_channel();
+        let subscription_id = self
+            .subscribe(Subscription::UserEvents { user }, sender)
+            .await?;
+        self.spawn_forwarder(async move {
+            while let Some(message) = receiver.recv().await {
+                let Message::User(user_event) = message else {
+                    continue;
+                };
+                for event in user_event.data.liquidation_events() {
+                    if sender_channel.send(event).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+        Ok(subscription_id)
+    }
+
+    // Spawns a task filtering one subscription's messages, next to the connection readers.
+    fn spawn_forwarder(&self, forwarder: impl Future<Output = ()> + Send + 'static) {
+        match &self.ws_runtime {
+            Some(runtime) => runtime.spawn(forwarder),
+            None => tokio::spawn(forwarder),
+        };
+    }
+
     pub async fn unsubscribe(&mut self, subscription_id: u32) -> Result<()> {
         if self.ws_manager.is_none() {
-            let ws_manager =
-                WsManager::new(format!("ws{}/ws", &self.http_client.base_url[4..])).await?;
+            let ws_manager = WsManager::new(
+                format!("ws{}/ws", &self.http_client.base_url[4..]),
+                self.ws_connector.clone(),
+                self.ws_runtime.clone(),
+            )
+            .await?;
             self.ws_manager = Some(ws_manager);
         }
 
@@ -115,6 +360,32 @@ impl InfoClient {
             .await
     }
 
+    // Runs the requests concurrently, at most `BATCH_CONCURRENCY` at a time, and returns the
+    // raw responses in request order. A failed request doesn't fail the others.
+    pub async fn batch(&self, requests: Vec<InfoRequest>) -> Vec<Result<serde_json::Value>> {
+        self.batch_with_concurrency(requests, Self::BATCH_CONCURRENCY)
+            .await
+    }
+
+    pub async fn batch_with_concurrency(
+        &self,
+        requests: Vec<InfoRequest>,
+        max_concurrency: usize,
+    ) -> Vec<Result<serde_json::Value>> {
+        let semaphore = &Semaphore::new(max_concurrency.max(1));
+        join_all(requests.into_iter().map(|request| async move {
+            let _permit = semaphore
+                .acquire()
+                .await
+                .map_err(|e| Error::GenericRequest(e.to_string()))?;
+            let data =
+                serde_json::to_string(&request).map_err(|e| Error::JsonParse(e.to_string()))?;
+            let return_data = self.http_client.post("/info", data).await?;
+            serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))
+        }))
+        .await
+    }
+
     pub async fn open_orders(&self, address: H160) -> Result<Vec<OpenOrdersResponse>> {
         let input = InfoRequest::OpenOrders { user: address };
         let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
@@ -123,12 +394,35 @@ impl InfoClient {
         serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))
     }
 
+    pub async fn frontend_open_orders(
+        &self,
+        address: H160,
+    ) -> Result<Vec<FrontendOpenOrdersResponse>> {
+        let input = InfoRequest::FrontendOpenOrders { user: address };
+        let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
+
+        let return_data = self.http_client.post("/info", data).await?;
+        serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))
+    }
+
+    // Looks up an order by oid or cloid, including orders that are no longer open.
+    pub async fn order_status(&self, address: H160, oid: OrderId) -> Result<OrderStatusResponse> {
+        let input = InfoRequest::OrderStatus { user: address, oid };
+        let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
+
+        let return_data = self.http_client.post("/info", data).await?;
+        serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))
+    }
+
     pub async fn user_state(&self, address: H160) -> Result<UserStateResponse> {
         let input = InfoRequest::UserState { user: address };
         let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
 
         let return_data = self.http_client.post("/info", data).await?;
-        serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))
+        let user_state =
+            serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))?;
+        unknown_fields::report_user_state(&user_state);
+        Ok(user_state)
     }
 
     pub async fn user_states(&self, addresses: Vec<H160>) -> Result<Vec<UserStateResponse>> {
@@ -136,23 +430,84 @@ impl InfoClient {
         let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
 
         let return_data = self.http_client.post("/info", data).await?;
-        serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))
+        let user_states: Vec<UserStateResponse> =
+            serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))?;
+        user_states
+            .iter()
+            .for_each(unknown_fields::report_user_state);
+        Ok(user_states)
     }
 
     pub async fn meta(&self) -> Result<Meta> {
+        if let Some(meta) = self.cache.as_ref().and_then(|cache| cache.meta.get(&())) {
+            return Ok(meta);
+        }
         let input = InfoRequest::Meta;
         let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
 
         let return_data = self.http_client.post("/info", data).await?;
-        serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))
+        let meta: Meta =
+            serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))?;
+        unknown_fields::report_meta(&meta);
+        if let Some(cache) = &self.cache {
+            cache.meta.insert((), meta.clone());
+        }
+        Ok(meta)
+    }
+
+    // Contexts are in the same order as `Meta::universe`. Never cached.
+    pub async fn meta_and_asset_ctxs(&self) -> Result<(Meta, Vec<PerpAssetCtx>)> {
+        let input = InfoRequest::MetaAndAssetCtxs;
+        let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
+
+        let return_data = self.http_client.post("/info", data).await?;
+        let (meta, asset_ctxs): (Meta, Vec<PerpAssetCtx>) =
+            serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))?;
+        unknown_fields::report_meta(&meta);
+        asset_ctxs
+            .iter()
+            .for_each(|ctx| unknown_fields::report("PerpAssetCtx", &ctx.extra));
+        Ok((meta, asset_ctxs))
     }
 
     pub async fn spot_meta(&self) -> Result<SpotMeta> {
+        if let Some(spot_meta) = self
+            .cache
+            .as_ref()
+            .and_then(|cache| cache.spot_meta.get(&()))
+        {
+            return Ok(spot_meta);
+        }
         let input = InfoRequest::SpotMeta;
         let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
 
         let return_data = self.http_client.post("/info", data).await?;
-        serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))
+        let spot_meta: SpotMeta =
+            serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))?;
+        if let Some(cache) = &self.cache {
+            cache.spot_meta.insert((), spot_meta.clone());
+        }
+        Ok(spot_meta)
+    }
+
+    pub async fn user_fees(&self, address: H160) -> Result<UserFeesResponse> {
+        if let Some(user_fees) = self
+            .cache
+            .as_ref()
+            .and_then(|cache| cache.user_fees.get(&address))
+        {
+            return Ok(user_fees);
+        }
+        let input = InfoRequest::UserFees { user: address };
+        let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
+
+        let return_data = self.http_client.post("/info", data).await?;
+        let user_fees: UserFeesResponse =
+            serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))?;
+        if let Some(cache) = &self.cache {
+            cache.user_fees.insert(address, user_fees.clone());
+        }
+        Ok(user_fees)
     }
 
     pub async fn all_mids(&self) -> Result<HashMap<String, String>> {
@@ -167,6 +522,71 @@ impl InfoClient {
         let input = InfoRequest::UserFills { user: address };
         let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
 
+        let return_data = self.http_client.post("/info", data).await?;
+        let fills: Vec<UserFillsResponse> =
+            serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))?;
+        fills
+            .iter()
+            .for_each(|fill| unknown_fields::report("UserFillsResponse", &fill.extra));
+        Ok(fills)
+    }
+
+    pub async fn user_fills_by_time(
+        &self,
+        address: H160,
+        start_time: Timestamp,
+        end_time: Option<Timestamp>,
+    ) -> Result<Vec<UserFillsResponse>> {
+        let input = InfoRequest::UserFillsByTime {
+            user: address,
+            start_time,
+            end_time,
+        };
+        let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
+
+        let return_data = self.http_client.post("/info", data).await?;
+        let fills: Vec<UserFillsResponse> =
+            serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))?;
+        fills
+            .iter()
+            .for_each(|fill| unknown_fields::report("UserFillsResponse", &fill.extra));
+        Ok(fills)
+    }
+
+    // Funding payments to and from `address`, oldest first. Responses are capped at 500
+    // payments, so longer ranges need paging.
+    pub async fn user_funding(
+        &self,
+        address: H160,
+        start_time: Timestamp,
+        end_time: Option<Timestamp>,
+    ) -> Result<Vec<UserFundingResponse>> {
+        let input = InfoRequest::UserFunding {
+            user: address,
+            start_time,
+            end_time,
+        };
+        let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
+
+        let return_data = self.http_client.post("/info", data).await?;
+        serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))
+    }
+
+    // Deposits, withdrawals, transfers and other ledger updates of `address` besides funding,
+    // oldest first.
+    pub async fn user_non_funding_ledger_updates(
+        &self,
+        address: H160,
+        start_time: Timestamp,
+        end_time: Option<Timestamp>,
+    ) -> Result<Vec<LedgerUpdateData>> {
+        let input = InfoRequest::UserNonFundingLedgerUpdates {
+            user: address,
+            start_time,
+            end_time,
+        };
+        let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
+
         let return_data = self.http_client.post("/info", data).await?;
         serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))
     }
@@ -174,8 +594,8 @@ impl InfoClient {
     pub async fn funding_history(
         &self,
         coin: String,
-        start_time: u64,
-        end_time: Option<u64>,
+        start_time: Timestamp,
+        end_time: Option<Timestamp>,
     ) -> Result<Vec<FundingHistoryResponse>> {
         let input = InfoRequest::FundingHistory {
             coin,
@@ -204,12 +624,77 @@ impl InfoClient {
         serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))
     }
 
+    pub async fn user_role(&self, address: H160) -> Result<UserRoleResponse> {
+        let input = InfoRequest::UserRole { user: address };
+        let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
+
+        let return_data = self.http_client.post("/info", data).await?;
+        serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))
+    }
+
+    // None unless `address` is a multi-sig user.
+    pub async fn multi_sig_signers(&self, address: H160) -> Result<Option<MultiSigSigners>> {
+        let input = InfoRequest::UserToMultiSigSigners { user: address };
+        let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
+
+        let return_data = self.http_client.post("/info", data).await?;
+        serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))
+    }
+
+    pub async fn referral(&self, address: H160) -> Result<ReferralResponse> {
+        let input = InfoRequest::Referral { user: address };
+        let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
+
+        let return_data = self.http_client.post("/info", data).await?;
+        serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))
+    }
+
+    // Agents approved with a name; the unnamed agent isn't listed.
+    pub async fn extra_agents(&self, address: H160) -> Result<Vec<ExtraAgentResponse>> {
+        let input = InfoRequest::ExtraAgents { user: address };
+        let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
+
+        let return_data = self.http_client.post("/info", data).await?;
+        serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))
+    }
+
+    // The maximum fee, in tenths of a basis point, `address` has approved for `builder`. Zero
+    // means orders carrying that builder code will be rejected.
+    pub async fn max_builder_fee(&self, address: H160, builder: H160) -> Result<u64> {
+        let input = InfoRequest::MaxBuilderFee {
+            user: address,
+            builder,
+        };
+        let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
+
+        let return_data = self.http_client.post("/info", data).await?;
+        serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))
+    }
+
+    // State of the gas auction for deploying a builder-deployed perp dex.
+    pub async fn perp_deploy_auction_status(&self) -> Result<PerpDeployAuctionStatus> {
+        let input = InfoRequest::PerpDeployAuctionStatus;
+        let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
+
+        let return_data = self.http_client.post("/info", data).await?;
+        serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))
+    }
+
+    // Requests used against the address-based rate limit, which grows with traded volume.
+    pub async fn user_rate_limit(&self, address: H160) -> Result<UserRateLimitResponse> {
+        let input = InfoRequest::UserRateLimit { user: address };
+        let data = serde_json::to_string(&input).map_err(|e| Error::JsonParse(e.to_string()))?;
+
+        let return_data = self.http_client.post("/info", data).await?;
+        serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))
+    }
+
     pub async fn candles_snapshot(
         &self,
         coin: String,
-        interval: String,
-        start_time: u64,
-        end_time: u64,
+        interval: CandleInterval,
+        start_time: Timestamp,
+        end_time: Timestamp,
     ) -> Result<Vec<CandlesSnapshotResponse>> {
         let input = InfoRequest::CandleSnapshot {
             req: CandleSnapshotRequest {
@@ -225,3 +710,59 @@ impl InfoClient {
         serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{sim::serve_rest, SimExchange};
+    use serde_json::{json, Value};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn test_batch_with_concurrency() -> Result<()> {
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let base_url = serve_rest({
+            let (in_flight, peak) = (in_flight.clone(), peak.clone());
+            move |request: Value| {
+                let (in_flight, peak) = (in_flight.clone(), peak.clone());
+                async move {
+                    let running = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(running, Ordering::SeqCst);
+                    // Later requests finish first, so order can't come from completion
+                    let index: u64 = request["coin"].as_str().unwrap().parse().unwrap();
+                    tokio::time::sleep(Duration::from_millis(50 - 4 * index)).await;
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                    if index == 3 {
+                        (500, json!("internal error"))
+                    } else {
+                        (200, json!({"coin": index}))
+                    }
+                }
+            }
+        })
+        .await?
+        .base_url;
+        let mut info_client = SimExchange::new().info_client();
+        info_client.http_client.base_url = base_url;
+
+        let requests = (0..10)
+            .map(|index| InfoRequest::L2Book {
+                coin: index.to_string(),
+            })
+            .collect();
+        let results = info_client.batch_with_concurrency(requests, 3).await;
+        assert_eq!(results.len(), 10);
+        for (index, result) in results.into_iter().enumerate() {
+            match result {
+                Err(Error::ServerRequest { status_code, .. }) if index == 3 => {
+                    assert_eq!(status_code, 500)
+                }
+                Ok(response) if index != 3 => assert_eq!(response, json!({"coin": index})),
+                result => panic!("unexpected result for request {index}: {result:?}"),
+            }
+        }
+        assert_eq!(peak.load(Ordering::SeqCst), 3);
+        Ok(())
+    }
+}
@@ -1,90 +1,398 @@
-use crate::info::{AssetPosition, Level, MarginSummary};
-use serde::Deserialize;
+use crate::{
+    info::{AssetPosition, DailyUserVlm, Level, MarginSummary, ReferredBy, ReferrerState},
+    serde_helpers::{integer_or_string, number_or_string, option_number_or_string},
+    BasicOrder, CandleInterval, ExtraFields, FillLiquidation, OrderUpdate, Timestamp, TradeInfo,
+};
+use ethers::types::H160;
+use serde::{Deserialize, Serialize};
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct UserStateResponse {
     pub asset_positions: Vec<AssetPosition>,
     pub cross_margin_summary: MarginSummary,
     pub margin_summary: MarginSummary,
+    #[serde(deserialize_with = "number_or_string")]
     pub withdrawable: String,
+    // Fields the SDK doesn't model yet
+    #[serde(flatten, skip_serializing)]
+    pub extra: ExtraFields,
 }
 
-#[derive(serde::Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct OpenOrdersResponse {
     pub coin: String,
+    #[serde(deserialize_with = "number_or_string")]
     pub limit_px: String,
     pub oid: u64,
     pub side: String,
+    #[serde(deserialize_with = "number_or_string")]
     pub sz: String,
-    pub timestamp: u64,
+    pub timestamp: Timestamp,
 }
 
-#[derive(serde::Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FrontendOpenOrdersResponse {
+    pub coin: String,
+    pub side: String,
+    #[serde(deserialize_with = "number_or_string")]
+    pub limit_px: String,
+    #[serde(deserialize_with = "number_or_string")]
+    pub sz: String,
+    #[serde(deserialize_with = "number_or_string")]
+    pub orig_sz: String,
+    pub oid: u64,
+    pub timestamp: Timestamp,
+    pub order_type: String,
+    pub tif: Option<String>,
+    pub reduce_only: bool,
+    pub is_trigger: bool,
+    #[serde(deserialize_with = "number_or_string")]
+    pub trigger_px: String,
+    pub trigger_condition: String,
+    pub is_position_tpsl: bool,
+    pub cloid: Option<String>,
+    // TP/SL orders attached to this order, only triggered once it fills
+    #[serde(default)]
+    pub children: Vec<FrontendOpenOrdersResponse>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum OrderStatusResponse {
+    Order { order: Box<OrderStatusData> },
+    UnknownOid,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderStatusData {
+    pub order: FrontendOpenOrdersResponse,
+    pub status: String,
+    pub status_timestamp: Timestamp,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct UserFillsResponse {
+    #[serde(deserialize_with = "number_or_string")]
     pub closed_pnl: String,
     pub coin: String,
     pub crossed: bool,
     pub dir: String,
     pub hash: String,
     pub oid: u64,
+    #[serde(deserialize_with = "number_or_string")]
     pub px: String,
     pub side: String,
+    #[serde(deserialize_with = "number_or_string")]
     pub start_position: String,
+    #[serde(deserialize_with = "number_or_string")]
     pub sz: String,
-    pub time: u64,
+    pub time: Timestamp,
+    #[serde(deserialize_with = "number_or_string")]
+    pub fee: String,
+    pub tid: u64,
+    pub cloid: Option<String>,
+    pub fee_token: Option<String>,
+    #[serde(default, deserialize_with = "option_number_or_string")]
+    pub builder_fee: Option<String>,
+    pub liquidation: Option<FillLiquidation>,
+    // Fields the SDK doesn't model yet
+    #[serde(flatten, skip_serializing)]
+    pub extra: ExtraFields,
 }
 
-#[derive(serde::Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "role", content = "data", rename_all = "camelCase")]
+pub enum UserRoleResponse {
+    Missing,
+    User,
+    Agent { user: H160 },
+    Vault,
+    SubAccount { master: H160 },
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ReferralResponse {
+    pub referred_by: Option<ReferredBy>,
+    pub cum_vlm: String,
+    pub unclaimed_rewards: String,
+    pub claimed_rewards: String,
+    pub builder_rewards: Option<String>,
+    pub referrer_state: ReferrerState,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtraAgentResponse {
+    pub name: String,
+    pub address: H160,
+    pub valid_until: u64,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PerpDeployAuctionStatus {
+    pub start_time_seconds: u64,
+    pub duration_seconds: u64,
+    pub start_gas: String,
+    pub current_gas: Option<String>,
+    pub end_gas: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UserRateLimitResponse {
+    pub cum_vlm: String,
+    #[serde(deserialize_with = "integer_or_string")]
+    pub n_requests_used: u64,
+    #[serde(deserialize_with = "integer_or_string")]
+    pub n_requests_cap: u64,
+}
+
+// Rates are fractions (0.00035 is 3.5 bps) after volume tier, staking and referral discounts.
+// The full tier table is kept as raw JSON in `fee_schedule`.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UserFeesResponse {
+    pub daily_user_vlm: Vec<DailyUserVlm>,
+    pub fee_schedule: serde_json::Value,
+    pub user_cross_rate: String,
+    pub user_add_rate: String,
+    pub user_spot_cross_rate: Option<String>,
+    pub user_spot_add_rate: Option<String>,
+    pub active_referral_discount: String,
+}
+
+impl From<FrontendOpenOrdersResponse> for OrderUpdate {
+    fn from(order: FrontendOpenOrdersResponse) -> Self {
+        OrderUpdate {
+            order: BasicOrder {
+                coin: order.coin,
+                side: order.side,
+                limit_px: order.limit_px,
+                sz: order.sz,
+                oid: order.oid,
+                timestamp: order.timestamp,
+                orig_sz: order.orig_sz,
+                cloid: order.cloid,
+                extra: ExtraFields::new(),
+            },
+            status: "open".to_string(),
+            status_timestamp: order.timestamp,
+        }
+    }
+}
+
+impl From<UserFillsResponse> for TradeInfo {
+    fn from(fill: UserFillsResponse) -> Self {
+        TradeInfo {
+            coin: fill.coin,
+            side: fill.side,
+            px: fill.px,
+            sz: fill.sz,
+            time: fill.time,
+            hash: fill.hash,
+            start_position: fill.start_position,
+            dir: fill.dir,
+            closed_pnl: fill.closed_pnl,
+            oid: fill.oid,
+            cloid: fill.cloid,
+            crossed: fill.crossed,
+            fee: fill.fee,
+            tid: fill.tid,
+            fee_token: fill.fee_token,
+            builder_fee: fill.builder_fee,
+            liquidation: fill.liquidation,
+            extra: fill.extra,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct FundingHistoryResponse {
     pub coin: String,
+    #[serde(deserialize_with = "number_or_string")]
     pub funding_rate: String,
+    #[serde(deserialize_with = "number_or_string")]
     pub premium: String,
-    pub time: u64,
+    pub time: Timestamp,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct UserFundingResponse {
+    pub time: Timestamp,
+    pub hash: String,
+    pub delta: UserFundingDelta,
 }
 
-#[derive(serde::Deserialize, Debug)]
+// `usdc` is signed from the user's side: negative when the user paid funding.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UserFundingDelta {
+    pub coin: String,
+    #[serde(deserialize_with = "number_or_string")]
+    pub usdc: String,
+    #[serde(deserialize_with = "number_or_string")]
+    pub szi: String,
+    #[serde(deserialize_with = "number_or_string")]
+    pub funding_rate: String,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct L2SnapshotResponse {
     pub coin: String,
     pub levels: Vec<Vec<Level>>,
-    pub time: u64,
+    pub time: Timestamp,
 }
 
-#[derive(serde::Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct RecentTradesResponse {
     pub coin: String,
     pub side: String,
+    #[serde(deserialize_with = "number_or_string")]
     pub px: String,
+    #[serde(deserialize_with = "number_or_string")]
     pub sz: String,
-    pub time: u64,
+    pub time: Timestamp,
     pub hash: String,
 }
 
-#[derive(serde::Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 pub struct CandlesSnapshotResponse {
     #[serde(rename = "t")]
-    pub time_open: u64,
+    pub time_open: Timestamp,
     #[serde(rename = "T")]
-    pub time_close: u64,
+    pub time_close: Timestamp,
     #[serde(rename = "s")]
     pub coin: String,
     #[serde(rename = "i")]
-    pub candle_interval: String,
+    pub candle_interval: CandleInterval,
     #[serde(rename = "o")]
+    #[serde(deserialize_with = "number_or_string")]
     pub open: String,
     #[serde(rename = "c")]
+    #[serde(deserialize_with = "number_or_string")]
     pub close: String,
     #[serde(rename = "h")]
+    #[serde(deserialize_with = "number_or_string")]
     pub high: String,
     #[serde(rename = "l")]
+    #[serde(deserialize_with = "number_or_string")]
     pub low: String,
     #[serde(rename = "v")]
+    #[serde(deserialize_with = "number_or_string")]
     pub vlm: String,
     #[serde(rename = "n")]
+    #[serde(deserialize_with = "integer_or_string")]
     pub num_trades: u64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::info::OrderId;
+
+    #[test]
+    fn test_frontend_open_orders() {
+        let orders: Vec<FrontendOpenOrdersResponse> = serde_json::from_str(
+            r#"[{"coin":"ETH","side":"A","limitPx":"3500.0","sz":"0.5","origSz":"1.0","oid":91,
+            "timestamp":1718000000000,"orderType":"Limit","tif":"Alo","reduceOnly":false,
+            "isTrigger":false,"triggerPx":"0.0","triggerCondition":"N/A","isPositionTpsl":false,
+            "cloid":"0x00000000000000000000000000000001","children":[{"coin":"ETH","side":"B",
+            "limitPx":"3300.0","sz":"1.0","origSz":"1.0","oid":92,"timestamp":1718000000000,
+            "orderType":"Stop Market","tif":null,"reduceOnly":true,"isTrigger":true,
+            "triggerPx":"3400.0","triggerCondition":"Price above 3400","isPositionTpsl":false,
+            "cloid":null,"children":[]}]}]"#,
+        )
+        .unwrap();
+        assert_eq!(orders[0].tif.as_deref(), Some("Alo"));
+        let child = &orders[0].children[0];
+        assert!(child.is_trigger && child.reduce_only);
+        assert_eq!(child.tif, None);
+
+        let update = OrderUpdate::from(orders[0].clone());
+        assert_eq!(update.order.orig_sz, "1.0");
+        assert_eq!(update.order.sz, "0.5");
+        assert!(update.order.cloid.is_some());
+    }
+
+    #[test]
+    fn test_user_role() {
+        let role: UserRoleResponse = serde_json::from_str(
+            r#"{"role":"agent","data":{"user":"0xc64cc00b46101bd40aa1c3121195e85c0b0918d8"}}"#,
+        )
+        .unwrap();
+        assert!(matches!(role, UserRoleResponse::Agent { .. }));
+        let role: UserRoleResponse = serde_json::from_str(r#"{"role":"missing"}"#).unwrap();
+        assert_eq!(role, UserRoleResponse::Missing);
+    }
+
+    #[test]
+    fn test_referral() {
+        let referral: ReferralResponse = serde_json::from_str(
+            r#"{"referredBy":{"referrer":"0x5ac99df645f3414876c816caa18b2d234024b487",
+            "code":"TESTNET"},"cumVlm":"149428030.6628420055","unclaimedRewards":"11.047162",
+            "claimedRewards":"22.141262","builderRewards":"0.0","referrerState":{"stage":"ready",
+            "data":{"code":"TEST","referralStates":[{"cumVlm":"960652.017122","cumRewardedFeesSinceReferred":"196.224188",
+            "cumFeesRewardedToReferrer":"19.622418","timeJoined":1679425029416,
+            "user":"0x11af2b93dcb3568b7bf2b15bd19ba1ac88b6dd8f"}]}},"rewardHistory":[]}"#,
+        )
+        .unwrap();
+        assert_eq!(referral.referred_by.unwrap().code, "TESTNET");
+        let ReferrerState::Ready {
+            referral_states, ..
+        } = referral.referrer_state
+        else {
+            panic!("expected ready referrer state");
+        };
+        assert_eq!(referral_states.len(), 1);
+
+        let referral: ReferralResponse = serde_json::from_str(
+            r#"{"referredBy":null,"cumVlm":"0.0","unclaimedRewards":"0.0","claimedRewards":"0.0",
+            "builderRewards":"0.0","referrerState":{"stage":"needToTrade","data":{"required":"10000.0"}},
+            "rewardHistory":[]}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            referral.referrer_state,
+            ReferrerState::NeedToTrade { .. }
+        ));
+    }
+
+    #[test]
+    fn test_order_status() {
+        let status: OrderStatusResponse = serde_json::from_str(
+            r#"{"status":"order","order":{"order":{"coin":"ETH","side":"B","limitPx":"3000.0",
+            "sz":"0.0","origSz":"0.1","oid":7,"timestamp":1718000000000,"orderType":"Limit",
+            "tif":"Gtc","reduceOnly":false,"isTrigger":false,"triggerPx":"0.0",
+            "triggerCondition":"N/A","isPositionTpsl":false,
+            "cloid":"0x00000000000000000000000000000001","children":[]},"status":"filled",
+            "statusTimestamp":1718000000100}}"#,
+        )
+        .unwrap();
+        let OrderStatusResponse::Order { order } = status else {
+            panic!("expected an order");
+        };
+        assert_eq!(order.status, "filled");
+        assert_eq!(order.order.oid, 7);
+        assert!(matches!(
+            serde_json::from_str(r#"{"status":"unknownOid"}"#).unwrap(),
+            OrderStatusResponse::UnknownOid
+        ));
+
+        let cloid = uuid::Uuid::from_u128(1);
+        assert_eq!(
+            serde_json::to_string(&OrderId::Cloid(cloid)).unwrap(),
+            r#""0x00000000000000000000000000000001""#
+        );
+        assert_eq!(serde_json::to_string(&OrderId::Oid(7)).unwrap(), "7");
+    }
+}
@@ -1,48 +1,148 @@
-use serde::Deserialize;
+use crate::{
+    helpers::uuid_to_hex_string,
+    serde_helpers::{integer_or_string, number_or_string, option_number_or_string},
+    ExtraFields, Timestamp,
+};
+use ethers::types::H160;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use uuid::Uuid;
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct Leverage {
     #[serde(rename = "type")]
     pub type_string: String,
+    #[serde(deserialize_with = "integer_or_string")]
     pub value: u32,
+    #[serde(default, deserialize_with = "option_number_or_string")]
     pub raw_usd: Option<String>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct PositionData {
     pub coin: String,
+    #[serde(default, deserialize_with = "option_number_or_string")]
     pub entry_px: Option<String>,
     pub leverage: Leverage,
+    #[serde(default, deserialize_with = "option_number_or_string")]
     pub liquidation_px: Option<String>,
+    #[serde(deserialize_with = "number_or_string")]
     pub margin_used: String,
+    #[serde(deserialize_with = "number_or_string")]
     pub position_value: String,
+    #[serde(deserialize_with = "number_or_string")]
     pub return_on_equity: String,
+    #[serde(deserialize_with = "number_or_string")]
     pub szi: String,
+    #[serde(deserialize_with = "number_or_string")]
     pub unrealized_pnl: String,
+    // Fields the SDK doesn't model yet
+    #[serde(flatten, skip_serializing)]
+    pub extra: ExtraFields,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 pub struct AssetPosition {
     pub position: PositionData,
     #[serde(rename = "type")]
     pub type_string: String,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct MarginSummary {
+    #[serde(deserialize_with = "number_or_string")]
     pub account_value: String,
+    #[serde(deserialize_with = "number_or_string")]
     pub total_margin_used: String,
+    #[serde(deserialize_with = "number_or_string")]
     pub total_ntl_pos: String,
+    #[serde(deserialize_with = "number_or_string")]
     pub total_raw_usd: String,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct Level {
+    #[serde(deserialize_with = "integer_or_string")]
     pub n: u64,
+    #[serde(deserialize_with = "number_or_string")]
     pub px: String,
+    #[serde(deserialize_with = "number_or_string")]
     pub sz: String,
 }
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct ReferredBy {
+    pub referrer: H160,
+    pub code: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ReferralUserState {
+    pub user: H160,
+    pub cum_vlm: String,
+    pub cum_rewarded_fees_since_referred: String,
+    pub cum_fees_rewarded_to_referrer: String,
+    pub time_joined: Timestamp,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(tag = "stage", content = "data", rename_all = "camelCase")]
+pub enum ReferrerState {
+    #[serde(rename_all = "camelCase")]
+    Ready {
+        code: String,
+        referral_states: Vec<ReferralUserState>,
+    },
+    NeedToCreateCode,
+    // Volume still required before a referral code can be created
+    NeedToTrade {
+        required: String,
+    },
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DailyUserVlm {
+    pub date: String,
+    pub user_cross: String,
+    pub user_add: String,
+    pub exchange: String,
+}
+
+// An order reference for queries: the exchange's oid, or the client order id it was placed
+// with, sent as a 16 byte hex string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderId {
+    Oid(u64),
+    Cloid(Uuid),
+}
+
+impl Serialize for OrderId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        match self {
+            OrderId::Oid(oid) => serializer.serialize_u64(*oid),
+            OrderId::Cloid(cloid) => serializer.serialize_str(&uuid_to_hex_string(*cloid)),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for OrderId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Oid(u64),
+            Cloid(String),
+        }
+        match Raw::deserialize(deserializer)? {
+            Raw::Oid(oid) => Ok(OrderId::Oid(oid)),
+            Raw::Cloid(cloid) => u128::from_str_radix(cloid.trim_start_matches("0x"), 16)
+                .map(|cloid| OrderId::Cloid(Uuid::from_u128(cloid)))
+                .map_err(de::Error::custom),
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use crate::{Message, Timestamp};
+use std::{
+    collections::VecDeque,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+// A rolling window of latency samples.
+#[derive(Debug, Clone)]
+pub struct LatencyStats {
+    pub capacity: usize,
+    samples: VecDeque<Duration>,
+    total_count: u64,
+}
+
+impl LatencyStats {
+    pub fn new(capacity: usize) -> LatencyStats {
+        LatencyStats {
+            capacity,
+            samples: VecDeque::with_capacity(capacity),
+            total_count: 0,
+        }
+    }
+
+    pub fn record(&mut self, latency: Duration) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(latency);
+        self.total_count += 1;
+    }
+
+    // Samples recorded since creation, including ones that fell out of the window.
+    pub fn total_count(&self) -> u64 {
+        self.total_count
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    // Nearest-rank percentile over the window, `percentile` in [0, 100].
+    pub fn percentile(&self, percentile: f64) -> Option<Duration> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        let mut sorted: Vec<Duration> = self.samples.iter().copied().collect();
+        sorted.sort_unstable();
+        let rank = (percentile.clamp(0.0, 100.0) / 100.0 * sorted.len() as f64).ceil() as usize;
+        Some(sorted[rank.saturating_sub(1)])
+    }
+
+    pub fn p50(&self) -> Option<Duration> {
+        self.percentile(50.0)
+    }
+
+    pub fn p90(&self) -> Option<Duration> {
+        self.percentile(90.0)
+    }
+
+    pub fn p99(&self) -> Option<Duration> {
+        self.percentile(99.0)
+    }
+
+    pub fn max(&self) -> Option<Duration> {
+        self.samples.iter().max().copied()
+    }
+
+    pub fn mean(&self) -> Option<Duration> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        Some(self.samples.iter().sum::<Duration>() / self.samples.len() as u32)
+    }
+}
+
+// Latencies collected by an ExchangeClient: time spent signing actions, and from sending a
+// request to receiving the exchange's response. Websocket consumers record how long after
+// the exchange's event time they processed a message with `record_event`.
+#[derive(Debug)]
+pub struct LatencyRecorder {
+    sign: Mutex<LatencyStats>,
+    send_to_ack: Mutex<LatencyStats>,
+    event_to_process: Mutex<LatencyStats>,
+}
+
+impl Default for LatencyRecorder {
+    fn default() -> Self {
+        LatencyRecorder::new(LatencyRecorder::DEFAULT_CAPACITY)
+    }
+}
+
+impl LatencyRecorder {
+    const DEFAULT_CAPACITY: usize = 10_000;
+
+    pub fn new(capacity: usize) -> LatencyRecorder {
+        LatencyRecorder {
+            sign: Mutex::new(LatencyStats::new(capacity)),
+            send_to_ack: Mutex::new(LatencyStats::new(capacity)),
+            event_to_process: Mutex::new(LatencyStats::new(capacity)),
+        }
+    }
+
+    pub fn sign(&self) -> LatencyStats {
+        self.sign.lock().unwrap().clone()
+    }
+
+    pub fn send_to_ack(&self) -> LatencyStats {
+        self.send_to_ack.lock().unwrap().clone()
+    }
+
+    pub fn event_to_process(&self) -> LatencyStats {
+        self.event_to_process.lock().unwrap().clone()
+    }
+
+    pub(crate) fn time_sign<T>(&self, sign: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let signature = sign();
+        self.sign.lock().unwrap().record(start.elapsed());
+        signature
+    }
+
+    pub(crate) fn record_send_to_ack(&self, latency: Duration) {
+        self.send_to_ack.lock().unwrap().record(latency);
+    }
+
+    // Records the delay between the message's exchange timestamp and now. Messages without a
+    // timestamp are ignored. Includes any clock offset between this host and the exchange.
+    pub fn record_event(&self, message: &Message) {
+        if let Some(event_time) = message.event_time() {
+            self.event_to_process
+                .lock()
+                .unwrap()
+                .record(Timestamp::now() - event_time);
+        }
+    }
+}
+
+impl Message {
+    // The exchange timestamp of the latest event in the message, if it carries one.
+    pub fn event_time(&self) -> Option<Timestamp> {
+        match self {
+            Message::Trades(trades) => trades.data.iter().map(|trade| trade.time).max(),
+            Message::L2Book(l2_book) => Some(l2_book.data.time),
+            Message::UserFills(user_fills) => {
+                user_fills.data.fills.iter().map(|fill| fill.time).max()
+            }
+            Message::OrderUpdates(order_updates) => order_updates
+                .data
+                .iter()
+                .map(|update| update.status_timestamp)
+                .max(),
+            Message::UserFundings(user_fundings) => user_fundings
+                .data
+                .fundings
+                .iter()
+                .map(|funding| funding.time)
+                .max(),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentiles() {
+        let mut stats = LatencyStats::new(100);
+        assert_eq!(stats.p50(), None);
+        for ms in 1..=200 {
+            stats.record(Duration::from_millis(ms));
+        }
+        // Only the last 100 samples (101..=200ms) are kept
+        assert_eq!(stats.len(), 100);
+        assert_eq!(stats.total_count(), 200);
+        assert_eq!(stats.p50(), Some(Duration::from_millis(150)));
+        assert_eq!(stats.p99(), Some(Duration::from_millis(199)));
+        assert_eq!(stats.percentile(0.0), Some(Duration::from_millis(101)));
+        assert_eq!(stats.max(), Some(Duration::from_millis(200)));
+        assert_eq!(stats.mean(), Some(Duration::from_micros(150_500)));
+    }
+}
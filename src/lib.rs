@@ -1,21 +1,187 @@
 #![deny(unreachable_pub)]
+mod accounting;
+mod agent_rotation;
+mod basis_monitor;
+#[cfg(feature = "bench")]
+mod bench;
+mod book_check;
+mod book_delta;
+mod bridge;
+mod candle;
+pub mod compat;
+#[cfg(feature = "config")]
+mod config;
 mod consts;
+mod dca;
+mod depth;
+mod drop_copy;
 mod errors;
+mod evm;
 mod exchange;
+mod expiry;
+mod explorer;
+mod export;
+mod external_feed;
+mod failover;
+#[cfg(feature = "ffi")]
+mod ffi;
+mod fill_reconciler;
+#[cfg(feature = "fix")]
+mod fix;
+mod flow_analytics;
+mod funding;
+mod grid;
+#[cfg(feature = "grpc")]
+mod grpc;
+mod hedge;
 mod helpers;
+mod history;
 mod info;
+mod latency;
+mod margin;
 mod market_maker;
 mod meta;
+mod mid_cache;
+mod notify;
+mod order_lifecycle;
+#[cfg(feature = "postgres")]
+mod postgres_sink;
 mod prelude;
+#[cfg(any(test, feature = "proptest-support"))]
+mod proptest_support;
 mod proxy_digest;
+#[cfg(feature = "pyo3")]
+mod python;
+mod queue_estimator;
+mod quoting;
+mod rebalance;
+#[cfg(feature = "redis")]
+mod redis_mirror;
+mod reference_price;
+mod report;
 mod req;
+#[cfg(feature = "proxy")]
+mod rest_proxy;
+mod runtime;
+mod safe_submitter;
+mod secrets;
+mod serde_helpers;
+mod shutdown;
 mod signature;
+#[cfg(any(test, feature = "sim"))]
+mod sim;
+mod snapshot;
+#[cfg(feature = "sqlite")]
+mod sqlite_sink;
+mod strict;
+mod testnet;
+mod timestamp;
+mod trade_bars;
+#[cfg(feature = "tui")]
+mod tui;
+mod unknown_fields;
+mod volatility;
 mod ws;
-pub use consts::{EPSILON, LOCAL_API_URL, MAINNET_API_URL, TESTNET_API_URL};
+pub use accounting::{
+    AccountPnl, CoinPnl, CostBasisMethod, Lot, PnlEngine, PositionMismatch, RealizedGain,
+};
+pub use agent_rotation::AgentRotator;
+pub use basis_monitor::{monitor_basis, BasisCrossing, BasisPair, BasisUpdate};
+#[cfg(feature = "bench")]
+pub use bench::{decode_ws_frame, l1_action_hash, order_action, sign_l1_action_hash};
+pub use book_check::{
+    compare_books, validate_books, BookCheckConfig, BookCheckMetrics, BookCheckStats,
+    BookDivergence, BookValidator,
+};
+pub use book_delta::{L2BookDelta, LevelChange};
+#[cfg(feature = "kafka")]
+pub use bridge::KafkaPublisher;
+#[cfg(feature = "nats")]
+pub use bridge::NatsPublisher;
+pub use bridge::{Bridge, Encoding, Publisher};
+pub use candle::CandleInterval;
+#[cfg(feature = "config")]
+pub use config::{BotConfig, RiskLimits};
+pub use consts::{
+    EPSILON, LOCAL_API_URL, MAINNET_API_URL, MAINNET_RPC_URL, MAX_WS_SUBSCRIPTIONS,
+    TESTNET_API_URL, TESTNET_RPC_URL,
+};
+pub use dca::{DcaOrderKind, DcaSchedule, DcaScheduler, DcaSize, DcaSkip, DcaState};
+pub use depth::{fill_for_size, size_within_impact, DepthFill};
+pub use drop_copy::{DropCopy, DropCopyEvent, DropCopyKind};
 pub use errors::Error;
+pub use evm::*;
 pub use exchange::*;
-pub use helpers::{bps_diff, truncate_float, BaseUrl};
+pub use expiry::{Expiry, ExpiryQueue, ExpiryScheduler};
+pub use explorer::{explorer_client::*, *};
+#[cfg(feature = "parquet")]
+pub use export::write_parquet;
+pub use export::{write_csv, ColumnType, ExportRecord, ExportValue};
+pub use external_feed::{join_mids, Basis, BinanceFeed, ExternalPrice, ExternalPriceFeed};
+pub use failover::{Failover, FailoverConfig, InMemoryLease, LeaseProvider, Role};
+#[cfg(feature = "ffi")]
+pub use ffi::*;
+pub use fill_reconciler::FillReconciler;
+#[cfg(feature = "fix")]
+pub use fix::{fix_msg_type, fix_tag, FixMessage, FixTranslator};
+pub use flow_analytics::{flow_features, FlowAnalytics, FlowFeatures};
+pub use funding::{next_funding_time, spawn_funding_alerts, FundingForecast};
+pub use grid::{Grid, GridActions, GridConfig, GridOrder, GridSpacing, GridStrategy, OutOfRange};
+#[cfg(feature = "grpc")]
+pub use grpc::{gateway_proto, GrpcGateway};
+pub use hedge::{hedge_orders, DeltaHedger, HedgeConfig, HedgeOrder};
+pub use helpers::{bps_diff, truncate_float, BaseUrl, NoncePartition};
+pub use history::{
+    archive_hours, decompress, parse_l2_books, parse_trades, ArchiveSource, HistoryClient,
+    MARKET_DATA_ARCHIVE_URL, NODE_DATA_ARCHIVE_URL,
+};
 pub use info::{info_client::*, *};
+pub use latency::{LatencyRecorder, LatencyStats};
+pub use margin::{liquidation_price, MarginAccount, MarginPosition, OrderImpact};
 pub use market_maker::{MarketMaker, MarketMakerInput, MarketMakerRestingOrder};
-pub use meta::{AssetMeta, Meta};
+pub use meta::{
+    AssetMeta, AssetPrices, EvmContract, Meta, PerpAssetCtx, SpotAssetMeta, SpotMeta, TokenInfo,
+};
+pub use mid_cache::{MidCache, MidChange};
+#[cfg(feature = "smtp")]
+pub use notify::SmtpAlerter;
+#[cfg(feature = "twilio")]
+pub use notify::TwilioAlerter;
+pub use notify::{Alert, AlertRule, Alerter, Notifier, Severity, WebhookTarget};
+pub use order_lifecycle::{OrderLifecycle, OrderState, OrderTransition};
+#[cfg(feature = "postgres")]
+pub use postgres_sink::PostgresSink;
+pub use queue_estimator::QueueEstimator;
+pub use quoting::{optimal_spread, reservation_price, InventoryQuote, InventoryQuoter};
+pub use rebalance::{
+    plan_rebalance, position_weights, RebalanceConfig, RebalanceReport, RebalanceTrade, Rebalancer,
+};
+#[cfg(feature = "redis")]
+pub use redis_mirror::RedisMirror;
+pub use reference_price::{
+    reference_prices, MedianMid, MidEma, ReferencePrice, RollingTwap, RollingVwap,
+};
+pub use report::{AccrualReport, CoinAccrual};
+pub use req::HttpConfig;
+#[cfg(feature = "proxy")]
+pub use rest_proxy::{ApiKey, RestProxy};
+pub use runtime::PinnedRuntime;
+pub use safe_submitter::{SafeOutcome, SafeSubmitter};
+pub use secrets::KeySource;
+pub use shutdown::GracefulShutdown;
+#[cfg(any(test, feature = "sim"))]
+pub use sim::{ClientFrame, SimConnection, SimExchange};
+pub use snapshot::{load_snapshot, save_snapshot};
+#[cfg(feature = "sqlite")]
+pub use sqlite_sink::SqliteSink;
+pub use strict::decode_strict;
+pub use testnet::{Connectivity, Faucet, TestnetOnboarding};
+pub use timestamp::Timestamp;
+pub use trade_bars::{trade_bars, BarKind, TradeBar, TradeBars};
+#[cfg(feature = "tui")]
+pub use tui::{BookPane, FillsPane, PositionRow, PositionsPane};
+pub use unknown_fields::{set_unknown_fields_hook, ExtraFields, UnknownFields};
+pub use volatility::{
+    volatilities, EwmaVolatility, ParkinsonVolatility, RealizedVolatility, VolatilityEstimator,
+};
 pub use ws::*;
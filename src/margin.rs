@@ -0,0 +1,341 @@
+use crate::{prelude::*, Error, Meta, UserStateResponse};
+
+// Offline replica of the exchange's margin formulas. Initial margin is notional / leverage and
+// maintenance margin is half the initial margin at the asset's max leverage. Funding accrued
+// since the last settlement and the tiered margin tables of large positions aren't modelled,
+// so results near the limits are approximate.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarginPosition {
+    pub coin: String,
+    // Signed size, positive for longs
+    pub szi: f64,
+    pub mark_px: f64,
+    pub leverage: u32,
+    pub max_leverage: u32,
+    pub is_cross: bool,
+    // Margin allocated to an isolated position, including its unrealized pnl
+    pub isolated_margin: f64,
+}
+
+impl MarginPosition {
+    pub fn notional(&self) -> f64 {
+        self.szi.abs() * self.mark_px
+    }
+
+    pub fn initial_margin(&self) -> f64 {
+        self.notional() / self.leverage as f64
+    }
+
+    pub fn maintenance_margin_fraction(&self) -> f64 {
+        1.0 / (2.0 * self.max_leverage as f64)
+    }
+
+    pub fn maintenance_margin(&self) -> f64 {
+        self.notional() * self.maintenance_margin_fraction()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarginAccount {
+    // Cross account value, including unrealized pnl of cross positions
+    pub account_value: f64,
+    pub positions: Vec<MarginPosition>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderImpact {
+    pub position_after: MarginPosition,
+    // Margin the account needs after the fill, against `margin_available`
+    pub initial_margin_required: f64,
+    pub margin_available: f64,
+    pub exceeds_margin: bool,
+    pub exceeds_max_leverage: bool,
+    pub liquidation_px_before: Option<f64>,
+    pub liquidation_px_after: Option<f64>,
+}
+
+impl MarginAccount {
+    // `meta` provides each asset's max leverage.
+    pub fn from_user_state(user_state: &UserStateResponse, meta: &Meta) -> Result<MarginAccount> {
+        let parse = |value: &str| value.parse::<f64>().map_err(|_| Error::FloatStringParse);
+        let mut positions = Vec::new();
+        for asset_position in &user_state.asset_positions {
+            let position = &asset_position.position;
+            let szi = parse(&position.szi)?;
+            if szi == 0.0 {
+                continue;
+            }
+            let max_leverage = meta
+                .universe
+                .iter()
+                .find(|asset| asset.name == position.coin)
+                .and_then(|asset| asset.max_leverage)
+                .ok_or(Error::AssetNotFound)?;
+            let is_cross = position.leverage.type_string == "cross";
+            positions.push(MarginPosition {
+                coin: position.coin.clone(),
+                szi,
+                mark_px: parse(&position.position_value)? / szi.abs(),
+                leverage: position.leverage.value,
+                max_leverage,
+                is_cross,
+                isolated_margin: if is_cross {
+                    0.0
+                } else {
+                    parse(&position.margin_used)?
+                },
+            });
+        }
+        Ok(MarginAccount {
+            account_value: parse(&user_state.cross_margin_summary.account_value)?,
+            positions,
+        })
+    }
+
+    pub fn position(&self, coin: &str) -> Option<&MarginPosition> {
+        self.positions.iter().find(|position| position.coin == coin)
+    }
+
+    pub fn cross_initial_margin(&self) -> f64 {
+        self.cross_positions()
+            .map(MarginPosition::initial_margin)
+            .sum()
+    }
+
+    pub fn cross_maintenance_margin(&self) -> f64 {
+        self.cross_positions()
+            .map(MarginPosition::maintenance_margin)
+            .sum()
+    }
+
+    // Margin not used by cross positions, which new orders and isolated margin draw from.
+    pub fn free_margin(&self) -> f64 {
+        (self.account_value - self.cross_initial_margin()).max(0.0)
+    }
+
+    pub fn liquidation_px(&self, coin: &str) -> Option<f64> {
+        self.position(coin)
+            .map(|position| liquidation_price(position, self))
+    }
+
+    // What filling an order of `sz` at `px` would do to the account. A new position takes
+    // `leverage` (and is cross unless it already exists as isolated); an existing one keeps
+    // its settings. Only margin for increased exposure is checked, as the exchange does; an order
+    // that flips the position increases it by whatever exceeds the current size.
+    pub fn order_impact(
+        &self,
+        coin: &str,
+        is_buy: bool,
+        sz: f64,
+        px: f64,
+        leverage: u32,
+        max_leverage: u32,
+    ) -> OrderImpact {
+        let signed_sz = if is_buy { sz } else { -sz };
+        let before = self.position(coin).cloned();
+        let mut position = before.clone().unwrap_or(MarginPosition {
+            coin: coin.to_string(),
+            szi: 0.0,
+            mark_px: px,
+            leverage,
+            max_leverage,
+            is_cross: true,
+            isolated_margin: 0.0,
+        });
+        // An opposite order first closes the position, and only what's left over adds exposure
+        let closed = if position.szi.signum() == -signed_sz.signum() {
+            sz.min(position.szi.abs())
+        } else {
+            0.0
+        };
+        let increased = sz - closed;
+        let flips = closed > 0.0 && closed == position.szi.abs() && increased > 0.0;
+        position.szi += signed_sz;
+        position.mark_px = px;
+
+        let mut margin_available = self.free_margin();
+        let mut after = self.clone();
+        after.positions.retain(|existing| existing.coin != coin);
+        let added_margin = increased * px / position.leverage as f64;
+        let initial_margin_required = if position.is_cross {
+            after.positions.push(position.clone());
+            after.cross_initial_margin() - self.cross_initial_margin()
+        } else {
+            if flips {
+                // The closed side's margin goes back to the cross account, the new side gets its own
+                margin_available += position.isolated_margin;
+                after.account_value += position.isolated_margin;
+                position.isolated_margin = added_margin;
+            } else {
+                position.isolated_margin += added_margin;
+            }
+            after.positions.push(position.clone());
+            added_margin
+        };
+
+        OrderImpact {
+            exceeds_margin: increased > 0.0 && initial_margin_required > margin_available,
+            exceeds_max_leverage: position.leverage > position.max_leverage,
+            liquidation_px_before: before.and_then(|_| self.liquidation_px(coin)),
+            liquidation_px_after: if position.szi == 0.0 {
+                None
+            } else {
+                after.liquidation_px(coin)
+            },
+            position_after: position,
+            initial_margin_required,
+            margin_available,
+        }
+    }
+
+    fn cross_positions(&self) -> impl Iterator<Item = &MarginPosition> {
+        self.positions.iter().filter(|position| position.is_cross)
+    }
+}
+
+// The mark price at which `position` would be liquidated, all else in `account` being equal:
+// `px - side * margin_available / |szi| / (1 - mmf * side)`, where margin_available is the
+// cross account value less the maintenance margin of all cross positions, or for an isolated
+// position its own margin less its maintenance margin. Returns 0 when the position can't be
+// liquidated (a long backed by more margin than its notional).
+pub fn liquidation_price(position: &MarginPosition, account: &MarginAccount) -> f64 {
+    let margin_available = if position.is_cross {
+        account.account_value - account.cross_maintenance_margin()
+    } else {
+        position.isolated_margin - position.maintenance_margin()
+    };
+    let side = position.szi.signum();
+    let liquidation_px = position.mark_px
+        - side * margin_available
+            / position.szi.abs()
+            / (1.0 - position.maintenance_margin_fraction() * side);
+    liquidation_px.max(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account() -> MarginAccount {
+        MarginAccount {
+            account_value: 1_000.0,
+            positions: vec![MarginPosition {
+                coin: "ETH".to_string(),
+                szi: 1.0,
+                mark_px: 2_000.0,
+                leverage: 10,
+                max_leverage: 50,
+                is_cross: true,
+                isolated_margin: 0.0,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_margin_and_liquidation_px() {
+        let account = account();
+        assert!((account.cross_initial_margin() - 200.0).abs() < 1e-9);
+        assert!((account.cross_maintenance_margin() - 20.0).abs() < 1e-9);
+        // 2000 - (1000 - 20) / 1 / (1 - 0.01)
+        let liquidation_px = account.liquidation_px("ETH").unwrap();
+        assert!((liquidation_px - (2_000.0 - 980.0 / 0.99)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_liquidation_price() {
+        // 10 BTC short at 60k, 20x isolated with 30k of margin, max leverage 40 (mmf 1.25%):
+        // 60000 + (30000 - 7500) / 10 / 1.0125
+        let short = MarginPosition {
+            coin: "BTC".to_string(),
+            szi: -10.0,
+            mark_px: 60_000.0,
+            leverage: 20,
+            max_leverage: 40,
+            is_cross: false,
+            isolated_margin: 30_000.0,
+        };
+        let mut account = account();
+        account.positions.push(short.clone());
+        let liquidation_px = liquidation_price(&short, &account);
+        assert!((liquidation_px - 62_222.222_222).abs() < 1e-3);
+        // Isolated margin is unaffected by the cross account
+        account.account_value = 0.0;
+        assert_eq!(liquidation_price(&short, &account), liquidation_px);
+
+        // A 1x long can't be liquidated
+        let long = MarginPosition {
+            szi: 1.0,
+            leverage: 1,
+            isolated_margin: 60_000.0,
+            ..short
+        };
+        assert_eq!(liquidation_price(&long, &account), 0.0);
+    }
+
+    #[test]
+    fn test_liquidation_price_matches_exchange() {
+        // The clearinghouseState example from the API docs: an isolated 20x ETH long, max
+        // leverage 50, that the exchange reports liquidating at 2866.26936529.
+        let user_state: UserStateResponse = serde_json::from_str(
+            r#"{"assetPositions":[{"position":{"coin":"ETH","cumFunding":{"allTime":"514.085417",
+            "sinceChange":"0.0","sinceOpen":"0.0"},"entryPx":"2986.3","leverage":{"rawUsd":
+            "-95.059824","type":"isolated","value":20},"liquidationPx":"2866.26936529",
+            "marginUsed":"4.967826","maxLeverage":50,"positionValue":"100.02765",
+            "returnOnEquity":"-0.0026789","szi":"0.0335","unrealizedPnl":"-0.0134"},
+            "type":"oneWay"}],"crossMaintenanceMarginUsed":"0.0","crossMarginSummary":
+            {"accountValue":"13104.514502","totalMarginUsed":"0.0","totalNtlPos":"0.0",
+            "totalRawUsd":"13104.514502"},"marginSummary":{"accountValue":"13109.482328",
+            "totalMarginUsed":"4.967826","totalNtlPos":"100.02765","totalRawUsd":"13009.454678"},
+            "time":1708622398623,"withdrawable":"13104.514502"}"#,
+        )
+        .unwrap();
+        let meta: Meta = serde_json::from_str(
+            r#"{"universe":[{"name":"ETH","szDecimals":4,"maxLeverage":50}]}"#,
+        )
+        .unwrap();
+        let account = MarginAccount::from_user_state(&user_state, &meta).unwrap();
+        let liquidation_px = account.liquidation_px("ETH").unwrap();
+        // Within the rounding of the 6 decimal positionValue and marginUsed
+        assert!((liquidation_px - 2_866.269_365_29).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_order_impact() {
+        let account = account();
+        let impact = account.order_impact("ETH", true, 3.0, 2_000.0, 10, 50);
+        assert!((impact.initial_margin_required - 600.0).abs() < 1e-9);
+        assert!(!impact.exceeds_margin);
+        assert!(impact.liquidation_px_after.unwrap() > impact.liquidation_px_before.unwrap());
+
+        let impact = account.order_impact("ETH", true, 5.0, 2_000.0, 10, 50);
+        assert!(impact.exceeds_margin);
+
+        // Reducing never needs margin
+        let impact = account.order_impact("ETH", false, 1.0, 2_000.0, 10, 50);
+        assert!(!impact.exceeds_margin);
+        assert_eq!(impact.liquidation_px_after, None);
+
+        // Long 1, sell 3: short 2 needs 400 of margin where the long needed 200
+        let impact = account.order_impact("ETH", false, 3.0, 2_000.0, 10, 50);
+        assert_eq!(impact.position_after.szi, -2.0);
+        assert!((impact.initial_margin_required - 200.0).abs() < 1e-9);
+        assert!(!impact.exceeds_margin);
+        assert!(impact.liquidation_px_after.unwrap() > 2_000.0);
+        // Short 6 needs 1200, more than the 800 of free margin
+        let impact = account.order_impact("ETH", false, 7.0, 2_000.0, 10, 50);
+        assert!(impact.exceeds_margin);
+
+        // An isolated flip releases the long's margin and funds the short from scratch:
+        // 2000 + (400 - 40) / 2 / 1.01
+        let mut isolated = account.clone();
+        isolated.positions[0].is_cross = false;
+        isolated.positions[0].isolated_margin = 200.0;
+        let impact = isolated.order_impact("ETH", false, 3.0, 2_000.0, 10, 50);
+        assert!((impact.position_after.isolated_margin - 400.0).abs() < 1e-9);
+        assert!((impact.initial_margin_required - 400.0).abs() < 1e-9);
+        assert!((impact.margin_available - 1_200.0).abs() < 1e-9);
+        assert!(!impact.exceeds_margin);
+        let liquidation_px = impact.liquidation_px_after.unwrap();
+        assert!((liquidation_px - (2_000.0 + 360.0 / 2.0 / 1.01)).abs() < 1e-9);
+    }
+}
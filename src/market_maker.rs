@@ -9,7 +9,7 @@ use tokio::sync::mpsc::unbounded_channel;
 use crate::{
     bps_diff, truncate_float, BaseUrl, ClientCancelRequest, ClientLimit, ClientOrder,
     ClientOrderRequest, ExchangeClient, ExchangeDataStatus, ExchangeResponseStatus, InfoClient,
-    Message, Subscription, UserData, EPSILON,
+    InventoryQuoter, Message, Subscription, UserData, EPSILON,
 };
 #[derive(Debug)]
 pub struct MarketMakerRestingOrder {
@@ -39,6 +39,9 @@ pub struct MarketMaker {
     pub upper_resting: MarketMakerRestingOrder,
     pub cur_position: f64,
     pub latest_mid_price: f64,
+    // Quotes around the inventory-skewed reservation price instead of a fixed half spread, with
+    // the horizon to pass as its `time_left`
+    pub inventory_quoter: Option<(InventoryQuoter, f64)>,
     pub info_client: InfoClient,
     pub exchange_client: ExchangeClient,
     pub user_address: H160,
@@ -73,12 +76,18 @@ impl MarketMaker {
             },
             cur_position: 0.0,
             latest_mid_price: -1.0,
+            inventory_quoter: None,
             info_client,
             exchange_client,
             user_address,
         }
     }
 
+    pub fn with_inventory_quoter(mut self, quoter: InventoryQuoter, horizon: f64) -> Self {
+        self.inventory_quoter = Some((quoter, horizon));
+        self
+    }
+
     pub async fn start(&mut self) {
         let (sender, mut receiver) = unbounded_channel();
 
@@ -95,7 +104,7 @@ impl MarketMaker {
 
         // Subscribe to AllMids so we can market make around the mid price
         self.info_client
-            .subscribe(Subscription::AllMids, sender)
+            .subscribe(Subscription::all_mids(), sender)
             .await
             .unwrap();
 
@@ -103,7 +112,7 @@ impl MarketMaker {
             let message = receiver.recv().await.unwrap();
             match message {
                 Message::AllMids(all_mids) => {
-                    let all_mids = all_mids.data.mids;
+                    let all_mids = &all_mids.data.mids;
                     let mid = all_mids.get(&self.asset);
                     if let Some(mid) = mid {
                         let mid: f64 = mid.parse().unwrap();
@@ -122,8 +131,7 @@ impl MarketMaker {
                     if self.latest_mid_price < 0.0 {
                         continue;
                     }
-                    let user_events = user_events.data;
-                    if let UserData::Fills(fills) = user_events {
+                    if let UserData::Fills(fills) = &*user_events.data {
                         for fill in fills {
                             let amount: f64 = fill.sz.parse().unwrap();
                             // Update our resting positions whenever we see a fill
@@ -240,12 +248,20 @@ impl MarketMaker {
     }
 
     async fn potentially_update(&mut self) {
-        let half_spread = (self.latest_mid_price * self.half_spread as f64) / 10000.0;
-        // Determine prices to target from the half spread
-        let (lower_price, upper_price) = (
-            self.latest_mid_price - half_spread,
-            self.latest_mid_price + half_spread,
-        );
+        // Determine prices to target from the half spread, or the inventory quoter
+        let (lower_price, upper_price) = match self.inventory_quoter {
+            Some((quoter, horizon)) => {
+                let quote = quoter.quote(self.latest_mid_price, self.cur_position, horizon);
+                (quote.bid, quote.ask)
+            }
+            None => {
+                let half_spread = (self.latest_mid_price * self.half_spread as f64) / 10000.0;
+                (
+                    self.latest_mid_price - half_spread,
+                    self.latest_mid_price + half_spread,
+                )
+            }
+        };
         let (mut lower_price, mut upper_price) = (
             truncate_float(lower_price, self.decimals, true),
             truncate_float(upper_price, self.decimals, false),
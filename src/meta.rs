@@ -1,25 +1,37 @@
-use ethers::abi::ethereum_types::H128;
-use serde::Deserialize;
+use crate::{prelude::*, Error, ExtraFields};
+use ethers::{abi::ethereum_types::H128, types::H160};
+use serde::{Deserialize, Serialize};
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct Meta {
     pub universe: Vec<AssetMeta>,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct SpotMeta {
     pub universe: Vec<SpotAssetMeta>,
     pub tokens: Vec<TokenInfo>,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+impl SpotMeta {
+    pub fn token(&self, name: &str) -> Option<&TokenInfo> {
+        self.tokens.iter().find(|token| token.name == name)
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct AssetMeta {
     pub name: String,
     pub sz_decimals: u32,
+    #[serde(default)]
+    pub max_leverage: Option<u32>,
+    // Fields the SDK doesn't model yet
+    #[serde(flatten, skip_serializing)]
+    pub extra: ExtraFields,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct SpotAssetMeta {
     pub tokens: [usize; 2],
@@ -28,7 +40,7 @@ pub struct SpotAssetMeta {
     pub is_canonical: bool,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct TokenInfo {
     pub name: String,
@@ -37,4 +49,100 @@ pub struct TokenInfo {
     pub index: usize,
     pub token_id: H128,
     pub is_canonical: bool,
+    #[serde(default)]
+    pub evm_contract: Option<EvmContract>,
+}
+
+impl TokenInfo {
+    // Token identifier used on the wire by spot transfers, e.g. "PURR:0xc4bf3f870c0e9465323c0b6ed28096c2".
+    pub fn wire_name(&self) -> String {
+        format!("{}:{:?}", self.name, self.token_id)
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct EvmContract {
+    pub address: H160,
+    pub evm_extra_wei_decimals: i8,
+}
+
+// Live context of a perp asset, as returned by metaAndAssetCtxs and the activeAssetCtx stream.
+// Prices are kept as sent; use `prices` for parsed values.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PerpAssetCtx {
+    pub funding: String,
+    pub open_interest: String,
+    pub prev_day_px: String,
+    pub day_ntl_vlm: String,
+    pub day_base_vlm: Option<String>,
+    // Premium the exchange uses for funding, sampled from impact prices rather than the mark
+    pub premium: Option<String>,
+    pub oracle_px: String,
+    pub mark_px: String,
+    // Absent when the book is empty on either side
+    pub mid_px: Option<String>,
+    pub impact_pxs: Option<Vec<String>>,
+    // Fields the SDK doesn't model yet
+    #[serde(flatten, skip_serializing)]
+    pub extra: ExtraFields,
+}
+
+impl PerpAssetCtx {
+    pub fn prices(&self) -> Result<AssetPrices> {
+        let parse = |px: &str| px.parse::<f64>().map_err(|_| Error::FloatStringParse);
+        Ok(AssetPrices {
+            mark_px: parse(&self.mark_px)?,
+            oracle_px: parse(&self.oracle_px)?,
+            mid_px: self.mid_px.as_deref().map(parse).transpose()?,
+        })
+    }
+}
+
+// The three prices of a perp. The oracle price is the weighted median of spot prices on
+// external venues; the mark price, used for margining and liquidations, is a median of the
+// oracle price adjusted by the book and external perp prices; the mid is the book's own.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AssetPrices {
+    pub mark_px: f64,
+    pub oracle_px: f64,
+    pub mid_px: Option<f64>,
+}
+
+impl AssetPrices {
+    // Mark minus oracle.
+    pub fn basis(&self) -> f64 {
+        self.mark_px - self.oracle_px
+    }
+
+    // Basis as a fraction of the oracle price.
+    pub fn mark_premium(&self) -> f64 {
+        self.basis() / self.oracle_px
+    }
+
+    // Mid relative to the oracle price, as a fraction.
+    pub fn mid_premium(&self) -> Option<f64> {
+        self.mid_px
+            .map(|mid_px| (mid_px - self.oracle_px) / self.oracle_px)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_asset_ctx_prices() -> Result<()> {
+        let ctx: PerpAssetCtx = serde_json::from_str(
+            r#"{"funding":"0.0000125","openInterest":"688.11","prevDayPx":"3500.0",
+            "dayNtlVlm":"1169046.29406","premium":"0.0005","oraclePx":"3600.0","markPx":"3603.6",
+            "midPx":"3601.8","impactPxs":["3601.7","3601.9"],"dayBaseVlm":"325.6"}"#,
+        )
+        .unwrap();
+        let prices = ctx.prices()?;
+        assert!((prices.basis() - 3.6).abs() < 1e-9);
+        assert!((prices.mark_premium() - 0.001).abs() < 1e-9);
+        assert!((prices.mid_premium().unwrap() - 0.0005).abs() < 1e-9);
+        Ok(())
+    }
 }
@@ -0,0 +1,158 @@
+use crate::{bps_diff, prelude::*, InfoClient, Message, Subscription};
+use log::warn;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, RwLock},
+};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MidChange {
+    // The mid last delivered to this watcher, `None` for the first update
+    pub prev: Option<f64>,
+    pub mid: f64,
+}
+
+struct Watcher {
+    coin: String,
+    min_move_bps: u16,
+    last_sent: Option<f64>,
+    sender: UnboundedSender<MidChange>,
+}
+
+// Latest mid of every coin, kept up to date from the AllMids subscription. Cloning is cheap
+// and clones share state.
+#[derive(Clone, Default)]
+pub struct MidCache {
+    mids: Arc<RwLock<Arc<HashMap<String, f64>>>>,
+    watchers: Arc<Mutex<Vec<Watcher>>>,
+}
+
+impl MidCache {
+    // Subscribes to AllMids and keeps the returned cache updated until unsubscribed with the
+    // returned subscription id.
+    pub async fn subscribe(info_client: &mut InfoClient) -> Result<(MidCache, u32)> {
+        let (sender, mut receiver) = unbounded_channel();
+        let subscription_id = info_client
+            .subscribe(Subscription::all_mids(), sender)
+            .await?;
+        let mid_cache = MidCache::default();
+        let updater = mid_cache.clone();
+        tokio::spawn(async move {
+            while let Some(message) = receiver.recv().await {
+                if let Message::AllMids(all_mids) = message {
+                    updater.update(&all_mids.data.mids);
+                }
+            }
+        });
+        Ok((mid_cache, subscription_id))
+    }
+
+    pub fn get(&self, coin: &str) -> Option<f64> {
+        self.mids.read().unwrap().get(coin).copied()
+    }
+
+    // All mids from a single AllMids update.
+    pub fn snapshot(&self) -> Arc<HashMap<String, f64>> {
+        Arc::clone(&self.mids.read().unwrap())
+    }
+
+    // Streams mids of `coin` that moved at least `min_move_bps` from the last one delivered on
+    // this stream. The current mid, if known, is delivered immediately.
+    pub fn watch(&self, coin: &str, min_move_bps: u16) -> UnboundedReceiver<MidChange> {
+        let (sender, receiver) = unbounded_channel();
+        let mut watcher = Watcher {
+            coin: coin.to_string(),
+            min_move_bps,
+            last_sent: None,
+            sender,
+        };
+        if let Some(mid) = self.get(coin) {
+            watcher.notify(mid);
+        }
+        self.watchers.lock().unwrap().push(watcher);
+        receiver
+    }
+
+    pub fn update(&self, mids: &HashMap<String, String>) {
+        let mut parsed = (**self.mids.read().unwrap()).clone();
+        for (coin, mid) in mids {
+            match mid.parse::<f64>() {
+                Ok(mid) => {
+                    parsed.insert(coin.clone(), mid);
+                }
+                Err(_) => warn!("Could not parse mid {mid} for {coin}"),
+            }
+        }
+        let parsed = Arc::new(parsed);
+        *self.mids.write().unwrap() = Arc::clone(&parsed);
+
+        let mut watchers = self.watchers.lock().unwrap();
+        watchers.retain_mut(|watcher| match parsed.get(&watcher.coin) {
+            Some(&mid) => watcher.notify(mid),
+            None => !watcher.sender.is_closed(),
+        });
+    }
+}
+
+impl Watcher {
+    // Returns false once the receiver is gone.
+    fn notify(&mut self, mid: f64) -> bool {
+        let moved = match self.last_sent {
+            Some(last) => mid != last && bps_diff(last, mid) >= self.min_move_bps,
+            None => true,
+        };
+        if !moved {
+            return !self.sender.is_closed();
+        }
+        let change = MidChange {
+            prev: self.last_sent,
+            mid,
+        };
+        self.last_sent = Some(mid);
+        self.sender.send(change).is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mids(eth: &str, btc: &str) -> HashMap<String, String> {
+        HashMap::from([
+            ("ETH".to_string(), eth.to_string()),
+            ("BTC".to_string(), btc.to_string()),
+        ])
+    }
+
+    #[test]
+    fn test_mid_cache() {
+        let cache = MidCache::default();
+        cache.update(&mids("2000", "60000"));
+        let before = cache.snapshot();
+
+        let mut eth = cache.watch("ETH", 10);
+        assert_eq!(
+            eth.try_recv().unwrap(),
+            MidChange {
+                prev: None,
+                mid: 2000.0
+            }
+        );
+
+        // 5 bps move is filtered, the cumulative 10 bps move isn't
+        cache.update(&mids("2001", "60010"));
+        assert!(eth.try_recv().is_err());
+        cache.update(&mids("2002", "60010"));
+        assert_eq!(
+            eth.try_recv().unwrap(),
+            MidChange {
+                prev: Some(2000.0),
+                mid: 2002.0
+            }
+        );
+
+        assert_eq!(cache.get("BTC"), Some(60010.0));
+        assert_eq!(before.get("BTC"), Some(&60000.0));
+    }
+}
@@ -0,0 +1,727 @@
+use crate::{prelude::*, Error, MarginAccount, Message, ResyncReason, TradeInfo, UserData};
+use ethers::types::H160;
+use futures_util::future::BoxFuture;
+use lazy_static::lazy_static;
+use log::warn;
+use reqwest::{header::CONTENT_TYPE, Client};
+use serde_json::{json, Map, Value};
+use std::{collections::VecDeque, time::Duration};
+use tokio::{
+    sync::mpsc::UnboundedReceiver,
+    task::JoinHandle,
+    time::{self, Instant},
+};
+
+lazy_static! {
+    static ref WEBHOOK_CLIENT: Client = Client::new();
+}
+
+// A channel alerts reach people through. Delivery is attempted once; the notifier retries.
+pub trait Alerter: Send + Sync {
+    fn alert<'a>(&'a self, alert: &'a Alert) -> BoxFuture<'a, Result<()>>;
+}
+
+// Where alerts are posted. Chat webhooks get the alert's text in the shape they expect;
+// generic webhooks get {"rule": ..., "severity": ..., "text": ..., "fields": {...}}.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WebhookTarget {
+    Discord { url: String },
+    Slack { url: String },
+    Telegram { bot_token: String, chat_id: String },
+    Generic { url: String },
+}
+
+impl WebhookTarget {
+    fn request(&self, alert: &Alert) -> (String, Value) {
+        match self {
+            WebhookTarget::Discord { url } => (url.clone(), json!({ "content": alert.text })),
+            WebhookTarget::Slack { url } => (url.clone(), json!({ "text": alert.text })),
+            WebhookTarget::Telegram { bot_token, chat_id } => (
+                format!("https://api.telegram.org/bot{bot_token}/sendMessage"),
+                json!({ "chat_id": chat_id, "text": alert.text }),
+            ),
+            WebhookTarget::Generic { url } => (
+                url.clone(),
+                json!({
+                    "rule": alert.rule,
+                    "severity": alert.severity.as_str(),
+                    "text": alert.text,
+                    "fields": alert.fields,
+                }),
+            ),
+        }
+    }
+}
+
+impl Alerter for WebhookTarget {
+    fn alert<'a>(&'a self, alert: &'a Alert) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let (url, body) = self.request(alert);
+            let response = WEBHOOK_CLIENT
+                .post(&url)
+                .header(CONTENT_TYPE, "application/json")
+                .body(body.to_string())
+                .send()
+                .await
+                .map_err(|e| Error::Webhook(e.to_string()))?;
+            match response.status() {
+                status if status.is_success() => Ok(()),
+                status => Err(Error::Webhook(format!("{url} returned {status}"))),
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Info,
+    Warning,
+    // Needs someone to act now. Never rate limited.
+    Critical,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Critical => "critical",
+        }
+    }
+}
+
+// What to alert on.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AlertRule {
+    // A fill of at least this notional, in USD
+    LargeFill { min_notional: f64 },
+    // A fill liquidating one of the user's positions
+    Liquidation,
+    // At least `count` dropped connections or interrupted streams within `window`
+    ReconnectStorm { count: usize, window: Duration },
+    // An error sent by the exchange over the websocket, including refused subscriptions
+    WsError,
+}
+
+impl AlertRule {
+    fn name(&self) -> &'static str {
+        match self {
+            AlertRule::LargeFill { .. } => "largeFill",
+            AlertRule::Liquidation => "liquidation",
+            AlertRule::ReconnectStorm { .. } => "reconnectStorm",
+            AlertRule::WsError => "wsError",
+        }
+    }
+
+    fn severity(&self) -> Severity {
+        match self {
+            AlertRule::LargeFill { .. } => Severity::Info,
+            AlertRule::ReconnectStorm { .. } | AlertRule::WsError => Severity::Warning,
+            AlertRule::Liquidation => Severity::Critical,
+        }
+    }
+
+    // Fields: coin, side, sz, px, notional, dir, oid for fills, plus mark_px for
+    // liquidations; count and window_secs for reconnect storms; error for errors.
+    fn default_template(&self) -> &'static str {
+        match self {
+            AlertRule::LargeFill { .. } => "Filled {side} {sz} {coin} @ {px} ({dir}, ${notional})",
+            AlertRule::Liquidation => "Liquidated: {side} {sz} {coin} @ {px}, mark {mark_px}",
+            AlertRule::ReconnectStorm { .. } => {
+                "{count} websocket disconnects in the last {window_secs}s"
+            }
+            AlertRule::WsError => "Websocket error: {error}",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alert {
+    pub rule: &'static str,
+    pub severity: Severity,
+    pub text: String,
+    pub fields: Map<String, Value>,
+}
+
+impl Alert {
+    // A critical "marginUsage" alert when the account's cross maintenance margin is over
+    // `max_usage` (e.g. 0.8) of its account value. At 1.0 the exchange liquidates. Fields:
+    // usage (a percentage), maintenance_margin and account_value.
+    pub fn margin_usage(account: &MarginAccount, max_usage: f64) -> Option<Alert> {
+        let maintenance_margin = account.cross_maintenance_margin();
+        let usage = if account.account_value > 0.0 {
+            maintenance_margin / account.account_value
+        } else if maintenance_margin > 0.0 {
+            f64::INFINITY
+        } else {
+            0.0
+        };
+        if usage <= max_usage {
+            return None;
+        }
+        let mut fields = Map::new();
+        fields.insert("usage".to_string(), format!("{:.1}", usage * 100.0).into());
+        fields.insert(
+            "maintenance_margin".to_string(),
+            format!("{maintenance_margin:.2}").into(),
+        );
+        fields.insert(
+            "account_value".to_string(),
+            format!("{:.2}", account.account_value).into(),
+        );
+        Some(Alert {
+            rule: "marginUsage",
+            severity: Severity::Critical,
+            text: render(
+                "Margin usage {usage}%: maintenance margin ${maintenance_margin} of \
+                 ${account_value} account value",
+                &fields,
+            ),
+            fields,
+        })
+    }
+}
+
+// Sends alerts for user events through webhooks and other alerters. Messages come from the
+// user's userFills (or user) subscriptions; every subscription's consumer also sees
+// disconnects and errors, so one channel shared by all of a bot's subscriptions covers every
+// rule. Templates replace `{field}` with the alert's fields. Alerts raised elsewhere, like
+// `Alert::margin_usage`, go through `raise`.
+//
+// Each alerter gets alerts of at least its minimum severity, so e.g. only critical ones are
+// texted. Beyond `max_per_minute`, non-critical alerts are dropped and the next alert sent
+// says how many were. Failed deliveries are retried with exponential backoff.
+//
+//     Notifier::new(vec![WebhookTarget::Discord { url }])
+//         .alerter_at(TwilioAlerter::new(sid, token, from, vec![on_call]), Severity::Critical)
+//         .on(AlertRule::LargeFill { min_notional: 10_000.0 }, None)
+//         .on(AlertRule::Liquidation, Some("@here {coin} position liquidated"))
+//         .spawn(receiver);
+pub struct Notifier {
+    alerters: Vec<(Box<dyn Alerter>, Severity)>,
+    rules: Vec<(AlertRule, String)>,
+    max_per_minute: u32,
+    max_retries: u32,
+    retry_delay: Duration,
+    disconnects: VecDeque<Instant>,
+    sent: VecDeque<Instant>,
+    suppressed: usize,
+}
+
+impl Notifier {
+    pub fn new(targets: Vec<WebhookTarget>) -> Notifier {
+        let notifier = Notifier {
+            alerters: Vec::new(),
+            rules: Vec::new(),
+            max_per_minute: 20,
+            max_retries: 3,
+            retry_delay: Duration::from_secs(1),
+            disconnects: VecDeque::new(),
+            sent: VecDeque::new(),
+            suppressed: 0,
+        };
+        targets.into_iter().fold(notifier, Notifier::alerter)
+    }
+
+    // Sends every alert through `alerter` too.
+    pub fn alerter(self, alerter: impl Alerter + 'static) -> Notifier {
+        self.alerter_at(alerter, Severity::Info)
+    }
+
+    // Sends alerts of at least `severity` through `alerter` too.
+    pub fn alerter_at(mut self, alerter: impl Alerter + 'static, severity: Severity) -> Notifier {
+        self.alerters.push((Box::new(alerter), severity));
+        self
+    }
+
+    // Alerts on `rule`, with `template` or the rule's default one.
+    pub fn on(mut self, rule: AlertRule, template: Option<&str>) -> Notifier {
+        let template = template.unwrap_or(rule.default_template()).to_string();
+        self.rules.push((rule, template));
+        self
+    }
+
+    pub fn max_per_minute(mut self, max_per_minute: u32) -> Notifier {
+        self.max_per_minute = max_per_minute;
+        self
+    }
+
+    pub fn retries(mut self, max_retries: u32, retry_delay: Duration) -> Notifier {
+        self.max_retries = max_retries;
+        self.retry_delay = retry_delay;
+        self
+    }
+
+    // The alerts `message` triggers, after rate limiting.
+    pub fn alerts(&mut self, message: &Message, now: Instant) -> Vec<Alert> {
+        if matches!(message, Message::Closed(_)) || is_interrupted(message) {
+            self.disconnects.push_back(now);
+        }
+        let longest_window = self
+            .rules
+            .iter()
+            .filter_map(|(rule, _)| match rule {
+                AlertRule::ReconnectStorm { window, .. } => Some(*window),
+                _ => None,
+            })
+            .max()
+            .unwrap_or_default();
+        while self
+            .disconnects
+            .front()
+            .is_some_and(|time| now.duration_since(*time) > longest_window)
+        {
+            self.disconnects.pop_front();
+        }
+
+        let mut alerts = Vec::new();
+        for (rule, template) in &self.rules {
+            for fields in rule_fields(rule, message, &self.disconnects, now) {
+                alerts.push(Alert {
+                    rule: rule.name(),
+                    severity: rule.severity(),
+                    text: render(template, &fields),
+                    fields,
+                });
+            }
+        }
+        // One alert per storm: counting starts over
+        if alerts.iter().any(|alert| alert.rule == "reconnectStorm") {
+            self.disconnects.clear();
+        }
+        alerts
+            .into_iter()
+            .filter_map(|alert| self.admit(alert, now))
+            .collect()
+    }
+
+    fn admit(&mut self, mut alert: Alert, now: Instant) -> Option<Alert> {
+        if alert.severity == Severity::Critical {
+            return Some(alert);
+        }
+        while self
+            .sent
+            .front()
+            .is_some_and(|sent| now.duration_since(*sent) >= Duration::from_secs(60))
+        {
+            self.sent.pop_front();
+        }
+        if self.sent.len() >= self.max_per_minute as usize {
+            self.suppressed += 1;
+            return None;
+        }
+        if self.suppressed > 0 {
+            alert.text = format!("{} ({} alerts suppressed)", alert.text, self.suppressed);
+            self.suppressed = 0;
+        }
+        self.sent.push_back(now);
+        Some(alert)
+    }
+
+    // Sends `alert` unless it's rate limited.
+    pub async fn raise(&mut self, alert: Alert) -> Result<()> {
+        match self.admit(alert, Instant::now()) {
+            Some(alert) => self.send(&alert).await,
+            None => Ok(()),
+        }
+    }
+
+    // Sends `alert` through every alerter taking its severity, retrying each on its own.
+    pub async fn send(&self, alert: &Alert) -> Result<()> {
+        let mut first_err = None;
+        for (alerter, severity) in &self.alerters {
+            if alert.severity < *severity {
+                continue;
+            }
+            if let Err(err) = self.deliver(alerter.as_ref(), alert).await {
+                first_err.get_or_insert(err);
+            }
+        }
+        first_err.map_or(Ok(()), Err)
+    }
+
+    async fn deliver(&self, alerter: &dyn Alerter, alert: &Alert) -> Result<()> {
+        let mut attempt = 0;
+        loop {
+            match alerter.alert(alert).await {
+                Ok(()) => return Ok(()),
+                Err(err) if attempt == self.max_retries => return Err(err),
+                Err(_) => {
+                    time::sleep(self.retry_delay * 2u32.saturating_pow(attempt)).await;
+                    attempt += 1;
+                }
+            }
+        }
+    }
+
+    // Sends alerts for every message `receiver` yields until it closes. Alerts are sent in
+    // order, so a slow alerter delays later ones.
+    pub fn spawn(mut self, mut receiver: UnboundedReceiver<Message>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            while let Some(message) = receiver.recv().await {
+                for alert in self.alerts(&message, Instant::now()) {
+                    if let Err(err) = self.send(&alert).await {
+                        warn!("Sending {} alert failed: {err}", alert.rule);
+                    }
+                }
+            }
+        })
+    }
+}
+
+// Emails alerts over SMTP with STARTTLS, e.g. through a provider's relay.
+#[cfg(feature = "smtp")]
+pub struct SmtpAlerter {
+    transport: lettre::AsyncSmtpTransport<lettre::Tokio1Executor>,
+    from: lettre::message::Mailbox,
+    to: Vec<lettre::message::Mailbox>,
+}
+
+#[cfg(feature = "smtp")]
+impl SmtpAlerter {
+    // `from` and `to` are addresses like "Bot <bot@example.com>".
+    pub fn new(
+        host: &str,
+        username: &str,
+        password: &str,
+        from: &str,
+        to: &[&str],
+    ) -> Result<SmtpAlerter> {
+        let parse = |address: &str| {
+            address
+                .parse()
+                .map_err(|e| Error::Webhook(format!("invalid address {address}: {e}")))
+        };
+        let transport = lettre::AsyncSmtpTransport::<lettre::Tokio1Executor>::starttls_relay(host)
+            .map_err(|e| Error::Webhook(e.to_string()))?
+            .credentials(lettre::transport::smtp::authentication::Credentials::new(
+                username.to_string(),
+                password.to_string(),
+            ))
+            .build();
+        Ok(SmtpAlerter {
+            transport,
+            from: parse(from)?,
+            to: to
+                .iter()
+                .map(|address| parse(address))
+                .collect::<Result<_>>()?,
+        })
+    }
+}
+
+#[cfg(feature = "smtp")]
+impl Alerter for SmtpAlerter {
+    fn alert<'a>(&'a self, alert: &'a Alert) -> BoxFuture<'a, Result<()>> {
+        use lettre::AsyncTransport;
+
+        Box::pin(async move {
+            let mut builder = lettre::Message::builder()
+                .from(self.from.clone())
+                .subject(format!("[{}] {}", alert.severity.as_str(), alert.text));
+            for to in &self.to {
+                builder = builder.to(to.clone());
+            }
+            let fields = Value::Object(alert.fields.clone());
+            let body = format!("{}\n\n{fields:#}", alert.text);
+            let email = builder
+                .body(body)
+                .map_err(|e| Error::Webhook(e.to_string()))?;
+            self.transport
+                .send(email)
+                .await
+                .map_err(|e| Error::Webhook(e.to_string()))?;
+            Ok(())
+        })
+    }
+}
+
+// Texts alerts through Twilio's Messages API, one message per recipient. `from` is a Twilio
+// number and recipients are E.164 numbers like "+15551234567".
+#[cfg(feature = "twilio")]
+pub struct TwilioAlerter {
+    account_sid: String,
+    auth_token: String,
+    from: String,
+    to: Vec<String>,
+}
+
+#[cfg(feature = "twilio")]
+impl TwilioAlerter {
+    pub fn new(account_sid: String, auth_token: String, from: String, to: Vec<String>) -> Self {
+        TwilioAlerter {
+            account_sid,
+            auth_token,
+            from,
+            to,
+        }
+    }
+}
+
+#[cfg(feature = "twilio")]
+impl Alerter for TwilioAlerter {
+    fn alert<'a>(&'a self, alert: &'a Alert) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let url = format!(
+                "https://api.twilio.com/2010-04-01/Accounts/{}/Messages.json",
+                self.account_sid
+            );
+            for to in &self.to {
+                let response = WEBHOOK_CLIENT
+                    .post(&url)
+                    .basic_auth(&self.account_sid, Some(&self.auth_token))
+                    .form(&[
+                        ("To", to.as_str()),
+                        ("From", self.from.as_str()),
+                        ("Body", alert.text.as_str()),
+                    ])
+                    .send()
+                    .await
+                    .map_err(|e| Error::Webhook(e.to_string()))?;
+                if !response.status().is_success() {
+                    let status = response.status();
+                    let body = response.text().await.unwrap_or_default();
+                    return Err(Error::Webhook(format!(
+                        "Twilio returned {status} for {to}: {body}"
+                    )));
+                }
+            }
+            Ok(())
+        })
+    }
+}
+
+fn rule_fields(
+    rule: &AlertRule,
+    message: &Message,
+    disconnects: &VecDeque<Instant>,
+    now: Instant,
+) -> Vec<Map<String, Value>> {
+    match rule {
+        AlertRule::LargeFill { min_notional } => fills(message)
+            .filter_map(|fill| {
+                let fields = fill_fields(fill)?;
+                (fields["notional"].as_str()?.parse::<f64>().ok()? >= *min_notional)
+                    .then_some(fields)
+            })
+            .collect(),
+        AlertRule::Liquidation => fills(message)
+            .filter_map(|fill| {
+                let liquidation = fill.liquidation.as_ref()?;
+                // Liquidators' fills carry the liquidation too
+                if let (Some(liquidated), Some(user)) =
+                    (liquidation.liquidated_user, fill_user(message))
+                {
+                    if liquidated != user {
+                        return None;
+                    }
+                }
+                let mut fields = fill_fields(fill)?;
+                fields.insert("mark_px".to_string(), liquidation.mark_px.clone().into());
+                Some(fields)
+            })
+            .collect(),
+        AlertRule::ReconnectStorm { count, window } => {
+            if !matches!(message, Message::Closed(_)) && !is_interrupted(message) {
+                return Vec::new();
+            }
+            let seen = disconnects
+                .iter()
+                .filter(|time| now.duration_since(**time) <= *window)
+                .count();
+            if seen < *count {
+                return Vec::new();
+            }
+            let mut fields = Map::new();
+            fields.insert("count".to_string(), seen.to_string().into());
+            fields.insert(
+                "window_secs".to_string(),
+                window.as_secs().to_string().into(),
+            );
+            vec![fields]
+        }
+        AlertRule::WsError => match message {
+            Message::Error(error) => {
+                let mut fields = Map::new();
+                fields.insert("error".to_string(), error.data.clone().into());
+                vec![fields]
+            }
+            Message::SubscriptionRejected(rejection) => {
+                let mut fields = Map::new();
+                fields.insert("error".to_string(), rejection.reason.clone().into());
+                vec![fields]
+            }
+            _ => Vec::new(),
+        },
+    }
+}
+
+// Snapshots replay past fills, so they don't alert.
+fn fills(message: &Message) -> Box<dyn Iterator<Item = &TradeInfo> + '_> {
+    match message {
+        Message::UserFills(user_fills) if !user_fills.data.is_snapshot.unwrap_or(false) => {
+            Box::new(user_fills.data.fills.iter())
+        }
+        Message::User(user) => match &*user.data {
+            UserData::Fills(fills) => Box::new(fills.iter()),
+            _ => Box::new(std::iter::empty()),
+        },
+        _ => Box::new(std::iter::empty()),
+    }
+}
+
+fn fill_user(message: &Message) -> Option<H160> {
+    match message {
+        Message::UserFills(user_fills) => Some(user_fills.data.user),
+        _ => None,
+    }
+}
+
+fn is_interrupted(message: &Message) -> bool {
+    matches!(message, Message::Resync(resync) if resync.reason == ResyncReason::Interrupted)
+}
+
+fn fill_fields(fill: &TradeInfo) -> Option<Map<String, Value>> {
+    let notional = fill.px.parse::<f64>().ok()? * fill.sz.parse::<f64>().ok()?;
+    let side = if fill.side == "B" { "buy" } else { "sell" };
+    let mut fields = Map::new();
+    fields.insert("coin".to_string(), fill.coin.clone().into());
+    fields.insert("side".to_string(), side.into());
+    fields.insert("sz".to_string(), fill.sz.clone().into());
+    fields.insert("px".to_string(), fill.px.clone().into());
+    fields.insert("notional".to_string(), format!("{notional:.2}").into());
+    fields.insert("dir".to_string(), fill.dir.clone().into());
+    fields.insert("oid".to_string(), fill.oid.to_string().into());
+    Some(fields)
+}
+
+fn render(template: &str, fields: &Map<String, Value>) -> String {
+    fields
+        .iter()
+        .fold(template.to_string(), |text, (name, value)| {
+            text.replace(&format!("{{{name}}}"), value.as_str().unwrap_or_default())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    fn fills_message(sz: &str, liquidated: bool) -> Message {
+        let mut fill = json!({
+            "coin": "ETH", "side": "A", "px": "2000.0", "sz": sz, "time": 1_000,
+            "hash": "0x00", "startPosition": "1.0", "dir": "Close Long", "closedPnl": "0.0",
+            "oid": 7, "crossed": true, "fee": "0.1", "tid": 1
+        });
+        if liquidated {
+            fill["liquidation"] = json!({
+                "liquidatedUser": "0x0000000000000000000000000000000000000001",
+                "markPx": "1990.0",
+                "method": "market"
+            });
+        }
+        serde_json::from_value(json!({
+            "channel": "userFills",
+            "data": {"user": "0x0000000000000000000000000000000000000001", "fills": [fill]}
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_alerts() {
+        let mut notifier = Notifier::new(Vec::new())
+            .on(
+                AlertRule::LargeFill {
+                    min_notional: 1_000.0,
+                },
+                None,
+            )
+            .on(
+                AlertRule::Liquidation,
+                Some("{coin} liquidated at {mark_px}"),
+            )
+            .on(
+                AlertRule::ReconnectStorm {
+                    count: 3,
+                    window: Duration::from_secs(60),
+                },
+                None,
+            )
+            .max_per_minute(2);
+        let now = Instant::now();
+
+        assert!(notifier
+            .alerts(&fills_message("0.1", false), now)
+            .is_empty());
+        let alerts = notifier.alerts(&fills_message("1.0", false), now);
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(
+            alerts[0].text,
+            "Filled sell 1.0 ETH @ 2000.0 (Close Long, $2000.00)"
+        );
+
+        let closed = Message::Closed(crate::WsClosed {
+            code: 1006,
+            reason: String::new(),
+        });
+        assert!(notifier.alerts(&closed, now).is_empty());
+        assert!(notifier.alerts(&closed, now).is_empty());
+        // The second alert this minute is the last one allowed
+        let alerts = notifier.alerts(&closed, now);
+        assert_eq!(alerts[0].text, "3 websocket disconnects in the last 60s");
+        assert!(notifier
+            .alerts(&fills_message("1.0", false), now)
+            .is_empty());
+
+        // Critical alerts aren't rate limited
+        let alerts = notifier.alerts(&fills_message("0.1", true), now);
+        assert_eq!(alerts[0].text, "ETH liquidated at 1990.0");
+        assert_eq!(alerts[0].severity, Severity::Critical);
+
+        let later = now + Duration::from_secs(60);
+        let alerts = notifier.alerts(&fills_message("1.0", false), later);
+        assert!(alerts[0].text.ends_with("(1 alerts suppressed)"));
+    }
+
+    #[tokio::test]
+    async fn test_alerter_severity() {
+        struct Recorder(Arc<Mutex<Vec<String>>>);
+
+        impl Alerter for Recorder {
+            fn alert<'a>(&'a self, alert: &'a Alert) -> BoxFuture<'a, Result<()>> {
+                self.0.lock().unwrap().push(alert.text.clone());
+                Box::pin(async { Ok(()) })
+            }
+        }
+
+        let texted = Arc::new(Mutex::new(Vec::new()));
+        let notifier =
+            Notifier::new(Vec::new()).alerter_at(Recorder(texted.clone()), Severity::Critical);
+        let account = MarginAccount {
+            account_value: 100.0,
+            positions: vec![crate::MarginPosition {
+                coin: "ETH".to_string(),
+                szi: 1.0,
+                mark_px: 2_000.0,
+                leverage: 20,
+                max_leverage: 20,
+                is_cross: true,
+                isolated_margin: 0.0,
+            }],
+        };
+        assert_eq!(Alert::margin_usage(&account, 0.8), None);
+        let alert = Alert::margin_usage(&account, 0.4).unwrap();
+        assert_eq!(
+            alert.text,
+            "Margin usage 50.0%: maintenance margin $50.00 of $100.00 account value"
+        );
+        notifier.send(&alert).await.unwrap();
+        let info = Alert {
+            severity: Severity::Info,
+            ..alert
+        };
+        notifier.send(&info).await.unwrap();
+        assert_eq!(texted.lock().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,334 @@
+use crate::{
+    consts::EPSILON, prelude::*, Error, ExchangeDataStatus, L2BookData, OrderUpdate,
+    QueueEstimator, Timestamp, Trade, TradeInfo,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, time::Duration};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum OrderState {
+    Created,
+    Resting,
+    PartiallyFilled,
+    Filled,
+    Canceled,
+    Rejected,
+}
+
+impl OrderState {
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            OrderState::Filled | OrderState::Canceled | OrderState::Rejected
+        )
+    }
+
+    pub fn can_transition_to(&self, to: OrderState) -> bool {
+        use OrderState::*;
+        match (self, to) {
+            (Created, _) => to != Created,
+            (Resting, Resting | PartiallyFilled | Filled | Canceled) => true,
+            (PartiallyFilled, PartiallyFilled | Filled | Canceled) => true,
+            // Duplicate terminal events are harmless, anything else after a terminal state is not
+            (from, to) => from.is_terminal() && *from == to,
+        }
+    }
+
+    // Maps the status string of an orderUpdates event. Any of the exchange's "...Canceled"
+    // reasons (e.g. marginCanceled, reduceOnlyCanceled) is treated as a cancel.
+    pub fn from_order_status(status: &str) -> Option<OrderState> {
+        match status {
+            "open" | "triggered" => Some(OrderState::Resting),
+            "filled" => Some(OrderState::Filled),
+            "rejected" => Some(OrderState::Rejected),
+            "canceled" | "scheduledCancel" => Some(OrderState::Canceled),
+            status if status.ends_with("Rejected") => Some(OrderState::Rejected),
+            status if status.ends_with("Canceled") => Some(OrderState::Canceled),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OrderTransition {
+    pub from: OrderState,
+    pub to: OrderState,
+}
+
+// Serializable, so a bot can snapshot its live orders with `save_snapshot` and resume tracking
+// them after a restart.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderLifecycle {
+    pub oid: Option<u64>,
+    pub cloid: Option<String>,
+    pub orig_sz: f64,
+    pub filled_sz: f64,
+    pub state: OrderState,
+    pub history: Vec<OrderState>,
+    // Where the order sits in its level's queue, once `track_queue` is called. Not persisted:
+    // after a restart, track it again from a fresh book.
+    #[serde(skip)]
+    pub queue: Option<QueueEstimator>,
+    seen_tids: HashSet<u64>,
+}
+
+impl OrderLifecycle {
+    pub fn new(orig_sz: f64, cloid: Option<String>) -> OrderLifecycle {
+        OrderLifecycle {
+            oid: None,
+            cloid,
+            orig_sz,
+            filled_sz: 0.0,
+            state: OrderState::Created,
+            history: vec![OrderState::Created],
+            queue: None,
+            seen_tids: HashSet::new(),
+        }
+    }
+
+    pub fn remaining_sz(&self) -> f64 {
+        (self.orig_sz - self.filled_sz).max(0.0)
+    }
+
+    // Applies the per-order status returned by the exchange when submitting.
+    pub fn on_ack(&mut self, status: &ExchangeDataStatus) -> Result<Option<OrderTransition>> {
+        match status {
+            ExchangeDataStatus::Resting(resting) => {
+                self.set_oid(resting.oid)?;
+                self.transition(OrderState::Resting)
+            }
+            ExchangeDataStatus::Filled(filled) => {
+                self.set_oid(filled.oid)?;
+                let total_sz: f64 = filled
+                    .total_sz
+                    .parse()
+                    .map_err(|_| Error::FloatStringParse)?;
+                // An IOC that only partially matched reports as filled with the rest canceled
+                if total_sz + EPSILON < self.orig_sz {
+                    self.transition(OrderState::Canceled)
+                } else {
+                    self.transition(OrderState::Filled)
+                }
+            }
+            ExchangeDataStatus::WaitingForFill | ExchangeDataStatus::WaitingForTrigger => {
+                self.transition(OrderState::Resting)
+            }
+            ExchangeDataStatus::Error(_) => self.transition(OrderState::Rejected),
+            ExchangeDataStatus::Success => Ok(None),
+        }
+    }
+
+    pub fn on_order_update(&mut self, update: &OrderUpdate) -> Result<Option<OrderTransition>> {
+        self.set_oid(update.order.oid)?;
+        let to = OrderState::from_order_status(&update.status).ok_or_else(|| {
+            Error::GenericParse(format!("unknown order status {}", update.status))
+        })?;
+        let to = if to == OrderState::Resting && self.filled_sz > EPSILON {
+            OrderState::PartiallyFilled
+        } else {
+            to
+        };
+        self.transition(to)
+    }
+
+    // Fills are deduplicated by tid. A fill can legitimately arrive after the order update that
+    // made the order terminal, so it only moves the state while the order is still live.
+    pub fn on_fill(&mut self, fill: &TradeInfo) -> Result<Option<OrderTransition>> {
+        self.set_oid(fill.oid)?;
+        if !self.seen_tids.insert(fill.tid) {
+            return Ok(None);
+        }
+        let sz: f64 = fill.sz.parse().map_err(|_| Error::FloatStringParse)?;
+        if self.filled_sz + sz > self.orig_sz + EPSILON {
+            return Err(Error::OrderOverfill {
+                oid: fill.oid,
+                filled_sz: self.filled_sz + sz,
+                orig_sz: self.orig_sz,
+            });
+        }
+        self.filled_sz += sz;
+
+        if self.state.is_terminal() {
+            return Ok(None);
+        }
+        if self.remaining_sz() < EPSILON {
+            self.transition(OrderState::Filled)
+        } else {
+            self.transition(OrderState::PartiallyFilled)
+        }
+    }
+
+    // Starts estimating the order's queue position, from the book as of placing it at `px`.
+    pub fn track_queue(&mut self, is_buy: bool, px: f64, book: &L2BookData, placed_at: Timestamp) {
+        self.queue = Some(QueueEstimator::new(is_buy, px, book, placed_at));
+    }
+
+    // Feed the order's coin's trades and books to a tracked queue.
+    pub fn on_trade(&mut self, trade: &Trade) {
+        if let Some(queue) = &mut self.queue {
+            queue.on_trade(trade);
+        }
+    }
+
+    pub fn on_book(&mut self, book: &L2BookData) {
+        let remaining_sz = self.remaining_sz();
+        if let Some(queue) = &mut self.queue {
+            queue.on_book(book, remaining_sz);
+        }
+    }
+
+    // How long until the rest of the order fills, for deciding whether to requote it.
+    pub fn expected_time_to_fill(&self) -> Option<Duration> {
+        self.queue
+            .as_ref()?
+            .expected_time_to_fill(self.remaining_sz())
+    }
+
+    fn set_oid(&mut self, oid: u64) -> Result<()> {
+        match self.oid {
+            Some(existing) if existing != oid => Err(Error::GenericParse(format!(
+                "event for oid {oid} applied to order {existing}"
+            ))),
+            _ => {
+                self.oid = Some(oid);
+                Ok(())
+            }
+        }
+    }
+
+    fn transition(&mut self, to: OrderState) -> Result<Option<OrderTransition>> {
+        let from = self.state;
+        if !from.can_transition_to(to) {
+            return Err(Error::IllegalOrderTransition { from, to });
+        }
+        if from == to {
+            return Ok(None);
+        }
+        self.state = to;
+        self.history.push(to);
+        if to.is_terminal() {
+            self.queue = None;
+        }
+        Ok(Some(OrderTransition { from, to }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BasicOrder, FilledOrder, RestingOrder, Timestamp};
+
+    fn update(oid: u64, status: &str) -> OrderUpdate {
+        OrderUpdate {
+            order: BasicOrder {
+                coin: "ETH".to_string(),
+                side: "B".to_string(),
+                limit_px: "2000".to_string(),
+                sz: "1".to_string(),
+                oid,
+                timestamp: Timestamp::default(),
+                orig_sz: "1".to_string(),
+                cloid: None,
+                extra: Default::default(),
+            },
+            status: status.to_string(),
+            status_timestamp: Timestamp::default(),
+        }
+    }
+
+    #[test]
+    fn test_resting_partial_fill_then_filled() -> Result<()> {
+        let mut order = OrderLifecycle::new(1.0, None);
+        order.on_ack(&ExchangeDataStatus::Resting(RestingOrder { oid: 7 }))?;
+        assert_eq!(order.state, OrderState::Resting);
+
+        let transition = order.on_fill(&TradeInfo::test_fill(7, 1, "0.4"))?;
+        assert_eq!(
+            transition,
+            Some(OrderTransition {
+                from: OrderState::Resting,
+                to: OrderState::PartiallyFilled
+            })
+        );
+        // Duplicate fill is ignored
+        assert_eq!(order.on_fill(&TradeInfo::test_fill(7, 1, "0.4"))?, None);
+        order.on_fill(&TradeInfo::test_fill(7, 2, "0.6"))?;
+        assert_eq!(order.state, OrderState::Filled);
+        // The filled order update arriving late is a no-op
+        assert_eq!(order.on_order_update(&update(7, "filled"))?, None);
+        assert_eq!(
+            order.history,
+            vec![
+                OrderState::Created,
+                OrderState::Resting,
+                OrderState::PartiallyFilled,
+                OrderState::Filled
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_queue_is_tracked_while_live() -> Result<()> {
+        let book = L2BookData {
+            coin: "ETH".to_string(),
+            time: Timestamp::default(),
+            levels: vec![vec![crate::BookLevel {
+                px: "2000".to_string(),
+                sz: "3".to_string(),
+                n: 2,
+            }]],
+        };
+        let mut order = OrderLifecycle::new(1.0, None);
+        order.on_ack(&ExchangeDataStatus::Resting(RestingOrder { oid: 7 }))?;
+        order.track_queue(true, 2000.0, &book, Timestamp::from_millis(0));
+        order.on_trade(&Trade {
+            tid: 1,
+            ..Trade::test_trade("A", "2000", "1", 1_000)
+        });
+        order.on_fill(&TradeInfo::test_fill(7, 2, "0.5"))?;
+        // 2 ahead and 0.5 left, at 1 a second
+        assert_eq!(
+            order.expected_time_to_fill(),
+            Some(Duration::from_millis(2_500))
+        );
+        order.on_order_update(&update(7, "canceled"))?;
+        assert_eq!(order.queue, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_illegal_transitions() -> Result<()> {
+        let mut order = OrderLifecycle::new(1.0, None);
+        order.on_order_update(&update(3, "marginCanceled"))?;
+        assert_eq!(order.state, OrderState::Canceled);
+        assert!(matches!(
+            order.on_order_update(&update(3, "open")),
+            Err(Error::IllegalOrderTransition {
+                from: OrderState::Canceled,
+                to: OrderState::Resting
+            })
+        ));
+        assert!(order.on_fill(&TradeInfo::test_fill(4, 1, "0.1")).is_err());
+        assert!(matches!(
+            order.on_fill(&TradeInfo::test_fill(3, 1, "1.5")),
+            Err(Error::OrderOverfill { .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_ioc_partial_ack_is_canceled() -> Result<()> {
+        let mut order = OrderLifecycle::new(2.0, None);
+        order.on_ack(&ExchangeDataStatus::Filled(FilledOrder {
+            total_sz: "0.5".to_string(),
+            avg_px: "2000".to_string(),
+            oid: 9,
+        }))?;
+        assert_eq!(order.state, OrderState::Canceled);
+        order.on_fill(&TradeInfo::test_fill(9, 10, "0.5"))?;
+        assert_eq!(order.state, OrderState::Canceled);
+        assert!((order.filled_sz - 0.5).abs() < EPSILON);
+        Ok(())
+    }
+}
@@ -0,0 +1,444 @@
+use crate::{prelude::*, CandleData, Error, L2BookData, Message, Trade};
+use chrono::{DateTime, Utc};
+use log::{info, warn};
+use native_tls::TlsConnector;
+use postgres_native_tls::MakeTlsConnector;
+use std::{collections::HashMap, time::Duration};
+use tokio::{sync::mpsc::Receiver, task::JoinHandle, time};
+use tokio_postgres::Client;
+
+// Tables for trades, L2 book levels and candles. Every table's key includes its time column,
+// and the tables become hypertables when the timescaledb extension is installed. Prices and
+// sizes are doubles.
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS trades (
+    time TIMESTAMPTZ NOT NULL,
+    coin TEXT NOT NULL,
+    side TEXT NOT NULL,
+    px DOUBLE PRECISION NOT NULL,
+    sz DOUBLE PRECISION NOT NULL,
+    hash TEXT NOT NULL,
+    tid BIGINT NOT NULL,
+    PRIMARY KEY (coin, tid, time)
+);
+
+-- One row per level of each book snapshot. `level` is 0 at the top of the book and `side` is
+-- 'bid' or 'ask'.
+CREATE TABLE IF NOT EXISTS book_levels (
+    time TIMESTAMPTZ NOT NULL,
+    coin TEXT NOT NULL,
+    side TEXT NOT NULL,
+    level SMALLINT NOT NULL,
+    px DOUBLE PRECISION NOT NULL,
+    sz DOUBLE PRECISION NOT NULL,
+    n BIGINT NOT NULL,
+    PRIMARY KEY (coin, side, level, time)
+);
+
+-- The latest update of each candle.
+CREATE TABLE IF NOT EXISTS candles (
+    time TIMESTAMPTZ NOT NULL,
+    coin TEXT NOT NULL,
+    interval TEXT NOT NULL,
+    time_close TIMESTAMPTZ NOT NULL,
+    open DOUBLE PRECISION NOT NULL,
+    high DOUBLE PRECISION NOT NULL,
+    low DOUBLE PRECISION NOT NULL,
+    close DOUBLE PRECISION NOT NULL,
+    volume DOUBLE PRECISION NOT NULL,
+    num_trades BIGINT NOT NULL,
+    PRIMARY KEY (coin, interval, time)
+);
+
+DO $$
+BEGIN
+    IF EXISTS (SELECT 1 FROM pg_extension WHERE extname = 'timescaledb') THEN
+        PERFORM create_hypertable('trades', 'time', if_not_exists => TRUE);
+        PERFORM create_hypertable('book_levels', 'time', if_not_exists => TRUE);
+        PERFORM create_hypertable('candles', 'time', if_not_exists => TRUE);
+    END IF;
+END $$;
+";
+
+const INSERT_TRADES: &str = "
+INSERT INTO trades (time, coin, side, px, sz, hash, tid)
+SELECT * FROM UNNEST($1::timestamptz[], $2::text[], $3::text[], $4::float8[], $5::float8[],
+    $6::text[], $7::int8[])
+ON CONFLICT DO NOTHING";
+
+const INSERT_BOOK_LEVELS: &str = "
+INSERT INTO book_levels (time, coin, side, level, px, sz, n)
+SELECT * FROM UNNEST($1::timestamptz[], $2::text[], $3::text[], $4::int2[], $5::float8[],
+    $6::float8[], $7::int8[])
+ON CONFLICT DO NOTHING";
+
+const UPSERT_CANDLES: &str = "
+INSERT INTO candles (time, coin, interval, time_close, open, high, low, close, volume,
+    num_trades)
+SELECT * FROM UNNEST($1::timestamptz[], $2::text[], $3::text[], $4::timestamptz[],
+    $5::float8[], $6::float8[], $7::float8[], $8::float8[], $9::float8[], $10::int8[])
+ON CONFLICT (coin, interval, time) DO UPDATE SET
+    time_close = excluded.time_close, open = excluded.open, high = excluded.high,
+    low = excluded.low, close = excluded.close, volume = excluded.volume,
+    num_trades = excluded.num_trades";
+
+// Writes trades, l2Book and candle messages to Postgres in batches, e.g. to feed a research
+// database. Rows are buffered until `batch_size` of them are pending or `flush_interval`
+// passes, then written in one transaction with one statement per table.
+//
+// The sink reads from a bounded subscription (`InfoClient::subscribe_bounded`) and stops
+// reading while a write is in flight or being retried, so a slow or unavailable database
+// fills the subscription's queue and its `SlowConsumerPolicy` applies instead of memory
+// growing without limit. Failed writes are retried with exponential backoff, reconnecting if
+// the connection dropped; once `max_retries` is exhausted the sink stops with the error.
+//
+//     let (sender, receiver) = channel(10_000);
+//     info_client
+//         .subscribe_bounded(Subscription::Trades { coin }, sender, SlowConsumerPolicy::Flag)
+//         .await?;
+//     PostgresSink::new("postgres://research@localhost/hl").spawn(receiver);
+pub struct PostgresSink {
+    url: String,
+    batch_size: usize,
+    flush_interval: Duration,
+    max_retries: u32,
+    retry_delay: Duration,
+}
+
+impl PostgresSink {
+    // `url` is a libpq connection string or URL. TLS is used if the server supports it, or
+    // required with `sslmode=require`.
+    pub fn new(url: &str) -> PostgresSink {
+        PostgresSink {
+            url: url.to_string(),
+            batch_size: 5_000,
+            flush_interval: Duration::from_secs(1),
+            max_retries: 5,
+            retry_delay: Duration::from_millis(500),
+        }
+    }
+
+    pub fn batch_size(mut self, batch_size: usize) -> PostgresSink {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    pub fn flush_interval(mut self, flush_interval: Duration) -> PostgresSink {
+        self.flush_interval = flush_interval;
+        self
+    }
+
+    // Retries of a failed write, the first after `retry_delay` and each following one after
+    // twice the previous delay.
+    pub fn retries(mut self, max_retries: u32, retry_delay: Duration) -> PostgresSink {
+        self.max_retries = max_retries;
+        self.retry_delay = retry_delay;
+        self
+    }
+
+    // Writes until `receiver` closes, flushing what's pending first, or a write fails for good.
+    pub fn spawn(self, mut receiver: Receiver<Message>) -> JoinHandle<Result<()>> {
+        tokio::spawn(async move {
+            let mut client = None;
+            let mut batch = Batch::default();
+            let mut ticker = time::interval(self.flush_interval);
+            ticker.set_missed_tick_behavior(time::MissedTickBehavior::Delay);
+            loop {
+                let (closed, due) = tokio::select! {
+                    message = receiver.recv() => match message {
+                        Some(message) => {
+                            if let Err(err) = batch.push(&message) {
+                                warn!("Postgres sink skipped a malformed message: {err}");
+                            }
+                            (false, false)
+                        }
+                        None => (true, true),
+                    },
+                    _ = ticker.tick() => (false, true),
+                };
+                if due || batch.len() >= self.batch_size {
+                    self.flush(&mut client, &mut batch).await?;
+                }
+                if closed {
+                    return Ok(());
+                }
+            }
+        })
+    }
+
+    async fn flush(&self, client: &mut Option<Client>, batch: &mut Batch) -> Result<()> {
+        if batch.len() == 0 {
+            return Ok(());
+        }
+        let mut attempt = 0;
+        loop {
+            let result = match client {
+                Some(connected) if !connected.is_closed() => batch.write(connected).await,
+                _ => match self.connect().await {
+                    Ok(connected) => batch.write(client.insert(connected)).await,
+                    Err(err) => Err(err),
+                },
+            };
+            let Err(err) = result else {
+                return Ok(());
+            };
+            if attempt == self.max_retries {
+                return Err(err);
+            }
+            warn!("Writing {} rows to Postgres failed: {err}", batch.len());
+            time::sleep(self.retry_delay * 2u32.saturating_pow(attempt)).await;
+            attempt += 1;
+        }
+    }
+
+    async fn connect(&self) -> Result<Client> {
+        let tls = TlsConnector::new().map_err(|e| Error::Postgres(e.to_string()))?;
+        let (client, connection) = tokio_postgres::connect(&self.url, MakeTlsConnector::new(tls))
+            .await
+            .map_err(postgres_err)?;
+        tokio::spawn(async move {
+            if let Err(err) = connection.await {
+                warn!("Postgres connection closed: {err}");
+            }
+        });
+        client.batch_execute(SCHEMA).await.map_err(postgres_err)?;
+        info!("Connected to Postgres");
+        Ok(client)
+    }
+}
+
+struct TradeRow {
+    time: DateTime<Utc>,
+    coin: String,
+    side: String,
+    px: f64,
+    sz: f64,
+    hash: String,
+    tid: i64,
+}
+
+struct BookLevelRow {
+    time: DateTime<Utc>,
+    coin: String,
+    side: &'static str,
+    level: i16,
+    px: f64,
+    sz: f64,
+    n: i64,
+}
+
+struct CandleRow {
+    time: DateTime<Utc>,
+    coin: String,
+    interval: &'static str,
+    time_close: DateTime<Utc>,
+    open: f64,
+    high: f64,
+    low: f64,
+    close: f64,
+    volume: f64,
+    num_trades: i64,
+}
+
+// Rows pending a write, parsed as messages arrive so a write can only fail in the database.
+// Candles are keyed by coin, interval and open time: the sink receives every update of the
+// current candle, and only the latest is kept.
+#[derive(Default)]
+struct Batch {
+    trades: Vec<TradeRow>,
+    book_levels: Vec<BookLevelRow>,
+    candles: HashMap<(String, &'static str, u64), CandleRow>,
+}
+
+impl Batch {
+    fn len(&self) -> usize {
+        self.trades.len() + self.book_levels.len() + self.candles.len()
+    }
+
+    fn push(&mut self, message: &Message) -> Result<()> {
+        match message {
+            Message::Trades(trades) => {
+                for trade in trades.data.iter() {
+                    self.push_trade(trade)?;
+                }
+            }
+            Message::L2Book(l2_book) => self.push_book(&l2_book.data)?,
+            Message::Candle(candle) => self.push_candle(&candle.data)?,
+            Message::SlowConsumer(slow) => warn!(
+                "Postgres sink fell behind: {} of {} messages queued",
+                slow.depth, slow.capacity
+            ),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn push_trade(&mut self, trade: &Trade) -> Result<()> {
+        self.trades.push(TradeRow {
+            time: trade.time.to_datetime(),
+            coin: trade.coin.clone(),
+            side: trade.side.clone(),
+            px: parse(&trade.px)?,
+            sz: parse(&trade.sz)?,
+            hash: trade.hash.clone(),
+            tid: trade.tid as i64,
+        });
+        Ok(())
+    }
+
+    fn push_book(&mut self, book: &L2BookData) -> Result<()> {
+        for (levels, side) in book.levels.iter().zip(["bid", "ask"]) {
+            for (level, book_level) in levels.iter().enumerate() {
+                self.book_levels.push(BookLevelRow {
+                    time: book.time.to_datetime(),
+                    coin: book.coin.clone(),
+                    side,
+                    level: level as i16,
+                    px: parse(&book_level.px)?,
+                    sz: parse(&book_level.sz)?,
+                    n: book_level.n as i64,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn push_candle(&mut self, candle: &CandleData) -> Result<()> {
+        let interval = candle.interval.as_str();
+        self.candles.insert(
+            (candle.coin.clone(), interval, candle.time_open.as_millis()),
+            CandleRow {
+                time: candle.time_open.to_datetime(),
+                coin: candle.coin.clone(),
+                interval,
+                time_close: candle.time_close.to_datetime(),
+                open: parse(&candle.open)?,
+                high: parse(&candle.high)?,
+                low: parse(&candle.low)?,
+                close: parse(&candle.close)?,
+                volume: parse(&candle.volume)?,
+                num_trades: candle.num_trades as i64,
+            },
+        );
+        Ok(())
+    }
+
+    // Each table's rows are written as one array per column, unnested into rows server side.
+    async fn write(&mut self, client: &mut Client) -> Result<()> {
+        let tx = client.transaction().await.map_err(postgres_err)?;
+        if !self.trades.is_empty() {
+            let rows = &self.trades;
+            let time: Vec<_> = rows.iter().map(|r| r.time).collect();
+            let coin: Vec<_> = rows.iter().map(|r| r.coin.as_str()).collect();
+            let side: Vec<_> = rows.iter().map(|r| r.side.as_str()).collect();
+            let px: Vec<_> = rows.iter().map(|r| r.px).collect();
+            let sz: Vec<_> = rows.iter().map(|r| r.sz).collect();
+            let hash: Vec<_> = rows.iter().map(|r| r.hash.as_str()).collect();
+            let tid: Vec<_> = rows.iter().map(|r| r.tid).collect();
+            tx.execute(INSERT_TRADES, &[&time, &coin, &side, &px, &sz, &hash, &tid])
+                .await
+                .map_err(postgres_err)?;
+        }
+        if !self.book_levels.is_empty() {
+            let rows = &self.book_levels;
+            let time: Vec<_> = rows.iter().map(|r| r.time).collect();
+            let coin: Vec<_> = rows.iter().map(|r| r.coin.as_str()).collect();
+            let side: Vec<_> = rows.iter().map(|r| r.side).collect();
+            let level: Vec<_> = rows.iter().map(|r| r.level).collect();
+            let px: Vec<_> = rows.iter().map(|r| r.px).collect();
+            let sz: Vec<_> = rows.iter().map(|r| r.sz).collect();
+            let n: Vec<_> = rows.iter().map(|r| r.n).collect();
+            tx.execute(
+                INSERT_BOOK_LEVELS,
+                &[&time, &coin, &side, &level, &px, &sz, &n],
+            )
+            .await
+            .map_err(postgres_err)?;
+        }
+        if !self.candles.is_empty() {
+            let rows: Vec<_> = self.candles.values().collect();
+            let time: Vec<_> = rows.iter().map(|r| r.time).collect();
+            let coin: Vec<_> = rows.iter().map(|r| r.coin.as_str()).collect();
+            let interval: Vec<_> = rows.iter().map(|r| r.interval).collect();
+            let time_close: Vec<_> = rows.iter().map(|r| r.time_close).collect();
+            let open: Vec<_> = rows.iter().map(|r| r.open).collect();
+            let high: Vec<_> = rows.iter().map(|r| r.high).collect();
+            let low: Vec<_> = rows.iter().map(|r| r.low).collect();
+            let close: Vec<_> = rows.iter().map(|r| r.close).collect();
+            let volume: Vec<_> = rows.iter().map(|r| r.volume).collect();
+            let num_trades: Vec<_> = rows.iter().map(|r| r.num_trades).collect();
+            tx.execute(
+                UPSERT_CANDLES,
+                &[
+                    &time,
+                    &coin,
+                    &interval,
+                    &time_close,
+                    &open,
+                    &high,
+                    &low,
+                    &close,
+                    &volume,
+                    &num_trades,
+                ],
+            )
+            .await
+            .map_err(postgres_err)?;
+        }
+        tx.commit().await.map_err(postgres_err)?;
+        self.trades.clear();
+        self.book_levels.clear();
+        self.candles.clear();
+        Ok(())
+    }
+}
+
+fn parse(value: &str) -> Result<f64> {
+    value.parse().map_err(|_| Error::FloatStringParse)
+}
+
+fn postgres_err(e: tokio_postgres::Error) -> Error {
+    Error::Postgres(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batch_rows() -> Result<()> {
+        let message = |value| serde_json::from_value::<Message>(value).unwrap();
+        let mut batch = Batch::default();
+        batch.push(&message(serde_json::json!({
+            "channel": "l2Book",
+            "data": {
+                "coin": "ETH",
+                "time": 1_000,
+                "levels": [
+                    [{"px": "1999.5", "sz": "1.0", "n": 2}, {"px": "1999.0", "sz": "3.0", "n": 1}],
+                    [{"px": "2000.5", "sz": "2.0", "n": 1}]
+                ]
+            }
+        })))?;
+        assert_eq!(batch.book_levels.len(), 3);
+        assert_eq!(batch.book_levels[1].side, "bid");
+        assert_eq!(batch.book_levels[1].level, 1);
+        assert_eq!(batch.book_levels[2].side, "ask");
+        assert_eq!(batch.book_levels[2].level, 0);
+
+        // Only the latest update of a candle is kept
+        for close in ["2000.0", "2001.0"] {
+            batch.push(&message(serde_json::json!({
+                "channel": "candle",
+                "data": {
+                    "t": 0, "T": 59_999, "s": "ETH", "i": "1m", "o": "1999.0", "c": close,
+                    "h": "2001.0", "l": "1998.0", "v": "10.0", "n": 4
+                }
+            })))?;
+        }
+        assert_eq!(batch.candles.len(), 1);
+        assert_eq!(batch.candles.values().next().unwrap().close, 2001.0);
+        assert_eq!(batch.len(), 4);
+        Ok(())
+    }
+}
@@ -0,0 +1,662 @@
+use crate::{
+    exchange::{CancelRequest, Limit, OrderRequest, Trigger},
+    AllMids, AllMidsData, BasicOrder, BookLevel, BulkCancel, BulkOrder, Candle, CandleData,
+    CandleInterval, ClientCancelRequest, ClientLimit, ClientOrder, ClientOrderRequest,
+    ClientTrigger, ExtraFields, FillLiquidation, L2Book, L2BookData, LiquidationMethod, Message,
+    Order, OrderUpdate, OrderUpdates, Subscription, Timestamp, Trade, TradeInfo, Trades, UserFills,
+    UserFillsData,
+};
+use ethers::types::{H160, H256};
+use proptest::{
+    collection::{hash_map, vec},
+    option,
+    prelude::*,
+    sample::select,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+// `Arbitrary` impls for order requests, subscriptions and websocket messages, so integrations
+// can be fuzzed with `any::<Message>()` and friends:
+//
+//     proptest! {
+//         #[test]
+//         fn handles_any_message(message in any::<Message>()) {
+//             my_strategy.on_message(&message);
+//         }
+//     }
+//
+// Values are shaped like the exchange's: known coins and spot pairs, decimal strings for
+// prices and sizes, millisecond timestamps up to the year 2100.
+
+fn coin() -> impl Strategy<Value = String> {
+    select(vec![
+        "BTC",
+        "ETH",
+        "SOL",
+        "HYPE",
+        "PURR/USDC",
+        "@107",
+        "kPEPE",
+    ])
+    .prop_map(String::from)
+}
+
+// A builder-deployed perp DEX
+fn dex() -> impl Strategy<Value = String> {
+    select(vec!["xyz", "flx"]).prop_map(String::from)
+}
+
+// A positive decimal string with up to 8 decimals, without trailing zeros, like the exchange
+// sends and `float_to_string_for_hashing` produces.
+fn decimal() -> impl Strategy<Value = String> {
+    (1u64..1_000_000_000_000, 0usize..=8).prop_map(|(mantissa, decimals)| {
+        let digits = format!("{mantissa:0>width$}", width = decimals + 1);
+        let (whole, fraction) = digits.split_at(digits.len() - decimals);
+        match fraction.trim_end_matches('0') {
+            "" => whole.to_string(),
+            fraction => format!("{whole}.{fraction}"),
+        }
+    })
+}
+
+fn side() -> impl Strategy<Value = String> {
+    select(vec!["A", "B"]).prop_map(String::from)
+}
+
+fn tx_hash() -> impl Strategy<Value = String> {
+    any::<[u8; 32]>().prop_map(|bytes| format!("{:?}", H256::from(bytes)))
+}
+
+fn address() -> impl Strategy<Value = H160> {
+    any::<[u8; 20]>().prop_map(H160::from)
+}
+
+fn cloid() -> impl Strategy<Value = Uuid> {
+    any::<u128>().prop_map(Uuid::from_u128)
+}
+
+fn tif() -> impl Strategy<Value = String> {
+    select(vec!["Alo", "Ioc", "Gtc"]).prop_map(String::from)
+}
+
+fn tpsl() -> impl Strategy<Value = String> {
+    select(vec!["tp", "sl"]).prop_map(String::from)
+}
+
+impl Arbitrary for Timestamp {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Timestamp>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (0u64..4_102_444_800_000)
+            .prop_map(Timestamp::from_millis)
+            .boxed()
+    }
+}
+
+impl Arbitrary for CandleInterval {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<CandleInterval>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        select(CandleInterval::ALL.to_vec()).boxed()
+    }
+}
+
+impl Arbitrary for Subscription {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Subscription>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        prop_oneof![
+            option::of(dex()).prop_map(|dex| Subscription::AllMids { dex }),
+            coin().prop_map(|coin| Subscription::Trades { coin }),
+            coin().prop_map(|coin| Subscription::L2Book { coin }),
+            address().prop_map(|user| Subscription::UserEvents { user }),
+            address().prop_map(|user| Subscription::UserFills { user }),
+            (coin(), any::<CandleInterval>())
+                .prop_map(|(coin, interval)| Subscription::Candle { coin, interval }),
+            address().prop_map(|user| Subscription::OrderUpdates { user }),
+            address().prop_map(|user| Subscription::UserFundings { user }),
+            address().prop_map(|user| Subscription::UserNonFundingLedgerUpdates { user }),
+            address().prop_map(|user| Subscription::Notification { user }),
+            coin().prop_map(|coin| Subscription::ActiveAssetCtx { coin }),
+        ]
+        .boxed()
+    }
+}
+
+impl Arbitrary for Limit {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Limit>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        tif().prop_map(|tif| Limit { tif }).boxed()
+    }
+}
+
+impl Arbitrary for Trigger {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Trigger>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (any::<bool>(), decimal(), tpsl())
+            .prop_map(|(is_market, trigger_px, tpsl)| Trigger {
+                is_market,
+                trigger_px,
+                tpsl,
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for Order {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Order>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        prop_oneof![
+            any::<Limit>().prop_map(Order::Limit),
+            any::<Trigger>().prop_map(Order::Trigger),
+        ]
+        .boxed()
+    }
+}
+
+impl Arbitrary for OrderRequest {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<OrderRequest>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (
+            0u32..20_000,
+            any::<bool>(),
+            decimal(),
+            decimal(),
+            any::<bool>(),
+            any::<Order>(),
+            option::of(cloid().prop_map(|cloid| format!("0x{}", cloid.simple()))),
+        )
+            .prop_map(
+                |(asset, is_buy, limit_px, sz, reduce_only, order_type, cloid)| OrderRequest {
+                    asset,
+                    is_buy,
+                    limit_px,
+                    sz,
+                    reduce_only,
+                    order_type,
+                    cloid,
+                },
+            )
+            .boxed()
+    }
+}
+
+impl Arbitrary for BulkOrder {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<BulkOrder>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (
+            vec(any::<OrderRequest>(), 1..8),
+            select(vec!["na", "normalTpsl", "positionTpsl"]),
+        )
+            .prop_map(|(orders, grouping)| BulkOrder {
+                orders,
+                grouping: grouping.to_string(),
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for CancelRequest {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<CancelRequest>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (0u32..20_000, any::<u64>())
+            .prop_map(|(asset, oid)| CancelRequest { asset, oid })
+            .boxed()
+    }
+}
+
+impl Arbitrary for BulkCancel {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<BulkCancel>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        vec(any::<CancelRequest>(), 1..8)
+            .prop_map(|cancels| BulkCancel { cancels })
+            .boxed()
+    }
+}
+
+impl Arbitrary for ClientLimit {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<ClientLimit>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        tif().prop_map(|tif| ClientLimit { tif }).boxed()
+    }
+}
+
+impl Arbitrary for ClientTrigger {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<ClientTrigger>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (any::<bool>(), decimal(), tpsl())
+            .prop_map(|(is_market, trigger_px, tpsl)| ClientTrigger {
+                is_market,
+                trigger_px: trigger_px.parse().unwrap_or_default(),
+                tpsl,
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for ClientOrder {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<ClientOrder>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        prop_oneof![
+            any::<ClientLimit>().prop_map(ClientOrder::Limit),
+            any::<ClientTrigger>().prop_map(ClientOrder::Trigger),
+        ]
+        .boxed()
+    }
+}
+
+impl Arbitrary for ClientOrderRequest {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<ClientOrderRequest>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (
+            coin(),
+            any::<bool>(),
+            any::<bool>(),
+            decimal(),
+            decimal(),
+            option::of(cloid()),
+            any::<ClientOrder>(),
+        )
+            .prop_map(
+                |(asset, is_buy, reduce_only, limit_px, sz, cloid, order_type)| {
+                    ClientOrderRequest {
+                        asset,
+                        is_buy,
+                        reduce_only,
+                        limit_px: limit_px.parse().unwrap_or_default(),
+                        sz: sz.parse().unwrap_or_default(),
+                        cloid,
+                        order_type,
+                    }
+                },
+            )
+            .boxed()
+    }
+}
+
+impl Arbitrary for ClientCancelRequest {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<ClientCancelRequest>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (coin(), any::<u64>())
+            .prop_map(|(asset, oid)| ClientCancelRequest { asset, oid })
+            .boxed()
+    }
+}
+
+impl Arbitrary for Trade {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Trade>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (
+            coin(),
+            side(),
+            decimal(),
+            decimal(),
+            any::<Timestamp>(),
+            tx_hash(),
+            any::<u64>(),
+        )
+            .prop_map(|(coin, side, px, sz, time, hash, tid)| Trade {
+                coin,
+                side,
+                px,
+                sz,
+                time,
+                hash,
+                tid,
+                extra: ExtraFields::new(),
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for BookLevel {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<BookLevel>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (decimal(), decimal(), 1u64..100)
+            .prop_map(|(px, sz, n)| BookLevel { px, sz, n })
+            .boxed()
+    }
+}
+
+impl Arbitrary for L2BookData {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<L2BookData>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (
+            coin(),
+            any::<Timestamp>(),
+            vec(any::<BookLevel>(), 0..20),
+            vec(any::<BookLevel>(), 0..20),
+        )
+            .prop_map(|(coin, time, bids, asks)| L2BookData {
+                coin,
+                time,
+                levels: vec![bids, asks],
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for AllMidsData {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<AllMidsData>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (hash_map(coin(), decimal(), 0..8), option::of(dex()))
+            .prop_map(|(mids, dex)| AllMidsData { mids, dex })
+            .boxed()
+    }
+}
+
+impl Arbitrary for BasicOrder {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<BasicOrder>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (
+            coin(),
+            side(),
+            decimal(),
+            decimal(),
+            any::<u64>(),
+            any::<Timestamp>(),
+            decimal(),
+            option::of(cloid().prop_map(|cloid| format!("0x{}", cloid.simple()))),
+        )
+            .prop_map(
+                |(coin, side, limit_px, sz, oid, timestamp, orig_sz, cloid)| BasicOrder {
+                    coin,
+                    side,
+                    limit_px,
+                    sz,
+                    oid,
+                    timestamp,
+                    orig_sz,
+                    cloid,
+                    extra: ExtraFields::new(),
+                },
+            )
+            .boxed()
+    }
+}
+
+impl Arbitrary for OrderUpdate {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<OrderUpdate>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (
+            any::<BasicOrder>(),
+            select(vec![
+                "open",
+                "filled",
+                "canceled",
+                "triggered",
+                "rejected",
+                "marginCanceled",
+            ]),
+            any::<Timestamp>(),
+        )
+            .prop_map(|(order, status, status_timestamp)| OrderUpdate {
+                order,
+                status: status.to_string(),
+                status_timestamp,
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for FillLiquidation {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<FillLiquidation>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (
+            option::of(address()),
+            decimal(),
+            select(vec![LiquidationMethod::Market, LiquidationMethod::Backstop]),
+        )
+            .prop_map(|(liquidated_user, mark_px, method)| FillLiquidation {
+                liquidated_user,
+                mark_px,
+                method,
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for TradeInfo {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<TradeInfo>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (
+            (coin(), side(), decimal(), decimal(), any::<Timestamp>()),
+            (
+                tx_hash(),
+                decimal(),
+                select(vec![
+                    "Open Long",
+                    "Close Long",
+                    "Open Short",
+                    "Close Short",
+                    "Buy",
+                    "Sell",
+                ]),
+                decimal(),
+                any::<u64>(),
+            ),
+            (
+                option::of(cloid().prop_map(|cloid| format!("0x{}", cloid.simple()))),
+                any::<bool>(),
+                decimal(),
+                any::<u64>(),
+                option::of(Just("USDC".to_string())),
+                option::of(decimal()),
+                option::of(any::<FillLiquidation>()),
+            ),
+        )
+            .prop_map(
+                |(
+                    (coin, side, px, sz, time),
+                    (hash, start_position, dir, closed_pnl, oid),
+                    (cloid, crossed, fee, tid, fee_token, builder_fee, liquidation),
+                )| TradeInfo {
+                    coin,
+                    side,
+                    px,
+                    sz,
+                    time,
+                    hash,
+                    start_position,
+                    dir: dir.to_string(),
+                    closed_pnl,
+                    oid,
+                    cloid,
+                    crossed,
+                    fee,
+                    tid,
+                    fee_token,
+                    builder_fee,
+                    liquidation,
+                    extra: ExtraFields::new(),
+                },
+            )
+            .boxed()
+    }
+}
+
+impl Arbitrary for UserFillsData {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<UserFillsData>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (
+            option::of(any::<bool>()),
+            address(),
+            vec(any::<TradeInfo>(), 0..8),
+        )
+            .prop_map(|(is_snapshot, user, fills)| UserFillsData {
+                is_snapshot,
+                user,
+                fills,
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for CandleData {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<CandleData>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (
+            coin(),
+            any::<CandleInterval>(),
+            any::<Timestamp>(),
+            (decimal(), decimal(), decimal(), decimal()),
+            decimal(),
+            any::<u64>(),
+        )
+            .prop_map(
+                |(coin, interval, time_open, (open, high, low, close), volume, num_trades)| {
+                    CandleData {
+                        time_close: Timestamp::from_millis(
+                            time_open.as_millis() + interval.duration().as_millis() as u64 - 1,
+                        ),
+                        close,
+                        high,
+                        interval,
+                        low,
+                        num_trades,
+                        open,
+                        coin,
+                        time_open,
+                        volume,
+                    }
+                },
+            )
+            .boxed()
+    }
+}
+
+// The market data and order/fill messages. Messages the SDK synthesizes itself, like
+// `Closed` or `Resync`, aren't generated.
+impl Arbitrary for Message {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Message>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        prop_oneof![
+            any::<AllMidsData>().prop_map(|data| Message::AllMids(AllMids {
+                data: Arc::new(data)
+            })),
+            vec(any::<Trade>(), 1..8).prop_map(|data| Message::Trades(Trades {
+                data: Arc::new(data)
+            })),
+            any::<L2BookData>().prop_map(|data| Message::L2Book(L2Book {
+                data: Arc::new(data)
+            })),
+            any::<UserFillsData>().prop_map(|data| Message::UserFills(UserFills {
+                data: Arc::new(data)
+            })),
+            any::<CandleData>().prop_map(|data| Message::Candle(Candle {
+                data: Arc::new(data)
+            })),
+            vec(any::<OrderUpdate>(), 1..8).prop_map(|data| Message::OrderUpdates(OrderUpdates {
+                data: Arc::new(data)
+            })),
+            Just(Message::SubscriptionResponse),
+            Just(Message::Pong),
+        ]
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{exchange::action_hash, Actions};
+    use std::collections::HashMap;
+
+    proptest! {
+        #[test]
+        fn test_subscription_round_trip(subscription in any::<Subscription>()) {
+            let json = serde_json::to_string(&subscription).unwrap();
+            prop_assert_eq!(serde_json::from_str::<Subscription>(&json).unwrap(), subscription);
+        }
+
+        #[test]
+        fn test_message_round_trip(message in any::<Message>()) {
+            let value = serde_json::to_value(&message).unwrap();
+            let parsed: Message = serde_json::from_value(value.clone()).unwrap();
+            prop_assert_eq!(serde_json::to_value(&parsed).unwrap(), value);
+        }
+
+        #[test]
+        fn test_order_hash_stability(
+            orders in vec(any::<ClientOrderRequest>(), 1..8),
+            nonce in any::<u64>(),
+        ) {
+            let coin_to_asset: HashMap<String, u32> = ["BTC", "ETH", "SOL", "HYPE", "kPEPE"]
+                .into_iter()
+                .map(String::from)
+                .chain(["PURR/USDC".to_string(), "@107".to_string()])
+                .zip(0..)
+                .collect();
+            let orders: Vec<OrderRequest> = orders
+                .into_iter()
+                .map(|order| {
+                    let limit_px = order.limit_px;
+                    let order = order.convert(&coin_to_asset).unwrap();
+                    // Wire prices parse back to the same float
+                    assert_eq!(order.limit_px.parse::<f64>().unwrap(), limit_px);
+                    order
+                })
+                .collect();
+            let action = Actions::Order(BulkOrder {
+                orders,
+                grouping: "na".to_string(),
+            });
+
+            let hash = action_hash(&action, nonce, None, None).unwrap();
+            let json = serde_json::to_string(&action).unwrap();
+            let parsed: Actions = serde_json::from_str(&json).unwrap();
+            prop_assert_eq!(action_hash(&parsed, nonce, None, None).unwrap(), hash);
+            prop_assert_eq!(action_hash(&action, nonce, None, None).unwrap(), hash);
+        }
+    }
+}
@@ -1,12 +1,9 @@
 // For synchronous signing.
 // Needed to duplicate our own copy because it wasn't possible to import from ethers-signers.
-use ethers::prelude::k256::{
-    elliptic_curve::generic_array::GenericArray,
-    sha2::{
-        self,
-        digest::{Output, OutputSizeUser},
-        Digest,
-    },
+use ethers::prelude::k256::sha2::{
+    self,
+    digest::{Output, OutputSizeUser},
+    Digest,
 };
 use ethers::{
     core::k256::ecdsa::signature::digest::{
@@ -25,10 +22,12 @@ pub(crate) enum ProxyDigest<D: Digest> {
 
 impl<D: Digest + Clone> From<H256> for ProxyDigest<D>
 where
-    GenericArray<u8, <D as OutputSizeUser>::OutputSize>: Copy,
+    Output<D>: Copy,
 {
     fn from(src: H256) -> Self {
-        ProxyDigest::Proxy(*GenericArray::from_slice(src.as_bytes()))
+        let mut output = Output::<D>::default();
+        output.copy_from_slice(src.as_bytes());
+        ProxyDigest::Proxy(output)
     }
 }
 
@@ -67,7 +66,7 @@ impl<D: Digest> OutputSizeUser for ProxyDigest<D> {
 }
 
 impl<D: Digest> FixedOutput for ProxyDigest<D> {
-    fn finalize_into(self, out: &mut GenericArray<u8, Self::OutputSize>) {
+    fn finalize_into(self, out: &mut Output<Self>) {
         match self {
             ProxyDigest::Digest(d) => {
                 *out = d.finalize();
@@ -0,0 +1,303 @@
+// Python bindings. Build and install into the active environment with `maturin develop`.
+use crate::{
+    BaseUrl, ClientCancelRequest, ClientCancelRequestCloid, ClientLimit, ClientOrder,
+    ClientOrderRequest, Error, ExchangeClient, InfoClient, InfoRequest, Subscription,
+};
+use ethers::{signers::LocalWallet, types::H160};
+use log::error;
+use pyo3::{exceptions::PyRuntimeError, prelude::*};
+use pyo3_async_runtimes::tokio::future_into_py;
+use serde::Serialize;
+use std::{str::FromStr, sync::Arc};
+use tokio::sync::{mpsc::unbounded_channel, Mutex};
+use uuid::Uuid;
+
+impl From<Error> for PyErr {
+    fn from(err: Error) -> Self {
+        PyRuntimeError::new_err(err.to_string())
+    }
+}
+
+fn parse_network(network: &str) -> PyResult<BaseUrl> {
+    match network {
+        "mainnet" => Ok(BaseUrl::Mainnet),
+        "testnet" => Ok(BaseUrl::Testnet),
+        "localhost" => Ok(BaseUrl::Localhost),
+        _ => Err(PyRuntimeError::new_err(format!(
+            "unknown network {network}, expected mainnet, testnet or localhost"
+        ))),
+    }
+}
+
+fn parse_address(address: &str) -> PyResult<H160> {
+    H160::from_str(address).map_err(|e| Error::GenericParse(e.to_string()).into())
+}
+
+fn json_to_py(py: Python<'_>, json: &str) -> PyResult<PyObject> {
+    Ok(py.import("json")?.call_method1("loads", (json,))?.unbind())
+}
+
+fn to_py<T: Serialize>(value: &T) -> PyResult<PyObject> {
+    let json = serde_json::to_string(value).map_err(|e| Error::JsonParse(e.to_string()))?;
+    Python::with_gil(|py| json_to_py(py, &json))
+}
+
+async fn post_info(info_client: &Mutex<InfoClient>, request: InfoRequest) -> PyResult<PyObject> {
+    let data = serde_json::to_string(&request).map_err(|e| Error::JsonParse(e.to_string()))?;
+    let response = info_client
+        .lock()
+        .await
+        .http_client
+        .post("/info", data)
+        .await?;
+    Python::with_gil(|py| json_to_py(py, &response))
+}
+
+#[pyclass(name = "InfoClient")]
+struct PyInfoClient {
+    inner: Arc<Mutex<InfoClient>>,
+}
+
+#[pymethods]
+impl PyInfoClient {
+    #[staticmethod]
+    #[pyo3(signature = (network = "mainnet"))]
+    fn connect<'py>(py: Python<'py>, network: &str) -> PyResult<Bound<'py, PyAny>> {
+        let base_url = parse_network(network)?;
+        future_into_py(py, async move {
+            let info_client = InfoClient::new(None, Some(base_url)).await?;
+            Ok(PyInfoClient {
+                inner: Arc::new(Mutex::new(info_client)),
+            })
+        })
+    }
+
+    // Accepts any info request as JSON, e.g. '{"type": "l2Book", "coin": "ETH"}'.
+    fn info<'py>(&self, py: Python<'py>, request: &str) -> PyResult<Bound<'py, PyAny>> {
+        let request = serde_json::from_str::<InfoRequest>(request)
+            .map_err(|e| Error::JsonParse(e.to_string()))?;
+        let inner = Arc::clone(&self.inner);
+        future_into_py(py, async move { post_info(&inner, request).await })
+    }
+
+    fn all_mids<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let inner = Arc::clone(&self.inner);
+        future_into_py(
+            py,
+            async move { post_info(&inner, InfoRequest::AllMids).await },
+        )
+    }
+
+    fn meta<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let inner = Arc::clone(&self.inner);
+        future_into_py(
+            py,
+            async move { post_info(&inner, InfoRequest::Meta).await },
+        )
+    }
+
+    fn spot_meta<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let inner = Arc::clone(&self.inner);
+        future_into_py(
+            py,
+            async move { post_info(&inner, InfoRequest::SpotMeta).await },
+        )
+    }
+
+    fn open_orders<'py>(&self, py: Python<'py>, user: &str) -> PyResult<Bound<'py, PyAny>> {
+        let request = InfoRequest::OpenOrders {
+            user: parse_address(user)?,
+        };
+        let inner = Arc::clone(&self.inner);
+        future_into_py(py, async move { post_info(&inner, request).await })
+    }
+
+    fn user_state<'py>(&self, py: Python<'py>, user: &str) -> PyResult<Bound<'py, PyAny>> {
+        let request = InfoRequest::UserState {
+            user: parse_address(user)?,
+        };
+        let inner = Arc::clone(&self.inner);
+        future_into_py(py, async move { post_info(&inner, request).await })
+    }
+
+    fn user_fills<'py>(&self, py: Python<'py>, user: &str) -> PyResult<Bound<'py, PyAny>> {
+        let request = InfoRequest::UserFills {
+            user: parse_address(user)?,
+        };
+        let inner = Arc::clone(&self.inner);
+        future_into_py(py, async move { post_info(&inner, request).await })
+    }
+
+    fn l2_snapshot<'py>(&self, py: Python<'py>, coin: String) -> PyResult<Bound<'py, PyAny>> {
+        let inner = Arc::clone(&self.inner);
+        future_into_py(py, async move {
+            post_info(&inner, InfoRequest::L2Book { coin }).await
+        })
+    }
+
+    // `callback` is called with each message as a dict from an SDK-owned thread. Use
+    // `loop.call_soon_threadsafe` inside it to hand messages to an asyncio event loop.
+    fn subscribe<'py>(
+        &self,
+        py: Python<'py>,
+        subscription: &str,
+        callback: PyObject,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let subscription = serde_json::from_str::<Subscription>(subscription)
+            .map_err(|e| Error::JsonParse(e.to_string()))?;
+        let inner = Arc::clone(&self.inner);
+        future_into_py(py, async move {
+            let (sender, mut receiver) = unbounded_channel();
+            let subscription_id = inner.lock().await.subscribe(subscription, sender).await?;
+            tokio::spawn(async move {
+                while let Some(message) = receiver.recv().await {
+                    let result = to_py(&message)
+                        .and_then(|message| Python::with_gil(|py| callback.call1(py, (message,))));
+                    if let Err(err) = result {
+                        error!("Error delivering message to Python callback: {err}");
+                    }
+                }
+            });
+            Ok(subscription_id)
+        })
+    }
+
+    fn unsubscribe<'py>(
+        &self,
+        py: Python<'py>,
+        subscription_id: u32,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let inner = Arc::clone(&self.inner);
+        future_into_py(py, async move {
+            inner.lock().await.unsubscribe(subscription_id).await?;
+            Ok(())
+        })
+    }
+}
+
+#[pyclass(name = "ExchangeClient")]
+struct PyExchangeClient {
+    inner: Arc<ExchangeClient>,
+}
+
+#[pymethods]
+impl PyExchangeClient {
+    #[staticmethod]
+    #[pyo3(signature = (private_key, network = "mainnet", vault_address = None))]
+    fn connect<'py>(
+        py: Python<'py>,
+        private_key: &str,
+        network: &str,
+        vault_address: Option<&str>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let wallet = private_key
+            .parse::<LocalWallet>()
+            .map_err(|e| Error::PrivateKeyParse(e.to_string()))?;
+        let base_url = parse_network(network)?;
+        let vault_address = vault_address.map(parse_address).transpose()?;
+        future_into_py(py, async move {
+            let exchange_client =
+                ExchangeClient::new(None, wallet, Some(base_url), None, vault_address).await?;
+            Ok(PyExchangeClient {
+                inner: Arc::new(exchange_client),
+            })
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (asset, is_buy, sz, limit_px, tif = "Gtc", reduce_only = false, cloid = None))]
+    fn order<'py>(
+        &self,
+        py: Python<'py>,
+        asset: String,
+        is_buy: bool,
+        sz: f64,
+        limit_px: f64,
+        tif: &str,
+        reduce_only: bool,
+        cloid: Option<&str>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let cloid = cloid
+            .map(Uuid::from_str)
+            .transpose()
+            .map_err(|e| Error::GenericParse(e.to_string()))?;
+        let order = ClientOrderRequest {
+            asset,
+            is_buy,
+            reduce_only,
+            limit_px,
+            sz,
+            cloid,
+            order_type: ClientOrder::Limit(ClientLimit {
+                tif: tif.to_string(),
+            }),
+        };
+        let inner = Arc::clone(&self.inner);
+        future_into_py(py, async move { to_py(&inner.order(order, None).await?) })
+    }
+
+    fn cancel<'py>(&self, py: Python<'py>, asset: String, oid: u64) -> PyResult<Bound<'py, PyAny>> {
+        let inner = Arc::clone(&self.inner);
+        future_into_py(py, async move {
+            to_py(
+                &inner
+                    .cancel(ClientCancelRequest { asset, oid }, None)
+                    .await?,
+            )
+        })
+    }
+
+    fn cancel_by_cloid<'py>(
+        &self,
+        py: Python<'py>,
+        asset: String,
+        cloid: &str,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let cloid = Uuid::from_str(cloid).map_err(|e| Error::GenericParse(e.to_string()))?;
+        let inner = Arc::clone(&self.inner);
+        future_into_py(py, async move {
+            to_py(
+                &inner
+                    .cancel_by_cloid(ClientCancelRequestCloid { asset, cloid }, None)
+                    .await?,
+            )
+        })
+    }
+
+    #[pyo3(signature = (leverage, coin, is_cross = true))]
+    fn update_leverage<'py>(
+        &self,
+        py: Python<'py>,
+        leverage: u32,
+        coin: String,
+        is_cross: bool,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let inner = Arc::clone(&self.inner);
+        future_into_py(py, async move {
+            to_py(
+                &inner
+                    .update_leverage(leverage, &coin, is_cross, None)
+                    .await?,
+            )
+        })
+    }
+
+    fn usdc_transfer<'py>(
+        &self,
+        py: Python<'py>,
+        amount: String,
+        destination: String,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let inner = Arc::clone(&self.inner);
+        future_into_py(py, async move {
+            to_py(&inner.usdc_transfer(&amount, &destination, None).await?)
+        })
+    }
+}
+
+#[pymodule]
+fn hyperliquid_rust_sdk(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyInfoClient>()?;
+    m.add_class::<PyExchangeClient>()?;
+    Ok(())
+}
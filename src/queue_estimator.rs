@@ -0,0 +1,151 @@
+use crate::{consts::EPSILON, L2BookData, Timestamp, Trade};
+use std::time::Duration;
+
+// Estimates where a resting order sits in its price level's queue, from the book it joined and
+// the trades since, assuming price-time priority and that it joined at the back of the level.
+// Cancels ahead of it can't be seen directly, so when the level shrinks below what's left of
+// the queue, the difference is taken off the size ahead: an upper bound on its position.
+//
+// Time to fill extrapolates the size traded at the order's price or through it since it was
+// placed, so it is only meaningful once some has traded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueueEstimator {
+    pub coin: String,
+    pub is_buy: bool,
+    pub px: f64,
+    pub placed_at: Timestamp,
+    ahead: f64,
+    // Size traded at or through `px` since the order was placed, and the last trade's time
+    reached: f64,
+    last_trade: Timestamp,
+}
+
+impl QueueEstimator {
+    // `book` is the coin's book as of placing the order, not yet including it.
+    pub fn new(is_buy: bool, px: f64, book: &L2BookData, placed_at: Timestamp) -> QueueEstimator {
+        let mut estimator = QueueEstimator {
+            coin: book.coin.clone(),
+            is_buy,
+            px,
+            placed_at,
+            ahead: 0.0,
+            reached: 0.0,
+            last_trade: placed_at,
+        };
+        estimator.ahead = estimator.level_sz(book);
+        estimator
+    }
+
+    // Size queued ahead of the order.
+    pub fn queue_ahead(&self) -> f64 {
+        self.ahead
+    }
+
+    // Traded size per second that reached the order's price since it was placed.
+    pub fn fill_rate(&self) -> Option<f64> {
+        let elapsed = self.last_trade.duration_since(self.placed_at).as_secs_f64();
+        (self.reached > EPSILON && elapsed > 0.0).then(|| self.reached / elapsed)
+    }
+
+    // How long until `remaining_sz` of the order trades at the current rate.
+    pub fn expected_time_to_fill(&self, remaining_sz: f64) -> Option<Duration> {
+        let rate = self.fill_rate()?;
+        Some(Duration::from_secs_f64((self.ahead + remaining_sz) / rate))
+    }
+
+    pub fn on_trade(&mut self, trade: &Trade) {
+        if trade.coin != self.coin || trade.time < self.placed_at {
+            return;
+        }
+        // The side is the aggressor's, so sells (A) trade against bids
+        let against_us = (trade.side == "A") == self.is_buy;
+        let (Ok(px), Ok(sz)) = (trade.px.parse::<f64>(), trade.sz.parse::<f64>()) else {
+            return;
+        };
+        if !against_us {
+            return;
+        }
+        let through = if self.is_buy {
+            px < self.px
+        } else {
+            px > self.px
+        };
+        if (px - self.px).abs() < EPSILON {
+            self.ahead = (self.ahead - sz).max(0.0);
+        } else if through {
+            self.ahead = 0.0;
+        } else {
+            return;
+        }
+        self.reached += sz;
+        self.last_trade = self.last_trade.max(trade.time);
+    }
+
+    // Takes cancels ahead of the order off the queue. `remaining_sz` is what's left of the
+    // order, which the book's level includes once it rests.
+    pub fn on_book(&mut self, book: &L2BookData, remaining_sz: f64) {
+        if book.coin != self.coin {
+            return;
+        }
+        let others = (self.level_sz(book) - remaining_sz).max(0.0);
+        self.ahead = self.ahead.min(others);
+    }
+
+    fn level_sz(&self, book: &L2BookData) -> f64 {
+        let side = if self.is_buy { 0 } else { 1 };
+        book.levels
+            .get(side)
+            .into_iter()
+            .flatten()
+            .find(|level| {
+                level
+                    .px
+                    .parse::<f64>()
+                    .is_ok_and(|px| (px - self.px).abs() < EPSILON)
+            })
+            .and_then(|level| level.sz.parse().ok())
+            .unwrap_or(0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BookLevel;
+
+    #[test]
+    fn test_queue_drains_with_trades_and_cancels() {
+        let book = |bid_sz: &str| {
+            L2BookData::test_book(
+                0,
+                vec![
+                    BookLevel::test_level("2000", bid_sz),
+                    BookLevel::test_level("1999", "4"),
+                ],
+                vec![BookLevel::test_level("2001", "3")],
+            )
+        };
+        let mut bid = QueueEstimator::new(true, 2000.0, &book("5"), Timestamp::from_millis(0));
+        assert_eq!(bid.queue_ahead(), 5.0);
+        assert_eq!(bid.expected_time_to_fill(1.0), None);
+
+        // Buys lift the asks, and don't move the bid's queue
+        bid.on_trade(&Trade::test_trade("B", "2001", "2", 1_000));
+        bid.on_trade(&Trade::test_trade("A", "2000", "2", 2_000));
+        assert_eq!(bid.queue_ahead(), 3.0);
+        assert_eq!(bid.fill_rate(), Some(1.0));
+        assert_eq!(bid.expected_time_to_fill(1.0), Some(Duration::from_secs(4)));
+
+        // The level holds the order's 1 and 3 ahead of it; 1 canceled
+        bid.on_book(&book("3"), 1.0);
+        assert_eq!(bid.queue_ahead(), 2.0);
+        // Growth behind the order doesn't push it back
+        bid.on_book(&book("10"), 1.0);
+        assert_eq!(bid.queue_ahead(), 2.0);
+
+        // Trading through the price clears the queue
+        bid.on_trade(&Trade::test_trade("A", "1999", "2", 4_000));
+        assert_eq!(bid.queue_ahead(), 0.0);
+        assert_eq!(bid.fill_rate(), Some(1.0));
+    }
+}
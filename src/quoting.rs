@@ -0,0 +1,115 @@
+// Inventory-skewed quoting from Avellaneda & Stoikov, "High-frequency trading in a limit order
+// book" (2008). Units have to agree: `volatility` is the standard deviation of mid changes, in
+// price units, over one unit of the time `time_left` is measured in, and `order_intensity` (the
+// paper's k) is how fast the rate of orders filling a quote decays with the quote's distance
+// from the mid, per price unit. The higher `risk_aversion` (gamma), the harder inventory skews
+// the quotes.
+//
+// Over an infinite horizon, pass a fixed `time_left` (e.g. the time it takes to turn inventory
+// over) rather than one counting down to zero.
+
+// The price at which holding `inventory` (positive long) is worth the same as not: below the
+// mid when long and above it when short, so quotes around it lean towards unwinding.
+pub fn reservation_price(
+    mid: f64,
+    inventory: f64,
+    risk_aversion: f64,
+    volatility: f64,
+    time_left: f64,
+) -> f64 {
+    mid - inventory * risk_aversion * volatility.powi(2) * time_left
+}
+
+// The spread between bid and ask, centered on the reservation price, that maximizes expected
+// utility. With no risk aversion it is just the spread the order flow pays for, 2 / k.
+pub fn optimal_spread(
+    risk_aversion: f64,
+    volatility: f64,
+    time_left: f64,
+    order_intensity: f64,
+) -> f64 {
+    if risk_aversion.abs() < f64::EPSILON {
+        return 2.0 / order_intensity;
+    }
+    risk_aversion * volatility.powi(2) * time_left
+        + 2.0 / risk_aversion * (1.0 + risk_aversion / order_intensity).ln()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InventoryQuote {
+    pub reservation_price: f64,
+    pub spread: f64,
+    pub bid: f64,
+    pub ask: f64,
+}
+
+// The model's parameters, for quoting repeatedly as the mid and inventory change.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InventoryQuoter {
+    pub risk_aversion: f64,
+    pub volatility: f64,
+    pub order_intensity: f64,
+}
+
+impl InventoryQuoter {
+    pub fn quote(&self, mid: f64, inventory: f64, time_left: f64) -> InventoryQuote {
+        let reservation_price = reservation_price(
+            mid,
+            inventory,
+            self.risk_aversion,
+            self.volatility,
+            time_left,
+        );
+        let spread = optimal_spread(
+            self.risk_aversion,
+            self.volatility,
+            time_left,
+            self.order_intensity,
+        );
+        InventoryQuote {
+            reservation_price,
+            spread,
+            bid: reservation_price - spread / 2.0,
+            ask: reservation_price + spread / 2.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn test_quotes_skew_against_inventory() {
+        let quoter = InventoryQuoter {
+            risk_aversion: 0.1,
+            volatility: 2.0,
+            order_intensity: 1.5,
+        };
+        let flat = quoter.quote(100.0, 0.0, 1.0);
+        assert_eq!(flat.reservation_price, 100.0);
+        // 0.1 * 4 * 1 + 2 / 0.1 * ln(1 + 0.1 / 1.5)
+        assert!(close(flat.spread, 0.4 + 20.0 * (1.0 + 0.1 / 1.5f64).ln()));
+        assert!(close(flat.ask - flat.bid, flat.spread));
+
+        // Long inventory lowers both quotes, to sell more and buy less, by 0.4 a unit
+        let long = quoter.quote(100.0, 2.0, 1.0);
+        assert!(close(long.reservation_price, 99.2));
+        assert!(close(long.bid, flat.bid - 0.8) && close(long.ask, flat.ask - 0.8));
+        let short = quoter.quote(100.0, -2.0, 1.0);
+        assert!(close(short.reservation_price, 100.8));
+
+        // Skew and spread shrink as the horizon runs out
+        let end = quoter.quote(100.0, 2.0, 0.0);
+        assert_eq!(end.reservation_price, 100.0);
+        assert!(end.spread < long.spread);
+
+        // Risk neutrality is the limit of vanishing risk aversion
+        assert!(close(optimal_spread(0.0, 2.0, 1.0, 1.5), 2.0 / 1.5));
+        assert!((optimal_spread(1e-9, 2.0, 1.0, 1.5) - 2.0 / 1.5).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,294 @@
+use crate::{
+    consts::EPSILON,
+    helpers::{round_perp_px, round_sz_down},
+    prelude::*,
+    ClientLimit, ClientOrder, ClientOrderRequest, Error, ExchangeClient, ExchangeResponseStatus,
+    SafeOutcome, SafeSubmitter, UserStateResponse,
+};
+use log::{info, warn};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+    time::Duration,
+};
+use uuid::Uuid;
+
+#[derive(Debug, Clone)]
+pub struct RebalanceConfig {
+    // Signed perp position weights, as fractions of account value: 0.5 is a long worth half
+    // the account, -0.2 a short worth a fifth. Open positions in coins not listed are closed.
+    pub targets: BTreeMap<String, f64>,
+    // Trades worth less than this, in USD, are skipped
+    pub min_trade_notional: f64,
+    // How far through the mid (a fraction) each IOC order may fill
+    pub slippage: f64,
+}
+
+// One coin's move towards its target, sized at the mid.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RebalanceTrade {
+    pub coin: String,
+    pub is_buy: bool,
+    pub sz: f64,
+    pub limit_px: f64,
+    // Set when the trade only shrinks the position, so it can't open one if a fill raced it
+    pub reduce_only: bool,
+    pub weight: f64,
+    pub target_weight: f64,
+}
+
+#[derive(Debug)]
+pub struct RebalanceReport {
+    pub trades: Vec<(RebalanceTrade, Result<SafeOutcome>)>,
+    pub weights_before: BTreeMap<String, f64>,
+    // Read back from the account once the trades are done
+    pub weights_after: BTreeMap<String, f64>,
+}
+
+// Each position's weight of `account_value`, at `mids`. Positions without a mid are left out.
+pub fn position_weights(
+    account_value: f64,
+    positions: &BTreeMap<String, f64>,
+    mids: &HashMap<String, f64>,
+) -> BTreeMap<String, f64> {
+    positions
+        .iter()
+        .filter_map(|(coin, szi)| Some((coin.clone(), szi * mids.get(coin)? / account_value)))
+        .collect()
+}
+
+// The trades that move `positions` to `config`'s target weights, positions being reduced first
+// so they free margin for the rest. `sz_decimals` are the coins' from `Meta`.
+pub fn plan_rebalance(
+    account_value: f64,
+    positions: &BTreeMap<String, f64>,
+    mids: &HashMap<String, f64>,
+    sz_decimals: &HashMap<String, u32>,
+    config: &RebalanceConfig,
+) -> Result<Vec<RebalanceTrade>> {
+    let coins = config.targets.keys().chain(
+        positions
+            .keys()
+            .filter(|coin| positions[*coin].abs() > EPSILON),
+    );
+    let mut trades = Vec::new();
+    for coin in coins {
+        if trades
+            .iter()
+            .any(|trade: &RebalanceTrade| &trade.coin == coin)
+        {
+            continue;
+        }
+        let mid = *mids.get(coin).ok_or(Error::AssetNotFound)?;
+        let &decimals = sz_decimals.get(coin).ok_or(Error::AssetNotFound)?;
+        let szi = positions.get(coin).copied().unwrap_or_default();
+        let target_weight = config.targets.get(coin).copied().unwrap_or_default();
+        let target_szi = target_weight * account_value / mid;
+        let delta = target_szi - szi;
+        // Closing a position entirely has to match it exactly rather than round down
+        let sz = if target_weight == 0.0 {
+            szi.abs()
+        } else {
+            round_sz_down(delta.abs(), decimals)
+        };
+        if sz <= EPSILON || sz * mid < config.min_trade_notional {
+            continue;
+        }
+        let is_buy = delta > 0.0;
+        let slipped = mid
+            * if is_buy {
+                1.0 + config.slippage
+            } else {
+                1.0 - config.slippage
+            };
+        trades.push(RebalanceTrade {
+            coin: coin.clone(),
+            is_buy,
+            sz,
+            limit_px: round_perp_px(slipped, decimals),
+            reduce_only: szi != 0.0 && (szi > 0.0) != is_buy && sz <= szi.abs() + EPSILON,
+            weight: szi * mid / account_value,
+            target_weight,
+        });
+    }
+    trades.sort_by_key(|trade| !trade.reduce_only);
+    Ok(trades)
+}
+
+// Brings an account's perp positions to target weights with IOC orders through `SafeSubmitter`,
+// so a lost ack never leaves a trade's outcome unknown.
+pub struct Rebalancer {
+    pub config: RebalanceConfig,
+    client: Arc<ExchangeClient>,
+    submitter: SafeSubmitter,
+}
+
+impl Rebalancer {
+    pub fn new(client: Arc<ExchangeClient>, config: RebalanceConfig) -> Rebalancer {
+        Rebalancer {
+            config,
+            submitter: SafeSubmitter::new(client.clone(), Duration::from_secs(5)),
+            client,
+        }
+    }
+
+    pub fn with_submitter(mut self, submitter: SafeSubmitter) -> Self {
+        self.submitter = submitter;
+        self
+    }
+
+    // Plans and executes one rebalance. A failed trade doesn't stop the others; its error is
+    // in the report.
+    pub async fn rebalance(&self) -> Result<RebalanceReport> {
+        let user = self.client.account_address(None).await?;
+        let info_client = self.client.info_client();
+        let sz_decimals: HashMap<String, u32> = info_client
+            .meta()
+            .await?
+            .universe
+            .into_iter()
+            .map(|asset| (asset.name, asset.sz_decimals))
+            .collect();
+        let mids: HashMap<String, f64> = info_client
+            .all_mids()
+            .await?
+            .into_iter()
+            .filter_map(|(coin, mid)| Some((coin, mid.parse().ok()?)))
+            .collect();
+        let (account_value, positions) = account(&info_client.user_state(user).await?)?;
+        let weights_before = position_weights(account_value, &positions, &mids);
+        let plan = plan_rebalance(account_value, &positions, &mids, &sz_decimals, &self.config)?;
+
+        let mut trades = Vec::new();
+        for trade in plan {
+            info!(
+                "Rebalancing {} from {:.4} to {:.4}: {} {}",
+                trade.coin,
+                trade.weight,
+                trade.target_weight,
+                if trade.is_buy { "buy" } else { "sell" },
+                trade.sz
+            );
+            let order = ClientOrderRequest {
+                asset: trade.coin.clone(),
+                is_buy: trade.is_buy,
+                reduce_only: trade.reduce_only,
+                limit_px: trade.limit_px,
+                sz: trade.sz,
+                cloid: Some(Uuid::new_v4()),
+                order_type: ClientOrder::Limit(ClientLimit {
+                    tif: "Ioc".to_string(),
+                }),
+            };
+            let outcome = self.submitter.submit(order, None).await;
+            match &outcome {
+                Ok(SafeOutcome::Acked(ExchangeResponseStatus::Err(err))) => {
+                    warn!("Rebalancing {} was rejected: {err:?}", trade.coin)
+                }
+                Err(err) => warn!("Rebalancing {} failed: {err}", trade.coin),
+                _ => {}
+            }
+            trades.push((trade, outcome));
+        }
+
+        let mids: HashMap<String, f64> = info_client
+            .all_mids()
+            .await?
+            .into_iter()
+            .filter_map(|(coin, mid)| Some((coin, mid.parse().ok()?)))
+            .collect();
+        let (account_value, positions) = account(&info_client.user_state(user).await?)?;
+        Ok(RebalanceReport {
+            trades,
+            weights_before,
+            weights_after: position_weights(account_value, &positions, &mids),
+        })
+    }
+}
+
+fn account(user_state: &UserStateResponse) -> Result<(f64, BTreeMap<String, f64>)> {
+    let account_value: f64 = user_state
+        .margin_summary
+        .account_value
+        .parse()
+        .map_err(|_| Error::FloatStringParse)?;
+    if account_value <= 0.0 {
+        return Err(Error::InvalidAmount(format!(
+            "can't rebalance an account worth {account_value}"
+        )));
+    }
+    let positions = user_state
+        .asset_positions
+        .iter()
+        .filter_map(|asset_position| {
+            let position = &asset_position.position;
+            Some((position.coin.clone(), position.szi.parse().ok()?))
+        })
+        .collect();
+    Ok((account_value, positions))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_moves_to_target_weights() {
+        let mids = HashMap::from([
+            ("BTC".to_string(), 50_000.0),
+            ("ETH".to_string(), 2_000.0),
+            ("SOL".to_string(), 100.0),
+            ("DOGE".to_string(), 0.1),
+        ]);
+        let sz_decimals = HashMap::from([
+            ("BTC".to_string(), 5),
+            ("ETH".to_string(), 4),
+            ("SOL".to_string(), 2),
+            ("DOGE".to_string(), 0),
+        ]);
+        let positions = BTreeMap::from([
+            ("BTC".to_string(), 0.1),
+            ("ETH".to_string(), -1.0),
+            ("DOGE".to_string(), 1_234.0),
+        ]);
+        let config = RebalanceConfig {
+            targets: BTreeMap::from([
+                ("BTC".to_string(), 0.3),
+                ("ETH".to_string(), 0.0),
+                ("SOL".to_string(), -0.2),
+            ]),
+            min_trade_notional: 10.0,
+            slippage: 0.01,
+        };
+        let weights = position_weights(10_000.0, &positions, &mids);
+        assert_eq!(weights["BTC"], 0.5);
+
+        let trades = plan_rebalance(10_000.0, &positions, &mids, &sz_decimals, &config).unwrap();
+        let summary: Vec<_> = trades
+            .iter()
+            .map(|t| (t.coin.as_str(), t.is_buy, t.sz, t.limit_px, t.reduce_only))
+            .collect();
+        // Reductions first: BTC from 0.5 to 0.3, closing the ETH short and the DOGE long,
+        // which isn't a target; then opening the SOL short
+        assert_eq!(
+            summary,
+            vec![
+                ("BTC", false, 0.04, 49_500.0, true),
+                ("ETH", true, 1.0, 2_020.0, true),
+                ("DOGE", false, 1_234.0, 0.099, true),
+                ("SOL", false, 20.0, 99.0, false),
+            ]
+        );
+
+        // Small trades are skipped
+        let config = RebalanceConfig {
+            min_trade_notional: 500.0,
+            ..config
+        };
+        let trades = plan_rebalance(10_000.0, &positions, &mids, &sz_decimals, &config).unwrap();
+        assert!(trades.iter().all(|t| t.coin != "DOGE"));
+        assert!(
+            plan_rebalance(10_000.0, &positions, &HashMap::new(), &sz_decimals, &config).is_err()
+        );
+    }
+}
@@ -0,0 +1,188 @@
+use crate::{prelude::*, Error, InfoClient, L2BookData, Message, UserStateResponse};
+use ethers::types::H160;
+use log::warn;
+use redis::{aio::ConnectionManager, Pipeline};
+use std::{collections::HashMap, time::Duration};
+use tokio::{sync::mpsc::UnboundedReceiver, task::JoinHandle, time};
+
+// Mirrors mids, books and positions into Redis, so services without an exchange connection
+// can read fresh state. Each update both sets a latest-value key and is published on a
+// channel of the same name, as JSON:
+//
+//   {prefix}:mids              hash of coin to mid, published as the allMids map
+//   {prefix}:book:{coin}       the coin's latest l2Book data
+//   {prefix}:positions:{user}  the user's clearinghouse state, from `user_state`
+//
+// With a TTL set, latest-value keys expire once the mirror stops updating them, so readers
+// can't mistake stale state for fresh.
+#[derive(Clone)]
+pub struct RedisMirror {
+    connection: ConnectionManager,
+    prefix: String,
+    ttl: Option<Duration>,
+}
+
+impl RedisMirror {
+    // Connects to e.g. "redis://localhost:6379", reconnecting whenever the connection drops.
+    pub async fn connect(url: &str) -> Result<RedisMirror> {
+        let client = redis::Client::open(url).map_err(redis_err)?;
+        let connection = ConnectionManager::new(client).await.map_err(redis_err)?;
+        Ok(RedisMirror {
+            connection,
+            prefix: "hl".to_string(),
+            ttl: None,
+        })
+    }
+
+    pub fn prefix(mut self, prefix: &str) -> RedisMirror {
+        self.prefix = prefix.to_string();
+        self
+    }
+
+    pub fn ttl(mut self, ttl: Duration) -> RedisMirror {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    // Mirrors allMids and l2Book messages; others are ignored.
+    pub async fn mirror(&mut self, message: &Message) -> Result<()> {
+        let pipe = match message {
+            Message::AllMids(all_mids) => {
+                mids_commands(&self.prefix, self.ttl, &all_mids.data.mids)?
+            }
+            Message::L2Book(l2_book) => book_commands(&self.prefix, self.ttl, &l2_book.data)?,
+            _ => return Ok(()),
+        };
+        pipe.query_async::<()>(&mut self.connection)
+            .await
+            .map_err(redis_err)
+    }
+
+    pub async fn mirror_positions(&mut self, user: H160, state: &UserStateResponse) -> Result<()> {
+        positions_commands(&self.prefix, self.ttl, user, state)?
+            .query_async::<()>(&mut self.connection)
+            .await
+            .map_err(redis_err)
+    }
+
+    // Mirrors every message `receiver` yields until it closes. Failed writes are logged; the
+    // next update of the same key replaces the missed one.
+    pub fn spawn(mut self, mut receiver: UnboundedReceiver<Message>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            while let Some(message) = receiver.recv().await {
+                if let Err(err) = self.mirror(&message).await {
+                    warn!("Mirroring to Redis failed: {err}");
+                }
+            }
+        })
+    }
+
+    // Polls `user`'s clearinghouse state every `interval` and mirrors it, until the returned
+    // task is aborted.
+    pub fn spawn_positions(
+        &self,
+        info_client: InfoClient,
+        user: H160,
+        interval: Duration,
+    ) -> JoinHandle<()> {
+        let mut mirror = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = time::interval(interval);
+            ticker.set_missed_tick_behavior(time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                let mirrored = async {
+                    let state = info_client.user_state(user).await?;
+                    mirror.mirror_positions(user, &state).await
+                };
+                if let Err(err) = mirrored.await {
+                    warn!("Mirroring positions of {user:?} to Redis failed: {err}");
+                }
+            }
+        })
+    }
+}
+
+fn mids_commands(
+    prefix: &str,
+    ttl: Option<Duration>,
+    mids: &HashMap<String, String>,
+) -> Result<Pipeline> {
+    let key = format!("{prefix}:mids");
+    let mut pipe = redis::pipe();
+    pipe.atomic();
+    // HMSET rejects an empty field list
+    if !mids.is_empty() {
+        pipe.hset_multiple(&key, &mids.iter().collect::<Vec<_>>())
+            .ignore();
+    }
+    if let Some(ttl) = ttl {
+        pipe.expire(&key, ttl.as_secs().max(1) as i64).ignore();
+    }
+    pipe.publish(&key, to_json(mids)?).ignore();
+    Ok(pipe)
+}
+
+fn book_commands(prefix: &str, ttl: Option<Duration>, book: &L2BookData) -> Result<Pipeline> {
+    let key = format!("{prefix}:book:{}", book.coin);
+    Ok(latest_value_commands(key, ttl, to_json(book)?))
+}
+
+fn positions_commands(
+    prefix: &str,
+    ttl: Option<Duration>,
+    user: H160,
+    state: &UserStateResponse,
+) -> Result<Pipeline> {
+    let key = format!("{prefix}:positions:{user:?}");
+    Ok(latest_value_commands(key, ttl, to_json(state)?))
+}
+
+fn latest_value_commands(key: String, ttl: Option<Duration>, value: String) -> Pipeline {
+    let mut pipe = redis::pipe();
+    pipe.atomic();
+    match ttl {
+        Some(ttl) => pipe.set_ex(&key, &value, ttl.as_secs().max(1)).ignore(),
+        None => pipe.set(&key, &value).ignore(),
+    };
+    pipe.publish(&key, &value).ignore();
+    pipe
+}
+
+fn to_json(value: &impl serde::Serialize) -> Result<String> {
+    serde_json::to_string(value).map_err(|e| Error::JsonParse(e.to_string()))
+}
+
+fn redis_err(e: redis::RedisError) -> Error {
+    Error::Redis(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redis_commands() -> Result<()> {
+        let book: L2BookData = serde_json::from_value(serde_json::json!({
+            "coin": "ETH",
+            "time": 1_000,
+            "levels": [[{"px": "1999.5", "sz": "1.0", "n": 2}], []]
+        }))
+        .unwrap();
+        let packed =
+            book_commands("hl", Some(Duration::from_secs(30)), &book)?.get_packed_pipeline();
+        let packed = String::from_utf8_lossy(&packed);
+        assert!(packed.contains("SETEX"));
+        assert!(packed.contains("PUBLISH"));
+        assert_eq!(packed.matches("hl:book:ETH").count(), 2);
+        assert!(packed.contains("\"1999.5\""));
+
+        let mids = HashMap::from([("BTC".to_string(), "60000.0".to_string())]);
+        let packed = mids_commands("desk", None, &mids)?.get_packed_pipeline();
+        let packed = String::from_utf8_lossy(&packed);
+        assert!(packed.contains("HMSET"));
+        assert!(!packed.contains("EXPIRE"));
+        assert!(packed.contains("desk:mids"));
+        Ok(())
+    }
+}
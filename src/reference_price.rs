@@ -0,0 +1,315 @@
+use crate::{Message, Timestamp};
+use std::{collections::VecDeque, time::Duration};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+
+// A price derived from a stream of websocket messages, e.g. a rolling VWAP of trades.
+// Messages for other coins or channels are ignored.
+pub trait ReferencePrice {
+    // Returns the updated price when the message changed it.
+    fn on_message(&mut self, message: &Message) -> Option<f64>;
+
+    fn value(&self) -> Option<f64>;
+}
+
+// Adapts the receiver of a subscription into a stream of reference prices, updated on every
+// message that changes them. The stream ends with the subscription.
+//
+//     let (sender, receiver) = unbounded_channel();
+//     info_client.subscribe(Subscription::Trades { coin: "ETH".to_string() }, sender).await?;
+//     let mut vwap = reference_prices(receiver, RollingVwap::new("ETH", Duration::from_secs(60)));
+pub fn reference_prices<R: ReferencePrice + Send + 'static>(
+    mut receiver: UnboundedReceiver<Message>,
+    mut calculator: R,
+) -> UnboundedReceiver<f64> {
+    let (sender, prices) = unbounded_channel();
+    tokio::spawn(async move {
+        while let Some(message) = receiver.recv().await {
+            if let Some(price) = calculator.on_message(&message) {
+                if sender.send(price).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+    prices
+}
+
+// The mid of `coin` in an AllMids or L2Book message. AllMids carries no time, so it's stamped
+// with the time it's read.
+fn mid(coin: &str, message: &Message) -> Option<(Timestamp, f64)> {
+    match message {
+        Message::AllMids(all_mids) => {
+            let mid = all_mids.data.mids.get(coin)?.parse().ok()?;
+            Some((Timestamp::now(), mid))
+        }
+        Message::L2Book(l2_book) if l2_book.data.coin == coin => {
+            let best = |side: usize| -> Option<f64> {
+                l2_book.data.levels.get(side)?.first()?.px.parse().ok()
+            };
+            Some((l2_book.data.time, (best(0)? + best(1)?) / 2.0))
+        }
+        _ => None,
+    }
+}
+
+// Volume weighted average price of the trades in the last `window`, measured from the latest
+// trade.
+pub struct RollingVwap {
+    coin: String,
+    window: Duration,
+    trades: VecDeque<(Timestamp, f64, f64)>,
+    notional: f64,
+    volume: f64,
+}
+
+impl RollingVwap {
+    pub fn new(coin: &str, window: Duration) -> RollingVwap {
+        RollingVwap {
+            coin: coin.to_string(),
+            window,
+            trades: VecDeque::new(),
+            notional: 0.0,
+            volume: 0.0,
+        }
+    }
+
+    pub fn push(&mut self, time: Timestamp, px: f64, sz: f64) -> Option<f64> {
+        self.trades.push_back((time, px, sz));
+        self.notional += px * sz;
+        self.volume += sz;
+        let cutoff = time.saturating_sub(self.window);
+        while let Some(&(time, px, sz)) = self.trades.front() {
+            if time >= cutoff {
+                break;
+            }
+            self.trades.pop_front();
+            self.notional -= px * sz;
+            self.volume -= sz;
+        }
+        self.value()
+    }
+}
+
+impl ReferencePrice for RollingVwap {
+    fn on_message(&mut self, message: &Message) -> Option<f64> {
+        let Message::Trades(trades) = message else {
+            return None;
+        };
+        let mut price = None;
+        for trade in trades.data.iter() {
+            if trade.coin != self.coin {
+                continue;
+            }
+            if let (Ok(px), Ok(sz)) = (trade.px.parse(), trade.sz.parse()) {
+                price = self.push(trade.time, px, sz);
+            }
+        }
+        price
+    }
+
+    fn value(&self) -> Option<f64> {
+        (self.volume > 0.0).then(|| self.notional / self.volume)
+    }
+}
+
+// Time weighted average of the mid over the last `window`: each mid is weighted by how long
+// it stood, up to the latest update.
+pub struct RollingTwap {
+    coin: String,
+    window: Duration,
+    mids: VecDeque<(Timestamp, f64)>,
+}
+
+impl RollingTwap {
+    pub fn new(coin: &str, window: Duration) -> RollingTwap {
+        RollingTwap {
+            coin: coin.to_string(),
+            window,
+            mids: VecDeque::new(),
+        }
+    }
+
+    pub fn push(&mut self, time: Timestamp, mid: f64) -> Option<f64> {
+        self.mids.push_back((time, mid));
+        // Keep the last mid set before the window, as it stood during its start
+        let cutoff = time.saturating_sub(self.window);
+        while self.mids.len() > 1 && self.mids[1].0 <= cutoff {
+            self.mids.pop_front();
+        }
+        self.value()
+    }
+}
+
+impl ReferencePrice for RollingTwap {
+    fn on_message(&mut self, message: &Message) -> Option<f64> {
+        let (time, mid) = mid(&self.coin, message)?;
+        self.push(time, mid)
+    }
+
+    fn value(&self) -> Option<f64> {
+        let &(end, last) = self.mids.back()?;
+        let start = end.saturating_sub(self.window);
+        let (mut weighted, mut total) = (0.0, 0.0);
+        for (&(from, mid), &(to, _)) in self.mids.iter().zip(self.mids.iter().skip(1)) {
+            let weight = to.duration_since(from.max(start)).as_secs_f64();
+            weighted += mid * weight;
+            total += weight;
+        }
+        // A single mid, or updates all at the same time
+        if total == 0.0 {
+            return Some(last);
+        }
+        Some(weighted / total)
+    }
+}
+
+// Exponential moving average of the mid, with each update weighted by `alpha`.
+pub struct MidEma {
+    coin: String,
+    alpha: f64,
+    value: Option<f64>,
+}
+
+impl MidEma {
+    pub fn new(coin: &str, alpha: f64) -> MidEma {
+        MidEma {
+            coin: coin.to_string(),
+            alpha,
+            value: None,
+        }
+    }
+
+    pub fn push(&mut self, mid: f64) -> f64 {
+        let value = match self.value {
+            Some(value) => value + self.alpha * (mid - value),
+            None => mid,
+        };
+        self.value = Some(value);
+        value
+    }
+}
+
+impl ReferencePrice for MidEma {
+    fn on_message(&mut self, message: &Message) -> Option<f64> {
+        let (_, mid) = mid(&self.coin, message)?;
+        Some(self.push(mid))
+    }
+
+    fn value(&self) -> Option<f64> {
+        self.value
+    }
+}
+
+// Median of the last `n` mids, robust to single-update spikes.
+pub struct MedianMid {
+    coin: String,
+    n: usize,
+    mids: VecDeque<f64>,
+}
+
+impl MedianMid {
+    pub fn new(coin: &str, n: usize) -> MedianMid {
+        MedianMid {
+            coin: coin.to_string(),
+            n: n.max(1),
+            mids: VecDeque::new(),
+        }
+    }
+
+    pub fn push(&mut self, mid: f64) -> Option<f64> {
+        if self.mids.len() == self.n {
+            self.mids.pop_front();
+        }
+        self.mids.push_back(mid);
+        self.value()
+    }
+}
+
+impl ReferencePrice for MedianMid {
+    fn on_message(&mut self, message: &Message) -> Option<f64> {
+        let (_, mid) = mid(&self.coin, message)?;
+        self.push(mid)
+    }
+
+    fn value(&self) -> Option<f64> {
+        let mut sorted: Vec<f64> = self.mids.iter().copied().collect();
+        sorted.sort_by(f64::total_cmp);
+        let middle = sorted.len() / 2;
+        match sorted.len() {
+            0 => None,
+            len if len % 2 == 1 => Some(sorted[middle]),
+            _ => Some((sorted[middle - 1] + sorted[middle]) / 2.0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AllMids, AllMidsData, Trade, Trades};
+    use std::sync::Arc;
+
+    fn at(secs: u64) -> Timestamp {
+        Timestamp::from_millis(secs * 1_000)
+    }
+
+    #[test]
+    fn test_rolling_prices() {
+        let mut vwap = RollingVwap::new("ETH", Duration::from_secs(10));
+        assert_eq!(vwap.push(at(0), 100.0, 1.0), Some(100.0));
+        assert_eq!(vwap.push(at(5), 110.0, 3.0), Some(107.5));
+        // The first trade falls out of the window
+        assert_eq!(vwap.push(at(12), 120.0, 1.0), Some(112.5));
+
+        let mut twap = RollingTwap::new("ETH", Duration::from_secs(10));
+        assert_eq!(twap.push(at(0), 100.0), Some(100.0));
+        assert_eq!(twap.push(at(6), 110.0), Some(100.0));
+        // 100 stood 2s and 110 stood 8s of the window ending at 14s
+        assert_eq!(twap.push(at(14), 130.0), Some(108.0));
+        assert_eq!(twap.push(at(30), 130.0), Some(130.0));
+
+        let mut ema = MidEma::new("ETH", 0.5);
+        assert_eq!(ema.push(100.0), 100.0);
+        assert_eq!(ema.push(110.0), 105.0);
+
+        let mut median = MedianMid::new("ETH", 3);
+        median.push(100.0);
+        assert_eq!(median.push(1000.0), Some(550.0));
+        median.push(101.0);
+        assert_eq!(median.push(102.0), Some(102.0));
+    }
+
+    #[tokio::test]
+    async fn test_reference_prices_stream() {
+        let (sender, receiver) = unbounded_channel();
+        let mut prices = reference_prices(receiver, RollingVwap::new("ETH", Duration::MAX));
+        let trade = |coin: &str, px: &str| Trade {
+            coin: coin.to_string(),
+            time: at(1),
+            tid: 0,
+            ..Trade::test_trade("B", px, "1.0", 0)
+        };
+        sender
+            .send(Message::AllMids(AllMids {
+                data: Arc::new(AllMidsData {
+                    mids: [("ETH".to_string(), "100.0".to_string())].into(),
+                    dex: None,
+                }),
+            }))
+            .unwrap();
+        sender
+            .send(Message::Trades(Trades {
+                data: Arc::new(vec![trade("BTC", "50000.0"), trade("ETH", "100.0")]),
+            }))
+            .unwrap();
+        sender
+            .send(Message::Trades(Trades {
+                data: Arc::new(vec![trade("ETH", "102.0")]),
+            }))
+            .unwrap();
+        drop(sender);
+        assert_eq!(prices.recv().await, Some(100.0));
+        assert_eq!(prices.recv().await, Some(101.0));
+        assert_eq!(prices.recv().await, None);
+    }
+}
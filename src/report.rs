@@ -0,0 +1,302 @@
+use crate::{
+    prelude::*, Error, InfoClient, LedgerUpdate, LedgerUpdateData, Timestamp, UserFillsResponse,
+    UserFundingResponse,
+};
+use ethers::types::H160;
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, HashSet},
+    future::Future,
+    io::Write,
+};
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct CoinAccrual {
+    pub coin: String,
+    pub fills: u64,
+    // Traded notional, in USD
+    pub volume: f64,
+    // Net of rebates, in USD
+    pub fees: f64,
+    pub builder_fees: f64,
+    pub funding_received: f64,
+    pub funding_paid: f64,
+    pub realized_pnl: f64,
+}
+
+impl CoinAccrual {
+    pub fn net_funding(&self) -> f64 {
+        self.funding_received - self.funding_paid
+    }
+
+    fn add(&mut self, other: &CoinAccrual) {
+        self.fills += other.fills;
+        self.volume += other.volume;
+        self.fees += other.fees;
+        self.builder_fees += other.builder_fees;
+        self.funding_received += other.funding_received;
+        self.funding_paid += other.funding_paid;
+        self.realized_pnl += other.realized_pnl;
+    }
+}
+
+// Fees, funding and volume of one account over [start, end), by coin, plus the account's
+// deposits, withdrawals and transfer fees, e.g. for month-end books:
+//
+//     let start = Timestamp::from(Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap());
+//     let end = Timestamp::from(Utc.with_ymd_and_hms(2024, 6, 1, 0, 0, 0).unwrap());
+//     let report = AccrualReport::fetch(&info_client, user, start, end).await?;
+//     report.write_csv(File::create("2024-05.csv")?)?;
+//
+// Fees charged in another token, like spot buys' fees in the bought token, are converted to
+// USD at the fill price.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccrualReport {
+    pub user: H160,
+    pub start: Timestamp,
+    pub end: Timestamp,
+    pub coins: Vec<CoinAccrual>,
+    pub total: CoinAccrual,
+    pub deposits: f64,
+    pub withdrawals: f64,
+    // Withdrawal and transfer fees
+    pub ledger_fees: f64,
+}
+
+impl AccrualReport {
+    // Pages through the account's fills, funding payments and ledger updates in the range.
+    pub async fn fetch(
+        info_client: &InfoClient,
+        user: H160,
+        start: Timestamp,
+        end: Timestamp,
+    ) -> Result<AccrualReport> {
+        let fills = fetch_all(
+            start,
+            |start| info_client.user_fills_by_time(user, start, Some(end)),
+            |fill: &UserFillsResponse| (fill.time, fill.tid.to_string()),
+        )
+        .await?;
+        let fundings = fetch_all(
+            start,
+            |start| info_client.user_funding(user, start, Some(end)),
+            |funding: &UserFundingResponse| {
+                (
+                    funding.time,
+                    format!("{}{}", funding.hash, funding.delta.coin),
+                )
+            },
+        )
+        .await?;
+        let ledger = fetch_all(
+            start,
+            |start| info_client.user_non_funding_ledger_updates(user, start, Some(end)),
+            |update: &LedgerUpdateData| (update.time, update.hash.clone()),
+        )
+        .await?;
+        Ok(AccrualReport::build(
+            user, start, end, &fills, &fundings, &ledger,
+        ))
+    }
+
+    // The report over records already fetched. Records outside [start, end) are ignored.
+    pub fn build(
+        user: H160,
+        start: Timestamp,
+        end: Timestamp,
+        fills: &[UserFillsResponse],
+        fundings: &[UserFundingResponse],
+        ledger: &[LedgerUpdateData],
+    ) -> AccrualReport {
+        let in_range = |time: Timestamp| start <= time && time < end;
+        let parse = |value: &str| value.parse::<f64>().unwrap_or_default();
+        let mut coins: BTreeMap<String, CoinAccrual> = BTreeMap::new();
+        for fill in fills.iter().filter(|fill| in_range(fill.time)) {
+            let px = parse(&fill.px);
+            let to_usd = match fill.fee_token.as_deref() {
+                None | Some("USDC") => 1.0,
+                Some(_) => px,
+            };
+            let accrual = coin_entry(&mut coins, &fill.coin);
+            accrual.fills += 1;
+            accrual.volume += px * parse(&fill.sz);
+            accrual.fees += parse(&fill.fee) * to_usd;
+            accrual.builder_fees += fill.builder_fee.as_deref().map_or(0.0, parse) * to_usd;
+            accrual.realized_pnl += parse(&fill.closed_pnl);
+        }
+        for funding in fundings.iter().filter(|funding| in_range(funding.time)) {
+            let usdc = parse(&funding.delta.usdc);
+            let accrual = coin_entry(&mut coins, &funding.delta.coin);
+            accrual.funding_received += usdc.max(0.0);
+            accrual.funding_paid += (-usdc).max(0.0);
+        }
+        let coins: Vec<CoinAccrual> = coins.into_values().collect();
+
+        let mut total = CoinAccrual {
+            coin: "TOTAL".to_string(),
+            ..CoinAccrual::default()
+        };
+        coins.iter().for_each(|accrual| total.add(accrual));
+
+        let (mut deposits, mut withdrawals, mut ledger_fees) = (0.0, 0.0, 0.0);
+        for update in ledger.iter().filter(|update| in_range(update.time)) {
+            match &update.delta {
+                LedgerUpdate::Deposit(deposit) => deposits += parse(&deposit.usdc),
+                LedgerUpdate::Withdraw(withdraw) => withdrawals += parse(&withdraw.usdc),
+                _ => {}
+            }
+            ledger_fees += update.delta.fee().map_or(0.0, parse);
+        }
+
+        AccrualReport {
+            user,
+            start,
+            end,
+            coins,
+            total,
+            deposits,
+            withdrawals,
+            ledger_fees,
+        }
+    }
+
+    // One row per coin and a TOTAL row. Ledger totals are only in the JSON report.
+    pub fn write_csv<W: Write>(&self, mut writer: W) -> Result<()> {
+        let write_err = |e: std::io::Error| Error::Export(e.to_string());
+        writeln!(
+            writer,
+            "coin,fills,volume,fees,builder_fees,funding_received,funding_paid,net_funding,\
+             realized_pnl"
+        )
+        .map_err(write_err)?;
+        for accrual in self.coins.iter().chain([&self.total]) {
+            writeln!(
+                writer,
+                "{},{},{:.2},{:.6},{:.6},{:.6},{:.6},{:.6},{:.6}",
+                accrual.coin,
+                accrual.fills,
+                accrual.volume,
+                accrual.fees,
+                accrual.builder_fees,
+                accrual.funding_received,
+                accrual.funding_paid,
+                accrual.net_funding(),
+                accrual.realized_pnl
+            )
+            .map_err(write_err)?;
+        }
+        writer.flush().map_err(write_err)
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self).map_err(|e| Error::JsonParse(e.to_string()))
+    }
+}
+
+fn coin_entry<'a>(coins: &'a mut BTreeMap<String, CoinAccrual>, coin: &str) -> &'a mut CoinAccrual {
+    coins
+        .entry(coin.to_string())
+        .or_insert_with(|| CoinAccrual {
+            coin: coin.to_string(),
+            ..CoinAccrual::default()
+        })
+}
+
+// Pages through a time-range endpoint returning records oldest first and capped per response:
+// each request starts at the last record's time, with records already seen skipped by `key`.
+async fn fetch_all<R, F, Fut, K>(start: Timestamp, mut fetch: F, key: K) -> Result<Vec<R>>
+where
+    F: FnMut(Timestamp) -> Fut,
+    Fut: Future<Output = Result<Vec<R>>>,
+    K: Fn(&R) -> (Timestamp, String),
+{
+    let mut records = Vec::new();
+    let mut seen = HashSet::new();
+    let mut start = start;
+    loop {
+        let page = fetch(start).await?;
+        let mut added = false;
+        for record in page {
+            let (time, id) = key(&record);
+            if seen.insert((time, id)) {
+                start = start.max(time);
+                records.push(record);
+                added = true;
+            }
+        }
+        if !added {
+            return Ok(records);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_accrual_report() {
+        let fills: Vec<UserFillsResponse> = serde_json::from_value(json!([
+            {"coin": "ETH", "side": "B", "px": "2000", "sz": "1", "time": 1_000, "hash": "0x1",
+             "startPosition": "0", "dir": "Open Long", "closedPnl": "0", "oid": 1,
+             "crossed": true, "fee": "0.9", "tid": 1, "feeToken": "USDC"},
+            {"coin": "ETH", "side": "A", "px": "2100", "sz": "1", "time": 2_000, "hash": "0x2",
+             "startPosition": "1", "dir": "Close Long", "closedPnl": "100", "oid": 2,
+             "crossed": false, "fee": "-0.2", "tid": 2, "feeToken": "USDC", "builderFee": "0.1"},
+            {"coin": "@107", "side": "B", "px": "10", "sz": "5", "time": 3_000, "hash": "0x3",
+             "startPosition": "0", "dir": "Buy", "closedPnl": "0", "oid": 3,
+             "crossed": true, "fee": "0.01", "tid": 3, "feeToken": "HYPE"},
+            {"coin": "ETH", "side": "B", "px": "2000", "sz": "1", "time": 9_000, "hash": "0x4",
+             "startPosition": "0", "dir": "Open Long", "closedPnl": "0", "oid": 4,
+             "crossed": true, "fee": "0.9", "tid": 4}
+        ]))
+        .unwrap();
+        let fundings: Vec<UserFundingResponse> = serde_json::from_value(json!([
+            {"time": 1_500, "hash": "0x0", "delta": {"type": "funding", "coin": "ETH",
+             "usdc": "-1.5", "szi": "1", "fundingRate": "0.0001", "nSamples": null}},
+            {"time": 2_500, "hash": "0x0", "delta": {"type": "funding", "coin": "BTC",
+             "usdc": "0.5", "szi": "-0.1", "fundingRate": "0.0001", "nSamples": null}}
+        ]))
+        .unwrap();
+        let ledger: Vec<LedgerUpdateData> = serde_json::from_value(json!([
+            {"time": 500, "hash": "0x5", "delta": {"type": "deposit", "usdc": "1000"}},
+            {"time": 4_000, "hash": "0x6",
+             "delta": {"type": "withdraw", "usdc": "200", "nonce": 1, "fee": "1"}}
+        ]))
+        .unwrap();
+
+        let user = H160::from_low_u64_be(1);
+        let report = AccrualReport::build(
+            user,
+            Timestamp::from_millis(0),
+            Timestamp::from_millis(5_000),
+            &fills,
+            &fundings,
+            &ledger,
+        );
+        let coins: Vec<_> = report.coins.iter().map(|c| c.coin.as_str()).collect();
+        assert_eq!(coins, vec!["@107", "BTC", "ETH"]);
+        let eth = &report.coins[2];
+        assert_eq!(eth.fills, 2);
+        assert_eq!(eth.volume, 4_100.0);
+        assert!((eth.fees - 0.7).abs() < 1e-9);
+        assert_eq!(eth.realized_pnl, 100.0);
+        assert_eq!(eth.net_funding(), -1.5);
+        // The spot fee is in HYPE, converted at the fill price
+        assert!((report.coins[0].fees - 0.1).abs() < 1e-9);
+        assert_eq!(report.total.funding_received, 0.5);
+        assert_eq!(report.total.fills, 3);
+        assert_eq!(
+            (report.deposits, report.withdrawals, report.ledger_fees),
+            (1000.0, 200.0, 1.0)
+        );
+
+        let mut csv = Vec::new();
+        report.write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(csv.lines().count(), 5);
+        assert!(csv.lines().last().unwrap().starts_with("TOTAL,3,4150.00,"));
+    }
+}
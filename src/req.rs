@@ -1,6 +1,7 @@
 use crate::{prelude::*, Error};
-use reqwest::{Client, Response};
+use reqwest::{header::HeaderMap, Client, Response};
 use serde::Deserialize;
+use std::time::Duration;
 
 #[derive(Deserialize, Debug)]
 struct ErrorData {
@@ -9,6 +10,60 @@ struct ErrorData {
     msg: String,
 }
 
+// Settings for a reqwest client meant to be shared by every InfoClient, ExchangeClient and
+// ExplorerClient of a process, so they reuse one connection pool. Build once and pass clones
+// of the client (it is reference counted) to each constructor. HTTPS connections negotiate
+// HTTP/2 through ALPN, so concurrent requests are multiplexed over one connection per host and
+// the `http2_*` keepalives apply; plain HTTP, as to a local node, stays on HTTP/1.1.
+#[derive(Debug, Clone)]
+pub struct HttpConfig {
+    pub pool_max_idle_per_host: usize,
+    pub pool_idle_timeout: Option<Duration>,
+    pub http2_keep_alive_interval: Option<Duration>,
+    pub http2_keep_alive_timeout: Duration,
+    pub tcp_nodelay: bool,
+    pub tcp_keepalive: Option<Duration>,
+    pub connect_timeout: Option<Duration>,
+    pub timeout: Option<Duration>,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        HttpConfig {
+            pool_max_idle_per_host: 8,
+            pool_idle_timeout: Some(Duration::from_secs(90)),
+            http2_keep_alive_interval: Some(Duration::from_secs(20)),
+            http2_keep_alive_timeout: Duration::from_secs(10),
+            tcp_nodelay: true,
+            tcp_keepalive: Some(Duration::from_secs(60)),
+            connect_timeout: Some(Duration::from_secs(5)),
+            timeout: None,
+        }
+    }
+}
+
+impl HttpConfig {
+    pub fn build(&self) -> Result<Client> {
+        let mut builder = Client::builder()
+            .pool_max_idle_per_host(self.pool_max_idle_per_host)
+            .pool_idle_timeout(self.pool_idle_timeout)
+            .http2_keep_alive_interval(self.http2_keep_alive_interval)
+            .http2_keep_alive_timeout(self.http2_keep_alive_timeout)
+            .http2_keep_alive_while_idle(self.http2_keep_alive_interval.is_some())
+            .tcp_nodelay(self.tcp_nodelay)
+            .tcp_keepalive(self.tcp_keepalive);
+        if let Some(connect_timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(connect_timeout);
+        }
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        builder
+            .build()
+            .map_err(|e| Error::GenericRequest(e.to_string()))
+    }
+}
+
 pub struct HttpClient {
     pub client: Client,
     pub base_url: String,
@@ -16,7 +71,7 @@ pub struct HttpClient {
 
 async fn parse_response(response: Response) -> Result<String> {
     let status_code = response.status().as_u16();
-    let headers = response.headers().clone();
+    let headers = Box::new(response.headers().clone());
     let text = response
         .text()
         .await
@@ -32,7 +87,7 @@ async fn parse_response(response: Response) -> Result<String> {
                 status_code,
                 error_code: Some(error_data.code),
                 error_message: error_data.msg,
-                headers,
+                headers: headers.clone(),
                 error_data: Some(error_data.data),
             },
             Err(err) => Error::ClientRequest {
@@ -54,6 +109,21 @@ async fn parse_response(response: Response) -> Result<String> {
 
 impl HttpClient {
     pub async fn post(&self, url_path: &'static str, data: String) -> Result<String> {
+        parse_response(self.execute(url_path, data).await?).await
+    }
+
+    // Also returns the response headers, which carry the server's time and request id.
+    pub async fn post_with_headers(
+        &self,
+        url_path: &'static str,
+        data: String,
+    ) -> Result<(String, HeaderMap)> {
+        let response = self.execute(url_path, data).await?;
+        let headers = response.headers().clone();
+        Ok((parse_response(response).await?, headers))
+    }
+
+    async fn execute(&self, url_path: &'static str, data: String) -> Result<Response> {
         let full_url = format!("{}{url_path}", self.base_url);
         let request = self
             .client
@@ -62,11 +132,88 @@ impl HttpClient {
             .body(data)
             .build()
             .map_err(|e| Error::GenericRequest(e.to_string()))?;
-        let result = self
-            .client
+        self.client
             .execute(request)
             .await
-            .map_err(|e| Error::GenericRequest(e.to_string()))?;
-        parse_response(result).await
+            .map_err(|e| Error::GenericRequest(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{sim::serve_rest, ExchangeClient, ExchangeResponseStatus, Meta, SimExchange};
+    use ethers::signers::LocalWallet;
+    use serde_json::{json, Value};
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn test_http_config_build() -> Result<()> {
+        let server = serve_rest(|request: Value| async move {
+            match request["type"].as_str() {
+                Some("slow") => {
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                    (200, request)
+                }
+                Some("allMids") => (200, json!({"ETH": "2000"})),
+                // A signed action posted to /exchange
+                _ => (
+                    200,
+                    json!({"status": "ok", "response": {"type": "default"}}),
+                ),
+            }
+        })
+        .await?;
+        let base_url = server.base_url.clone();
+
+        // An InfoClient and an ExchangeClient sharing one built client share its pool, so their
+        // requests all go over the one keep-alive connection
+        let client = HttpConfig::default().build()?;
+        let mut info_client = SimExchange::new().info_client();
+        info_client.http_client = HttpClient {
+            client: client.clone(),
+            base_url: base_url.clone(),
+        };
+        let exchange_client = ExchangeClient {
+            http_client: HttpClient {
+                client,
+                base_url: base_url.clone(),
+            },
+            wallet: LocalWallet::new(&mut rand::thread_rng()),
+            meta: Meta {
+                universe: Vec::new(),
+            },
+            vault_address: None,
+            coin_to_asset: HashMap::from([("ETH".to_string(), 1)]),
+            latency: Default::default(),
+            reduce_only_closes: None,
+            nonce_partition: Default::default(),
+        };
+        for _ in 0..3 {
+            assert_eq!(info_client.all_mids().await?["ETH"], "2000");
+            assert!(matches!(
+                exchange_client
+                    .update_leverage(5, "ETH", true, None)
+                    .await?,
+                ExchangeResponseStatus::Ok(_)
+            ));
+        }
+        assert_eq!(server.connections(), 1);
+
+        let http_client = HttpClient {
+            client: HttpConfig {
+                timeout: Some(Duration::from_millis(50)),
+                ..HttpConfig::default()
+            }
+            .build()?,
+            base_url,
+        };
+        assert!(matches!(
+            http_client
+                .post("/info", json!({"type": "slow"}).to_string())
+                .await,
+            Err(Error::GenericRequest(_))
+        ));
+        Ok(())
     }
 }
@@ -0,0 +1,392 @@
+use crate::{
+    prelude::*, ClientCancelRequest, ClientLimit, ClientOrder, ClientOrderRequest, Error,
+    ExchangeClient, ExchangeDataStatus, ExchangeResponseStatus, OpenOrdersResponse,
+    UserStateResponse,
+};
+use axum::{
+    extract::{Path, State},
+    http::{header::RETRY_AFTER, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{delete, get},
+    Json, Router,
+};
+use ethers::types::H160;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+use uuid::Uuid;
+
+// A client of the proxy. Read-only keys can list orders and positions but not trade.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct ApiKey {
+    pub key: String,
+    pub name: String,
+    pub requests_per_minute: u32,
+    #[serde(default)]
+    pub read_only: bool,
+}
+
+// A REST front for one account's order management, so internal tools can trade without ever
+// holding its signing key. Every request needs an `x-api-key` header naming one of `keys`,
+// and each key is rate limited on its own.
+//
+//   GET    /positions           clearinghouse state
+//   GET    /orders              open orders
+//   POST   /orders              place a limit order: {"coin": "ETH", "side": "buy",
+//                               "sz": 0.1, "px": 2000.0, "tif": "Alo", "reduce_only": false,
+//                               "cloid": "<uuid>"}; tif, reduce_only and cloid are optional
+//   DELETE /orders              cancel all open orders
+//   DELETE /orders/{coin}/{oid} cancel one order
+//
+// Errors are returned as {"error": "..."}: 401 for unknown keys, 403 for trades with a
+// read-only key, 429 with Retry-After once a key is over its limit, 422 when the exchange
+// rejects a request and 502 when it can't be reached.
+pub struct RestProxy {
+    exchange_client: Arc<ExchangeClient>,
+    keys: ApiKeys,
+}
+
+impl RestProxy {
+    pub fn new(exchange_client: Arc<ExchangeClient>, keys: Vec<ApiKey>) -> RestProxy {
+        RestProxy {
+            exchange_client,
+            keys: ApiKeys::new(keys),
+        }
+    }
+
+    // Resolves the account orders act on, then serves until the listener fails.
+    pub async fn serve(self, addr: SocketAddr) -> Result<()> {
+        let account = self.exchange_client.account_address(None).await?;
+        let listener = tokio::net::TcpListener::bind(addr)
+            .await
+            .map_err(|e| Error::Proxy(format!("binding {addr}: {e}")))?;
+        axum::serve(listener, self.router(account))
+            .await
+            .map_err(|e| Error::Proxy(e.to_string()))
+    }
+
+    pub fn router(self, account: H160) -> Router {
+        let state = Arc::new(ProxyState {
+            exchange_client: self.exchange_client,
+            account,
+            keys: self.keys,
+        });
+        Router::new()
+            .route("/positions", get(positions))
+            .route(
+                "/orders",
+                get(open_orders).post(place_order).delete(cancel_all),
+            )
+            .route("/orders/{coin}/{oid}", delete(cancel))
+            .with_state(state)
+    }
+}
+
+struct ProxyState {
+    exchange_client: Arc<ExchangeClient>,
+    account: H160,
+    keys: ApiKeys,
+}
+
+// API keys with a token bucket each, holding up to a minute's worth of requests.
+struct ApiKeys {
+    keys: HashMap<String, ApiKey>,
+    buckets: Mutex<HashMap<String, (f64, Instant)>>,
+}
+
+impl ApiKeys {
+    fn new(keys: Vec<ApiKey>) -> ApiKeys {
+        ApiKeys {
+            keys: keys.into_iter().map(|key| (key.key.clone(), key)).collect(),
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn authorize(
+        &self,
+        headers: &HeaderMap,
+        trade: bool,
+        now: Instant,
+    ) -> std::result::Result<(), ProxyError> {
+        let key = headers
+            .get("x-api-key")
+            .and_then(|key| key.to_str().ok())
+            .and_then(|key| self.keys.get(key))
+            .ok_or(ProxyError::Unauthorized)?;
+        if trade && key.read_only {
+            return Err(ProxyError::Forbidden(key.name.clone()));
+        }
+
+        let per_second = f64::from(key.requests_per_minute) / 60.0;
+        let capacity = f64::from(key.requests_per_minute);
+        let mut buckets = self.buckets.lock().unwrap();
+        let (tokens, updated) = buckets.entry(key.key.clone()).or_insert((capacity, now));
+        *tokens = (*tokens + now.duration_since(*updated).as_secs_f64() * per_second).min(capacity);
+        *updated = now;
+        if *tokens < 1.0 {
+            let retry_after = if per_second > 0.0 {
+                ((1.0 - *tokens) / per_second).ceil() as u64
+            } else {
+                60
+            };
+            return Err(ProxyError::RateLimited(retry_after));
+        }
+        *tokens -= 1.0;
+        Ok(())
+    }
+}
+
+enum ProxyError {
+    Unauthorized,
+    Forbidden(String),
+    // Seconds until the next request is allowed
+    RateLimited(u64),
+    BadRequest(String),
+    Rejected(String),
+    Upstream(Error),
+}
+
+impl IntoResponse for ProxyError {
+    fn into_response(self) -> Response {
+        let (status, message) = match self {
+            ProxyError::Unauthorized => (StatusCode::UNAUTHORIZED, "unknown API key".to_string()),
+            ProxyError::Forbidden(name) => (
+                StatusCode::FORBIDDEN,
+                format!("API key {name} is read-only"),
+            ),
+            ProxyError::RateLimited(retry_after) => {
+                let body = Json(ErrorBody {
+                    error: "rate limited".to_string(),
+                });
+                return (
+                    StatusCode::TOO_MANY_REQUESTS,
+                    [(RETRY_AFTER, retry_after.to_string())],
+                    body,
+                )
+                    .into_response();
+            }
+            ProxyError::BadRequest(message) => (StatusCode::BAD_REQUEST, message),
+            ProxyError::Rejected(message) => (StatusCode::UNPROCESSABLE_ENTITY, message),
+            ProxyError::Upstream(err) => (StatusCode::BAD_GATEWAY, err.to_string()),
+        };
+        (status, Json(ErrorBody { error: message })).into_response()
+    }
+}
+
+impl From<Error> for ProxyError {
+    fn from(err: Error) -> ProxyError {
+        ProxyError::Upstream(err)
+    }
+}
+
+#[derive(Serialize)]
+struct ErrorBody {
+    error: String,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum Side {
+    Buy,
+    Sell,
+}
+
+#[derive(Deserialize)]
+struct OrderBody {
+    coin: String,
+    side: Side,
+    sz: f64,
+    px: f64,
+    #[serde(default = "default_tif")]
+    tif: String,
+    #[serde(default)]
+    reduce_only: bool,
+    cloid: Option<String>,
+}
+
+fn default_tif() -> String {
+    "Gtc".to_string()
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(tag = "status", rename_all = "lowercase")]
+enum OrderResult {
+    Resting {
+        oid: u64,
+    },
+    Filled {
+        oid: u64,
+        total_sz: String,
+        avg_px: String,
+    },
+}
+
+#[derive(Serialize)]
+struct CancelAllResult {
+    canceled: Vec<u64>,
+    already_gone: Vec<u64>,
+    failed: Vec<(u64, String)>,
+}
+
+type ProxyResult<T> = std::result::Result<Json<T>, ProxyError>;
+
+async fn positions(
+    State(state): State<Arc<ProxyState>>,
+    headers: HeaderMap,
+) -> ProxyResult<UserStateResponse> {
+    state.keys.authorize(&headers, false, Instant::now())?;
+    let info_client = state.exchange_client.info_client();
+    Ok(Json(info_client.user_state(state.account).await?))
+}
+
+async fn open_orders(
+    State(state): State<Arc<ProxyState>>,
+    headers: HeaderMap,
+) -> ProxyResult<Vec<OpenOrdersResponse>> {
+    state.keys.authorize(&headers, false, Instant::now())?;
+    let info_client = state.exchange_client.info_client();
+    Ok(Json(info_client.open_orders(state.account).await?))
+}
+
+async fn place_order(
+    State(state): State<Arc<ProxyState>>,
+    headers: HeaderMap,
+    Json(body): Json<OrderBody>,
+) -> ProxyResult<OrderResult> {
+    state.keys.authorize(&headers, true, Instant::now())?;
+    if !matches!(body.tif.as_str(), "Gtc" | "Alo" | "Ioc") {
+        return Err(ProxyError::BadRequest(format!("unknown tif {}", body.tif)));
+    }
+    let cloid = body
+        .cloid
+        .as_deref()
+        .map(Uuid::parse_str)
+        .transpose()
+        .map_err(|e| ProxyError::BadRequest(format!("invalid cloid: {e}")))?;
+    let order = ClientOrderRequest {
+        asset: body.coin,
+        is_buy: body.side == Side::Buy,
+        reduce_only: body.reduce_only,
+        limit_px: body.px,
+        sz: body.sz,
+        cloid,
+        order_type: ClientOrder::Limit(ClientLimit { tif: body.tif }),
+    };
+    let response = state.exchange_client.order(order, None).await?;
+    match first_status(response)? {
+        ExchangeDataStatus::Resting(resting) => Ok(Json(OrderResult::Resting { oid: resting.oid })),
+        ExchangeDataStatus::Filled(filled) => Ok(Json(OrderResult::Filled {
+            oid: filled.oid,
+            total_sz: filled.total_sz,
+            avg_px: filled.avg_px,
+        })),
+        status => Err(ProxyError::Rejected(format!(
+            "unexpected status {status:?}"
+        ))),
+    }
+}
+
+async fn cancel(
+    State(state): State<Arc<ProxyState>>,
+    headers: HeaderMap,
+    Path((coin, oid)): Path<(String, u64)>,
+) -> std::result::Result<StatusCode, ProxyError> {
+    state.keys.authorize(&headers, true, Instant::now())?;
+    let response = state
+        .exchange_client
+        .cancel(ClientCancelRequest { asset: coin, oid }, None)
+        .await?;
+    first_status(response)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn cancel_all(
+    State(state): State<Arc<ProxyState>>,
+    headers: HeaderMap,
+) -> ProxyResult<CancelAllResult> {
+    state.keys.authorize(&headers, true, Instant::now())?;
+    let summary = state.exchange_client.cancel_all_orders(None, None).await?;
+    Ok(Json(CancelAllResult {
+        canceled: summary.canceled,
+        already_gone: summary.already_gone,
+        failed: summary.failed,
+    }))
+}
+
+// The status of a single order or cancel; rejections of the action or the order become
+// errors.
+fn first_status(
+    response: ExchangeResponseStatus,
+) -> std::result::Result<ExchangeDataStatus, ProxyError> {
+    match response {
+        ExchangeResponseStatus::Ok(response) => {
+            match response
+                .data
+                .and_then(|data| data.statuses.into_iter().next())
+            {
+                Some(ExchangeDataStatus::Error(message)) => Err(ProxyError::Rejected(message)),
+                Some(status) => Ok(status),
+                None => Err(ProxyError::Rejected("missing status".to_string())),
+            }
+        }
+        ExchangeResponseStatus::Err(err) => Err(ProxyError::Rejected(err.message)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_api_keys() {
+        let keys = ApiKeys::new(vec![
+            ApiKey {
+                key: "trader-key".to_string(),
+                name: "trader".to_string(),
+                requests_per_minute: 2,
+                read_only: false,
+            },
+            ApiKey {
+                key: "viewer-key".to_string(),
+                name: "viewer".to_string(),
+                requests_per_minute: 60,
+                read_only: true,
+            },
+        ]);
+        let headers = |key: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert("x-api-key", key.parse().unwrap());
+            headers
+        };
+        let now = Instant::now();
+
+        assert!(matches!(
+            keys.authorize(&HeaderMap::new(), false, now),
+            Err(ProxyError::Unauthorized)
+        ));
+        assert!(matches!(
+            keys.authorize(&headers("wrong"), false, now),
+            Err(ProxyError::Unauthorized)
+        ));
+        assert!(matches!(
+            keys.authorize(&headers("viewer-key"), true, now),
+            Err(ProxyError::Forbidden(_))
+        ));
+        assert!(keys.authorize(&headers("viewer-key"), false, now).is_ok());
+
+        // Two requests a minute: the third is limited until 30s have refilled a token
+        assert!(keys.authorize(&headers("trader-key"), true, now).is_ok());
+        assert!(keys.authorize(&headers("trader-key"), true, now).is_ok());
+        assert!(matches!(
+            keys.authorize(&headers("trader-key"), true, now),
+            Err(ProxyError::RateLimited(30))
+        ));
+        let later = now + Duration::from_secs(30);
+        assert!(keys.authorize(&headers("trader-key"), true, later).is_ok());
+    }
+}
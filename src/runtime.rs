@@ -0,0 +1,150 @@
+use crate::{prelude::*, Error};
+use std::thread;
+use tokio::{
+    runtime::{Builder, Handle},
+    sync::oneshot,
+};
+
+// A current-thread tokio runtime on a thread of its own, optionally pinned to a CPU core (with
+// the `pinning` feature), for keeping the websocket core off latency-critical strategy threads:
+//
+//     let ws_runtime = PinnedRuntime::start(Some(3))?;
+//     let mut info_client = InfoClient::new(None, None)
+//         .await?
+//         .with_ws_runtime(ws_runtime.handle());
+//
+// The client's connections are then opened, read, decoded and fanned out to subscribers on
+// that thread; the strategy only receives the messages. Each `PinnedRuntime` is one thread, so
+// thread-per-core deployments start one per core and give each client its own.
+//
+// The runtime is tokio's because the websocket client is: tokio-tungstenite's sockets only run
+// on a tokio reactor, so there is no async-std option.
+//
+// Dropping it stops the thread, and with it the connections of the clients using it, so close
+// those clients first.
+pub struct PinnedRuntime {
+    handle: Handle,
+    stop: Option<oneshot::Sender<()>>,
+}
+
+impl PinnedRuntime {
+    pub fn start(core: Option<usize>) -> Result<PinnedRuntime> {
+        #[cfg(not(feature = "pinning"))]
+        if core.is_some() {
+            return Err(Error::Runtime(
+                "pinning a runtime to a core needs the `pinning` feature".to_string(),
+            ));
+        }
+
+        let runtime = Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| Error::Runtime(e.to_string()))?;
+        let handle = runtime.handle().clone();
+        let (stop, stopped) = oneshot::channel();
+        let (started, pinned) = std::sync::mpsc::channel();
+        let name = match core {
+            Some(core) => format!("hl-ws-core-{core}"),
+            None => "hl-ws".to_string(),
+        };
+        thread::Builder::new()
+            .name(name)
+            .spawn(move || {
+                let _ = started.send(PinnedRuntime::pin(core));
+                // Receives an error, rather than (), once the `PinnedRuntime` is dropped
+                let _ = runtime.block_on(stopped);
+            })
+            .map_err(|e| Error::Runtime(e.to_string()))?;
+        pinned.recv().map_err(|e| Error::Runtime(e.to_string()))??;
+
+        Ok(PinnedRuntime {
+            handle,
+            stop: Some(stop),
+        })
+    }
+
+    // Pins the calling thread to `core`, if any.
+    #[cfg(feature = "pinning")]
+    fn pin(core: Option<usize>) -> Result<()> {
+        let Some(core) = core else {
+            return Ok(());
+        };
+        let available = core_affinity::get_core_ids().unwrap_or_default();
+        match available.into_iter().find(|core_id| core_id.id == core) {
+            Some(core_id) if core_affinity::set_for_current(core_id) => Ok(()),
+            Some(_) => Err(Error::Runtime(format!(
+                "couldn't pin the runtime to core {core}"
+            ))),
+            None => Err(Error::Runtime(format!("no core {core} to pin to"))),
+        }
+    }
+
+    #[cfg(not(feature = "pinning"))]
+    fn pin(_core: Option<usize>) -> Result<()> {
+        Ok(())
+    }
+
+    // For `InfoClient::with_ws_runtime`, or to spawn other tasks next to the client's.
+    pub fn handle(&self) -> Handle {
+        self.handle.clone()
+    }
+}
+
+impl Drop for PinnedRuntime {
+    fn drop(&mut self) {
+        if let Some(stop) = self.stop.take() {
+            let _ = stop.send(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{sim::SimExchange, Message, Subscription};
+    use serde_json::json;
+    use std::{sync::mpsc, time::Duration};
+    use tokio::sync::mpsc::unbounded_channel;
+
+    #[tokio::test]
+    async fn test_ws_core_runs_on_the_given_runtime() {
+        let runtime = PinnedRuntime::start(None).unwrap();
+        let mut exchange = SimExchange::new();
+        let mut info_client = exchange.info_client().with_ws_runtime(runtime.handle());
+        let (sender, mut receiver) = unbounded_channel();
+        info_client
+            .subscribe(Subscription::trades("ETH"), sender)
+            .await
+            .unwrap();
+        let mut connection = exchange.accept().await;
+        assert_eq!(
+            connection.expect_subscribe().await,
+            Subscription::trades("ETH")
+        );
+        connection
+            .send(
+                "trades",
+                json!([{"coin": "ETH", "side": "B", "px": "2000.1", "sz": "0.5", "time": 1,
+                    "hash": "0x0", "tid": 7}]),
+            )
+            .await
+            .unwrap();
+
+        // The test's runtime is blocked until the trade arrives, so only the pinned runtime
+        // can have read and delivered it
+        let (forward, forwarded) = mpsc::channel();
+        thread::spawn(move || forward.send(receiver.blocking_recv()));
+        let message = forwarded.recv_timeout(Duration::from_secs(5)).unwrap();
+        let Some(Message::Trades(trades)) = message else {
+            panic!("expected trades, got {message:?}");
+        };
+        assert_eq!(trades.data[0].tid, 7);
+        info_client.close_ws().await.unwrap();
+    }
+
+    #[cfg(not(feature = "pinning"))]
+    #[test]
+    fn test_pinning_needs_the_feature() {
+        assert!(PinnedRuntime::start(Some(0)).is_err());
+    }
+}
@@ -0,0 +1,147 @@
+use crate::{
+    prelude::*, ClientCancelRequestCloid, ClientOrderRequest, Error, ExchangeClient,
+    ExchangeResponseStatus, OrderId, OrderStatusData, OrderStatusResponse, Timestamp,
+};
+use ethers::signers::LocalWallet;
+use log::debug;
+use std::{sync::Arc, time::Duration};
+use tokio::time::{self, Instant};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+// Margin for the exchange's clock running behind ours when deciding a submission expired
+const CLOCK_SKEW: Duration = Duration::from_secs(1);
+
+// Places orders without ever leaving their state unknown. Each order is signed to expire when
+// its ack is due, so a submission that's still in flight once the ack timed out can't land
+// later. Without an ack, the order is canceled by its cloid (required) and polled until it's
+// known to have never been placed or to be filled, canceled or rejected.
+//
+//     let submitter = SafeSubmitter::new(exchange_client.clone(), Duration::from_secs(2));
+//     match submitter.submit(order, None).await? {
+//         SafeOutcome::Acked(response) => { ... }
+//         SafeOutcome::NotPlaced => { /* safe to resubmit */ }
+//         SafeOutcome::Settled(order) => { /* account for any fills */ }
+//     }
+pub struct SafeSubmitter {
+    client: Arc<ExchangeClient>,
+    ack_timeout: Duration,
+    settle_timeout: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub enum SafeOutcome {
+    // The exchange answered the submission in time
+    Acked(ExchangeResponseStatus),
+    // No answer in time, and the order never reached the exchange
+    NotPlaced,
+    // No answer in time, but the order reached the exchange and is now in a final state, e.g.
+    // filled, or canceled after a partial fill
+    Settled(Box<OrderStatusData>),
+}
+
+impl SafeSubmitter {
+    pub fn new(client: Arc<ExchangeClient>, ack_timeout: Duration) -> SafeSubmitter {
+        SafeSubmitter {
+            client,
+            ack_timeout,
+            settle_timeout: Duration::from_secs(10),
+        }
+    }
+
+    // How long to keep canceling and polling after a missed ack before giving up with
+    // `Error::OrderUnresolved`. Defaults to 10 seconds.
+    pub fn settle_timeout(mut self, settle_timeout: Duration) -> SafeSubmitter {
+        self.settle_timeout = settle_timeout;
+        self
+    }
+
+    pub async fn submit(
+        &self,
+        order: ClientOrderRequest,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<SafeOutcome> {
+        let cloid = order.cloid.ok_or(Error::NoCloid)?;
+        let coin = order.asset.clone();
+        let expires_after = Timestamp::now() + self.ack_timeout;
+        let submission = self
+            .client
+            .bulk_order_expiring(vec![order], Some(expires_after), wallet);
+        match time::timeout(self.ack_timeout, submission).await {
+            Ok(Ok(response)) => return Ok(SafeOutcome::Acked(response)),
+            Ok(Err(err @ (Error::GenericRequest(_) | Error::ServerRequest { .. }))) => {
+                debug!("Submitting {cloid} failed, settling it: {err}")
+            }
+            Ok(Err(err)) => return Err(err),
+            Err(_) => debug!("No ack for {cloid} within {:?}", self.ack_timeout),
+        }
+
+        let user = self.client.account_address(wallet).await?;
+        let info = self.client.info_client();
+        let deadline = Instant::now() + self.settle_timeout;
+        loop {
+            // Also cancels orders landing between polls, until the submission expired
+            let cancel = ClientCancelRequestCloid {
+                asset: coin.clone(),
+                cloid,
+            };
+            if let Err(err) = self.client.cancel_by_cloid(cancel, wallet).await {
+                debug!("Canceling {cloid} failed: {err}");
+            }
+            match info.order_status(user, OrderId::Cloid(cloid)).await {
+                Ok(status) => {
+                    let expired = Timestamp::now() > expires_after + CLOCK_SKEW;
+                    if let Some(outcome) = resolve(status, expired) {
+                        return Ok(outcome);
+                    }
+                }
+                Err(err) => debug!("Order status lookup for {cloid} failed: {err}"),
+            }
+            if Instant::now() >= deadline {
+                return Err(Error::OrderUnresolved(cloid.to_string()));
+            }
+            time::sleep(POLL_INTERVAL).await;
+        }
+    }
+}
+
+// None while the order is live, or unknown but its submission could still land.
+fn resolve(status: OrderStatusResponse, expired: bool) -> Option<SafeOutcome> {
+    match status {
+        OrderStatusResponse::Order { order } => match order.status.as_str() {
+            "open" | "triggered" => None,
+            _ => Some(SafeOutcome::Settled(order)),
+        },
+        OrderStatusResponse::UnknownOid => expired.then_some(SafeOutcome::NotPlaced),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order_status(status: &str) -> OrderStatusResponse {
+        serde_json::from_str(&format!(
+            r#"{{"status":"order","order":{{"order":{{"coin":"ETH","side":"B","limitPx":"3000.0",
+            "sz":"0.05","origSz":"0.1","oid":7,"timestamp":1718000000000,"orderType":"Limit",
+            "tif":"Gtc","reduceOnly":false,"isTrigger":false,"triggerPx":"0.0",
+            "triggerCondition":"N/A","isPositionTpsl":false,
+            "cloid":"0x00000000000000000000000000000001","children":[]}},"status":"{status}",
+            "statusTimestamp":1718000000100}}}}"#
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_resolve() {
+        assert!(resolve(order_status("open"), true).is_none());
+        assert!(matches!(
+            resolve(order_status("canceled"), false),
+            Some(SafeOutcome::Settled(order)) if order.order.sz == "0.05"
+        ));
+        assert!(resolve(OrderStatusResponse::UnknownOid, false).is_none());
+        assert!(matches!(
+            resolve(OrderStatusResponse::UnknownOid, true),
+            Some(SafeOutcome::NotPlaced)
+        ));
+    }
+}
@@ -0,0 +1,135 @@
+use crate::{prelude::*, Error};
+use ethers::signers::LocalWallet;
+use serde::Deserialize;
+use std::{path::PathBuf, process::Command};
+
+// Where the signing key comes from, so it never has to appear in code or config files.
+// Deserializes from e.g. `{ source = "keystore", path = "key.json", password_env = "PW" }`.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "source", rename_all = "snake_case", deny_unknown_fields)]
+pub enum KeySource {
+    // A hex private key in an environment variable
+    Env {
+        var: String,
+    },
+    // A web3 JSON keystore, decrypted with the password in an environment variable
+    Keystore {
+        path: PathBuf,
+        password_env: String,
+    },
+    // A command printing the hex private key, e.g. a password manager or cloud secrets CLI
+    Command {
+        program: String,
+        #[serde(default)]
+        args: Vec<String>,
+    },
+}
+
+impl Default for KeySource {
+    fn default() -> Self {
+        KeySource::Env {
+            var: "HL_PRIVATE_KEY".to_string(),
+        }
+    }
+}
+
+impl KeySource {
+    pub fn load(&self) -> Result<LocalWallet> {
+        match self {
+            KeySource::Env { var } => {
+                let private_key = std::env::var(var)
+                    .map_err(|_| Error::PrivateKeyParse(format!("{var} is not set")))?;
+                parse_private_key(&private_key)
+            }
+            KeySource::Keystore { path, password_env } => {
+                let password = std::env::var(password_env)
+                    .map_err(|_| Error::PrivateKeyParse(format!("{password_env} is not set")))?;
+                LocalWallet::decrypt_keystore(path, password).map_err(|e| {
+                    Error::PrivateKeyParse(format!("decrypting {}: {e}", path.display()))
+                })
+            }
+            KeySource::Command { program, args } => {
+                let output = Command::new(program)
+                    .args(args)
+                    .output()
+                    .map_err(|e| Error::PrivateKeyParse(format!("running {program}: {e}")))?;
+                if !output.status.success() {
+                    return Err(Error::PrivateKeyParse(format!(
+                        "{program} exited with {}",
+                        output.status
+                    )));
+                }
+                let private_key = String::from_utf8(output.stdout)
+                    .map_err(|e| Error::PrivateKeyParse(e.to_string()))?;
+                parse_private_key(&private_key)
+            }
+        }
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        let empty = match self {
+            KeySource::Env { var } => var.is_empty(),
+            KeySource::Keystore { password_env, .. } => password_env.is_empty(),
+            KeySource::Command { program, .. } => program.is_empty(),
+        };
+        if empty {
+            return Err(Error::PrivateKeyParse(format!(
+                "incomplete key source {self:?}"
+            )));
+        }
+        Ok(())
+    }
+}
+
+// Errors don't include the key, in case it was malformed rather than wrong.
+fn parse_private_key(private_key: &str) -> Result<LocalWallet> {
+    private_key
+        .trim()
+        .parse()
+        .map_err(|_| Error::PrivateKeyParse("malformed private key".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::signers::Signer;
+
+    const KEY: &str = "e908f86dbb4d55ac876378565aafeabc187f6690f046459397b17d9b9a19688e";
+
+    #[test]
+    fn test_key_sources() -> Result<()> {
+        let expected = parse_private_key(KEY)?.address();
+
+        let command = KeySource::Command {
+            program: "echo".to_string(),
+            args: vec![KEY.to_string()],
+        };
+        assert_eq!(command.load()?.address(), expected);
+        let failing = KeySource::Command {
+            program: "false".to_string(),
+            args: Vec::new(),
+        };
+        assert!(failing.load().is_err());
+
+        let dir = std::env::temp_dir().join(format!("hl-keystore-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        LocalWallet::encrypt_keystore(
+            &dir,
+            &mut rand::thread_rng(),
+            hex::decode(KEY).unwrap(),
+            "hunter2",
+            Some("key.json"),
+        )
+        .unwrap();
+        let keystore = KeySource::Keystore {
+            path: dir.join("key.json"),
+            password_env: "HL_TEST_KEYSTORE_PASSWORD".to_string(),
+        };
+        std::env::set_var("HL_TEST_KEYSTORE_PASSWORD", "hunter2");
+        assert_eq!(keystore.load()?.address(), expected);
+        std::env::set_var("HL_TEST_KEYSTORE_PASSWORD", "wrong");
+        assert!(keystore.load().is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+        Ok(())
+    }
+}
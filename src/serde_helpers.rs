@@ -0,0 +1,402 @@
+use serde::{de, Deserialize, Deserializer};
+use std::{fmt::Display, str::FromStr};
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum NumberOrString {
+    Number(serde_json::Number),
+    String(String),
+}
+
+// Accepts either a JSON number or a string and keeps it as its decimal string. With the
+// `arbitrary-precision` feature the original digits are preserved exactly, otherwise
+// non-integer numbers round-trip through f64.
+pub(crate) fn number_or_string<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match NumberOrString::deserialize(deserializer)? {
+        NumberOrString::Number(number) => Ok(number.to_string()),
+        NumberOrString::String(string) => {
+            if string.parse::<f64>().is_err() {
+                return Err(de::Error::custom(format!("invalid number {string}")));
+            }
+            Ok(string)
+        }
+    }
+}
+
+// `number_or_string` for optional fields, which also need `#[serde(default)]` to be omittable
+pub(crate) fn option_number_or_string<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    struct Decimal(#[serde(deserialize_with = "number_or_string")] String);
+
+    Ok(Option::<Decimal>::deserialize(deserializer)?.map(|Decimal(decimal)| decimal))
+}
+
+// Accepts an integer as a JSON number or a string, rejecting fractions and values out of the
+// target type's range instead of truncating them.
+pub(crate) fn integer_or_string<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: Display,
+{
+    let integer = number_or_string(deserializer)?;
+    integer
+        .parse()
+        .map_err(|e| de::Error::custom(format!("invalid integer {integer}: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        info::{
+            CandlesSnapshotResponse, FrontendOpenOrdersResponse, FundingHistoryResponse, Level,
+            MarginSummary, OpenOrdersResponse, PositionData, RecentTradesResponse,
+            UserFillsResponse, UserFundingDelta, UserRateLimitResponse, UserStateResponse,
+        },
+        BasicOrder, BookLevel, CandleData, LedgerUpdate, Liquidation, Message, OrderUpdate, Trade,
+        TradeInfo, UserFunding,
+    };
+    use serde::{de::DeserializeOwned, Serialize};
+    use serde_json::{json, Value};
+
+    // Decodes `payload` with each field, a JSON pointer, set to boundary decimals sent both as
+    // numbers and as strings, and checks the field keeps the exact digits.
+    fn assert_decimal_fields<T: DeserializeOwned + Serialize>(payload: Value, fields: &[&str]) {
+        for field in fields {
+            for (value, expected) in [
+                (json!(0), "0"),
+                (json!("0.0"), "0.0"),
+                (json!(-12345.678901), "-12345.678901"),
+                (json!("-0.00000001"), "-0.00000001"),
+                (json!(u64::MAX), "18446744073709551615"),
+                (
+                    json!("123456789012345678901234567890.123456789"),
+                    "123456789012345678901234567890.123456789",
+                ),
+            ] {
+                let mut payload = payload.clone();
+                *payload.pointer_mut(field).unwrap() = value.clone();
+                let decoded: T = serde_json::from_value(payload)
+                    .unwrap_or_else(|e| panic!("{field} = {value}: {e}"));
+                let encoded = serde_json::to_value(decoded).unwrap();
+                assert_eq!(
+                    encoded.pointer(field),
+                    Some(&json!(expected)),
+                    "{field} = {value}"
+                );
+            }
+            let mut payload = payload.clone();
+            *payload.pointer_mut(field).unwrap() = json!("abc");
+            assert!(serde_json::from_value::<T>(payload).is_err(), "{field}");
+        }
+    }
+
+    // Like `assert_decimal_fields` for integer fields of at most `max`, which must reject
+    // fractions and values past the maximum.
+    fn assert_integer_fields<T: DeserializeOwned + Serialize>(
+        payload: Value,
+        fields: &[&str],
+        max: u64,
+    ) {
+        for field in fields {
+            for value in [json!(0), json!(max), json!(max.to_string())] {
+                let mut payload = payload.clone();
+                *payload.pointer_mut(field).unwrap() = value.clone();
+                let decoded: T = serde_json::from_value(payload)
+                    .unwrap_or_else(|e| panic!("{field} = {value}: {e}"));
+                let encoded = serde_json::to_value(decoded).unwrap();
+                let expected = if value == json!(0) {
+                    json!(0)
+                } else {
+                    json!(max)
+                };
+                assert_eq!(encoded.pointer(field), Some(&expected), "{field} = {value}");
+            }
+            for value in [
+                json!(1.5),
+                json!(-1),
+                json!((u128::from(max) + 1).to_string()),
+            ] {
+                let mut payload = payload.clone();
+                *payload.pointer_mut(field).unwrap() = value.clone();
+                assert!(
+                    serde_json::from_value::<T>(payload).is_err(),
+                    "{field} = {value}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_ws_numeric_fields() {
+        assert_decimal_fields::<Trade>(
+            json!({"coin": "ETH", "side": "B", "px": "3000", "sz": "1", "time": 1718000000000u64,
+                "hash": "0x0", "tid": 1}),
+            &["/px", "/sz"],
+        );
+        let level = json!({"px": "3000", "sz": "1", "n": 1});
+        assert_decimal_fields::<BookLevel>(level.clone(), &["/px", "/sz"]);
+        assert_integer_fields::<BookLevel>(level, &["/n"], u64::MAX);
+
+        let fill = json!({"coin": "ETH", "side": "B", "px": "3000", "sz": "1",
+            "time": 1718000000000u64, "hash": "0x0", "startPosition": "0", "dir": "Open Long",
+            "closedPnl": "0", "oid": 1, "cloid": null, "crossed": true, "fee": "0.1", "tid": 1,
+            "feeToken": "USDC", "builderFee": "0.01",
+            "liquidation": {"liquidatedUser": null, "markPx": "3000", "method": "market"}});
+        let fields = [
+            "/px",
+            "/sz",
+            "/startPosition",
+            "/closedPnl",
+            "/fee",
+            "/builderFee",
+            "/liquidation/markPx",
+        ];
+        assert_decimal_fields::<TradeInfo>(fill.clone(), &fields);
+        assert_decimal_fields::<UserFillsResponse>(fill.clone(), &fields);
+        // The builder fee is only present on fills through a builder
+        let mut null_fee = fill.clone();
+        null_fee["builderFee"] = json!(null);
+        let mut no_fee = fill;
+        no_fee.as_object_mut().unwrap().remove("builderFee");
+        for fill in [null_fee, no_fee] {
+            let decoded: TradeInfo = serde_json::from_value(fill).unwrap();
+            assert_eq!(decoded.builder_fee, None);
+        }
+
+        assert_decimal_fields::<Liquidation>(
+            json!({"lid": 1, "liquidator": "0x0000000000000000000000000000000000000001",
+                "liquidated_user": "0x0000000000000000000000000000000000000002",
+                "liquidated_ntl_pos": "1000", "liquidated_account_value": "10"}),
+            &["/liquidated_ntl_pos", "/liquidated_account_value"],
+        );
+        let candle = json!({"t": 1718000000000u64, "T": 1718000059999u64, "s": "ETH", "i": "1m",
+            "o": "1", "c": "1", "h": "1", "l": "1", "v": "1", "n": 1});
+        assert_decimal_fields::<CandleData>(candle.clone(), &["/o", "/c", "/h", "/l", "/v"]);
+        assert_integer_fields::<CandleData>(candle.clone(), &["/n"], u64::MAX);
+        assert_decimal_fields::<CandlesSnapshotResponse>(
+            candle.clone(),
+            &["/o", "/c", "/h", "/l", "/v"],
+        );
+        assert_integer_fields::<CandlesSnapshotResponse>(candle, &["/n"], u64::MAX);
+
+        let order = json!({"coin": "ETH", "side": "B", "limitPx": "3000", "sz": "1", "oid": 1,
+            "timestamp": 1718000000000u64, "origSz": "1", "cloid": null});
+        assert_decimal_fields::<BasicOrder>(order.clone(), &["/limitPx", "/sz", "/origSz"]);
+        assert_decimal_fields::<OpenOrdersResponse>(order, &["/limitPx", "/sz"]);
+        assert_decimal_fields::<FrontendOpenOrdersResponse>(
+            json!({"coin": "ETH", "side": "A", "limitPx": "3500", "sz": "0.5", "origSz": "1",
+                "oid": 1, "timestamp": 1718000000000u64, "orderType": "Limit", "tif": "Gtc",
+                "reduceOnly": false, "isTrigger": false, "triggerPx": "0", "triggerCondition": "N/A",
+                "isPositionTpsl": false, "cloid": null}),
+            &["/limitPx", "/sz", "/origSz", "/triggerPx"],
+        );
+        assert_decimal_fields::<RecentTradesResponse>(
+            json!({"coin": "ETH", "side": "B", "px": "3000", "sz": "1",
+                "time": 1718000000000u64, "hash": "0x0"}),
+            &["/px", "/sz"],
+        );
+    }
+
+    #[test]
+    fn test_funding_and_ledger_numeric_fields() {
+        let funding = json!({"time": 1718000000000u64, "coin": "ETH", "usdc": "-1.5",
+            "szi": "10", "fundingRate": "0.0000125"});
+        assert_decimal_fields::<UserFunding>(funding.clone(), &["/usdc", "/szi", "/fundingRate"]);
+        assert_decimal_fields::<UserFundingDelta>(funding, &["/usdc", "/szi", "/fundingRate"]);
+        assert_decimal_fields::<FundingHistoryResponse>(
+            json!({"coin": "ETH", "fundingRate": "0.0000125", "premium": "0.0001",
+                "time": 1718000000000u64}),
+            &["/fundingRate", "/premium"],
+        );
+
+        let user = "0x0000000000000000000000000000000000000001";
+        for (delta, fields) in [
+            (json!({"type": "deposit", "usdc": "1"}), &["/usdc"][..]),
+            (
+                json!({"type": "withdraw", "usdc": "1", "nonce": 1, "fee": "1"}),
+                &["/usdc", "/fee"],
+            ),
+            (
+                json!({"type": "internalTransfer", "usdc": "1", "user": user,
+                    "destination": user, "fee": "1"}),
+                &["/usdc", "/fee"],
+            ),
+            (
+                json!({"type": "subAccountTransfer", "usdc": "1", "user": user,
+                    "destination": user}),
+                &["/usdc"],
+            ),
+            (
+                json!({"type": "ledgerLiquidation", "accountValue": "1", "leverageType": "Cross",
+                    "liquidatedPositions": [{"coin": "ETH", "szi": "-1.5"}]}),
+                &["/accountValue", "/liquidatedPositions/0/szi"],
+            ),
+            (
+                json!({"type": "vaultDeposit", "vault": user, "usdc": "1"}),
+                &["/usdc"],
+            ),
+            (
+                json!({"type": "vaultWithdraw", "vault": user, "user": user,
+                    "requestedUsd": "1", "commission": "1", "closingCost": "1", "basis": "1",
+                    "netWithdrawnUsd": "1"}),
+                &[
+                    "/requestedUsd",
+                    "/commission",
+                    "/closingCost",
+                    "/basis",
+                    "/netWithdrawnUsd",
+                ],
+            ),
+            (
+                json!({"type": "vaultLeaderCommission", "user": user, "usdc": "1"}),
+                &["/usdc"],
+            ),
+            (
+                json!({"type": "accountClassTransfer", "usdc": "1", "toPerp": true}),
+                &["/usdc"],
+            ),
+            (
+                json!({"type": "spotTransfer", "token": "PURR", "amount": "1", "usdcValue": "1",
+                    "user": user, "destination": user, "fee": "1"}),
+                &["/amount", "/usdcValue", "/fee"],
+            ),
+            (
+                json!({"type": "spotGenesis", "token": "PURR", "amount": "1"}),
+                &["/amount"],
+            ),
+        ] {
+            assert_decimal_fields::<LedgerUpdate>(delta, fields);
+        }
+    }
+
+    #[test]
+    fn test_user_state_numeric_fields() {
+        let position = json!({"coin": "ETH", "entryPx": "3000", "leverage": {"type": "isolated",
+            "value": 20, "rawUsd": "-1000"}, "liquidationPx": "2900", "marginUsed": "100",
+            "positionValue": "2000", "returnOnEquity": "0.1", "szi": "1",
+            "unrealizedPnl": "10"});
+        assert_decimal_fields::<PositionData>(
+            position.clone(),
+            &[
+                "/entryPx",
+                "/leverage/rawUsd",
+                "/liquidationPx",
+                "/marginUsed",
+                "/positionValue",
+                "/returnOnEquity",
+                "/szi",
+                "/unrealizedPnl",
+            ],
+        );
+        assert_integer_fields::<PositionData>(
+            position.clone(),
+            &["/leverage/value"],
+            u32::MAX.into(),
+        );
+        // Cross positions have no raw USD, and positions without one have no entry or
+        // liquidation price
+        let mut cross = position;
+        cross["leverage"] = json!({"type": "cross", "value": 20});
+        cross["entryPx"] = json!(null);
+        cross["liquidationPx"] = json!(null);
+        let cross: PositionData = serde_json::from_value(cross).unwrap();
+        assert!(cross.leverage.raw_usd.is_none() && cross.entry_px.is_none());
+        assert!(cross.liquidation_px.is_none());
+
+        let summary = json!({"accountValue": "1", "totalMarginUsed": "1", "totalNtlPos": "1",
+            "totalRawUsd": "1"});
+        let fields = [
+            "/accountValue",
+            "/totalMarginUsed",
+            "/totalNtlPos",
+            "/totalRawUsd",
+        ];
+        assert_decimal_fields::<MarginSummary>(summary.clone(), &fields);
+        assert_decimal_fields::<UserStateResponse>(
+            json!({"assetPositions": [], "crossMarginSummary": summary,
+                "marginSummary": summary, "withdrawable": "1"}),
+            &["/withdrawable"],
+        );
+
+        let level = json!({"px": "3000", "sz": "1", "n": 1});
+        assert_decimal_fields::<Level>(level.clone(), &["/px", "/sz"]);
+        assert_integer_fields::<Level>(level, &["/n"], u64::MAX);
+        assert_integer_fields::<UserRateLimitResponse>(
+            json!({"cumVlm": "1", "nRequestsUsed": 1, "nRequestsCap": 1}),
+            &["/nRequestsUsed", "/nRequestsCap"],
+            u64::MAX,
+        );
+    }
+
+    #[test]
+    fn test_u64_boundaries() {
+        let fill: TradeInfo = serde_json::from_str(&format!(
+            r#"{{"coin":"ETH","side":"B","px":"3000.5","sz":"0.0001","time":{},"hash":"0x0",
+            "startPosition":"0","dir":"Open Long","closedPnl":"0","oid":{},"cloid":null,
+            "crossed":true,"fee":"0.0","tid":{}}}"#,
+            u64::MAX,
+            u64::MAX,
+            u64::MAX - 1
+        ))
+        .unwrap();
+        assert_eq!(fill.time.as_millis(), u64::MAX);
+        assert_eq!(fill.oid, u64::MAX);
+        assert_eq!(fill.tid, u64::MAX - 1);
+
+        // Values above 2^53 lose precision as f64, so they must stay integers end to end
+        let update: OrderUpdate = serde_json::from_str(
+            r#"{"order":{"coin":"BTC","side":"A","limitPx":"65000","sz":"1","oid":9007199254740993,
+            "timestamp":1718000000123,"origSz":"1","cloid":null},"status":"open",
+            "statusTimestamp":1718000000124}"#,
+        )
+        .unwrap();
+        assert_eq!(update.order.oid, 9_007_199_254_740_993);
+    }
+
+    #[test]
+    fn test_ledger_liquidation_account_value() {
+        for (account_value, expected) in [
+            ("12345.678901", "12345.678901"),
+            (r#""12345.678901""#, "12345.678901"),
+            ("18446744073709551615", "18446744073709551615"),
+            ("0", "0"),
+        ] {
+            let update: LedgerUpdate = serde_json::from_str(&format!(
+                r#"{{"type":"ledgerLiquidation","accountValue":{account_value},
+                "leverageType":"Cross","liquidatedPositions":[{{"coin":"ETH","szi":"-1.5"}}]}}"#
+            ))
+            .unwrap();
+            let LedgerUpdate::LedgerLiquidation(liquidation) = update else {
+                panic!("unexpected ledger update {update:?}");
+            };
+            assert_eq!(liquidation.account_value, expected);
+        }
+        assert!(serde_json::from_str::<LedgerUpdate>(
+            r#"{"type":"ledgerLiquidation","accountValue":"abc","leverageType":"Cross",
+            "liquidatedPositions":[]}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_candle_large_volume() {
+        let message: Message = serde_json::from_str(
+            r#"{"channel":"candle","data":{"t":1718000000000,"T":1718000059999,"s":"kPEPE",
+            "i":"1m","o":"0.012","c":"0.013","h":"0.0135","l":"0.0119",
+            "v":"123456789012345678.123","n":4294967296}}"#,
+        )
+        .unwrap();
+        let Message::Candle(candle) = message else {
+            panic!("unexpected message {message:?}");
+        };
+        assert_eq!(candle.data.num_trades, 4_294_967_296);
+        assert_eq!(candle.data.volume, "123456789012345678.123");
+    }
+}
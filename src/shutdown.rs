@@ -0,0 +1,156 @@
+use crate::{prelude::*, CancelAllSummary, CoinFilter, Error, ExchangeClient, InfoClient};
+use log::{error, info};
+use std::sync::Arc;
+use tokio::sync::watch;
+
+// The teardown sequence of a trading process, run on ctrl-c or SIGTERM:
+//
+//  1. the stop signal flips, so quoting loops stop placing orders,
+//  2. open orders are canceled, if configured,
+//  3. websocket connections are closed,
+//  4. flush hooks run, e.g. to write recorded fills or latencies to disk.
+//
+// Every step runs even if an earlier one failed; the first error is returned at the end.
+//
+//     let shutdown = GracefulShutdown::new()
+//         .cancel_open_orders(exchange_client.clone(), None)
+//         .on_flush(move || write_fills(&fills));
+//     let mut stop = shutdown.stop_signal();
+//     tokio::spawn(async move { quote_until(&mut stop).await });
+//     shutdown.run([&mut info_client]).await?;
+pub struct GracefulShutdown {
+    stop: watch::Sender<bool>,
+    cancel: Option<(Arc<ExchangeClient>, Option<CoinFilter>)>,
+    flushes: Vec<Box<dyn FnOnce() + Send>>,
+}
+
+impl Default for GracefulShutdown {
+    fn default() -> Self {
+        GracefulShutdown::new()
+    }
+}
+
+impl GracefulShutdown {
+    pub fn new() -> GracefulShutdown {
+        GracefulShutdown {
+            stop: watch::channel(false).0,
+            cancel: None,
+            flushes: Vec::new(),
+        }
+    }
+
+    // Cancels the open orders of `exchange_client`'s account, or those passing `filter`.
+    pub fn cancel_open_orders(
+        mut self,
+        exchange_client: Arc<ExchangeClient>,
+        filter: Option<CoinFilter>,
+    ) -> GracefulShutdown {
+        self.cancel = Some((exchange_client, filter));
+        self
+    }
+
+    pub fn on_flush(mut self, flush: impl FnOnce() + Send + 'static) -> GracefulShutdown {
+        self.flushes.push(Box::new(flush));
+        self
+    }
+
+    // Becomes true when shutdown starts. Quoting loops should wait on `changed()` alongside
+    // their other events and stop when it fires.
+    pub fn stop_signal(&self) -> watch::Receiver<bool> {
+        self.stop.subscribe()
+    }
+
+    // Waits for ctrl-c or, on unix, SIGTERM, then shuts down.
+    pub async fn run<'a>(
+        self,
+        info_clients: impl IntoIterator<Item = &'a mut InfoClient>,
+    ) -> Result<Option<CancelAllSummary>> {
+        wait_for_signal().await?;
+        info!("Shutdown signal received");
+        self.shutdown(info_clients).await
+    }
+
+    // Runs the teardown sequence immediately.
+    pub async fn shutdown<'a>(
+        self,
+        info_clients: impl IntoIterator<Item = &'a mut InfoClient>,
+    ) -> Result<Option<CancelAllSummary>> {
+        let mut first_err = None;
+        self.stop.send_replace(true);
+
+        let mut summary = None;
+        if let Some((exchange_client, filter)) = self.cancel {
+            match exchange_client.cancel_all_orders(filter, None).await {
+                Ok(canceled) => {
+                    info!(
+                        "Canceled {} orders on shutdown, {} failed",
+                        canceled.canceled.len(),
+                        canceled.failed.len()
+                    );
+                    summary = Some(canceled);
+                }
+                Err(err) => {
+                    error!("Canceling open orders on shutdown failed: {err}");
+                    first_err.get_or_insert(err);
+                }
+            }
+        }
+
+        for info_client in info_clients {
+            if let Err(err) = info_client.close_ws().await {
+                error!("Closing websocket on shutdown failed: {err}");
+                first_err.get_or_insert(err);
+            }
+        }
+
+        for flush in self.flushes {
+            flush();
+        }
+        match first_err {
+            Some(err) => Err(err),
+            None => Ok(summary),
+        }
+    }
+}
+
+async fn wait_for_signal() -> Result<()> {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        let mut terminate =
+            signal(SignalKind::terminate()).map_err(|e| Error::Signal(e.to_string()))?;
+        tokio::select! {
+            res = tokio::signal::ctrl_c() => res.map_err(|e| Error::Signal(e.to_string())),
+            _ = terminate.recv() => Ok(()),
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        tokio::signal::ctrl_c()
+            .await
+            .map_err(|e| Error::Signal(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    #[tokio::test]
+    async fn test_shutdown_sequence() -> Result<()> {
+        let flushed = Arc::new(AtomicBool::new(false));
+        let shutdown = GracefulShutdown::new().on_flush({
+            let flushed = flushed.clone();
+            move || flushed.store(true, Ordering::SeqCst)
+        });
+        let stop = shutdown.stop_signal();
+        assert!(!*stop.borrow());
+
+        let mut info_client = InfoClient::new(None, None).await?;
+        assert!(shutdown.shutdown([&mut info_client]).await?.is_none());
+        assert!(*stop.borrow());
+        assert!(flushed.load(Ordering::SeqCst));
+        Ok(())
+    }
+}
@@ -1,5 +1,4 @@
 pub(crate) mod agent;
 mod create_signature;
-pub(crate) mod usdc_transfer;
 
 pub(crate) use create_signature::{sign_l1_action, sign_typed_data};
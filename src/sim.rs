@@ -0,0 +1,672 @@
+use crate::{
+    prelude::*,
+    req::HttpClient,
+    ws::{Connector, CountingStream, TrafficCounters},
+    BaseUrl, Error, InfoClient, Subscription,
+};
+use futures_util::{SinkExt, StreamExt};
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{
+    borrow::Cow,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::{
+    io::{duplex, DuplexStream},
+    spawn,
+    sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+    time,
+};
+use tokio_tungstenite::{
+    accept_async, client_async,
+    tungstenite::protocol::{frame::coding::CloseCode, CloseFrame, Message as WsFrame},
+    WebSocketStream,
+};
+
+// Bytes buffered in each direction of an in-memory connection
+const BUFFER_SIZE: usize = 1 << 16;
+
+// Hands the exchange side of each connection a client opens to its `SimExchange`.
+#[derive(Clone)]
+pub(crate) struct SimConnector {
+    connections: UnboundedSender<SimConnection>,
+    opened: Arc<AtomicUsize>,
+}
+
+impl SimConnector {
+    // Counts the bytes the client reads as wire traffic, like a network connection would.
+    pub(crate) async fn connect(
+        &self,
+        url: &str,
+        traffic: &Arc<TrafficCounters>,
+    ) -> Result<WebSocketStream<CountingStream<DuplexStream>>> {
+        let (client, server) = duplex(BUFFER_SIZE);
+        let index = self.opened.fetch_add(1, Ordering::SeqCst);
+        let connections = self.connections.clone();
+        spawn(async move {
+            if let Ok(stream) = accept_async(server).await {
+                let _ = connections.send(SimConnection { index, stream });
+            }
+        });
+        let (stream, _) = client_async(url, CountingStream::new(client, Arc::clone(traffic)))
+            .await
+            .map_err(|e| Error::Websocket(e.to_string()))?;
+        Ok(stream)
+    }
+}
+
+// A scripted stand-in for the exchange's websocket, for reproducing reconnect, resubscribe and
+// ordering races deterministically. Clients made by `info_client` connect to it in memory, so
+// with tokio's paused time (the `sim` feature enables tokio's test-util) nothing but the test
+// decides what happens when:
+//
+//     #[tokio::test(start_paused = true)]
+//     async fn test_fill_after_close() {
+//         let mut exchange = SimExchange::new();
+//         let mut info_client = exchange.info_client();
+//         let (sender, mut receiver) = unbounded_channel();
+//         info_client.subscribe(Subscription::UserFills { user }, sender).await.unwrap();
+//         let mut connection = exchange.accept().await;
+//         assert_eq!(connection.expect_subscribe().await, Subscription::UserFills { user });
+//         connection.send("userFills", json!({"user": user, "fills": [..]})).await.unwrap();
+//         connection.close(1001, "going away").await.unwrap();
+//         ..
+//     }
+//
+// REST queries still go to the local API URL.
+pub struct SimExchange {
+    connector: SimConnector,
+    connections: UnboundedReceiver<SimConnection>,
+}
+
+impl Default for SimExchange {
+    fn default() -> SimExchange {
+        SimExchange::new()
+    }
+}
+
+impl SimExchange {
+    pub fn new() -> SimExchange {
+        let (sender, connections) = unbounded_channel();
+        SimExchange {
+            connector: SimConnector {
+                connections: sender,
+                opened: Arc::new(AtomicUsize::new(0)),
+            },
+            connections,
+        }
+    }
+
+    // An InfoClient whose websocket connections are opened to this exchange.
+    pub fn info_client(&self) -> InfoClient {
+        InfoClient {
+            http_client: HttpClient {
+                client: Client::default(),
+                base_url: BaseUrl::Localhost.get_url(),
+            },
+            ws_manager: None,
+            cache: None,
+            ws_connector: Connector::Sim(self.connector.clone()),
+            ws_runtime: None,
+        }
+    }
+
+    // The next connection a client opens, in the order they were opened.
+    pub async fn accept(&mut self) -> SimConnection {
+        self.connections
+            .recv()
+            .await
+            .expect("the exchange holds a sender")
+    }
+
+    // A connection opened and not yet accepted, if any.
+    pub fn try_accept(&mut self) -> Option<SimConnection> {
+        self.connections.try_recv().ok()
+    }
+
+    // Lets every spawned task run until it waits, e.g. for the client to process frames before
+    // asserting nothing was delivered. Only deterministic with paused time, where the clock
+    // doesn't advance while any task can run.
+    pub async fn settle() {
+        time::sleep(Duration::from_millis(1)).await;
+    }
+}
+
+// A REST API served by `serve_rest`
+#[cfg(test)]
+pub(crate) struct RestServer {
+    // The base URL to point an `HttpClient` at
+    pub(crate) base_url: String,
+    connections: Arc<std::sync::atomic::AtomicUsize>,
+}
+
+#[cfg(test)]
+impl RestServer {
+    // How many connections clients have opened so far
+    pub(crate) fn connections(&self) -> usize {
+        self.connections.load(std::sync::atomic::Ordering::SeqCst)
+    }
+}
+
+// Serves the REST API on a local port until the runtime shuts down, answering each POST with
+// `handler`'s status and JSON for the request's JSON body. Plain HTTP/1.1 with keep-alive; just
+// enough for reqwest.
+#[cfg(test)]
+pub(crate) async fn serve_rest<F, Fut>(handler: F) -> Result<RestServer>
+where
+    F: Fn(Value) -> Fut + Send + Sync + 'static,
+    Fut: std::future::Future<Output = (u16, Value)> + Send,
+{
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+        net::TcpListener,
+    };
+
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .map_err(|e| Error::GenericRequest(e.to_string()))?;
+    let base_url = format!(
+        "http://{}",
+        listener
+            .local_addr()
+            .map_err(|e| Error::GenericRequest(e.to_string()))?
+    );
+    let connections = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let handler = Arc::new(handler);
+    spawn({
+        let connections = connections.clone();
+        async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                connections.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                let handler = handler.clone();
+                spawn(async move {
+                    let mut stream = BufReader::new(stream);
+                    loop {
+                        let mut content_length = 0;
+                        let mut line = String::new();
+                        // Request line, then headers up to the blank line
+                        loop {
+                            line.clear();
+                            match stream.read_line(&mut line).await {
+                                Ok(0) | Err(_) => return,
+                                Ok(_) => {}
+                            }
+                            if line == "\r\n" {
+                                break;
+                            }
+                            if let Some((name, value)) = line.split_once(':') {
+                                if name.eq_ignore_ascii_case("content-length") {
+                                    content_length = value.trim().parse().unwrap_or_default();
+                                }
+                            }
+                        }
+                        let mut body = vec![0; content_length];
+                        if stream.read_exact(&mut body).await.is_err() {
+                            return;
+                        }
+                        let request = serde_json::from_slice(&body).unwrap_or_default();
+                        let (status, response) = handler(request).await;
+                        let response = response.to_string();
+                        let head = format!(
+                            "HTTP/1.1 {status} Sim\r\ncontent-type: application/json\r\n\
+                         content-length: {}\r\n\r\n",
+                            response.len()
+                        );
+                        let stream = stream.get_mut();
+                        if stream.write_all(head.as_bytes()).await.is_err()
+                            || stream.write_all(response.as_bytes()).await.is_err()
+                        {
+                            return;
+                        }
+                    }
+                });
+            }
+        }
+    });
+    Ok(RestServer {
+        base_url,
+        connections,
+    })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClientFrame {
+    Subscribe(Subscription),
+    Unsubscribe(Subscription),
+    Ping,
+    // Any other text the client sent
+    Other(String),
+}
+
+impl ClientFrame {
+    fn parse(text: String) -> ClientFrame {
+        #[derive(Deserialize)]
+        struct Request {
+            method: String,
+            subscription: Option<Subscription>,
+        }
+
+        match serde_json::from_str::<Request>(&text) {
+            Ok(Request {
+                method,
+                subscription: Some(subscription),
+            }) if method == "subscribe" => ClientFrame::Subscribe(subscription),
+            Ok(Request {
+                method,
+                subscription: Some(subscription),
+            }) if method == "unsubscribe" => ClientFrame::Unsubscribe(subscription),
+            Ok(Request { method, .. }) if method == "ping" => ClientFrame::Ping,
+            _ => ClientFrame::Other(text),
+        }
+    }
+}
+
+// The exchange side of one client connection. Dropping it without `close` cuts the connection
+// like a network failure would.
+pub struct SimConnection {
+    index: usize,
+    stream: WebSocketStream<DuplexStream>,
+}
+
+impl SimConnection {
+    // Which of the exchange's connections this is, counting from 0 in the order opened.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    // The next frame from the client, or None once it closed the connection.
+    pub async fn recv(&mut self) -> Option<ClientFrame> {
+        loop {
+            match self.stream.next().await? {
+                Ok(WsFrame::Text(text)) => return Some(ClientFrame::parse(text)),
+                Ok(WsFrame::Close(_)) | Err(_) => return None,
+                Ok(_) => {}
+            }
+        }
+    }
+
+    // The next subscribe request, skipping pings. Panics on anything else.
+    pub async fn expect_subscribe(&mut self) -> Subscription {
+        match self.recv_request().await {
+            Some(ClientFrame::Subscribe(subscription)) => subscription,
+            frame => panic!("expected a subscribe request, got {frame:?}"),
+        }
+    }
+
+    // The next unsubscribe request, skipping pings. Panics on anything else.
+    pub async fn expect_unsubscribe(&mut self) -> Subscription {
+        match self.recv_request().await {
+            Some(ClientFrame::Unsubscribe(subscription)) => subscription,
+            frame => panic!("expected an unsubscribe request, got {frame:?}"),
+        }
+    }
+
+    async fn recv_request(&mut self) -> Option<ClientFrame> {
+        loop {
+            match self.recv().await {
+                Some(ClientFrame::Ping) => {}
+                frame => return frame,
+            }
+        }
+    }
+
+    // Sends a message in the exchange's {"channel": .., "data": ..} shape.
+    pub async fn send(&mut self, channel: &str, data: Value) -> Result<()> {
+        self.send_text(json!({ "channel": channel, "data": data }).to_string())
+            .await
+    }
+
+    // Sends a text frame as is, e.g. a plain text error.
+    pub async fn send_text(&mut self, text: impl Into<String>) -> Result<()> {
+        self.stream
+            .send(WsFrame::Text(text.into()))
+            .await
+            .map_err(|e| Error::Websocket(e.to_string()))
+    }
+
+    // Closes the connection with a close frame, like the exchange does before maintenance.
+    pub async fn close(mut self, code: u16, reason: &str) -> Result<()> {
+        self.stream
+            .close(Some(CloseFrame {
+                code: CloseCode::from(code),
+                reason: Cow::Owned(reason.to_string()),
+            }))
+            .await
+            .map_err(|e| Error::Websocket(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Message, ResyncReason, WsTraffic, MAX_WS_SUBSCRIPTIONS};
+    use ethers::types::H160;
+    use tokio::sync::mpsc::unbounded_channel;
+
+    fn order_update(oid: u64, status_timestamp: u64) -> Value {
+        json!([{
+            "order": {"coin": "ETH", "side": "B", "limitPx": "2000", "sz": "1", "oid": oid,
+                      "timestamp": 1_000, "origSz": "1"},
+            "status": "open",
+            "statusTimestamp": status_timestamp
+        }])
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_ordering_and_close_per_connection() -> Result<()> {
+        let mut exchange = SimExchange::new();
+        let mut info_client = exchange.info_client();
+        let (trader, hedger) = (H160::from_low_u64_be(1), H160::from_low_u64_be(2));
+        let (trader_sender, mut trader_updates) = unbounded_channel();
+        let (hedger_sender, mut hedger_updates) = unbounded_channel();
+        info_client
+            .subscribe(Subscription::OrderUpdates { user: trader }, trader_sender)
+            .await?;
+        info_client
+            .subscribe(Subscription::OrderUpdates { user: hedger }, hedger_sender)
+            .await?;
+
+        // orderUpdates don't name their user, so each user gets a connection of their own
+        let mut first = exchange.accept().await;
+        let mut second = exchange.accept().await;
+        assert_eq!((first.index(), second.index()), (0, 1));
+        assert_eq!(
+            first.expect_subscribe().await,
+            Subscription::OrderUpdates { user: trader }
+        );
+        assert_eq!(
+            second.expect_subscribe().await,
+            Subscription::OrderUpdates { user: hedger }
+        );
+
+        second.send("orderUpdates", order_update(1, 2_000)).await?;
+        second.send("orderUpdates", order_update(2, 1_500)).await?;
+        first.close(1001, "going away").await?;
+        SimExchange::settle().await;
+
+        assert!(matches!(
+            hedger_updates.try_recv(),
+            Ok(Message::OrderUpdates(_))
+        ));
+        let Ok(Message::Resync(resync)) = hedger_updates.try_recv() else {
+            panic!("expected a resync before the older update");
+        };
+        assert_eq!(resync.reason, ResyncReason::OutOfOrder);
+        assert!(matches!(
+            hedger_updates.try_recv(),
+            Ok(Message::OrderUpdates(updates)) if updates.data[0].order.oid == 2
+        ));
+        assert!(hedger_updates.try_recv().is_err());
+        let Ok(Message::Closed(closed)) = trader_updates.try_recv() else {
+            panic!("expected the close to reach the trader's subscription");
+        };
+        assert_eq!(closed.code, 1001);
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_user_connections_are_released() -> Result<()> {
+        let mut exchange = SimExchange::new();
+        let mut info_client = exchange.info_client();
+        let (trader, hedger, auditor) = (
+            H160::from_low_u64_be(1),
+            H160::from_low_u64_be(2),
+            H160::from_low_u64_be(3),
+        );
+        let (sender, _receiver) = unbounded_channel();
+        let trader_id = info_client
+            .subscribe(Subscription::OrderUpdates { user: trader }, sender)
+            .await?;
+        let mut connection = exchange.accept().await;
+        connection.expect_subscribe().await;
+        info_client.unsubscribe(trader_id).await?;
+        assert_eq!(
+            connection.expect_unsubscribe().await,
+            Subscription::OrderUpdates { user: trader }
+        );
+
+        // Until the exchange acknowledges the unsubscribe, updates for the trader may still
+        // arrive, so another user gets a connection of their own
+        let (auditor_sender, _auditor_updates) = unbounded_channel();
+        let auditor_id = info_client
+            .subscribe(Subscription::OrderUpdates { user: auditor }, auditor_sender)
+            .await?;
+        let mut auditor_connection = exchange.accept().await;
+        assert_eq!(auditor_connection.index(), 1);
+        auditor_connection.expect_subscribe().await;
+
+        connection
+            .send("orderUpdates", order_update(1, 1_000))
+            .await?;
+        let ack = json!({"method": "unsubscribe",
+                         "subscription": Subscription::OrderUpdates { user: trader }});
+        connection.send("subscriptionResponse", ack).await?;
+        SimExchange::settle().await;
+
+        // Then the freed connection is reused
+        let (hedger_sender, mut hedger_updates) = unbounded_channel();
+        info_client
+            .subscribe(Subscription::OrderUpdates { user: hedger }, hedger_sender)
+            .await?;
+        assert_eq!(
+            connection.expect_subscribe().await,
+            Subscription::OrderUpdates { user: hedger }
+        );
+        assert!(exchange.try_accept().is_none());
+        connection
+            .send("orderUpdates", order_update(2, 2_000))
+            .await?;
+        SimExchange::settle().await;
+        assert!(matches!(
+            hedger_updates.try_recv(),
+            Ok(Message::OrderUpdates(updates)) if updates.data[0].order.oid == 2
+        ));
+        assert!(hedger_updates.try_recv().is_err());
+        info_client.unsubscribe(auditor_id).await?;
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rejected_subscriptions_claim_no_connection() -> Result<()> {
+        let mut exchange = SimExchange::new();
+        let mut info_client = exchange.info_client();
+        let (sender, _receiver) = unbounded_channel();
+        info_client
+            .subscribe(
+                Subscription::OrderUpdates {
+                    user: H160::from_low_u64_be(1),
+                },
+                sender.clone(),
+            )
+            .await?;
+        let mut connection = exchange.accept().await;
+        spawn(async move { while connection.recv().await.is_some() {} });
+        for coin in 1..MAX_WS_SUBSCRIPTIONS {
+            info_client
+                .subscribe(Subscription::l2_book(format!("COIN{coin}")), sender.clone())
+                .await?;
+        }
+        let result = info_client
+            .subscribe(
+                Subscription::OrderUpdates {
+                    user: H160::from_low_u64_be(2),
+                },
+                sender,
+            )
+            .await;
+        assert!(matches!(result, Err(Error::SubscriptionRejected { .. })));
+        SimExchange::settle().await;
+        assert!(exchange.try_accept().is_none());
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_refused_subscriptions_are_dropped() -> Result<()> {
+        let mut exchange = SimExchange::new();
+        let mut info_client = exchange.info_client();
+        let (sender, mut receiver) = unbounded_channel();
+        info_client
+            .subscribe(Subscription::l2_book("NOPE"), sender.clone())
+            .await?;
+        let mut connection = exchange.accept().await;
+        connection.expect_subscribe().await;
+        connection
+            .send(
+                "error",
+                json!(r#"Invalid subscription {"type":"l2Book","coin":"NOPE"}"#),
+            )
+            .await?;
+        let Some(Message::SubscriptionRejected(rejection)) = receiver.recv().await else {
+            panic!("expected a typed rejection");
+        };
+        assert_eq!(rejection.subscription, Subscription::l2_book("NOPE"));
+        assert_eq!(rejection.active, 0);
+
+        // Dropped, so subscribing again asks the exchange again
+        info_client
+            .subscribe(Subscription::l2_book("NOPE"), sender)
+            .await?;
+        assert_eq!(
+            connection.expect_subscribe().await,
+            Subscription::l2_book("NOPE")
+        );
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_resubscribe_after_close() -> Result<()> {
+        let mut exchange = SimExchange::new();
+        let mut info_client = exchange.info_client();
+        let (sender, mut receiver) = unbounded_channel();
+        info_client
+            .subscribe(Subscription::all_mids(), sender.clone())
+            .await?;
+        let mut connection = exchange.accept().await;
+        assert_eq!(
+            connection.expect_subscribe().await,
+            Subscription::all_mids()
+        );
+
+        // Pings go out on the client's schedule, which paused time makes exact
+        assert_eq!(connection.recv().await, Some(ClientFrame::Ping));
+        time::advance(Duration::from_secs(50)).await;
+        assert_eq!(connection.recv().await, Some(ClientFrame::Ping));
+
+        info_client.close_ws().await?;
+        assert_eq!(connection.recv().await, None);
+        assert!(exchange.try_accept().is_none());
+
+        info_client
+            .subscribe(Subscription::all_mids(), sender)
+            .await?;
+        let mut connection = exchange.accept().await;
+        assert_eq!(connection.index(), 1);
+        assert_eq!(
+            connection.expect_subscribe().await,
+            Subscription::all_mids()
+        );
+        connection
+            .send("allMids", json!({"mids": {"ETH": "2000.5"}}))
+            .await?;
+        SimExchange::settle().await;
+        assert!(matches!(receiver.try_recv(), Ok(Message::AllMids(_))));
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_all_mids_per_dex() -> Result<()> {
+        let mut exchange = SimExchange::new();
+        let mut info_client = exchange.info_client();
+        let (first_sender, mut first_dex) = unbounded_channel();
+        let (xyz_sender, mut xyz_dex) = unbounded_channel();
+        info_client
+            .subscribe(Subscription::all_mids(), first_sender)
+            .await?;
+        info_client
+            .subscribe(Subscription::all_mids_for_dex("xyz"), xyz_sender)
+            .await?;
+        let mut connection = exchange.accept().await;
+        assert_eq!(
+            connection.expect_subscribe().await,
+            Subscription::all_mids()
+        );
+        assert_eq!(
+            connection.expect_subscribe().await,
+            Subscription::all_mids_for_dex("xyz")
+        );
+
+        // The DEX's mids are told apart by their coin names
+        connection
+            .send("allMids", json!({"mids": {"xyz:XYZ100": "25010.5"}}))
+            .await?;
+        connection
+            .send(
+                "allMids",
+                json!({"mids": {"BTC": "97412.5", "@107": "24.842"}}),
+            )
+            .await?;
+        SimExchange::settle().await;
+        let Ok(Message::AllMids(all_mids)) = xyz_dex.try_recv() else {
+            panic!("expected the DEX's mids");
+        };
+        assert_eq!(all_mids.data.dex(), Some("xyz"));
+        assert!(xyz_dex.try_recv().is_err());
+        let Ok(Message::AllMids(all_mids)) = first_dex.try_recv() else {
+            panic!("expected the first DEX's mids");
+        };
+        assert_eq!(all_mids.data.dex(), None);
+        assert_eq!(
+            all_mids.data.perp_mids().collect::<Vec<_>>(),
+            [("BTC", "97412.5")]
+        );
+        assert_eq!(
+            all_mids.data.spot_mids().collect::<Vec<_>>(),
+            [("@107", "24.842")]
+        );
+        assert!(first_dex.try_recv().is_err());
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_ws_traffic() -> Result<()> {
+        let mut exchange = SimExchange::new();
+        let mut info_client = exchange.info_client();
+        assert_eq!(info_client.ws_traffic(), None);
+        let (sender, _receiver) = unbounded_channel();
+        info_client
+            .subscribe(Subscription::all_mids(), sender)
+            .await?;
+        let mut connection = exchange.accept().await;
+        connection.expect_subscribe().await;
+
+        // Every frame counts, including ones that deliver nothing. Over an uncompressed
+        // connection the wire carries each payload plus a 2 byte frame header.
+        let before = info_client.ws_traffic().unwrap();
+        assert_eq!(before.frames, 0);
+        assert!(
+            before.wire_bytes > 0,
+            "the handshake response is read off the wire"
+        );
+        let mids = json!({"channel": "allMids", "data": {"mids": {"ETH": "2000.5"}}}).to_string();
+        connection.send_text(mids.clone()).await?;
+        connection
+            .send_text("Websocket connection established.")
+            .await?;
+        SimExchange::settle().await;
+        let payload_bytes = (mids.len() + "Websocket connection established.".len()) as u64;
+        assert_eq!(
+            info_client.ws_traffic(),
+            Some(WsTraffic {
+                frames: 2,
+                wire_bytes: before.wire_bytes + payload_bytes + 4,
+                payload_bytes,
+            })
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,32 @@
+use crate::{prelude::*, Error};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{fs, io::ErrorKind, path::Path};
+
+// Writes `state` as JSON, through a temporary file renamed over `path`, so a crash mid-write
+// leaves the previous snapshot intact.
+pub fn save_snapshot<T: Serialize>(path: impl AsRef<Path>, state: &T) -> Result<()> {
+    let path = path.as_ref();
+    let json = serde_json::to_vec(state).map_err(|e| Error::JsonParse(e.to_string()))?;
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, json)
+        .and_then(|()| fs::rename(&tmp, path))
+        .map_err(|e| Error::Snapshot(format!("writing {}: {e}", path.display())))
+}
+
+// None if there's no snapshot at `path` yet, e.g. on the first start.
+pub fn load_snapshot<T: DeserializeOwned>(path: impl AsRef<Path>) -> Result<Option<T>> {
+    let path = path.as_ref();
+    let json = match fs::read(path) {
+        Ok(json) => json,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(err) => {
+            return Err(Error::Snapshot(format!(
+                "reading {}: {err}",
+                path.display()
+            )))
+        }
+    };
+    serde_json::from_slice(&json)
+        .map(Some)
+        .map_err(|e| Error::Snapshot(format!("parsing {}: {e}", path.display())))
+}
@@ -0,0 +1,367 @@
+use crate::{
+    prelude::*, BasicOrder, Error, LedgerUpdateData, Message, OrderUpdate, TradeInfo, UserData,
+    UserFunding,
+};
+use rusqlite::{params, Connection, Transaction};
+use std::path::Path;
+use tokio::{sync::mpsc::UnboundedReceiver, task::JoinHandle};
+
+// Persists an account's fills, order updates, funding payments and non-funding ledger events
+// (deposits, withdrawals, transfers, ...) to a local SQLite database. Use one database per
+// account. Every write is an upsert, so replaying the snapshot a subscription starts with, or
+// messages already written before a restart, leaves the database unchanged.
+//
+// Prices, sizes and amounts are stored as the decimal strings the API sends; times are unix
+// milliseconds.
+const SCHEMA: &str = "
+-- One row per fill. A fill is identified by its trade id and order id; a self-trade has two
+-- fills with the same tid.
+CREATE TABLE IF NOT EXISTS fills (
+    tid INTEGER NOT NULL,
+    oid INTEGER NOT NULL,
+    coin TEXT NOT NULL,
+    side TEXT NOT NULL,
+    px TEXT NOT NULL,
+    sz TEXT NOT NULL,
+    time INTEGER NOT NULL,
+    hash TEXT NOT NULL,
+    start_position TEXT NOT NULL,
+    dir TEXT NOT NULL,
+    closed_pnl TEXT NOT NULL,
+    crossed INTEGER NOT NULL,
+    fee TEXT NOT NULL,
+    fee_token TEXT,
+    builder_fee TEXT,
+    cloid TEXT,
+    PRIMARY KEY (tid, oid)
+);
+CREATE INDEX IF NOT EXISTS fills_time ON fills (time);
+
+-- The latest known status of each order. An update older than the stored one is ignored, so
+-- out-of-order delivery can't regress an order's status.
+CREATE TABLE IF NOT EXISTS orders (
+    oid INTEGER PRIMARY KEY,
+    cloid TEXT,
+    coin TEXT NOT NULL,
+    side TEXT NOT NULL,
+    limit_px TEXT NOT NULL,
+    sz TEXT NOT NULL,
+    orig_sz TEXT NOT NULL,
+    created_time INTEGER NOT NULL,
+    status TEXT NOT NULL,
+    status_time INTEGER NOT NULL
+);
+
+-- One row per hourly funding payment.
+CREATE TABLE IF NOT EXISTS fundings (
+    coin TEXT NOT NULL,
+    time INTEGER NOT NULL,
+    usdc TEXT NOT NULL,
+    szi TEXT NOT NULL,
+    funding_rate TEXT NOT NULL,
+    PRIMARY KEY (coin, time)
+);
+
+-- Deposits, withdrawals, transfers and other non-funding ledger events. `type` is the
+-- event's type, e.g. \"deposit\" or \"internalTransfer\", and `delta` its full JSON.
+CREATE TABLE IF NOT EXISTS ledger_updates (
+    hash TEXT NOT NULL,
+    time INTEGER NOT NULL,
+    type TEXT NOT NULL,
+    delta TEXT NOT NULL,
+    PRIMARY KEY (hash, time, type)
+);
+";
+
+pub struct SqliteSink {
+    connection: Connection,
+}
+
+impl SqliteSink {
+    // Opens, or creates, the database at `path` and its tables.
+    pub fn open(path: impl AsRef<Path>) -> Result<SqliteSink> {
+        let path = path.as_ref();
+        let connection = Connection::open(path)
+            .map_err(|e| Error::Sqlite(format!("opening {}: {e}", path.display())))?;
+        SqliteSink::with_connection(connection)
+    }
+
+    pub fn in_memory() -> Result<SqliteSink> {
+        let connection = Connection::open_in_memory().map_err(sqlite_err)?;
+        SqliteSink::with_connection(connection)
+    }
+
+    fn with_connection(connection: Connection) -> Result<SqliteSink> {
+        connection.execute_batch(SCHEMA).map_err(sqlite_err)?;
+        Ok(SqliteSink { connection })
+    }
+
+    // For queries against the sink's tables.
+    pub fn connection(&self) -> &Connection {
+        &self.connection
+    }
+
+    // Writes the fills, order updates, fundings or ledger updates in `message`, in one
+    // transaction. Other messages are ignored.
+    pub fn write(&mut self, message: &Message) -> Result<()> {
+        let tx = self.connection.transaction().map_err(sqlite_err)?;
+        match message {
+            Message::UserFills(user_fills) => {
+                for fill in &user_fills.data.fills {
+                    upsert_fill(&tx, fill)?;
+                }
+            }
+            Message::User(user) => match &*user.data {
+                UserData::Fills(fills) => {
+                    for fill in fills {
+                        upsert_fill(&tx, fill)?;
+                    }
+                }
+                UserData::Funding(funding) => upsert_funding(&tx, funding)?,
+                _ => {}
+            },
+            Message::OrderUpdates(order_updates) => {
+                for update in order_updates.data.iter() {
+                    upsert_order(&tx, update)?;
+                }
+            }
+            Message::UserFundings(user_fundings) => {
+                for funding in &user_fundings.data.fundings {
+                    upsert_funding(&tx, funding)?;
+                }
+            }
+            Message::UserNonFundingLedgerUpdates(ledger_updates) => {
+                for update in &ledger_updates.data.non_funding_ledger_updates {
+                    upsert_ledger_update(&tx, update)?;
+                }
+            }
+            _ => return Ok(()),
+        }
+        tx.commit().map_err(sqlite_err)
+    }
+
+    // Writes every message `receiver` yields on a blocking thread, until the channel closes or
+    // a write fails. Feed it the receiver of the userFills, orderUpdates, userFundings and
+    // userNonFundingLedgerUpdates subscriptions.
+    pub fn spawn(mut self, mut receiver: UnboundedReceiver<Message>) -> JoinHandle<Result<()>> {
+        tokio::task::spawn_blocking(move || {
+            while let Some(message) = receiver.blocking_recv() {
+                self.write(&message)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+fn upsert_fill(tx: &Transaction, fill: &TradeInfo) -> Result<()> {
+    tx.execute(
+        "INSERT INTO fills (tid, oid, coin, side, px, sz, time, hash, start_position, dir,
+             closed_pnl, crossed, fee, fee_token, builder_fee, cloid)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)
+         ON CONFLICT (tid, oid) DO UPDATE SET
+             coin = excluded.coin, side = excluded.side, px = excluded.px, sz = excluded.sz,
+             time = excluded.time, hash = excluded.hash,
+             start_position = excluded.start_position, dir = excluded.dir,
+             closed_pnl = excluded.closed_pnl, crossed = excluded.crossed, fee = excluded.fee,
+             fee_token = excluded.fee_token, builder_fee = excluded.builder_fee,
+             cloid = excluded.cloid",
+        params![
+            fill.tid as i64,
+            fill.oid as i64,
+            fill.coin,
+            fill.side,
+            fill.px,
+            fill.sz,
+            fill.time.as_millis() as i64,
+            fill.hash,
+            fill.start_position,
+            fill.dir,
+            fill.closed_pnl,
+            fill.crossed,
+            fill.fee,
+            fill.fee_token,
+            fill.builder_fee,
+            fill.cloid,
+        ],
+    )
+    .map_err(sqlite_err)?;
+    Ok(())
+}
+
+fn upsert_order(tx: &Transaction, update: &OrderUpdate) -> Result<()> {
+    let BasicOrder {
+        coin,
+        side,
+        limit_px,
+        sz,
+        oid,
+        timestamp,
+        orig_sz,
+        cloid,
+        ..
+    } = &update.order;
+    tx.execute(
+        "INSERT INTO orders (oid, cloid, coin, side, limit_px, sz, orig_sz, created_time, status,
+             status_time)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+         ON CONFLICT (oid) DO UPDATE SET
+             cloid = excluded.cloid, coin = excluded.coin, side = excluded.side,
+             limit_px = excluded.limit_px, sz = excluded.sz, orig_sz = excluded.orig_sz,
+             created_time = excluded.created_time, status = excluded.status,
+             status_time = excluded.status_time
+         WHERE excluded.status_time >= orders.status_time",
+        params![
+            *oid as i64,
+            cloid,
+            coin,
+            side,
+            limit_px,
+            sz,
+            orig_sz,
+            timestamp.as_millis() as i64,
+            update.status,
+            update.status_timestamp.as_millis() as i64,
+        ],
+    )
+    .map_err(sqlite_err)?;
+    Ok(())
+}
+
+fn upsert_funding(tx: &Transaction, funding: &UserFunding) -> Result<()> {
+    tx.execute(
+        "INSERT INTO fundings (coin, time, usdc, szi, funding_rate)
+         VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT (coin, time) DO UPDATE SET
+             usdc = excluded.usdc, szi = excluded.szi, funding_rate = excluded.funding_rate",
+        params![
+            funding.coin,
+            funding.time.as_millis() as i64,
+            funding.usdc,
+            funding.szi,
+            funding.funding_rate,
+        ],
+    )
+    .map_err(sqlite_err)?;
+    Ok(())
+}
+
+fn upsert_ledger_update(tx: &Transaction, update: &LedgerUpdateData) -> Result<()> {
+    let delta = serde_json::to_value(&update.delta).map_err(|e| Error::JsonParse(e.to_string()))?;
+    let kind = delta["type"].as_str().unwrap_or_default().to_string();
+    tx.execute(
+        "INSERT INTO ledger_updates (hash, time, type, delta)
+         VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT (hash, time, type) DO UPDATE SET delta = excluded.delta",
+        params![
+            update.hash,
+            update.time.as_millis() as i64,
+            kind,
+            delta.to_string()
+        ],
+    )
+    .map_err(sqlite_err)?;
+    Ok(())
+}
+
+fn sqlite_err(e: rusqlite::Error) -> Error {
+    Error::Sqlite(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{OrderUpdates, Timestamp, UserFills, UserFillsData};
+    use ethers::types::H160;
+    use std::sync::Arc;
+
+    fn fill(tid: u64, oid: u64, sz: &str) -> TradeInfo {
+        TradeInfo {
+            time: Timestamp::from_millis(1_000),
+            hash: "0x00".to_string(),
+            crossed: true,
+            fee: "0.1".to_string(),
+            fee_token: Some("USDC".to_string()),
+            ..TradeInfo::test_fill(oid, tid, sz)
+        }
+    }
+
+    fn order_update(status: &str, time: u64) -> Message {
+        Message::OrderUpdates(OrderUpdates {
+            data: Arc::new(vec![OrderUpdate {
+                order: BasicOrder {
+                    coin: "ETH".to_string(),
+                    side: "B".to_string(),
+                    limit_px: "2000.0".to_string(),
+                    sz: "0.1".to_string(),
+                    oid: 7,
+                    timestamp: Timestamp::from_millis(1_000),
+                    orig_sz: "0.1".to_string(),
+                    cloid: None,
+                    extra: Default::default(),
+                },
+                status: status.to_string(),
+                status_timestamp: Timestamp::from_millis(time),
+            }]),
+        })
+    }
+
+    #[test]
+    fn test_sqlite_upserts() -> Result<()> {
+        let mut sink = SqliteSink::in_memory()?;
+        let fills = |fills| {
+            Message::UserFills(UserFills {
+                data: Arc::new(UserFillsData {
+                    is_snapshot: None,
+                    user: H160::zero(),
+                    fills,
+                }),
+            })
+        };
+        sink.write(&fills(vec![fill(1, 7, "0.05"), fill(2, 7, "0.05")]))?;
+        // Replays don't duplicate rows
+        sink.write(&fills(vec![fill(2, 7, "0.05")]))?;
+        let (count, total): (i64, f64) = sink
+            .connection()
+            .query_row(
+                "SELECT COUNT(*), SUM(CAST(sz AS REAL)) FROM fills",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(count, 2);
+        assert!((total - 0.1).abs() < 1e-9);
+
+        sink.write(&order_update("open", 1_000))?;
+        sink.write(&order_update("filled", 3_000))?;
+        // A stale update doesn't regress the status
+        sink.write(&order_update("open", 2_000))?;
+        let status: String = sink
+            .connection()
+            .query_row("SELECT status FROM orders WHERE oid = 7", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(status, "filled");
+
+        let ledger: Message = serde_json::from_value(serde_json::json!({
+            "channel": "userNonFundingLedgerUpdates",
+            "data": {
+                "user": "0x0000000000000000000000000000000000000000",
+                "nonFundingLedgerUpdates": [{
+                    "time": 5_000,
+                    "hash": "0x01",
+                    "delta": {"type": "deposit", "usdc": "100.0"}
+                }]
+            }
+        }))
+        .unwrap();
+        sink.write(&ledger)?;
+        sink.write(&ledger)?;
+        let kind: String = sink
+            .connection()
+            .query_row("SELECT type FROM ledger_updates", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(kind, "deposit");
+        Ok(())
+    }
+}
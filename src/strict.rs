@@ -0,0 +1,74 @@
+use crate::{prelude::*, Error};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+// Decodes `json` as `T` like `serde_json::from_str`, but fails with `Error::SchemaDrift` when
+// the payload has fields `T` doesn't model, naming each by its path (e.g.
+// `data.fills[0].twapId`), so new fields from the exchange are noticed instead of dropped.
+// Decoding normally stays lenient; this is for tests and monitoring.
+//
+// Unknown fields are found by re-serializing the decoded value and comparing keys, which
+// works for any type serializing under the names it deserializes from. Fields that are null
+// in the payload are ignored, since optional fields are often skipped when serializing.
+pub fn decode_strict<T: DeserializeOwned + Serialize>(json: &str) -> Result<T> {
+    let payload: Value = serde_json::from_str(json).map_err(|e| Error::JsonParse(e.to_string()))?;
+    let decoded: T =
+        serde_json::from_value(payload.clone()).map_err(|e| Error::JsonParse(e.to_string()))?;
+    let known = serde_json::to_value(&decoded).map_err(|e| Error::JsonParse(e.to_string()))?;
+    let mut fields = Vec::new();
+    unknown_fields(&payload, &known, String::new(), &mut fields);
+    if fields.is_empty() {
+        Ok(decoded)
+    } else {
+        Err(Error::SchemaDrift {
+            type_name: std::any::type_name::<T>(),
+            fields,
+        })
+    }
+}
+
+fn unknown_fields(payload: &Value, known: &Value, path: String, fields: &mut Vec<String>) {
+    match (payload, known) {
+        (Value::Object(payload), Value::Object(known)) => {
+            for (key, value) in payload {
+                let field = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{path}.{key}")
+                };
+                match known.get(key) {
+                    Some(known) => unknown_fields(value, known, field, fields),
+                    None if value.is_null() => {}
+                    None => fields.push(field),
+                }
+            }
+        }
+        (Value::Array(payload), Value::Array(known)) => {
+            for (index, (value, known)) in payload.iter().zip(known).enumerate() {
+                unknown_fields(value, known, format!("{path}[{index}]"), fields);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Message;
+
+    #[test]
+    fn test_decode_strict() {
+        let known = r#"{"channel": "trades", "data": [{"coin": "ETH", "side": "B",
+            "px": "2000", "sz": "1", "time": 1, "hash": "0x00", "tid": 2, "users": null}]}"#;
+        assert!(decode_strict::<Message>(known).is_ok());
+
+        let drifted = r#"{"channel": "trades", "data": [{"coin": "ETH", "side": "B",
+            "px": "2000", "sz": "1", "time": 1, "hash": "0x00", "tid": 2,
+            "users": ["0x01", "0x02"]}]}"#;
+        let Err(Error::SchemaDrift { fields, .. }) = decode_strict::<Message>(drifted) else {
+            panic!("expected schema drift");
+        };
+        assert_eq!(fields, vec!["data[0].users"]);
+    }
+}
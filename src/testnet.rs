@@ -0,0 +1,212 @@
+use crate::{
+    consts::TESTNET_API_URL, prelude::*, BaseUrl, Error, ExchangeClient, ExchangeResponseStatus,
+    InfoClient, Message, Subscription, UserRoleResponse,
+};
+use ethers::types::H160;
+use reqwest::{header::CONTENT_TYPE, Client};
+use std::{sync::Arc, time::Duration};
+use tokio::{
+    sync::mpsc::unbounded_channel,
+    time::{self, Instant},
+};
+
+// Where test accounts get USDC from. Testnet has no public faucet API, so CI suites usually
+// keep a funded treasury account and send from it.
+pub enum Faucet {
+    // Sends USDC with usdSend from a funded testnet account
+    Treasury(Arc<ExchangeClient>),
+    // POSTs {"user": "0x..", "amount": ".."} to a faucet service of your own
+    Http { url: String },
+}
+
+impl Faucet {
+    async fn request(&self, user: H160, amount: &str) -> Result<()> {
+        match self {
+            Faucet::Treasury(exchange_client) => {
+                if exchange_client.http_client.base_url != TESTNET_API_URL {
+                    return Err(Error::Testnet(
+                        "the treasury's exchange client isn't on testnet".to_string(),
+                    ));
+                }
+                let status = exchange_client
+                    .usdc_transfer(amount, &format!("{user:?}"), None)
+                    .await?;
+                match status {
+                    ExchangeResponseStatus::Ok(_) => Ok(()),
+                    ExchangeResponseStatus::Err(err) => Err(Error::Testnet(format!(
+                        "treasury transfer failed: {}",
+                        err.message
+                    ))),
+                }
+            }
+            Faucet::Http { url } => {
+                let body = serde_json::json!({ "user": user, "amount": amount });
+                let response = Client::new()
+                    .post(url)
+                    .header(CONTENT_TYPE, "application/json")
+                    .body(body.to_string())
+                    .send()
+                    .await
+                    .map_err(|e| Error::Testnet(e.to_string()))?;
+                match response.status() {
+                    status if status.is_success() => Ok(()),
+                    status => Err(Error::Testnet(format!("faucet returned {status}"))),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Connectivity {
+    // Round trip of a meta request
+    pub info_latency: Duration,
+    // From subscribing to the first allMids update
+    pub ws_latency: Duration,
+    pub perp_assets: usize,
+}
+
+// Self-provisioning for suites running against testnet: checks the API and websocket are
+// reachable, and tops test accounts up to a minimum balance from a faucet, waiting until the
+// funds (and with them the account) show up.
+//
+//     let onboarding = TestnetOnboarding::new()
+//         .await?
+//         .faucet(Faucet::Treasury(Arc::new(treasury_client)));
+//     onboarding.check_connectivity().await?;
+//     onboarding.ensure_funded(test_wallet.address(), 100.0).await?;
+pub struct TestnetOnboarding {
+    info_client: InfoClient,
+    faucet: Option<Faucet>,
+    poll_interval: Duration,
+    timeout: Duration,
+}
+
+impl TestnetOnboarding {
+    pub async fn new() -> Result<TestnetOnboarding> {
+        Ok(TestnetOnboarding {
+            info_client: InfoClient::new(None, Some(BaseUrl::Testnet)).await?,
+            faucet: None,
+            poll_interval: Duration::from_secs(2),
+            timeout: Duration::from_secs(60),
+        })
+    }
+
+    pub fn faucet(mut self, faucet: Faucet) -> TestnetOnboarding {
+        self.faucet = Some(faucet);
+        self
+    }
+
+    // How long connectivity checks and funding wait before failing.
+    pub fn timeout(mut self, timeout: Duration) -> TestnetOnboarding {
+        self.timeout = timeout;
+        self
+    }
+
+    pub async fn check_connectivity(&self) -> Result<Connectivity> {
+        let started = Instant::now();
+        let meta = self.info_client.meta().await?;
+        let info_latency = started.elapsed();
+
+        let mut ws_client = InfoClient::new(None, Some(BaseUrl::Testnet)).await?;
+        let (sender, mut receiver) = unbounded_channel();
+        let started = Instant::now();
+        let subscription_id = ws_client
+            .subscribe(Subscription::all_mids(), sender)
+            .await?;
+        let first_update = time::timeout(self.timeout, async {
+            while let Some(message) = receiver.recv().await {
+                if matches!(message, Message::AllMids(_)) {
+                    return true;
+                }
+            }
+            false
+        })
+        .await;
+        let ws_latency = started.elapsed();
+        ws_client.unsubscribe(subscription_id).await?;
+        ws_client.close_ws().await?;
+        match first_update {
+            Ok(true) => Ok(Connectivity {
+                info_latency,
+                ws_latency,
+                perp_assets: meta.universe.len(),
+            }),
+            Ok(false) => Err(Error::Testnet(
+                "websocket closed before any update".to_string(),
+            )),
+            Err(_) => Err(Error::Testnet(format!(
+                "no allMids update within {:?}",
+                self.timeout
+            ))),
+        }
+    }
+
+    // The perp account value of `user`, in USDC.
+    pub async fn balance(&self, user: H160) -> Result<f64> {
+        let user_state = self.info_client.user_state(user).await?;
+        user_state
+            .margin_summary
+            .account_value
+            .parse()
+            .map_err(|_| Error::FloatStringParse)
+    }
+
+    // Whether the exchange knows `user`, which it does from the first deposit or transfer in.
+    pub async fn is_established(&self, user: H160) -> Result<bool> {
+        Ok(!matches!(
+            self.info_client.user_role(user).await?,
+            UserRoleResponse::Missing
+        ))
+    }
+
+    // Requests the shortfall from the faucet if `user` holds less than `min_usdc`, then waits
+    // for the balance to arrive. Returns the balance.
+    pub async fn ensure_funded(&self, user: H160, min_usdc: f64) -> Result<f64> {
+        let balance = self.balance(user).await?;
+        let Some(amount) = top_up_amount(balance, min_usdc) else {
+            return Ok(balance);
+        };
+        let faucet = self.faucet.as_ref().ok_or_else(|| {
+            Error::Testnet(format!(
+                "{user:?} holds {balance} USDC, below {min_usdc}, and no faucet is configured"
+            ))
+        })?;
+        faucet.request(user, &amount).await?;
+
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            time::sleep(self.poll_interval).await;
+            let balance = self.balance(user).await?;
+            if top_up_amount(balance, min_usdc).is_none() && self.is_established(user).await? {
+                return Ok(balance);
+            }
+            if Instant::now() >= deadline {
+                return Err(Error::Testnet(format!(
+                    "{user:?} still holds {balance} USDC after {:?}",
+                    self.timeout
+                )));
+            }
+        }
+    }
+}
+
+// The USDC to request so `balance` reaches `min_usdc`, rounded up to the cent as usdSend
+// amounts are, or None if it already has.
+fn top_up_amount(balance: f64, min_usdc: f64) -> Option<String> {
+    let cents = ((min_usdc - balance) * 100.0).ceil();
+    (cents > 0.0).then(|| format!("{:.2}", cents / 100.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_top_up_amount() {
+        assert_eq!(top_up_amount(100.0, 50.0), None);
+        assert_eq!(top_up_amount(50.0, 50.0), None);
+        assert_eq!(top_up_amount(0.0, 100.0), Some("100.00".to_string()));
+        assert_eq!(top_up_amount(12.345, 20.0), Some("7.66".to_string()));
+    }
+}
@@ -0,0 +1,152 @@
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt,
+    ops::{Add, AddAssign, Sub, SubAssign},
+    time::Duration,
+};
+
+// Milliseconds since the Unix epoch, the unit of every time field on the wire. Serializes as
+// the bare integer. Constructing one from a raw number always names the unit, which is what
+// keeps seconds from being passed where milliseconds are expected.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct Timestamp(u64);
+
+impl Timestamp {
+    pub const UNIX_EPOCH: Timestamp = Timestamp(0);
+
+    pub const fn from_millis(millis: u64) -> Timestamp {
+        Timestamp(millis)
+    }
+
+    pub const fn from_secs(secs: u64) -> Timestamp {
+        Timestamp(secs * 1000)
+    }
+
+    pub fn now() -> Timestamp {
+        Timestamp::from(Utc::now())
+    }
+
+    pub const fn as_millis(self) -> u64 {
+        self.0
+    }
+
+    pub const fn as_secs(self) -> u64 {
+        self.0 / 1000
+    }
+
+    pub fn to_datetime(self) -> DateTime<Utc> {
+        Utc.timestamp_millis_opt(self.0 as i64)
+            .single()
+            .unwrap_or_default()
+    }
+
+    // Zero if `earlier` is later than `self`.
+    pub fn duration_since(self, earlier: Timestamp) -> Duration {
+        Duration::from_millis(self.0.saturating_sub(earlier.0))
+    }
+
+    pub fn saturating_sub(self, duration: Duration) -> Timestamp {
+        Timestamp(self.0.saturating_sub(millis(duration)))
+    }
+
+    pub fn saturating_add(self, duration: Duration) -> Timestamp {
+        Timestamp(self.0.saturating_add(millis(duration)))
+    }
+}
+
+// Durations too long for u64 milliseconds are clamped rather than truncated.
+fn millis(duration: Duration) -> u64 {
+    u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
+}
+
+impl From<DateTime<Utc>> for Timestamp {
+    fn from(datetime: DateTime<Utc>) -> Timestamp {
+        Timestamp(datetime.timestamp_millis().max(0) as u64)
+    }
+}
+
+impl From<Timestamp> for DateTime<Utc> {
+    fn from(timestamp: Timestamp) -> DateTime<Utc> {
+        timestamp.to_datetime()
+    }
+}
+
+impl Add<Duration> for Timestamp {
+    type Output = Timestamp;
+
+    fn add(self, duration: Duration) -> Timestamp {
+        self.saturating_add(duration)
+    }
+}
+
+impl AddAssign<Duration> for Timestamp {
+    fn add_assign(&mut self, duration: Duration) {
+        *self = *self + duration;
+    }
+}
+
+impl Sub<Duration> for Timestamp {
+    type Output = Timestamp;
+
+    fn sub(self, duration: Duration) -> Timestamp {
+        self.saturating_sub(duration)
+    }
+}
+
+impl SubAssign<Duration> for Timestamp {
+    fn sub_assign(&mut self, duration: Duration) {
+        *self = *self - duration;
+    }
+}
+
+impl Sub for Timestamp {
+    type Output = Duration;
+
+    fn sub(self, earlier: Timestamp) -> Duration {
+        self.duration_since(earlier)
+    }
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            self.to_datetime()
+                .to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timestamp() {
+        let timestamp: Timestamp = serde_json::from_str("1700000000123").unwrap();
+        assert_eq!(timestamp.as_millis(), 1_700_000_000_123);
+        assert_eq!(timestamp.as_secs(), 1_700_000_000);
+        assert_eq!(serde_json::to_string(&timestamp).unwrap(), "1700000000123");
+        assert_eq!(timestamp.to_string(), "2023-11-14T22:13:20.123Z");
+        assert_eq!(Timestamp::from(timestamp.to_datetime()), timestamp);
+
+        let later = timestamp + Duration::from_secs(60);
+        assert!(later > timestamp);
+        assert_eq!(later - timestamp, Duration::from_secs(60));
+        assert_eq!(timestamp - later, Duration::ZERO);
+        assert_eq!(Timestamp::from_secs(2), Timestamp::from_millis(2_000));
+
+        // Arithmetic past either end of the range saturates
+        assert_eq!(timestamp - Duration::MAX, Timestamp::UNIX_EPOCH);
+        assert_eq!(
+            Timestamp::from_millis(1) - Duration::from_millis(2),
+            Timestamp::UNIX_EPOCH
+        );
+        assert_eq!((timestamp + Duration::MAX).as_millis(), u64::MAX);
+    }
+}
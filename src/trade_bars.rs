@@ -0,0 +1,206 @@
+use crate::{Message, Timestamp, Trade};
+use std::collections::BTreeMap;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+
+// When a bar closes: after a number of trades, or once its traded size (in base units) or
+// notional (in quote units) reaches a threshold. Bars aren't split across trades, so a
+// size or notional bar overshoots its threshold by up to its last trade.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BarKind {
+    Tick(usize),
+    Volume(f64),
+    Dollar(f64),
+}
+
+// A bar of one coin's trades.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TradeBar {
+    pub coin: String,
+    // Times of the first and last trades
+    pub open_time: Timestamp,
+    pub close_time: Timestamp,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub trade_count: usize,
+    // Traded size in base units, in total and bought by aggressors
+    pub volume: f64,
+    pub buy_volume: f64,
+    pub notional: f64,
+}
+
+impl TradeBar {
+    fn open(trade: &Trade, px: f64) -> TradeBar {
+        TradeBar {
+            coin: trade.coin.clone(),
+            open_time: trade.time,
+            close_time: trade.time,
+            open: px,
+            high: px,
+            low: px,
+            close: px,
+            trade_count: 0,
+            volume: 0.0,
+            buy_volume: 0.0,
+            notional: 0.0,
+        }
+    }
+
+    // Volume-weighted average price
+    pub fn vwap(&self) -> Option<f64> {
+        (self.volume > 0.0).then(|| self.notional / self.volume)
+    }
+
+    fn add(&mut self, trade: &Trade, px: f64, sz: f64) {
+        self.close_time = trade.time;
+        self.high = self.high.max(px);
+        self.low = self.low.min(px);
+        self.close = px;
+        self.trade_count += 1;
+        self.volume += sz;
+        // The side is the aggressor's: B for buys, A for sells
+        if trade.side == "B" {
+            self.buy_volume += sz;
+        }
+        self.notional += px * sz;
+    }
+
+    fn is_complete(&self, kind: BarKind) -> bool {
+        match kind {
+            BarKind::Tick(trades) => self.trade_count >= trades,
+            BarKind::Volume(volume) => self.volume >= volume,
+            BarKind::Dollar(notional) => self.notional >= notional,
+        }
+    }
+}
+
+// Builds bars of `kind` per coin from Trades messages. Trades with unparsable prices or sizes
+// are skipped.
+pub struct TradeBars {
+    kind: BarKind,
+    open: BTreeMap<String, TradeBar>,
+}
+
+impl TradeBars {
+    pub fn new(kind: BarKind) -> TradeBars {
+        TradeBars {
+            kind,
+            open: BTreeMap::new(),
+        }
+    }
+
+    // The bars the message's trades completed, oldest first.
+    pub fn on_message(&mut self, message: &Message) -> Vec<TradeBar> {
+        let Message::Trades(trades) = message else {
+            return Vec::new();
+        };
+        trades
+            .data
+            .iter()
+            .filter_map(|trade| self.on_trade(trade))
+            .collect()
+    }
+
+    pub fn on_trade(&mut self, trade: &Trade) -> Option<TradeBar> {
+        let (Ok(px), Ok(sz)) = (trade.px.parse::<f64>(), trade.sz.parse::<f64>()) else {
+            return None;
+        };
+        let bar = self
+            .open
+            .entry(trade.coin.clone())
+            .or_insert_with(|| TradeBar::open(trade, px));
+        bar.add(trade, px, sz);
+        if bar.is_complete(self.kind) {
+            self.open.remove(&trade.coin)
+        } else {
+            None
+        }
+    }
+
+    // The bar being built for `coin`, if it has trades.
+    pub fn partial(&self, coin: &str) -> Option<&TradeBar> {
+        self.open.get(coin)
+    }
+}
+
+// Emits the bars completed on `receiver`, until the subscriptions feeding it end. Subscribe
+// the same sender to the Trades channel of each coin of interest.
+pub fn trade_bars(
+    mut receiver: UnboundedReceiver<Message>,
+    kind: BarKind,
+) -> UnboundedReceiver<TradeBar> {
+    let (sender, bars) = unbounded_channel();
+    tokio::spawn(async move {
+        let mut builder = TradeBars::new(kind);
+        while let Some(message) = receiver.recv().await {
+            for bar in builder.on_message(&message) {
+                if sender.send(bar).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+    bars
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Trades;
+    use std::sync::Arc;
+
+    fn trade(coin: &str, side: &str, px: &str, sz: &str, time: u64) -> Trade {
+        Trade {
+            coin: coin.to_string(),
+            ..Trade::test_trade(side, px, sz, time)
+        }
+    }
+    fn trades(trades: Vec<Trade>) -> Message {
+        Message::Trades(Trades {
+            data: Arc::new(trades),
+        })
+    }
+
+    #[test]
+    fn test_bar_kinds() {
+        let mut ticks = TradeBars::new(BarKind::Tick(2));
+        let bars = ticks.on_message(&trades(vec![
+            trade("ETH", "B", "2000", "1", 1),
+            trade("BTC", "A", "50000", "0.1", 2),
+            trade("ETH", "A", "1990", "2", 3),
+            trade("ETH", "B", "2010", "1", 4),
+        ]));
+        assert_eq!(bars.len(), 1);
+        let eth = &bars[0];
+        assert_eq!(
+            (eth.open, eth.high, eth.low, eth.close),
+            (2000.0, 2000.0, 1990.0, 1990.0)
+        );
+        assert_eq!(
+            (eth.open_time, eth.close_time),
+            (Timestamp::from_millis(1), Timestamp::from_millis(3))
+        );
+        assert_eq!((eth.volume, eth.buy_volume), (3.0, 1.0));
+        assert_eq!(eth.vwap(), Some((2000.0 + 2.0 * 1990.0) / 3.0));
+        assert_eq!(ticks.partial("ETH").unwrap().trade_count, 1);
+        assert_eq!(ticks.partial("BTC").unwrap().trade_count, 1);
+
+        // Volume bars close on the trade that reaches the threshold, overshooting it
+        let mut volume = TradeBars::new(BarKind::Volume(2.5));
+        assert!(volume
+            .on_trade(&trade("ETH", "B", "2000", "2", 1))
+            .is_none());
+        let bar = volume.on_trade(&trade("ETH", "B", "2001", "1", 2)).unwrap();
+        assert_eq!((bar.volume, bar.trade_count), (3.0, 2));
+        assert!(volume.partial("ETH").is_none());
+
+        let mut dollar = TradeBars::new(BarKind::Dollar(5_000.0));
+        assert!(dollar
+            .on_trade(&trade("ETH", "B", "2000", "2", 1))
+            .is_none());
+        assert!(dollar.on_trade(&trade("ETH", "B", "bad", "2", 2)).is_none());
+        let bar = dollar.on_trade(&trade("ETH", "A", "2000", "1", 3)).unwrap();
+        assert_eq!(bar.notional, 6_000.0);
+    }
+}
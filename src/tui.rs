@@ -0,0 +1,270 @@
+use crate::{BookLevel, Message, TradeInfo, UserData, UserStateResponse};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Rect},
+    style::{Color, Style, Stylize},
+    widgets::{Block, Borders, Row, Table, Widget},
+};
+use std::collections::{HashMap, VecDeque};
+
+// Panes hold the state for one widget and are updated straight from subscription messages
+// with `on_message`, which returns whether anything changed so callers only redraw when
+// needed. Render them with `frame.render_widget(&pane, area)`.
+
+#[derive(Debug, Clone)]
+pub struct BookPane {
+    pub coin: String,
+    pub depth: usize,
+    // (px, sz) per level, best first
+    bids: Vec<(String, String)>,
+    asks: Vec<(String, String)>,
+}
+
+impl BookPane {
+    pub fn new(coin: &str, depth: usize) -> BookPane {
+        BookPane {
+            coin: coin.to_string(),
+            depth,
+            bids: Vec::new(),
+            asks: Vec::new(),
+        }
+    }
+
+    pub fn on_message(&mut self, message: &Message) -> bool {
+        let Message::L2Book(l2_book) = message else {
+            return false;
+        };
+        let [bids, asks] = l2_book.data.levels.as_slice() else {
+            return false;
+        };
+        if l2_book.data.coin != self.coin {
+            return false;
+        }
+        let levels = |levels: &[BookLevel]| {
+            levels
+                .iter()
+                .take(self.depth)
+                .map(|level| (level.px.clone(), level.sz.clone()))
+                .collect()
+        };
+        self.bids = levels(bids);
+        self.asks = levels(asks);
+        true
+    }
+}
+
+impl Widget for &BookPane {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let asks = self.asks.iter().rev().map(|(px, sz)| {
+            Row::new(vec![px.clone(), sz.clone()]).style(Style::default().fg(Color::Red))
+        });
+        let bids = self.bids.iter().map(|(px, sz)| {
+            Row::new(vec![px.clone(), sz.clone()]).style(Style::default().fg(Color::Green))
+        });
+        Table::new(
+            asks.chain(bids),
+            [Constraint::Percentage(50), Constraint::Percentage(50)],
+        )
+        .header(Row::new(vec!["Price", "Size"]).bold())
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(" {} ", self.coin)),
+        )
+        .render(area, buf);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PositionRow {
+    pub coin: String,
+    pub szi: f64,
+    pub entry_px: Option<f64>,
+    pub mark_px: Option<f64>,
+    pub liquidation_px: Option<f64>,
+}
+
+impl PositionRow {
+    pub fn unrealized_pnl(&self) -> Option<f64> {
+        Some((self.mark_px? - self.entry_px?) * self.szi)
+    }
+}
+
+// Positions come from (polled) user state, there's no websocket feed for them. Mark prices
+// are kept live from AllMids so unrealized pnl moves between polls.
+#[derive(Debug, Clone, Default)]
+pub struct PositionsPane {
+    positions: Vec<PositionRow>,
+}
+
+impl PositionsPane {
+    pub fn positions(&self) -> &[PositionRow] {
+        &self.positions
+    }
+
+    pub fn on_user_state(&mut self, user_state: &UserStateResponse) {
+        let marks: HashMap<String, Option<f64>> = self
+            .positions
+            .drain(..)
+            .map(|position| (position.coin, position.mark_px))
+            .collect();
+        self.positions = user_state
+            .asset_positions
+            .iter()
+            .map(|asset_position| {
+                let position = &asset_position.position;
+                PositionRow {
+                    coin: position.coin.clone(),
+                    szi: position.szi.parse().unwrap_or_default(),
+                    entry_px: position.entry_px.as_ref().and_then(|px| px.parse().ok()),
+                    mark_px: marks.get(&position.coin).copied().flatten(),
+                    liquidation_px: position
+                        .liquidation_px
+                        .as_ref()
+                        .and_then(|px| px.parse().ok()),
+                }
+            })
+            .collect();
+    }
+
+    pub fn on_message(&mut self, message: &Message) -> bool {
+        let Message::AllMids(all_mids) = message else {
+            return false;
+        };
+        let mut changed = false;
+        for position in &mut self.positions {
+            if let Some(mid) = all_mids
+                .data
+                .mids
+                .get(&position.coin)
+                .and_then(|mid| mid.parse().ok())
+            {
+                changed |= position.mark_px != Some(mid);
+                position.mark_px = Some(mid);
+            }
+        }
+        changed
+    }
+}
+
+impl Widget for &PositionsPane {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let fmt = |value: Option<f64>| value.map(|value| value.to_string()).unwrap_or_default();
+        let rows = self.positions.iter().map(|position| {
+            let pnl = position.unrealized_pnl();
+            let color = match pnl {
+                Some(pnl) if pnl < 0.0 => Color::Red,
+                _ => Color::Green,
+            };
+            Row::new(vec![
+                position.coin.clone(),
+                position.szi.to_string(),
+                fmt(position.entry_px),
+                fmt(position.mark_px),
+                fmt(position.liquidation_px),
+                pnl.map(|pnl| format!("{pnl:.2}")).unwrap_or_default(),
+            ])
+            .style(Style::default().fg(color))
+        });
+        Table::new(rows, [Constraint::Ratio(1, 6); 6])
+            .header(Row::new(vec!["Coin", "Size", "Entry", "Mark", "Liq", "uPnL"]).bold())
+            .block(Block::default().borders(Borders::ALL).title(" Positions "))
+            .render(area, buf);
+    }
+}
+
+// The most recent fills, newest first.
+#[derive(Debug, Clone)]
+pub struct FillsPane {
+    pub capacity: usize,
+    fills: VecDeque<TradeInfo>,
+}
+
+impl FillsPane {
+    pub fn new(capacity: usize) -> FillsPane {
+        FillsPane {
+            capacity,
+            fills: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    pub fn fills(&self) -> impl Iterator<Item = &TradeInfo> {
+        self.fills.iter()
+    }
+
+    pub fn on_message(&mut self, message: &Message) -> bool {
+        let fills = match message {
+            Message::UserFills(user_fills) => &user_fills.data.fills,
+            Message::User(user) => match &*user.data {
+                UserData::Fills(fills) => fills,
+                _ => return false,
+            },
+            _ => return false,
+        };
+        for fill in fills {
+            if self.fills.iter().any(|seen| seen.tid == fill.tid) {
+                continue;
+            }
+            self.fills.push_front(fill.clone());
+        }
+        self.fills.truncate(self.capacity);
+        !fills.is_empty()
+    }
+}
+
+impl Widget for &FillsPane {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let rows = self.fills.iter().map(|fill| {
+            let color = if fill.side == "B" {
+                Color::Green
+            } else {
+                Color::Red
+            };
+            Row::new(vec![
+                fill.coin.clone(),
+                fill.dir.clone(),
+                fill.px.clone(),
+                fill.sz.clone(),
+                fill.fee.clone(),
+            ])
+            .style(Style::default().fg(color))
+        });
+        Table::new(rows, [Constraint::Ratio(1, 5); 5])
+            .header(Row::new(vec!["Coin", "Dir", "Price", "Size", "Fee"]).bold())
+            .block(Block::default().borders(Borders::ALL).title(" Fills "))
+            .render(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{L2Book, L2BookData, Timestamp};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_book_pane() {
+        let level = |px: &str| BookLevel::test_level(px, "1");
+        let mut pane = BookPane::new("ETH", 1);
+        let message = Message::L2Book(L2Book {
+            data: Arc::new(L2BookData {
+                coin: "ETH".to_string(),
+                time: Timestamp::from_millis(1),
+                levels: vec![
+                    vec![level("1999"), level("1998")],
+                    vec![level("2001"), level("2002")],
+                ],
+            }),
+        });
+        assert!(pane.on_message(&message));
+        assert!(!pane.on_message(&Message::Pong));
+
+        let area = Rect::new(0, 0, 20, 5);
+        let mut buf = Buffer::empty(area);
+        (&pane).render(area, &mut buf);
+        let text: String = buf.content().iter().map(|cell| cell.symbol()).collect();
+        assert!(text.contains("2001"));
+        assert!(text.contains("1999"));
+        assert!(!text.contains("1998"));
+    }
+}
@@ -0,0 +1,145 @@
+use crate::{Message, Meta, UserData, UserStateResponse};
+use lazy_static::lazy_static;
+use log::warn;
+use serde_json::{Map, Value};
+use std::{
+    collections::HashSet,
+    sync::{Mutex, RwLock},
+};
+
+// Fields the exchange sent that the SDK doesn't model yet. The types most likely to gain
+// fields (trades, fills, orders, fundings, asset contexts, positions) keep them in `extra`
+// rather than dropping them, and they're reported to the hook set with
+// `set_unknown_fields_hook` as messages and responses arrive. `extra` isn't serialized, so
+// re-encoded values stay in the SDK's schema.
+pub type ExtraFields = Map<String, Value>;
+
+#[derive(Debug, Clone, Copy)]
+pub struct UnknownFields<'a> {
+    // The SDK type that didn't model the fields, e.g. "TradeInfo"
+    pub type_name: &'static str,
+    pub fields: &'a ExtraFields,
+}
+
+type Hook = Box<dyn Fn(&UnknownFields) + Send + Sync>;
+
+lazy_static! {
+    static ref HOOK: RwLock<Option<Hook>> = RwLock::new(None);
+    static ref WARNED: Mutex<HashSet<(&'static str, String)>> = Mutex::new(HashSet::new());
+}
+
+// Replaces the default hook, which logs a warning the first time each unknown field is seen:
+//
+//     set_unknown_fields_hook(|unknown| {
+//         metrics::counter!("hl_unknown_fields", "type" => unknown.type_name).increment(1);
+//     });
+pub fn set_unknown_fields_hook(hook: impl Fn(&UnknownFields) + Send + Sync + 'static) {
+    if let Ok(mut current) = HOOK.write() {
+        *current = Some(Box::new(hook));
+    }
+}
+
+pub(crate) fn report(type_name: &'static str, fields: &ExtraFields) {
+    if fields.is_empty() {
+        return;
+    }
+    let unknown = UnknownFields { type_name, fields };
+    match HOOK.read().as_deref() {
+        Ok(Some(hook)) => hook(&unknown),
+        _ => warn_once(&unknown),
+    }
+}
+
+fn warn_once(unknown: &UnknownFields) {
+    let Ok(mut warned) = WARNED.lock() else {
+        return;
+    };
+    for field in unknown.fields.keys() {
+        if warned.insert((unknown.type_name, field.clone())) {
+            warn!(
+                "{} has a field the SDK doesn't model yet: {field}",
+                unknown.type_name
+            );
+        }
+    }
+}
+
+// Reports the unknown fields of the parts of `message` that capture them.
+pub(crate) fn report_message(message: &Message) {
+    match message {
+        Message::Trades(trades) => trades
+            .data
+            .iter()
+            .for_each(|trade| report("Trade", &trade.extra)),
+        Message::UserFills(fills) => fills
+            .data
+            .fills
+            .iter()
+            .for_each(|fill| report("TradeInfo", &fill.extra)),
+        Message::User(user) => match &*user.data {
+            UserData::Fills(fills) => fills
+                .iter()
+                .for_each(|fill| report("TradeInfo", &fill.extra)),
+            UserData::Funding(funding) => report("UserFunding", &funding.extra),
+            _ => {}
+        },
+        Message::OrderUpdates(updates) => updates
+            .data
+            .iter()
+            .for_each(|update| report("BasicOrder", &update.order.extra)),
+        Message::UserFundings(fundings) => fundings
+            .data
+            .fundings
+            .iter()
+            .for_each(|funding| report("UserFunding", &funding.extra)),
+        Message::ActiveAssetCtx(ctx) => report("PerpAssetCtx", &ctx.data.ctx.extra),
+        _ => {}
+    }
+}
+
+pub(crate) fn report_user_state(user_state: &UserStateResponse) {
+    report("UserStateResponse", &user_state.extra);
+    user_state
+        .asset_positions
+        .iter()
+        .for_each(|position| report("PositionData", &position.position.extra));
+}
+
+pub(crate) fn report_meta(meta: &Meta) {
+    meta.universe
+        .iter()
+        .for_each(|asset| report("AssetMeta", &asset.extra));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_unknown_fields_are_captured_and_reported() {
+        let message: Message = serde_json::from_str(
+            r#"{"channel": "trades", "data": [{"coin": "ETH", "side": "B", "px": "2000",
+            "sz": "1", "time": 1, "hash": "0x00", "tid": 2, "users": ["0x01", "0x02"]}]}"#,
+        )
+        .unwrap();
+        let Message::Trades(trades) = &message else {
+            panic!("expected trades");
+        };
+        assert_eq!(trades.data[0].extra["users"][1], "0x02");
+        assert!(!serde_json::to_string(&message).unwrap().contains("users"));
+
+        let reported = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&reported);
+        set_unknown_fields_hook(move |unknown| {
+            let fields = unknown.fields.keys().cloned().collect::<Vec<_>>();
+            sink.lock().unwrap().push((unknown.type_name, fields));
+        });
+        report_message(&message);
+        // The hook is global, so other tests' reports may land here too
+        assert!(reported
+            .lock()
+            .unwrap()
+            .contains(&("Trade", vec!["users".to_string()])));
+    }
+}
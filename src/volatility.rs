@@ -0,0 +1,331 @@
+use crate::{CandleData, Message, Timestamp};
+use std::{collections::VecDeque, sync::Arc, time::Duration};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+
+// The volatility of a coin's log price, estimated from a stream of websocket messages. Every
+// estimator reports it per square root of a second, however often it samples; scale it by the
+// square root of a horizon in seconds for that horizon, e.g. `vol * (86_400f64).sqrt()` for a
+// day. Multiply by the mid for the volatility in price units that `InventoryQuoter` takes.
+//
+// Candles are only used once closed, which shows when the next candle's first update arrives.
+pub trait VolatilityEstimator {
+    // Returns the updated volatility when the message changed it.
+    fn on_message(&mut self, message: &Message) -> Option<f64>;
+
+    fn value(&self) -> Option<f64>;
+}
+
+// Adapts the receiver of a subscription into a stream of volatilities, updated on every message
+// that changes them. The stream ends with the subscription.
+//
+//     let (sender, receiver) = unbounded_channel();
+//     info_client.subscribe(Subscription::trades("ETH"), sender).await?;
+//     let mut vol = volatilities(receiver, EwmaVolatility::new("ETH", Duration::from_secs(300)));
+pub fn volatilities<V: VolatilityEstimator + Send + 'static>(
+    mut receiver: UnboundedReceiver<Message>,
+    mut estimator: V,
+) -> UnboundedReceiver<f64> {
+    let (sender, vols) = unbounded_channel();
+    tokio::spawn(async move {
+        while let Some(message) = receiver.recv().await {
+            if let Some(vol) = estimator.on_message(&message) {
+                if sender.send(vol).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+    vols
+}
+
+// Tracks the candle `coin` is building, to tell when it closes.
+struct CandleCloser {
+    coin: String,
+    building: Option<Arc<CandleData>>,
+}
+
+impl CandleCloser {
+    fn new(coin: &str) -> CandleCloser {
+        CandleCloser {
+            coin: coin.to_string(),
+            building: None,
+        }
+    }
+
+    // The candle the message's candle followed, if this is the first update of a new one.
+    fn on_message(&mut self, message: &Message) -> Option<Arc<CandleData>> {
+        let Message::Candle(candle) = message else {
+            return None;
+        };
+        if candle.data.coin != self.coin {
+            return None;
+        }
+        match self.building.replace(candle.data.clone()) {
+            Some(previous) if previous.time_open < candle.data.time_open => Some(previous),
+            _ => None,
+        }
+    }
+}
+
+// Prices of `coin`'s trades and closed candles, one per timestamp: later trades at the same
+// millisecond are dropped, since there is no time between them to scale their returns by.
+struct Prices {
+    candles: CandleCloser,
+    last: Option<(Timestamp, f64)>,
+}
+
+impl Prices {
+    fn new(coin: &str) -> Prices {
+        Prices {
+            candles: CandleCloser::new(coin),
+            last: None,
+        }
+    }
+
+    // The log returns the message's prices make, with the time each spans.
+    fn returns(&mut self, message: &Message) -> Vec<(Timestamp, Duration, f64)> {
+        let prices: Vec<(Timestamp, f64)> = match message {
+            Message::Trades(trades) => trades
+                .data
+                .iter()
+                .filter(|trade| trade.coin == self.candles.coin)
+                .filter_map(|trade| Some((trade.time, trade.px.parse().ok()?)))
+                .collect(),
+            Message::Candle(_) => self
+                .candles
+                .on_message(message)
+                .and_then(|candle| Some((candle.time_close, candle.close.parse().ok()?)))
+                .into_iter()
+                .collect(),
+            _ => Vec::new(),
+        };
+        let mut returns = Vec::new();
+        for (time, px) in prices {
+            if px <= 0.0 {
+                continue;
+            }
+            match self.last {
+                Some((last_time, _)) if time <= last_time => continue,
+                Some((last_time, last_px)) => {
+                    returns.push((time, time.duration_since(last_time), (px / last_px).ln()))
+                }
+                None => {}
+            }
+            self.last = Some((time, px));
+        }
+        returns
+    }
+}
+
+// Realized volatility of the returns in the last `window`, measured from the latest price:
+// the square root of their summed squares over the time they span.
+pub struct RealizedVolatility {
+    window: Duration,
+    prices: Prices,
+    returns: VecDeque<(Timestamp, Duration, f64)>,
+}
+
+impl RealizedVolatility {
+    pub fn new(coin: &str, window: Duration) -> RealizedVolatility {
+        RealizedVolatility {
+            window,
+            prices: Prices::new(coin),
+            returns: VecDeque::new(),
+        }
+    }
+}
+
+impl VolatilityEstimator for RealizedVolatility {
+    fn on_message(&mut self, message: &Message) -> Option<f64> {
+        let returns = self.prices.returns(message);
+        let latest = returns.last()?.0;
+        self.returns.extend(returns);
+        let cutoff = latest.saturating_sub(self.window);
+        while self
+            .returns
+            .front()
+            .is_some_and(|(time, _, _)| *time < cutoff)
+        {
+            self.returns.pop_front();
+        }
+        self.value()
+    }
+
+    fn value(&self) -> Option<f64> {
+        let span: Duration = self.returns.iter().map(|(_, span, _)| *span).sum();
+        let squares: f64 = self.returns.iter().map(|(_, _, r)| r * r).sum();
+        (!span.is_zero()).then(|| (squares / span.as_secs_f64()).sqrt())
+    }
+}
+
+// Exponentially weighted volatility: each return's variance rate is weighted by the time it
+// spans, so the weight of past returns halves every `half_life` of time, not of updates.
+pub struct EwmaVolatility {
+    half_life: Duration,
+    prices: Prices,
+    variance_rate: Option<f64>,
+}
+
+impl EwmaVolatility {
+    pub fn new(coin: &str, half_life: Duration) -> EwmaVolatility {
+        EwmaVolatility {
+            half_life,
+            prices: Prices::new(coin),
+            variance_rate: None,
+        }
+    }
+}
+
+impl VolatilityEstimator for EwmaVolatility {
+    fn on_message(&mut self, message: &Message) -> Option<f64> {
+        let returns = self.prices.returns(message);
+        if returns.is_empty() {
+            return None;
+        }
+        for (_, span, r) in returns {
+            let span = span.as_secs_f64();
+            let rate = r * r / span;
+            self.variance_rate = Some(match self.variance_rate {
+                Some(variance_rate) => {
+                    let alpha = 1.0 - 0.5f64.powf(span / self.half_life.as_secs_f64());
+                    variance_rate + alpha * (rate - variance_rate)
+                }
+                None => rate,
+            });
+        }
+        self.value()
+    }
+
+    fn value(&self) -> Option<f64> {
+        self.variance_rate.map(f64::sqrt)
+    }
+}
+
+// Parkinson's range-based volatility of the last `window` closed candles, from their highs and
+// lows. It doesn't see overnight-style gaps between candles, which a continuous market barely
+// has, and needs far fewer candles than close-to-close returns for the same accuracy.
+pub struct ParkinsonVolatility {
+    window: usize,
+    candles: CandleCloser,
+    // Each candle's variance rate
+    variances: VecDeque<f64>,
+}
+
+impl ParkinsonVolatility {
+    pub fn new(coin: &str, window: usize) -> ParkinsonVolatility {
+        ParkinsonVolatility {
+            window,
+            candles: CandleCloser::new(coin),
+            variances: VecDeque::with_capacity(window),
+        }
+    }
+}
+
+impl VolatilityEstimator for ParkinsonVolatility {
+    fn on_message(&mut self, message: &Message) -> Option<f64> {
+        let candle = self.candles.on_message(message)?;
+        let (Ok(high), Ok(low)) = (candle.high.parse::<f64>(), candle.low.parse::<f64>()) else {
+            return None;
+        };
+        if low <= 0.0 {
+            return None;
+        }
+        let range = (high / low).ln();
+        let span = candle.interval.duration().as_secs_f64();
+        self.variances
+            .push_back(range * range / (4.0 * 2f64.ln()) / span);
+        while self.variances.len() > self.window {
+            self.variances.pop_front();
+        }
+        self.value()
+    }
+
+    fn value(&self) -> Option<f64> {
+        let count = self.variances.len();
+        (count > 0).then(|| (self.variances.iter().sum::<f64>() / count as f64).sqrt())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Candle, CandleInterval, Trade, Trades};
+
+    fn trades(prices: &[(u64, f64)]) -> Message {
+        Message::Trades(Trades {
+            data: Arc::new(
+                prices
+                    .iter()
+                    .map(|&(time, px)| Trade::test_trade("B", &px.to_string(), "1", time))
+                    .collect(),
+            ),
+        })
+    }
+
+    fn candle(minute: u64, high: f64, low: f64, close: f64) -> Message {
+        let open = minute * 60_000;
+        Message::Candle(Candle {
+            data: Arc::new(CandleData {
+                time_close: Timestamp::from_millis(open + 59_999),
+                close: close.to_string(),
+                high: high.to_string(),
+                interval: CandleInterval::OneMinute,
+                low: low.to_string(),
+                num_trades: 1,
+                open: close.to_string(),
+                coin: "ETH".to_string(),
+                time_open: Timestamp::from_millis(open),
+                volume: "1".to_string(),
+            }),
+        })
+    }
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-12
+    }
+
+    #[test]
+    fn test_estimators() {
+        let up = 1.01f64.ln();
+        let mut realized = RealizedVolatility::new("ETH", Duration::from_secs(2));
+        assert_eq!(realized.on_message(&trades(&[(0, 100.0)])), None);
+        // The second trade at 1s is dropped, and the return to 2s spans a second
+        let vol = realized
+            .on_message(&trades(&[(1_000, 101.0), (1_000, 50.0), (2_000, 100.0)]))
+            .unwrap();
+        assert!(close(vol, ((up * up + up * up) / 2.0).sqrt()));
+        // Returns older than the window fall out
+        let vol = realized.on_message(&trades(&[(5_000, 101.0)])).unwrap();
+        assert!(close(vol, (up * up / 3.0).sqrt()));
+
+        let mut ewma = EwmaVolatility::new("ETH", Duration::from_secs(1));
+        ewma.on_message(&trades(&[(0, 100.0), (1_000, 101.0)]));
+        assert!(close(ewma.value().unwrap(), up));
+        // A return a half life later counts as much as everything before it
+        let vol = ewma.on_message(&trades(&[(2_000, 101.0)])).unwrap();
+        assert!(close(vol, (up * up / 2.0).sqrt()));
+
+        // Updates of the building candle are ignored until the next one starts
+        let mut parkinson = ParkinsonVolatility::new("ETH", 2);
+        assert_eq!(parkinson.on_message(&candle(0, 101.0, 100.0, 100.5)), None);
+        assert_eq!(parkinson.on_message(&candle(0, 102.0, 100.0, 101.0)), None);
+        let vol = parkinson
+            .on_message(&candle(1, 101.0, 101.0, 101.0))
+            .unwrap();
+        let range = 1.02f64.ln();
+        assert!(close(
+            vol,
+            (range * range / (4.0 * 2f64.ln()) / 60.0).sqrt()
+        ));
+
+        // Candle closes feed the return-based estimators too
+        let mut realized = RealizedVolatility::new("ETH", Duration::from_secs(600));
+        realized.on_message(&candle(0, 101.0, 100.0, 100.0));
+        realized.on_message(&candle(1, 101.0, 100.0, 101.0));
+        assert_eq!(realized.value(), None);
+        let vol = realized
+            .on_message(&candle(2, 101.0, 100.0, 101.0))
+            .unwrap();
+        assert!(close(vol, (up * up / 60.0).sqrt()));
+    }
+}
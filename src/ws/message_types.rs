@@ -1,52 +1,118 @@
-use crate::ws::sub_structs::*;
-use serde::Deserialize;
+use crate::{ws::sub_structs::*, Subscription};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
-#[derive(Deserialize, Clone, Debug)]
+// Channel payloads are behind an `Arc`, so delivering a message to each subscriber copies a
+// pointer rather than the payload. Use `Arc::make_mut` or `Arc::unwrap_or_clone` for an owned,
+// mutable payload.
+#[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct Trades {
-    pub data: Vec<Trade>,
+    pub data: Arc<Vec<Trade>>,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct L2Book {
-    pub data: L2BookData,
+    pub data: Arc<L2BookData>,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct AllMids {
-    pub data: AllMidsData,
+    pub data: Arc<AllMidsData>,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct User {
-    pub data: UserData,
+    pub data: Arc<UserData>,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct UserFills {
-    pub data: UserFillsData,
+    pub data: Arc<UserFillsData>,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct Candle {
-    pub data: CandleData,
+    pub data: Arc<CandleData>,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct OrderUpdates {
-    pub data: Vec<OrderUpdate>,
+    pub data: Arc<Vec<OrderUpdate>>,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct UserFundings {
-    pub data: UserFundingsData,
+    pub data: Arc<UserFundingsData>,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct UserNonFundingLedgerUpdates {
-    pub data: UserNonFundingLedgerUpdatesData,
+    pub data: Arc<UserNonFundingLedgerUpdatesData>,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct Notification {
-    pub data: NotificationData,
+    pub data: Arc<NotificationData>,
+}
+
+// An error sent by the exchange, either on the "error" channel or as a plain text frame, e.g.
+// in response to an invalid subscription or one over the subscription limit.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct WsError {
+    pub data: String,
+}
+
+// Sent to the consumers on a connection the exchange closed. 1005 means no code was given.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct WsClosed {
+    pub code: u16,
+    pub reason: String,
+}
+
+// Sent to the consumers of a subscription the exchange refused, after which it's dropped.
+// `active` counts the wire subscriptions left, against `limit`.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct SubscriptionRejected {
+    pub subscription: Subscription,
+    pub reason: String,
+    pub active: usize,
+    pub limit: usize,
+}
+
+// Sent to a bounded subscriber whose queue filled up, as its last queued message.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SlowConsumer {
+    pub subscription_id: u32,
+    pub depth: usize,
+    pub capacity: usize,
+    pub evicted: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WsErrorKind {
+    InvalidSubscription,
+    SubscriptionLimit,
+    RateLimited,
+    Other,
+}
+
+impl WsError {
+    pub fn kind(&self) -> WsErrorKind {
+        let data = self.data.to_lowercase();
+        if data.contains("rate limit") || data.contains("too many") {
+            WsErrorKind::RateLimited
+        } else if data.contains("more than") && data.contains("subscriptions") {
+            WsErrorKind::SubscriptionLimit
+        } else if data.contains("subscription") || data.contains("subscribe") {
+            WsErrorKind::InvalidSubscription
+        } else {
+            WsErrorKind::Other
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct ActiveAssetCtx {
+    pub data: Arc<ActiveAssetCtxData>,
 }
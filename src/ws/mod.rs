@@ -1,7 +1,13 @@
 mod message_types;
+mod sequence;
 mod sub_structs;
+mod subscription_key;
+mod transport;
 mod ws_manager;
 pub use message_types::*;
+pub use sequence::{Resync, ResyncReason};
 pub use sub_structs::*;
-pub(crate) use ws_manager::WsManager;
-pub use ws_manager::{Message, Subscription};
+pub(crate) use ws_manager::{Connector, Consumer, WsManager};
+pub use ws_manager::{Message, SlowConsumerPolicy, Subscription, WsTraffic};
+#[cfg(any(test, feature = "sim"))]
+pub(crate) use {transport::CountingStream, ws_manager::TrafficCounters};
@@ -0,0 +1,168 @@
+use crate::{Message, Subscription, Timestamp};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+// Sent to the consumers of an orderUpdates or userFills subscription when updates were likely
+// missed. Consumers should refresh their state from the REST API, e.g. fetch fills since
+// `since`, the latest update time delivered before the gap. `validate_books` also sends them
+// for l2Book subscriptions whose books drifted from the exchange's.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Resync {
+    pub subscription: Subscription,
+    pub since: Timestamp,
+    pub reason: ResyncReason,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ResyncReason {
+    // The connection failed to read a frame since the last update
+    Interrupted,
+    // An update is older than one already delivered
+    OutOfOrder,
+    // The book differs from a REST snapshot of it, which follows the resync
+    BookDiverged,
+}
+
+// The latest update time seen on each orderUpdates and userFills stream. Neither channel
+// carries sequence numbers, so gaps are inferred: after a read error on the stream's
+// connection, and when updates arrive older than ones already delivered. Snapshots carry the
+// full state and reset the stream.
+#[derive(Default)]
+pub(crate) struct SequenceTracker {
+    latest: HashMap<Subscription, Timestamp>,
+    interrupted: HashSet<Subscription>,
+}
+
+impl SequenceTracker {
+    pub(crate) fn interrupt(&mut self, subscription: &Subscription) {
+        if self.latest.contains_key(subscription) {
+            self.interrupted.insert(subscription.clone());
+        }
+    }
+
+    pub(crate) fn forget(&mut self, subscription: &Subscription) {
+        self.latest.remove(subscription);
+        self.interrupted.remove(subscription);
+    }
+
+    pub(crate) fn observe(
+        &mut self,
+        subscription: &Subscription,
+        message: &Message,
+    ) -> Option<Resync> {
+        let (times, is_snapshot): (Vec<Timestamp>, _) = match message {
+            Message::OrderUpdates(order_updates) => (
+                order_updates
+                    .data
+                    .iter()
+                    .map(|update| update.status_timestamp)
+                    .collect(),
+                false,
+            ),
+            Message::UserFills(user_fills) => (
+                user_fills.data.fills.iter().map(|fill| fill.time).collect(),
+                user_fills.data.is_snapshot.unwrap_or(false),
+            ),
+            _ => return None,
+        };
+        let newest = times.iter().copied().max();
+        if is_snapshot {
+            self.interrupted.remove(subscription);
+            self.latest
+                .insert(subscription.clone(), newest.unwrap_or_default());
+            return None;
+        }
+
+        let previous = self.latest.get(subscription).copied();
+        let reason = if self.interrupted.remove(subscription) {
+            Some(ResyncReason::Interrupted)
+        } else if previous.is_some_and(|previous| times.iter().any(|time| *time < previous)) {
+            Some(ResyncReason::OutOfOrder)
+        } else {
+            None
+        };
+        if let Some(newest) = newest.max(previous) {
+            self.latest.insert(subscription.clone(), newest);
+        }
+
+        let since = previous?;
+        reason.map(|reason| Resync {
+            subscription: subscription.clone(),
+            since,
+            reason,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BasicOrder, OrderUpdate, OrderUpdates};
+    use ethers::types::H160;
+    use std::sync::Arc;
+
+    fn order_update(oid: u64, time: u64) -> Message {
+        Message::OrderUpdates(OrderUpdates {
+            data: Arc::new(vec![OrderUpdate {
+                order: BasicOrder {
+                    coin: "ETH".to_string(),
+                    side: "B".to_string(),
+                    limit_px: "2000.0".to_string(),
+                    sz: "0.1".to_string(),
+                    oid,
+                    timestamp: Timestamp::from_millis(time),
+                    orig_sz: "0.1".to_string(),
+                    cloid: None,
+                    extra: Default::default(),
+                },
+                status: "open".to_string(),
+                status_timestamp: Timestamp::from_millis(time),
+            }]),
+        })
+    }
+
+    #[test]
+    fn test_sequence_gaps() {
+        let subscription = Subscription::OrderUpdates {
+            user: H160::from_low_u64_be(1),
+        };
+        let mut tracker = SequenceTracker::default();
+
+        assert!(tracker
+            .observe(&subscription, &order_update(1, 1_000))
+            .is_none());
+        assert!(tracker
+            .observe(&subscription, &order_update(2, 1_000))
+            .is_none());
+        assert!(tracker
+            .observe(&subscription, &order_update(3, 2_000))
+            .is_none());
+
+        let resync = tracker
+            .observe(&subscription, &order_update(4, 1_500))
+            .unwrap();
+        assert_eq!(resync.reason, ResyncReason::OutOfOrder);
+        assert_eq!(resync.since, Timestamp::from_millis(2_000));
+
+        tracker.interrupt(&subscription);
+        let resync = tracker
+            .observe(&subscription, &order_update(5, 3_000))
+            .unwrap();
+        assert_eq!(resync.reason, ResyncReason::Interrupted);
+        assert!(matches!(
+            resync.subscription,
+            Subscription::OrderUpdates { .. }
+        ));
+        assert!(tracker
+            .observe(&subscription, &order_update(6, 3_000))
+            .is_none());
+
+        // Streams are only tracked once they delivered an update
+        tracker.forget(&subscription);
+        tracker.interrupt(&subscription);
+        assert!(tracker
+            .observe(&subscription, &order_update(7, 500))
+            .is_none());
+    }
+}
@@ -1,63 +1,199 @@
+use crate::{
+    prelude::*,
+    serde_helpers::{integer_or_string, number_or_string, option_number_or_string},
+    CandleInterval, Error, ExtraFields, PerpAssetCtx, Timestamp,
+};
 use ethers::types::H160;
-use serde::Deserialize;
+use log::error;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct Trade {
     pub coin: String,
     pub side: String,
+    #[serde(deserialize_with = "number_or_string")]
     pub px: String,
+    #[serde(deserialize_with = "number_or_string")]
     pub sz: String,
-    pub time: u64,
+    pub time: Timestamp,
     pub hash: String,
     pub tid: u64,
+    // Fields the SDK doesn't model yet
+    #[serde(flatten, skip_serializing)]
+    pub extra: ExtraFields,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
 pub struct BookLevel {
+    #[serde(deserialize_with = "number_or_string")]
     pub px: String,
+    #[serde(deserialize_with = "number_or_string")]
     pub sz: String,
+    #[serde(deserialize_with = "integer_or_string")]
     pub n: u64,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
 pub struct L2BookData {
     pub coin: String,
-    pub time: u64,
+    pub time: Timestamp,
     pub levels: Vec<Vec<BookLevel>>,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct AllMidsData {
     pub mids: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dex: Option<String>,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+impl AllMidsData {
+    // The builder-deployed perp DEX these mids are for, or None for the first perp DEX. Taken
+    // from the coin names when the exchange doesn't say.
+    pub fn dex(&self) -> Option<&str> {
+        self.dex.as_deref().or_else(|| {
+            self.mids
+                .keys()
+                .find_map(|coin| coin.split_once(':').map(|(dex, _)| dex))
+        })
+    }
+
+    // Mids of perps, e.g. "BTC", or "xyz:XYZ100" on a builder-deployed DEX.
+    pub fn perp_mids(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.mids()
+            .filter(|(coin, _)| !AllMidsData::is_spot_pair(coin))
+    }
+
+    // Mids of spot pairs, named like "PURR/USDC" or "@107" by their index.
+    pub fn spot_mids(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.mids()
+            .filter(|(coin, _)| AllMidsData::is_spot_pair(coin))
+    }
+
+    pub fn is_spot_pair(coin: &str) -> bool {
+        coin.starts_with('@') || coin.contains('/')
+    }
+
+    fn mids(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.mids
+            .iter()
+            .map(|(coin, mid)| (coin.as_str(), mid.as_str()))
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct TradeInfo {
     pub coin: String,
     pub side: String,
+    #[serde(deserialize_with = "number_or_string")]
     pub px: String,
+    #[serde(deserialize_with = "number_or_string")]
     pub sz: String,
-    pub time: u64,
+    pub time: Timestamp,
     pub hash: String,
+    #[serde(deserialize_with = "number_or_string")]
     pub start_position: String,
     pub dir: String,
+    #[serde(deserialize_with = "number_or_string")]
     pub closed_pnl: String,
     pub oid: u64,
     pub cloid: Option<String>,
     pub crossed: bool,
+    #[serde(deserialize_with = "number_or_string")]
     pub fee: String,
     pub tid: u64,
+    pub fee_token: Option<String>,
+    #[serde(default, deserialize_with = "option_number_or_string")]
+    pub builder_fee: Option<String>,
+    pub liquidation: Option<FillLiquidation>,
+    // Fields the SDK doesn't model yet
+    #[serde(flatten, skip_serializing)]
+    pub extra: ExtraFields,
+}
+
+// Present on fills that were part of a liquidation, for both the liquidated user and the
+// liquidator.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FillLiquidation {
+    pub liquidated_user: Option<H160>,
+    #[serde(deserialize_with = "number_or_string")]
+    pub mark_px: String,
+    pub method: LiquidationMethod,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum LiquidationMethod {
+    Market,
+    Backstop,
+    #[serde(other)]
+    Unknown,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+// A liquidation fill with its numeric fields parsed. `liquidated_user` is `None` when the
+// subscribed user is the one being liquidated, otherwise the subscribed user was the
+// liquidator.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LiquidationFill {
+    pub coin: String,
+    pub time: Timestamp,
+    pub oid: u64,
+    pub tid: u64,
+    pub is_buy: bool,
+    pub px: f64,
+    pub sz: f64,
+    pub mark_px: f64,
+    pub method: LiquidationMethod,
+    pub liquidated_user: Option<H160>,
+    pub closed_pnl: f64,
+    pub fee: f64,
+}
+
+impl TryFrom<&TradeInfo> for LiquidationFill {
+    type Error = Error;
+
+    fn try_from(fill: &TradeInfo) -> Result<LiquidationFill> {
+        let liquidation = fill.liquidation.as_ref().ok_or_else(|| {
+            Error::GenericParse(format!("fill {} is not a liquidation", fill.tid))
+        })?;
+        let parse = |value: &str| value.parse::<f64>().map_err(|_| Error::FloatStringParse);
+        Ok(LiquidationFill {
+            coin: fill.coin.clone(),
+            time: fill.time,
+            oid: fill.oid,
+            tid: fill.tid,
+            is_buy: fill.side == "B",
+            px: parse(&fill.px)?,
+            sz: parse(&fill.sz)?,
+            mark_px: parse(&liquidation.mark_px)?,
+            method: liquidation.method,
+            liquidated_user: liquidation.liquidated_user,
+            closed_pnl: parse(&fill.closed_pnl)?,
+            fee: parse(&fill.fee)?,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LiquidationEvent {
+    // A fill executed as part of a liquidation
+    Fill(LiquidationFill),
+    // The account-level liquidation notice
+    Account(Liquidation),
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
 pub struct UserFillsData {
+    pub is_snapshot: Option<bool>,
     pub user: H160,
     pub fills: Vec<TradeInfo>,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub enum UserData {
     Fills(Vec<TradeInfo>),
@@ -66,67 +202,81 @@ pub enum UserData {
     NonUserCancel(Vec<NonUserCancel>),
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
 pub struct Liquidation {
     pub lid: u64,
-    pub liquidator: String,
-    pub liquidated_user: String,
+    pub liquidator: H160,
+    pub liquidated_user: H160,
+    #[serde(deserialize_with = "number_or_string")]
     pub liquidated_ntl_pos: String,
+    #[serde(deserialize_with = "number_or_string")]
     pub liquidated_account_value: String,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct NonUserCancel {
     pub coin: String,
     pub oid: u64,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct CandleData {
     #[serde(rename = "T")]
-    pub time_close: u64,
+    pub time_close: Timestamp,
     #[serde(rename = "c")]
+    #[serde(deserialize_with = "number_or_string")]
     pub close: String,
     #[serde(rename = "h")]
+    #[serde(deserialize_with = "number_or_string")]
     pub high: String,
     #[serde(rename = "i")]
-    pub interval: String,
+    pub interval: CandleInterval,
     #[serde(rename = "l")]
+    #[serde(deserialize_with = "number_or_string")]
     pub low: String,
     #[serde(rename = "n")]
+    #[serde(deserialize_with = "integer_or_string")]
     pub num_trades: u64,
     #[serde(rename = "o")]
+    #[serde(deserialize_with = "number_or_string")]
     pub open: String,
     #[serde(rename = "s")]
     pub coin: String,
     #[serde(rename = "t")]
-    pub time_open: u64,
+    pub time_open: Timestamp,
     #[serde(rename = "v")]
+    #[serde(deserialize_with = "number_or_string")]
     pub volume: String,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct OrderUpdate {
     pub order: BasicOrder,
     pub status: String,
-    pub status_timestamp: u64,
+    pub status_timestamp: Timestamp,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct BasicOrder {
     pub coin: String,
     pub side: String,
+    #[serde(deserialize_with = "number_or_string")]
     pub limit_px: String,
+    #[serde(deserialize_with = "number_or_string")]
     pub sz: String,
     pub oid: u64,
-    pub timestamp: u64,
+    pub timestamp: Timestamp,
+    #[serde(deserialize_with = "number_or_string")]
     pub orig_sz: String,
     pub cloid: Option<String>,
+    // Fields the SDK doesn't model yet
+    #[serde(flatten, skip_serializing)]
+    pub extra: ExtraFields,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct UserFundingsData {
     pub is_snapshot: Option<bool>,
@@ -134,17 +284,23 @@ pub struct UserFundingsData {
     pub fundings: Vec<UserFunding>,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct UserFunding {
-    pub time: u64,
+    pub time: Timestamp,
     pub coin: String,
+    #[serde(deserialize_with = "number_or_string")]
     pub usdc: String,
+    #[serde(deserialize_with = "number_or_string")]
     pub szi: String,
+    #[serde(deserialize_with = "number_or_string")]
     pub funding_rate: String,
+    // Fields the SDK doesn't model yet
+    #[serde(flatten, skip_serializing)]
+    pub extra: ExtraFields,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct UserNonFundingLedgerUpdatesData {
     pub is_snapshot: Option<bool>,
@@ -152,14 +308,14 @@ pub struct UserNonFundingLedgerUpdatesData {
     pub non_funding_ledger_updates: Vec<LedgerUpdateData>,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct LedgerUpdateData {
-    pub time: u64,
+    pub time: Timestamp,
     pub hash: String,
     pub delta: LedgerUpdate,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 #[serde(tag = "type")]
 pub enum LedgerUpdate {
@@ -178,96 +334,306 @@ pub enum LedgerUpdate {
     SpotGenesis(SpotGenesis),
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct Deposit {
+    #[serde(deserialize_with = "number_or_string")]
     pub usdc: String,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct Withdraw {
+    #[serde(deserialize_with = "number_or_string")]
     pub usdc: String,
     pub nonce: u64,
+    #[serde(deserialize_with = "number_or_string")]
     pub fee: String,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct InternalTransfer {
+    #[serde(deserialize_with = "number_or_string")]
     pub usdc: String,
     pub user: H160,
     pub destination: H160,
+    #[serde(deserialize_with = "number_or_string")]
     pub fee: String,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct SubAccountTransfer {
+    #[serde(deserialize_with = "number_or_string")]
     pub usdc: String,
     pub user: H160,
     pub destination: H160,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct LedgerLiquidation {
-    pub account_value: u64,
+    #[serde(deserialize_with = "number_or_string")]
+    pub account_value: String,
     pub leverage_type: String,
     pub liquidated_positions: Vec<LiquidatedPosition>,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct LiquidatedPosition {
     pub coin: String,
+    #[serde(deserialize_with = "number_or_string")]
     pub szi: String,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct VaultDelta {
     pub vault: H160,
+    #[serde(deserialize_with = "number_or_string")]
     pub usdc: String,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct VaultWithdraw {
     pub vault: H160,
     pub user: H160,
+    #[serde(deserialize_with = "number_or_string")]
     pub requested_usd: String,
+    #[serde(deserialize_with = "number_or_string")]
     pub commission: String,
+    #[serde(deserialize_with = "number_or_string")]
     pub closing_cost: String,
+    #[serde(deserialize_with = "number_or_string")]
     pub basis: String,
+    #[serde(deserialize_with = "number_or_string")]
     pub net_withdrawn_usd: String,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct VaultLeaderCommission {
     pub user: H160,
+    #[serde(deserialize_with = "number_or_string")]
     pub usdc: String,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct AccountClassTransfer {
+    #[serde(deserialize_with = "number_or_string")]
     pub usdc: String,
     pub to_perp: bool,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct SpotTransfer {
     pub token: String,
+    #[serde(deserialize_with = "number_or_string")]
     pub amount: String,
+    #[serde(deserialize_with = "number_or_string")]
     pub usdc_value: String,
     pub user: H160,
     pub destination: H160,
+    #[serde(deserialize_with = "number_or_string")]
     pub fee: String,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct SpotGenesis {
     pub token: String,
+    #[serde(deserialize_with = "number_or_string")]
     pub amount: String,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct ActiveAssetCtxData {
+    pub coin: String,
+    pub ctx: PerpAssetCtx,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct NotificationData {
     pub notification: String,
 }
+
+impl UserData {
+    // Liquidation fills that fail to parse are logged and skipped.
+    pub fn liquidation_events(&self) -> Vec<LiquidationEvent> {
+        match self {
+            UserData::Fills(fills) => fills
+                .iter()
+                .filter(|fill| fill.liquidation.is_some())
+                .filter_map(|fill| match LiquidationFill::try_from(fill) {
+                    Ok(fill) => Some(LiquidationEvent::Fill(fill)),
+                    Err(err) => {
+                        error!("Could not parse liquidation fill {}: {err}", fill.tid);
+                        None
+                    }
+                })
+                .collect(),
+            UserData::Liquidation(liquidation) => {
+                vec![LiquidationEvent::Account(liquidation.clone())]
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+// Test fixtures shared across the crate. Tests override what they care about with struct
+// update syntax, e.g. `TradeInfo { side: "A".to_string(), ..TradeInfo::test_fill(1, 1, "1") }`.
+#[cfg(test)]
+impl Trade {
+    // An ETH trade, with `time` doubling as the trade id.
+    pub(crate) fn test_trade(side: &str, px: &str, sz: &str, time: u64) -> Trade {
+        Trade {
+            coin: "ETH".to_string(),
+            side: side.to_string(),
+            px: px.to_string(),
+            sz: sz.to_string(),
+            time: Timestamp::from_millis(time),
+            hash: String::new(),
+            tid: time,
+            extra: Default::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+impl BookLevel {
+    pub(crate) fn test_level(px: &str, sz: &str) -> BookLevel {
+        BookLevel {
+            px: px.to_string(),
+            sz: sz.to_string(),
+            n: 1,
+        }
+    }
+}
+
+#[cfg(test)]
+impl L2BookData {
+    pub(crate) fn test_book(time: u64, bids: Vec<BookLevel>, asks: Vec<BookLevel>) -> L2BookData {
+        L2BookData {
+            coin: "ETH".to_string(),
+            time: Timestamp::from_millis(time),
+            levels: vec![bids, asks],
+        }
+    }
+}
+
+#[cfg(test)]
+impl TradeInfo {
+    // A fill of an ETH buy at 2000 that opens a long, without fees.
+    pub(crate) fn test_fill(oid: u64, tid: u64, sz: &str) -> TradeInfo {
+        TradeInfo {
+            coin: "ETH".to_string(),
+            side: "B".to_string(),
+            px: "2000".to_string(),
+            sz: sz.to_string(),
+            time: Timestamp::default(),
+            hash: String::new(),
+            start_position: "0".to_string(),
+            dir: "Open Long".to_string(),
+            closed_pnl: "0".to_string(),
+            oid,
+            cloid: None,
+            crossed: false,
+            fee: "0".to_string(),
+            tid,
+            fee_token: None,
+            builder_fee: None,
+            liquidation: None,
+            extra: Default::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Message;
+    use std::sync::Arc;
+
+    fn user_event(payload: &str) -> UserData {
+        let message: Message =
+            serde_json::from_str(&format!(r#"{{"channel":"user","data":{payload}}}"#)).unwrap();
+        match message {
+            Message::User(user) => Arc::unwrap_or_clone(user.data),
+            message => panic!("unexpected message {message:?}"),
+        }
+    }
+
+    #[test]
+    fn test_user_event_fills() {
+        let UserData::Fills(fills) = user_event(
+            r#"{"fills":[{"coin":"ETH","px":"3012.4","sz":"0.05","side":"A","time":1718000000000,
+            "startPosition":"0.05","dir":"Close Long","closedPnl":"1.23","hash":"0xabc",
+            "oid":123456,"crossed":true,"fee":"0.0678","tid":987654321,"feeToken":"USDC",
+            "builderFee":"0.01","liquidation":{"liquidatedUser":"0x010461c14e146ac35fe42271bdc1134ee31c703a",
+            "markPx":"3010.0","method":"market"}}]}"#,
+        ) else {
+            panic!("expected fills");
+        };
+        assert_eq!(fills.len(), 1);
+        assert_eq!(fills[0].fee_token.as_deref(), Some("USDC"));
+        assert_eq!(fills[0].cloid, None);
+        let liquidation = fills[0].liquidation.as_ref().unwrap();
+        assert_eq!(liquidation.method, LiquidationMethod::Market);
+        assert_eq!(liquidation.mark_px, "3010.0");
+    }
+
+    #[test]
+    fn test_user_event_funding() {
+        let UserData::Funding(funding) = user_event(
+            r#"{"funding":{"time":1718000000000,"coin":"BTC","usdc":"-0.8123","szi":"0.3",
+            "fundingRate":"0.0000125"}}"#,
+        ) else {
+            panic!("expected funding");
+        };
+        assert_eq!(funding.coin, "BTC");
+        assert_eq!(funding.usdc, "-0.8123");
+    }
+
+    #[test]
+    fn test_user_event_liquidation() {
+        let UserData::Liquidation(liquidation) = user_event(
+            r#"{"liquidation":{"lid":4242,"liquidator":"0x2b804617c6f63c040377e95bb276811747006f4b",
+            "liquidated_user":"0x010461c14e146ac35fe42271bdc1134ee31c703a",
+            "liquidated_ntl_pos":"1520.33","liquidated_account_value":"12.5"}}"#,
+        ) else {
+            panic!("expected liquidation");
+        };
+        assert_eq!(liquidation.lid, 4242);
+        assert_eq!(liquidation.liquidated_ntl_pos, "1520.33");
+    }
+
+    #[test]
+    fn test_liquidation_events() {
+        let user_data = user_event(
+            r#"{"fills":[{"coin":"ETH","px":"3012.4","sz":"0.05","side":"B","time":1,
+            "startPosition":"-0.05","dir":"Close Short","closedPnl":"-4.5","hash":"0xabc",
+            "oid":1,"crossed":true,"fee":"0","tid":11,
+            "liquidation":{"markPx":"3010.0","method":"backstop"}},
+            {"coin":"ETH","px":"3012.4","sz":"0.1","side":"B","time":1,"startPosition":"0",
+            "dir":"Open Long","closedPnl":"0","hash":"0xabc","oid":2,"crossed":true,"fee":"0.1",
+            "tid":12}]}"#,
+        );
+        let events = user_data.liquidation_events();
+        assert_eq!(events.len(), 1);
+        let LiquidationEvent::Fill(fill) = &events[0] else {
+            panic!("expected liquidation fill");
+        };
+        assert_eq!(fill.method, LiquidationMethod::Backstop);
+        assert_eq!(fill.liquidated_user, None);
+        assert!(fill.is_buy);
+        assert_eq!((fill.sz, fill.mark_px), (0.05, 3010.0));
+    }
+
+    #[test]
+    fn test_user_event_non_user_cancel() {
+        let UserData::NonUserCancel(cancels) =
+            user_event(r#"{"nonUserCancel":[{"coin":"SOL","oid":77},{"coin":"ETH","oid":78}]}"#)
+        else {
+            panic!("expected nonUserCancel");
+        };
+        assert_eq!(cancels.len(), 2);
+        assert_eq!(cancels[1].oid, 78);
+    }
+}
@@ -0,0 +1,219 @@
+use crate::{CandleInterval, Message, Subscription};
+use ethers::types::H160;
+use hashbrown::Equivalent;
+
+// The channel of a subscription, without its parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum Channel {
+    Trades,
+    L2Book,
+    ActiveAssetCtx,
+    UserEvents,
+    UserFills,
+    OrderUpdates,
+    UserFundings,
+    UserNonFundingLedgerUpdates,
+    Notification,
+}
+
+// What wire subscriptions are routed by. Equal subscriptions have equal keys however they were
+// built, unlike their serialized JSON, which depends on field order.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum SubscriptionKey {
+    // allMids, by perp DEX
+    AllMids(Option<String>),
+    Coin(Channel, String),
+    CoinInterval(String, CandleInterval),
+    User(Channel, H160),
+}
+
+// A `SubscriptionKey` borrowing its coin from a message, so routing a message doesn't
+// allocate. Its variants must stay in the same order as `SubscriptionKey`'s, with the same
+// fields, for the two to hash alike.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum KeyRef<'a> {
+    AllMids(Option<&'a str>),
+    Coin(Channel, &'a str),
+    CoinInterval(&'a str, CandleInterval),
+    User(Channel, H160),
+}
+
+impl From<&Subscription> for SubscriptionKey {
+    fn from(subscription: &Subscription) -> SubscriptionKey {
+        match subscription {
+            Subscription::AllMids { dex } => SubscriptionKey::AllMids(dex.clone()),
+            Subscription::Trades { coin } => SubscriptionKey::Coin(Channel::Trades, coin.clone()),
+            Subscription::L2Book { coin } => SubscriptionKey::Coin(Channel::L2Book, coin.clone()),
+            Subscription::ActiveAssetCtx { coin } => {
+                SubscriptionKey::Coin(Channel::ActiveAssetCtx, coin.clone())
+            }
+            Subscription::Candle { coin, interval } => {
+                SubscriptionKey::CoinInterval(coin.clone(), *interval)
+            }
+            Subscription::UserEvents { user } => SubscriptionKey::User(Channel::UserEvents, *user),
+            Subscription::UserFills { user } => SubscriptionKey::User(Channel::UserFills, *user),
+            Subscription::OrderUpdates { user } => {
+                SubscriptionKey::User(Channel::OrderUpdates, *user)
+            }
+            Subscription::UserFundings { user } => {
+                SubscriptionKey::User(Channel::UserFundings, *user)
+            }
+            Subscription::UserNonFundingLedgerUpdates { user } => {
+                SubscriptionKey::User(Channel::UserNonFundingLedgerUpdates, *user)
+            }
+            Subscription::Notification { user } => {
+                SubscriptionKey::User(Channel::Notification, *user)
+            }
+        }
+    }
+}
+
+impl<'a> KeyRef<'a> {
+    // The key of the subscription a message was sent for, if any. userEvents, orderUpdates and
+    // notification messages don't name their user, who is the owner of the connection they
+    // arrived on.
+    pub(crate) fn of(message: &'a Message, owner: Option<H160>) -> Option<KeyRef<'a>> {
+        Some(match message {
+            Message::AllMids(all_mids) => KeyRef::AllMids(all_mids.data.dex()),
+            Message::Trades(trades) => KeyRef::Coin(Channel::Trades, &trades.data.first()?.coin),
+            Message::L2Book(l2_book) => KeyRef::Coin(Channel::L2Book, &l2_book.data.coin),
+            Message::ActiveAssetCtx(active_asset_ctx) => {
+                KeyRef::Coin(Channel::ActiveAssetCtx, &active_asset_ctx.data.coin)
+            }
+            Message::Candle(candle) => {
+                KeyRef::CoinInterval(&candle.data.coin, candle.data.interval)
+            }
+            Message::User(_) => KeyRef::User(Channel::UserEvents, owner?),
+            Message::UserFills(user_fills) => {
+                KeyRef::User(Channel::UserFills, user_fills.data.user)
+            }
+            Message::OrderUpdates(_) => KeyRef::User(Channel::OrderUpdates, owner?),
+            Message::UserFundings(user_fundings) => {
+                KeyRef::User(Channel::UserFundings, user_fundings.data.user)
+            }
+            Message::UserNonFundingLedgerUpdates(user_non_funding_ledger_updates) => KeyRef::User(
+                Channel::UserNonFundingLedgerUpdates,
+                user_non_funding_ledger_updates.data.user,
+            ),
+            Message::Notification(_) => KeyRef::User(Channel::Notification, owner?),
+            Message::SubscriptionResponse
+            | Message::Error(_)
+            | Message::SubscriptionRejected(_)
+            | Message::Closed(_)
+            | Message::SlowConsumer(_)
+            | Message::Resync(_)
+            | Message::Pong => return None,
+        })
+    }
+}
+
+impl Equivalent<SubscriptionKey> for KeyRef<'_> {
+    fn equivalent(&self, key: &SubscriptionKey) -> bool {
+        match (*self, key) {
+            (KeyRef::AllMids(dex), SubscriptionKey::AllMids(other)) => dex == other.as_deref(),
+            (KeyRef::Coin(channel, coin), SubscriptionKey::Coin(other, other_coin)) => {
+                channel == *other && coin == other_coin
+            }
+            (
+                KeyRef::CoinInterval(coin, interval),
+                SubscriptionKey::CoinInterval(other_coin, other),
+            ) => coin == other_coin && interval == *other,
+            (KeyRef::User(channel, user), SubscriptionKey::User(other, other_user)) => {
+                channel == *other && user == *other_user
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Candle, CandleData, L2Book, L2BookData, Notification, NotificationData, Timestamp,
+        UserFills, UserFillsData,
+    };
+    use hashbrown::HashMap;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_messages_find_their_subscriptions() {
+        let alice = H160::from_low_u64_be(1);
+        let candle = CandleData {
+            time_close: Timestamp::default(),
+            close: "2000".to_string(),
+            high: "2001".to_string(),
+            interval: CandleInterval::OneHour,
+            low: "1999".to_string(),
+            num_trades: 1,
+            open: "2000".to_string(),
+            coin: "ETH".to_string(),
+            time_open: Timestamp::default(),
+            volume: "1".to_string(),
+        };
+        let routed = [
+            (
+                Subscription::l2_book("ETH"),
+                Message::L2Book(L2Book {
+                    data: Arc::new(L2BookData {
+                        coin: "ETH".to_string(),
+                        time: Timestamp::default(),
+                        levels: Vec::new(),
+                    }),
+                }),
+            ),
+            (
+                Subscription::candle("ETH", CandleInterval::OneHour),
+                Message::Candle(Candle {
+                    data: Arc::new(candle.clone()),
+                }),
+            ),
+            (
+                Subscription::user_fills(alice),
+                Message::UserFills(UserFills {
+                    data: Arc::new(UserFillsData {
+                        is_snapshot: None,
+                        user: alice,
+                        fills: Vec::new(),
+                    }),
+                }),
+            ),
+            (
+                Subscription::notification(alice),
+                Message::Notification(Notification {
+                    data: Arc::new(NotificationData {
+                        notification: "hi".to_string(),
+                    }),
+                }),
+            ),
+        ];
+        let routes: HashMap<_, _> = routed
+            .iter()
+            .map(|(subscription, _)| (SubscriptionKey::from(subscription), subscription))
+            .collect();
+
+        for (subscription, message) in &routed {
+            let key = KeyRef::of(message, Some(alice)).unwrap();
+            assert_eq!(routes.get(&key), Some(&subscription));
+        }
+
+        // Parameters have to match too
+        let four_hours = Message::Candle(Candle {
+            data: Arc::new(CandleData {
+                interval: CandleInterval::FourHours,
+                ..candle
+            }),
+        });
+        let key = KeyRef::of(&four_hours, None).unwrap();
+        assert_eq!(routes.get(&key), None);
+
+        // Unattributed channels are routed by the connection's owner, and dropped without one;
+        // the others by the user the message names
+        let bob = H160::from_low_u64_be(2);
+        let key = KeyRef::of(&routed[3].1, Some(bob)).unwrap();
+        assert_eq!(routes.get(&key), None);
+        assert_eq!(KeyRef::of(&routed[3].1, None), None);
+        let key = KeyRef::of(&routed[2].1, Some(bob)).unwrap();
+        assert_eq!(routes.get(&key), Some(&&routed[2].0));
+    }
+}
@@ -0,0 +1,286 @@
+// Opens websocket connections to the exchange. Bytes are counted as they come off the socket,
+// before TLS and before decompression, so `WsTraffic` can tell wire bytes from payload bytes.
+//
+// tungstenite rejects frames with the RSV1 bit set, so it can't negotiate permessage-deflate
+// (RFC 7692). Compressed connections go through soketto instead, adapted to the same frame
+// type so the readers don't care which client a connection uses.
+use crate::{
+    prelude::*,
+    ws::ws_manager::{FrameSink, Reader, TrafficCounters},
+    Error,
+};
+use futures_util::{sink, stream, StreamExt};
+use soketto::{
+    connection::{self, Mode},
+    data::ByteSlice125,
+    extension::deflate::Deflate,
+    handshake::{Client, ServerResponse},
+    Data, Incoming,
+};
+use std::{
+    io,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    net::TcpStream,
+};
+use tokio_native_tls::{native_tls, TlsConnector};
+use tokio_tungstenite::{
+    client_async_tls,
+    tungstenite::{
+        self,
+        protocol::{self, CloseFrame},
+    },
+};
+use tokio_util::compat::{Compat, TokioAsyncReadCompatExt};
+
+// A socket that records the bytes read from it as wire traffic.
+pub(crate) struct CountingStream<S> {
+    inner: S,
+    traffic: Arc<TrafficCounters>,
+}
+
+impl<S> CountingStream<S> {
+    pub(crate) fn new(inner: S, traffic: Arc<TrafficCounters>) -> CountingStream<S> {
+        CountingStream { inner, traffic }
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for CountingStream<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let filled = buf.filled().len();
+        let poll = Pin::new(&mut self.inner).poll_read(cx, buf);
+        self.traffic.record_wire(buf.filled().len() - filled);
+        poll
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for CountingStream<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+trait Socket: AsyncRead + AsyncWrite + Send + Unpin {}
+
+impl<S: AsyncRead + AsyncWrite + Send + Unpin> Socket for S {}
+
+type DeflateSocket = Compat<Box<dyn Socket>>;
+
+pub(crate) async fn connect(
+    url: &str,
+    compression: bool,
+    traffic: &Arc<TrafficCounters>,
+) -> Result<(FrameSink, Reader)> {
+    let websocket_error = |e: &dyn std::fmt::Display| Error::Websocket(e.to_string());
+    let uri: http::Uri = url.parse().map_err(|e| websocket_error(&e))?;
+    let host = uri
+        .host()
+        .ok_or_else(|| Error::Websocket(format!("no host in {url}")))?;
+    let tls = uri.scheme_str() == Some("wss");
+    let port = uri.port_u16().unwrap_or(if tls { 443 } else { 80 });
+    let tcp = TcpStream::connect((host, port))
+        .await
+        .map_err(|e| websocket_error(&e))?;
+    let socket = CountingStream::new(tcp, Arc::clone(traffic));
+
+    if !compression {
+        let (stream, _) = client_async_tls(url, socket)
+            .await
+            .map_err(|e| websocket_error(&e))?;
+        let (writer, reader) = stream.split();
+        return Ok((Box::pin(writer), Box::pin(reader)));
+    }
+
+    let socket: Box<dyn Socket> = if tls {
+        let connector = native_tls::TlsConnector::new().map_err(|e| websocket_error(&e))?;
+        let stream = TlsConnector::from(connector)
+            .connect(host, socket)
+            .await
+            .map_err(|e| websocket_error(&e))?;
+        Box::new(stream)
+    } else {
+        Box::new(socket)
+    };
+    let authority = uri.authority().map_or(host, |authority| authority.as_str());
+    let resource = uri
+        .path_and_query()
+        .map_or("/", |path_and_query| path_and_query.as_str());
+    let mut client = Client::new(socket.compat(), authority, resource);
+    client.add_extension(Box::new(Deflate::new(Mode::Client)));
+    match client.handshake().await.map_err(|e| websocket_error(&e))? {
+        ServerResponse::Accepted { .. } => {}
+        ServerResponse::Redirect { status_code, .. } | ServerResponse::Rejected { status_code } => {
+            return Err(Error::Websocket(format!(
+                "websocket handshake refused with status {status_code}"
+            )));
+        }
+    }
+    // Without the exchange's agreement the extension stays disabled and frames go uncompressed
+    let (sender, receiver) = client.into_builder().finish();
+    Ok((deflate_writer(sender), deflate_reader(receiver)))
+}
+
+fn io_error(err: connection::Error) -> tungstenite::Error {
+    match err {
+        connection::Error::Io(err) => tungstenite::Error::Io(err),
+        err => tungstenite::Error::Io(io::Error::other(err)),
+    }
+}
+
+fn deflate_writer(sender: connection::Sender<DeflateSocket>) -> FrameSink {
+    let writer = sink::unfold(sender, |mut sender, frame: protocol::Message| async move {
+        let sent = match frame {
+            protocol::Message::Text(text) => sender.send_text_owned(text).await,
+            protocol::Message::Binary(bytes) => sender.send_binary_mut(bytes).await,
+            protocol::Message::Ping(payload) => {
+                let payload = ByteSlice125::try_from(&payload[..])
+                    .map_err(|_| tungstenite::Error::Io(io::Error::other("ping too long")))?;
+                sender.send_ping(payload).await
+            }
+            protocol::Message::Pong(payload) => {
+                let payload = ByteSlice125::try_from(&payload[..])
+                    .map_err(|_| tungstenite::Error::Io(io::Error::other("pong too long")))?;
+                sender.send_pong(payload).await
+            }
+            protocol::Message::Close(_) => sender.close().await,
+            protocol::Message::Frame(_) => Ok(()),
+        };
+        sent.map_err(io_error)?;
+        sender.flush().await.map_err(io_error)?;
+        Ok::<_, tungstenite::Error>(sender)
+    });
+    Box::pin(writer)
+}
+
+// Ends after the close frame, like tungstenite's stream.
+fn deflate_reader(receiver: connection::Receiver<DeflateSocket>) -> Reader {
+    let reader = stream::unfold(Some(receiver), |receiver| async move {
+        let mut receiver = receiver?;
+        let mut payload = Vec::new();
+        let frame = match receiver.receive(&mut payload).await {
+            Ok(Incoming::Data(Data::Text(_))) => String::from_utf8(payload)
+                .map(protocol::Message::Text)
+                .map_err(|_| tungstenite::Error::Utf8),
+            Ok(Incoming::Data(Data::Binary(_))) => Ok(protocol::Message::Binary(payload)),
+            Ok(Incoming::Pong(pong)) => Ok(protocol::Message::Pong(pong.to_vec())),
+            Ok(Incoming::Closed(reason)) => {
+                let frame = CloseFrame {
+                    code: reason.code.into(),
+                    reason: reason.descr.unwrap_or_default().into(),
+                };
+                return Some((Ok(protocol::Message::Close(Some(frame))), None));
+            }
+            Err(connection::Error::Closed) => return None,
+            Err(err) => return Some((Err(io_error(err)), None)),
+        };
+        Some((frame, Some(receiver)))
+    });
+    Box::pin(reader)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InfoClient, Message, Subscription};
+    use serde_json::json;
+    use soketto::handshake::{server::Response, Server};
+    use std::time::Duration;
+    use tokio::{net::TcpListener, spawn, sync::mpsc::unbounded_channel, time::timeout};
+
+    // Accepts one websocket connection, offering permessage-deflate, and answers the client's
+    // first frame with `book`. Returns the base URL to point an `InfoClient` at.
+    async fn serve_book(book: String) -> Result<String> {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .map_err(|e| Error::Websocket(e.to_string()))?;
+        let base_url = format!(
+            "http://{}",
+            listener
+                .local_addr()
+                .map_err(|e| Error::Websocket(e.to_string()))?
+        );
+        spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut server = Server::new(stream.compat());
+            server.add_extension(Box::new(Deflate::new(Mode::Server)));
+            let key = server.receive_request().await.unwrap().key();
+            server
+                .send_response(&Response::Accept {
+                    key,
+                    protocol: None,
+                })
+                .await
+                .unwrap();
+            let (mut sender, mut receiver) = server.into_builder().finish();
+            let mut subscribe = Vec::new();
+            receiver.receive_data(&mut subscribe).await.unwrap();
+            sender.send_text(book).await.unwrap();
+            sender.flush().await.unwrap();
+            // Hold the connection open until the client goes away
+            while receiver.receive_data(&mut Vec::new()).await.is_ok() {}
+        });
+        Ok(base_url)
+    }
+
+    #[tokio::test]
+    async fn test_ws_compression() -> Result<()> {
+        let levels: Vec<_> = (0..200)
+            .map(|level| json!({"px": format!("{}.5", 3000 + level), "sz": "1.25", "n": 3}))
+            .collect();
+        let book = json!({"channel": "l2Book",
+            "data": {"coin": "ETH", "time": 1718000000000u64, "levels": [levels, levels]}})
+        .to_string();
+
+        for compression in [true, false] {
+            let mut info_client = InfoClient::new(None, None).await?;
+            info_client.http_client.base_url = serve_book(book.clone()).await?;
+            if compression {
+                info_client = info_client.with_ws_compression();
+            }
+            let (sender, mut receiver) = unbounded_channel();
+            info_client
+                .subscribe(Subscription::l2_book("ETH"), sender)
+                .await?;
+            let message = timeout(Duration::from_secs(5), receiver.recv())
+                .await
+                .map_err(|e| Error::Websocket(e.to_string()))?;
+            let Some(Message::L2Book(l2_book)) = message else {
+                panic!("expected the book, got {message:?}");
+            };
+            assert_eq!(l2_book.data.levels[1].len(), 200);
+
+            let traffic = info_client.ws_traffic().unwrap();
+            assert_eq!(traffic.payload_bytes, book.len() as u64);
+            if compression {
+                assert!(
+                    traffic.wire_bytes * 4 < traffic.payload_bytes,
+                    "{traffic:?}"
+                );
+            } else {
+                assert!(traffic.wire_bytes > traffic.payload_bytes, "{traffic:?}");
+            }
+            info_client.close_ws().await?;
+        }
+        Ok(())
+    }
+}
@@ -1,57 +1,584 @@
 use crate::{
+    consts::MAX_WS_SUBSCRIPTIONS,
     prelude::*,
-    ws::message_types::{AllMids, Candle, L2Book, OrderUpdates, Trades, User},
-    Error, Notification, UserFills, UserFundings, UserNonFundingLedgerUpdates,
+    unknown_fields,
+    ws::{
+        message_types::{
+            ActiveAssetCtx, AllMids, Candle, L2Book, OrderUpdates, SlowConsumer,
+            SubscriptionRejected, Trades, User, WsClosed, WsError, WsErrorKind,
+        },
+        sequence::SequenceTracker,
+        subscription_key::{KeyRef, SubscriptionKey},
+        transport, Resync,
+    },
+    CandleInterval, Error, Notification, UserFills, UserFundings, UserNonFundingLedgerUpdates,
 };
-use futures_util::{stream::SplitSink, SinkExt, StreamExt};
+use arc_swap::{ArcSwap, ArcSwapOption};
+use futures_util::{Sink, SinkExt, Stream, StreamExt};
+use hashbrown::hash_map::Entry;
 use log::error;
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, sync::Arc, time::Duration};
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex as StdMutex,
+    },
+    time::Duration,
+};
 use tokio::{
-    net::TcpStream,
+    runtime::Handle,
     spawn,
-    sync::{mpsc::UnboundedSender, Mutex},
+    sync::{
+        mpsc::{Sender, UnboundedSender},
+        Mutex,
+    },
+    task::JoinHandle,
     time,
 };
-use tokio_tungstenite::{
-    connect_async,
-    tungstenite::{self, protocol},
-    MaybeTlsStream, WebSocketStream,
-};
+use tokio_tungstenite::tungstenite::{self, protocol};
 
 use ethers::types::H160;
 
 #[derive(Debug)]
 struct SubscriptionData {
-    sending_channel: UnboundedSender<Message>,
+    sending_channel: Consumer,
     subscription_id: u32,
 }
+
+#[derive(Debug)]
+pub(crate) enum Consumer {
+    Unbounded(UnboundedSender<Message>),
+    Bounded {
+        sender: Sender<Message>,
+        policy: SlowConsumerPolicy,
+        // Only written by the reader of the consumer's connection
+        flagged: AtomicBool,
+    },
+}
+
+impl Consumer {
+    pub(crate) fn bounded(sender: Sender<Message>, policy: SlowConsumerPolicy) -> Consumer {
+        Consumer::Bounded {
+            sender,
+            policy,
+            flagged: AtomicBool::new(false),
+        }
+    }
+}
+
+impl From<UnboundedSender<Message>> for Consumer {
+    fn from(sender: UnboundedSender<Message>) -> Consumer {
+        Consumer::Unbounded(sender)
+    }
+}
+
+// What happens to a bounded subscriber whose queue is full. The last slot of the queue is
+// kept for the `Message::SlowConsumer` notification, so queues need a capacity above one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlowConsumerPolicy {
+    // Drop messages until more than half the consumer's queue is free again, then flag it
+    // anew on the next overflow
+    Flag,
+    // Unsubscribe the consumer
+    Evict,
+}
+
+// A wire subscription: the connection it's on, its consumers, and its JSON, serialized once
+// for the subscribe and unsubscribe requests.
+#[derive(Clone)]
+struct Route {
+    subscription: Subscription,
+    connection: usize,
+    consumers: Vec<Arc<SubscriptionData>>,
+    payload: Arc<str>,
+}
+
+// Looked up with a `KeyRef` borrowed from each message, which hashbrown's maps allow and std's
+// don't.
+type Routes = hashbrown::HashMap<SubscriptionKey, Route>;
+
+// What the connection readers deliver with. Every subscribe and unsubscribe publishes a new
+// snapshot of the routes, which readers load without locking, so delivery never waits on
+// subscription changes and its cost doesn't grow with the number of other subscriptions.
+#[derive(Clone, Default)]
+struct Router {
+    routes: Arc<ArcSwap<Routes>>,
+    // Only locked for the orderUpdates and userFills streams it tracks
+    sequences: Arc<StdMutex<SequenceTracker>>,
+}
+
+impl Router {
+    // Wire subscriptions, counted against MAX_WS_SUBSCRIPTIONS
+    fn active(&self) -> usize {
+        self.routes.load().len()
+    }
+
+    // Forwards an error from the exchange to the consumers of the subscription it quotes, or to
+    // every consumer on the connection it arrived on when it doesn't name one, and returns it
+    // as a typed error. A quoted subscription the exchange refused is delivered as
+    // `Message::SubscriptionRejected` instead, and its consumers are added to `rejected` for
+    // the reader to drop.
+    fn reject(
+        &self,
+        error: &WsError,
+        connection: usize,
+        evicted: &mut Vec<u32>,
+        rejected: &mut Vec<u32>,
+    ) -> Error {
+        let routes = self.routes.load();
+        let quoted = error
+            .data
+            .find('{')
+            .and_then(|start| serde_json::from_str::<Subscription>(&error.data[start..]).ok())
+            .and_then(|subscription| routes.get(&SubscriptionKey::from(&subscription)));
+        let refused = matches!(
+            error.kind(),
+            WsErrorKind::InvalidSubscription | WsErrorKind::SubscriptionLimit
+        ) && !error.data.to_lowercase().contains("already subscribed");
+        // Not counting the refused subscription, which is about to be dropped
+        let active = self.active() - usize::from(refused && quoted.is_some());
+        let sent = match quoted {
+            Some(route) if refused => {
+                rejected.extend(route.consumers.iter().map(|data| data.subscription_id));
+                let message = Message::SubscriptionRejected(SubscriptionRejected {
+                    subscription: route.subscription.clone(),
+                    reason: error.data.clone(),
+                    active,
+                    limit: MAX_WS_SUBSCRIPTIONS,
+                });
+                Router::send_to(route, &message, evicted)
+            }
+            Some(route) => Router::send_to(route, &Message::Error(error.clone()), evicted),
+            None => self.broadcast(connection, &Message::Error(error.clone()), evicted),
+        };
+        if let Err(err) = sent {
+            error!("Error forwarding websocket error: {err}");
+        }
+        if refused {
+            Error::SubscriptionRejected {
+                reason: error.data.clone(),
+                active,
+                limit: MAX_WS_SUBSCRIPTIONS,
+            }
+        } else {
+            Error::WsServer {
+                kind: error.kind(),
+                message: error.data.clone(),
+            }
+        }
+    }
+
+    // Sends `message` to every consumer of a subscription on `connection`.
+    fn broadcast(
+        &self,
+        connection: usize,
+        message: &Message,
+        evicted: &mut Vec<u32>,
+    ) -> Result<()> {
+        let routes = self.routes.load();
+        let mut res = Ok(());
+        for route in routes
+            .values()
+            .filter(|route| route.connection == connection)
+        {
+            if let Err(e) = Router::send_to(route, message, evicted) {
+                res = Err(e);
+            }
+        }
+        res
+    }
+
+    // Messages queued for each bounded consumer.
+    fn queue_depths(&self) -> HashMap<u32, usize> {
+        self.routes
+            .load()
+            .values()
+            .flat_map(|route| &route.consumers)
+            .filter_map(
+                |subscription_data| match &subscription_data.sending_channel {
+                    Consumer::Bounded { sender, .. } => Some((
+                        subscription_data.subscription_id,
+                        sender.max_capacity() - sender.capacity(),
+                    )),
+                    Consumer::Unbounded(_) => None,
+                },
+            )
+            .collect()
+    }
+
+    // Marks the user streams on `connection` as possibly missing updates after a read error.
+    fn interrupt(&self, connection: usize) {
+        let routes = self.routes.load();
+        let Ok(mut sequences) = self.sequences.lock() else {
+            return;
+        };
+        for route in routes.values() {
+            if route.connection == connection {
+                sequences.interrupt(&route.subscription);
+            }
+        }
+    }
+
+    // Sends `message`, preceded by a `Message::Resync` when updates were likely missed.
+    // Consumers evicted for being slow are added to `evicted`.
+    fn deliver(&self, key: KeyRef, message: &Message, evicted: &mut Vec<u32>) -> Result<()> {
+        let routes = self.routes.load();
+        let Some(route) = routes.get(&key) else {
+            return Ok(());
+        };
+        if matches!(message, Message::OrderUpdates(_) | Message::UserFills(_)) {
+            let resync = self
+                .sequences
+                .lock()
+                .ok()
+                .and_then(|mut sequences| sequences.observe(&route.subscription, message));
+            if let Some(resync) = resync {
+                Router::send_to(route, &Message::Resync(resync), evicted)?;
+            }
+        }
+        Router::send_to(route, message, evicted)
+    }
+
+    #[cfg(test)]
+    fn send<K>(&self, key: &K, message: &Message, evicted: &mut Vec<u32>) -> Result<()>
+    where
+        K: Hash + hashbrown::Equivalent<SubscriptionKey> + ?Sized,
+    {
+        match self.routes.load().get(key) {
+            Some(route) => Router::send_to(route, message, evicted),
+            None => Ok(()),
+        }
+    }
+
+    fn send_to(route: &Route, message: &Message, evicted: &mut Vec<u32>) -> Result<()> {
+        let mut res = Ok(());
+        for subscription_data in &route.consumers {
+            let subscription_id = subscription_data.subscription_id;
+            let sent = match &subscription_data.sending_channel {
+                Consumer::Unbounded(sender) => sender
+                    .send(message.clone())
+                    .map_err(|e| Error::WsSend(e.to_string())),
+                Consumer::Bounded {
+                    sender,
+                    policy,
+                    flagged,
+                } => {
+                    let capacity = sender.max_capacity();
+                    let free = sender.capacity();
+                    if flagged.load(Ordering::Relaxed) && free > capacity / 2 {
+                        flagged.store(false, Ordering::Relaxed);
+                    }
+                    if flagged.load(Ordering::Relaxed) {
+                        Ok(())
+                    } else if free > 1 {
+                        sender.try_send(message.clone())
+                    } else {
+                        flagged.store(true, Ordering::Relaxed);
+                        if *policy == SlowConsumerPolicy::Evict {
+                            evicted.push(subscription_id);
+                        }
+                        sender.try_send(Message::SlowConsumer(SlowConsumer {
+                            subscription_id,
+                            depth: capacity - free,
+                            capacity,
+                            evicted: *policy == SlowConsumerPolicy::Evict,
+                        }))
+                    }
+                    .map_err(|e| Error::WsSend(e.to_string()))
+                }
+            };
+            if let Err(e) = sent {
+                res = Err(e);
+            }
+        }
+        res
+    }
+}
+
+// The consumers of each wire subscription. Identical subscriptions share one wire
+// subscription, which is only removed with its last consumer. Changes are published to
+// `router` for the readers.
+#[derive(Default)]
+struct Subscriptions {
+    routes: Routes,
+    // Key and connection of each subscription id
+    subscriptions: HashMap<u32, (SubscriptionKey, usize)>,
+    next_id: u32,
+    router: Router,
+}
+
+impl Subscriptions {
+    fn is_subscribed(&self, subscription: &Subscription) -> bool {
+        self.routes
+            .contains_key(&SubscriptionKey::from(subscription))
+    }
+
+    // Wire subscriptions, counted against MAX_WS_SUBSCRIPTIONS
+    fn active(&self) -> usize {
+        self.routes.len()
+    }
+
+    // Whether `connection` carries any userEvents, orderUpdates or notification subscriptions,
+    // which keep it owned by their user.
+    fn has_user_channels(&self, connection: usize) -> bool {
+        self.routes.values().any(|route| {
+            route.connection == connection
+                && WsManager::unattributed_user(&route.subscription).is_some()
+        })
+    }
+
+    // Returns the subscription id, and the payload to subscribe with if this is the first
+    // consumer.
+    fn add(
+        &mut self,
+        subscription: Subscription,
+        connection: usize,
+        sending_channel: impl Into<Consumer>,
+    ) -> Result<(u32, Option<Arc<str>>)> {
+        let key = SubscriptionKey::from(&subscription);
+        let (route, subscribe) = match self.routes.entry(key.clone()) {
+            Entry::Occupied(entry) => (entry.into_mut(), None),
+            Entry::Vacant(entry) => {
+                let payload: Arc<str> = serde_json::to_string(&subscription)
+                    .map_err(|e| Error::JsonParse(e.to_string()))?
+                    .into();
+                let route = entry.insert(Route {
+                    subscription,
+                    connection,
+                    consumers: Vec::new(),
+                    payload: Arc::clone(&payload),
+                });
+                (route, Some(payload))
+            }
+        };
+        let subscription_id = self.next_id;
+        self.next_id += 1;
+        route.consumers.push(Arc::new(SubscriptionData {
+            sending_channel: sending_channel.into(),
+            subscription_id,
+        }));
+        self.subscriptions
+            .insert(subscription_id, (key, connection));
+        self.publish();
+        Ok((subscription_id, subscribe))
+    }
+
+    // Returns the payload and connection to unsubscribe on if this was the last consumer.
+    fn remove(&mut self, subscription_id: u32) -> Result<Option<(Arc<str>, usize)>> {
+        let unsubscribe = self.unroute(subscription_id)?;
+        self.publish();
+        Ok(unsubscribe)
+    }
+
+    fn unroute(&mut self, subscription_id: u32) -> Result<Option<(Arc<str>, usize)>> {
+        let (key, connection) = self
+            .subscriptions
+            .remove(&subscription_id)
+            .ok_or(Error::SubscriptionNotFound)?;
+        let route = self
+            .routes
+            .get_mut(&key)
+            .ok_or(Error::SubscriptionNotFound)?;
+        route
+            .consumers
+            .retain(|subscription_data| subscription_data.subscription_id != subscription_id);
+        if !route.consumers.is_empty() {
+            return Ok(None);
+        }
+        let route = self
+            .routes
+            .remove(&key)
+            .ok_or(Error::SubscriptionNotFound)?;
+        if let Ok(mut sequences) = self.router.sequences.lock() {
+            sequences.forget(&route.subscription);
+        }
+        Ok(Some((route.payload, connection)))
+    }
+
+    // Removes the consumers evicted for being slow, returning the wire subscriptions left
+    // without consumers.
+    fn evict(&mut self, evicted: Vec<u32>) -> Vec<(Arc<str>, usize)> {
+        let emptied = evicted
+            .into_iter()
+            .filter_map(|subscription_id| self.unroute(subscription_id).ok().flatten())
+            .collect();
+        self.publish();
+        emptied
+    }
+
+    // Removes the consumers of subscriptions the exchange refused. Nothing is unsubscribed,
+    // since the exchange never subscribed them.
+    fn reject(&mut self, rejected: Vec<u32>) {
+        for subscription_id in rejected {
+            let _ = self.unroute(subscription_id);
+        }
+        self.publish();
+    }
+
+    fn publish(&self) {
+        self.router.routes.store(Arc::new(self.routes.clone()));
+    }
+}
+
+pub(super) type FrameSink =
+    Pin<Box<dyn Sink<protocol::Message, Error = tungstenite::Error> + Send>>;
+type Writer = Arc<Mutex<FrameSink>>;
+pub(super) type Reader =
+    Pin<Box<dyn Stream<Item = std::result::Result<protocol::Message, tungstenite::Error>> + Send>>;
+
+// How connections are opened: over the network to the exchange, or in memory to a
+// `SimExchange` in tests. Network connections offer permessage-deflate when `compression` is
+// set, and fall back to uncompressed frames if the exchange declines.
+#[derive(Clone)]
+pub(crate) enum Connector {
+    Network {
+        compression: bool,
+    },
+    #[cfg(any(test, feature = "sim"))]
+    Sim(crate::sim::SimConnector),
+}
+
+impl Default for Connector {
+    fn default() -> Connector {
+        Connector::Network { compression: false }
+    }
+}
+
+impl Connector {
+    // In-memory connections are left as they are
+    pub(crate) fn compressed(self) -> Connector {
+        match self {
+            Connector::Network { .. } => Connector::Network { compression: true },
+            #[cfg(any(test, feature = "sim"))]
+            sim @ Connector::Sim(_) => sim,
+        }
+    }
+
+    async fn connect(&self, url: &str, traffic: &Arc<TrafficCounters>) -> Result<(Writer, Reader)> {
+        let (writer, reader): (FrameSink, Reader) = match self {
+            Connector::Network { compression } => {
+                transport::connect(url, *compression, traffic).await?
+            }
+            #[cfg(any(test, feature = "sim"))]
+            Connector::Sim(sim) => {
+                let (writer, reader) = sim.connect(url, traffic).await?.split();
+                (Box::pin(writer), Box::pin(reader))
+            }
+        };
+        Ok((Arc::new(Mutex::new(writer)), reader))
+    }
+}
+
+// userEvents, orderUpdates and notification messages don't say which user they are for, so
+// each connection carries those channels for at most one user (its owner) and routes them to
+// that user's subscriptions. Additional users get a connection of their own. Once its owner's
+// last user channel is removed the connection is freed, but only claimed again after the
+// exchange acknowledged every unsubscribe sent on it, so no message still in flight for the
+// previous owner can reach the next one.
+struct Connection {
+    writer: Writer,
+    owner: Arc<ArcSwapOption<H160>>,
+    unacked_unsubscribes: Arc<AtomicUsize>,
+    pinger: JoinHandle<()>,
+}
+
 pub(crate) struct WsManager {
-    writer: Arc<Mutex<SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, protocol::Message>>>,
-    subscriptions: Arc<Mutex<HashMap<String, Vec<SubscriptionData>>>>,
-    subscription_id: u32,
-    subscription_identifiers: HashMap<u32, String>,
+    url: String,
+    connector: Connector,
+    runtime: Option<Handle>,
+    connections: Vec<Connection>,
+    subscriptions: Arc<Mutex<Subscriptions>>,
+    traffic: Arc<TrafficCounters>,
+}
+
+// Traffic received over all connections. `wire_bytes` counts everything read off the sockets,
+// handshakes and frame headers included, compressed when permessage-deflate was negotiated
+// (and encrypted for wss). `payload_bytes` counts the data frames' decompressed payloads.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WsTraffic {
+    pub frames: u64,
+    pub wire_bytes: u64,
+    pub payload_bytes: u64,
+}
+
+#[derive(Default)]
+pub(crate) struct TrafficCounters {
+    frames: AtomicU64,
+    wire_bytes: AtomicU64,
+    payload_bytes: AtomicU64,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+impl TrafficCounters {
+    fn record(&self, payload_bytes: usize) {
+        self.frames.fetch_add(1, Ordering::Relaxed);
+        self.payload_bytes
+            .fetch_add(payload_bytes as u64, Ordering::Relaxed);
+    }
+
+    pub(super) fn record_wire(&self, bytes: usize) {
+        self.wire_bytes.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> WsTraffic {
+        WsTraffic {
+            frames: self.frames.load(Ordering::Relaxed),
+            wire_bytes: self.wire_bytes.load(Ordering::Relaxed),
+            payload_bytes: self.payload_bytes.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
 #[serde(tag = "type")]
 #[serde(rename_all = "camelCase")]
+#[non_exhaustive]
 pub enum Subscription {
-    AllMids,
-    Trades { coin: String },
-    L2Book { coin: String },
-    UserEvents { user: H160 },
-    UserFills { user: H160 },
-    Candle { coin: String, interval: String },
-    OrderUpdates { user: H160 },
-    UserFundings { user: H160 },
-    UserNonFundingLedgerUpdates { user: H160 },
-    Notification { user: H160 },
+    // Perp mids of the builder-deployed perp DEX `dex`, or without one the first perp DEX's
+    // mids and spot pairs' mids
+    AllMids {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        dex: Option<String>,
+    },
+    Trades {
+        coin: String,
+    },
+    L2Book {
+        coin: String,
+    },
+    UserEvents {
+        user: H160,
+    },
+    UserFills {
+        user: H160,
+    },
+    Candle {
+        coin: String,
+        interval: CandleInterval,
+    },
+    OrderUpdates {
+        user: H160,
+    },
+    UserFundings {
+        user: H160,
+    },
+    UserNonFundingLedgerUpdates {
+        user: H160,
+    },
+    Notification {
+        user: H160,
+    },
+    ActiveAssetCtx {
+        coin: String,
+    },
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
 #[serde(tag = "channel")]
 #[serde(rename_all = "camelCase")]
+#[non_exhaustive]
 pub enum Message {
     AllMids(AllMids),
     Trades(Trades),
@@ -64,13 +591,97 @@ pub enum Message {
     UserFundings(UserFundings),
     UserNonFundingLedgerUpdates(UserNonFundingLedgerUpdates),
     Notification(Notification),
+    ActiveAssetCtx(ActiveAssetCtx),
+    Error(WsError),
+    SubscriptionRejected(SubscriptionRejected),
+    Closed(WsClosed),
+    SlowConsumer(SlowConsumer),
+    Resync(Resync),
     Pong,
 }
 
-#[derive(Serialize)]
-pub(crate) struct SubscriptionSendData<'a> {
-    method: &'static str,
-    subscription: &'a serde_json::Value,
+// Constructors that keep compiling if a subscription gains optional fields, which the struct
+// variants wouldn't.
+impl Subscription {
+    pub fn all_mids() -> Subscription {
+        Subscription::AllMids { dex: None }
+    }
+
+    // The mids of a builder-deployed perp DEX, whose coins are named `<dex>:<coin>`.
+    pub fn all_mids_for_dex(dex: impl Into<String>) -> Subscription {
+        Subscription::AllMids {
+            dex: Some(dex.into()),
+        }
+    }
+
+    pub fn trades(coin: impl Into<String>) -> Subscription {
+        Subscription::Trades { coin: coin.into() }
+    }
+
+    pub fn l2_book(coin: impl Into<String>) -> Subscription {
+        Subscription::L2Book { coin: coin.into() }
+    }
+
+    pub fn user_events(user: H160) -> Subscription {
+        Subscription::UserEvents { user }
+    }
+
+    pub fn user_fills(user: H160) -> Subscription {
+        Subscription::UserFills { user }
+    }
+
+    pub fn candle(coin: impl Into<String>, interval: CandleInterval) -> Subscription {
+        Subscription::Candle {
+            coin: coin.into(),
+            interval,
+        }
+    }
+
+    pub fn order_updates(user: H160) -> Subscription {
+        Subscription::OrderUpdates { user }
+    }
+
+    pub fn user_fundings(user: H160) -> Subscription {
+        Subscription::UserFundings { user }
+    }
+
+    pub fn user_non_funding_ledger_updates(user: H160) -> Subscription {
+        Subscription::UserNonFundingLedgerUpdates { user }
+    }
+
+    pub fn notification(user: H160) -> Subscription {
+        Subscription::Notification { user }
+    }
+
+    pub fn active_asset_ctx(coin: impl Into<String>) -> Subscription {
+        Subscription::ActiveAssetCtx { coin: coin.into() }
+    }
+}
+
+impl Message {
+    // The channel the message is tagged with when serialized, or None for acks and pongs.
+    // Handy in the `_` arm of a match, which `Message` needs since channels get added.
+    pub fn channel(&self) -> Option<&'static str> {
+        Some(match self {
+            Message::AllMids(_) => "allMids",
+            Message::Trades(_) => "trades",
+            Message::L2Book(_) => "l2Book",
+            Message::User(_) => "user",
+            Message::UserFills(_) => "userFills",
+            Message::Candle(_) => "candle",
+            Message::OrderUpdates(_) => "orderUpdates",
+            Message::UserFundings(_) => "userFundings",
+            Message::UserNonFundingLedgerUpdates(_) => "userNonFundingLedgerUpdates",
+            Message::Notification(_) => "notification",
+            Message::ActiveAssetCtx(_) => "activeAssetCtx",
+            Message::Error(_) => "error",
+            Message::SubscriptionRejected(_) => "subscriptionRejected",
+            Message::Closed(_) => "closed",
+            Message::SlowConsumer(_) => "slowConsumer",
+            Message::Resync(_) => "resync",
+            Message::SubscriptionResponse | Message::Pong => return None,
+        })
+    }
 }
 
 #[derive(Serialize)]
@@ -80,31 +691,135 @@ pub(crate) struct Ping {
 
 impl WsManager {
     const SEND_PING_INTERVAL: u64 = 50;
+    // Sent as plain text when a connection opens
+    const GREETING: &'static str = "Websocket connection established.";
 
-    pub(crate) async fn new(url: String) -> Result<WsManager> {
-        let (ws_stream, _) = connect_async(url.clone())
-            .await
-            .map_err(|e| Error::Websocket(e.to_string()))?;
+    // Connections are opened and read on `runtime` if given, or else on the caller's runtime.
+    pub(crate) async fn new(
+        url: String,
+        connector: Connector,
+        runtime: Option<Handle>,
+    ) -> Result<WsManager> {
+        let subscriptions = Arc::new(Mutex::new(Subscriptions::default()));
+        let traffic = Arc::new(TrafficCounters::default());
+        let connection = WsManager::open(
+            &url,
+            &connector,
+            runtime.as_ref(),
+            0,
+            &subscriptions,
+            &traffic,
+        )
+        .await?;
 
-        let (writer, mut reader) = ws_stream.split();
-        let writer = Arc::new(Mutex::new(writer));
+        Ok(WsManager {
+            url,
+            connector,
+            runtime,
+            connections: vec![connection],
+            subscriptions,
+            traffic,
+        })
+    }
 
-        let subscriptions_map: HashMap<String, Vec<SubscriptionData>> = HashMap::new();
-        let subscriptions = Arc::new(Mutex::new(subscriptions_map));
-        let subscriptions_copy = Arc::clone(&subscriptions);
+    pub(crate) fn traffic(&self) -> WsTraffic {
+        self.traffic.snapshot()
+    }
 
-        let reader_fut = async move {
-            // TODO: reconnect
-            loop {
-                let data = reader.next().await;
-                if let Err(err) = WsManager::parse_and_send_data(data, &subscriptions_copy).await {
-                    error!("Error processing data received by WS manager reader: {err}");
-                }
-            }
+    // Runs `connect` on `runtime`, so the socket registers with that runtime's reactor and the
+    // reader and pinger are spawned on it.
+    async fn open(
+        url: &str,
+        connector: &Connector,
+        runtime: Option<&Handle>,
+        index: usize,
+        subscriptions: &Arc<Mutex<Subscriptions>>,
+        traffic: &Arc<TrafficCounters>,
+    ) -> Result<Connection> {
+        let Some(runtime) = runtime else {
+            return WsManager::connect(url, connector, index, subscriptions, traffic).await;
         };
-        spawn(reader_fut);
+        let (url, connector) = (url.to_string(), connector.clone());
+        let (subscriptions, traffic) = (Arc::clone(subscriptions), Arc::clone(traffic));
+        runtime
+            .spawn(async move {
+                WsManager::connect(&url, &connector, index, &subscriptions, &traffic).await
+            })
+            .await
+            .map_err(|e| Error::Websocket(e.to_string()))?
+    }
+
+    async fn connect(
+        url: &str,
+        connector: &Connector,
+        index: usize,
+        subscriptions: &Arc<Mutex<Subscriptions>>,
+        traffic: &Arc<TrafficCounters>,
+    ) -> Result<Connection> {
+        let (writer, mut reader) = connector.connect(url, traffic).await?;
+        let owner = Arc::new(ArcSwapOption::empty());
+        let unacked_unsubscribes = Arc::new(AtomicUsize::new(0));
 
         {
+            let subscriptions = Arc::clone(subscriptions);
+            let router = subscriptions.lock().await.router.clone();
+            let owner = Arc::clone(&owner);
+            let unacked_unsubscribes = Arc::clone(&unacked_unsubscribes);
+            let traffic = Arc::clone(traffic);
+            let writer = Arc::clone(&writer);
+            let reader_fut = async move {
+                // TODO: reconnect
+                loop {
+                    let Some(data) = reader.next().await else {
+                        router.interrupt(index);
+                        error!("WS connection {index} ended");
+                        return;
+                    };
+                    if let Ok(frame) = &data {
+                        traffic.record(frame.len());
+                    }
+                    if let Ok(protocol::Message::Text(text)) = &data {
+                        if WsManager::is_unsubscribe_ack(text) {
+                            let _ = unacked_unsubscribes.fetch_update(
+                                Ordering::AcqRel,
+                                Ordering::Acquire,
+                                |unacked| unacked.checked_sub(1),
+                            );
+                        }
+                    }
+                    let owner = owner.load().as_deref().copied();
+                    let (mut evicted, mut rejected) = (Vec::new(), Vec::new());
+                    if let Err(err) = WsManager::parse_and_send_data(
+                        data,
+                        index,
+                        owner,
+                        &router,
+                        &mut evicted,
+                        &mut rejected,
+                    ) {
+                        error!("Error processing data received by WS manager reader: {err}");
+                    }
+                    if !rejected.is_empty() {
+                        subscriptions.lock().await.reject(rejected);
+                    }
+                    if evicted.is_empty() {
+                        continue;
+                    }
+                    let emptied = subscriptions.lock().await.evict(evicted);
+                    for (payload, _) in emptied {
+                        match WsManager::send_subscription(&writer, "unsubscribe", &payload).await {
+                            Ok(()) => {
+                                unacked_unsubscribes.fetch_add(1, Ordering::AcqRel);
+                            }
+                            Err(err) => error!("Error unsubscribing slow consumer: {err}"),
+                        }
+                    }
+                }
+            };
+            spawn(reader_fut);
+        }
+
+        let pinger = {
             let writer = Arc::clone(&writer);
             let ping_fut = async move {
                 loop {
@@ -120,193 +835,640 @@ impl WsManager {
                     time::sleep(Duration::from_secs(Self::SEND_PING_INTERVAL)).await;
                 }
             };
-            spawn(ping_fut);
-        }
+            spawn(ping_fut)
+        };
 
-        Ok(WsManager {
+        Ok(Connection {
             writer,
-            subscriptions,
-            subscription_id: 0,
-            subscription_identifiers: HashMap::new(),
+            owner,
+            unacked_unsubscribes,
+            pinger,
         })
     }
 
-    fn get_identifier(message: &Message) -> Result<String> {
-        match message {
-            Message::AllMids(_) => serde_json::to_string(&Subscription::AllMids)
-                .map_err(|e| Error::JsonParse(e.to_string())),
-            Message::User(_) => Ok("userEvents".to_string()),
-            Message::UserFills(_) => Ok("userFills".to_string()),
-            Message::Trades(trades) => {
-                if trades.data.is_empty() {
-                    Ok(String::default())
-                } else {
-                    serde_json::to_string(&Subscription::Trades {
-                        coin: trades.data[0].coin.clone(),
-                    })
-                    .map_err(|e| Error::JsonParse(e.to_string()))
-                }
+    // Sends a close frame on every connection. Each reader then delivers `Message::Closed` to
+    // the consumers on its connection and exits once the exchange closes the stream.
+    pub(crate) async fn close(self) -> Result<()> {
+        let mut res = Ok(());
+        for connection in self.connections {
+            connection.pinger.abort();
+            let mut writer = connection.writer.lock().await;
+            if let Err(err) = writer.close().await {
+                res = Err(Error::Websocket(err.to_string()));
             }
-            Message::L2Book(l2_book) => serde_json::to_string(&Subscription::L2Book {
-                coin: l2_book.data.coin.clone(),
-            })
-            .map_err(|e| Error::JsonParse(e.to_string())),
-            Message::Candle(candle) => serde_json::to_string(&Subscription::Candle {
-                coin: candle.data.coin.clone(),
-                interval: candle.data.interval.clone(),
-            })
-            .map_err(|e| Error::JsonParse(e.to_string())),
-            Message::OrderUpdates(_) => Ok("orderUpdates".to_string()),
-            Message::UserFundings(_) => Ok("userFundings".to_string()),
-            Message::UserNonFundingLedgerUpdates(user_non_funding_ledger_updates) => {
-                serde_json::to_string(&Subscription::UserNonFundingLedgerUpdates {
-                    user: user_non_funding_ledger_updates.data.user,
-                })
-                .map_err(|e| Error::JsonParse(e.to_string()))
+        }
+        res
+    }
+
+    // The user of a subscription whose messages don't identify the user.
+    fn unattributed_user(subscription: &Subscription) -> Option<H160> {
+        match subscription {
+            Subscription::UserEvents { user }
+            | Subscription::OrderUpdates { user }
+            | Subscription::Notification { user } => Some(*user),
+            _ => None,
+        }
+    }
+
+    // Frees the connections whose owner no longer has user channels on them, e.g. after the
+    // last one was removed or evicted, for other users to claim.
+    fn release_owners(&self, subscriptions: &Subscriptions) {
+        for (index, connection) in self.connections.iter().enumerate() {
+            if connection.owner.load().is_some() && !subscriptions.has_user_channels(index) {
+                connection.owner.store(None);
             }
-            Message::Notification(_) => Ok("notification".to_string()),
-            Message::SubscriptionResponse | Message::Pong => Ok(String::default()),
         }
     }
 
-    async fn parse_and_send_data(
-        data: Option<std::result::Result<protocol::Message, tungstenite::Error>>,
-        subscriptions: &Arc<Mutex<HashMap<String, Vec<SubscriptionData>>>>,
-    ) -> Result<()> {
-        let data = data
-            .ok_or(Error::ReaderDataNotFound)?
-            .map_err(|e| Error::GenericReader(e.to_string()))?
-            .into_text()
-            .map_err(|e| Error::ReaderTextConversion(e.to_string()))?;
-        if !data.starts_with('{') {
-            return Ok(());
+    // Whether a text frame acknowledges an unsubscribe request.
+    fn is_unsubscribe_ack(text: &str) -> bool {
+        #[derive(Deserialize)]
+        struct Ack<'a> {
+            channel: &'a str,
+            data: AckData<'a>,
         }
-        let message =
-            serde_json::from_str::<Message>(&data).map_err(|e| Error::JsonParse(e.to_string()))?;
-        let identifier = WsManager::get_identifier(&message)?;
-        if identifier.is_empty() {
-            return Ok(());
+        #[derive(Deserialize)]
+        struct AckData<'a> {
+            method: &'a str,
         }
 
-        let mut subscriptions = subscriptions.lock().await;
-        let mut res = Ok(());
-        if let Some(subscription_datas) = subscriptions.get_mut(&identifier) {
-            for subscription_data in subscription_datas {
-                if let Err(e) = subscription_data
-                    .sending_channel
-                    .send(message.clone())
-                    .map_err(|e| Error::WsSend(e.to_string()))
+        text.contains("subscriptionResponse")
+            && serde_json::from_str::<Ack>(text).is_ok_and(|ack| {
+                ack.channel == "subscriptionResponse" && ack.data.method == "unsubscribe"
+            })
+    }
+
+    // Picks the connection owned by `user`, claiming a free one or opening a new one.
+    async fn connection_for(&mut self, user: H160) -> Result<usize> {
+        let mut unowned = None;
+        for (index, connection) in self.connections.iter().enumerate() {
+            match connection.owner.load().as_deref() {
+                Some(owner) if *owner == user => return Ok(index),
+                None if unowned.is_none()
+                    && connection.unacked_unsubscribes.load(Ordering::Acquire) == 0 =>
                 {
-                    res = Err(e);
+                    unowned = Some(index)
                 }
+                _ => {}
             }
         }
-        res
+        let index = match unowned {
+            Some(index) => index,
+            None => {
+                let connection = WsManager::open(
+                    &self.url,
+                    &self.connector,
+                    self.runtime.as_ref(),
+                    self.connections.len(),
+                    &self.subscriptions,
+                    &self.traffic,
+                )
+                .await?;
+                self.connections.push(connection);
+                self.connections.len() - 1
+            }
+        };
+        self.connections[index].owner.store(Some(Arc::new(user)));
+        Ok(index)
+    }
+
+    // Parses a text frame. Besides JSON messages the exchange sends a plain text greeting, which
+    // is skipped, and plain text errors, which become `Message::Error`.
+    pub(crate) fn classify(data: &str) -> Result<Option<Message>> {
+        let data = data.trim();
+        if data.starts_with('{') {
+            return serde_json::from_str::<Message>(data)
+                .map(Some)
+                .map_err(|e| Error::JsonParse(e.to_string()));
+        }
+        if data.is_empty() || data == Self::GREETING {
+            return Ok(None);
+        }
+        Ok(Some(Message::Error(WsError {
+            data: data.to_string(),
+        })))
+    }
+
+    // Consumers evicted for being slow are added to `evicted`, and those of subscriptions the
+    // exchange refused to `rejected`.
+    fn parse_and_send_data(
+        data: std::result::Result<protocol::Message, tungstenite::Error>,
+        connection: usize,
+        owner: Option<H160>,
+        router: &Router,
+        evicted: &mut Vec<u32>,
+        rejected: &mut Vec<u32>,
+    ) -> Result<()> {
+        let data = match data {
+            Ok(protocol::Message::Text(text)) => text,
+            // The exchange sends JSON as text, but accept it as binary too
+            Ok(protocol::Message::Binary(bytes)) => {
+                String::from_utf8(bytes).map_err(|e| Error::ReaderTextConversion(e.to_string()))?
+            }
+            // tungstenite answers pings and reassembles fragmented messages itself, and never
+            // yields raw frames when reading
+            Ok(protocol::Message::Ping(_) | protocol::Message::Pong(_))
+            | Ok(protocol::Message::Frame(_)) => return Ok(()),
+            Ok(protocol::Message::Close(frame)) => {
+                let closed = WsClosed {
+                    code: frame.as_ref().map_or(1005, |frame| frame.code.into()),
+                    reason: frame
+                        .map(|frame| frame.reason.into_owned())
+                        .unwrap_or_default(),
+                };
+                router.interrupt(connection);
+                router.broadcast(connection, &Message::Closed(closed.clone()), evicted)?;
+                return Err(Error::WsClosed {
+                    code: closed.code,
+                    reason: closed.reason,
+                });
+            }
+            Err(err) => {
+                router.interrupt(connection);
+                return Err(Error::GenericReader(err.to_string()));
+            }
+        };
+        let message = match WsManager::classify(&data)? {
+            Some(message) => message,
+            None => return Ok(()),
+        };
+        unknown_fields::report_message(&message);
+        if let Message::Error(error) = &message {
+            return Err(router.reject(error, connection, evicted, rejected));
+        }
+        match KeyRef::of(&message, owner) {
+            Some(key) => router.deliver(key, &message, evicted),
+            None => Ok(()),
+        }
     }
 
     pub(crate) async fn add_subscription(
         &mut self,
-        identifier: String,
-        sending_channel: UnboundedSender<Message>,
+        subscription: Subscription,
+        sending_channel: Consumer,
     ) -> Result<u32> {
-        let mut subscriptions = self.subscriptions.lock().await;
-
-        let identifier_entry = if let Subscription::UserEvents { user: _ } =
-            serde_json::from_str::<Subscription>(&identifier)
-                .map_err(|e| Error::JsonParse(e.to_string()))?
-        {
-            "userEvents".to_string()
-        } else if let Subscription::OrderUpdates { user: _ } =
-            serde_json::from_str::<Subscription>(&identifier)
-                .map_err(|e| Error::JsonParse(e.to_string()))?
+        // Checked before claiming a connection, so a rejected subscription doesn't leave one
+        // owned or open a new one
         {
-            "orderUpdates".to_string()
-        } else {
-            identifier.clone()
+            let subscriptions = self.subscriptions.lock().await;
+            if !subscriptions.is_subscribed(&subscription)
+                && subscriptions.active() >= MAX_WS_SUBSCRIPTIONS
+            {
+                return Err(Error::SubscriptionRejected {
+                    reason: format!(
+                        "subscription limit reached, not subscribing to {subscription:?}"
+                    ),
+                    active: subscriptions.active(),
+                    limit: MAX_WS_SUBSCRIPTIONS,
+                });
+            }
+            self.release_owners(&subscriptions);
+        }
+        let connection = match WsManager::unattributed_user(&subscription) {
+            Some(user) => self.connection_for(user).await?,
+            None => 0,
         };
-        let subscriptions = subscriptions
-            .entry(identifier_entry.clone())
-            .or_insert(Vec::new());
 
-        if !subscriptions.is_empty() && identifier_entry.eq("userEvents") {
-            return Err(Error::UserEvents);
+        let mut subscriptions = self.subscriptions.lock().await;
+        let added = subscriptions.add(subscription, connection, sending_channel);
+        let result = match added {
+            Ok((subscription_id, Some(payload))) => {
+                match self.send_to(connection, "subscribe", &payload).await {
+                    Ok(()) => Ok(subscription_id),
+                    Err(err) => subscriptions.remove(subscription_id).and(Err(err)),
+                }
+            }
+            Ok((subscription_id, None)) => Ok(subscription_id),
+            Err(err) => Err(err),
+        };
+        if result.is_err() {
+            self.release_owners(&subscriptions);
         }
+        result
+    }
 
-        if subscriptions.is_empty() {
-            let payload = serde_json::to_string(&SubscriptionSendData {
-                method: "subscribe",
-                subscription: &serde_json::from_str::<serde_json::Value>(&identifier)
-                    .map_err(|e| Error::JsonParse(e.to_string()))?,
-            })
-            .map_err(|e| Error::JsonParse(e.to_string()))?;
-
-            let mut writer = self.writer.lock().await;
-            writer
-                .send(protocol::Message::Text(payload))
-                .await
-                .map_err(|e| Error::Websocket(e.to_string()))?;
+    pub(crate) async fn remove_subscription(&mut self, subscription_id: u32) -> Result<()> {
+        let mut subscriptions = self.subscriptions.lock().await;
+        let unsubscribe = subscriptions.remove(subscription_id)?;
+        self.release_owners(&subscriptions);
+        drop(subscriptions);
+        if let Some((payload, connection)) = unsubscribe {
+            // Counted before sending, since the reader may see the ack before this returns
+            let unacked = &self.connections[connection].unacked_unsubscribes;
+            unacked.fetch_add(1, Ordering::AcqRel);
+            if let Err(err) = self.send_to(connection, "unsubscribe", &payload).await {
+                unacked.fetch_sub(1, Ordering::AcqRel);
+                return Err(err);
+            }
         }
+        Ok(())
+    }
 
-        let subscription_id = self.subscription_id;
-        self.subscription_identifiers
-            .insert(subscription_id, identifier.clone());
-        subscriptions.push(SubscriptionData {
-            sending_channel,
-            subscription_id,
-        });
+    pub(crate) async fn queue_depths(&self) -> HashMap<u32, usize> {
+        self.subscriptions.lock().await.router.queue_depths()
+    }
 
-        self.subscription_id += 1;
-        Ok(subscription_id)
+    async fn send_to(&self, connection: usize, method: &'static str, payload: &str) -> Result<()> {
+        WsManager::send_subscription(&self.connections[connection].writer, method, payload).await
     }
 
-    pub(crate) async fn remove_subscription(&mut self, subscription_id: u32) -> Result<()> {
-        let identifier = self
-            .subscription_identifiers
-            .get(&subscription_id)
-            .ok_or(Error::SubscriptionNotFound)?
-            .clone();
-
-        let identifier_entry = if let Subscription::UserEvents { user: _ } =
-            serde_json::from_str::<Subscription>(&identifier)
-                .map_err(|e| Error::JsonParse(e.to_string()))?
-        {
-            "userEvents".to_string()
-        } else if let Subscription::OrderUpdates { user: _ } =
-            serde_json::from_str::<Subscription>(&identifier)
-                .map_err(|e| Error::JsonParse(e.to_string()))?
-        {
-            "orderUpdates".to_string()
-        } else {
-            identifier.clone()
+    // Sends a subscribe or unsubscribe request with a `Route`'s payload, which is already JSON.
+    async fn send_subscription(writer: &Writer, method: &'static str, payload: &str) -> Result<()> {
+        let payload = format!(r#"{{"method":"{method}","subscription":{payload}}}"#);
+        let mut writer = writer.lock().await;
+        writer
+            .send(protocol::Message::Text(payload))
+            .await
+            .map_err(|e| Error::Websocket(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{L2BookData, Timestamp};
+    use tokio::sync::mpsc::unbounded_channel;
+
+    fn payload(subscription: &Subscription) -> Arc<str> {
+        serde_json::to_string(subscription).unwrap().into()
+    }
+
+    fn l2_book(coin: &str) -> Message {
+        Message::L2Book(L2Book {
+            data: Arc::new(L2BookData {
+                coin: coin.to_string(),
+                time: Timestamp::default(),
+                levels: Vec::new(),
+            }),
+        })
+    }
+
+    #[test]
+    fn test_identical_subscriptions_share_wire_subscription() -> Result<()> {
+        let eth = Subscription::L2Book {
+            coin: "ETH".to_string(),
         };
+        let mut subscriptions = Subscriptions::default();
+        let (first_sender, mut first_receiver) = unbounded_channel();
+        let (second_sender, mut second_receiver) = unbounded_channel();
 
-        self.subscription_identifiers.remove(&subscription_id);
+        // Only the first consumer subscribes on the wire, with the payload kept for
+        // unsubscribing
+        assert!(!subscriptions.is_subscribed(&eth));
+        let (first, subscribe) = subscriptions.add(eth.clone(), 0, first_sender)?;
+        assert_eq!(
+            subscribe.as_deref(),
+            Some(r#"{"type":"l2Book","coin":"ETH"}"#)
+        );
+        assert!(subscriptions.is_subscribed(&eth));
+        let (second, subscribe) = subscriptions.add(eth.clone(), 0, second_sender)?;
+        assert_ne!(first, second);
+        assert_eq!(subscribe, None);
 
-        let mut subscriptions = self.subscriptions.lock().await;
+        subscriptions.router.send(
+            &SubscriptionKey::from(&eth),
+            &l2_book("ETH"),
+            &mut Vec::new(),
+        )?;
+        assert!(first_receiver.try_recv().is_ok());
+        assert!(second_receiver.try_recv().is_ok());
 
-        let subscriptions = subscriptions
-            .get_mut(&identifier_entry)
-            .ok_or(Error::SubscriptionNotFound)?;
-        let index = subscriptions
-            .iter()
-            .position(|subscription_data| subscription_data.subscription_id == subscription_id)
-            .ok_or(Error::SubscriptionNotFound)?;
-        subscriptions.remove(index);
+        // Removing one consumer keeps the wire subscription for the other
+        assert_eq!(subscriptions.remove(first)?, None);
+        assert!(first_receiver.try_recv().is_err());
+        subscriptions.router.send(
+            &SubscriptionKey::from(&eth),
+            &l2_book("ETH"),
+            &mut Vec::new(),
+        )?;
+        assert!(second_receiver.try_recv().is_ok());
 
-        if subscriptions.is_empty() {
-            let payload = serde_json::to_string(&SubscriptionSendData {
-                method: "unsubscribe",
-                subscription: &serde_json::from_str::<serde_json::Value>(&identifier)
-                    .map_err(|e| Error::JsonParse(e.to_string()))?,
-            })
-            .map_err(|e| Error::JsonParse(e.to_string()))?;
+        assert_eq!(subscriptions.remove(second)?, Some((payload(&eth), 0)));
+        assert!(!subscriptions.is_subscribed(&eth));
+        assert!(matches!(
+            subscriptions.remove(second),
+            Err(Error::SubscriptionNotFound)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_readers_deliver_from_published_snapshots() -> Result<()> {
+        let eth = Subscription::L2Book {
+            coin: "ETH".to_string(),
+        };
+        let mut subscriptions = Subscriptions::default();
+        let (first_sender, mut first_receiver) = unbounded_channel();
+        let (second_sender, mut second_receiver) = unbounded_channel();
+        subscriptions.add(eth.clone(), 0, first_sender)?;
+
+        // A delivery in flight keeps the snapshot it loaded while subscribers change
+        let in_flight = subscriptions.router.routes.load_full();
+        let (second, _) = subscriptions.add(eth.clone(), 0, second_sender)?;
+        assert_eq!(in_flight[&SubscriptionKey::from(&eth)].consumers.len(), 1);
+        subscriptions.router.send(
+            &SubscriptionKey::from(&eth),
+            &l2_book("ETH"),
+            &mut Vec::new(),
+        )?;
+        assert!(first_receiver.try_recv().is_ok());
+        assert!(second_receiver.try_recv().is_ok());
+
+        subscriptions.remove(second)?;
+        subscriptions.router.send(
+            &SubscriptionKey::from(&eth),
+            &l2_book("ETH"),
+            &mut Vec::new(),
+        )?;
+        assert!(first_receiver.try_recv().is_ok());
+        assert!(second_receiver.try_recv().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_user_channels_are_refcounted_per_user() -> Result<()> {
+        let alice = Subscription::UserEvents {
+            user: H160::from_low_u64_be(1),
+        };
+        let bob = Subscription::UserEvents {
+            user: H160::from_low_u64_be(2),
+        };
+        let mut subscriptions = Subscriptions::default();
+        let (sender, _receiver) = unbounded_channel();
+
+        let (alice_first, _) = subscriptions.add(alice.clone(), 0, sender.clone())?;
+        let (alice_second, _) = subscriptions.add(alice.clone(), 0, sender.clone())?;
+        let (bob_only, _) = subscriptions.add(bob.clone(), 1, sender)?;
+
+        assert_eq!(subscriptions.remove(alice_first)?, None);
+        assert_eq!(subscriptions.remove(bob_only)?, Some((payload(&bob), 1)));
+        assert!(subscriptions.is_subscribed(&alice));
+        assert_eq!(
+            subscriptions.remove(alice_second)?,
+            Some((payload(&alice), 0))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_subscription_rejections_are_surfaced() -> Result<()> {
+        let eth = Subscription::L2Book {
+            coin: "ETH".to_string(),
+        };
+        let btc = Subscription::L2Book {
+            coin: "BTC".to_string(),
+        };
+        let mut subscriptions = Subscriptions::default();
+        let (eth_sender, mut eth_receiver) = unbounded_channel();
+        let (btc_sender, mut btc_receiver) = unbounded_channel();
+        subscriptions.add(eth, 0, eth_sender)?;
+        subscriptions.add(btc, 0, btc_sender)?;
 
-            let mut writer = self.writer.lock().await;
-            writer
-                .send(protocol::Message::Text(payload))
-                .await
-                .map_err(|e| Error::Websocket(e.to_string()))?;
+        let message: Message = serde_json::from_str(
+            r#"{"channel":"error","data":"Invalid subscription {\"type\":\"l2Book\",\"coin\":\"ETH\"}"}"#,
+        )
+        .unwrap();
+        let Message::Error(error) = message else {
+            panic!("expected an error message");
+        };
+        let mut rejected = Vec::new();
+        let Error::SubscriptionRejected {
+            reason,
+            active,
+            limit,
+        } = subscriptions
+            .router
+            .reject(&error, 0, &mut Vec::new(), &mut rejected)
+        else {
+            panic!("expected a rejection");
+        };
+        assert!(reason.starts_with("Invalid subscription"));
+        // The refused subscription no longer counts
+        assert_eq!((active, limit), (1, MAX_WS_SUBSCRIPTIONS));
+        let Ok(Message::SubscriptionRejected(rejection)) = eth_receiver.try_recv() else {
+            panic!("expected a typed rejection");
+        };
+        assert_eq!(rejection.subscription, Subscription::l2_book("ETH"));
+        assert_eq!(
+            (rejection.active, rejection.limit),
+            (1, MAX_WS_SUBSCRIPTIONS)
+        );
+        assert!(btc_receiver.try_recv().is_err());
+        assert_eq!(rejected.len(), 1);
+        subscriptions.reject(rejected);
+        assert_eq!(subscriptions.active(), 1);
+        assert_eq!(subscriptions.router.active(), 1);
+        assert!(!subscriptions.is_subscribed(&Subscription::l2_book("ETH")));
+
+        // "Already subscribed" leaves the subscription in place
+        let error = WsError {
+            data: r#"Already subscribed: {"type":"l2Book","coin":"BTC"}"#.to_string(),
+        };
+        let mut rejected = Vec::new();
+        subscriptions
+            .router
+            .reject(&error, 0, &mut Vec::new(), &mut rejected);
+        assert!(rejected.is_empty());
+        assert!(matches!(btc_receiver.try_recv(), Ok(Message::Error(_))));
+
+        // Errors that don't quote a subscription go to every consumer on the connection
+        let (sol_sender, mut sol_receiver) = unbounded_channel();
+        subscriptions.add(Subscription::l2_book("SOL"), 1, sol_sender)?;
+        let error = WsError {
+            data: "Cannot track more than 1000 total subscriptions".to_string(),
+        };
+        assert_eq!(error.kind(), WsErrorKind::SubscriptionLimit);
+        let mut rejected = Vec::new();
+        subscriptions
+            .router
+            .reject(&error, 0, &mut Vec::new(), &mut rejected);
+        assert!(rejected.is_empty());
+        assert!(btc_receiver.try_recv().is_ok());
+        assert!(sol_receiver.try_recv().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_text_frames_are_classified() -> Result<()> {
+        assert!(WsManager::classify("Websocket connection established.")?.is_none());
+        let Some(Message::Error(error)) = WsManager::classify("Too many messages, rate limited")?
+        else {
+            panic!("expected an error message");
+        };
+        assert_eq!(error.kind(), WsErrorKind::RateLimited);
+        let message = WsManager::classify(r#"{"channel":"error","data":"Already subscribed"}"#)?;
+        assert!(matches!(message, Some(Message::Error(error))
+            if error.kind() == WsErrorKind::InvalidSubscription));
+        assert!(matches!(
+            WsManager::classify(r#"{"channel":"pong"}"#)?,
+            Some(Message::Pong)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_constructors_and_channels_match_the_wire() {
+        assert_eq!(
+            serde_json::to_value(Subscription::candle("ETH", CandleInterval::OneHour)).unwrap(),
+            serde_json::json!({"type": "candle", "coin": "ETH", "interval": "1h"})
+        );
+        assert_eq!(
+            Subscription::l2_book("ETH"),
+            Subscription::L2Book {
+                coin: "ETH".to_string()
+            }
+        );
+        let message = l2_book("ETH");
+        let serialized = serde_json::to_value(&message).unwrap();
+        assert_eq!(message.channel(), serialized["channel"].as_str());
+        assert_eq!(Message::Pong.channel(), None);
+    }
+
+    #[test]
+    fn test_unusual_frames() -> Result<()> {
+        let mut subscriptions = Subscriptions::default();
+        let (sender, mut receiver) = unbounded_channel();
+        let eth = Subscription::L2Book {
+            coin: "ETH".to_string(),
+        };
+        subscriptions.add(eth, 0, sender)?;
+        let router = &subscriptions.router;
+        let evicted = &mut Vec::new();
+
+        let book = serde_json::to_string(&l2_book("ETH")).unwrap();
+        let binary = Ok(protocol::Message::Binary(book.into_bytes()));
+        WsManager::parse_and_send_data(binary, 0, None, router, evicted, &mut Vec::new())?;
+        assert!(matches!(receiver.try_recv(), Ok(Message::L2Book(_))));
+
+        let ping = Ok(protocol::Message::Ping(b"hi".to_vec()));
+        WsManager::parse_and_send_data(ping, 0, None, router, evicted, &mut Vec::new())?;
+        assert!(receiver.try_recv().is_err());
+
+        let close = Ok(protocol::Message::Close(Some(protocol::CloseFrame {
+            code: protocol::frame::coding::CloseCode::Away,
+            reason: "maintenance".into(),
+        })));
+        let result =
+            WsManager::parse_and_send_data(close, 0, None, router, evicted, &mut Vec::new());
+        assert!(matches!(result, Err(Error::WsClosed { code: 1001, .. })));
+        let Ok(Message::Closed(closed)) = receiver.try_recv() else {
+            panic!("expected a close notification");
+        };
+        assert_eq!(closed.reason, "maintenance");
+        Ok(())
+    }
+
+    #[test]
+    fn test_flagged_consumers_drop_until_half_drained() -> Result<()> {
+        let eth = SubscriptionKey::from(&Subscription::l2_book("ETH"));
+        let mut subscriptions = Subscriptions::default();
+        let (sender, mut receiver) = tokio::sync::mpsc::channel(4);
+        subscriptions.add(
+            Subscription::l2_book("ETH"),
+            0,
+            Consumer::bounded(sender, SlowConsumerPolicy::Flag),
+        )?;
+        let router = &subscriptions.router;
+        let overflow = || -> Result<()> {
+            for _ in 0..5 {
+                router.send(&eth, &l2_book("ETH"), &mut Vec::new())?;
+            }
+            Ok(())
+        };
+
+        overflow()?;
+        for _ in 0..2 {
+            assert!(matches!(receiver.try_recv(), Ok(Message::L2Book(_))));
+        }
+        // Half the queue is free, but not more, so this is still dropped
+        router.send(&eth, &l2_book("ETH"), &mut Vec::new())?;
+        assert!(matches!(receiver.try_recv(), Ok(Message::L2Book(_))));
+        assert!(matches!(receiver.try_recv(), Ok(Message::SlowConsumer(_))));
+        assert!(receiver.try_recv().is_err());
+
+        // Once drained, a second overflow is flagged again rather than dropped silently
+        overflow()?;
+        for _ in 0..3 {
+            assert!(matches!(receiver.try_recv(), Ok(Message::L2Book(_))));
         }
+        assert!(matches!(receiver.try_recv(), Ok(Message::SlowConsumer(_))));
+        Ok(())
+    }
+
+    #[test]
+    fn test_slow_consumers() -> Result<()> {
+        let eth = Subscription::L2Book {
+            coin: "ETH".to_string(),
+        };
+        let mut subscriptions = Subscriptions::default();
+        let (flag_sender, mut flag_receiver) = tokio::sync::mpsc::channel(4);
+        let (evict_sender, mut evict_receiver) = tokio::sync::mpsc::channel(4);
+        let (flagged, _) = subscriptions.add(
+            eth.clone(),
+            0,
+            Consumer::bounded(flag_sender, SlowConsumerPolicy::Flag),
+        )?;
+        let (evicted, _) = subscriptions.add(
+            eth.clone(),
+            0,
+            Consumer::bounded(evict_sender, SlowConsumerPolicy::Evict),
+        )?;
+
+        let mut to_evict = Vec::new();
+        for _ in 0..5 {
+            subscriptions.router.send(
+                &SubscriptionKey::from(&eth),
+                &l2_book("ETH"),
+                &mut to_evict,
+            )?;
+        }
+        assert_eq!(subscriptions.router.queue_depths()[&flagged], 4);
+        assert_eq!(to_evict, vec![evicted]);
+        assert_eq!(subscriptions.evict(to_evict), Vec::new());
+        assert_eq!(
+            subscriptions.routes[&SubscriptionKey::from(&eth)]
+                .consumers
+                .len(),
+            1
+        );
+        assert_eq!(
+            subscriptions.router.routes.load()[&SubscriptionKey::from(&eth)]
+                .consumers
+                .len(),
+            1
+        );
+
+        // Three messages, then the notification in the last slot; the fifth was dropped
+        for _ in 0..3 {
+            assert!(matches!(flag_receiver.try_recv(), Ok(Message::L2Book(_))));
+            assert!(matches!(evict_receiver.try_recv(), Ok(Message::L2Book(_))));
+        }
+        let Ok(Message::SlowConsumer(slow)) = flag_receiver.try_recv() else {
+            panic!("expected a slow consumer notification");
+        };
+        assert_eq!(
+            (slow.subscription_id, slow.depth, slow.capacity),
+            (flagged, 3, 4)
+        );
+        assert!(!slow.evicted);
+        assert!(matches!(
+            evict_receiver.try_recv(),
+            Ok(Message::SlowConsumer(SlowConsumer { evicted: true, .. }))
+        ));
+
+        // The flagged consumer resumes once drained; the evicted one is gone
+        subscriptions.router.send(
+            &SubscriptionKey::from(&eth),
+            &l2_book("ETH"),
+            &mut Vec::new(),
+        )?;
+        assert!(matches!(flag_receiver.try_recv(), Ok(Message::L2Book(_))));
+        assert!(evict_receiver.try_recv().is_err());
+        assert_eq!(subscriptions.remove(flagged)?, Some((payload(&eth), 0)));
+        assert!(matches!(
+            subscriptions.remove(evicted),
+            Err(Error::SubscriptionNotFound)
+        ));
         Ok(())
     }
 }
@@ -0,0 +1,152 @@
+// Decodes every payload under tests/corpus strictly, so fields the exchange adds are caught
+// here instead of being silently dropped. Files under ws/ are websocket messages; files under
+// info/ and exchange/ are named after the request type they answer.
+//
+// To add a payload, save the exchange's response (with addresses, hashes and amounts
+// replaced) to the matching directory. A new info type needs a line in `decode`.
+#![allow(clippy::result_large_err)]
+
+use hyperliquid_rust_sdk::{
+    decode_strict, CandlesSnapshotResponse, Error, ExchangeResponseStatus,
+    FrontendOpenOrdersResponse, FundingHistoryResponse, L2SnapshotResponse, LedgerUpdateData,
+    Message, Meta, OpenOrdersResponse, OrderStatusResponse, RecentTradesResponse, ReferralResponse,
+    SpotMeta, UserFeesResponse, UserFillsResponse, UserFundingResponse, UserRateLimitResponse,
+    UserRoleResponse, UserStateResponse,
+};
+use std::{collections::HashSet, fs, path::Path};
+
+// Fields the exchange sends that the SDK doesn't model yet, by file and path with array
+// indices dropped. Model a field and remove it here rather than adding to the list.
+const UNMODELLED: &[(&str, &str)] = &[
+    ("ws/subscriptionResponse.json", "data"),
+    ("ws/trades.json", "data[].users"),
+    ("info/clearinghouseState.json", "crossMaintenanceMarginUsed"),
+    ("info/clearinghouseState.json", "time"),
+    (
+        "info/clearinghouseState.json",
+        "assetPositions[].position.maxLeverage",
+    ),
+    (
+        "info/clearinghouseState.json",
+        "assetPositions[].position.cumFunding",
+    ),
+    ("info/userFunding.json", "[].delta.type"),
+    ("info/recentTrades.json", "[].tid"),
+    ("info/recentTrades.json", "[].users"),
+    ("info/userFees.json", "feeTrialReward"),
+    ("info/userFees.json", "activeStakingDiscount"),
+    ("info/referral.json", "rewardHistory"),
+    ("info/meta.json", "marginTables"),
+    ("info/meta.json", "universe[].onlyIsolated"),
+    ("info/meta.json", "universe[].isDelisted"),
+    ("info/spotMeta.json", "tokens[].fullName"),
+];
+
+fn decode(file: &str, json: &str) -> Result<(), Error> {
+    let (dir, name) = file.split_once('/').unwrap();
+    let kind = name.trim_end_matches(".json");
+    match (dir, kind) {
+        ("ws", _) => decode_strict::<Message>(json).map(drop),
+        ("exchange", _) => decode_strict::<ExchangeResponseStatus>(json).map(drop),
+        ("info", "clearinghouseState") => decode_strict::<UserStateResponse>(json).map(drop),
+        ("info", "openOrders") => decode_strict::<Vec<OpenOrdersResponse>>(json).map(drop),
+        ("info", "frontendOpenOrders") => {
+            decode_strict::<Vec<FrontendOpenOrdersResponse>>(json).map(drop)
+        }
+        ("info", "orderStatus") => decode_strict::<OrderStatusResponse>(json).map(drop),
+        ("info", "userFills") => decode_strict::<Vec<UserFillsResponse>>(json).map(drop),
+        ("info", "userFunding") => decode_strict::<Vec<UserFundingResponse>>(json).map(drop),
+        ("info", "userNonFundingLedgerUpdates") => {
+            decode_strict::<Vec<LedgerUpdateData>>(json).map(drop)
+        }
+        ("info", "fundingHistory") => decode_strict::<Vec<FundingHistoryResponse>>(json).map(drop),
+        ("info", "l2Book") => decode_strict::<L2SnapshotResponse>(json).map(drop),
+        ("info", "candleSnapshot") => decode_strict::<Vec<CandlesSnapshotResponse>>(json).map(drop),
+        ("info", "recentTrades") => decode_strict::<Vec<RecentTradesResponse>>(json).map(drop),
+        ("info", "userRole") => decode_strict::<UserRoleResponse>(json).map(drop),
+        ("info", "userRateLimit") => decode_strict::<UserRateLimitResponse>(json).map(drop),
+        ("info", "userFees") => decode_strict::<UserFeesResponse>(json).map(drop),
+        ("info", "referral") => decode_strict::<ReferralResponse>(json).map(drop),
+        ("info", "meta") => decode_strict::<Meta>(json).map(drop),
+        ("info", "spotMeta") => decode_strict::<SpotMeta>(json).map(drop),
+        _ => panic!("no decoder for {file}; add one to `decode`"),
+    }
+}
+
+fn without_indices(path: &str) -> String {
+    let mut stripped = String::new();
+    let mut in_index = false;
+    for c in path.chars() {
+        match c {
+            '[' => {
+                in_index = true;
+                stripped.push('[');
+            }
+            ']' => {
+                in_index = false;
+                stripped.push(']');
+            }
+            _ if in_index => {}
+            c => stripped.push(c),
+        }
+    }
+    stripped
+}
+
+#[test]
+fn test_corpus_decodes_strictly() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/corpus");
+    let mut failures = Vec::new();
+    let mut decoded = 0;
+    let mut unmodelled_seen = HashSet::new();
+    for dir in ["ws", "info", "exchange"] {
+        let mut entries: Vec<_> = fs::read_dir(root.join(dir))
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        entries.sort();
+        for path in entries {
+            let file = format!("{dir}/{}", path.file_name().unwrap().to_string_lossy());
+            let json = fs::read_to_string(&path).unwrap();
+            match decode(&file, &json) {
+                Ok(()) => {}
+                Err(Error::SchemaDrift { type_name, fields }) => {
+                    let mut unknown = Vec::new();
+                    for field in fields {
+                        let entry = (file.as_str(), without_indices(&field));
+                        match UNMODELLED
+                            .iter()
+                            .find(|&&(f, p)| (f, p) == (entry.0, &entry.1))
+                        {
+                            Some(known) => {
+                                unmodelled_seen.insert(*known);
+                            }
+                            None => unknown.push(field),
+                        }
+                    }
+                    if !unknown.is_empty() {
+                        failures.push(format!(
+                            "{file}: {type_name} doesn't model {}",
+                            unknown.join(", ")
+                        ));
+                    }
+                }
+                Err(err) => failures.push(format!("{file}: {err}")),
+            }
+            decoded += 1;
+        }
+    }
+    assert!(decoded > 0, "no payloads under {}", root.display());
+    for &(file, field) in UNMODELLED {
+        if !unmodelled_seen.contains(&(file, field)) {
+            failures.push(format!(
+                "{file}: {field} is listed as unmodelled but wasn't found; remove it from UNMODELLED"
+            ));
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "schema drift in the corpus:\n{}",
+        failures.join("\n")
+    );
+}